@@ -0,0 +1,451 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Find and rank second-source / replacement candidates for a part.
+//!
+//! Flow:
+//! 1. Collect candidates from DigiKey substitutions and Mouser suggested replacements
+//! 2. Score each candidate by comparing DigiKey parametric data with the original
+//! 3. Optionally send the original datasheet plus candidate parameters to Gemini for
+//!    an electrical/footprint compatibility assessment
+
+use crate::digikey;
+use crate::extract::{DEFAULT_MODEL, __DEFAULT__, resolve_model};
+use crate::llm::{LlmProvider, LlmRequest, build_client, resolve_api_key};
+use crate::parameters::PartParameters;
+use crate::{mouser, page_render};
+use anyhow::{Result, anyhow};
+use clap::Args;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+
+const PROMPT: &str = include_str!("../prompts/alternates-compare.md");
+
+/// Parameters that describe packaging or ordering rather than the part itself.
+const IGNORED_PARAMETERS: &[&str] = &[
+    "packaging",
+    "part status",
+    "product status",
+    "series",
+    "base product number",
+];
+
+#[derive(Args, Debug)]
+pub struct AlternatesArgs {
+    /// Manufacturer part number to find replacements for
+    pub mpn: String,
+
+    /// Maximum number of candidates to evaluate
+    #[arg(long, default_value = "10")]
+    pub limit: usize,
+
+    /// Datasheet PDF of the original part; enables LLM-assisted comparison
+    #[arg(long)]
+    pub datasheet: Option<PathBuf>,
+
+    /// LLM provider
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
+    pub provider: LlmProvider,
+
+    /// Model name
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
+    pub model: String,
+
+    /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Optional base URL override for Gemini API
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Disable file caching (re-upload PDF every request)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct Candidate {
+    mpn: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manufacturer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    /// Where the candidate came from (e.g. "digikey: Direct", "mouser: suggested replacement")
+    sources: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock: Option<i64>,
+    /// Fraction of the original's electrical parameters matched (0-1)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    electrical_match: Option<f64>,
+    /// Fraction of the original's package parameters matched (0-1)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    footprint_match: Option<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    differences: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    llm: Option<LlmAssessment>,
+    score: f64,
+}
+
+#[derive(Serialize, Debug)]
+struct LlmAssessment {
+    electrical_score: f64,
+    footprint_score: f64,
+    drop_in: bool,
+    notes: String,
+}
+
+#[derive(Serialize, Debug)]
+struct AlternatesReport<'a> {
+    mpn: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manufacturer: Option<&'a str>,
+    candidates: &'a [Candidate],
+}
+
+pub fn run(args: &AlternatesArgs) -> Result<()> {
+    let original = match digikey::lookup_parameters(&args.mpn) {
+        Ok(params) => params,
+        Err(e) => {
+            eprintln!("[ALTERNATES] DigiKey parameters unavailable for {}: {}", args.mpn, e);
+            PartParameters::default()
+        }
+    };
+
+    // ── Step 1: Gather candidates from distributor substitution data ───
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    match digikey::find_substitutes(&args.mpn) {
+        Ok(subs) => {
+            for sub in subs {
+                let source = format!(
+                    "digikey: {}",
+                    sub.substitute_type.as_deref().unwrap_or("substitute")
+                );
+                let candidate = upsert_candidate(&mut candidates, &sub.mpn, &args.mpn);
+                if let Some(candidate) = candidate {
+                    candidate.sources.push(source);
+                    candidate.manufacturer = candidate.manufacturer.take().or(sub.manufacturer);
+                    candidate.description = candidate.description.take().or(sub.description);
+                    candidate.stock = candidate.stock.or(sub.stock);
+                }
+            }
+        }
+        Err(e) => eprintln!("[ALTERNATES] DigiKey substitutions failed: {}", e),
+    }
+
+    match mouser::lookup_stock(&args.mpn) {
+        Ok(info) => {
+            if let Some(replacement) = info.suggested_replacement.filter(|r| !r.is_empty()) {
+                if let Some(candidate) = upsert_candidate(&mut candidates, &replacement, &args.mpn) {
+                    candidate.sources.push("mouser: suggested replacement".to_string());
+                }
+            }
+        }
+        Err(e) => eprintln!("[ALTERNATES] Mouser lookup failed: {}", e),
+    }
+
+    if candidates.is_empty() {
+        return Err(anyhow!("No replacement candidates found for {}", args.mpn));
+    }
+    candidates.truncate(args.limit);
+
+    // ── Step 2: Parametric comparison ──────────────────────────────────
+    let mut candidate_params = Vec::with_capacity(candidates.len());
+    for candidate in candidates.iter_mut() {
+        eprintln!("[ALTERNATES] Comparing {}", candidate.mpn);
+        let params = digikey::lookup_parameters(&candidate.mpn).unwrap_or_default();
+        let cmp = compare_parameters(&original.parameters, &params.parameters);
+        candidate.electrical_match = cmp.electrical;
+        candidate.footprint_match = cmp.footprint;
+        candidate.differences = cmp.differences;
+        candidate.manufacturer = candidate.manufacturer.take().or(params.manufacturer.clone());
+        candidate.description = candidate.description.take().or(params.description.clone());
+        candidate.score = combined_score(cmp.electrical, cmp.footprint);
+        candidate_params.push(params);
+    }
+
+    // ── Step 3: Optional LLM assessment against the original datasheet ─
+    if let Some(ref pdf) = args.datasheet {
+        if !pdf.exists() {
+            return Err(anyhow!("PDF not found: {}", pdf.display()));
+        }
+        let mut assessments = llm_assess(args, pdf, &original, &candidates, &candidate_params)?;
+        for candidate in candidates.iter_mut() {
+            if let Some(pos) = assessments
+                .iter()
+                .position(|(mpn, _)| mpn.eq_ignore_ascii_case(&candidate.mpn))
+            {
+                let (_, assessment) = assessments.swap_remove(pos);
+                candidate.score = combined_score(
+                    Some(assessment.electrical_score / 100.0),
+                    Some(assessment.footprint_score / 100.0),
+                );
+                candidate.llm = Some(assessment);
+            }
+        }
+    }
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    if args.json {
+        let report = AlternatesReport {
+            mpn: &args.mpn,
+            manufacturer: original.manufacturer.as_deref(),
+            candidates: &candidates,
+        };
+        println!("{}", crate::select::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("Alternates for {} ({} candidate(s)):", args.mpn, candidates.len());
+    for (i, c) in candidates.iter().enumerate() {
+        println!();
+        print!("{}. {}", i + 1, c.mpn);
+        if let Some(ref mfr) = c.manufacturer {
+            print!(" ({})", mfr);
+        }
+        println!("  score: {:.0}%", c.score * 100.0);
+        if let Some(ref desc) = c.description {
+            println!("   {}", desc);
+        }
+        println!("   Source: {}", c.sources.join(", "));
+        if let Some(stock) = c.stock {
+            println!("   Stock: {}", stock);
+        }
+        if let Some(e) = c.electrical_match {
+            println!("   Electrical parameters matched: {:.0}%", e * 100.0);
+        }
+        if let Some(f) = c.footprint_match {
+            println!("   Package parameters matched: {:.0}%", f * 100.0);
+        }
+        for diff in c.differences.iter().take(5) {
+            println!("   - {}", diff);
+        }
+        if let Some(ref llm) = c.llm {
+            println!(
+                "   LLM: electrical {:.0}, footprint {:.0}{} — {}",
+                llm.electrical_score,
+                llm.footprint_score,
+                if llm.drop_in { ", drop-in" } else { "" },
+                llm.notes
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Return the candidate entry for `mpn`, creating it if needed. Returns `None` for the original part.
+fn upsert_candidate<'a>(
+    candidates: &'a mut Vec<Candidate>,
+    mpn: &str,
+    original: &str,
+) -> Option<&'a mut Candidate> {
+    if mpn.eq_ignore_ascii_case(original) {
+        return None;
+    }
+    let idx = match candidates.iter().position(|c| c.mpn.eq_ignore_ascii_case(mpn)) {
+        Some(idx) => idx,
+        None => {
+            candidates.push(Candidate {
+                mpn: mpn.to_string(),
+                ..Default::default()
+            });
+            candidates.len() - 1
+        }
+    };
+    candidates.get_mut(idx)
+}
+
+struct ParameterComparison {
+    electrical: Option<f64>,
+    footprint: Option<f64>,
+    differences: Vec<String>,
+}
+
+fn is_package_parameter(name: &str) -> bool {
+    name.contains("package") || name.contains("case") || name.contains("mounting type")
+}
+
+fn normalize_value(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Compare a candidate's parameters against the original, split into electrical and package groups.
+fn compare_parameters(original: &[(String, String)], candidate: &[(String, String)]) -> ParameterComparison {
+    let (mut e_total, mut e_matched, mut f_total, mut f_matched) = (0, 0, 0, 0);
+    let mut differences = Vec::new();
+
+    for (name, value) in original {
+        let key = name.to_lowercase();
+        if IGNORED_PARAMETERS.contains(&key.as_str()) || value.trim() == "-" {
+            continue;
+        }
+        let other = candidate
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str());
+        let matched = other.is_some_and(|v| normalize_value(v) == normalize_value(value));
+
+        if is_package_parameter(&key) {
+            f_total += 1;
+            f_matched += matched as usize;
+        } else {
+            e_total += 1;
+            e_matched += matched as usize;
+        }
+        if !matched {
+            differences.push(format!("{}: {} → {}", name, value, other.unwrap_or("n/a")));
+        }
+    }
+
+    let ratio = |matched: usize, total: usize| (total > 0).then(|| matched as f64 / total as f64);
+    ParameterComparison {
+        electrical: ratio(e_matched, e_total),
+        footprint: ratio(f_matched, f_total),
+        differences,
+    }
+}
+
+/// Weighted score favoring electrical fit; missing halves fall back to the other.
+fn combined_score(electrical: Option<f64>, footprint: Option<f64>) -> f64 {
+    match (electrical, footprint) {
+        (Some(e), Some(f)) => 0.6 * e + 0.4 * f,
+        (Some(x), None) | (None, Some(x)) => x,
+        (None, None) => 0.0,
+    }
+}
+
+fn format_parameters(params: &PartParameters) -> String {
+    if params.parameters.is_empty() {
+        return "  (no parametric data)\n".to_string();
+    }
+    params
+        .parameters
+        .iter()
+        .map(|(name, value)| format!("  - {}: {}\n", name, value))
+        .collect()
+}
+
+fn assessment_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "candidates": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "mpn": { "type": "string" },
+                        "electrical_score": { "type": "number" },
+                        "footprint_score": { "type": "number" },
+                        "drop_in": { "type": "boolean" },
+                        "notes": { "type": "string" }
+                    },
+                    "required": ["mpn", "electrical_score", "footprint_score", "drop_in", "notes"]
+                }
+            }
+        },
+        "required": ["candidates"]
+    })
+}
+
+fn llm_assess(
+    args: &AlternatesArgs,
+    pdf: &std::path::Path,
+    original: &PartParameters,
+    candidates: &[Candidate],
+    candidate_params: &[PartParameters],
+) -> Result<Vec<(String, LlmAssessment)>> {
+    let api_key = resolve_api_key(args.provider, args.api_key.clone())?;
+    let model = resolve_model(&args.model, DEFAULT_MODEL);
+
+    let mut prompt = format!(
+        "{}\n\n## Original part: {}\n{}",
+        PROMPT,
+        args.mpn,
+        format_parameters(original)
+    );
+    for (candidate, params) in candidates.iter().zip(candidate_params) {
+        prompt.push_str(&format!("\n## Candidate: {}\n{}", candidate.mpn, format_parameters(params)));
+    }
+
+    let attachment = page_render::make_attachment(pdf, args.no_cache, &api_key, &args.base_url)?;
+    let client = build_client(args.provider, api_key, args.base_url.clone())?;
+    let _usage = crate::usage::scope("alternates", Some(pdf));
+    eprintln!("[ALTERNATES] Assessing {} candidate(s) with {}", candidates.len(), model);
+    let response = client.generate_json(LlmRequest {
+        model,
+        prompt,
+        schema: assessment_schema(),
+        attachment,
+        temperature: Some(0.0),
+    })?;
+
+    let items = response
+        .json
+        .get("candidates")
+        .and_then(|c| c.as_array())
+        .ok_or_else(|| anyhow!("LLM response missing 'candidates' array"))?;
+
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            Some((
+                item.get("mpn")?.as_str()?.to_string(),
+                LlmAssessment {
+                    electrical_score: item.get("electrical_score")?.as_f64()?,
+                    footprint_score: item.get("footprint_score")?.as_f64()?,
+                    drop_in: item.get("drop_in").and_then(|d| d.as_bool()).unwrap_or(false),
+                    notes: item
+                        .get("notes")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                },
+            ))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_compare_parameters() {
+        let original = params(&[
+            ("Voltage - Supply", "1.8V ~ 5.5V"),
+            ("Output Type", "Push-Pull"),
+            ("Package / Case", "SOT-23-5"),
+            ("Packaging", "Tape & Reel (TR)"),
+        ]);
+        let candidate = params(&[
+            ("Voltage - Supply", "1.8V ~ 5.5V"),
+            ("Output Type", "Open Drain"),
+            ("Package / Case", "SOT-23-5"),
+            ("Packaging", "Cut Tape (CT)"),
+        ]);
+
+        let cmp = compare_parameters(&original, &candidate);
+        assert_eq!(cmp.electrical, Some(0.5));
+        assert_eq!(cmp.footprint, Some(1.0));
+        assert_eq!(cmp.differences.len(), 1);
+        assert!(cmp.differences[0].starts_with("Output Type"));
+    }
+}
@@ -0,0 +1,332 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Highlight where extracted values come from, for visual auditing.
+//!
+//! Extraction results do not carry their sources, so this asks the model to
+//! cite each field (page and bounding box, via the same detection used by
+//! extract-pages) and writes a copy of the PDF with one highlight annotation
+//! per citation. The annotation's note names the field and its value, so a
+//! reviewer can step through them in any PDF viewer.
+
+use crate::extract::{DEFAULT_MODEL, __DEFAULT__, resolve_model};
+use crate::llm::{LlmProvider, resolve_api_key};
+use crate::page_render::{self, PageLocation};
+use anyhow::{Context, Result, anyhow};
+use clap::Args;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream, StringFormat, dictionary};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+
+const PROMPT_TEMPLATE: &str = include_str!("../prompts/annotate.md");
+/// Longest value text shown to the model and in annotation notes
+const MAX_VALUE_CHARS: usize = 160;
+
+#[derive(Args, Debug)]
+pub struct AnnotateArgs {
+    /// Datasheet PDF the result was extracted from
+    pub pdf: PathBuf,
+
+    /// Extraction result (JSON file written by `extract`)
+    pub result: PathBuf,
+
+    /// Annotated PDF to write (default: <download dir>/reports/<name>.annotated.pdf)
+    #[arg(long, short = 'o')]
+    pub out: Option<PathBuf>,
+
+    /// Only cite fields under this JSON pointer, e.g. /packages/0/pins
+    #[arg(long)]
+    pub pointer: Option<String>,
+
+    /// Cite at most this many fields (in document order of the result)
+    #[arg(long, default_value = "200")]
+    pub max_fields: usize,
+
+    /// LLM provider
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
+    pub provider: LlmProvider,
+
+    /// Model name
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
+    pub model: String,
+
+    /// API key
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Base URL override
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Disable file caching
+    #[arg(long)]
+    pub no_cache: bool,
+}
+
+pub fn run(args: &AnnotateArgs) -> Result<()> {
+    if !args.pdf.exists() {
+        return Err(anyhow!("PDF not found: {}", args.pdf.display()));
+    }
+    let text = std::fs::read_to_string(&args.result)
+        .with_context(|| format!("reading {}", args.result.display()))?;
+    let result: Value = serde_json::from_str(&text)
+        .with_context(|| format!("parsing {} as JSON", args.result.display()))?;
+    let root = match &args.pointer {
+        Some(pointer) => result
+            .pointer(pointer)
+            .ok_or_else(|| anyhow!("{} has nothing at {}", args.result.display(), pointer))?,
+        None => &result,
+    };
+
+    let mut fields = Vec::new();
+    collect_fields(root, &mut args.pointer.clone().unwrap_or_default(), &mut fields);
+    if fields.is_empty() {
+        return Err(anyhow!("no values to annotate in {}", args.result.display()));
+    }
+    if fields.len() > args.max_fields {
+        eprintln!(
+            "[ANNOTATE] Citing the first {} of {} fields (raise --max-fields or narrow with --pointer)",
+            args.max_fields,
+            fields.len()
+        );
+        fields.truncate(args.max_fields);
+    }
+
+    let api_key = resolve_api_key(args.provider, args.api_key.clone())?;
+    let model = resolve_model(&args.model, DEFAULT_MODEL);
+    let listing: Vec<String> = fields.iter().map(|(pointer, value)| format!("{pointer}\t{value}")).collect();
+    let prompt = PROMPT_TEMPLATE.replace("{FIELDS}", &listing.join("\n"));
+
+    let _usage = crate::usage::scope("annotate", Some(&args.pdf));
+    let locations = page_render::detect_pages(
+        &args.pdf,
+        &prompt,
+        &page_render::DetectOptions {
+            api_key: &api_key,
+            base_url: &args.base_url,
+            provider: args.provider,
+            model: &model,
+            no_cache: args.no_cache,
+            log_prefix: "ANNOTATE",
+        },
+    )?;
+
+    let mut highlights = Vec::new();
+    for loc in &locations {
+        let label = loc.label.trim();
+        match fields.iter().find(|(pointer, _)| pointer == label) {
+            Some((pointer, value)) => highlights.push((loc, format!("{pointer}: {value}"))),
+            None => eprintln!("[ANNOTATE] Ignoring citation for unknown field '{}'", label),
+        }
+    }
+    let not_found: Vec<&str> = fields
+        .iter()
+        .filter(|(pointer, _)| !highlights.iter().any(|(loc, _)| loc.label.trim() == pointer))
+        .map(|(pointer, _)| pointer.as_str())
+        .collect();
+
+    let out = match &args.out {
+        Some(out) => out.clone(),
+        None => {
+            let dir = crate::paths::reports_dir();
+            std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+            let stem = args.pdf.file_stem().unwrap_or_default().to_string_lossy();
+            dir.join(format!("{stem}.annotated.pdf"))
+        }
+    };
+    let written = write_annotated(&args.pdf, &out, &highlights)?;
+    eprintln!(
+        "[ANNOTATE] Wrote {} highlight(s) to {} ({} of {} fields not located)",
+        written,
+        out.display(),
+        not_found.len(),
+        fields.len()
+    );
+
+    let manifest = json!({
+        "pdf": out,
+        "fields": fields.len(),
+        "highlights": written,
+        "citations": highlights
+            .iter()
+            .map(|(loc, note)| json!({ "page": loc.page, "field": loc.label.trim(), "note": note }))
+            .collect::<Vec<_>>(),
+        "not_found": not_found,
+    });
+    println!("{}", crate::select::to_string_pretty(&manifest)?);
+    Ok(())
+}
+
+/// Non-empty string and number fields with their JSON pointers.
+fn collect_fields(value: &Value, pointer: &mut String, out: &mut Vec<(String, String)>) {
+    let len = pointer.len();
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                if pointer.is_empty() && key == crate::migrate::STAMP {
+                    continue;
+                }
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                collect_fields(child, pointer, out);
+                pointer.truncate(len);
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                pointer.push_str(&format!("/{i}"));
+                collect_fields(child, pointer, out);
+                pointer.truncate(len);
+            }
+        }
+        Value::String(s) if !s.trim().is_empty() => {
+            let flat = s.split_whitespace().collect::<Vec<_>>().join(" ");
+            out.push((pointer.clone(), flat.chars().take(MAX_VALUE_CHARS).collect()));
+        }
+        Value::Number(n) => out.push((pointer.clone(), n.to_string())),
+        _ => {}
+    }
+}
+
+/// Copy `pdf` to `out` with a highlight annotation per located field.
+/// Returns the number of highlights written.
+fn write_annotated(pdf: &Path, out: &Path, highlights: &[(&PageLocation, String)]) -> Result<usize> {
+    let mut doc = Document::load(pdf).with_context(|| format!("loading {}", pdf.display()))?;
+    let pages = doc.get_pages();
+    let mut written = 0;
+    for (loc, note) in highlights {
+        let Some(&page_id) = pages.get(&loc.page) else {
+            eprintln!("[ANNOTATE] Skipping citation on page {}: the PDF has {} pages", loc.page, pages.len());
+            continue;
+        };
+        let page_box = inherited(&doc, page_id, b"CropBox")
+            .or_else(|| inherited(&doc, page_id, b"MediaBox"))
+            .and_then(|b| rect(&doc, b))
+            .unwrap_or([0.0, 0.0, 612.0, 792.0]);
+        let rotate = inherited(&doc, page_id, b"Rotate").and_then(|r| r.as_i64().ok()).unwrap_or(0);
+        let bbox = [loc.bbox_x_min, loc.bbox_y_min, loc.bbox_x_max, loc.bbox_y_max];
+        let area = to_user_space(page_box, rotate, bbox);
+        add_highlight(&mut doc, page_id, area, note)?;
+        written += 1;
+    }
+    doc.save(out).with_context(|| format!("saving {}", out.display()))?;
+    Ok(written)
+}
+
+/// Page attribute, looked up through the page tree for inheritable keys.
+pub(crate) fn inherited(doc: &Document, page_id: ObjectId, key: &[u8]) -> Option<Object> {
+    let mut dict = doc.get_dictionary(page_id).ok()?;
+    loop {
+        if let Ok(value) = dict.get(key) {
+            return doc.dereference(value).ok().map(|(_, v)| v.clone());
+        }
+        dict = doc.get_dictionary(dict.get(b"Parent").ok()?.as_reference().ok()?).ok()?;
+    }
+}
+
+pub(crate) fn rect(doc: &Document, value: Object) -> Option<[f32; 4]> {
+    let items = value.as_array().ok()?;
+    let mut out = [0.0; 4];
+    for (slot, item) in out.iter_mut().zip(items) {
+        *slot = doc.dereference(item).ok()?.1.as_float().ok()?;
+    }
+    Some([out[0].min(out[2]), out[1].min(out[3]), out[0].max(out[2]), out[1].max(out[3])])
+}
+
+/// Map a 0-1000 bounding box on the page as displayed (origin top-left) to a
+/// rectangle in PDF user space, allowing for the page's /Rotate.
+fn to_user_space(page_box: [f32; 4], rotate: i64, bbox: [u32; 4]) -> [f32; 4] {
+    let [x0, y0, x1, y1] = page_box;
+    let (w, h) = (x1 - x0, y1 - y0);
+    let point = |bx: u32, by: u32| {
+        let (fx, fy) = (bx.min(1000) as f32 / 1000.0, by.min(1000) as f32 / 1000.0);
+        match rotate.rem_euclid(360) {
+            90 => (x0 + fy * w, y0 + fx * h),
+            180 => (x1 - fx * w, y0 + fy * h),
+            270 => (x1 - fy * w, y1 - fx * h),
+            _ => (x0 + fx * w, y1 - fy * h),
+        }
+    };
+    let (ax, ay) = point(bbox[0], bbox[1]);
+    let (bx, by) = point(bbox[2], bbox[3]);
+    [ax.min(bx), ay.min(by), ax.max(bx), ay.max(by)]
+}
+
+/// Append a yellow highlight with an appearance stream, so viewers that do not
+/// synthesize one still draw it.
+fn add_highlight(doc: &mut Document, page_id: ObjectId, area: [f32; 4], note: &str) -> Result<()> {
+    let [x1, y1, x2, y2] = area;
+    let appearance = Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => vec![x1.into(), y1.into(), x2.into(), y2.into()],
+            "Resources" => dictionary! {
+                "ExtGState" => dictionary! {
+                    "GS0" => dictionary! { "Type" => "ExtGState", "BM" => "Multiply", "CA" => 0.4, "ca" => 0.4 },
+                },
+            },
+        },
+        format!("q /GS0 gs 1 0.85 0 rg {x1} {y1} {} {} re f Q", x2 - x1, y2 - y1).into_bytes(),
+    );
+    let appearance_id = doc.add_object(appearance);
+    let annotation = dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Highlight",
+        "Rect" => vec![x1.into(), y1.into(), x2.into(), y2.into()],
+        "QuadPoints" => vec![
+            x1.into(), y2.into(), x2.into(), y2.into(), x1.into(), y1.into(), x2.into(), y1.into(),
+        ],
+        "C" => vec![1.into(), 0.85.into(), 0.into()],
+        "CA" => 0.4,
+        "F" => 4,
+        "T" => text_string("datasheet"),
+        "Contents" => text_string(note),
+        "P" => page_id,
+        "AP" => dictionary! { "N" => appearance_id },
+    };
+    let annotation_id = doc.add_object(annotation);
+
+    let existing = doc.get_dictionary(page_id)?.get(b"Annots").ok().cloned();
+    match existing {
+        Some(Object::Reference(id)) => match doc.get_object_mut(id)? {
+            Object::Array(annots) => annots.push(annotation_id.into()),
+            _ => return Err(anyhow!("page {:?} has an invalid /Annots entry", page_id)),
+        },
+        Some(Object::Array(mut annots)) => {
+            annots.push(annotation_id.into());
+            page_dict(doc, page_id)?.set("Annots", annots);
+        }
+        _ => page_dict(doc, page_id)?.set("Annots", vec![annotation_id.into()]),
+    }
+    Ok(())
+}
+
+fn page_dict(doc: &mut Document, page_id: ObjectId) -> Result<&mut Dictionary> {
+    Ok(doc.get_object_mut(page_id)?.as_dict_mut()?)
+}
+
+/// PDF text string: literal for ASCII, UTF-16BE with a byte order mark otherwise.
+fn text_string(text: &str) -> Object {
+    if text.is_ascii() {
+        return Object::string_literal(text);
+    }
+    let mut bytes = vec![0xFE, 0xFF];
+    bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_be_bytes()));
+    Object::String(bytes, StringFormat::Hexadecimal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_user_space() {
+        let letter = [0.0, 0.0, 600.0, 800.0];
+        // Top-left quarter of an unrotated page is the upper-left in user space
+        assert_eq!(to_user_space(letter, 0, [0, 0, 500, 500]), [0.0, 400.0, 300.0, 800.0]);
+        // With /Rotate 90 the displayed top-left corner is the user-space origin
+        assert_eq!(to_user_space(letter, 90, [0, 0, 500, 500]), [0.0, 0.0, 300.0, 400.0]);
+        assert_eq!(to_user_space(letter, -90, [0, 0, 1000, 1000]), letter);
+    }
+}
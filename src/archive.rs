@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! ZIP archives as batch input.
+//!
+//! Vendors often ship documentation as a ZIP bundle (datasheet, errata,
+//! reference manual, application notes). Any `.zip` passed where a list of
+//! PDFs is expected is unpacked and replaced by the PDFs inside it. Archives
+//! are unpacked under the datasheets directory rather than a temporary one, so
+//! queued jobs still find their PDFs when a worker runs them later.
+
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+
+/// Replace each `.zip` in `inputs` with the PDFs it contains; other paths pass
+/// through unchanged.
+pub fn expand_inputs(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut pdfs = Vec::new();
+    for input in inputs {
+        if !is_zip(input) {
+            pdfs.push(input.clone());
+            continue;
+        }
+        let bytes = std::fs::read(input).map_err(|e| format!("{}: {}", input.display(), e))?;
+        let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+        let hash = format!("{:x}", Sha256::digest(&bytes));
+        let dir = paths::datasheets_dir().join(format!("{}-{}", stem, &hash[..8]));
+        let extracted = unpack_pdfs(&bytes, &dir).map_err(|e| format!("{}: {}", input.display(), e))?;
+        if extracted.is_empty() {
+            return Err(format!("{}: archive contains no PDFs", input.display()));
+        }
+        eprintln!(
+            "[ARCHIVE] {}: {} PDF(s) unpacked to {}",
+            input.display(),
+            extracted.len(),
+            dir.display()
+        );
+        pdfs.extend(extracted);
+    }
+    Ok(pdfs)
+}
+
+fn is_zip(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Unpack the PDFs in a ZIP archive into `dir`, keeping their relative paths.
+/// Entries whose names escape `dir`, macOS resource forks, and files that are
+/// not PDFs are skipped. Returns the unpacked paths in archive order.
+fn unpack_pdfs(bytes: &[u8], dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut archive =
+        zip::ZipArchive::new(std::io::Cursor::new(bytes)).map_err(|e| format!("not a ZIP archive: {}", e))?;
+    let mut pdfs = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(|e| format!("reading archive entry: {}", e))?;
+        // enclosed_name rejects absolute paths and `..` components
+        let Some(name) = file.enclosed_name() else { continue };
+        let is_pdf = name.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+        let hidden = name.components().any(|c| {
+            let c = c.as_os_str().to_string_lossy();
+            c == "__MACOSX" || c.starts_with('.')
+        });
+        if file.is_dir() || !is_pdf || hidden {
+            continue;
+        }
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).map_err(|e| format!("reading {}: {}", name.display(), e))?;
+        if !data.starts_with(b"%PDF") {
+            eprintln!("[ARCHIVE] Skipping {}: not a PDF", name.display());
+            continue;
+        }
+        let path = dir.join(&name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("creating {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&path, data).map_err(|e| format!("writing {}: {}", path.display(), e))?;
+        pdfs.push(path);
+    }
+    Ok(pdfs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_unpack_pdfs() {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, data) in [
+            ("docs/STM32F407.pdf", &b"%PDF-1.7"[..]),
+            ("__MACOSX/docs/._STM32F407.pdf", b"%PDF-1.7"),
+            ("docs/readme.txt", b"hello"),
+            ("docs/broken.PDF", b"<html>"),
+            ("../escape.pdf", b"%PDF-1.7"),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+
+        let dir = std::env::temp_dir().join(format!("datasheet-archive-test-{}", std::process::id()));
+        let pdfs = unpack_pdfs(&bytes, &dir).unwrap();
+        assert_eq!(pdfs, vec![dir.join("docs/STM32F407.pdf")]);
+        assert!(!dir.join("docs/broken.PDF").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
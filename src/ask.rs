@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Free-form questions about a datasheet.
+//!
+//! A lightweight companion to the structured extraction tasks: the PDF goes
+//! through the same file cache, and the model returns a short answer with the
+//! pages and quotes it is based on.
+
+use crate::error::Error;
+use crate::extract::{DEFAULT_MODEL, __DEFAULT__, resolve_model};
+use crate::llm::{LlmProvider, LlmRequest, build_client, resolve_api_key};
+use crate::page_render;
+use anyhow::Result;
+use clap::Args;
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+const PROMPT: &str = include_str!("../prompts/ask.md");
+
+#[derive(Args, Debug)]
+pub struct AskArgs {
+    /// Datasheet PDF
+    pub pdf: PathBuf,
+
+    /// Question to answer from the datasheet
+    pub question: String,
+
+    /// LLM provider
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
+    pub provider: LlmProvider,
+
+    /// Model name
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
+    pub model: String,
+
+    /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Optional base URL override for Gemini API
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Disable file caching (re-upload PDF every request)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+pub fn run(args: &AskArgs) -> Result<()> {
+    if !args.pdf.exists() {
+        return Err(Error::not_found(format!("PDF not found: {}", args.pdf.display())).into());
+    }
+    let question = args.question.trim();
+    if question.is_empty() {
+        return Err(Error::validation("question is empty").into());
+    }
+    let model = resolve_model(&args.model, DEFAULT_MODEL);
+
+    let key = format!(
+        "ask|{}|{}|{}",
+        crate::file_cache::hash_file(&args.pdf)?,
+        model,
+        question
+    );
+
+    let answer = if crate::offline::is_enabled() {
+        let cached = crate::offline::cached_result(&key).ok_or_else(|| {
+            Error::not_found(format!(
+                "--offline: no cached answer to this question for {}; ask it once online first",
+                args.pdf.display()
+            ))
+        })?;
+        eprintln!("[OFFLINE] Using cached answer for {}", args.pdf.display());
+        cached
+    } else {
+        let answer = ask(args, &model, question)?;
+        crate::offline::store_result(&key, &answer);
+        answer
+    };
+
+    if args.json {
+        println!("{}", crate::select::to_string_pretty(&answer)?);
+    } else {
+        print!("{}", format_answer(&answer));
+    }
+    Ok(())
+}
+
+fn ask(args: &AskArgs, model: &str, question: &str) -> Result<Value> {
+    let api_key = resolve_api_key(args.provider, args.api_key.clone())?;
+    let attachment = page_render::make_attachment(&args.pdf, args.no_cache, &api_key, &args.base_url)?;
+    let client = build_client(args.provider, api_key, args.base_url.clone())?;
+    let _usage = crate::usage::scope("ask", Some(&args.pdf));
+    let response = client.generate_json(LlmRequest {
+        model: model.to_string(),
+        prompt: format!("{}{}\n", PROMPT, question),
+        schema: answer_schema(),
+        attachment,
+        temperature: Some(0.0),
+    })?;
+    Ok(response.json)
+}
+
+fn answer_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "answer": { "type": "string" },
+            "found": { "type": "boolean" },
+            "citations": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "page": { "type": "integer" },
+                        "location": { "type": ["string", "null"] },
+                        "quote": { "type": "string" }
+                    },
+                    "required": ["page", "quote"]
+                }
+            }
+        },
+        "required": ["answer", "found", "citations"]
+    })
+}
+
+/// Render an answer with its citations for the terminal.
+fn format_answer(answer: &Value) -> String {
+    let mut out = String::new();
+    let text = answer.get("answer").and_then(|a| a.as_str()).unwrap_or_default();
+    if answer.get("found").and_then(|f| f.as_bool()) == Some(false) {
+        let _ = writeln!(out, "Not found in the datasheet. {}", text);
+    } else {
+        let _ = writeln!(out, "{}", text);
+    }
+    for citation in answer
+        .get("citations")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let page = citation.get("page").and_then(|p| p.as_i64());
+        let location = citation.get("location").and_then(|l| l.as_str());
+        let quote = citation.get("quote").and_then(|q| q.as_str()).unwrap_or_default();
+        let mut source = match page {
+            Some(page) => format!("page {}", page),
+            None => "page ?".to_string(),
+        };
+        if let Some(location) = location {
+            source.push_str(&format!(", {}", location));
+        }
+        let _ = writeln!(out, "  [{}] \"{}\"", source, quote);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_answer() {
+        let answer = json!({
+            "answer": "50 MHz in dual I/O mode.",
+            "found": true,
+            "citations": [
+                {"page": 62, "location": "Table 9-6", "quote": "fR Dual I/O 50 MHz"},
+                {"page": 30, "location": null, "quote": "Dual Output Fast Read"}
+            ]
+        });
+        assert_eq!(
+            format_answer(&answer),
+            "50 MHz in dual I/O mode.\n  [page 62, Table 9-6] \"fR Dual I/O 50 MHz\"\n  [page 30] \"Dual Output Fast Read\"\n"
+        );
+        let missing = json!({"answer": "No SPI timing is given.", "found": false, "citations": []});
+        assert!(format_answer(&missing).starts_with("Not found in the datasheet."));
+    }
+}
@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Audit log of distributor API calls.
+//!
+//! Every request to the Mouser, DigiKey, and JLCPCB APIs is appended to
+//! `<data dir>/datasheet-cli/api-audit.jsonl` (or `$DATASHEET_AUDIT_LOG`; `off`
+//! disables it) with the endpoint, part number, HTTP status, latency, and
+//! response size. Query strings are left out of the endpoint, since Mouser
+//! takes its API key there. Responses replayed from the `--offline` cache make
+//! no request and are not logged.
+//!
+//! Once the log passes [`MAX_SIZE`] it is renamed to `api-audit.jsonl.1`, the
+//! older files move up by one, and the oldest beyond [`KEEP`] is deleted.
+
+use fs4::fs_std::FileExt;
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+const ENV_VAR_LOG: &str = "DATASHEET_AUDIT_LOG";
+
+/// Size at which the log is rotated
+const MAX_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Rotated files kept besides the current log
+const KEEP: usize = 5;
+
+/// One API request.
+#[derive(Debug, Serialize)]
+struct AuditEntry {
+    /// Unix seconds
+    ts: u64,
+    service: &'static str,
+    method: &'static str,
+    endpoint: String,
+    part: Option<String>,
+    /// `None` when no response arrived (see `error`)
+    status: Option<u16>,
+    latency_ms: u64,
+    /// Response body size; from Content-Length for error responses
+    bytes: Option<u64>,
+    error: Option<String>,
+}
+
+/// A request in flight, logged by [`Call::finish`].
+pub struct Call {
+    service: &'static str,
+    method: &'static str,
+    endpoint: String,
+    part: Option<String>,
+    started: Instant,
+}
+
+/// Start timing a request to `url` for `part`.
+pub fn start(service: &'static str, method: &'static str, url: &str, part: Option<&str>) -> Call {
+    Call {
+        service,
+        method,
+        endpoint: url.split(['?', '#']).next().unwrap_or_default().to_string(),
+        part: part.map(str::to_string),
+        started: Instant::now(),
+    }
+}
+
+impl Call {
+    /// Log the outcome of the request and return the response body. Error
+    /// responses are passed through unread (boxed, as ureq's error is large),
+    /// for the caller's error message.
+    pub fn finish(self, result: Result<ureq::Response, ureq::Error>) -> Result<Vec<u8>, Box<ureq::Error>> {
+        let (status, bytes, error, result) = match result {
+            Ok(response) => {
+                let status = response.status();
+                let mut body = Vec::new();
+                match response.into_reader().read_to_end(&mut body) {
+                    Ok(n) => (Some(status), Some(n as u64), None, Ok(body)),
+                    Err(e) => (Some(status), None, Some(e.to_string()), Err(ureq::Error::from(e))),
+                }
+            }
+            Err(ureq::Error::Status(code, response)) => {
+                let bytes = response.header("Content-Length").and_then(|n| n.parse().ok());
+                (Some(code), bytes, None, Err(ureq::Error::Status(code, response)))
+            }
+            Err(e) => (None, None, Some(e.to_string()), Err(e)),
+        };
+        let entry = AuditEntry {
+            ts: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            service: self.service,
+            method: self.method,
+            endpoint: self.endpoint,
+            part: self.part,
+            status,
+            latency_ms: self.started.elapsed().as_millis() as u64,
+            bytes,
+            error,
+        };
+        if let Some(path) = log_path() {
+            if let Err(e) = append(&path, &entry, MAX_SIZE) {
+                eprintln!("[AUDIT] Warning: could not write {}: {}", path.display(), e);
+            }
+        }
+        result.map_err(Box::new)
+    }
+}
+
+fn log_path() -> Option<PathBuf> {
+    match std::env::var(ENV_VAR_LOG) {
+        Ok(path) if path.trim().eq_ignore_ascii_case("off") => None,
+        Ok(path) if !path.trim().is_empty() => Some(PathBuf::from(path)),
+        _ => Some(dirs::data_dir()?.join("datasheet-cli").join("api-audit.jsonl")),
+    }
+}
+
+/// Append `entry` to the log at `path`, rotating it first if it has reached `max_size`.
+fn append(path: &Path, entry: &AuditEntry, max_size: u64) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+
+    // Parallel runs share the log; the lock keeps rotation from racing appends
+    let lock = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path(path))?;
+    FileExt::lock_exclusive(&lock)?;
+    if std::fs::metadata(path).is_ok_and(|m| m.len() >= max_size) {
+        let _ = std::fs::remove_file(rotated(path, KEEP));
+        for n in (0..KEEP).rev() {
+            let _ = std::fs::rename(rotated(path, n), rotated(path, n + 1));
+        }
+    }
+    std::fs::OpenOptions::new().create(true).append(true).open(path)?.write_all(line.as_bytes())
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+/// `path` for the current log (0), else `path.N`.
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    if n == 0 {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_rotates() {
+        let dir = std::env::temp_dir().join(format!("datasheet-audit-{}", std::process::id()));
+        let path = dir.join("api-audit.jsonl");
+        let call = start("mouser", "POST", "https://api.mouser.com/api/v1/search/partnumber?apiKey=SECRET", Some("LM358"));
+        assert_eq!(call.endpoint, "https://api.mouser.com/api/v1/search/partnumber");
+        let entry = AuditEntry {
+            ts: 0,
+            service: call.service,
+            method: call.method,
+            endpoint: call.endpoint,
+            part: call.part,
+            status: Some(200),
+            latency_ms: 120,
+            bytes: Some(4096),
+            error: None,
+        };
+
+        for _ in 0..(KEEP + 3) {
+            append(&path, &entry, 1).unwrap();
+        }
+        assert!(rotated(&path, KEEP).exists());
+        assert!(!rotated(&path, KEEP + 1).exists());
+        let line = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(line.lines().count(), 1);
+        let logged: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(logged["part"], "LM358");
+        assert_eq!(logged["bytes"], 4096);
+        assert!(!line.contains("SECRET"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! API keys and secrets stored in the OS keyring.
+//!
+//! `datasheet auth set <service>` prompts for each secret without echoing it
+//! and stores it under the environment variable name it replaces (e.g.
+//! `MOUSER_API_KEY`) in the platform keyring: Keychain on macOS, Credential
+//! Manager on Windows, Secret Service on Linux.
+//!
+//! Credentials resolve as: command-line flag, then environment variable, then
+//! keyring. Modules call [`credential`] wherever they used to read the
+//! variable directly.
+
+use clap::{Subcommand, ValueEnum};
+use std::io::Write;
+
+const KEYRING_SERVICE: &str = "datasheet-cli";
+
+#[derive(Subcommand, Debug)]
+pub enum AuthSubcommand {
+    /// Store a service's credentials in the OS keyring (prompts for each one)
+    Set {
+        service: AuthService,
+    },
+    /// Show where each credential currently comes from (never prints secrets)
+    Status,
+    /// Remove a service's credentials from the OS keyring
+    Delete {
+        service: AuthService,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum AuthService {
+    Mouser,
+    Digikey,
+    Gemini,
+    Inventree,
+    Partsbox,
+}
+
+impl AuthService {
+    pub const ALL: [AuthService; 5] = [
+        AuthService::Mouser,
+        AuthService::Digikey,
+        AuthService::Gemini,
+        AuthService::Inventree,
+        AuthService::Partsbox,
+    ];
+
+    /// Variables holding this service's credentials, with prompt labels.
+    pub fn credentials(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            AuthService::Mouser => &[("MOUSER_API_KEY", "Mouser API key")],
+            AuthService::Digikey => &[
+                ("DIGIKEY_CLIENT_ID", "DigiKey client ID"),
+                ("DIGIKEY_CLIENT_SECRET", "DigiKey client secret"),
+            ],
+            AuthService::Gemini => &[("GOOGLE_API_KEY", "Gemini API key")],
+            AuthService::Inventree => &[("INVENTREE_TOKEN", "InvenTree API token")],
+            AuthService::Partsbox => &[("PARTSBOX_API_KEY", "PartsBox API key")],
+        }
+    }
+}
+
+/// Value of the credential variable `var`: the environment first, then the
+/// keyring. Callers check their command-line flag before calling this.
+pub fn credential(var: &str) -> Option<String> {
+    std::env::var(var)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .or_else(|| keyring_get(var))
+}
+
+/// Where the credential variable `var` would be read from: "environment",
+/// "keyring", or `None` when it is not set anywhere.
+pub fn credential_source(var: &str) -> Option<&'static str> {
+    if std::env::var(var).is_ok_and(|v| !v.is_empty()) {
+        Some("environment")
+    } else if keyring_get(var).is_some() {
+        Some("keyring")
+    } else {
+        None
+    }
+}
+
+fn entry(var: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, var).map_err(|e| format!("Keyring unavailable: {}", e))
+}
+
+/// A missing entry or an unavailable keyring both mean "not stored".
+fn keyring_get(var: &str) -> Option<String> {
+    entry(var).ok()?.get_password().ok().filter(|v| !v.is_empty())
+}
+
+pub fn execute(cmd: AuthSubcommand) -> Result<(), String> {
+    match cmd {
+        AuthSubcommand::Set { service } => {
+            for &(var, label) in service.credentials() {
+                eprint!("{}: ", label);
+                std::io::stderr().flush().ok();
+                let secret = rpassword::read_password()
+                    .map_err(|e| format!("Failed to read {}: {}", label, e))?;
+                let secret = secret.trim();
+                if secret.is_empty() {
+                    return Err(format!("{} cannot be empty", label));
+                }
+                entry(var)?
+                    .set_password(secret)
+                    .map_err(|e| format!("Failed to store {} in the keyring: {}", var, e))?;
+            }
+            eprintln!("[AUTH] Stored {:?} credentials in the OS keyring", service);
+        }
+        AuthSubcommand::Status => {
+            for service in AuthService::ALL {
+                for &(var, _) in service.credentials() {
+                    println!("{:<24} {}", var, credential_source(var).unwrap_or("not set"));
+                }
+            }
+        }
+        AuthSubcommand::Delete { service } => {
+            for &(var, _) in service.credentials() {
+                match entry(var)?.delete_credential() {
+                    Ok(()) | Err(keyring::Error::NoEntry) => {}
+                    Err(e) => return Err(format!("Failed to delete {}: {}", var, e)),
+                }
+            }
+            eprintln!("[AUTH] Removed {:?} credentials from the OS keyring", service);
+        }
+    }
+    Ok(())
+}
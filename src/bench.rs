@@ -0,0 +1,504 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Accuracy benchmark over a labeled corpus.
+//!
+//! Each case pairs a PDF and task with a golden (hand-checked) result. Every
+//! case is extracted with every model under test and scored field by field:
+//! both results are flattened into `path = value` pairs, with array positions
+//! dropped from the path so a reordered list is not penalized, and matched as
+//! multisets. Precision is the share of extracted fields found in the golden
+//! result, recall the share of golden fields that were extracted.
+//!
+//! `--sweep model=a,b --sweep temperature=0,0.5` runs every combination, and
+//! `--out <DIR>` keeps each configuration's results and a summary (with the
+//! cost of each configuration from the usage ledger's prices) for later comparison.
+//!
+//! ```toml
+//! models = ["gemini-3.1-pro-preview", "gemini-3-flash-preview"]   # optional
+//!
+//! [[case]]
+//! pdf = "tps62130.pdf"                 # paths are relative to this file
+//! task = "pinout"
+//! golden = "tps62130.pinout.json"
+//!
+//! [[case]]
+//! pdf = "bme280.pdf"
+//! task = "custom"
+//! prompt = "prompts/i2c.md"
+//! schema = "prompts/i2c.schema.json"
+//! golden = "bme280.i2c.json"
+//! ```
+
+use anyhow::{Context, Result, anyhow};
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::paths::{UniqueNames, sanitize_filename};
+use crate::extract::{self, ExtractArgs, ExtractTask, __DEFAULT__};
+use crate::llm::LlmProvider;
+use crate::pipeline::resolve_path;
+
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// TOML file listing the corpus cases
+    #[arg(long)]
+    pub corpus: PathBuf,
+
+    /// Models to compare (comma-separated; overrides `models` in the corpus file)
+    #[arg(long, value_delimiter = ',')]
+    pub models: Vec<String>,
+
+    /// Parameter values to sweep, as KEY=V1,V2 with KEY `model` or `temperature` (repeatable);
+    /// every combination is run
+    #[arg(long, value_parser = parse_sweep)]
+    pub sweep: Vec<(String, Vec<String>)>,
+
+    /// Experiment directory for per-configuration results and the summary
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+
+    /// F1 score a configuration must reach to be recommended as the cheapest
+    #[arg(long, value_name = "F1")]
+    pub target_f1: Option<f64>,
+
+    /// Only run cases for these tasks (comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub tasks: Vec<ExtractTask>,
+
+    /// LLM provider (always Gemini)
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
+    pub provider: LlmProvider,
+
+    /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Optional base URL override for Gemini API
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Disable file caching (re-upload PDF every request)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Output the scoreboard and per-case scores as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct Corpus {
+    #[serde(default)]
+    models: Vec<String>,
+    #[serde(default, rename = "case")]
+    cases: Vec<Case>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Case {
+    pdf: String,
+    task: String,
+    golden: String,
+    prompt: Option<String>,
+    schema: Option<String>,
+    pages: Option<String>,
+}
+
+/// Field counts for one or more extractions.
+#[derive(Serialize, Debug, Default, Clone, Copy, PartialEq)]
+struct Score {
+    golden_fields: usize,
+    extracted_fields: usize,
+    matched_fields: usize,
+}
+
+impl Score {
+    fn add(&mut self, other: Score) {
+        self.golden_fields += other.golden_fields;
+        self.extracted_fields += other.extracted_fields;
+        self.matched_fields += other.matched_fields;
+    }
+
+    fn precision(&self) -> f64 {
+        ratio(self.matched_fields, self.extracted_fields)
+    }
+
+    fn recall(&self) -> f64 {
+        ratio(self.matched_fields, self.golden_fields)
+    }
+
+    fn f1(&self) -> f64 {
+        let (p, r) = (self.precision(), self.recall());
+        if p + r == 0.0 { 0.0 } else { 2.0 * p * r / (p + r) }
+    }
+}
+
+fn ratio(n: usize, d: usize) -> f64 {
+    if d == 0 { 0.0 } else { n as f64 / d as f64 }
+}
+
+/// One combination of swept parameters.
+#[derive(Debug, Clone)]
+struct Config {
+    model: String,
+    temperature: Option<f32>,
+}
+
+impl Config {
+    /// Name for reports and the experiment directory, e.g. `gemini-3-flash-preview_t0.5`.
+    fn label(&self, model: &str) -> String {
+        match self.temperature {
+            Some(t) => format!("{}_t{}", model, t),
+            None => model.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct CaseResult {
+    pdf: String,
+    task: String,
+    model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(flatten)]
+    score: Score,
+    /// Estimated USD for this extraction (0 when served from cache)
+    cost: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct Row {
+    task: String,
+    model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    cases: usize,
+    failed: usize,
+    #[serde(flatten)]
+    score: Score,
+    precision: f64,
+    recall: f64,
+    f1: f64,
+    cost: f64,
+}
+
+pub fn run(args: &BenchArgs) -> Result<()> {
+    let text = std::fs::read_to_string(&args.corpus)
+        .with_context(|| format!("reading {}", args.corpus.display()))?;
+    let corpus: Corpus =
+        toml::from_str(&text).with_context(|| format!("parsing {}", args.corpus.display()))?;
+    let base = args.corpus.parent().unwrap_or(Path::new("."));
+
+    let mut cases = Vec::new();
+    for case in &corpus.cases {
+        let task = ExtractTask::from_str(&case.task, true)
+            .map_err(|_| anyhow!("unknown task '{}' in {}", case.task, args.corpus.display()))?;
+        if args.tasks.is_empty() || args.tasks.iter().any(|t| t.prompt().name == task.prompt().name) {
+            cases.push((task, case));
+        }
+    }
+    if cases.is_empty() {
+        return Err(anyhow!("no [[case]] entries to run in {}", args.corpus.display()));
+    }
+    let configs = configs(args, &corpus.models)?;
+
+    let mut results = Vec::new();
+    // Output file names per configuration and task, so two cases' PDFs with the same stem do not collide
+    let mut names: HashMap<(String, &str), UniqueNames> = HashMap::new();
+    for (task, case) in &cases {
+        let pdf = base.join(&case.pdf);
+        let golden_path = base.join(&case.golden);
+        let golden: Value = serde_json::from_str(
+            &std::fs::read_to_string(&golden_path)
+                .with_context(|| format!("reading {}", golden_path.display()))?,
+        )
+        .with_context(|| format!("parsing {}", golden_path.display()))?;
+
+        for config in &configs {
+            let model_name = extract::resolve_model(&config.model, task.default_model());
+            let label = config.label(&model_name);
+            eprintln!("[BENCH] {} {} with {}", task.prompt().name, case.pdf, label);
+            let extract_args = ExtractArgs {
+                provider: args.provider,
+                model: config.model.clone(),
+                api_key: args.api_key.clone(),
+                base_url: args.base_url.clone(),
+                temperature: config.temperature,
+                prompt: case.prompt.as_deref().map(|p| resolve_path(base, p)),
+                schema: case.schema.as_deref().map(|s| resolve_path(base, s)),
+                pages: case.pages.clone(),
+                no_cache: args.no_cache,
+                ..ExtractArgs::for_task(*task, pdf.clone())
+            };
+            let spent = crate::usage::session_totals().cost;
+            let output = extract::extract_json(&extract_args);
+            let cost = crate::usage::session_totals().cost - spent;
+            let (score, error) = match output {
+                Ok(extracted) => {
+                    if let Some(dir) = &args.out {
+                        let dir = dir.join(sanitize_filename(&label));
+                        std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+                        let stem = Path::new(&case.pdf).file_stem().unwrap_or_default().to_string_lossy();
+                        let names = names.entry((label.clone(), task.prompt().name)).or_default();
+                        let name = format!("{}.{}.json", names.name_for(&case.pdf, &stem), task.prompt().name);
+                        extract::write_output(&extracted, Some(&dir.join(name)), true)?;
+                    }
+                    (score(&golden, &extracted), None)
+                }
+                Err(e) => {
+                    eprintln!("[BENCH] Failed: {:#}", e);
+                    let missed = Score { golden_fields: fields(&golden).values().sum(), ..Score::default() };
+                    (missed, Some(format!("{:#}", e)))
+                }
+            };
+            results.push(CaseResult {
+                pdf: case.pdf.clone(),
+                task: task.prompt().name.to_string(),
+                model: model_name,
+                temperature: config.temperature,
+                score,
+                cost,
+                error,
+            });
+        }
+    }
+
+    let scoreboard = scoreboard(&results);
+    let recommended = args.target_f1.map(|target| cheapest(&scoreboard, target));
+    let table = format_table(&scoreboard, args.target_f1, recommended.as_deref());
+    let report = serde_json::json!({
+        "scoreboard": scoreboard,
+        "recommended": recommended,
+        "cases": results,
+    });
+    if let Some(dir) = &args.out {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        crate::paths::write_file(&dir.join("summary.json"), serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("writing summary to {}", dir.display()))?;
+        crate::paths::write_file(&dir.join("summary.txt"), &table)
+            .with_context(|| format!("writing summary to {}", dir.display()))?;
+        eprintln!("[BENCH] Results and summary written to {}", dir.display());
+    }
+    if args.json {
+        println!("{}", crate::select::to_string_pretty(&report)?);
+    } else {
+        print!("{}", table);
+    }
+    Ok(())
+}
+
+/// Expand `--sweep` (or the model list) into the configurations to run.
+fn configs(args: &BenchArgs, corpus_models: &[String]) -> Result<Vec<Config>> {
+    let mut models = if !args.models.is_empty() {
+        args.models.clone()
+    } else if !corpus_models.is_empty() {
+        corpus_models.to_vec()
+    } else {
+        vec![__DEFAULT__.to_string()]
+    };
+    let mut temperatures = vec![None];
+    for (key, values) in &args.sweep {
+        match key.as_str() {
+            "model" => models = values.clone(),
+            _ => {
+                temperatures = values
+                    .iter()
+                    .map(|v| v.parse::<f32>().map(Some).map_err(|_| anyhow!("invalid temperature '{}'", v)))
+                    .collect::<Result<_>>()?;
+            }
+        }
+    }
+    Ok(models
+        .iter()
+        .flat_map(|model| {
+            temperatures.iter().map(|&temperature| Config { model: model.clone(), temperature })
+        })
+        .collect())
+}
+
+fn parse_sweep(s: &str) -> Result<(String, Vec<String>), String> {
+    let (key, values) = s.split_once('=').ok_or_else(|| format!("'{}': expected KEY=V1,V2", s))?;
+    let key = key.trim().to_ascii_lowercase();
+    if key != "model" && key != "temperature" {
+        return Err(format!("cannot sweep '{}'; use model or temperature", key));
+    }
+    let values: Vec<String> =
+        values.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect();
+    if values.is_empty() {
+        return Err(format!("'{}': no values to sweep", s));
+    }
+    Ok((key, values))
+}
+
+/// Cheapest configuration per task whose F1 reaches `target`, as "task: configuration" lines.
+fn cheapest(scoreboard: &[Row], target: f64) -> Vec<String> {
+    let mut tasks: Vec<&str> = scoreboard.iter().map(|row| row.task.as_str()).collect();
+    tasks.dedup();
+    tasks
+        .into_iter()
+        .map(|task| {
+            let best = scoreboard
+                .iter()
+                .filter(|row| row.task == task && row.failed == 0 && row.f1 >= target)
+                .min_by(|a, b| a.cost.total_cmp(&b.cost).then(b.f1.total_cmp(&a.f1)));
+            match best {
+                Some(row) => format!(
+                    "{}: {} (F1 {:.3}, ${:.4})",
+                    task,
+                    Config { model: row.model.clone(), temperature: row.temperature }.label(&row.model),
+                    row.f1,
+                    row.cost
+                ),
+                None => format!("{}: no configuration reaches F1 {}", task, target),
+            }
+        })
+        .collect()
+}
+
+fn format_table(scoreboard: &[Row], target: Option<f64>, recommended: Option<&[String]>) -> String {
+    let mut out = format!(
+        "{:<20} {:<28} {:>5} {:>6} {:>7} {:>10} {:>8} {:>6} {:>10}\n",
+        "Task", "Model", "Temp", "Cases", "Failed", "Precision", "Recall", "F1", "Cost (USD)"
+    );
+    for row in scoreboard {
+        let temperature = row.temperature.map(|t| t.to_string()).unwrap_or_else(|| "-".to_string());
+        out.push_str(&format!(
+            "{:<20} {:<28} {:>5} {:>6} {:>7} {:>10.3} {:>8.3} {:>6.3} {:>10.4}\n",
+            row.task, row.model, temperature, row.cases, row.failed, row.precision, row.recall, row.f1, row.cost
+        ));
+    }
+    if let (Some(target), Some(recommended)) = (target, recommended) {
+        out.push_str(&format!("\nCheapest configuration with F1 >= {}:\n", target));
+        for line in recommended {
+            out.push_str(&format!("  {}\n", line));
+        }
+    }
+    out
+}
+
+/// Totals per task and configuration, in task, model, then temperature order.
+fn scoreboard(results: &[CaseResult]) -> Vec<Row> {
+    let mut rows: BTreeMap<(String, String, String), Row> = BTreeMap::new();
+    for result in results {
+        let temperature = result.temperature.map(|t| format!("{:08.3}", t)).unwrap_or_default();
+        let key = (result.task.clone(), result.model.clone(), temperature);
+        let row = rows.entry(key).or_insert_with(|| Row {
+            task: result.task.clone(),
+            model: result.model.clone(),
+            temperature: result.temperature,
+            ..Row::default()
+        });
+        row.cases += 1;
+        row.failed += usize::from(result.error.is_some());
+        row.score.add(result.score);
+        row.cost += result.cost;
+    }
+    rows.into_values()
+        .map(|mut row| {
+            row.precision = row.score.precision();
+            row.recall = row.score.recall();
+            row.f1 = row.score.f1();
+            row
+        })
+        .collect()
+}
+
+/// Compare an extraction against its golden result.
+fn score(golden: &Value, extracted: &Value) -> Score {
+    let golden = fields(golden);
+    let extracted = fields(extracted);
+    let matched = extracted
+        .iter()
+        .map(|(field, &count)| count.min(golden.get(field).copied().unwrap_or(0)))
+        .sum();
+    Score {
+        golden_fields: golden.values().sum(),
+        extracted_fields: extracted.values().sum(),
+        matched_fields: matched,
+    }
+}
+
+/// `path = value` pairs (array positions dropped) with their counts. Nulls are
+/// skipped; strings compare case- and whitespace-insensitively.
+pub(crate) fn fields(value: &Value) -> HashMap<String, usize> {
+    fn walk(value: &Value, path: &mut String, out: &mut HashMap<String, usize>) {
+        let len = path.len();
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    if path.is_empty() && key == crate::migrate::STAMP {
+                        continue;
+                    }
+                    path.push('/');
+                    path.push_str(key);
+                    walk(child, path, out);
+                    path.truncate(len);
+                }
+            }
+            Value::Array(items) => {
+                path.push_str("/*");
+                for child in items {
+                    walk(child, path, out);
+                }
+                path.truncate(len);
+            }
+            Value::Null => {}
+            Value::String(s) => {
+                let normalized = s.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+                *out.entry(format!("{path} = {normalized:?}")).or_default() += 1;
+            }
+            Value::Number(n) => {
+                let n = n.as_f64().map_or_else(|| n.to_string(), |f| f.to_string());
+                *out.entry(format!("{path} = {n}")).or_default() += 1;
+            }
+            Value::Bool(b) => *out.entry(format!("{path} = {b}")).or_default() += 1,
+        }
+    }
+    let mut out = HashMap::new();
+    walk(value, &mut String::new(), &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_score() {
+        let golden = json!({"pins": [{"number": 1, "name": "VIN"}, {"number": 2, "name": "GND"}], "note": null});
+        let extracted = json!({"pins": [{"number": 2.0, "name": "gnd"}, {"number": 1, "name": "VIN "}, {"number": 3, "name": "EN"}]});
+        let score = score(&golden, &extracted);
+        assert_eq!(score, Score { golden_fields: 4, extracted_fields: 6, matched_fields: 4 });
+        assert_eq!(score.recall(), 1.0);
+        assert!((score.precision() - 4.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sweep_configs() {
+        let args = BenchArgs {
+            corpus: PathBuf::from("corpus.toml"),
+            models: Vec::new(),
+            sweep: vec![parse_sweep("model=a,b").unwrap(), parse_sweep("temperature=0, 0.5").unwrap()],
+            out: None,
+            target_f1: None,
+            tasks: Vec::new(),
+            provider: LlmProvider::Gemini,
+            api_key: None,
+            base_url: None,
+            no_cache: false,
+            json: false,
+        };
+        let labels: Vec<String> =
+            configs(&args, &[]).unwrap().iter().map(|c| c.label(&c.model)).collect();
+        assert_eq!(labels, vec!["a_t0", "a_t0.5", "b_t0", "b_t0.5"]);
+        assert!(parse_sweep("top_p=0.9").is_err());
+    }
+}
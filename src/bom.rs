@@ -0,0 +1,965 @@
+//! Bill-of-materials (BOM) commands.
+//!
+//! Reads a CSV/TSV BOM exported from an EDA tool and runs distributor lookups
+//! for every line, e.g. downloading a datasheet per manufacturer part number.
+
+use clap::{Subcommand, ValueEnum};
+use serde::Serialize;
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+use crate::download::{self, DownloadOptions};
+use crate::file_cache::compute_hash;
+use crate::library::Library;
+use crate::paths::UniqueNames;
+use crate::stock::{SearchHit, StockInfo};
+use crate::{digikey, jlcpcb, mouser, resolver};
+
+
+/// Header names (normalized: lowercase, alphanumerics only) recognized as the MPN column.
+const MPN_HEADERS: &[&str] = &[
+    "mpn",
+    "manufacturerpartnumber",
+    "manufacturerpn",
+    "mfrpartnumber",
+    "mfrpn",
+    "mfrno",
+    "mfgpartnumber",
+    "mfgpn",
+    "partnumber",
+    "partno",
+];
+const MANUFACTURER_HEADERS: &[&str] = &["manufacturer", "manufacturername", "mfr", "mfg", "vendor"];
+const QUANTITY_HEADERS: &[&str] = &["qty", "quantity", "count"];
+
+/// BOM subcommands.
+#[derive(Subcommand, Debug)]
+pub enum BomSubcommand {
+    /// Download a datasheet for every BOM line, trying distributors in order
+    Datasheets {
+        /// BOM file (CSV, TSV, or semicolon-separated with a header row), or a plain
+        /// list of part numbers; `-` reads from stdin
+        bom: PathBuf,
+
+        /// Output directory for downloaded datasheets (default: <download dir>/datasheets)
+        #[arg(long, short)]
+        dir: Option<PathBuf>,
+
+        /// Name of the column holding manufacturer part numbers (auto-detected by default)
+        #[arg(long)]
+        mpn_column: Option<String>,
+
+        /// Sources to try, in order (comma-separated)
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "mouser,digikey,jlcpcb,manufacturer")]
+        source: Vec<Source>,
+
+        /// Number of parts to download concurrently
+        #[arg(long, short, default_value = "4")]
+        jobs: usize,
+
+        /// Also add downloaded datasheets to the local library
+        #[arg(long)]
+        library: bool,
+
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Webhook to POST a summary to when the run finishes or fails (repeatable; Slack-compatible)
+        #[arg(long, value_name = "URL", env = "DATASHEET_NOTIFY", value_delimiter = ',')]
+        notify: Vec<String>,
+    },
+
+    /// Report lifecycle, stock, sourcing, and lead-time risks for every BOM line
+    Risk {
+        /// BOM file (CSV, TSV, or semicolon-separated with a header row), or a plain
+        /// list of part numbers; `-` reads from stdin
+        bom: PathBuf,
+
+        /// Name of the column holding manufacturer part numbers (auto-detected by default)
+        #[arg(long)]
+        mpn_column: Option<String>,
+
+        /// Distributors to query (comma-separated)
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "mouser,digikey,jlcpcb")]
+        source: Vec<Source>,
+
+        /// Number of boards to build (multiplies the BOM quantity column)
+        #[arg(long, default_value = "1")]
+        builds: u32,
+
+        /// Flag parts whose combined distributor stock is below this many units
+        #[arg(long, default_value = "1000")]
+        min_stock: i64,
+
+        /// Flag parts whose shortest quoted lead time exceeds this many weeks
+        #[arg(long, default_value = "12")]
+        max_lead_weeks: f64,
+
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Webhook to POST a summary to when the run finishes or fails (repeatable; Slack-compatible)
+        #[arg(long, value_name = "URL", env = "DATASHEET_NOTIFY", value_delimiter = ',')]
+        notify: Vec<String>,
+    },
+}
+
+/// Part data sources queried by BOM commands.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Mouser,
+    Digikey,
+    Jlcpcb,
+    /// Direct manufacturer website URLs (datasheets only)
+    Manufacturer,
+}
+
+impl Source {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Source::Mouser => "mouser",
+            Source::Digikey => "digikey",
+            Source::Jlcpcb => "jlcpcb",
+            Source::Manufacturer => "manufacturer",
+        }
+    }
+
+    pub(crate) fn find_datasheet_url(self, mpn: &str, manufacturer: Option<&str>) -> Result<Option<String>, String> {
+        match self {
+            Source::Mouser => Ok(mouser::find_datasheet_url(mpn)?),
+            Source::Digikey => Ok(digikey::find_datasheet_url(mpn)?),
+            Source::Jlcpcb => jlcpcb::find_datasheet_url(mpn),
+            Source::Manufacturer => resolver::find_datasheet_url(mpn, manufacturer),
+        }
+    }
+
+    pub(crate) fn search(self, keyword: &str, limit: usize) -> Result<Vec<SearchHit>, String> {
+        match self {
+            Source::Mouser => Ok(mouser::search_parts(keyword, limit)?),
+            Source::Digikey => Ok(digikey::search_parts(keyword, limit)?),
+            Source::Jlcpcb => jlcpcb::search_parts(keyword, limit),
+            Source::Manufacturer => Err("manufacturer sites do not support search".to_string()),
+        }
+    }
+
+    pub(crate) fn lookup_stock(self, mpn: &str) -> Result<StockInfo, String> {
+        match self {
+            Source::Mouser => Ok(mouser::lookup_stock(mpn)?),
+            Source::Digikey => Ok(digikey::lookup_stock(mpn)?),
+            Source::Jlcpcb => jlcpcb::lookup_stock(mpn),
+            Source::Manufacturer => Err("manufacturer sites do not report stock".to_string()),
+        }
+    }
+}
+
+/// A single BOM line with a manufacturer part number.
+#[derive(Debug, Clone)]
+pub struct BomLine {
+    /// 1-based line number in the BOM file
+    pub line: usize,
+    pub mpn: String,
+    pub manufacturer: Option<String>,
+    /// Quantity per board, if the BOM has a quantity column
+    pub quantity: Option<u32>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum DownloadStatus {
+    Downloaded,
+    Existing,
+    Duplicate,
+    NotFound,
+    Failed,
+}
+
+#[derive(Serialize, Debug)]
+struct DatasheetReport {
+    mpn: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manufacturer: Option<String>,
+    lines: Vec<usize>,
+    status: DownloadStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicate_of: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>,
+}
+
+/// Execute a BOM subcommand.
+pub fn execute(command: BomSubcommand) -> Result<(), String> {
+    match command {
+        BomSubcommand::Datasheets {
+            bom,
+            dir,
+            mpn_column,
+            source,
+            jobs,
+            library,
+            json,
+            notify,
+        } => {
+            let started = Instant::now();
+            let dir = dir.unwrap_or_else(crate::paths::datasheets_dir);
+            let outcome = (if library { Library::open(None).map(Some) } else { Ok(None) }).and_then(|library| {
+                cmd_datasheets(&bom, &dir, mpn_column.as_deref(), &source, jobs, library.as_ref(), json)
+            });
+            crate::notify::send(&notify, "bom datasheets", started, &outcome);
+            outcome.map(|_| ())
+        }
+        BomSubcommand::Risk {
+            bom,
+            mpn_column,
+            source,
+            builds,
+            min_stock,
+            max_lead_weeks,
+            json,
+            notify,
+        } => {
+            let started = Instant::now();
+            let thresholds = RiskThresholds {
+                builds,
+                min_stock,
+                max_lead_weeks,
+            };
+            let outcome = cmd_risk(&bom, mpn_column.as_deref(), &source, &thresholds, json);
+            crate::notify::send(&notify, "bom risk", started, &outcome);
+            outcome.map(|_| ())
+        }
+    }
+}
+
+/// Returns the summary line.
+fn cmd_datasheets(
+    bom: &Path,
+    dir: &Path,
+    mpn_column: Option<&str>,
+    sources: &[Source],
+    jobs: usize,
+    library: Option<&Library>,
+    json_output: bool,
+) -> Result<String, String> {
+    let lines = read_bom(bom, mpn_column)?;
+    if lines.is_empty() {
+        return Err(format!("No part numbers found in BOM: {}", bom.display()));
+    }
+
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create output directory {}: {}", dir.display(), e))?;
+
+    // Group BOM lines by MPN so each part is fetched once
+    let mut reports: Vec<DatasheetReport> = Vec::new();
+    let mut by_mpn: HashMap<String, usize> = HashMap::new();
+    for line in &lines {
+        let key = line.mpn.to_uppercase();
+        if let Some(&idx) = by_mpn.get(&key) {
+            reports[idx].lines.push(line.line);
+            continue;
+        }
+        by_mpn.insert(key, reports.len());
+        reports.push(DatasheetReport {
+            mpn: line.mpn.clone(),
+            manufacturer: line.manufacturer.clone(),
+            lines: vec![line.line],
+            status: DownloadStatus::NotFound,
+            source: None,
+            url: None,
+            file: None,
+            sha256: None,
+            duplicate_of: None,
+            errors: Vec::new(),
+        });
+    }
+
+    let options = DownloadOptions::default();
+    // Content hash -> first file written with that content
+    let mut hashes: HashMap<String, PathBuf> = HashMap::new();
+    let total = reports.len();
+
+    // File names per part, numbered where two part numbers sanitize to the same name
+    let mut names = UniqueNames::default();
+    let stems: Vec<String> = reports.iter().map(|report| names.name(&report.mpn)).collect();
+
+    // Register datasheets already on disk first so duplicate detection does not
+    // depend on which worker finishes first
+    for (report, stem) in reports.iter_mut().zip(&stems) {
+        let output_path = dir.join(format!("{}.pdf", stem));
+        if let Ok(data) = fs::read(&output_path) {
+            let hash = compute_hash(&data);
+            hashes.entry(hash.clone()).or_insert_with(|| output_path.clone());
+            report.status = DownloadStatus::Existing;
+            report.file = Some(output_path);
+            report.sha256 = Some(hash);
+        }
+    }
+    let hashes = Mutex::new(hashes);
+
+    let multi = MultiProgress::new();
+    if crate::progress::is_json() {
+        multi.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    let overall = multi.add(ProgressBar::new(total as u64));
+    overall.set_style(
+        ProgressStyle::with_template("[BOM] {bar:30} {pos}/{len} parts, {msg} (ETA {eta})")
+            .unwrap_or_else(|_| ProgressStyle::default_bar()),
+    );
+    overall.set_message("0 B");
+    let downloaded_bytes = AtomicU64::new(0);
+
+    // Bounded worker pool pulling parts off a shared index
+    let next = AtomicUsize::new(0);
+    let reports: Vec<Mutex<DatasheetReport>> = reports.into_iter().map(Mutex::new).collect();
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, total) {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(slot) = reports.get(i) else { break };
+                    let mut report = slot.lock().unwrap_or_else(|e| e.into_inner());
+                    if report.status == DownloadStatus::Existing {
+                        overall.inc(1);
+                        continue;
+                    }
+
+                    let bar = multi.insert_before(&overall, ProgressBar::new_spinner());
+                    bar.set_style(
+                        ProgressStyle::with_template("  {spinner} {prefix:<24} {bytes}/{total_bytes} {msg}")
+                            .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+                    );
+                    bar.set_prefix(report.mpn.clone());
+
+                    let bytes = fetch_datasheet(&mut report, &stems[i], sources, dir, &options, &hashes, &bar);
+                    if let Some(library) = library {
+                        add_to_library(library, &report);
+                    }
+                    let sum = downloaded_bytes.fetch_add(bytes, Ordering::SeqCst) + bytes;
+
+                    bar.finish_and_clear();
+                    multi.remove(&bar);
+                    overall.set_message(HumanBytes(sum).to_string());
+                    overall.inc(1);
+                }
+            });
+        }
+    });
+    overall.finish_and_clear();
+
+    let reports: Vec<DatasheetReport> = reports
+        .into_iter()
+        .map(|r| r.into_inner().unwrap_or_else(|e| e.into_inner()))
+        .collect();
+
+    let count = |status: DownloadStatus| reports.iter().filter(|r| r.status == status).count();
+    let summary = format!(
+        "{} part(s): {} downloaded, {} already present, {} duplicate, {} not found, {} failed",
+        total,
+        count(DownloadStatus::Downloaded),
+        count(DownloadStatus::Existing),
+        count(DownloadStatus::Duplicate),
+        count(DownloadStatus::NotFound),
+        count(DownloadStatus::Failed),
+    );
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&reports)
+            .map_err(|e| format!("Failed to serialize report: {}", e))?;
+        println!("{}", json);
+        return Ok(summary);
+    }
+
+    println!("{}", summary);
+
+    for report in &reports {
+        if report.status == DownloadStatus::Duplicate {
+            if let Some(ref original) = report.duplicate_of {
+                println!("  {} is identical to {}", report.mpn, original.display());
+            }
+        }
+    }
+
+    let missing: Vec<&DatasheetReport> = reports
+        .iter()
+        .filter(|r| matches!(r.status, DownloadStatus::NotFound | DownloadStatus::Failed))
+        .collect();
+    if !missing.is_empty() {
+        println!();
+        println!("No datasheet found:");
+        for report in missing {
+            println!("  {} (line {})", report.mpn, join_lines(&report.lines));
+            for err in &report.errors {
+                println!("    {}", err);
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Copy a downloaded datasheet into the library, recording failures on the report.
+fn add_to_library(library: &Library, report: &DatasheetReport) {
+    let file = match (&report.file, &report.duplicate_of) {
+        (Some(file), _) if report.status == DownloadStatus::Downloaded => file,
+        (_, Some(original)) => original,
+        _ => return,
+    };
+    let result = fs::read(file).map_err(|e| e.to_string()).and_then(|data| {
+        library.add_bytes(&report.mpn, report.manufacturer.as_deref(), &data, report.url.clone(), None)
+    });
+    if let Err(e) = result {
+        eprintln!("[BOM] Failed to add {} to library: {}", report.mpn, e);
+    }
+}
+
+/// Try each source in order until one yields a PDF; returns the bytes downloaded.
+fn fetch_datasheet(
+    report: &mut DatasheetReport,
+    stem: &str,
+    sources: &[Source],
+    dir: &Path,
+    options: &DownloadOptions,
+    hashes: &Mutex<HashMap<String, PathBuf>>,
+    bar: &ProgressBar,
+) -> u64 {
+    let output_path = dir.join(format!("{}.pdf", stem));
+    let scratch_path = dir.join(format!("{}.pdf.download", stem));
+
+    for &source in sources {
+        bar.set_message(format!("looking up on {}", source.name()));
+        let url = match source.find_datasheet_url(&report.mpn, report.manufacturer.as_deref()) {
+            Ok(Some(url)) => url,
+            Ok(None) => continue,
+            Err(e) => {
+                report.errors.push(format!("{}: {}", source.name(), e));
+                continue;
+            }
+        };
+
+        bar.set_message(format!("from {}", source.name()));
+        let progress = |downloaded: u64, total: Option<u64>| {
+            if let Some(total) = total {
+                bar.set_length(total);
+            }
+            bar.set_position(downloaded);
+        };
+        match download::fetch_pdf_attributed(&url, &scratch_path, options, &progress) {
+            Ok((data, attribution)) => {
+                let hash = compute_hash(&data);
+                report.source = Some(source.name());
+                report.url = Some(url);
+                let mut hashes = hashes.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(existing) = hashes.get(&hash) {
+                    report.status = DownloadStatus::Duplicate;
+                    report.duplicate_of = Some(existing.clone());
+                } else {
+                    if let Err(e) = crate::paths::write_file(&output_path, &data) {
+                        report.status = DownloadStatus::Failed;
+                        report.errors.push(format!("writing {}: {}", output_path.display(), e));
+                        return 0;
+                    }
+                    if let Some(record) = attribution {
+                        if let Err(e) = crate::download_policy::write_attribution(&output_path, &record) {
+                            report.errors.push(e);
+                        }
+                    }
+                    hashes.insert(hash.clone(), output_path.clone());
+                    report.status = DownloadStatus::Downloaded;
+                    report.file = Some(output_path);
+                }
+                report.sha256 = Some(hash);
+                return data.len() as u64;
+            }
+            Err(e) => {
+                report.status = DownloadStatus::Failed;
+                report.errors.push(format!("{}: {}", source.name(), e));
+            }
+        }
+    }
+    0
+}
+
+// --- Risk report ---
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+enum Severity {
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct RiskFinding {
+    severity: Severity,
+    category: &'static str,
+    message: String,
+}
+
+#[derive(Serialize, Debug)]
+struct PartRisk {
+    mpn: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manufacturer: Option<String>,
+    lines: Vec<usize>,
+    required_quantity: i64,
+    total_stock: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<Severity>,
+    findings: Vec<RiskFinding>,
+    listings: Vec<StockInfo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>,
+}
+
+struct RiskThresholds {
+    builds: u32,
+    min_stock: i64,
+    max_lead_weeks: f64,
+}
+
+/// Returns the summary line.
+fn cmd_risk(
+    bom: &Path,
+    mpn_column: Option<&str>,
+    sources: &[Source],
+    thresholds: &RiskThresholds,
+    json_output: bool,
+) -> Result<String, String> {
+    let lines = read_bom(bom, mpn_column)?;
+    if lines.is_empty() {
+        return Err(format!("No part numbers found in BOM: {}", bom.display()));
+    }
+
+    // Group BOM lines by MPN, summing per-board quantities
+    let mut parts: Vec<PartRisk> = Vec::new();
+    let mut by_mpn: HashMap<String, usize> = HashMap::new();
+    for line in &lines {
+        let qty = line.quantity.unwrap_or(1) as i64 * thresholds.builds as i64;
+        let key = line.mpn.to_uppercase();
+        if let Some(&idx) = by_mpn.get(&key) {
+            parts[idx].lines.push(line.line);
+            parts[idx].required_quantity += qty;
+            continue;
+        }
+        by_mpn.insert(key, parts.len());
+        parts.push(PartRisk {
+            mpn: line.mpn.clone(),
+            manufacturer: line.manufacturer.clone(),
+            lines: vec![line.line],
+            required_quantity: qty,
+            total_stock: 0,
+            severity: None,
+            findings: Vec::new(),
+            listings: Vec::new(),
+            errors: Vec::new(),
+        });
+    }
+
+    let total = parts.len();
+    for (i, part) in parts.iter_mut().enumerate() {
+        eprintln!("[BOM] ({}/{}) {}", i + 1, total, part.mpn);
+        for &source in sources {
+            match source.lookup_stock(&part.mpn) {
+                Ok(info) => part.listings.push(info),
+                Err(e) => part.errors.push(format!("{}: {}", source.name(), e)),
+            }
+        }
+        assess_risk(part, thresholds);
+    }
+
+    parts.sort_by_key(|p| std::cmp::Reverse(p.severity));
+
+    let flagged: Vec<&PartRisk> = parts.iter().filter(|p| p.severity.is_some()).collect();
+    let summary = format!(
+        "{} part(s) checked, {} flagged (builds: {})",
+        total,
+        flagged.len(),
+        thresholds.builds
+    );
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&parts)
+            .map_err(|e| format!("Failed to serialize report: {}", e))?;
+        println!("{}", json);
+        return Ok(summary);
+    }
+
+    println!("{}", summary);
+
+    for part in flagged {
+        let severity = part.severity.map(Severity::label).unwrap_or_default();
+        println!();
+        println!("{:<8} {} (line {})", severity, part.mpn, join_lines(&part.lines));
+        for finding in &part.findings {
+            println!("  [{}] {}: {}", finding.severity.label(), finding.category, finding.message);
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Populate `findings`, `total_stock`, and the overall `severity` for one part.
+fn assess_risk(part: &mut PartRisk, thresholds: &RiskThresholds) {
+    let mut findings = Vec::new();
+
+    if part.listings.is_empty() {
+        findings.push(RiskFinding {
+            severity: Severity::High,
+            category: "availability",
+            message: "not found at any queried distributor".to_string(),
+        });
+    }
+
+    for listing in &part.listings {
+        let Some(status) = listing.lifecycle_status.as_deref() else {
+            continue;
+        };
+        if let Some(severity) = lifecycle_severity(status) {
+            let mut message = format!("{} reports \"{}\"", listing.distributor, status);
+            if let Some(ref replacement) = listing.suggested_replacement {
+                if !replacement.is_empty() {
+                    message.push_str(&format!(" (suggested replacement: {})", replacement));
+                }
+            }
+            findings.push(RiskFinding {
+                severity,
+                category: "lifecycle",
+                message,
+            });
+        }
+    }
+
+    part.total_stock = part.listings.iter().filter_map(|l| l.stock).sum();
+    let stocked: Vec<&str> = part
+        .listings
+        .iter()
+        .filter(|l| l.stock.unwrap_or(0) > 0)
+        .map(|l| l.distributor)
+        .collect();
+
+    if !part.listings.is_empty() {
+        if part.total_stock == 0 {
+            findings.push(RiskFinding {
+                severity: Severity::Critical,
+                category: "stock",
+                message: "out of stock at every distributor".to_string(),
+            });
+        } else if part.total_stock < part.required_quantity {
+            findings.push(RiskFinding {
+                severity: Severity::High,
+                category: "stock",
+                message: format!(
+                    "combined stock {} is below the required {}",
+                    part.total_stock, part.required_quantity
+                ),
+            });
+        } else if part.total_stock < thresholds.min_stock {
+            findings.push(RiskFinding {
+                severity: Severity::Medium,
+                category: "stock",
+                message: format!(
+                    "combined stock {} is below the {} unit threshold",
+                    part.total_stock, thresholds.min_stock
+                ),
+            });
+        }
+    }
+
+    if stocked.len() == 1 && part.listings.len() > 1 {
+        findings.push(RiskFinding {
+            severity: Severity::Medium,
+            category: "sourcing",
+            message: format!("only stocked at {}", stocked[0]),
+        });
+    }
+
+    let shortest_lead = part
+        .listings
+        .iter()
+        .filter_map(|l| l.lead_time.as_deref().and_then(parse_lead_time_weeks))
+        .fold(None, |acc: Option<f64>, w| Some(acc.map_or(w, |a| a.min(w))));
+    if let Some(weeks) = shortest_lead {
+        if weeks > thresholds.max_lead_weeks {
+            findings.push(RiskFinding {
+                severity: Severity::Medium,
+                category: "lead_time",
+                message: format!(
+                    "shortest quoted lead time is {:.0} weeks (threshold {:.0})",
+                    weeks, thresholds.max_lead_weeks
+                ),
+            });
+        }
+    }
+
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    part.severity = findings.first().map(|f| f.severity);
+    part.findings = findings;
+}
+
+/// Map a distributor lifecycle string onto a risk severity, if it indicates risk.
+fn lifecycle_severity(status: &str) -> Option<Severity> {
+    let s = status.to_lowercase();
+    if s.contains("obsolete") || s.contains("discontinued") || s.contains("end of life") || s == "eol" {
+        Some(Severity::Critical)
+    } else if s.contains("last time buy") || s.contains("not recommended") || s.contains("nrnd") {
+        Some(Severity::High)
+    } else {
+        None
+    }
+}
+
+/// Parse a free-text lead time ("6 Weeks", "84 Days", "12 wks") into weeks.
+fn parse_lead_time_weeks(lead_time: &str) -> Option<f64> {
+    let lower = lead_time.to_lowercase();
+    let number: String = lower
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let value: f64 = number.parse().ok()?;
+    if lower.contains("day") {
+        Some(value / 7.0)
+    } else {
+        Some(value)
+    }
+}
+
+fn join_lines(lines: &[usize]) -> String {
+    lines.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+// --- BOM parsing ---
+
+/// Read a delimited BOM file and return every line that has a part number.
+///
+/// The delimiter (comma, tab, or semicolon) is inferred from the header row.
+/// Columns are matched by common header names unless `mpn_column` is given.
+/// A file without delimiters or a header is read as one part number per line,
+/// and `path` `-` reads from stdin.
+pub fn read_bom(path: &Path, mpn_column: Option<&str>) -> Result<Vec<BomLine>, String> {
+    let content = if path == Path::new("-") {
+        read_stdin()?
+    } else {
+        fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read BOM {}: {}", path.display(), e))?
+    };
+    parse_bom(&content, mpn_column)
+}
+
+/// Expand `-` in a list of part numbers into newline-separated MPNs read from stdin.
+pub(crate) fn expand_stdin(mpns: Vec<String>) -> Result<Vec<String>, String> {
+    if !mpns.iter().any(|m| m == "-") {
+        return Ok(mpns);
+    }
+    let stdin = read_stdin()?;
+    let mut expanded = Vec::new();
+    for mpn in mpns {
+        if mpn == "-" {
+            expanded.extend(parse_part_list(&stdin).into_iter().map(|line| line.mpn));
+        } else {
+            expanded.push(mpn);
+        }
+    }
+    Ok(expanded)
+}
+
+fn read_stdin() -> Result<String, String> {
+    let mut content = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut content)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    Ok(content)
+}
+
+/// One MPN per line; blank lines and `#` comments are skipped.
+fn parse_part_list(content: &str) -> Vec<BomLine> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i, l.trim_start_matches('\u{feff}').trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .map(|(i, l)| BomLine {
+            line: i + 1,
+            mpn: l.to_string(),
+            manufacturer: None,
+            quantity: None,
+        })
+        .collect()
+}
+
+fn parse_bom(content: &str, mpn_column: Option<&str>) -> Result<Vec<BomLine>, String> {
+    let mut rows = content
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty());
+
+    let (_, header_line) = rows.next().ok_or("BOM file is empty")?;
+    let header_line = header_line.trim_start_matches('\u{feff}');
+    let delimiter = detect_delimiter(header_line);
+    let headers: Vec<String> = split_record(header_line, delimiter)
+        .iter()
+        .map(|h| normalize_header(h))
+        .collect();
+
+    // A bare list of part numbers has no delimiter and no recognizable header
+    let single_column = !header_line.contains([',', '\t', ';']);
+    if single_column && mpn_column.is_none() && !MPN_HEADERS.contains(&headers[0].as_str()) {
+        return Ok(parse_part_list(content));
+    }
+
+    let find = |candidates: &[&str]| {
+        candidates
+            .iter()
+            .find_map(|c| headers.iter().position(|h| h == c))
+    };
+
+    let mpn_idx = match mpn_column {
+        Some(name) => {
+            let wanted = normalize_header(name);
+            headers
+                .iter()
+                .position(|h| *h == wanted)
+                .ok_or_else(|| format!("Column '{}' not found in BOM header", name))?
+        }
+        None => find(MPN_HEADERS).ok_or_else(|| {
+            "Could not find a manufacturer part number column in the BOM header. \
+             Use --mpn-column to name it explicitly."
+                .to_string()
+        })?,
+    };
+    let mfr_idx = find(MANUFACTURER_HEADERS);
+    let qty_idx = find(QUANTITY_HEADERS);
+
+    let mut lines = Vec::new();
+    for (line_no, row) in rows {
+        let fields = split_record(row, delimiter);
+        let field = |idx: Option<usize>| {
+            idx.and_then(|i| fields.get(i))
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+
+        let Some(mpn) = field(Some(mpn_idx)) else {
+            continue;
+        };
+
+        lines.push(BomLine {
+            line: line_no + 1,
+            mpn,
+            manufacturer: field(mfr_idx),
+            quantity: field(qty_idx).and_then(|q| q.parse().ok()),
+        });
+    }
+
+    Ok(lines)
+}
+
+fn detect_delimiter(header: &str) -> char {
+    [',', '\t', ';']
+        .into_iter()
+        .max_by_key(|d| header.matches(*d).count())
+        .unwrap_or(',')
+}
+
+fn normalize_header(h: &str) -> String {
+    h.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Split one delimited record, honoring double-quoted fields with `""` escapes.
+fn split_record(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => {
+                fields.push(std::mem::take(&mut current));
+            }
+            c => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bom_plain_part_list() {
+        let lines = parse_bom("# from stdin\nLM358DR\n\nNE555P\n", None).unwrap();
+        let mpns: Vec<&str> = lines.iter().map(|l| l.mpn.as_str()).collect();
+        assert_eq!(mpns, vec!["LM358DR", "NE555P"]);
+        assert_eq!(lines[1].line, 4);
+
+        let lines = parse_bom("MPN\nLM358DR\n", None).unwrap();
+        assert_eq!(lines.len(), 1);
+    }
+
+    #[test]
+    fn test_split_record_quotes() {
+        let fields = split_record(r#"C1,"100nF, 16V","say ""hi""",3"#, ',');
+        assert_eq!(fields, vec!["C1", "100nF, 16V", r#"say "hi""#, "3"]);
+    }
+
+    #[test]
+    fn test_parse_bom_detects_columns() {
+        let bom = "Reference;Qty;Manufacturer;Manufacturer Part Number\n\
+                   U1;1;TI;LM5164DDAR\n\
+                   R1;2;;\n\
+                   U2,U3;2;ST;STM32F407VGT6\n";
+        let lines = parse_bom(bom, None).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].mpn, "LM5164DDAR");
+        assert_eq!(lines[0].manufacturer.as_deref(), Some("TI"));
+        assert_eq!(lines[1].line, 4);
+        assert_eq!(lines[1].mpn, "STM32F407VGT6");
+        assert_eq!(lines[1].quantity, Some(2));
+    }
+
+    #[test]
+    fn test_lead_time_and_lifecycle() {
+        assert_eq!(parse_lead_time_weeks("6 Weeks"), Some(6.0));
+        assert_eq!(parse_lead_time_weeks("84 Days"), Some(12.0));
+        assert_eq!(parse_lead_time_weeks("Not Available"), None);
+        assert_eq!(lifecycle_severity("Obsolete"), Some(Severity::Critical));
+        assert_eq!(lifecycle_severity("Not Recommended for New Designs"), Some(Severity::High));
+        assert_eq!(lifecycle_severity("Active"), None);
+    }
+}
@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Hard limits on LLM use, checked before every Gemini request.
+//!
+//! `--max-requests-per-day` counts today's calls (UTC) in the usage ledger, so
+//! it holds across runs and across parallel `jobs run` workers.
+//! `--max-tokens-per-run` and `--max-spend-per-run` count this process only.
+//! Spend uses the ledger's cost estimates; calls to models without a known
+//! price do not count towards it. A call that would start past a limit fails
+//! with a `budget_exceeded` error instead of being sent.
+
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use crate::error::Error;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Limits {
+    pub max_requests_per_day: Option<u64>,
+    pub max_tokens_per_run: Option<u64>,
+    /// USD
+    pub max_spend_per_run: Option<f64>,
+}
+
+static LIMITS: OnceLock<Limits> = OnceLock::new();
+
+/// Estimated USD spent by this process so far.
+static SPENT: Mutex<f64> = Mutex::new(0.0);
+
+pub fn set(limits: Limits) {
+    let _ = LIMITS.set(limits);
+}
+
+/// Add the estimated cost of a finished call to this run's spend.
+pub fn record_cost(cost: f64) {
+    *SPENT.lock().unwrap_or_else(|e| e.into_inner()) += cost;
+}
+
+/// Fail with a `budget_exceeded` error if another LLM call would go past a limit.
+pub fn check() -> Result<(), Error> {
+    let Some(limits) = LIMITS.get() else {
+        return Ok(());
+    };
+    let requests_today = match limits.max_requests_per_day {
+        Some(_) => crate::usage::calls_today().map_err(Error::from)?,
+        None => 0,
+    };
+    let spent = *SPENT.lock().unwrap_or_else(|e| e.into_inner());
+    match exceeded(limits, requests_today, crate::llm::tokens_used(), spent) {
+        Some(message) => Err(Error::budget(format!("LLM budget exceeded: {}", message))),
+        None => Ok(()),
+    }
+}
+
+fn exceeded(limits: &Limits, requests_today: u64, tokens: u64, spent: f64) -> Option<String> {
+    if let Some(max) = limits.max_requests_per_day.filter(|&max| requests_today >= max) {
+        return Some(format!(
+            "{} of {} requests allowed today already made (--max-requests-per-day)",
+            requests_today, max
+        ));
+    }
+    if let Some(max) = limits.max_tokens_per_run.filter(|&max| tokens >= max) {
+        return Some(format!("{} tokens used of {} allowed per run (--max-tokens-per-run)", tokens, max));
+    }
+    if let Some(max) = limits.max_spend_per_run.filter(|&max| spent >= max) {
+        return Some(format!("~${:.2} spent of ${:.2} allowed per run (--max-spend-per-run)", spent, max));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exceeded() {
+        let limits = Limits {
+            max_requests_per_day: Some(100),
+            max_tokens_per_run: Some(50_000),
+            max_spend_per_run: Some(1.5),
+        };
+        assert_eq!(exceeded(&limits, 99, 49_999, 1.49), None);
+        assert!(exceeded(&limits, 100, 0, 0.0).unwrap().contains("--max-requests-per-day"));
+        assert!(exceeded(&limits, 0, 50_000, 0.0).unwrap().contains("--max-tokens-per-run"));
+        assert_eq!(
+            exceeded(&limits, 0, 0, 1.5).as_deref(),
+            Some("~$1.50 spent of $1.50 allowed per run (--max-spend-per-run)")
+        );
+        assert_eq!(exceeded(&Limits::default(), u64::MAX, u64::MAX, f64::MAX), None);
+    }
+}
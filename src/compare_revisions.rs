@@ -0,0 +1,487 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Change report between two revisions of a datasheet.
+//!
+//! Both PDFs are attached to one request and the model reports changed
+//! parameters, pins, and incorporated errata. A local diff of the PDF text
+//! layers backs the report up with the exact lines that differ, which also
+//! catches small edits the model glosses over.
+
+use crate::error::Error;
+use crate::extract::{DEFAULT_MODEL, __DEFAULT__, resolve_model};
+use crate::llm::{AttachmentSource, LlmProvider, LlmRequest, build_client, resolve_api_key};
+use crate::page_render;
+use anyhow::Result;
+use clap::Args;
+use lopdf::content::Content;
+use lopdf::{Document, Encoding, Object, ObjectId};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+const PROMPT: &str = include_str!("../prompts/compare-revisions.md");
+
+/// Text diff lines shown in the human-readable report
+const SHOWN_DIFF_LINES: usize = 40;
+
+#[derive(Args, Debug)]
+pub struct CompareRevisionsArgs {
+    /// Older revision of the datasheet
+    pub old: PathBuf,
+
+    /// Newer revision of the datasheet
+    pub new: PathBuf,
+
+    /// Skip the model report; only diff the PDF text layers (no API key needed)
+    #[arg(long)]
+    pub text_only: bool,
+
+    /// LLM provider
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
+    pub provider: LlmProvider,
+
+    /// Model name
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
+    pub model: String,
+
+    /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Optional base URL override for Gemini API
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Disable file caching (re-upload PDFs every request)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct DiffLine {
+    /// 1-based PDF page
+    page: u32,
+    text: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct TextDiff {
+    old_pages: usize,
+    new_pages: usize,
+    /// Lines only in the old revision
+    removed: Vec<DiffLine>,
+    /// Lines only in the new revision
+    added: Vec<DiffLine>,
+}
+
+#[derive(Serialize, Debug)]
+struct Report<'a> {
+    old: &'a Path,
+    new: &'a Path,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changes: Option<Value>,
+    text_diff: TextDiff,
+}
+
+pub fn run(args: &CompareRevisionsArgs) -> Result<()> {
+    for pdf in [&args.old, &args.new] {
+        if !pdf.exists() {
+            return Err(Error::not_found(format!("PDF not found: {}", pdf.display())).into());
+        }
+    }
+
+    let changes = if args.text_only {
+        None
+    } else {
+        Some(model_report(args)?)
+    };
+    let text_diff = diff_text_layers(&args.old, &args.new)?;
+    let report = Report {
+        old: &args.old,
+        new: &args.new,
+        changes,
+        text_diff,
+    };
+
+    if args.json {
+        println!("{}", crate::select::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    print_report(&report);
+    Ok(())
+}
+
+fn model_report(args: &CompareRevisionsArgs) -> Result<Value> {
+    let model = resolve_model(&args.model, DEFAULT_MODEL);
+    let hash = |path: &Path| crate::file_cache::hash_file(path);
+    let key = format!(
+        "compare-revisions|{}|{}|{}|{}",
+        hash(&args.old)?,
+        hash(&args.new)?,
+        model,
+        crate::file_cache::compute_hash(PROMPT.as_bytes())
+    );
+
+    if crate::offline::is_enabled() {
+        let cached = crate::offline::cached_result(&key).ok_or_else(|| {
+            Error::not_found(
+                "--offline: no cached comparison of these revisions; run it once online first or use --text-only",
+            )
+        })?;
+        eprintln!("[OFFLINE] Using cached comparison");
+        return Ok(cached);
+    }
+
+    let api_key = resolve_api_key(args.provider, args.api_key.clone())?;
+    let attachment = AttachmentSource::Multiple(vec![
+        page_render::make_attachment(&args.old, args.no_cache, &api_key, &args.base_url)?,
+        page_render::make_attachment(&args.new, args.no_cache, &api_key, &args.base_url)?,
+    ]);
+    let client = build_client(args.provider, api_key, args.base_url.clone())?;
+    let _usage = crate::usage::scope("compare-revisions", Some(&args.new));
+    eprintln!(
+        "[COMPARE] Comparing {} -> {} with {}",
+        args.old.display(),
+        args.new.display(),
+        model
+    );
+    let response = client.generate_json(LlmRequest {
+        model,
+        prompt: PROMPT.to_string(),
+        schema: report_schema(),
+        attachment,
+        temperature: Some(0.0),
+    })?;
+    crate::offline::store_result(&key, &response.json);
+    Ok(response.json)
+}
+
+fn report_schema() -> Value {
+    let text = serde_json::json!({ "type": ["string", "null"] });
+    let page = serde_json::json!({ "type": ["integer", "null"] });
+    let revision = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "part_number": { "type": "string" },
+            "revision": text,
+            "date": text
+        },
+        "required": ["part_number"]
+    });
+    let parameter = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "parameter": { "type": "string" },
+            "old_value": text,
+            "new_value": text,
+            "impact": { "type": "string", "enum": [
+                "tightened", "relaxed", "added", "removed", "conditions_changed", "typical_only"
+            ] },
+            "old_page": page,
+            "new_page": page
+        },
+        "required": ["parameter", "old_value", "new_value", "impact"]
+    });
+    let pin = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "pin": { "type": "string" },
+            "change": { "type": "string", "enum": ["added", "removed", "renamed", "function_changed"] },
+            "old": text,
+            "new": text,
+            "new_page": page
+        },
+        "required": ["pin", "change"]
+    });
+    let change = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "description": { "type": "string" },
+            "old_page": page,
+            "new_page": page
+        },
+        "required": ["description"]
+    });
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "old_revision": revision,
+            "new_revision": revision,
+            "summary": { "type": "string" },
+            "revision_history": { "type": "array", "items": { "type": "string" } },
+            "parameter_changes": { "type": "array", "items": parameter },
+            "pin_changes": { "type": "array", "items": pin },
+            "errata_incorporated": { "type": "array", "items": change },
+            "other_changes": { "type": "array", "items": change }
+        },
+        "required": ["old_revision", "new_revision", "summary", "parameter_changes", "pin_changes"]
+    })
+}
+
+/// Lines of the PDF text layer, tagged with their 1-based page.
+fn text_lines(path: &Path) -> Result<(usize, Vec<DiffLine>)> {
+    let doc = Document::load(path)
+        .map_err(|e| Error::validation(format!("opening {}: {}", path.display(), e)))?;
+    let pages = doc.get_pages();
+    let mut lines = Vec::new();
+    let mut failed = 0;
+    for (&page, &page_id) in &pages {
+        match page_text(&doc, page_id) {
+            Ok(text) if !text.trim().is_empty() => lines.extend(
+                text.lines()
+                    .filter_map(normalize)
+                    .map(|text| DiffLine { page, text }),
+            ),
+            _ => failed += 1,
+        }
+    }
+    if failed > 0 {
+        eprintln!(
+            "[COMPARE] Warning: no text layer on {} of {} pages in {}",
+            failed,
+            pages.len(),
+            path.display()
+        );
+    }
+    Ok((pages.len(), lines))
+}
+
+/// Text shown on a page, one line per text line.
+///
+/// lopdf's `extract_text` only breaks lines at the end of a text object, and
+/// most datasheet generators put a whole page in one; here a line break is
+/// inserted whenever the text position moves vertically.
+pub(crate) fn page_text(doc: &Document, page_id: ObjectId) -> lopdf::Result<String> {
+    let encodings: BTreeMap<Vec<u8>, Encoding> = doc
+        .get_page_fonts(page_id)?
+        .into_iter()
+        .filter_map(|(name, font)| font.get_font_encoding(doc).ok().map(|e| (name, e)))
+        .collect();
+    let content = Content::decode(&doc.get_page_content(page_id)?)?;
+
+    let mut text = String::new();
+    let mut encoding = None;
+    let newline = |text: &mut String| {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+    };
+    for op in &content.operations {
+        match op.operator.as_str() {
+            "Tf" => {
+                encoding = op
+                    .operands
+                    .first()
+                    .and_then(|font| font.as_name().ok())
+                    .and_then(|name| encodings.get(name));
+            }
+            "Td" | "TD" => {
+                let dy = op.operands.get(1).and_then(|y| y.as_float().ok()).unwrap_or(0.0);
+                if dy != 0.0 {
+                    newline(&mut text);
+                }
+            }
+            "T*" | "Tm" | "ET" => newline(&mut text),
+            "Tj" | "TJ" | "'" | "\"" => {
+                if matches!(op.operator.as_str(), "'" | "\"") {
+                    newline(&mut text);
+                }
+                if let (Some(encoding), Some(operand)) = (encoding, op.operands.last()) {
+                    show_text(&mut text, encoding, operand);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(text)
+}
+
+fn show_text(text: &mut String, encoding: &Encoding, operand: &Object) {
+    match operand {
+        Object::String(bytes, _) => {
+            if let Ok(decoded) = Document::decode_text(encoding, bytes) {
+                text.push_str(&decoded);
+            }
+        }
+        Object::Array(items) => {
+            for item in items {
+                match item {
+                    // Large negative kerning is a word gap
+                    Object::Integer(i) if *i < -100 => text.push(' '),
+                    Object::Real(r) if *r < -100.0 => text.push(' '),
+                    _ => show_text(text, encoding, item),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Collapse whitespace and drop lines too short to mean anything (page
+/// numbers, stray symbols).
+fn normalize(line: &str) -> Option<String> {
+    let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().filter(|c| c.is_alphanumeric()).count() < 3 {
+        return None;
+    }
+    Some(line)
+}
+
+fn diff_text_layers(old: &Path, new: &Path) -> Result<TextDiff> {
+    let (old_pages, old_lines) = text_lines(old)?;
+    let (new_pages, new_lines) = text_lines(new)?;
+    let (removed, added) = diff_lines(&old_lines, &new_lines);
+    Ok(TextDiff {
+        old_pages,
+        new_pages,
+        removed,
+        added,
+    })
+}
+
+/// Lines present in only one side, compared as multisets.
+///
+/// Revisions reflow text across pages, so an order-sensitive diff reports
+/// whole pages as changed; matching lines regardless of position keeps the
+/// result down to text that was actually edited.
+fn diff_lines(old: &[DiffLine], new: &[DiffLine]) -> (Vec<DiffLine>, Vec<DiffLine>) {
+    let only_in = |side: &[DiffLine], other: &[DiffLine]| {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for line in other {
+            *counts.entry(line.text.as_str()).or_default() += 1;
+        }
+        side.iter()
+            .filter(|line| match counts.get_mut(line.text.as_str()) {
+                Some(n) if *n > 0 => {
+                    *n -= 1;
+                    false
+                }
+                _ => true,
+            })
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    (only_in(old, new), only_in(new, old))
+}
+
+fn print_report(report: &Report) {
+    if let Some(ref changes) = report.changes {
+        let field = |v: &Value, key: &str| {
+            v.get(key).and_then(|f| f.as_str()).unwrap_or("?").to_string()
+        };
+        let items = |key: &str| changes.get(key).and_then(|i| i.as_array()).cloned().unwrap_or_default();
+        let pages = |v: &Value| {
+            let page = |key: &str| v.get(key).and_then(|p| p.as_i64()).map(|p| p.to_string());
+            match (page("old_page"), page("new_page")) {
+                (Some(old), Some(new)) => format!(" (p. {} -> {})", old, new),
+                (None, Some(new)) => format!(" (new p. {})", new),
+                (Some(old), None) => format!(" (old p. {})", old),
+                (None, None) => String::new(),
+            }
+        };
+        let revision = |key: &str| {
+            let rev = changes.get(key).cloned().unwrap_or_default();
+            let mut label = field(&rev, "revision");
+            if let Some(date) = rev.get("date").and_then(|d| d.as_str()) {
+                label.push_str(&format!(", {}", date));
+            }
+            label
+        };
+
+        println!("{} -> {}", revision("old_revision"), revision("new_revision"));
+        println!();
+        println!("{}", field(changes, "summary"));
+
+        let parameters = items("parameter_changes");
+        if !parameters.is_empty() {
+            println!("\nParameter changes:");
+            for p in &parameters {
+                let value = |key: &str| p.get(key).and_then(|v| v.as_str()).unwrap_or("-").to_string();
+                println!(
+                    "  [{}] {}: {} -> {}{}",
+                    field(p, "impact"),
+                    field(p, "parameter"),
+                    value("old_value"),
+                    value("new_value"),
+                    pages(p)
+                );
+            }
+        }
+        let pins = items("pin_changes");
+        if !pins.is_empty() {
+            println!("\nPin changes:");
+            for p in &pins {
+                let detail = p
+                    .get("new")
+                    .or_else(|| p.get("old"))
+                    .and_then(|d| d.as_str())
+                    .unwrap_or_default();
+                println!("  [{}] {}: {}{}", field(p, "change"), field(p, "pin"), detail, pages(p));
+            }
+        }
+        let lists = [
+            ("errata_incorporated", "Errata incorporated"),
+            ("other_changes", "Other changes"),
+        ];
+        for (key, title) in lists {
+            let list = items(key);
+            if !list.is_empty() {
+                println!("\n{}:", title);
+                for c in &list {
+                    println!("  - {}{}", field(c, "description"), pages(c));
+                }
+            }
+        }
+        println!();
+    }
+
+    let diff = &report.text_diff;
+    println!(
+        "Text diff ({} pages -> {} pages): {} line(s) removed, {} added",
+        diff.old_pages,
+        diff.new_pages,
+        diff.removed.len(),
+        diff.added.len()
+    );
+    let lines = diff
+        .removed
+        .iter()
+        .map(|l| ('-', l))
+        .chain(diff.added.iter().map(|l| ('+', l)));
+    for (sign, line) in lines.clone().take(SHOWN_DIFF_LINES) {
+        println!("  {} p.{}: {}", sign, line.page, line.text);
+    }
+    let hidden = lines.count().saturating_sub(SHOWN_DIFF_LINES);
+    if hidden > 0 {
+        println!("  ... and {} more (use --json for all)", hidden);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(items: &[(u32, &str)]) -> Vec<DiffLine> {
+        items
+            .iter()
+            .map(|&(page, text)| DiffLine { page, text: text.to_string() })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_lines_ignores_reflow() {
+        let old = lines(&[(1, "Quiescent current 25 uA"), (2, "Output accuracy ±3%"), (2, "EN pin")]);
+        let new = lines(&[(1, "EN pin"), (2, "Quiescent current 25 uA"), (3, "Output accuracy ±2%")]);
+        let (removed, added) = diff_lines(&old, &new);
+        assert_eq!(removed, lines(&[(2, "Output accuracy ±3%")]));
+        assert_eq!(added, lines(&[(3, "Output accuracy ±2%")]));
+        assert_eq!(normalize("  12 "), None);
+        assert_eq!(normalize("VIN   range"), Some("VIN range".to_string()));
+    }
+}
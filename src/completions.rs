@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Shell completion scripts and man pages generated from the CLI definition.
+
+use anyhow::{Context, Result};
+use clap::{Args, CommandFactory};
+use clap_complete::Shell;
+use std::fs;
+use std::path::PathBuf;
+
+/// The installed binary name (the package is `datasheet-cli`).
+const BIN_NAME: &str = env!("CARGO_BIN_NAME");
+
+#[derive(Args, Debug)]
+pub struct CompletionsArgs {
+    /// Shell to generate completions for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+#[derive(Args, Debug)]
+pub struct ManpagesArgs {
+    /// Directory to write the man pages to (one per command and subcommand)
+    #[arg(long, default_value = "man")]
+    pub dir: PathBuf,
+}
+
+/// Print the completion script for `args.shell` to stdout.
+pub fn completions(args: &CompletionsArgs) -> Result<()> {
+    let mut cmd = crate::Cli::command();
+    clap_complete::generate(args.shell, &mut cmd, BIN_NAME, &mut std::io::stdout());
+    Ok(())
+}
+
+/// Write `datasheet.1` plus `datasheet-<subcommand>.1` pages for every subcommand.
+pub fn manpages(args: &ManpagesArgs) -> Result<()> {
+    fs::create_dir_all(&args.dir).with_context(|| format!("creating {}", args.dir.display()))?;
+    let count = write_pages(&args.dir, BIN_NAME, crate::Cli::command())?;
+    eprintln!("Wrote {} man pages to {}", count, args.dir.display());
+    Ok(())
+}
+
+/// Pages are named after the full command path, e.g. `datasheet-mouser-search.1`.
+fn write_pages(dir: &std::path::Path, name: &str, cmd: clap::Command) -> Result<usize> {
+    let cmd = cmd
+        .disable_help_subcommand(true)
+        .version(env!("CARGO_PKG_VERSION"))
+        .display_name(name.to_string())
+        .bin_name(name.replace('-', " "));
+    let mut count = 0;
+    for sub in cmd.get_subcommands().filter(|s| !s.is_hide_set()) {
+        count += write_pages(dir, &format!("{}-{}", name, sub.get_name()), sub.clone())?;
+    }
+    let path = dir.join(format!("{}.1", name));
+    let mut page = Vec::new();
+    clap_mangen::Man::new(cmd).render(&mut page).context("rendering man page")?;
+    crate::paths::write_file(&path, page).with_context(|| format!("writing {}", path.display()))?;
+    Ok(count + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manpages_cover_subcommands() {
+        let dir = std::env::temp_dir().join(format!("datasheet-man-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let count = write_pages(&dir, BIN_NAME, crate::Cli::command()).unwrap();
+        assert!(count > 20);
+        assert!(dir.join("datasheet.1").exists());
+        assert!(dir.join("datasheet-mouser-search.1").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
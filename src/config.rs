@@ -0,0 +1,374 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Global configuration file with named profiles.
+//!
+//! `~/.config/datasheet-cli/config.toml` (or `$DATASHEET_CONFIG`):
+//!
+//! ```toml
+//! default_profile = "work"        # used when --profile is not given
+//!
+//! # Top-level settings apply to every profile
+//! model = "gemini-2.5-flash"
+//! formatted = true
+//!
+//! [profiles.work]
+//! currency = "EUR"
+//! cache_dir = "~/work/datasheet-cache"
+//! download_dir = "~/work/datasheets"
+//! download_policy = "~/work/download-policy.toml"
+//! cache_max_size = "2G"
+//! notify = ["https://hooks.slack.com/services/T000/B000/XXXX"]
+//! max_spend_per_run = 5.0
+//!
+//! # Credentials are references: the variable to read each secret from
+//! [profiles.work.credentials]
+//! MOUSER_API_KEY = "WORK_MOUSER_API_KEY"
+//! GOOGLE_API_KEY = "WORK_GEMINI_KEY"
+//! ```
+//!
+//! The selected profile is applied before argument parsing by exporting each
+//! setting as the environment variable its flag already reads (`model` becomes
+//! `DATASHEET_MODEL`, and so on). Variables that are already set win, and
+//! command-line flags win over both.
+//!
+//! String settings and credential references may use `${VAR}` and
+//! `${VAR:-default}`, expanded from the environment after the env file named
+//! by `env_file` or `--env-file` (see [`crate::dotenv`]) has been loaded.
+
+use anyhow::{Context, Result, anyhow};
+use clap::Subcommand;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The env file `apply` loaded, for `config show`
+static ENV_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigSubcommand {
+    /// Print the config file location
+    Path,
+    /// Show the settings of the selected profile and list all profiles
+    Show,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Config {
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+    #[serde(flatten)]
+    base: Profile,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+struct Profile {
+    model: Option<String>,
+    provider: Option<String>,
+    currency: Option<String>,
+    offline: Option<bool>,
+    cache_dir: Option<String>,
+    cache_max_size: Option<String>,
+    download_dir: Option<String>,
+    backup: Option<bool>,
+    record_history: Option<bool>,
+    download_policy: Option<String>,
+    formatted: Option<bool>,
+    notify: Option<Vec<String>>,
+    max_requests_per_day: Option<u64>,
+    max_tokens_per_run: Option<u64>,
+    max_spend_per_run: Option<f64>,
+    upload_concurrency: Option<usize>,
+    schema_mode: Option<String>,
+    max_response_size: Option<String>,
+    prompt_source: Option<String>,
+    env_file: Option<String>,
+    #[serde(default)]
+    credentials: BTreeMap<String, String>,
+}
+
+impl Profile {
+    /// `other` on top of `self`.
+    fn merged(mut self, other: &Profile) -> Profile {
+        macro_rules! take {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() { self.$field = other.$field.clone(); })*
+            };
+        }
+        take!(
+            model, provider, currency, offline, cache_dir, cache_max_size, download_dir, backup, record_history,
+            download_policy, formatted, notify, max_requests_per_day, max_tokens_per_run, max_spend_per_run,
+            upload_concurrency, schema_mode, max_response_size, prompt_source, env_file
+        );
+        self.credentials.extend(other.credentials.clone());
+        self
+    }
+
+    /// `self` with `${VAR}` expanded in string settings and credential references.
+    fn expanded(mut self) -> Result<Profile> {
+        let lookup = |name: &str| std::env::var(name).ok();
+        macro_rules! expand {
+            ($($field:ident),*) => {
+                $(if let Some(value) = &self.$field {
+                    let value = crate::dotenv::expand(value, &lookup)
+                        .with_context(|| format!("expanding {}", stringify!($field)))?;
+                    self.$field = Some(value);
+                })*
+            };
+        }
+        expand!(
+            model, provider, currency, cache_dir, cache_max_size, download_dir, download_policy, schema_mode,
+            max_response_size, prompt_source
+        );
+        for target in self.notify.iter_mut().flatten() {
+            *target = crate::dotenv::expand(target, &lookup).context("expanding notify")?;
+        }
+        for (name, source) in self.credentials.iter_mut() {
+            *source = crate::dotenv::expand(source, &lookup).with_context(|| format!("expanding credential {}", name))?;
+        }
+        Ok(self)
+    }
+
+    /// Environment variables this profile provides.
+    fn env_vars(&self) -> Vec<(String, String)> {
+        let settings = [
+            ("DATASHEET_MODEL", self.model.clone()),
+            ("DATASHEET_PROVIDER", self.provider.clone()),
+            ("DATASHEET_CURRENCY", self.currency.clone()),
+            ("DATASHEET_OFFLINE", self.offline.map(|b| b.to_string())),
+            ("DATASHEET_CACHE_DIR", self.cache_dir.as_deref().map(expand_home)),
+            ("DATASHEET_CACHE_MAX_SIZE", self.cache_max_size.clone()),
+            ("DATASHEET_DOWNLOAD_DIR", self.download_dir.as_deref().map(expand_home)),
+            ("DATASHEET_BACKUP", self.backup.map(|b| b.to_string())),
+            ("DATASHEET_RECORD_HISTORY", self.record_history.map(|b| b.to_string())),
+            ("DATASHEET_DOWNLOAD_POLICY", self.download_policy.as_deref().map(expand_home)),
+            ("DATASHEET_FORMATTED", self.formatted.map(|b| b.to_string())),
+            ("DATASHEET_NOTIFY", self.notify.as_ref().map(|targets| targets.join(","))),
+            ("DATASHEET_MAX_REQUESTS_PER_DAY", self.max_requests_per_day.map(|n| n.to_string())),
+            ("DATASHEET_MAX_TOKENS_PER_RUN", self.max_tokens_per_run.map(|n| n.to_string())),
+            ("DATASHEET_MAX_SPEND_PER_RUN", self.max_spend_per_run.map(|usd| usd.to_string())),
+            ("DATASHEET_UPLOAD_CONCURRENCY", self.upload_concurrency.map(|n| n.to_string())),
+            ("DATASHEET_SCHEMA_MODE", self.schema_mode.clone()),
+            ("DATASHEET_MAX_RESPONSE_SIZE", self.max_response_size.clone()),
+            ("DATASHEET_PROMPT_SOURCE", self.prompt_source.as_deref().map(expand_home)),
+        ];
+        let mut vars: Vec<(String, String)> = settings
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value?)))
+            .collect();
+        for (name, source) in &self.credentials {
+            if let Ok(secret) = std::env::var(source) {
+                vars.push((name.clone(), secret));
+            }
+        }
+        vars
+    }
+}
+
+/// Location of the config file (`$DATASHEET_CONFIG` overrides the default).
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("DATASHEET_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    dirs::config_dir().map(|dir| dir.join("datasheet-cli").join("config.toml"))
+}
+
+fn load() -> Result<Option<Config>> {
+    let Some(path) = config_path() else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    let config = toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+    Ok(Some(config))
+}
+
+/// The profile named on the command line (`--profile NAME` / `--profile=NAME`)
+/// or in `DATASHEET_PROFILE`. Clap also declares the flag, but it has to be
+/// known before parsing so the profile can supply defaults.
+fn requested_profile(args: &[OsString]) -> Option<String> {
+    flag_value(args, "--profile").or_else(|| std::env::var("DATASHEET_PROFILE").ok().filter(|p| !p.is_empty()))
+}
+
+/// The env file named with `--env-file` or `DATASHEET_ENV_FILE`, which is
+/// loaded before the profile is chosen.
+fn requested_env_file(args: &[OsString]) -> Option<String> {
+    flag_value(args, "--env-file").or_else(|| std::env::var("DATASHEET_ENV_FILE").ok().filter(|p| !p.is_empty()))
+}
+
+/// Value of `--flag VALUE` / `--flag=VALUE` before a `--`.
+fn flag_value(args: &[OsString], flag: &str) -> Option<String> {
+    let mut iter = args.iter().skip(1).map(|a| a.to_string_lossy());
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == flag {
+            return iter.next().map(|v| v.into_owned());
+        }
+        if let Some(value) = arg.strip_prefix(flag).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Load an env file. One given on the command line must exist; one named in
+/// the config file is skipped outside projects that have it.
+fn load_env_file(name: &str, required: bool) -> Result<()> {
+    let name = expand_home(name);
+    match crate::dotenv::find(Path::new(&name)) {
+        Some(path) => {
+            crate::dotenv::load(&path)?;
+            let _ = ENV_FILE.set(path);
+            Ok(())
+        }
+        None if required => Err(anyhow!("env file {} not found", name)),
+        None => Ok(()),
+    }
+}
+
+/// Resolve the active profile: the top-level settings overlaid with the
+/// requested (or default) profile. Returns its name, if any, and settings.
+fn resolve(config: &Config, requested: Option<String>) -> Result<(Option<String>, Profile)> {
+    let name = requested.or_else(|| config.default_profile.clone());
+    let Some(name) = name else {
+        return Ok((None, config.base.clone()));
+    };
+    let profile = config.profiles.get(&name).ok_or_else(|| {
+        let known: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+        anyhow!(
+            "Unknown profile '{}' (configured: {})",
+            name,
+            if known.is_empty() { "none".to_string() } else { known.join(", ") }
+        )
+    })?;
+    Ok((Some(name), config.base.clone().merged(profile)))
+}
+
+/// Load the config file and export the active profile's settings.
+///
+/// Must run at the very start of `main`, before any threads are spawned.
+pub fn apply(args: &[OsString]) -> Result<()> {
+    let env_file = requested_env_file(args);
+    if let Some(ref name) = env_file {
+        load_env_file(name, true)?;
+    }
+    let requested = requested_profile(args);
+    let Some(config) = load()? else {
+        if let Some(name) = requested {
+            return Err(anyhow!(
+                "--profile {} given, but there is no config file at {}",
+                name,
+                config_path().map(|p| p.display().to_string()).unwrap_or_default()
+            ));
+        }
+        return Ok(());
+    };
+    let (_, profile) = resolve(&config, requested)?;
+    if let (None, Some(name)) = (&env_file, &profile.env_file) {
+        load_env_file(name, false)?;
+    }
+    let profile = profile.expanded()?;
+    for (name, value) in profile.env_vars() {
+        if std::env::var_os(&name).is_none() {
+            // SAFETY: called before any other thread exists (see above).
+            unsafe { std::env::set_var(&name, value) };
+        }
+    }
+    Ok(())
+}
+
+pub fn execute(cmd: ConfigSubcommand) -> Result<(), String> {
+    let path = config_path().ok_or("Could not determine config directory")?;
+    match cmd {
+        ConfigSubcommand::Path => {
+            println!("{}", path.display());
+        }
+        ConfigSubcommand::Show => {
+            let config = load().map_err(|e| format!("{e:#}"))?;
+            let Some(config) = config else {
+                println!("No config file at {}", path.display());
+                return Ok(());
+            };
+            let args: Vec<OsString> = std::env::args_os().collect();
+            let (name, profile) = resolve(&config, requested_profile(&args)).map_err(|e| e.to_string())?;
+            let profile = profile.expanded().map_err(|e| format!("{e:#}"))?;
+            println!("Config:   {}", path.display());
+            println!("Profile:  {}", name.as_deref().unwrap_or("(none)"));
+            println!("Env file: {}", ENV_FILE.get().map(|p| p.display().to_string()).unwrap_or("(none)".into()));
+            let profiles: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+            println!("Profiles: {}", if profiles.is_empty() { "(none)".to_string() } else { profiles.join(", ") });
+            println!();
+            for (name, value) in profile.env_vars() {
+                if !profile.credentials.contains_key(&name) {
+                    println!("  {:<26} {}", name, value);
+                }
+            }
+            for (name, source) in &profile.credentials {
+                let state = if std::env::var_os(source).is_some() { "set" } else { "NOT SET" };
+                println!("  {:<26} from ${} ({})", name, source, state);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().into_owned(),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_overrides_base_settings() {
+        let config: Config = toml::from_str(
+            r#"
+            default_profile = "home"
+            model = "gemini-2.5-flash"
+            currency = "USD"
+
+            [profiles.home]
+            currency = "EUR"
+
+            [profiles.work]
+            offline = true
+            [profiles.work.credentials]
+            MOUSER_API_KEY = "WORK_MOUSER"
+            "#,
+        )
+        .unwrap();
+
+        let (name, home) = resolve(&config, None).unwrap();
+        assert_eq!(name.as_deref(), Some("home"));
+        assert_eq!(home.currency.as_deref(), Some("EUR"));
+        assert_eq!(home.model.as_deref(), Some("gemini-2.5-flash"));
+
+        let (_, work) = resolve(&config, Some("work".into())).unwrap();
+        assert_eq!(work.currency.as_deref(), Some("USD"));
+        assert_eq!(work.offline, Some(true));
+        assert_eq!(work.credentials["MOUSER_API_KEY"], "WORK_MOUSER");
+
+        assert!(resolve(&config, Some("nope".into())).is_err());
+    }
+
+    #[test]
+    fn test_requested_profile_from_args() {
+        let args = |v: &[&str]| v.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(requested_profile(&args(&["ds", "--profile", "work", "part", "X"])).as_deref(), Some("work"));
+        assert_eq!(requested_profile(&args(&["ds", "part", "--profile=lab"])).as_deref(), Some("lab"));
+        assert_eq!(flag_value(&args(&["ds", "--env-file=hw/.env", "bom"]), "--env-file").as_deref(), Some("hw/.env"));
+        assert_eq!(flag_value(&args(&["ds", "--", "--env-file", "x"]), "--env-file"), None);
+    }
+}
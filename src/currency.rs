@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Currency parsing and conversion for price output.
+//!
+//! Distributors quote in different currencies (DigiKey and JLCPCB in USD, Mouser
+//! in the account's local currency with localized number formats). When the
+//! global `--currency` flag is set, every price is converted to that currency
+//! using daily reference rates, cached for 12 hours. `DATASHEET_FX_RATES`
+//! (e.g. `EUR=0.92,GBP=0.79`, units per USD) overrides the provider for offline use.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const RATES_URL: &str = "https://api.frankfurter.dev/v1/latest?base=USD";
+const ENV_VAR_RATES: &str = "DATASHEET_FX_RATES";
+const RATES_TTL_SECS: u64 = 12 * 60 * 60;
+
+static TARGET: OnceLock<String> = OnceLock::new();
+static RATES: OnceLock<Result<HashMap<String, f64>, String>> = OnceLock::new();
+
+/// Set the currency all prices are converted to (called once from `main`).
+pub fn set_target(code: &str) {
+    let _ = TARGET.set(code.trim().to_uppercase());
+}
+
+fn target() -> Option<&'static str> {
+    TARGET.get().map(String::as_str)
+}
+
+/// Convert `amount` from `from` to the target currency.
+///
+/// Returns the amount unchanged (in `from`) when no target is set or the rate
+/// is unavailable; a warning is printed once in the latter case.
+pub fn convert(amount: f64, from: &str) -> (f64, String) {
+    let from = from.trim().to_uppercase();
+    let Some(to) = target() else {
+        return (amount, from);
+    };
+    if from == to {
+        return (amount, from);
+    }
+    match rates().and_then(|rates| cross_rate(rates, &from, to)) {
+        Ok(rate) => (amount * rate, to.to_string()),
+        Err(e) => {
+            static WARNED: OnceLock<()> = OnceLock::new();
+            WARNED.get_or_init(|| eprintln!("[CURRENCY] {}; showing original prices", e));
+            (amount, from)
+        }
+    }
+}
+
+/// Format a price, converting it to the target currency first.
+pub fn format_price(amount: f64, currency: &str, decimals: usize) -> String {
+    let (amount, currency) = convert(amount, currency);
+    match currency.as_str() {
+        "USD" => format!("${:.*}", decimals, amount),
+        "EUR" => format!("€{:.*}", decimals, amount),
+        "GBP" => format!("£{:.*}", decimals, amount),
+        _ => format!("{:.*} {}", decimals, amount, currency),
+    }
+}
+
+/// Parse a localized price string ("$10.13", "1,234.56", "0,123 €", "1.234,56 €").
+pub fn parse_price(s: &str) -> Option<f64> {
+    let cleaned: String = s.chars().filter(|c| c.is_ascii_digit() || *c == '.' || *c == ',').collect();
+    if cleaned.is_empty() {
+        return None;
+    }
+    let normalized = match (cleaned.rfind('.'), cleaned.rfind(',')) {
+        // Both present: whichever comes last is the decimal separator
+        (Some(dot), Some(comma)) if comma > dot => cleaned.replace('.', "").replace(',', "."),
+        (Some(_), Some(_)) => cleaned.replace(',', ""),
+        // Only commas: a single comma followed by anything but exactly three digits,
+        // or after a leading zero, is a decimal comma
+        (None, Some(comma)) => {
+            let after = cleaned.len() - comma - 1;
+            if cleaned.matches(',').count() == 1 && (after != 3 || cleaned.starts_with("0,")) {
+                cleaned.replace(',', ".")
+            } else {
+                cleaned.replace(',', "")
+            }
+        }
+        _ => cleaned,
+    };
+    normalized.parse().ok()
+}
+
+/// Rate to multiply an amount in `from` by to get `to`, via USD.
+fn cross_rate(rates: &HashMap<String, f64>, from: &str, to: &str) -> Result<f64, String> {
+    let per_usd = |code: &str| -> Result<f64, String> {
+        if code == "USD" {
+            return Ok(1.0);
+        }
+        rates
+            .get(code)
+            .copied()
+            .filter(|r| *r > 0.0)
+            .ok_or_else(|| format!("no exchange rate for {}", code))
+    };
+    Ok(per_usd(to)? / per_usd(from)?)
+}
+
+fn rates() -> Result<&'static HashMap<String, f64>, String> {
+    RATES.get_or_init(load_rates).as_ref().map_err(Clone::clone)
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedRates {
+    fetched_at: u64,
+    rates: HashMap<String, f64>,
+}
+
+#[derive(Deserialize)]
+struct RatesResponse {
+    rates: HashMap<String, f64>,
+}
+
+fn load_rates() -> Result<HashMap<String, f64>, String> {
+    if let Ok(spec) = std::env::var(ENV_VAR_RATES) {
+        return parse_rate_overrides(&spec);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let cache = cache_path();
+    if let Some(cached) = cache
+        .as_ref()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str::<CachedRates>(&s).ok())
+    {
+        if now.saturating_sub(cached.fetched_at) < RATES_TTL_SECS {
+            return Ok(cached.rates);
+        }
+    }
+
+    crate::offline::ensure_online("Fetching exchange rates")?;
+    let response: RatesResponse = ureq::get(RATES_URL)
+        .call()
+        .map_err(|e| format!("failed to fetch exchange rates: {}", e))?
+        .into_json()
+        .map_err(|e| format!("failed to parse exchange rates: {}", e))?;
+
+    if let Some(path) = cache {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let cached = CachedRates {
+            fetched_at: now,
+            rates: response.rates.clone(),
+        };
+        if let Ok(json) = serde_json::to_string(&cached) {
+            let _ = fs::write(path, json);
+        }
+    }
+    Ok(response.rates)
+}
+
+fn parse_rate_overrides(spec: &str) -> Result<HashMap<String, f64>, String> {
+    spec.split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| {
+            let (code, rate) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("invalid {} entry '{}' (expected CODE=RATE)", ENV_VAR_RATES, pair))?;
+            let rate: f64 = rate
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid rate in {} entry '{}'", ENV_VAR_RATES, pair))?;
+            Ok((code.trim().to_uppercase(), rate))
+        })
+        .collect()
+}
+
+fn cache_path() -> Option<PathBuf> {
+    Some(crate::file_cache::cache_root().join("fx_rates.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_price() {
+        assert_eq!(parse_price("$10.13"), Some(10.13));
+        assert_eq!(parse_price("1,234.56"), Some(1234.56));
+        assert_eq!(parse_price("0,123 €"), Some(0.123));
+        assert_eq!(parse_price("1.234,56 €"), Some(1234.56));
+        assert_eq!(parse_price("12,5 €"), Some(12.5));
+        assert_eq!(parse_price("1,234"), Some(1234.0));
+        assert_eq!(parse_price("N/A"), None);
+    }
+
+    #[test]
+    fn test_cross_rate() {
+        let rates = parse_rate_overrides("EUR=0.5, GBP=0.25").unwrap();
+        assert_eq!(cross_rate(&rates, "USD", "EUR"), Ok(0.5));
+        assert_eq!(cross_rate(&rates, "EUR", "GBP"), Ok(0.5));
+        assert!(cross_rate(&rates, "USD", "JPY").is_err());
+    }
+}
@@ -0,0 +1,1357 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Local parts database backed by SQLite.
+//!
+//! Stores one row per part plus a flat list of attributes gathered from
+//! extraction results, distributor lookups, and the datasheet library. Every
+//! attribute keeps its raw text, a normalized form for substring matching, and
+//! (when the value starts with a number) an SI-scaled numeric value, so queries
+//! like `package:QFN48 AND vcc<=3.6` can be answered without re-reading JSON.
+//!
+//! `datasheet embed` adds embedding vectors for datasheet text and extraction
+//! sections; `db semantic-search` ranks parts by cosine similarity against them.
+//!
+//! The database lives at `<data dir>/datasheet-cli/parts.db` unless `--db` or
+//! `DATASHEET_DB` is set.
+
+use clap::{Subcommand, ValueEnum};
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::bom::Source;
+use crate::digikey;
+use crate::mouser;
+use crate::parameters;
+use crate::library::Library;
+
+const ENV_VAR_DB: &str = "DATASHEET_DB";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS parts (
+    id INTEGER PRIMARY KEY,
+    mpn TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    manufacturer TEXT,
+    description TEXT,
+    datasheet TEXT,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS attributes (
+    part_id INTEGER NOT NULL REFERENCES parts(id) ON DELETE CASCADE,
+    source TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    norm TEXT NOT NULL,
+    num REAL
+);
+CREATE INDEX IF NOT EXISTS attributes_name ON attributes(name, num);
+CREATE INDEX IF NOT EXISTS attributes_part ON attributes(part_id, source);
+CREATE VIRTUAL TABLE IF NOT EXISTS parts_fts USING fts5(mpn, manufacturer, description, body);
+CREATE TABLE IF NOT EXISTS stock_history (
+    mpn TEXT NOT NULL COLLATE NOCASE,
+    distributor TEXT NOT NULL,
+    stock INTEGER,
+    unit_price REAL,
+    currency TEXT,
+    recorded_at INTEGER NOT NULL,
+    quantity INTEGER
+);
+CREATE INDEX IF NOT EXISTS stock_history_part ON stock_history(mpn, distributor, recorded_at);
+CREATE TABLE IF NOT EXISTS embeddings (
+    part_id INTEGER NOT NULL REFERENCES parts(id) ON DELETE CASCADE,
+    source TEXT NOT NULL,
+    label TEXT NOT NULL,
+    text TEXT NOT NULL,
+    model TEXT NOT NULL,
+    vector BLOB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS embeddings_part ON embeddings(part_id, source);
+";
+
+/// Extraction sections whose rows are keyed by a parameter symbol, and the
+/// attribute prefix they are stored under. Recommended operating conditions
+/// come first so a bare `vcc` refers to the operating range, not the abs-max.
+const SYMBOL_SECTIONS: &[(&str, &str)] = &[
+    ("recommended_operating_conditions", ""),
+    ("electrical_specifications", ""),
+    ("absolute_maximum_ratings", "absmax."),
+    ("power_rails", "rail."),
+];
+
+/// Field-name tokens mapped to the `.min` / `.typ` / `.max` suffixes.
+const LIMIT_FIELDS: &[(&str, &str)] = &[
+    ("min", "min"),
+    ("minimum", "min"),
+    ("typ", "typ"),
+    ("typical", "typ"),
+    ("nom", "typ"),
+    ("nominal", "typ"),
+    ("max", "max"),
+    ("maximum", "max"),
+];
+
+/// Database subcommands.
+#[derive(Subcommand, Debug)]
+pub enum DbSubcommand {
+    /// Index an extraction result (JSON from `datasheet extract`) for a part
+    Index {
+        /// Extraction JSON file
+        json: PathBuf,
+
+        /// Manufacturer part number (defaults to part_number in the JSON)
+        #[arg(long)]
+        mpn: Option<String>,
+
+        /// Manufacturer name
+        #[arg(long)]
+        manufacturer: Option<String>,
+
+        /// Extraction task the JSON came from (used to replace earlier results)
+        #[arg(long, default_value = "extract")]
+        task: String,
+
+        /// Database path (defaults to DATASHEET_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+
+    /// Fetch distributor metadata (stock, lifecycle, parameters) and index it
+    Fetch {
+        /// Manufacturer part numbers (`-` reads newline-separated MPNs from stdin)
+        #[arg(required = true)]
+        mpns: Vec<String>,
+
+        /// Distributors to query (comma-separated)
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "digikey,mouser,jlcpcb")]
+        source: Vec<Source>,
+
+        /// Database path (defaults to DATASHEET_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+
+    /// Index every datasheet in the local library
+    ImportLibrary {
+        /// Library root (defaults to DATASHEET_LIBRARY or the platform data directory)
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// Database path (defaults to DATASHEET_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+
+    /// Query parts, e.g. "package:QFN48 AND vcc<=3.6" or "ldo NOT manufacturer:ti"
+    Query {
+        /// Query expression
+        query: String,
+
+        /// Maximum number of results
+        #[arg(long, default_value = "50")]
+        limit: usize,
+
+        /// Database path (defaults to DATASHEET_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Rank parts by meaning, e.g. "low-Iq buck for 2-cell li-ion" (needs `datasheet embed`)
+    SemanticSearch {
+        /// Free-text description of the part you are looking for
+        query: String,
+
+        /// Maximum number of results
+        #[arg(long, default_value = "10")]
+        limit: usize,
+
+        /// Embedding model (must match the one used by `datasheet embed`)
+        #[arg(long, default_value = crate::llm::DEFAULT_EMBEDDING_MODEL)]
+        model: String,
+
+        /// API key (overrides env vars)
+        #[arg(long)]
+        api_key: Option<String>,
+
+        /// Override base URL for the API
+        #[arg(long)]
+        base_url: Option<String>,
+
+        /// Database path (defaults to DATASHEET_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show everything indexed for a part
+    Show {
+        /// Manufacturer part number
+        mpn: String,
+
+        /// Database path (defaults to DATASHEET_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// A part row with its attributes.
+#[derive(Serialize, Debug, Clone)]
+pub struct PartRecord {
+    pub mpn: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manufacturer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub datasheet: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Attribute>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub source: String,
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num: Option<f64>,
+}
+
+impl Attribute {
+    pub(crate) fn new(source: &str, name: &str, value: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            name: attribute_name(name),
+            value: value.trim().to_string(),
+            num: parse_number(value),
+        }
+    }
+}
+
+/// One stock/price observation for a part at a distributor.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StockSample {
+    pub stock: Option<i64>,
+    pub unit_price: Option<f64>,
+    pub currency: Option<String>,
+    /// Unix timestamp of the observation
+    pub recorded_at: i64,
+    /// Order quantity `unit_price` is quoted at (`None` in databases from before it was recorded)
+    pub quantity: Option<i32>,
+}
+
+/// One embedded chunk of text belonging to a part.
+#[derive(Debug, Clone)]
+pub struct Embedding {
+    /// Where in the source the text came from, e.g. "page 3" or "electrical_specifications"
+    pub label: String,
+    pub text: String,
+    pub vector: Vec<f32>,
+}
+
+/// A part ranked by `semantic_search`, with its best-matching chunk.
+#[derive(Serialize, Debug, Clone)]
+pub struct SemanticMatch {
+    pub mpn: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manufacturer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Cosine similarity of the best chunk
+    pub score: f32,
+    pub source: String,
+    pub label: String,
+    pub text: String,
+}
+
+/// Handle to the parts database.
+pub struct PartsDb {
+    conn: Connection,
+}
+
+impl PartsDb {
+    /// Open (and create if needed) the database at `path`, `DATASHEET_DB`, or the default location.
+    pub fn open(path: Option<PathBuf>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path,
+            None => match std::env::var(ENV_VAR_DB) {
+                Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+                _ => dirs::data_dir()
+                    .ok_or_else(|| "Could not determine data directory".to_string())?
+                    .join("datasheet-cli")
+                    .join("parts.db"),
+            },
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let conn = Connection::open(&path)
+            .map_err(|e| format!("Failed to open database {}: {}", path.display(), e))?;
+        Self::init(conn)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn open_in_memory() -> Result<Self, String> {
+        Self::init(Connection::open_in_memory().map_err(db_err)?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, String> {
+        conn.execute_batch("PRAGMA foreign_keys = ON;").map_err(db_err)?;
+        conn.execute_batch(SCHEMA).map_err(db_err)?;
+        // Databases created before stock_history.quantity existed
+        let has_quantity: bool = conn
+            .query_row("SELECT COUNT(*) > 0 FROM pragma_table_info('stock_history') WHERE name = 'quantity'", [], |r| r.get(0))
+            .map_err(db_err)?;
+        if !has_quantity {
+            conn.execute_batch("ALTER TABLE stock_history ADD COLUMN quantity INTEGER;").map_err(db_err)?;
+        }
+        Ok(Self { conn })
+    }
+
+    /// Insert or update a part row, returning its id. `None` fields keep their stored value.
+    pub fn upsert_part(
+        &self,
+        mpn: &str,
+        manufacturer: Option<&str>,
+        description: Option<&str>,
+        datasheet: Option<&str>,
+    ) -> Result<i64, String> {
+        self.conn
+            .execute(
+                "INSERT INTO parts (mpn, manufacturer, description, datasheet, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(mpn) DO UPDATE SET
+                    manufacturer = COALESCE(excluded.manufacturer, manufacturer),
+                    description = COALESCE(excluded.description, description),
+                    datasheet = COALESCE(excluded.datasheet, datasheet),
+                    updated_at = excluded.updated_at",
+                params![mpn, manufacturer, description, datasheet, now()],
+            )
+            .map_err(db_err)?;
+        let id = self
+            .conn
+            .query_row("SELECT id FROM parts WHERE mpn = ?1", params![mpn], |r| r.get(0))
+            .map_err(db_err)?;
+        self.refresh_fts(id)?;
+        Ok(id)
+    }
+
+    /// Replace all attributes a source previously stored for a part.
+    pub fn set_attributes(&self, part_id: i64, source: &str, attributes: &[Attribute]) -> Result<(), String> {
+        let tx = self.conn.unchecked_transaction().map_err(db_err)?;
+        tx.execute(
+            "DELETE FROM attributes WHERE part_id = ?1 AND source = ?2",
+            params![part_id, source],
+        )
+        .map_err(db_err)?;
+        {
+            let mut stmt = tx
+                .prepare("INSERT INTO attributes (part_id, source, name, value, norm, num) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
+                .map_err(db_err)?;
+            for attr in attributes {
+                stmt.execute(params![part_id, source, attr.name, attr.value, normalize(&attr.value), attr.num])
+                    .map_err(db_err)?;
+            }
+        }
+        tx.commit().map_err(db_err)?;
+        self.refresh_fts(part_id)
+    }
+
+    fn refresh_fts(&self, part_id: i64) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM parts_fts WHERE rowid = ?1", params![part_id])
+            .map_err(db_err)?;
+        self.conn
+            .execute(
+                "INSERT INTO parts_fts (rowid, mpn, manufacturer, description, body)
+                 SELECT p.id, p.mpn, COALESCE(p.manufacturer, ''), COALESCE(p.description, ''),
+                        COALESCE((SELECT group_concat(a.value, ' ') FROM attributes a WHERE a.part_id = p.id), '')
+                 FROM parts p WHERE p.id = ?1",
+                params![part_id],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    /// Look up a part by MPN (case-insensitive), with all attributes.
+    pub fn get(&self, mpn: &str) -> Result<Option<PartRecord>, String> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT id, mpn, manufacturer, description, datasheet FROM parts WHERE mpn = ?1",
+                params![mpn],
+                |r| {
+                    Ok((
+                        r.get::<_, i64>(0)?,
+                        PartRecord {
+                            mpn: r.get(1)?,
+                            manufacturer: r.get(2)?,
+                            description: r.get(3)?,
+                            datasheet: r.get(4)?,
+                            attributes: Vec::new(),
+                        },
+                    ))
+                },
+            )
+            .optional()
+            .map_err(db_err)?;
+        let Some((id, mut record)) = row else {
+            return Ok(None);
+        };
+        record.attributes = self.attributes(id)?;
+        Ok(Some(record))
+    }
+
+    fn attributes(&self, part_id: i64) -> Result<Vec<Attribute>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT source, name, value, num FROM attributes WHERE part_id = ?1 ORDER BY source, rowid")
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map(params![part_id], |r| {
+                Ok(Attribute {
+                    source: r.get(0)?,
+                    name: r.get(1)?,
+                    value: r.get(2)?,
+                    num: r.get(3)?,
+                })
+            })
+            .map_err(db_err)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+    }
+
+    /// Append a stock/price observation to the history.
+    pub fn record_stock(&self, mpn: &str, distributor: &str, sample: &StockSample) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT INTO stock_history (mpn, distributor, stock, unit_price, currency, recorded_at, quantity)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![mpn, distributor, sample.stock, sample.unit_price, sample.currency, sample.recorded_at, sample.quantity],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    /// Most recent stock/price observation for a part at a distributor, priced
+    /// at `quantity` in `currency`. Snapshots of other quantities or currencies
+    /// (e.g. from `--record-history`) are skipped; rows from before quantities
+    /// were recorded all came from `watch` and still count.
+    pub fn last_stock(
+        &self,
+        mpn: &str,
+        distributor: &str,
+        quantity: Option<i32>,
+        currency: Option<&str>,
+    ) -> Result<Option<StockSample>, String> {
+        self.conn
+            .query_row(
+                "SELECT stock, unit_price, currency, recorded_at, quantity FROM stock_history
+                 WHERE mpn = ?1 AND distributor = ?2 AND (quantity IS ?3 OR quantity IS NULL) AND currency IS ?4
+                 ORDER BY recorded_at DESC, rowid DESC LIMIT 1",
+                params![mpn, distributor, quantity, currency],
+                stock_sample,
+            )
+            .optional()
+            .map_err(db_err)
+    }
+
+    /// Every stock/price observation for a part since `since` (Unix seconds),
+    /// oldest first, with the distributor it was made at.
+    pub fn stock_history(&self, mpn: &str, since: i64) -> Result<Vec<(String, StockSample)>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT stock, unit_price, currency, recorded_at, quantity, distributor FROM stock_history
+                 WHERE mpn = ?1 AND recorded_at >= ?2 ORDER BY distributor, recorded_at, rowid",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map(params![mpn, since], |r| Ok((r.get(5)?, stock_sample(r)?)))
+            .map_err(db_err)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+    }
+
+    /// Replace all embeddings a source previously stored for a part with `model`.
+    pub fn set_embeddings(
+        &self,
+        part_id: i64,
+        source: &str,
+        model: &str,
+        embeddings: &[Embedding],
+    ) -> Result<(), String> {
+        let tx = self.conn.unchecked_transaction().map_err(db_err)?;
+        tx.execute(
+            "DELETE FROM embeddings WHERE part_id = ?1 AND source = ?2 AND model = ?3",
+            params![part_id, source, model],
+        )
+        .map_err(db_err)?;
+        {
+            let mut stmt = tx
+                .prepare("INSERT INTO embeddings (part_id, source, label, text, model, vector) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
+                .map_err(db_err)?;
+            for embedding in embeddings {
+                let blob: Vec<u8> = embedding.vector.iter().flat_map(|x| x.to_le_bytes()).collect();
+                stmt.execute(params![part_id, source, embedding.label, embedding.text, model, blob])
+                    .map_err(db_err)?;
+            }
+        }
+        tx.commit().map_err(db_err)
+    }
+
+    /// Parts ranked by cosine similarity between `query` and their best chunk
+    /// embedded with `model`. Vectors are compared in full, which is fine for
+    /// a personal library of a few thousand datasheets.
+    pub fn semantic_search(&self, model: &str, query: &[f32], limit: usize) -> Result<Vec<SemanticMatch>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT p.mpn, p.manufacturer, p.description, e.source, e.label, e.text, e.vector
+                 FROM embeddings e JOIN parts p ON p.id = e.part_id WHERE e.model = ?1",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map(params![model], |r| {
+                let blob: Vec<u8> = r.get(6)?;
+                let vector: Vec<f32> = blob
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect();
+                Ok(SemanticMatch {
+                    mpn: r.get(0)?,
+                    manufacturer: r.get(1)?,
+                    description: r.get(2)?,
+                    score: cosine(query, &vector),
+                    source: r.get(3)?,
+                    label: r.get(4)?,
+                    text: r.get(5)?,
+                })
+            })
+            .map_err(db_err)?;
+
+        let mut best: Vec<SemanticMatch> = Vec::new();
+        for row in rows {
+            let row = row.map_err(db_err)?;
+            match best.iter_mut().find(|m| m.mpn == row.mpn) {
+                Some(existing) if existing.score < row.score => *existing = row,
+                Some(_) => {}
+                None => best.push(row),
+            }
+        }
+        best.sort_by(|a, b| b.score.total_cmp(&a.score));
+        best.truncate(limit);
+        Ok(best)
+    }
+
+    /// Run a query expression and return matching parts (without attributes).
+    pub fn query(&self, expr: &str, limit: usize) -> Result<Vec<PartRecord>, String> {
+        let ast = parse_query(expr)?;
+        let mut args: Vec<rusqlite::types::Value> = Vec::new();
+        let where_sql = ast.to_sql(&mut args)?;
+        let sql = format!(
+            "SELECT p.mpn, p.manufacturer, p.description, p.datasheet FROM parts p WHERE {} ORDER BY p.mpn LIMIT {}",
+            where_sql, limit
+        );
+        let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
+        let rows = stmt
+            .query_map(params_from_iter(args), |r| {
+                Ok(PartRecord {
+                    mpn: r.get(0)?,
+                    manufacturer: r.get(1)?,
+                    description: r.get(2)?,
+                    datasheet: r.get(3)?,
+                    attributes: Vec::new(),
+                })
+            })
+            .map_err(db_err)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+    }
+}
+
+/// A `stock_history` row selected as stock, unit_price, currency, recorded_at, quantity.
+fn stock_sample(r: &rusqlite::Row) -> rusqlite::Result<StockSample> {
+    Ok(StockSample {
+        stock: r.get(0)?,
+        unit_price: r.get(1)?,
+        currency: r.get(2)?,
+        recorded_at: r.get(3)?,
+        quantity: r.get(4)?,
+    })
+}
+
+fn db_err(e: rusqlite::Error) -> String {
+    format!("Database error: {}", e)
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denom = norm(a) * norm(b);
+    if denom > 0.0 { dot / denom } else { 0.0 }
+}
+
+pub(crate) fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// Lowercase alphanumerics only, so "QFN-48" and "qfn48" compare equal.
+fn normalize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Attribute names are lowercase with `_` separators ("Voltage - Supply" → "voltage_supply").
+fn attribute_name(name: &str) -> String {
+    name.split('.')
+        .map(|segment| {
+            segment
+                .to_lowercase()
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>()
+                .join("_")
+        })
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Parse a leading number with an optional SI prefix ("3.3V" → 3.3, "25uA" → 2.5e-5, "10k" → 1e4).
+pub(crate) fn parse_number(value: &str) -> Option<f64> {
+    let s = value.trim().trim_start_matches('+');
+    let end = s
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (i == 0 && c == '-')))
+        .map(|(i, _)| i)
+        .unwrap_or(s.len());
+    let number: f64 = s[..end].parse().ok()?;
+    let rest = s[end..].trim_start();
+    let mut chars = rest.chars();
+    let Some(prefix) = chars.next() else {
+        return Some(number);
+    };
+    // Only treat a letter as an SI prefix when a base unit (or nothing) follows,
+    // so "1.27mm" and "5 pins" are not scaled
+    let next = chars.next();
+    let unit_follows = next.is_none_or(|c| "VAWFHsgΩO".contains(c));
+    let scale = match prefix {
+        'p' if unit_follows => 1e-12,
+        'n' if unit_follows => 1e-9,
+        'u' | 'µ' | 'μ' if unit_follows => 1e-6,
+        'm' if next.is_some() && unit_follows => 1e-3,
+        'k' | 'K' if unit_follows => 1e3,
+        'M' if unit_follows => 1e6,
+        'G' if unit_follows => 1e9,
+        _ => 1.0,
+    };
+    Some(number * scale)
+}
+
+// --- Indexing ---
+
+/// Flatten an extraction result into attributes.
+///
+/// Top-level objects (e.g. `part_details`) become `section.field` attributes.
+/// Rows in symbol-keyed sections become `<symbol>.min/.typ/.max`, plus a bare
+/// `<symbol>` holding the max (or typ, or min) value. Packages contribute
+/// `package` and `pin_count`.
+fn flatten_extraction(json: &Value, source: &str) -> Vec<Attribute> {
+    let mut attrs = Vec::new();
+    let Some(obj) = json.as_object() else {
+        return attrs;
+    };
+
+    for (key, value) in obj.iter().filter(|(key, _)| *key != crate::migrate::STAMP) {
+        match value {
+            Value::String(_) | Value::Number(_) | Value::Bool(_) => {
+                attrs.push(Attribute::new(source, key, &scalar_text(value)));
+            }
+            Value::Object(fields) => {
+                for (field, v) in fields {
+                    if !v.is_object() && !v.is_array() && !v.is_null() {
+                        attrs.push(Attribute::new(source, &format!("{}.{}", key, field), &scalar_text(v)));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (section, prefix) in SYMBOL_SECTIONS {
+        let Some(rows) = obj.get(*section).and_then(|v| v.as_array()) else {
+            continue;
+        };
+        for row in rows {
+            let Some(row) = row.as_object() else { continue };
+            let Some(symbol) = ["symbol", "rail_name", "parameter", "parameter_name"]
+                .iter()
+                .find_map(|k| row.get(*k).and_then(|v| v.as_str()).filter(|s| !s.trim().is_empty()))
+            else {
+                continue;
+            };
+            let base = format!("{}{}", prefix, symbol);
+            let mut limits: Vec<(&str, String)> = Vec::new();
+            for (field, v) in row {
+                if v.is_null() || v.is_object() || v.is_array() {
+                    continue;
+                }
+                let lower = field.to_lowercase();
+                // Whole tokens only, so "terminal" or "nominal" is not read as a minimum
+                let limit = lower
+                    .split(|c: char| !c.is_alphanumeric())
+                    .find_map(|token| LIMIT_FIELDS.iter().find(|(name, _)| *name == token));
+                if let Some((_, suffix)) = limit {
+                    limits.push((suffix, scalar_text(v)));
+                } else if lower.contains("voltage") || lower.contains("current") {
+                    attrs.push(Attribute::new(source, &format!("{}.{}", base, field), &scalar_text(v)));
+                }
+            }
+            for (suffix, text) in &limits {
+                attrs.push(Attribute::new(source, &format!("{}.{}", base, suffix), text));
+            }
+            let headline = ["max", "typ", "min"]
+                .iter()
+                .find_map(|s| limits.iter().find(|(suffix, _)| suffix == s));
+            if let Some((_, text)) = headline {
+                attrs.push(Attribute::new(source, &base, text));
+            }
+        }
+    }
+
+    let packages = obj
+        .get("packages")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .chain(obj.get("thermal_data").and_then(|v| v.as_array()).into_iter().flatten());
+    for package in packages {
+        for key in ["package_name", "package_type", "package"] {
+            if let Some(name) = package.get(key).and_then(|v| v.as_str()) {
+                attrs.push(Attribute::new(source, "package", name));
+            }
+        }
+        if let Some(pins) = package.get("pin_count") {
+            attrs.push(Attribute::new(source, "pin_count", &scalar_text(pins)));
+        }
+    }
+
+    attrs.dedup();
+    attrs
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// --- Query language ---
+
+#[derive(Debug, PartialEq)]
+enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    /// Bare word: full-text search
+    Text(String),
+    /// `field op value`
+    Compare { field: String, op: CompareOp, value: String },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum CompareOp {
+    Contains,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn sql(self) -> &'static str {
+        match self {
+            CompareOp::Contains | CompareOp::Eq => "=",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+}
+
+/// Columns on `parts` that can be queried directly.
+const PART_COLUMNS: &[&str] = &["mpn", "manufacturer", "description"];
+
+impl Query {
+    fn to_sql(&self, args: &mut Vec<rusqlite::types::Value>) -> Result<String, String> {
+        use rusqlite::types::Value as V;
+        Ok(match self {
+            Query::And(a, b) => format!("({} AND {})", a.to_sql(args)?, b.to_sql(args)?),
+            Query::Or(a, b) => format!("({} OR {})", a.to_sql(args)?, b.to_sql(args)?),
+            Query::Not(q) => format!("NOT {}", q.to_sql(args)?),
+            Query::Text(word) => {
+                args.push(V::Text(format!("\"{}\"*", word.replace('"', ""))));
+                "p.id IN (SELECT rowid FROM parts_fts WHERE parts_fts MATCH ?)".to_string()
+            }
+            Query::Compare { field, op, value } => {
+                let name = attribute_name(field);
+                let numeric = parse_number(value).filter(|_| *op != CompareOp::Contains);
+                if PART_COLUMNS.contains(&name.as_str()) {
+                    let sql = match op {
+                        CompareOp::Contains => {
+                            args.push(V::Text(format!("%{}%", value)));
+                            format!("p.{} LIKE ?", name)
+                        }
+                        CompareOp::Eq | CompareOp::Ne => {
+                            args.push(V::Text(value.clone()));
+                            format!("p.{} {} ? COLLATE NOCASE", name, op.sql())
+                        }
+                        _ => return Err(format!("Cannot use '{}' on {}; use ':', '=' or '!='", op.sql(), name)),
+                    };
+                    return Ok(sql);
+                }
+                args.push(V::Text(name));
+                match (op, numeric) {
+                    (CompareOp::Contains, _) | (CompareOp::Eq | CompareOp::Ne, None) => {
+                        let sql = if *op == CompareOp::Contains {
+                            args.push(V::Text(format!("%{}%", normalize(value))));
+                            "p.id IN (SELECT part_id FROM attributes WHERE name = ? AND norm LIKE ?)"
+                        } else {
+                            args.push(V::Text(normalize(value)));
+                            "p.id IN (SELECT part_id FROM attributes WHERE name = ? AND norm = ?)"
+                        };
+                        if *op == CompareOp::Ne { format!("NOT {}", sql) } else { sql.to_string() }
+                    }
+                    (_, Some(n)) => {
+                        args.push(V::Real(n));
+                        format!(
+                            "p.id IN (SELECT part_id FROM attributes WHERE name = ? AND num {} ?)",
+                            op.sql()
+                        )
+                    }
+                    (_, None) => {
+                        // Ordering comparison against a non-number never matches
+                        args.pop();
+                        "0".to_string()
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(String),
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' {
+            chars.next();
+            tokens.push(Token::LParen);
+        } else if c == ')' {
+            chars.next();
+            tokens.push(Token::RParen);
+        } else {
+            let mut term = String::new();
+            let mut quoted = false;
+            while let Some(&c) = chars.peek() {
+                if c == '"' {
+                    quoted = !quoted;
+                    chars.next();
+                    continue;
+                }
+                if !quoted && (c.is_whitespace() || c == '(' || c == ')') {
+                    break;
+                }
+                term.push(c);
+                chars.next();
+            }
+            tokens.push(match term.as_str() {
+                "AND" => Token::And,
+                "OR" => Token::Or,
+                "NOT" => Token::Not,
+                _ => Token::Term(term),
+            });
+        }
+    }
+    tokens
+}
+
+/// Parse `expr := or; or := and (OR and)*; and := unary ((AND)? unary)*; unary := NOT unary | atom`.
+fn parse_query(input: &str) -> Result<Query, String> {
+    let tokens = tokenize(input);
+    if tokens.is_empty() {
+        return Err("Empty query".to_string());
+    }
+    let mut pos = 0;
+    let query = parse_or(&tokens, &mut pos)?;
+    if pos != tokens.len() {
+        return Err(format!("Unexpected token in query: {:?}", tokens[pos]));
+    }
+    Ok(query)
+}
+
+fn parse_or(tokens: &[Token], pos: &mut usize) -> Result<Query, String> {
+    let mut left = parse_and(tokens, pos)?;
+    while tokens.get(*pos) == Some(&Token::Or) {
+        *pos += 1;
+        let right = parse_and(tokens, pos)?;
+        left = Query::Or(Box::new(left), Box::new(right));
+    }
+    Ok(left)
+}
+
+fn parse_and(tokens: &[Token], pos: &mut usize) -> Result<Query, String> {
+    let mut left = parse_unary(tokens, pos)?;
+    loop {
+        match tokens.get(*pos) {
+            Some(Token::And) => *pos += 1,
+            // Implicit AND between adjacent terms
+            Some(Token::Term(_)) | Some(Token::Not) | Some(Token::LParen) => {}
+            _ => break,
+        }
+        let right = parse_unary(tokens, pos)?;
+        left = Query::And(Box::new(left), Box::new(right));
+    }
+    Ok(left)
+}
+
+fn parse_unary(tokens: &[Token], pos: &mut usize) -> Result<Query, String> {
+    match tokens.get(*pos) {
+        Some(Token::Not) => {
+            *pos += 1;
+            Ok(Query::Not(Box::new(parse_unary(tokens, pos)?)))
+        }
+        Some(Token::LParen) => {
+            *pos += 1;
+            let inner = parse_or(tokens, pos)?;
+            if tokens.get(*pos) != Some(&Token::RParen) {
+                return Err("Missing ')' in query".to_string());
+            }
+            *pos += 1;
+            Ok(inner)
+        }
+        Some(Token::Term(term)) => {
+            *pos += 1;
+            Ok(parse_term(term))
+        }
+        Some(other) => Err(format!("Unexpected token in query: {:?}", other)),
+        None => Err("Query ends unexpectedly".to_string()),
+    }
+}
+
+fn parse_term(term: &str) -> Query {
+    const OPS: &[(&str, CompareOp)] = &[
+        ("<=", CompareOp::Le),
+        (">=", CompareOp::Ge),
+        ("!=", CompareOp::Ne),
+        ("<", CompareOp::Lt),
+        (">", CompareOp::Gt),
+        ("=", CompareOp::Eq),
+        (":", CompareOp::Contains),
+    ];
+    // Split on the earliest operator position; prefer two-character operators at that position
+    let found = OPS
+        .iter()
+        .filter_map(|(sym, op)| term.find(sym).map(|i| (i, sym.len(), *op)))
+        .min_by_key(|&(i, len, _)| (i, std::cmp::Reverse(len)));
+    match found {
+        Some((i, len, op)) if i > 0 && i + len < term.len() => Query::Compare {
+            field: term[..i].to_string(),
+            op,
+            value: term[i + len..].to_string(),
+        },
+        _ => Query::Text(term.to_string()),
+    }
+}
+
+// --- Commands ---
+
+/// Execute a database subcommand.
+pub fn execute(command: DbSubcommand) -> Result<(), String> {
+    match command {
+        DbSubcommand::Index {
+            json,
+            mpn,
+            manufacturer,
+            task,
+            db,
+        } => cmd_index(&PartsDb::open(db)?, &json, mpn.as_deref(), manufacturer.as_deref(), &task),
+        DbSubcommand::Fetch { mpns, source, db } => {
+            cmd_fetch(&PartsDb::open(db)?, &crate::bom::expand_stdin(mpns)?, &source)
+        }
+        DbSubcommand::ImportLibrary { root, db } => cmd_import_library(&PartsDb::open(db)?, root),
+        DbSubcommand::Query {
+            query,
+            limit,
+            db,
+            json,
+        } => cmd_query(&PartsDb::open(db)?, &query, limit, json),
+        DbSubcommand::SemanticSearch {
+            query,
+            limit,
+            model,
+            api_key,
+            base_url,
+            db,
+            json,
+        } => {
+            let db = PartsDb::open(db)?;
+            let api_key = crate::llm::resolve_api_key(crate::llm::LlmProvider::Gemini, api_key)
+                .map_err(|e| e.to_string())?;
+            cmd_semantic_search(&db, &query, limit, &model, &api_key, base_url, json)
+        }
+        DbSubcommand::Show { mpn, db, json } => cmd_show(&PartsDb::open(db)?, &mpn, json),
+    }
+}
+
+fn cmd_index(
+    db: &PartsDb,
+    path: &Path,
+    mpn: Option<&str>,
+    manufacturer: Option<&str>,
+    task: &str,
+) -> Result<(), String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut json: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    // Older output is upgraded so attributes keep the current names
+    let known_task = crate::extract::ExtractTask::from_str(task, true).ok().map(|t| t.prompt().name);
+    if json.get(crate::migrate::STAMP).is_some() || known_task.is_some() {
+        crate::migrate::upgrade(&mut json, known_task).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+
+    let mpn = mpn
+        .map(str::to_string)
+        .or_else(|| {
+            json.get("part_number")
+                .or_else(|| json.pointer("/part_details/part_number"))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        })
+        .ok_or_else(|| "No --mpn given and no part_number in the JSON".to_string())?;
+    let manufacturer = manufacturer.map(str::to_string).or_else(|| {
+        json.pointer("/part_details/manufacturer")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    });
+
+    let attrs = flatten_extraction(&json, &format!("extract:{}", task));
+    let id = db.upsert_part(&mpn, manufacturer.as_deref(), None, None)?;
+    db.set_attributes(id, &format!("extract:{}", task), &attrs)?;
+    println!("Indexed {} attribute(s) for {} ({})", attrs.len(), mpn, task);
+    Ok(())
+}
+
+fn cmd_fetch(db: &PartsDb, mpns: &[String], sources: &[Source]) -> Result<(), String> {
+    for mpn in mpns {
+        let mut manufacturer: Option<String> = None;
+        let mut description: Option<String> = None;
+        let mut indexed = 0;
+
+        for &source in sources {
+            let mut attrs = Vec::new();
+            let tag = format!("distributor:{}", source.name());
+            match source.lookup_stock(mpn) {
+                Ok(info) => {
+                    manufacturer = manufacturer.or(info.manufacturer.clone());
+                    if let Some(ref status) = info.lifecycle_status {
+                        attrs.push(Attribute::new(&tag, "lifecycle", status));
+                    }
+                    if let Some(stock) = info.stock {
+                        attrs.push(Attribute::new(&tag, "stock", &stock.to_string()));
+                    }
+                    if let Some(ref lead) = info.lead_time {
+                        attrs.push(Attribute::new(&tag, "lead_time", lead));
+                    }
+                    if let Some(pb) = info.price_breaks.first() {
+                        attrs.push(Attribute::new(&tag, "price", &pb.unit_price.to_string()));
+                    }
+                }
+                Err(e) => {
+                    eprintln!("[DB] {}: {}: {}", mpn, source.name(), e);
+                    continue;
+                }
+            }
+            let params = match source {
+                Source::Digikey => digikey::lookup_parameters(mpn).ok(),
+                Source::Mouser => mouser::lookup_parameters(mpn).ok(),
+                _ => None,
+            };
+            if let Some(params) = params {
+                description = description.or(params.description);
+                manufacturer = manufacturer.or(params.manufacturer);
+                // Canonical attributes replace a raw parameter of the same name
+                let canonical = parameters::canonical(&params.parameters);
+                for (name, value) in &canonical {
+                    attrs.push(Attribute::new(&tag, name, value));
+                }
+                for (name, value) in &params.parameters {
+                    let attr = Attribute::new(&tag, name, value);
+                    if !canonical.iter().any(|(c, _)| *c == attr.name) {
+                        attrs.push(attr);
+                    }
+                }
+            }
+            let id = db.upsert_part(mpn, manufacturer.as_deref(), description.as_deref(), None)?;
+            indexed += attrs.len();
+            db.set_attributes(id, &tag, &attrs)?;
+        }
+        println!("{}: {} attribute(s) indexed", mpn, indexed);
+    }
+    Ok(())
+}
+
+fn cmd_import_library(db: &PartsDb, root: Option<PathBuf>) -> Result<(), String> {
+    let library = Library::open(root)?;
+    let entries = library.entries()?;
+    for entry in &entries {
+        let path = library.pdf_path(entry);
+        let id = db.upsert_part(
+            &entry.mpn,
+            Some(&entry.manufacturer),
+            None,
+            Some(&path.to_string_lossy()),
+        )?;
+        let mut attrs = vec![Attribute::new("library", "sha256", &entry.sha256)];
+        if let Some(ref rev) = entry.revision {
+            attrs.push(Attribute::new("library", "revision", rev));
+        }
+        db.set_attributes(id, "library", &attrs)?;
+    }
+    println!("Imported {} datasheet(s) from the library", entries.len());
+    Ok(())
+}
+
+fn cmd_query(db: &PartsDb, query: &str, limit: usize, json_output: bool) -> Result<(), String> {
+    let results = db.query(query, limit)?;
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&results)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if results.is_empty() {
+        println!("No parts match: {}", query);
+        return Ok(());
+    }
+
+    println!("{} part(s):", results.len());
+    for part in &results {
+        print!("  {:<24}", part.mpn);
+        if let Some(ref mfr) = part.manufacturer {
+            print!(" {}", mfr);
+        }
+        println!();
+        if let Some(ref desc) = part.description {
+            println!("    {}", desc);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_semantic_search(
+    db: &PartsDb,
+    query: &str,
+    limit: usize,
+    model: &str,
+    api_key: &str,
+    base_url: Option<String>,
+    json_output: bool,
+) -> Result<(), String> {
+    let vector = crate::llm::embed(api_key, base_url, model, &[query.to_string()], true)
+        .map_err(|e| e.to_string())?
+        .pop()
+        .unwrap_or_default();
+    let results = db.semantic_search(model, &vector, limit)?;
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&results)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if results.is_empty() {
+        println!("No embedded parts for model {}; run `datasheet embed` first", model);
+        return Ok(());
+    }
+
+    for hit in &results {
+        print!("  {:.3}  {:<24}", hit.score, hit.mpn);
+        if let Some(ref mfr) = hit.manufacturer {
+            print!(" {}", mfr);
+        }
+        println!();
+        println!("         {} ({})", hit.label, hit.source);
+    }
+    Ok(())
+}
+
+fn cmd_show(db: &PartsDb, mpn: &str, json_output: bool) -> Result<(), String> {
+    let part = db
+        .get(mpn)?
+        .ok_or_else(|| format!("Part not in database: {}", mpn))?;
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&part)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    println!("{}", part.mpn);
+    if let Some(ref mfr) = part.manufacturer {
+        println!("  Manufacturer: {}", mfr);
+    }
+    if let Some(ref desc) = part.description {
+        println!("  Description: {}", desc);
+    }
+    if let Some(ref ds) = part.datasheet {
+        println!("  Datasheet: {}", ds);
+    }
+    let mut current_source = "";
+    for attr in &part.attributes {
+        if attr.source != current_source {
+            println!();
+            println!("  [{}]", attr.source);
+            current_source = &attr.source;
+        }
+        println!("    {:<32} {}", attr.name, attr.value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("3.3V"), Some(3.3));
+        assert_eq!(parse_number("-0.3 V"), Some(-0.3));
+        assert_eq!(parse_number("+2%"), Some(2.0));
+        assert_eq!(parse_number("1.27mm"), Some(1.27));
+        assert_eq!(parse_number("10k"), Some(10_000.0));
+        assert!((parse_number("25uA").unwrap() - 25e-6).abs() < 1e-12);
+        assert!((parse_number("1.5mA").unwrap() - 1.5e-3).abs() < 1e-12);
+        assert_eq!(parse_number("n/a"), None);
+    }
+
+    #[test]
+    fn test_query_end_to_end() {
+        let db = PartsDb::open_in_memory().unwrap();
+        let extraction = json!({
+            "part_number": "LDO1",
+            "recommended_operating_conditions": [
+                {"parameter": "Input Voltage", "symbol": "VCC", "range_min": "1.8V", "range_max": "3.6V"}
+            ],
+            "absolute_maximum_ratings": [
+                {"parameter": "Input Voltage", "symbol": "VCC", "limit_max": "6V"}
+            ],
+            "packages": [{"package_name": "QFN-48", "pin_count": 48}]
+        });
+        let id = db.upsert_part("LDO1", Some("Acme"), Some("Low dropout regulator"), None).unwrap();
+        db.set_attributes(id, "extract:test", &flatten_extraction(&extraction, "extract:test"))
+            .unwrap();
+        let other = db.upsert_part("LDO2", Some("Other"), None, None).unwrap();
+        db.set_attributes(other, "extract:test", &[Attribute::new("extract:test", "vcc", "5.5V")])
+            .unwrap();
+
+        let mpns = |q: &str| -> Vec<String> { db.query(q, 10).unwrap().into_iter().map(|p| p.mpn).collect() };
+        assert_eq!(mpns("package:QFN48 AND vcc<=3.6"), vec!["LDO1"]);
+        assert_eq!(mpns("absmax.vcc>=6"), vec!["LDO1"]);
+        assert_eq!(mpns("vcc>3.6"), vec!["LDO2"]);
+        assert_eq!(mpns("regulator"), vec!["LDO1"]);
+        assert_eq!(mpns("NOT manufacturer:acme"), vec!["LDO2"]);
+        assert_eq!(mpns("(pin_count=48 OR vcc>5) AND mpn:ldo"), vec!["LDO1", "LDO2"]);
+        assert_eq!(mpns("mpn=ldo1"), vec!["LDO1"]);
+        assert!(mpns("mpn=LDO").is_empty());
+        assert_eq!(mpns("mpn!=LDO1"), vec!["LDO2"]);
+        assert!(db.query("mpn>LDO1", 10).is_err());
+    }
+
+    #[test]
+    fn test_flatten_limit_fields_are_whole_tokens() {
+        let extraction = json!({
+            "electrical_specifications": [
+                {"symbol": "IOUT", "nominal": "150mA", "terminal_count": 3, "max_value": "300mA"}
+            ]
+        });
+        let attrs = flatten_extraction(&extraction, "extract:test");
+        let value = |name: &str| attrs.iter().find(|a| a.name == name).map(|a| a.value.as_str());
+        assert_eq!(value("iout.typ"), Some("150mA"));
+        assert_eq!(value("iout.max"), Some("300mA"));
+        assert_eq!(value("iout.min"), None);
+        assert_eq!(value("iout"), Some("300mA"));
+    }
+
+    #[test]
+    fn test_semantic_search_ranks_best_chunk() {
+        let db = PartsDb::open_in_memory().unwrap();
+        let chunk = |label: &str, vector: Vec<f32>| Embedding {
+            label: label.to_string(),
+            text: label.to_string(),
+            vector,
+        };
+        let buck = db.upsert_part("BUCK1", None, None, None).unwrap();
+        db.set_embeddings(buck, "pdf:buck1.pdf", "m", &[
+            chunk("page 1", vec![0.6, 0.8, 0.0]),
+            chunk("page 2", vec![1.0, 0.0, 0.0]),
+        ])
+        .unwrap();
+        let ldo = db.upsert_part("LDO1", None, None, None).unwrap();
+        db.set_embeddings(ldo, "pdf:ldo1.pdf", "m", &[chunk("page 1", vec![0.0, 0.0, 1.0])])
+            .unwrap();
+        db.set_embeddings(ldo, "pdf:ldo1.pdf", "other", &[chunk("page 1", vec![1.0, 0.0, 0.0])])
+            .unwrap();
+
+        let hits = db.semantic_search("m", &[1.0, 0.0, 0.0], 10).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!((hits[0].mpn.as_str(), hits[0].label.as_str()), ("BUCK1", "page 2"));
+        assert!((hits[0].score - 1.0).abs() < 1e-6);
+        assert_eq!(hits[1].mpn, "LDO1");
+        assert!(hits[1].score.abs() < 1e-6);
+    }
+}
@@ -5,13 +5,23 @@
 
 use clap::Subcommand;
 use serde::{Deserialize, Serialize};
-use std::fs::File;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::currency;
+use crate::download::{self, DownloadOptions};
+use crate::error::{Error, ErrorKind};
+use crate::parameters::PartParameters;
+use crate::stock::{SearchHit, StockInfo, StockPriceBreak, TableFormat, TableRow};
 
 const DIGIKEY_API_BASE: &str = "https://api.digikey.com";
 const DIGIKEY_API_BASE_SANDBOX: &str = "https://sandbox-api.digikey.com";
 const ENV_VAR_CLIENT_ID: &str = "DIGIKEY_CLIENT_ID";
 const ENV_VAR_CLIENT_SECRET: &str = "DIGIKEY_CLIENT_SECRET";
+/// Overrides the API root, e.g. to point the tests at a mock server
+const ENV_VAR_API_BASE: &str = "DIGIKEY_API_BASE";
+/// When set, raw API responses are saved here as test fixtures
+const ENV_VAR_RECORD_DIR: &str = "DIGIKEY_RECORD_DIR";
 
 /// DigiKey API subcommands.
 #[derive(Subcommand, Debug)]
@@ -33,7 +43,15 @@ pub enum DigikeySubcommand {
         #[arg(long, short, default_value = "10")]
         limit: usize,
 
-        /// Output results as JSON
+        /// Page number (1-indexed, takes precedence over --offset)
+        #[arg(long, short)]
+        page: Option<usize>,
+
+        /// Starting record offset (0-indexed)
+        #[arg(long, short = 'o')]
+        offset: Option<usize>,
+
+        /// Output results as JSON: the products with total counts, paging, and applied filters
         #[arg(long)]
         json: bool,
 
@@ -57,6 +75,10 @@ pub enum DigikeySubcommand {
         #[arg(long)]
         in_stock: bool,
 
+        /// Only return parts whose manufacturer part number is exactly the query
+        #[arg(long, short)]
+        exact: bool,
+
         /// Sort results: price, stock, mpn, manufacturer
         #[arg(long)]
         sort: Option<String>,
@@ -76,11 +98,20 @@ pub enum DigikeySubcommand {
         /// Output FilterOptions from API response as JSON instead of products
         #[arg(long)]
         show_filters: bool,
+
+        /// Print a table with these columns instead of the summary, e.g.
+        /// "mpn,mfr,stock,price@100,lifecycle" (also: desc, sku, moq, lead, currency, distributor)
+        #[arg(long, conflicts_with_all = ["json", "show_filters"])]
+        columns: Option<String>,
+
+        /// Print CSV (of --columns, default mpn,mfr,stock,price,lifecycle)
+        #[arg(long, conflicts_with_all = ["json", "show_filters"])]
+        csv: bool,
     },
 
     /// Download datasheet for a part
     Download {
-        /// Part number to download datasheet for
+        /// Part number to download datasheet for, or a digikey.com product page URL
         part_number: String,
 
         /// DigiKey Client ID (defaults to DIGIKEY_CLIENT_ID env var)
@@ -95,10 +126,14 @@ pub enum DigikeySubcommand {
         #[arg(long, short)]
         output: Option<PathBuf>,
 
-        /// Output directory (used if --output not specified)
+        /// Output directory (used if --output not specified; default: <download dir>/datasheets)
         #[arg(long, short)]
         dir: Option<PathBuf>,
 
+        /// Print the resolved datasheet URL instead of downloading it
+        #[arg(long, conflicts_with_all = ["output", "dir"])]
+        url_only: bool,
+
         /// Use sandbox API for testing
         #[arg(long)]
         sandbox: bool,
@@ -106,7 +141,7 @@ pub enum DigikeySubcommand {
 
     /// Get detailed information about a specific part
     Part {
-        /// DigiKey part number or manufacturer part number
+        /// DigiKey part number, manufacturer part number, or digikey.com product page URL
         part_number: String,
 
         /// DigiKey Client ID (defaults to DIGIKEY_CLIENT_ID env var)
@@ -128,7 +163,7 @@ pub enum DigikeySubcommand {
 
     /// Quick stock and pricing check for a part
     Stock {
-        /// Part number to check
+        /// Part number to check, or a digikey.com product page URL
         part_number: String,
 
         #[arg(long, env = "DIGIKEY_CLIENT_ID")]
@@ -146,43 +181,11 @@ pub enum DigikeySubcommand {
     },
 }
 
-// Normalized stock/pricing output type
-
-#[derive(Serialize)]
-struct StockInfo {
-    mpn: String,
-    manufacturer: Option<String>,
-    distributor: &'static str,
-    distributor_pn: Option<String>,
-    lifecycle_status: Option<String>,
-    stock: Option<i64>,
-    lead_time: Option<String>,
-    moq: Option<i32>,
-    order_multiple: Option<i32>,
-    currency: String,
-    price_breaks: Vec<StockPriceBreak>,
-}
-
-#[derive(Serialize)]
-struct StockPriceBreak {
-    quantity: i32,
-    unit_price: f64,
-}
-
 // DigiKey API OAuth token types
 
-#[derive(Serialize)]
-struct TokenRequest {
-    client_id: String,
-    client_secret: String,
-    grant_type: String,
-}
-
 #[derive(Deserialize, Debug)]
 struct TokenResponse {
     access_token: String,
-    token_type: String,
-    expires_in: i32,
 }
 
 // DigiKey API request/response types
@@ -248,6 +251,8 @@ struct SortOptions {
 struct SearchResponse {
     products: Vec<Product>,
     #[serde(default)]
+    exact_matches: Vec<Product>,
+    #[serde(default)]
     products_count: i32,
     #[serde(default)]
     exact_manufacturer_products_count: i32,
@@ -255,6 +260,66 @@ struct SearchResponse {
     filter_options: Option<FilterOptions>,
 }
 
+/// `search --json` output: one page of products plus what scripts need to
+/// page through the rest.
+#[derive(Serialize)]
+struct SearchEnvelope<'a> {
+    query: &'a str,
+    /// Total matches across all pages
+    products_count: usize,
+    exact_manufacturer_products_count: usize,
+    offset: usize,
+    limit: usize,
+    /// 1-indexed page, as accepted by --page
+    page: usize,
+    returned: usize,
+    /// Offset of the next page, `null` on the last one
+    next_offset: Option<usize>,
+    filters: AppliedFilters,
+    products: &'a [Product],
+}
+
+/// Filters sent with the search, by resolved DigiKey ID.
+#[derive(Serialize, Default)]
+struct AppliedFilters {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    category_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    manufacturer_ids: Vec<String>,
+    /// "ParameterId=ValueId" pairs, as accepted by --param-id
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    parameters: Vec<String>,
+    in_stock: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    exact: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort: Option<String>,
+}
+
+impl AppliedFilters {
+    fn new(request: Option<&FilterOptionsRequest>, in_stock: bool, exact: bool, sort: Option<String>) -> Self {
+        let ids = |filter: Option<&Vec<FilterId>>| -> Vec<String> {
+            filter.into_iter().flatten().map(|f| f.id.clone()).collect()
+        };
+        let Some(request) = request else {
+            return Self { in_stock, exact, sort, ..Default::default() };
+        };
+        Self {
+            category_ids: ids(request.category_filter.as_ref()),
+            manufacturer_ids: ids(request.manufacturer_filter.as_ref()),
+            parameters: request
+                .parameter_filter_request
+                .iter()
+                .flat_map(|p| &p.parameter_filters)
+                .flat_map(|p| p.filter_values.iter().map(move |v| format!("{}={}", p.parameter_id, v.id)))
+                .collect(),
+            in_stock,
+            exact,
+            sort,
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Serialize)]
 #[serde(rename_all = "PascalCase")]
 struct FilterOptions {
@@ -311,16 +376,26 @@ struct CategoryInfo {
     product_count: Option<i64>,
 }
 
+/// A product as returned by ProductDetails and keyword search.
+///
+/// Older responses carry the flat fields (`ProductDescription`, `StandardPricing`,
+/// ...); API v4 nests them under `Description`, `ProductVariations`, and so on.
+/// [`Product::normalized`] fills the flat fields from the v4 ones so the rest
+/// of this module can use either. Fields not modeled here are kept in `other`,
+/// so `--json` prints the whole response.
 #[derive(Deserialize, Debug, Serialize)]
 #[serde(rename_all = "PascalCase")]
 struct Product {
     digi_key_part_number: Option<String>,
+    #[serde(alias = "ManufacturerProductNumber")]
     manufacturer_part_number: Option<String>,
     manufacturer: Option<Manufacturer>,
     product_description: Option<String>,
     detailed_description: Option<String>,
+    #[serde(alias = "DatasheetUrl")]
     data_sheet_url: Option<String>,
     product_url: Option<String>,
+    #[serde(alias = "PhotoUrl")]
     primary_photo: Option<String>,
     quantity_available: Option<i32>,
     minimum_order_quantity: Option<i32>,
@@ -332,6 +407,118 @@ struct Product {
     lead_status: Option<String>,
     part_status: Option<String>,
     parameters: Option<Vec<Parameter>>,
+    // API v4
+    description: Option<ProductDescription>,
+    product_status: Option<ProductStatus>,
+    #[serde(default)]
+    product_variations: Vec<ProductVariation>,
+    classifications: Option<Classifications>,
+    category: Option<Category>,
+    series: Option<IdName>,
+    base_product_number: Option<IdName>,
+    #[serde(default)]
+    other_names: Vec<String>,
+    primary_video_url: Option<String>,
+    manufacturer_lead_weeks: Option<String>,
+    normally_stocking: Option<bool>,
+    discontinued: Option<bool>,
+    end_of_life: Option<bool>,
+    ncnr: Option<bool>,
+    date_last_buy_chance: Option<String>,
+    #[serde(default)]
+    media_links: Vec<MediaLink>,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Product {
+    /// `self` with the flat fields filled in from their API v4 counterparts.
+    fn normalized(mut self) -> Self {
+        if let Some(description) = self.description.as_ref() {
+            self.product_description = self.product_description.or_else(|| description.product_description.clone());
+            self.detailed_description = self.detailed_description.or_else(|| description.detailed_description.clone());
+        }
+        self.part_status = self.part_status.or_else(|| self.product_status.as_ref()?.status.clone());
+        self.ro_hs_status = self.ro_hs_status.or_else(|| self.classifications.as_ref()?.rohs_status.clone());
+        // The first variation (usually cut tape) stands in for the product
+        if let Some(variation) = self.product_variations.first() {
+            self.digi_key_part_number =
+                self.digi_key_part_number.or_else(|| variation.digi_key_product_number.clone());
+            self.minimum_order_quantity = self.minimum_order_quantity.or(variation.minimum_order_quantity);
+            if self.standard_pricing.as_ref().is_none_or(|p| p.is_empty()) && !variation.standard_pricing.is_empty() {
+                self.standard_pricing = Some(variation.standard_pricing.clone());
+            }
+            if self.packaging.is_none() {
+                let value = variation.package_type.as_ref().and_then(|p| p.name.clone());
+                self.packaging = value.map(|value| PackagingInfo { value: Some(value) });
+            }
+        }
+        self
+    }
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ProductDescription {
+    product_description: Option<String>,
+    detailed_description: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ProductStatus {
+    id: Option<i64>,
+    status: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ProductVariation {
+    digi_key_product_number: Option<String>,
+    package_type: Option<IdName>,
+    #[serde(default)]
+    standard_pricing: Vec<PriceBreak>,
+    #[serde(alias = "QuantityAvailableforPackageType")]
+    quantity_available_for_package_type: Option<i64>,
+    minimum_order_quantity: Option<i32>,
+    standard_package: Option<i64>,
+    digi_reel_fee: Option<f64>,
+    market_place: Option<bool>,
+    supplier: Option<IdName>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Classifications {
+    reach_status: Option<String>,
+    rohs_status: Option<String>,
+    moisture_sensitivity_level: Option<String>,
+    export_control_class_number: Option<String>,
+    htsus_code: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Category {
+    category_id: Option<i64>,
+    name: Option<String>,
+    #[serde(default)]
+    child_categories: Vec<Category>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct IdName {
+    id: Option<i64>,
+    name: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct MediaLink {
+    media_type: Option<String>,
+    title: Option<String>,
+    url: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Serialize)]
@@ -341,7 +528,7 @@ struct Manufacturer {
     id: Option<i32>,
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 struct PriceBreak {
     break_quantity: Option<i32>,
@@ -358,45 +545,85 @@ struct PackagingInfo {
 #[derive(Deserialize, Debug, Serialize)]
 #[serde(rename_all = "PascalCase")]
 struct Parameter {
+    #[serde(alias = "ParameterText")]
     parameter: Option<String>,
+    #[serde(alias = "ValueText")]
     value: Option<String>,
+    parameter_id: Option<i64>,
+    parameter_type: Option<String>,
+    value_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct SubstitutionsResponse {
+    product_substitutes: Option<Vec<ProductSubstitute>>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct ProductSubstitute {
+    substitute_type: Option<String>,
+    manufacturer_product_number: Option<String>,
+    manufacturer: Option<Manufacturer>,
+    description: Option<String>,
+    quantity_available: Option<i64>,
+}
+
+/// A part DigiKey lists as a substitute for another part.
+#[derive(Debug, Clone)]
+pub(crate) struct Substitute {
+    pub mpn: String,
+    pub manufacturer: Option<String>,
+    pub substitute_type: Option<String>,
+    pub description: Option<String>,
+    pub stock: Option<i64>,
 }
 
 /// Execute a DigiKey subcommand.
-pub fn execute(command: DigikeySubcommand) -> Result<(), String> {
+pub fn execute(command: DigikeySubcommand) -> Result<(), Error> {
     match command {
         DigikeySubcommand::Search {
             query,
             client_id,
             client_secret,
             limit,
+            page,
+            offset,
             json,
             sandbox,
             category,
             param,
             manufacturer,
             in_stock,
+            exact,
             sort,
             category_id,
             manufacturer_id,
             param_id,
             show_filters,
+            columns,
+            csv,
         } => cmd_search(
             &query,
             client_id.as_deref(),
             client_secret.as_deref(),
             limit,
+            page,
+            offset,
             json,
             sandbox,
             category,
             param,
             manufacturer,
             in_stock,
+            exact,
             sort,
             category_id,
             manufacturer_id,
             param_id,
             show_filters,
+            TableFormat::from_args(columns.as_deref(), csv)?,
         ),
         DigikeySubcommand::Download {
             part_number,
@@ -404,46 +631,60 @@ pub fn execute(command: DigikeySubcommand) -> Result<(), String> {
             client_secret,
             output,
             dir,
+            url_only,
             sandbox,
-        } => cmd_download(&part_number, client_id.as_deref(), client_secret.as_deref(), output, dir, sandbox),
+        } => cmd_download(
+            &part_number_arg(&part_number)?,
+            client_id.as_deref(),
+            client_secret.as_deref(),
+            output,
+            dir,
+            url_only,
+            sandbox,
+        ),
         DigikeySubcommand::Part {
             part_number,
             client_id,
             client_secret,
             json,
             sandbox,
-        } => cmd_part(&part_number, client_id.as_deref(), client_secret.as_deref(), json, sandbox),
+        } => cmd_part(&part_number_arg(&part_number)?, client_id.as_deref(), client_secret.as_deref(), json, sandbox),
         DigikeySubcommand::Stock {
             part_number,
             client_id,
             client_secret,
             json,
             sandbox,
-        } => cmd_stock(&part_number, client_id.as_deref(), client_secret.as_deref(), json, sandbox),
+        } => cmd_stock(&part_number_arg(&part_number)?, client_id.as_deref(), client_secret.as_deref(), json, sandbox),
     }
 }
 
 fn get_credentials(
     provided_client_id: Option<&str>,
     provided_client_secret: Option<&str>,
-) -> Result<(String, String), String> {
+) -> Result<(String, String), Error> {
+    // Offline lookups replay cached responses, which need no credentials
+    if crate::offline::is_enabled() {
+        return Ok((provided_client_id.unwrap_or_default().to_string(), String::new()));
+    }
+
     let client_id = if let Some(id) = provided_client_id {
         if !id.is_empty() {
             id.to_string()
         } else {
-            std::env::var(ENV_VAR_CLIENT_ID).map_err(|_| {
-                format!(
-                    "DigiKey Client ID not provided. Set {} environment variable or use --client-id",
+            crate::auth::credential(ENV_VAR_CLIENT_ID).ok_or_else(|| {
+                Error::auth(format!(
+                    "DigiKey Client ID not provided. Set {} environment variable, run `datasheet auth set digikey`, or use --client-id",
                     ENV_VAR_CLIENT_ID
-                )
+                ))
             })?
         }
     } else {
-        std::env::var(ENV_VAR_CLIENT_ID).map_err(|_| {
-            format!(
-                "DigiKey Client ID not provided. Set {} environment variable or use --client-id",
+        crate::auth::credential(ENV_VAR_CLIENT_ID).ok_or_else(|| {
+            Error::auth(format!(
+                "DigiKey Client ID not provided. Set {} environment variable, run `datasheet auth set digikey`, or use --client-id",
                 ENV_VAR_CLIENT_ID
-            )
+            ))
         })?
     };
 
@@ -451,43 +692,400 @@ fn get_credentials(
         if !secret.is_empty() {
             secret.to_string()
         } else {
-            std::env::var(ENV_VAR_CLIENT_SECRET).map_err(|_| {
-                format!(
-                    "DigiKey Client Secret not provided. Set {} environment variable or use --client-secret",
+            crate::auth::credential(ENV_VAR_CLIENT_SECRET).ok_or_else(|| {
+                Error::auth(format!(
+                    "DigiKey Client Secret not provided. Set {} environment variable, run `datasheet auth set digikey`, or use --client-secret",
                     ENV_VAR_CLIENT_SECRET
-                )
+                ))
             })?
         }
     } else {
-        std::env::var(ENV_VAR_CLIENT_SECRET).map_err(|_| {
-            format!(
-                "DigiKey Client Secret not provided. Set {} environment variable or use --client-secret",
+        crate::auth::credential(ENV_VAR_CLIENT_SECRET).ok_or_else(|| {
+            Error::auth(format!(
+                "DigiKey Client Secret not provided. Set {} environment variable, run `datasheet auth set digikey`, or use --client-secret",
                 ENV_VAR_CLIENT_SECRET
-            )
+            ))
         })?
     };
 
     Ok((client_id, client_secret))
 }
 
-fn get_access_token(client_id: &str, client_secret: &str, sandbox: bool) -> Result<String, String> {
-    let base_url = if sandbox { DIGIKEY_API_BASE_SANDBOX } else { DIGIKEY_API_BASE };
+/// The part number to look up: `input` itself, or the one in a pasted
+/// digikey.com product page URL.
+///
+/// Current product pages are `/<lang>/products/detail/<mfr>/<mpn>/<product id>`;
+/// older ones are `/product-detail/<lang>/<mfr>/<mpn>/<digikey pn>/<product id>`,
+/// where the DigiKey part number is used since it names the exact packaging.
+fn part_number_arg(input: &str) -> Result<String, Error> {
+    let input = input.trim();
+    let without_scheme = input
+        .strip_prefix("https://")
+        .or_else(|| input.strip_prefix("http://"))
+        .unwrap_or(input);
+    let (host, path) = without_scheme.split_once('/').unwrap_or((without_scheme, ""));
+    let is_url = without_scheme.len() != input.len() || host.contains("digikey.");
+    if !is_url {
+        return Ok(input.to_string());
+    }
+    if !host.contains("digikey.") {
+        return Err(Error::validation(format!("Not a DigiKey URL: {}", input)));
+    }
+
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| urlencoding::decode(s).map(|s| s.into_owned()).unwrap_or_else(|_| s.to_string()))
+        .collect();
+    let position = |name: &str| segments.iter().position(|s| s == name);
+    let part_number = if let Some(i) = position("detail") {
+        segments.get(i + 2)
+    } else if let Some(i) = position("product-detail") {
+        segments.get(i + 4).filter(|pn| pn.ends_with("-ND")).or_else(|| segments.get(i + 3))
+    } else {
+        None
+    };
+    part_number
+        .cloned()
+        .ok_or_else(|| Error::validation(format!("No part number in DigiKey URL: {}", input)))
+}
+
+/// API root: `DIGIKEY_API_BASE` when set, otherwise production or sandbox.
+fn api_base(sandbox: bool) -> String {
+    match std::env::var(ENV_VAR_API_BASE) {
+        Ok(base) if !base.trim().is_empty() => base.trim_end_matches('/').to_string(),
+        _ if sandbox => DIGIKEY_API_BASE_SANDBOX.to_string(),
+        _ => DIGIKEY_API_BASE.to_string(),
+    }
+}
+
+/// Save a raw API response as `<DIGIKEY_RECORD_DIR>/<name>.json` so sandbox
+/// sessions can be turned into test fixtures. Tokens are never recorded.
+fn record_fixture(name: &str, value: &serde_json::Value) {
+    let Some(dir) = std::env::var_os(ENV_VAR_RECORD_DIR).map(PathBuf::from) else {
+        return;
+    };
+    let path = dir.join(format!("{}.json", crate::paths::sanitize_filename(name)));
+    let result = std::fs::create_dir_all(&dir)
+        .and_then(|_| crate::paths::write_file(&path, serde_json::to_string_pretty(value).unwrap_or_default()));
+    match result {
+        Ok(()) => eprintln!("[DIGIKEY] Recorded {}", path.display()),
+        Err(e) => eprintln!("[DIGIKEY] Warning: could not record {}: {}", path.display(), e),
+    }
+}
+
+fn get_access_token(client_id: &str, client_secret: &str, sandbox: bool) -> Result<String, Error> {
+    if crate::offline::is_enabled() {
+        return Ok(String::new());
+    }
+    let base_url = api_base(sandbox);
     let url = format!("{}/v1/oauth2/token", base_url);
 
-    let response: TokenResponse = ureq::post(&url)
-        .send_form(&[
+    let call = crate::audit::start("digikey", "POST", &url, None);
+    let body = call
+        .finish(ureq::post(&url).send_form(&[
             ("client_id", client_id),
             ("client_secret", client_secret),
             ("grant_type", "client_credentials"),
-        ])
-        .map_err(|e| format!("Failed to get access token: {}", e))?
-        .into_json()
-        .map_err(|e| format!("Failed to parse token response: {}", e))?;
+        ]))
+        .map_err(|e| Error::from_ureq("Failed to get access token", *e))?;
+    let response: TokenResponse =
+        serde_json::from_slice(&body).map_err(|e| format!("Failed to parse token response: {}", e))?;
 
     Ok(response.access_token)
 }
 
-fn map_sort_field(sort: &str) -> Result<SortOptions, String> {
+/// Confirm the configured client credentials are accepted by fetching an access token.
+pub(crate) fn check_credentials() -> Result<(), Error> {
+    let (client_id, client_secret) = get_credentials(None, None)?;
+    get_access_token(&client_id, &client_secret, false).map(|_| ())
+}
+
+/// Client ID and access token from environment credentials, fetched once per process.
+fn env_session() -> Result<(String, String), Error> {
+    static SESSION: OnceLock<Result<(String, String), Error>> = OnceLock::new();
+    SESSION
+        .get_or_init(|| {
+            let (client_id, client_secret) = get_credentials(None, None)?;
+            let access_token = get_access_token(&client_id, &client_secret, false)?;
+            Ok((client_id, access_token))
+        })
+        .clone()
+}
+
+/// Resolve the datasheet URL for a part using credentials from the environment.
+///
+/// Returns `Ok(None)` when the part exists but has no datasheet link.
+pub(crate) fn find_datasheet_url(part_number: &str) -> Result<Option<String>, Error> {
+    let (client_id, access_token) = env_session()?;
+    let product = get_part_by_number(&client_id, &access_token, part_number, false)?;
+    Ok(product.data_sheet_url.filter(|u| !u.is_empty()))
+}
+
+/// Keyword search returning normalized hits, using credentials from the environment.
+pub(crate) fn search_parts(keyword: &str, limit: usize) -> Result<Vec<SearchHit>, Error> {
+    let (client_id, access_token) = env_session()?;
+    let response = search_by_keyword(&client_id, &access_token, KeywordSearch::new(keyword, limit))?;
+    Ok(response
+        .products
+        .into_iter()
+        .filter_map(|product| {
+            Some(SearchHit {
+                mpn: product.manufacturer_part_number.filter(|m| !m.is_empty())?,
+                manufacturer: product.manufacturer.and_then(|m| m.name),
+                description: product.product_description,
+                distributor: "digikey",
+                distributor_pn: product.digi_key_part_number,
+                stock: product.quantity_available.map(i64::from),
+                datasheet_url: product.data_sheet_url.filter(|u| !u.is_empty()),
+            })
+        })
+        .collect())
+}
+
+/// The category a keyword search mostly lands in, with the parametric
+/// filters DigiKey offers there.
+pub(crate) struct CategoryParameters {
+    pub id: i64,
+    pub name: String,
+    pub parameters: Vec<ParameterOptions>,
+}
+
+/// One parametric filter of a category and its values as (ValueId, name).
+pub(crate) struct ParameterOptions {
+    pub id: i64,
+    pub name: String,
+    pub values: Vec<(String, String)>,
+}
+
+/// Find the top category for `keyword` and its parametric filters (two
+/// discovery searches), using credentials from the environment. `None` when
+/// the keyword matches nothing.
+pub(crate) fn category_parameters(keyword: &str) -> Result<Option<CategoryParameters>, Error> {
+    let (client_id, access_token) = env_session()?;
+    let discovery = search_by_keyword(&client_id, &access_token, KeywordSearch::new(keyword, 1))?;
+    let top = discovery
+        .filter_options
+        .iter()
+        .flat_map(|f| &f.top_categories)
+        .filter_map(|tc| tc.category.as_ref())
+        .filter(|c| c.id.is_some())
+        .max_by_key(|c| c.product_count.unwrap_or(0));
+    let Some(category) = top else {
+        return Ok(None);
+    };
+    let id = category.id.unwrap_or_default();
+    let name = category.name.clone().unwrap_or_else(|| id.to_string());
+
+    // Without a category filter the parametric filters span every category hit
+    let request = FilterOptionsRequest {
+        manufacturer_filter: None,
+        category_filter: Some(vec![FilterId { id: id.to_string() }]),
+        minimum_quantity_available: None,
+        parameter_filter_request: None,
+        search_options: None,
+    };
+    let discovery = search_by_keyword(
+        &client_id,
+        &access_token,
+        KeywordSearch {
+            filters: Some(request),
+            ..KeywordSearch::new(keyword, 1)
+        },
+    )?;
+    let parameters = discovery
+        .filter_options
+        .into_iter()
+        .flat_map(|f| f.parametric_filters)
+        .filter_map(|pf| {
+            Some(ParameterOptions {
+                id: pf.parameter_id?,
+                name: pf.parameter_name?,
+                values: pf
+                    .filter_values
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|v| Some((v.value_id?, v.value_name?)))
+                    .collect(),
+            })
+        })
+        .collect();
+    Ok(Some(CategoryParameters { id, name, parameters }))
+}
+
+/// Keyword search within a category with parametric filters given as
+/// (ParameterId, ValueIds), using credentials from the environment. Returns
+/// the listings and the total match count.
+pub(crate) fn parametric_search(
+    keyword: &str,
+    category_id: i64,
+    filters: Vec<(i64, Vec<String>)>,
+    in_stock: bool,
+    limit: usize,
+) -> Result<(Vec<TableRow>, usize), Error> {
+    let (client_id, access_token) = env_session()?;
+    let category = FilterId { id: category_id.to_string() };
+    let parameter_filter_request = (!filters.is_empty()).then(|| ParameterFilterRequest {
+        category_filter: FilterId { id: category.id.clone() },
+        parameter_filters: filters
+            .into_iter()
+            .map(|(parameter_id, values)| ParametricFilter {
+                parameter_id,
+                filter_values: values.into_iter().map(|id| FilterId { id }).collect(),
+            })
+            .collect(),
+    });
+    let request = FilterOptionsRequest {
+        manufacturer_filter: None,
+        category_filter: Some(vec![category]),
+        minimum_quantity_available: None,
+        parameter_filter_request,
+        search_options: build_search_options(in_stock, false),
+    };
+    let response = search_by_keyword(
+        &client_id,
+        &access_token,
+        KeywordSearch {
+            filters: Some(request),
+            ..KeywordSearch::new(keyword, limit)
+        },
+    )?;
+    let rows = response
+        .products
+        .iter()
+        .map(|product| TableRow {
+            info: stock_info(product, keyword),
+            description: product.product_description.clone(),
+        })
+        .collect();
+    Ok((rows, response.products_count.max(0) as usize))
+}
+
+/// Resolve the primary product photo URL for a part using credentials from the environment.
+pub(crate) fn find_photo_url(part_number: &str) -> Result<Option<String>, Error> {
+    let (client_id, access_token) = env_session()?;
+    let product = get_part_by_number(&client_id, &access_token, part_number, false)?;
+    Ok(product.primary_photo.filter(|u| !u.is_empty()))
+}
+
+/// Look up normalized stock and pricing for a part using credentials from the environment.
+pub(crate) fn lookup_stock(part_number: &str) -> Result<StockInfo, Error> {
+    let (client_id, access_token) = env_session()?;
+    fetch_stock_info(&client_id, &access_token, part_number, false)
+}
+
+/// List DigiKey substitutes for a part using credentials from the environment.
+pub(crate) fn find_substitutes(part_number: &str) -> Result<Vec<Substitute>, Error> {
+    let (client_id, access_token) = env_session()?;
+    let encoded_part = urlencoding::encode(part_number);
+    let url = format!("{}/products/v4/search/{}/substitutions", api_base(false), encoded_part);
+
+    let response = crate::offline::cached_response("digikey", &format!("substitutions:{}", part_number), || {
+        let call = crate::audit::start("digikey", "GET", &url, Some(part_number));
+        let request = ureq::get(&url)
+            .set("X-DIGIKEY-Client-Id", &client_id)
+            .set("Authorization", &format!("Bearer {}", access_token))
+            .set("Accept", "application/json");
+        let body = call.finish(request.call()).map_err(|e| match *e {
+            ureq::Error::Status(404, _) => Error::not_found(format!("Part not found: {}", part_number)),
+            e => Error::from_ureq("API request failed", e),
+        })?;
+        serde_json::from_slice(&body)
+            .map_err(|e| Error::network(format!("Failed to parse API response: {}", e)))
+            .inspect(|value| record_fixture(&format!("substitutions-{}", part_number), value))
+    })?;
+    let response: SubstitutionsResponse =
+        serde_json::from_value(response).map_err(|e| format!("Failed to parse API response: {}", e))?;
+
+    Ok(response
+        .product_substitutes
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|s| {
+            Some(Substitute {
+                mpn: s.manufacturer_product_number.filter(|m| !m.is_empty())?,
+                manufacturer: s.manufacturer.and_then(|m| m.name),
+                substitute_type: s.substitute_type,
+                description: s.description,
+                stock: s.quantity_available,
+            })
+        })
+        .collect())
+}
+
+/// Fetch the parametric attributes of a part using credentials from the environment.
+pub(crate) fn lookup_parameters(part_number: &str) -> Result<PartParameters, Error> {
+    let (client_id, access_token) = env_session()?;
+    let product = get_part_by_number(&client_id, &access_token, part_number, false)?;
+
+    Ok(PartParameters {
+        parameters: product_parameters(&product),
+        manufacturer: product.manufacturer.and_then(|m| m.name),
+        description: product.detailed_description.or(product.product_description),
+    })
+}
+
+fn product_parameters(product: &Product) -> Vec<(String, String)> {
+    product
+        .parameters
+        .iter()
+        .flatten()
+        .filter_map(|p| Some((p.parameter.clone()?, p.value.clone()?)))
+        .collect()
+}
+
+fn fetch_stock_info(
+    client_id: &str,
+    access_token: &str,
+    part_number: &str,
+    sandbox: bool,
+) -> Result<StockInfo, Error> {
+    let product = get_part_by_number(client_id, access_token, part_number, sandbox)?;
+    Ok(stock_info(&product, part_number))
+}
+
+/// Normalized stock and pricing of a product; `part_number` is the fallback MPN.
+fn stock_info(product: &Product, part_number: &str) -> StockInfo {
+    let mpn = product
+        .manufacturer_part_number
+        .clone()
+        .or_else(|| product.digi_key_part_number.clone())
+        .unwrap_or_else(|| part_number.to_string());
+
+    let price_breaks: Vec<StockPriceBreak> = product
+        .standard_pricing
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|pb| {
+            Some(StockPriceBreak {
+                quantity: pb.break_quantity?,
+                unit_price: pb.unit_price?,
+            })
+        })
+        .collect();
+
+    StockInfo {
+        mpn,
+        manufacturer: product.manufacturer.as_ref().and_then(|m| m.name.clone()),
+        distributor: "digikey",
+        distributor_pn: product.digi_key_part_number.clone(),
+        lifecycle_status: product.part_status.clone(),
+        stock: product.quantity_available.map(|q| q as i64),
+        lead_time: product.manufacturer_lead_weeks.as_ref().map(|weeks| format!("{} weeks", weeks)),
+        moq: product.minimum_order_quantity,
+        order_multiple: None,
+        currency: "USD".to_string(),
+        price_breaks,
+        suggested_replacement: None,
+        jlcpcb_category: None,
+        datasheet_url: product.data_sheet_url.clone().filter(|u| !u.is_empty()),
+    }
+    .into_target_currency()
+}
+
+fn map_sort_field(sort: &str) -> Result<SortOptions, Error> {
     let (field, order) = match sort {
         "price" => ("Price", "Ascending"),
         "stock" => ("QuantityAvailable", "Descending"),
@@ -497,18 +1095,22 @@ fn map_sort_field(sort: &str) -> Result<SortOptions, String> {
             return Err(format!(
                 "Unknown sort field: {}. Options: price, stock, mpn, manufacturer",
                 sort
-            ))
+            )
+            .into())
         }
     };
     Ok(SortOptions { field: field.to_string(), sort_order: order.to_string() })
 }
 
-fn build_search_options(in_stock: bool) -> Option<Vec<String>> {
+fn build_search_options(in_stock: bool, exact: bool) -> Option<Vec<String>> {
+    let mut options = Vec::new();
     if in_stock {
-        Some(vec!["InStock".to_string()])
-    } else {
-        None
+        options.push("InStock".to_string());
+    }
+    if exact {
+        options.push("ManufacturerPartSearch".to_string());
     }
+    Some(options).filter(|o| !o.is_empty())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -517,32 +1119,46 @@ fn cmd_search(
     client_id: Option<&str>,
     client_secret: Option<&str>,
     limit: usize,
+    page: Option<usize>,
+    offset: Option<usize>,
     json_output: bool,
     sandbox: bool,
     category: Option<String>,
     params: Vec<String>,
     manufacturer: Option<String>,
     in_stock: bool,
+    exact: bool,
     sort: Option<String>,
     category_id: Option<i64>,
     manufacturer_ids: Vec<i64>,
     param_ids: Vec<String>,
     show_filters: bool,
-) -> Result<(), String> {
+    table: Option<TableFormat>,
+) -> Result<(), Error> {
     let (client_id, client_secret) = get_credentials(client_id, client_secret)?;
     let access_token = get_access_token(&client_id, &client_secret, sandbox)?;
 
     let sort_options = sort.as_deref().map(map_sort_field).transpose()?;
 
+    // Page takes precedence over offset
+    let offset = match page {
+        Some(0) => return Err("Page number must be 1 or greater".to_string().into()),
+        Some(p) => (p - 1) * limit,
+        None => offset.unwrap_or(0),
+    };
+
     let has_name_filters = category.is_some() || !params.is_empty() || manufacturer.is_some();
     let has_id_filters =
         category_id.is_some() || !manufacturer_ids.is_empty() || !param_ids.is_empty();
 
-    let response = if has_name_filters && !has_id_filters {
+    let filter_request = if has_name_filters && !has_id_filters {
         // TWO-STEP: Discovery search to resolve names to IDs, then filtered search.
 
-        let discovery =
-            search_by_keyword(&client_id, &access_token, query, 1, sandbox, None, None)?;
+        let search = KeywordSearch {
+            sandbox,
+            ..KeywordSearch::new(query, 1)
+        };
+        let discovery = search_by_keyword(&client_id, &access_token, search)?;
         let filter_opts = discovery
             .filter_options
             .ok_or("API did not return filter options for discovery search")?;
@@ -567,11 +1183,11 @@ fn cmd_search(
                         .iter()
                         .filter_map(|tc| tc.category.as_ref().and_then(|c| c.name.clone()))
                         .collect();
-                    return Err(format!(
+                    return Err(Error::not_found(format!(
                         "Category '{}' not found. Available: {}",
                         cat_name,
                         available.join(", ")
-                    ));
+                    )));
                 }
             }
         } else {
@@ -600,11 +1216,11 @@ fn cmd_search(
                         .filter_map(|m| m.value.clone())
                         .take(20)
                         .collect();
-                    return Err(format!(
+                    return Err(Error::not_found(format!(
                         "Manufacturer '{}' not found. Top matches: {}",
                         mfr_name,
                         available.join(", ")
-                    ));
+                    )));
                 }
             }
         } else {
@@ -638,11 +1254,11 @@ fn cmd_search(
                         .iter()
                         .filter_map(|pf| pf.parameter_name.clone())
                         .collect();
-                    return Err(format!(
+                    return Err(Error::not_found(format!(
                         "Parameter '{}' not found. Available: {}",
                         name_part.trim(),
                         available.join(", ")
-                    ));
+                    )));
                 }
             };
 
@@ -668,12 +1284,12 @@ fn cmd_search(
                         .iter()
                         .filter_map(|fv| fv.value_name.clone())
                         .collect();
-                    return Err(format!(
+                    return Err(Error::not_found(format!(
                         "Value '{}' not found for parameter '{}'. Available: {}",
                         value_part.trim(),
                         name_part.trim(),
                         available.join(", ")
-                    ));
+                    )));
                 }
             };
 
@@ -701,24 +1317,14 @@ fn cmd_search(
             None
         };
 
-        let filter_request = FilterOptionsRequest {
+        Some(FilterOptionsRequest {
             manufacturer_filter: resolved_mfr_ids,
             category_filter: resolved_category_id
                 .map(|id| vec![FilterId { id: id.to_string() }]),
             minimum_quantity_available: None,
             parameter_filter_request,
-            search_options: build_search_options(in_stock),
-        };
-
-        search_by_keyword(
-            &client_id,
-            &access_token,
-            query,
-            limit,
-            sandbox,
-            Some(filter_request),
-            sort_options,
-        )?
+            search_options: build_search_options(in_stock, exact),
+        })
     } else if has_id_filters {
         // DIRECT: Use provided IDs without discovery.
 
@@ -761,148 +1367,174 @@ fn cmd_search(
             None
         };
 
-        let filter_request = FilterOptionsRequest {
+        Some(FilterOptionsRequest {
             manufacturer_filter,
             category_filter,
             minimum_quantity_available: None,
             parameter_filter_request,
-            search_options: build_search_options(in_stock),
-        };
-
-        search_by_keyword(
-            &client_id,
-            &access_token,
-            query,
-            limit,
-            sandbox,
-            Some(filter_request),
-            sort_options,
-        )?
+            search_options: build_search_options(in_stock, exact),
+        })
+    } else if in_stock || exact {
+        // SIMPLE: Keyword search, in-stock parts or part number matches only.
+        Some(FilterOptionsRequest {
+            manufacturer_filter: None,
+            category_filter: None,
+            minimum_quantity_available: None,
+            parameter_filter_request: None,
+            search_options: build_search_options(in_stock, exact),
+        })
     } else {
-        // SIMPLE: No filters, keyword search only.
-        let filter_request = if in_stock {
-            Some(FilterOptionsRequest {
-                manufacturer_filter: None,
-                category_filter: None,
-                minimum_quantity_available: None,
-                parameter_filter_request: None,
-                search_options: build_search_options(in_stock),
-            })
-        } else {
-            None
-        };
+        None
+    };
 
-        search_by_keyword(
-            &client_id,
-            &access_token,
-            query,
+    let filters = AppliedFilters::new(filter_request.as_ref(), in_stock, exact, sort);
+    let mut response = search_by_keyword(
+        &client_id,
+        &access_token,
+        KeywordSearch {
+            keyword: query,
             limit,
+            offset,
             sandbox,
-            filter_request,
-            sort_options,
-        )?
-    };
+            filters: filter_request,
+            sort: sort_options,
+        },
+    )?;
 
     if show_filters {
-        let json = serde_json::to_string_pretty(&response.filter_options)
+        let json = crate::select::to_string_pretty(&response.filter_options)
             .map_err(|e| format!("Failed to serialize filter options: {}", e))?;
         println!("{}", json);
         return Ok(());
     }
 
-    let products = &response.products;
+    // Pages are the API's, counted before anything is dropped here
+    let page_len = response.products.len();
+    if exact {
+        keep_exact_matches(&mut response, query);
+    }
+    if !sandbox {
+        crate::history::record(response.products.iter().take(limit).map(|product| stock_info(product, query)));
+    }
+
+    if let Some(table) = table {
+        let rows: Vec<TableRow> = response
+            .products
+            .iter()
+            .take(limit)
+            .map(|product| TableRow {
+                info: stock_info(product, query),
+                description: product.product_description.clone(),
+            })
+            .collect();
+        table.print(&rows);
+        return Ok(());
+    }
+
+    let total = usize::try_from(response.products_count).unwrap_or(0);
+    let returned = response.products.len();
+    let next_offset = Some(offset + page_len).filter(|&next| page_len > 0 && next < total);
 
     if json_output {
-        let json = serde_json::to_string_pretty(products)
+        let envelope = SearchEnvelope {
+            query,
+            products_count: total,
+            exact_manufacturer_products_count: usize::try_from(response.exact_manufacturer_products_count)
+                .unwrap_or(0),
+            offset,
+            limit,
+            page: offset / limit.max(1) + 1,
+            returned,
+            next_offset,
+            filters,
+            products: &response.products,
+        };
+        let json = crate::select::to_string_pretty(&envelope)
             .map_err(|e| format!("Failed to serialize results: {}", e))?;
         println!("{}", json);
     } else {
+        let products = &response.products;
         if products.is_empty() {
             println!("No parts found for query: {}", query);
             return Ok(());
         }
 
-        println!("Found {} part(s):\n", products.len());
+        if exact {
+            println!("{} exact match(es) for {}:\n", returned, query);
+        } else {
+            println!("Showing {}-{} of {} part(s):\n", offset + 1, offset + returned, total.max(offset + returned));
+        }
 
         for (i, product) in products.iter().take(limit).enumerate() {
-            println!("{}. {}", i + 1, format_product_summary(product));
+            println!("{}. {}", offset + i + 1, format_product_summary(product));
             println!();
         }
+        if let Some(next) = next_offset {
+            println!("More results: --offset {}", next);
+        }
     }
 
     Ok(())
 }
 
+/// Keep only products whose manufacturer part number is `query` (ignoring
+/// case), including DigiKey's separate `ExactMatches` list.
+fn keep_exact_matches(response: &mut SearchResponse, query: &str) {
+    let query = query.trim();
+    let is_exact = |product: &Product| {
+        product.manufacturer_part_number.as_deref().is_some_and(|mpn| mpn.trim().eq_ignore_ascii_case(query))
+    };
+    let mut products: Vec<Product> = Vec::new();
+    let candidates = std::mem::take(&mut response.exact_matches).into_iter().chain(std::mem::take(&mut response.products));
+    for product in candidates.filter(is_exact) {
+        if !products.iter().any(|p| p.digi_key_part_number == product.digi_key_part_number) {
+            products.push(product);
+        }
+    }
+    response.products = products;
+}
+
 fn cmd_download(
     part_number: &str,
     client_id: Option<&str>,
     client_secret: Option<&str>,
     output: Option<PathBuf>,
     dir: Option<PathBuf>,
+    url_only: bool,
     sandbox: bool,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     let (client_id, client_secret) = get_credentials(client_id, client_secret)?;
     let access_token = get_access_token(&client_id, &client_secret, sandbox)?;
 
-    // Get exact part details using the ProductDetails endpoint
-    let product = get_part_by_number(&client_id, &access_token, part_number, sandbox)?;
+    let product = resolve_product(&client_id, &access_token, part_number, sandbox)?;
     let datasheet_url = product
         .data_sheet_url
         .as_ref()
-        .ok_or_else(|| format!("No datasheet available for part: {}", part_number))?;
+        .ok_or_else(|| Error::not_found(format!("No datasheet available for part: {}", part_number)))?;
 
     if datasheet_url.is_empty() {
-        return Err(format!("No datasheet available for part: {}", part_number));
+        return Err(Error::not_found(format!("No datasheet available for part: {}", part_number)));
+    }
+
+    if url_only {
+        println!("{}", download::normalize_url(datasheet_url));
+        return Ok(());
     }
 
     // Determine output path
     let output_path = if let Some(path) = output {
         path
     } else {
-        let filename = format!(
-            "{}.pdf",
-            product.manufacturer_part_number
-                .as_ref()
-                .unwrap_or(&part_number.to_string())
-                .replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_")
-        );
-        if let Some(dir) = dir {
-            dir.join(filename)
-        } else {
-            PathBuf::from(filename)
-        }
+        let mpn = product.manufacturer_part_number.as_deref().unwrap_or(part_number);
+        let filename = format!("{}.pdf", crate::paths::sanitize_filename(mpn));
+        dir.unwrap_or_else(crate::paths::datasheets_dir).join(filename)
     };
 
     println!("Downloading datasheet for {}...", part_number);
     println!("  URL: {}", datasheet_url);
     println!("  Output: {}", output_path.display());
 
-    // Download the datasheet with proper headers (distributor CDNs require User-Agent)
-    let response = ureq::get(datasheet_url)
-        .set("User-Agent", "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
-        .set("Accept", "application/pdf,*/*")
-        .call()
-        .map_err(|e| format!("Failed to download datasheet: {}", e))?;
-
-    // Verify we got a PDF, not an HTML error/redirect page
-    let content_type = response.content_type().to_string();
-
-    let mut file =
-        File::create(&output_path).map_err(|e| format!("Failed to create output file: {}", e))?;
-
-    let mut reader = response.into_reader();
-    let bytes_written = std::io::copy(&mut reader, &mut file)
-        .map_err(|e| format!("Failed to write datasheet: {}", e))?;
-
-    // Check if we got HTML instead of PDF (bot protection / redirect)
-    if content_type.contains("text/html") || bytes_written < 1024 {
-        let _ = std::fs::remove_file(&output_path);
-        return Err(format!(
-            "Download returned HTML instead of PDF (content-type: {}). \
-             Distributor may be blocking automated downloads for this URL.",
-            content_type
-        ));
-    }
+    let bytes_written = download::download_pdf(datasheet_url, &output_path, &DownloadOptions::default())?;
 
     println!("Datasheet downloaded successfully! ({:.1} KB)", bytes_written as f64 / 1024.0);
 
@@ -915,15 +1547,16 @@ fn cmd_part(
     client_secret: Option<&str>,
     json_output: bool,
     sandbox: bool,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     let (client_id, client_secret) = get_credentials(client_id, client_secret)?;
     let access_token = get_access_token(&client_id, &client_secret, sandbox)?;
 
-    // Get exact part details using the ProductDetails endpoint
-    let product = get_part_by_number(&client_id, &access_token, part_number, sandbox)?;
+    let product = resolve_product(&client_id, &access_token, part_number, sandbox)?;
 
     if json_output {
-        let json = serde_json::to_string_pretty(&product)
+        let json = serde_json::to_value(&product)
+            .map(|value| crate::parameters::with_canonical(value, &product_parameters(&product)))
+            .and_then(|value| crate::select::to_string_pretty(&value))
             .map_err(|e| format!("Failed to serialize part: {}", e))?;
         println!("{}", json);
     } else {
@@ -939,47 +1572,16 @@ fn cmd_stock(
     client_secret: Option<&str>,
     json_output: bool,
     sandbox: bool,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     let (client_id, client_secret) = get_credentials(client_id, client_secret)?;
     let access_token = get_access_token(&client_id, &client_secret, sandbox)?;
-
-    let product = get_part_by_number(&client_id, &access_token, part_number, sandbox)?;
-
-    let mpn = product
-        .manufacturer_part_number
-        .clone()
-        .or_else(|| product.digi_key_part_number.clone())
-        .unwrap_or_else(|| part_number.to_string());
-
-    let price_breaks: Vec<StockPriceBreak> = product
-        .standard_pricing
-        .as_deref()
-        .unwrap_or_default()
-        .iter()
-        .filter_map(|pb| {
-            Some(StockPriceBreak {
-                quantity: pb.break_quantity?,
-                unit_price: pb.unit_price?,
-            })
-        })
-        .collect();
-
-    let info = StockInfo {
-        mpn,
-        manufacturer: product.manufacturer.as_ref().and_then(|m| m.name.clone()),
-        distributor: "digikey",
-        distributor_pn: product.digi_key_part_number.clone(),
-        lifecycle_status: product.part_status.clone(),
-        stock: product.quantity_available.map(|q| q as i64),
-        lead_time: None,
-        moq: product.minimum_order_quantity,
-        order_multiple: None,
-        currency: "USD".to_string(),
-        price_breaks,
-    };
+    let info = fetch_stock_info(&client_id, &access_token, part_number, sandbox)?;
+    if !sandbox {
+        crate::history::record([info.clone()]);
+    }
 
     if json_output {
-        let json = serde_json::to_string_pretty(&info)
+        let json = crate::select::to_string_pretty(&info)
             .map_err(|e| format!("Failed to serialize stock info: {}", e))?;
         println!("{}", json);
     } else {
@@ -1020,9 +1622,9 @@ fn cmd_stock(
             for pb in &info.price_breaks {
                 let qty_label = format!("{}+", pb.quantity);
                 println!(
-                    "    {:>width$} : ${:.2}",
+                    "    {:>width$} : {}",
                     qty_label,
-                    pb.unit_price,
+                    currency::format_price(pb.unit_price, &info.currency, 2),
                     width = max_qty_width
                 );
             }
@@ -1044,39 +1646,104 @@ fn format_number(n: i64) -> String {
     result.chars().rev().collect()
 }
 
-fn search_by_keyword(
-    client_id: &str,
-    access_token: &str,
-    keyword: &str,
+/// One page of keyword search results, optionally filtered and sorted.
+struct KeywordSearch<'a> {
+    keyword: &'a str,
     limit: usize,
+    offset: usize,
     sandbox: bool,
-    filter_options_request: Option<FilterOptionsRequest>,
-    sort_options: Option<SortOptions>,
-) -> Result<SearchResponse, String> {
-    let base_url = if sandbox { DIGIKEY_API_BASE_SANDBOX } else { DIGIKEY_API_BASE };
+    filters: Option<FilterOptionsRequest>,
+    sort: Option<SortOptions>,
+}
+
+impl<'a> KeywordSearch<'a> {
+    /// The first `limit` results for `keyword` from the production API, unfiltered and unsorted.
+    fn new(keyword: &'a str, limit: usize) -> Self {
+        Self {
+            keyword,
+            limit,
+            offset: 0,
+            sandbox: false,
+            filters: None,
+            sort: None,
+        }
+    }
+}
+
+fn search_by_keyword(client_id: &str, access_token: &str, search: KeywordSearch) -> Result<SearchResponse, Error> {
+    let KeywordSearch {
+        keyword,
+        limit,
+        offset,
+        sandbox,
+        filters,
+        sort,
+    } = search;
+    let base_url = api_base(sandbox);
     let url = format!("{}/products/v4/search/keyword", base_url);
 
     let request = KeywordSearchRequest {
         keywords: keyword.to_string(),
         limit: Some(limit),
-        offset: Some(0),
-        filter_options_request,
-        sort_options,
+        offset: Some(offset),
+        filter_options_request: filters,
+        sort_options: sort,
     };
 
-    let response: SearchResponse = ureq::post(&url)
-        .set("X-DIGIKEY-Client-Id", client_id)
-        .set("Authorization", &format!("Bearer {}", access_token))
-        .set("Content-Type", "application/json")
-        .set("Accept", "application/json")
-        .send_json(&request)
-        .map_err(|e| format!("API request failed: {}", e))?
-        .into_json()
-        .map_err(|e| format!("Failed to parse API response: {}", e))?;
-
+    let key = format!("{}:keyword:{}", base_url, serde_json::to_string(&request).unwrap_or_default());
+    let response = crate::offline::cached_response("digikey", &key, || {
+        let call = crate::audit::start("digikey", "POST", &url, Some(keyword));
+        let sent = ureq::post(&url)
+            .set("X-DIGIKEY-Client-Id", client_id)
+            .set("Authorization", &format!("Bearer {}", access_token))
+            .set("Content-Type", "application/json")
+            .set("Accept", "application/json")
+            .send_json(&request);
+        let body = call.finish(sent).map_err(|e| Error::from_ureq("API request failed", *e))?;
+        serde_json::from_slice(&body)
+            .map_err(|e| Error::network(format!("Failed to parse API response: {}", e)))
+            .inspect(|value| record_fixture(&format!("keyword-{}-{}-{}", keyword, limit, offset), value))
+    })?;
+
+    let mut response: SearchResponse = serde_json::from_value(response)
+        .map_err(|e| Error::from(format!("Failed to parse API response: {}", e)))?;
+    response.products = response.products.into_iter().map(Product::normalized).collect();
+    response.exact_matches = response.exact_matches.into_iter().map(Product::normalized).collect();
     Ok(response)
 }
 
+/// The product meant by `part_number`: an exact DigiKey or manufacturer part
+/// number match, or else one of the keyword search hits, chosen by the user.
+fn resolve_product(
+    client_id: &str,
+    access_token: &str,
+    part_number: &str,
+    sandbox: bool,
+) -> Result<Product, Error> {
+    match get_part_by_number(client_id, access_token, part_number, sandbox) {
+        Err(e) if e.kind == ErrorKind::NotFound => {}
+        result => return result,
+    }
+    let search = KeywordSearch {
+        sandbox,
+        ..KeywordSearch::new(part_number, 10)
+    };
+    let mut response = search_by_keyword(client_id, access_token, search)?;
+    if response.products.is_empty() {
+        return Err(Error::not_found(format!("Part not found: {}", part_number)));
+    }
+    let rows: Vec<TableRow> = response
+        .products
+        .iter()
+        .map(|product| TableRow {
+            info: stock_info(product, part_number),
+            description: product.product_description.clone(),
+        })
+        .collect();
+    let index = crate::stock::pick(&format!("DigiKey matches for {}", part_number), &rows)?;
+    Ok(response.products.swap_remove(index))
+}
+
 /// Get exact part details by part number using the ProductDetails endpoint.
 /// This endpoint returns exact matches for DigiKey or manufacturer part numbers.
 fn get_part_by_number(
@@ -1084,29 +1751,36 @@ fn get_part_by_number(
     access_token: &str,
     part_number: &str,
     sandbox: bool,
-) -> Result<Product, String> {
-    let base_url = if sandbox { DIGIKEY_API_BASE_SANDBOX } else { DIGIKEY_API_BASE };
+) -> Result<Product, Error> {
+    let base_url = api_base(sandbox);
     // URL encode the part number to handle special characters
     let encoded_part = urlencoding::encode(part_number);
     let url = format!("{}/products/v4/search/{}/productdetails", base_url, encoded_part);
 
-    let product: Product = ureq::get(&url)
-        .set("X-DIGIKEY-Client-Id", client_id)
-        .set("Authorization", &format!("Bearer {}", access_token))
-        .set("Accept", "application/json")
-        .call()
-        .map_err(|e| {
-            match e {
+    let product = crate::offline::cached_response("digikey", &format!("{}:productdetails:{}", base_url, part_number), || {
+        let call = crate::audit::start("digikey", "GET", &url, Some(part_number));
+        let request = ureq::get(&url)
+            .set("X-DIGIKEY-Client-Id", client_id)
+            .set("Authorization", &format!("Bearer {}", access_token))
+            .set("Accept", "application/json");
+        let body = call.finish(request.call()).map_err(|e| {
+            match *e {
                 ureq::Error::Status(404, _) => {
-                    format!("Part not found: {}", part_number)
+                    Error::not_found(format!("Part not found: {}", part_number))
                 }
-                _ => format!("API request failed: {}", e)
+                e => Error::from_ureq("API request failed", e)
             }
-        })?
-        .into_json()
-        .map_err(|e| format!("Failed to parse API response: {}", e))?;
-
-    Ok(product)
+        })?;
+        serde_json::from_slice(&body)
+            .map_err(|e| Error::network(format!("Failed to parse API response: {}", e)))
+            .inspect(|value| record_fixture(&format!("productdetails-{}", part_number), value))
+    })?;
+
+    // API v4 wraps the product in an envelope
+    let product = product.get("Product").cloned().unwrap_or(product);
+    serde_json::from_value(product)
+        .map(Product::normalized)
+        .map_err(|e| Error::from(format!("Failed to parse API response: {}", e)))
 }
 
 fn format_product_summary(product: &Product) -> String {
@@ -1137,7 +1811,7 @@ fn format_product_summary(product: &Product) -> String {
     if let Some(ref prices) = product.standard_pricing {
         if let Some(first) = prices.first() {
             if let (Some(qty), Some(price)) = (first.break_quantity, first.unit_price) {
-                lines.push(format!("   Price: ${:.4} (qty {}+)", price, qty));
+                lines.push(format!("   Price: {} (qty {}+)", currency::format_price(price, "USD", 4), qty));
             }
         }
     }
@@ -1179,6 +1853,11 @@ fn print_product_details(product: &Product) {
     if let Some(ref lead) = product.lead_status {
         println!("Lead Status: {}", lead);
     }
+    for (flag, label) in [(product.discontinued, "Discontinued"), (product.end_of_life, "End of Life"), (product.ncnr, "NCNR")] {
+        if flag == Some(true) {
+            println!("{}: yes", label);
+        }
+    }
 
     println!();
     println!("Availability");
@@ -1192,6 +1871,15 @@ fn print_product_details(product: &Product) {
     if let Some(min_qty) = product.minimum_order_quantity {
         println!("Minimum Order: {}", min_qty);
     }
+    if let Some(ref weeks) = product.manufacturer_lead_weeks {
+        println!("Manufacturer Lead Time: {} weeks", weeks);
+    }
+    if let Some(stocking) = product.normally_stocking {
+        println!("Normally Stocking: {}", if stocking { "yes" } else { "no" });
+    }
+    if let Some(ref date) = product.date_last_buy_chance {
+        println!("Last Time Buy: {}", date);
+    }
     if let Some(ref packaging) = product.packaging {
         if let Some(ref value) = packaging.value {
             println!("Packaging: {}", value);
@@ -1205,7 +1893,7 @@ fn print_product_details(product: &Product) {
             println!("-------");
             for pb in prices {
                 if let (Some(qty), Some(price)) = (pb.break_quantity, pb.unit_price) {
-                    println!("  {:>6}+ : ${:.4}", qty, price);
+                    println!("  {:>6}+ : {}", qty, currency::format_price(price, "USD", 4));
                 }
             }
         }
@@ -1216,13 +1904,77 @@ fn print_product_details(product: &Product) {
             println!();
             println!("Parameters");
             println!("----------");
-            for param in params.iter().take(10) {
+            for param in params {
                 if let (Some(name), Some(value)) = (&param.parameter, &param.value) {
                     println!("  {}: {}", name, value);
                 }
             }
-            if params.len() > 10 {
-                println!("  ... and {} more parameters", params.len() - 10);
+        }
+    }
+
+    let classifications = product.classifications.as_ref().map(|c| {
+        [
+            ("REACH Status", &c.reach_status),
+            ("Moisture Sensitivity Level", &c.moisture_sensitivity_level),
+            ("ECCN", &c.export_control_class_number),
+            ("HTSUS Code", &c.htsus_code),
+        ]
+    });
+    let mut category = Vec::new();
+    let mut next = product.category.as_ref();
+    while let Some(c) = next {
+        category.extend(c.name.clone());
+        next = c.child_categories.first();
+    }
+    if classifications.is_some() || !category.is_empty() || product.series.is_some() || !product.other_names.is_empty()
+    {
+        println!();
+        println!("Classification");
+        println!("--------------");
+        if !category.is_empty() {
+            println!("Category: {}", category.join(" > "));
+        }
+        if let Some(name) = product.series.as_ref().and_then(|s| s.name.as_deref()) {
+            println!("Series: {}", name);
+        }
+        if let Some(name) = product.base_product_number.as_ref().and_then(|b| b.name.as_deref()) {
+            println!("Base Product Number: {}", name);
+        }
+        for (label, value) in classifications.iter().flatten() {
+            if let Some(value) = value {
+                println!("{}: {}", label, value);
+            }
+        }
+        if !product.other_names.is_empty() {
+            println!("Other Names: {}", product.other_names.join(", "));
+        }
+    }
+
+    if !product.product_variations.is_empty() {
+        println!();
+        println!("Packaging Options");
+        println!("-----------------");
+        for variation in &product.product_variations {
+            println!(
+                "  {} ({})",
+                variation.digi_key_product_number.as_deref().unwrap_or("?"),
+                variation.package_type.as_ref().and_then(|p| p.name.as_deref()).unwrap_or("unknown packaging"),
+            );
+            let mut facts = Vec::new();
+            if let Some(qty) = variation.quantity_available_for_package_type {
+                facts.push(format!("stock {}", qty));
+            }
+            if let Some(moq) = variation.minimum_order_quantity {
+                facts.push(format!("min {}", moq));
+            }
+            if let Some(pack) = variation.standard_package.filter(|&pack| pack > 0) {
+                facts.push(format!("std pack {}", pack));
+            }
+            facts.extend(variation.standard_pricing.iter().filter_map(|pb| {
+                Some(format!("{}+ {}", pb.break_quantity?, currency::format_price(pb.unit_price?, "USD", 4)))
+            }));
+            if !facts.is_empty() {
+                println!("      {}", facts.join(", "));
             }
         }
     }
@@ -1242,4 +1994,19 @@ fn print_product_details(product: &Product) {
     } else {
         println!("Datasheet: Not available");
     }
+    if let Some(url) = product.primary_photo.as_deref().filter(|u| !u.is_empty()) {
+        println!("Photo: {}", url);
+    }
+    if let Some(url) = product.primary_video_url.as_deref().filter(|u| !u.is_empty()) {
+        println!("Video: {}", url);
+    }
+    for link in &product.media_links {
+        if let Some(ref url) = link.url {
+            let kind = link.media_type.as_deref().unwrap_or("Media");
+            match link.title.as_deref() {
+                Some(title) => println!("{}: {} ({})", kind, url, title),
+                None => println!("{}: {}", kind, url),
+            }
+        }
+    }
 }
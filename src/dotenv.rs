@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Project-local `.env` files and `${VAR}` expansion.
+//!
+//! An env file is only read when asked for, with `--env-file` /
+//! `DATASHEET_ENV_FILE` or `env_file` in the config file. Its variables are
+//! set before arguments are parsed, so they work for every flag that reads an
+//! environment variable, and for credential references in profiles. Variables
+//! already set in the environment win over the file.
+//!
+//! ```text
+//! # hardware/.env
+//! export MOUSER_API_KEY=abc123
+//! DATASHEET_DOWNLOAD_DIR="${HOME}/projects/widget/datasheets"
+//! DATASHEET_MODEL='gemini-2.5-flash'   # single quotes: no expansion
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+/// Where the env file named `name` is: a bare file name is looked for in the
+/// current directory and its parents, up to the root of the git repository;
+/// any other path is taken as given.
+pub fn find(name: &Path) -> Option<PathBuf> {
+    if name.is_absolute() || name.components().count() > 1 {
+        return name.is_file().then(|| name.to_path_buf());
+    }
+    let cwd = std::env::current_dir().ok()?;
+    for dir in cwd.ancestors() {
+        let candidate = dir.join(name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+/// Set the variables in the env file at `path` that are not set yet.
+///
+/// Must run before any threads are spawned (see `config::apply`).
+pub fn load(path: &Path) -> Result<()> {
+    let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let vars = parse(&text, &|name| std::env::var(name).ok()).with_context(|| format!("parsing {}", path.display()))?;
+    for (name, value) in vars {
+        if std::env::var_os(&name).is_none() {
+            // SAFETY: called before any other thread exists (see above).
+            unsafe { std::env::set_var(&name, value) };
+        }
+    }
+    Ok(())
+}
+
+/// `NAME=value` lines of an env file, in order. Values may be unquoted (a
+/// ` #` starts a comment), 'single-quoted' (taken literally), or
+/// "double-quoted" (with `\n`, `\"`, and `\\` escapes). `${VAR}` in unquoted
+/// and double-quoted values is expanded from `lookup`, then from earlier lines.
+pub fn parse(text: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<Vec<(String, String)>> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    let mut earlier: HashMap<String, String> = HashMap::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, raw) = line.split_once('=').ok_or_else(|| anyhow!("line {}: expected NAME=value", n + 1))?;
+        let name = name.trim();
+        if !is_name(name) {
+            bail!("line {}: '{}' is not a variable name", n + 1, name);
+        }
+        let raw = raw.trim_start();
+        let lookup = |var: &str| lookup(var).or_else(|| earlier.get(var).cloned());
+        let value = if let Some(quoted) = raw.strip_prefix('\'') {
+            let end = quoted.find('\'').ok_or_else(|| anyhow!("line {}: missing closing '", n + 1))?;
+            quoted[..end].to_string()
+        } else if let Some(quoted) = raw.strip_prefix('"') {
+            let unescaped = unescape(quoted).ok_or_else(|| anyhow!("line {}: missing closing \"", n + 1))?;
+            expand(&unescaped, &lookup).with_context(|| format!("line {}", n + 1))?
+        } else {
+            let unquoted = raw.split(" #").next().unwrap_or_default().trim_end();
+            expand(unquoted, &lookup).with_context(|| format!("line {}", n + 1))?
+        };
+        earlier.insert(name.to_string(), value.clone());
+        vars.push((name.to_string(), value));
+    }
+    Ok(vars)
+}
+
+/// Replace `${VAR}` in `text` with the variable's value, or with `default` for
+/// `${VAR:-default}` when it is unset or empty. `$${` stands for a literal `${`.
+/// A variable that is unset and has no default is an error.
+pub fn expand(text: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(escaped) = after.strip_prefix("${") {
+            out.push_str("${");
+            rest = escaped;
+        } else if let Some(body) = after.strip_prefix('{') {
+            let end = body.find('}').ok_or_else(|| anyhow!("unterminated ${{ in \"{}\"", text))?;
+            let (name, default) = match body[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&body[..end], None),
+            };
+            if !is_name(name) {
+                bail!("'{}' is not a variable name in \"{}\"", name, text);
+            }
+            let value = match lookup(name) {
+                Some(value) if !(value.is_empty() && default.is_some()) => value,
+                _ => default.map(str::to_string).ok_or_else(|| anyhow!("${{{}}} is not set", name))?,
+            };
+            out.push_str(&value);
+            rest = &body[end + 1..];
+        } else {
+            out.push('$');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The text of a double-quoted value up to its closing quote, with escapes
+/// resolved; `None` if the quote is never closed.
+fn unescape(quoted: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(out),
+            '\\' => match chars.next()? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                other => out.push(other),
+            },
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let env = |name: &str| (name == "HOME").then(|| "/home/ee".to_string());
+        let text = r#"
+            # distributor keys
+            export MOUSER_API_KEY=abc123  # work account
+            PROJECT=${HOME}/widget
+            DATASHEET_DOWNLOAD_DIR="${PROJECT}/datasheets"
+            LITERAL='${HOME} # kept'
+            ESCAPED="say \"hi\"\n"
+            MODEL=${MODEL:-gemini-2.5-flash}
+            PRICE=$5 and $${NOT_EXPANDED}
+        "#;
+        let vars = parse(text, &env).unwrap();
+        let get = |name: &str| vars.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+        assert_eq!(get("MOUSER_API_KEY"), Some("abc123"));
+        assert_eq!(get("DATASHEET_DOWNLOAD_DIR"), Some("/home/ee/widget/datasheets"));
+        assert_eq!(get("LITERAL"), Some("${HOME} # kept"));
+        assert_eq!(get("ESCAPED"), Some("say \"hi\"\n"));
+        assert_eq!(get("MODEL"), Some("gemini-2.5-flash"));
+        assert_eq!(get("PRICE"), Some("$5 and ${NOT_EXPANDED}"));
+
+        assert!(parse("KEY=${MISSING}", &env).unwrap_err().to_string().contains("line 1"));
+        assert!(parse("not an assignment", &env).is_err());
+        assert!(expand("${UNCLOSED", &env).is_err());
+    }
+}
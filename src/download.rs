@@ -0,0 +1,481 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Shared datasheet download engine.
+//!
+//! Distributor and manufacturer CDNs are inconsistent: some need a browser
+//! User-Agent or Referer, some serve an HTML bot-check page that sets cookies
+//! before the PDF, and large files occasionally drop mid-transfer. All datasheet
+//! downloads go through [`download_pdf`], which:
+//!
+//! - follows redirects and keeps cookies across retries (one agent per download)
+//! - validates the result by content-type and `%PDF` magic bytes
+//! - writes to `<output>.part` and resumes with a `Range` request on retry, but
+//!   only for the same URL and, via `If-Range`, the same version of the file
+//! - optionally hands the URL to an external (e.g. headless browser) command
+//! - enforces the download policy (allow/deny lists, rate limits, robots.txt),
+//!   see `download_policy`
+
+use crate::progress::Throttle;
+use serde_json::json;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+pub(crate) const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+const ENV_VAR_USER_AGENT: &str = "DATASHEET_USER_AGENT";
+const ENV_VAR_BROWSER_CMD: &str = "DATASHEET_BROWSER_CMD";
+const DEFAULT_RETRIES: u32 = 3;
+const MAX_REDIRECTS: u32 = 10;
+
+/// Per-download settings.
+#[derive(Debug, Clone)]
+pub(crate) struct DownloadOptions {
+    pub user_agent: String,
+    pub referer: Option<String>,
+    /// Extra attempts after the first one for transient failures
+    pub retries: u32,
+    /// External command run when direct download fails; `{url}` and `{output}` are substituted
+    pub browser_command: Option<String>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            user_agent: std::env::var(ENV_VAR_USER_AGENT)
+                .ok()
+                .filter(|ua| !ua.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string()),
+            referer: None,
+            retries: DEFAULT_RETRIES,
+            browser_command: std::env::var(ENV_VAR_BROWSER_CMD)
+                .ok()
+                .filter(|cmd| !cmd.trim().is_empty()),
+        }
+    }
+}
+
+impl DownloadOptions {
+    pub fn with_referer(mut self, referer: &str) -> Self {
+        self.referer = Some(referer.to_string());
+        self
+    }
+}
+
+/// Why a single attempt failed, and whether retrying can help.
+enum AttemptError {
+    /// Network error or 5xx/429; retry and resume
+    Transient(String),
+    /// Server answered with something other than a PDF (HTML bot check, error page)
+    NotPdf(String),
+    /// 4xx or local I/O error; retrying will not help
+    Fatal(String),
+}
+
+/// Normalize distributor URLs (DigiKey frequently returns protocol-relative links).
+pub(crate) fn normalize_url(url: &str) -> String {
+    if url.starts_with("//") {
+        format!("https:{}", url)
+    } else {
+        url.to_string()
+    }
+}
+
+/// Called with `(bytes downloaded so far, total bytes if known)` as data arrives.
+pub(crate) type ProgressFn<'a> = &'a (dyn Fn(u64, Option<u64>) + Sync);
+
+/// Download a PDF to `output`, returning the number of bytes written.
+///
+/// The file only appears at `output` once it has been validated as a PDF; partial
+/// transfers are kept at `<output>.part` and resumed on the next attempt or run
+/// (see [`PartMeta`]).
+pub(crate) fn download_pdf(url: &str, output: &Path, options: &DownloadOptions) -> Result<u64, String> {
+    download_with_progress(url, output, options, &|_, _| {})
+}
+
+fn download_with_progress(
+    url: &str,
+    output: &Path,
+    options: &DownloadOptions,
+    progress: ProgressFn,
+) -> Result<u64, String> {
+    crate::offline::ensure_online("Downloading datasheets")?;
+    crate::progress::emit("download_started", json!({ "url": url, "path": output }));
+    let throttle = Mutex::new(Throttle::new("download", ("url", url.to_string()), None));
+    let report = |done: u64, total: Option<u64>| {
+        progress(done, total);
+        if let Ok(mut throttle) = throttle.lock() {
+            throttle.update(done, total);
+        }
+    };
+    let result = download_attempts(url, output, options, &report);
+    match &result {
+        Ok(bytes) => crate::progress::emit("download_done", json!({ "url": url, "path": output, "bytes": bytes })),
+        Err(e) => crate::progress::emit("download_failed", json!({ "url": url, "error": e })),
+    }
+    result
+}
+
+fn download_attempts(
+    url: &str,
+    output: &Path,
+    options: &DownloadOptions,
+    progress: ProgressFn,
+) -> Result<u64, String> {
+    let output = &crate::paths::long_path(output);
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("creating {}: {}", parent.display(), e))?;
+    }
+    let url = normalize_url(url);
+    let policy = crate::download_policy::current()?;
+    if let Some(policy) = policy {
+        policy.check(&url)?;
+    }
+    let part_path = part_path(output);
+
+    let agent = ureq::AgentBuilder::new()
+        .redirects(MAX_REDIRECTS)
+        .timeout_connect(Duration::from_secs(30))
+        .timeout_read(Duration::from_secs(120))
+        .user_agent(&options.user_agent)
+        .build();
+
+    let mut last_error = String::new();
+    let mut retried_not_pdf = false;
+    for attempt in 0..=options.retries {
+        if attempt > 0 {
+            thread::sleep(Duration::from_secs(1 << (attempt - 1).min(4)));
+        }
+        if let Some(policy) = policy {
+            policy.throttle(&url);
+        }
+        match attempt_download(&agent, &url, &part_path, options, progress) {
+            Ok(attribution) => {
+                let bytes = finish(&part_path, output)?;
+                if let Some(record) = attribution {
+                    crate::download_policy::write_attribution(output, &record)?;
+                }
+                return Ok(bytes);
+            }
+            Err(AttemptError::Transient(e)) => last_error = e,
+            Err(AttemptError::NotPdf(e)) => {
+                discard_part(&part_path);
+                last_error = e;
+                // Bot-check pages usually set a cookie and expect the client to reload;
+                // the agent keeps cookies, so one immediate retry is worth it.
+                if retried_not_pdf {
+                    break;
+                }
+                retried_not_pdf = true;
+            }
+            Err(AttemptError::Fatal(e)) => {
+                last_error = e;
+                break;
+            }
+        }
+    }
+
+    if let Some(ref command) = options.browser_command {
+        eprintln!("[DOWNLOAD] Direct download failed ({}); trying browser command", last_error);
+        return browser_fallback(command, &url, output);
+    }
+
+    Err(last_error)
+}
+
+/// Download a PDF into memory (via a temporary `.part` file next to `scratch`).
+pub(crate) fn fetch_pdf(
+    url: &str,
+    scratch: &Path,
+    options: &DownloadOptions,
+    progress: ProgressFn,
+) -> Result<Vec<u8>, String> {
+    fetch_pdf_attributed(url, scratch, options, progress).map(|(data, _)| data)
+}
+
+/// [`fetch_pdf`], plus the attribution record to store with the file when the
+/// download policy asks for one.
+pub(crate) fn fetch_pdf_attributed(
+    url: &str,
+    scratch: &Path,
+    options: &DownloadOptions,
+    progress: ProgressFn,
+) -> Result<(Vec<u8>, Option<String>), String> {
+    download_with_progress(url, scratch, options, progress)?;
+    let data = fs::read(scratch).map_err(|e| format!("reading {}: {}", scratch.display(), e));
+    let _ = fs::remove_file(scratch);
+    let attribution = crate::download_policy::take_attribution(scratch);
+    Ok((data?, attribution))
+}
+
+fn part_path(output: &Path) -> PathBuf {
+    let mut name = output.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    output.with_file_name(name)
+}
+
+/// Where a `.part` file came from, kept beside it as `<output>.part.meta`.
+///
+/// A partial file is only resumed by a request for the same URL, and the
+/// `Range` request carries the validator in `If-Range`, so a datasheet revised
+/// since the first attempt comes back whole (200) instead of being appended
+/// to the old bytes.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct PartMeta {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl PartMeta {
+    fn from_response(url: &str, response: &ureq::Response) -> Self {
+        PartMeta {
+            url: url.to_string(),
+            etag: response.header("ETag").map(str::to_string),
+            last_modified: response.header("Last-Modified").map(str::to_string),
+        }
+    }
+
+    fn read(part_path: &Path) -> Option<Self> {
+        serde_json::from_slice(&fs::read(meta_path(part_path)).ok()?).ok()
+    }
+
+    /// `If-Range` value; without one the server cannot tell us the file changed.
+    fn validator(&self) -> Option<&str> {
+        self.etag.as_deref().or(self.last_modified.as_deref())
+    }
+}
+
+fn meta_path(part_path: &Path) -> PathBuf {
+    let mut name = part_path.as_os_str().to_owned();
+    name.push(".meta");
+    PathBuf::from(name)
+}
+
+fn discard_part(part_path: &Path) {
+    let _ = fs::remove_file(part_path);
+    let _ = fs::remove_file(meta_path(part_path));
+}
+
+fn attempt_download(
+    agent: &ureq::Agent,
+    url: &str,
+    part_path: &Path,
+    options: &DownloadOptions,
+    progress: ProgressFn,
+) -> Result<Option<String>, AttemptError> {
+    let mut existing = fs::metadata(part_path).map(|m| m.len()).unwrap_or(0);
+    let validator = PartMeta::read(part_path)
+        .filter(|meta| meta.url == url)
+        .and_then(|meta| meta.validator().map(str::to_string));
+    if existing > 0 && validator.is_none() {
+        // Left by another URL, or by a server that gave no ETag or Last-Modified
+        discard_part(part_path);
+        existing = 0;
+    }
+
+    let mut request = agent.get(url).set("Accept", "application/pdf,*/*");
+    if let Some(ref referer) = options.referer {
+        request = request.set("Referer", referer);
+    }
+    if let (true, Some(validator)) = (existing > 0, &validator) {
+        request = request.set("Range", &format!("bytes={}-", existing)).set("If-Range", validator);
+    }
+
+    let response = match request.call() {
+        Ok(response) => response,
+        // Range past the end of an unchanged file: the partial file is already complete
+        Err(ureq::Error::Status(416, _)) if existing > 0 => {
+            if !starts_with_pdf_magic(part_path) {
+                return Err(AttemptError::NotPdf("partial download is not a PDF".to_string()));
+            }
+            return Ok(None);
+        }
+        Err(ureq::Error::Status(code, _)) if code == 429 || code >= 500 => {
+            return Err(AttemptError::Transient(format!("download failed: HTTP {}", code)));
+        }
+        Err(ureq::Error::Status(code, _)) => {
+            return Err(AttemptError::Fatal(format!("download failed: HTTP {}", code)));
+        }
+        Err(e) => return Err(AttemptError::Transient(format!("download failed: {}", e))),
+    };
+
+    let attribution = match crate::download_policy::current().ok().flatten() {
+        Some(policy) => policy.inspect(url, &response).map_err(AttemptError::Fatal)?,
+        None => None,
+    };
+
+    let content_type = response.content_type().to_string();
+    if content_type.contains("text/html") {
+        return Err(AttemptError::NotPdf(format!(
+            "response is not a PDF (content-type: {}); the host may be blocking automated downloads",
+            content_type
+        )));
+    }
+
+    let resume = response.status() == 206 && existing > 0;
+    let offset = if resume { existing } else { 0 };
+    let total = response
+        .header("Content-Length")
+        .and_then(|len| len.parse::<u64>().ok())
+        .map(|len| len + offset);
+    let mut file = if resume {
+        OpenOptions::new().append(true).open(part_path)
+    } else {
+        let meta = serde_json::to_vec(&PartMeta::from_response(url, &response)).unwrap_or_default();
+        fs::write(meta_path(part_path), meta).and_then(|_| File::create(part_path))
+    }
+    .map_err(|e| AttemptError::Fatal(format!("creating {}: {}", part_path.display(), e)))?;
+
+    let mut reader = response.into_reader();
+    copy_body(&mut reader, &mut file, offset, total, progress)
+        .map_err(|e| AttemptError::Transient(format!("download interrupted: {}", e)))?;
+
+    if !starts_with_pdf_magic(part_path) {
+        return Err(AttemptError::NotPdf(format!(
+            "response is not a PDF (content-type: {}); the host may be blocking automated downloads",
+            content_type
+        )));
+    }
+    Ok(attribution)
+}
+
+fn copy_body(
+    reader: &mut impl Read,
+    file: &mut File,
+    mut downloaded: u64,
+    total: Option<u64>,
+    progress: ProgressFn,
+) -> io::Result<()> {
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        file.write_all(&buf[..n])?;
+        downloaded += n as u64;
+        progress(downloaded, total);
+    }
+    file.flush()
+}
+
+fn starts_with_pdf_magic(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .is_ok()
+        && &magic == b"%PDF"
+}
+
+fn finish(part_path: &Path, output: &Path) -> Result<u64, String> {
+    fs::rename(part_path, output)
+        .map_err(|e| format!("moving download to {}: {}", output.display(), e))?;
+    let _ = fs::remove_file(meta_path(part_path));
+    fs::metadata(output)
+        .map(|m| m.len())
+        .map_err(|e| format!("reading {}: {}", output.display(), e))
+}
+
+/// Run the user-configured fallback command and validate what it produced.
+fn browser_fallback(command: &str, url: &str, output: &Path) -> Result<u64, String> {
+    let output_str = output.to_string_lossy();
+    let mut parts = command
+        .split_whitespace()
+        .map(|arg| arg.replace("{url}", url).replace("{output}", &output_str));
+    let program = parts
+        .next()
+        .ok_or_else(|| format!("{} is empty", ENV_VAR_BROWSER_CMD))?;
+
+    let status = Command::new(&program)
+        .args(parts)
+        .status()
+        .map_err(|e| format!("running {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("browser command exited with {}", status));
+    }
+    if !starts_with_pdf_magic(output) {
+        let _ = fs::remove_file(output);
+        return Err("browser command did not produce a PDF".to_string());
+    }
+    fs::metadata(output)
+        .map(|m| m.len())
+        .map_err(|e| format!("reading {}: {}", output.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_and_urls() {
+        assert_eq!(
+            normalize_url("//mm.digikey.com/Volume0/ds.pdf"),
+            "https://mm.digikey.com/Volume0/ds.pdf"
+        );
+        assert_eq!(
+            part_path(Path::new("out/LM358.pdf")),
+            PathBuf::from("out/LM358.pdf.part")
+        );
+    }
+
+    /// Serve `body` to one request on a local port; returns the URL and the request's headers.
+    fn serve_once(body: &'static [u8]) -> (String, thread::JoinHandle<String>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/b.pdf", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/pdf\r\nContent-Length: {}\r\nETag: \"b1\"\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(body).unwrap();
+            String::from_utf8_lossy(&request).to_lowercase()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_part_from_other_url_is_not_resumed() {
+        let dir = std::env::temp_dir().join(format!("datasheet-download-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("LM358.pdf");
+        let part = part_path(&output);
+        fs::write(&part, b"%PDF-1.4 stale bytes from A").unwrap();
+        let meta = PartMeta { url: "http://a.example/a.pdf".to_string(), etag: Some("\"a1\"".to_string()), last_modified: None };
+        fs::write(meta_path(&part), serde_json::to_vec(&meta).unwrap()).unwrap();
+
+        let (url, server) = serve_once(b"%PDF-1.7 fresh from B");
+        download_pdf(&url, &output, &DownloadOptions { retries: 0, ..Default::default() }).unwrap();
+        let request = server.join().unwrap();
+        assert!(!request.contains("range:"), "resumed a .part from another URL: {}", request);
+        assert_eq!(fs::read(&output).unwrap(), b"%PDF-1.7 fresh from B");
+        assert!(!part.exists() && !meta_path(&part).exists());
+
+        // A .part from the same URL resumes, guarded by If-Range
+        fs::write(&part, b"%PDF-1.7 fre").unwrap();
+        let (url, server) = serve_once(b"%PDF-1.7 fresh from B");
+        let meta = PartMeta { url: url.clone(), etag: Some("\"b1\"".to_string()), last_modified: None };
+        fs::write(meta_path(&part), serde_json::to_vec(&meta).unwrap()).unwrap();
+        download_pdf(&url, &output, &DownloadOptions { retries: 0, ..Default::default() }).unwrap();
+        let request = server.join().unwrap();
+        assert!(request.contains("range: bytes=12-") && request.contains("if-range: \"b1\""));
+        // The server ignored the range and sent the whole file, which replaced the partial one
+        assert_eq!(fs::read(&output).unwrap(), b"%PDF-1.7 fresh from B");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,326 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Acceptable-use policy for datasheet downloads.
+//!
+//! Read from `$DATASHEET_DOWNLOAD_POLICY`, or `download-policy.toml` next to the
+//! config file, and enforced by the download engine for every fetch:
+//!
+//! ```toml
+//! allow = ["ti.com", "analog.com"]   # when set, only these hosts (and subdomains)
+//! deny = ["datasheet-mirror.example"] # never fetched; wins over allow
+//! min_interval = 1.0                  # seconds between requests to one host
+//! robots = true                       # honor robots.txt and X-Robots-Tag: noarchive
+//! attribution = true                  # write <file>.source.json next to each PDF
+//!
+//! [domains."mouser.com"]
+//! min_interval = 5.0
+//! ```
+//!
+//! Without a policy file nothing is restricted.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const ENV_VAR_POLICY: &str = "DATASHEET_DOWNLOAD_POLICY";
+const ROBOTS_AGENT: &str = "datasheet-cli";
+
+/// `(allow, path prefix)` rules from a robots.txt.
+type RobotsRules = Vec<(bool, String)>;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Policy {
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>,
+    min_interval: Option<f64>,
+    #[serde(default)]
+    robots: bool,
+    #[serde(default)]
+    attribution: bool,
+    #[serde(default)]
+    domains: BTreeMap<String, DomainPolicy>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DomainPolicy {
+    min_interval: Option<f64>,
+}
+
+/// Where a downloaded PDF came from, written to `<file>.source.json`.
+#[derive(Debug, Serialize)]
+struct Attribution<'a> {
+    url: &'a str,
+    /// Where redirects ended up
+    final_url: &'a str,
+    retrieved_at: u64,
+    /// `Link: <...>; rel="license"` from the response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    x_robots_tag: Option<String>,
+}
+
+static POLICY: OnceLock<Result<Option<Policy>, String>> = OnceLock::new();
+/// Earliest time the next request to each host may start.
+static NEXT_REQUEST: Mutex<Option<HashMap<String, Instant>>> = Mutex::new(None);
+/// robots.txt rules fetched this run, by origin.
+static ROBOTS: Mutex<Option<HashMap<String, RobotsRules>>> = Mutex::new(None);
+
+/// The active policy, if a policy file exists.
+pub(crate) fn current() -> Result<Option<&'static Policy>, String> {
+    POLICY.get_or_init(load).as_ref().map(Option::as_ref).map_err(Clone::clone)
+}
+
+fn policy_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(ENV_VAR_POLICY).filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let path = crate::config::config_path()?.with_file_name("download-policy.toml");
+    path.exists().then_some(path)
+}
+
+fn load() -> Result<Option<Policy>, String> {
+    let Some(path) = policy_path() else {
+        return Ok(None);
+    };
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("reading download policy {}: {}", path.display(), e))?;
+    toml::from_str(&text)
+        .map(Some)
+        .map_err(|e| format!("invalid download policy {}: {}", path.display(), e))
+}
+
+impl Policy {
+    /// Refuse URLs whose host is denied, not allowed, or disallowed by robots.txt.
+    pub(crate) fn check(&self, url: &str) -> Result<(), String> {
+        let (origin, host, path) =
+            split_url(url).ok_or_else(|| format!("blocked by download policy: cannot parse URL {}", url))?;
+        if self.deny.iter().any(|d| host_matches(&host, d)) {
+            return Err(format!("blocked by download policy: {} is denied", host));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|a| host_matches(&host, a)) {
+            return Err(format!("blocked by download policy: {} is not in the allow list", host));
+        }
+        if self.robots && !robots_allows(&origin, &path) {
+            return Err(format!("blocked by download policy: robots.txt of {} disallows {}", host, path));
+        }
+        Ok(())
+    }
+
+    /// Wait until the host's rate limit permits another request.
+    pub(crate) fn throttle(&self, url: &str) {
+        let Some((_, host, _)) = split_url(url) else {
+            return;
+        };
+        let interval = self
+            .domains
+            .iter()
+            .filter(|(domain, _)| host_matches(&host, domain))
+            .max_by_key(|(domain, _)| domain.len())
+            .and_then(|(_, d)| d.min_interval)
+            .or(self.min_interval)
+            .filter(|s| *s > 0.0);
+        let Some(interval) = interval else {
+            return;
+        };
+
+        let wait = {
+            let mut next = NEXT_REQUEST.lock().unwrap_or_else(|e| e.into_inner());
+            let next = next.get_or_insert_with(HashMap::new);
+            let now = Instant::now();
+            let start = next.get(&host).copied().filter(|t| *t > now).unwrap_or(now);
+            next.insert(host, start + Duration::from_secs_f64(interval));
+            start - now
+        };
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+
+    /// Check the response's `X-Robots-Tag` and, if attribution is on, remember
+    /// what to record once the file is complete.
+    pub(crate) fn inspect(&self, url: &str, response: &ureq::Response) -> Result<Option<String>, String> {
+        let x_robots_tag = response.header("X-Robots-Tag").map(str::to_string);
+        if self.robots {
+            let tag = x_robots_tag.as_deref().unwrap_or_default().to_lowercase();
+            if tag.contains("noarchive") || tag.split(',').any(|t| t.trim() == "none") {
+                return Err(format!(
+                    "blocked by download policy: {} sends X-Robots-Tag: {}",
+                    response.get_url(),
+                    x_robots_tag.unwrap_or_default()
+                ));
+            }
+        }
+        // A redirect may have left the allowed hosts
+        if response.get_url() != url {
+            self.check(response.get_url())?;
+        }
+        if !self.attribution {
+            return Ok(None);
+        }
+        let record = Attribution {
+            url,
+            final_url: response.get_url(),
+            retrieved_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            license: response.all("Link").into_iter().find_map(license_link),
+            x_robots_tag,
+        };
+        serde_json::to_string_pretty(&record)
+            .map(Some)
+            .map_err(|e| format!("recording attribution: {}", e))
+    }
+}
+
+/// Write an attribution record from [`Policy::inspect`] next to `output`.
+pub(crate) fn write_attribution(output: &Path, record: &str) -> Result<(), String> {
+    let path = attribution_path(output);
+    crate::paths::write_file(&path, record).map_err(|e| format!("writing {}: {}", path.display(), e))
+}
+
+/// Read and remove the attribution record written next to `output`.
+pub(crate) fn take_attribution(output: &Path) -> Option<String> {
+    let path = attribution_path(output);
+    let record = std::fs::read_to_string(&path).ok()?;
+    let _ = std::fs::remove_file(&path);
+    Some(record)
+}
+
+fn attribution_path(output: &Path) -> PathBuf {
+    let mut name = output.file_name().unwrap_or_default().to_os_string();
+    name.push(".source.json");
+    output.with_file_name(name)
+}
+
+/// `(origin, lowercase host, path)` of an absolute URL; the origin is
+/// `scheme://host[:port]`.
+fn split_url(url: &str) -> Option<(String, String, String)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let authority = rest[..end].rsplit('@').next()?.to_lowercase();
+    let host = authority.split(':').next()?.to_string();
+    let path = rest[end..].split('#').next().unwrap_or_default();
+    let path = if path.starts_with('/') { path.to_string() } else { format!("/{}", path) };
+    (!host.is_empty()).then(|| (format!("{}://{}", scheme.to_lowercase(), authority), host, path))
+}
+
+/// `pattern` is a host name (optionally `*.`-prefixed) covering its subdomains.
+fn host_matches(host: &str, pattern: &str) -> bool {
+    let pattern = pattern.trim().trim_start_matches("*.").to_lowercase();
+    host == pattern || host.ends_with(&format!(".{}", pattern))
+}
+
+/// URL of a `Link: <url>; rel="license"` header value.
+fn license_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|link| {
+        let (target, params) = link.split_once(';')?;
+        let is_license = params
+            .split(';')
+            .any(|p| p.trim().replace('"', "").eq_ignore_ascii_case("rel=license"));
+        is_license.then(|| target.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+fn robots_allows(origin: &str, path: &str) -> bool {
+    let mut cache = ROBOTS.lock().unwrap_or_else(|e| e.into_inner());
+    let rules = cache.get_or_insert_with(HashMap::new).entry(origin.to_string()).or_insert_with(|| {
+        // A missing or unreachable robots.txt allows everything
+        ureq::get(&format!("{}/robots.txt", origin))
+            .timeout(Duration::from_secs(10))
+            .call()
+            .ok()
+            .and_then(|r| r.into_string().ok())
+            .map(|text| robots_rules(&text))
+            .unwrap_or_default()
+    });
+    robots_permits(rules, path)
+}
+
+/// Rules of the robots.txt groups for `*` or this tool; a group naming this
+/// tool replaces the `*` group.
+fn robots_rules(text: &str) -> RobotsRules {
+    let mut ours = RobotsRules::new();
+    let mut any = RobotsRules::new();
+    let mut named_us = false;
+    let (mut agents, mut in_rules): (Vec<String>, bool) = (Vec::new(), false);
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (key, value) = (key.trim().to_lowercase(), value.trim());
+        match key.as_str() {
+            "user-agent" => {
+                if in_rules {
+                    agents.clear();
+                    in_rules = false;
+                }
+                agents.push(value.to_lowercase());
+            }
+            "allow" | "disallow" => {
+                in_rules = true;
+                if value.is_empty() {
+                    continue;
+                }
+                let rule = (key == "allow", value.to_string());
+                if agents.iter().any(|a| a.contains(ROBOTS_AGENT)) {
+                    named_us = true;
+                    ours.push(rule);
+                } else if agents.iter().any(|a| a == "*") {
+                    any.push(rule);
+                }
+            }
+            _ => {}
+        }
+    }
+    if named_us { ours } else { any }
+}
+
+/// Longest matching prefix decides; `Allow` wins a tie.
+fn robots_permits(rules: &[(bool, String)], path: &str) -> bool {
+    rules
+        .iter()
+        .filter(|(_, prefix)| path.starts_with(prefix.trim_end_matches('*')))
+        .max_by_key(|(allow, prefix)| (prefix.len(), *allow))
+        .is_none_or(|(allow, _)| *allow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_rules() {
+        let policy: Policy = toml::from_str(
+            r#"
+            allow = ["ti.com", "*.analog.com"]
+            deny = ["legacy.ti.com"]
+            "#,
+        )
+        .unwrap();
+        assert!(policy.check("https://www.ti.com/lit/ds/symlink/lm5164.pdf").is_ok());
+        assert!(policy.check("https://www.analog.com/media/en/ad7124.pdf").is_ok());
+        assert!(policy.check("https://legacy.ti.com/old.pdf").unwrap_err().contains("denied"));
+        assert!(policy.check("https://notti.com/x.pdf").unwrap_err().contains("allow list"));
+
+        let rules = robots_rules(
+            "User-agent: *\nDisallow: /\n\nUser-agent: datasheet-cli\nDisallow: /private/\nAllow: /private/ds/\n",
+        );
+        assert!(robots_permits(&rules, "/lit/ds.pdf"));
+        assert!(!robots_permits(&rules, "/private/x.pdf"));
+        assert!(robots_permits(&rules, "/private/ds/x.pdf"));
+        assert!(!robots_permits(&robots_rules("User-agent: *\nDisallow: /\n"), "/x.pdf"));
+
+        assert_eq!(
+            license_link(r#"<https://example.com/terms>; rel="license", <https://example.com/>; rel="home""#),
+            Some("https://example.com/terms".to_string())
+        );
+    }
+}
@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Embeddings for semantic part search.
+//!
+//! A datasheet PDF is embedded one page at a time from its text layer; an
+//! extraction result is embedded one top-level section at a time. The vectors
+//! go into the parts database next to the attributes, where
+//! `datasheet db semantic-search` compares a query against them.
+
+use crate::compare_revisions::page_text;
+use crate::db::{Embedding, PartsDb};
+use crate::error::Error;
+use crate::llm::{self, LlmProvider, resolve_api_key};
+use anyhow::{Result, anyhow};
+use clap::Args;
+use lopdf::Document;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Longest text sent for one chunk; longer pages and sections are cut.
+const MAX_CHUNK_CHARS: usize = 6000;
+
+/// Label and text of one chunk.
+type Chunk = (String, String);
+
+#[derive(Args, Debug)]
+pub struct EmbedArgs {
+    /// Datasheet PDFs or extraction JSON files
+    #[arg(required = true)]
+    pub inputs: Vec<PathBuf>,
+
+    /// Manufacturer part number (defaults to part_number in the JSON, or the PDF file name)
+    #[arg(long)]
+    pub mpn: Option<String>,
+
+    /// Embedding model
+    #[arg(long, default_value = llm::DEFAULT_EMBEDDING_MODEL)]
+    pub model: String,
+
+    /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Optional base URL override for Gemini API
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Database path (defaults to DATASHEET_DB or the platform data directory)
+    #[arg(long)]
+    pub db: Option<PathBuf>,
+}
+
+pub fn run(args: &EmbedArgs) -> Result<()> {
+    let db = PartsDb::open(args.db.clone()).map_err(|e| anyhow!(e))?;
+    let api_key = resolve_api_key(LlmProvider::Gemini, args.api_key.clone())?;
+
+    for input in &args.inputs {
+        if !input.exists() {
+            return Err(Error::not_found(format!("input not found: {}", input.display())).into());
+        }
+        let name = input.file_name().unwrap_or_default().to_string_lossy();
+        let is_json = input.extension().is_some_and(|e| e.eq_ignore_ascii_case("json"));
+        let (mpn, source, chunks) = if is_json {
+            let (mpn, chunks) = json_chunks(input)?;
+            let mpn = args.mpn.clone().or(mpn).ok_or_else(|| {
+                Error::validation(format!("no --mpn given and no part_number in {}", input.display()))
+            })?;
+            (mpn, format!("extract:{}", name), chunks)
+        } else {
+            let mpn = args
+                .mpn
+                .clone()
+                .unwrap_or_else(|| input.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+            (mpn, format!("pdf:{}", name), pdf_chunks(input)?)
+        };
+        if chunks.is_empty() {
+            eprintln!("[EMBED] {}: no text to embed, skipping", input.display());
+            continue;
+        }
+
+        eprintln!("[EMBED] {}: embedding {} chunk(s) for {}", input.display(), chunks.len(), mpn);
+        let texts: Vec<String> = chunks.iter().map(|(_, text)| text.clone()).collect();
+        let vectors = llm::embed(&api_key, args.base_url.clone(), &args.model, &texts, false)?;
+        let embeddings: Vec<Embedding> = chunks
+            .into_iter()
+            .zip(vectors)
+            .map(|((label, text), vector)| Embedding { label, text, vector })
+            .collect();
+
+        let id = db.upsert_part(&mpn, None, None, None).map_err(|e| anyhow!(e))?;
+        db.set_embeddings(id, &source, &args.model, &embeddings)
+            .map_err(|e| anyhow!(e))?;
+        println!("Embedded {} chunk(s) for {} ({})", embeddings.len(), mpn, source);
+    }
+    Ok(())
+}
+
+/// One chunk per page with a text layer, labelled "page N".
+fn pdf_chunks(path: &Path) -> Result<Vec<Chunk>> {
+    Ok(page_texts(path)?
+        .1
+        .into_iter()
+        .map(|(page, text)| (format!("page {}", page), text))
+        .collect())
+}
+
+/// Page count, and the whitespace-collapsed text of each page that has any
+/// (truncated for embedding).
+pub(crate) fn page_texts(path: &Path) -> Result<(usize, Vec<(u32, String)>)> {
+    let doc = Document::load(path)
+        .map_err(|e| Error::validation(format!("opening {}: {}", path.display(), e)))?;
+    let all_pages = doc.get_pages();
+    let mut pages = Vec::new();
+    for (&page, &page_id) in &all_pages {
+        let Ok(text) = page_text(&doc, page_id) else {
+            continue;
+        };
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !text.is_empty() {
+            pages.push((page, truncate(text)));
+        }
+    }
+    Ok((all_pages.len(), pages))
+}
+
+/// The part number and one chunk per top-level section of an extraction result.
+fn json_chunks(path: &Path) -> Result<(Option<String>, Vec<Chunk>)> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| Error::not_found(format!("reading {}: {}", path.display(), e)))?;
+    let json: Value = serde_json::from_str(&content)
+        .map_err(|e| Error::validation(format!("parsing {}: {}", path.display(), e)))?;
+    let mpn = json
+        .get("part_number")
+        .or_else(|| json.pointer("/part_details/part_number"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    Ok((mpn, section_chunks(&json)))
+}
+
+fn section_chunks(json: &Value) -> Vec<Chunk> {
+    let Some(object) = json.as_object() else {
+        return Vec::new();
+    };
+    object
+        .iter()
+        .filter(|(key, value)| key.as_str() != crate::migrate::STAMP && !value.is_null())
+        .map(|(key, value)| {
+            let body = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            (key.clone(), truncate(format!("{}: {}", key.replace('_', " "), body)))
+        })
+        .collect()
+}
+
+fn truncate(mut text: String) -> String {
+    if let Some((end, _)) = text.char_indices().nth(MAX_CHUNK_CHARS) {
+        text.truncate(end);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_section_chunks() {
+        let json = json!({
+            "_schema": {"task": "pinout", "version": 2},
+            "part_number": "TPS62840",
+            "features": ["60 nA quiescent current", "1.8 V to 6.5 V input"],
+            "notes": null,
+        });
+        let chunks = section_chunks(&json);
+        let labels: Vec<&str> = chunks.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, vec!["features", "part_number"]);
+        assert!(chunks[0].1.starts_with("features: [\"60 nA"));
+        assert_eq!(chunks[1].1, "part number: TPS62840");
+        assert_eq!(truncate("é".repeat(MAX_CHUNK_CHARS + 5)).chars().count(), MAX_CHUNK_CHARS);
+    }
+}
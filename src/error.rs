@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Failure categories, exit codes, and `--error-format json`.
+//!
+//! | Exit code | Kind              | Meaning                                          |
+//! |-----------|-------------------|--------------------------------------------------|
+//! | 1         | `error`           | Anything not covered below                       |
+//! | 2         | `usage`           | Invalid arguments (reported by clap)             |
+//! | 3         | `auth`            | Missing or rejected credentials                  |
+//! | 4         | `not_found`       | Part, datasheet, or cached entry does not exist  |
+//! | 5         | `rate_limited`    | The remote service asked us to slow down         |
+//! | 6         | `validation`      | Schema invalid, or model output did not match it |
+//! | 7         | `network`         | Connection failed, timed out, or server error    |
+//! | 8         | `budget_exceeded` | An LLM request/token/spend limit was reached     |
+//!
+//! Code that knows the category returns [`Error`] (or, for Gemini requests,
+//! [`crate::llm::LlmError`]); anything else (plain `String` or `anyhow` errors)
+//! is reported as `error`.
+
+use clap::ValueEnum;
+use std::fmt;
+use std::process::ExitCode;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Human-readable message
+    Text,
+    /// One JSON object on stderr: {"error":{"kind":..,"exit_code":..,"message":..}}
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Other,
+    Auth,
+    NotFound,
+    RateLimited,
+    Validation,
+    Network,
+    BudgetExceeded,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Auth => 3,
+            ErrorKind::NotFound => 4,
+            ErrorKind::RateLimited => 5,
+            ErrorKind::Validation => 6,
+            ErrorKind::Network => 7,
+            ErrorKind::BudgetExceeded => 8,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Other => "error",
+            ErrorKind::Auth => "auth",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::RateLimited => "rate_limited",
+            ErrorKind::Validation => "validation",
+            ErrorKind::Network => "network",
+            ErrorKind::BudgetExceeded => "budget_exceeded",
+        }
+    }
+
+    /// Category for an HTTP error status.
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 | 403 => ErrorKind::Auth,
+            404 | 410 => ErrorKind::NotFound,
+            429 => ErrorKind::RateLimited,
+            500.. => ErrorKind::Network,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// An error with a known failure category.
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Error { kind, message: message.into() }
+    }
+
+    pub fn auth(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Auth, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    pub fn network(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Network, message)
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Validation, message)
+    }
+
+    pub fn budget(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::BudgetExceeded, message)
+    }
+
+    /// Classify a failed `ureq` request: HTTP status or transport failure.
+    pub fn from_ureq(context: &str, error: ureq::Error) -> Self {
+        match error {
+            ureq::Error::Status(code, response) => {
+                let body = response.into_string().unwrap_or_default();
+                Self::new(
+                    ErrorKind::from_status(code),
+                    format!("{}: HTTP {} {}", context, code, body.trim()),
+                )
+            }
+            ureq::Error::Transport(e) => Self::network(format!("{}: {}", context, e)),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Untyped errors keep working with `?`; they are reported as `error`.
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self::new(ErrorKind::Other, message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Self::new(ErrorKind::Other, message)
+    }
+}
+
+/// Lets `String`-returning callers use `?` on typed results.
+impl From<Error> for String {
+    fn from(error: Error) -> Self {
+        error.message
+    }
+}
+
+/// The category of the first typed error in `err`'s chain.
+pub fn kind_of(err: &anyhow::Error) -> ErrorKind {
+    err.chain()
+        .find_map(|cause| {
+            cause
+                .downcast_ref::<Error>()
+                .map(|e| e.kind)
+                .or_else(|| cause.downcast_ref::<crate::llm::LlmError>().map(|e| e.kind()))
+        })
+        .unwrap_or(ErrorKind::Other)
+}
+
+/// Print `err` in the requested format and return the matching exit code.
+pub fn report(err: &anyhow::Error, format: ErrorFormat) -> ExitCode {
+    let kind = kind_of(err);
+    match format {
+        ErrorFormat::Text => eprintln!("Error: {:?}", err),
+        ErrorFormat::Json => {
+            let causes: Vec<String> = err.chain().skip(1).map(|c| c.to_string()).collect();
+            let body = serde_json::json!({
+                "error": {
+                    "kind": kind.as_str(),
+                    "exit_code": kind.exit_code(),
+                    "message": err.to_string(),
+                    "causes": causes,
+                }
+            });
+            eprintln!("{}", body);
+        }
+    }
+    ExitCode::from(kind.exit_code())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_kind_survives_context() {
+        let err = Err::<(), _>(Error::not_found("Part not found: X"))
+            .context("looking up X")
+            .unwrap_err();
+        assert_eq!(kind_of(&err), ErrorKind::NotFound);
+        assert_eq!(kind_of(&anyhow::anyhow!("plain")), ErrorKind::Other);
+        assert_eq!(ErrorKind::from_status(429).exit_code(), 5);
+    }
+}
@@ -1,37 +1,44 @@
 // SPDX-License-Identifier: GPL-3.0-only
 // SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
 
-use crate::file_cache::FileCache;
+use crate::error::Error;
+use crate::file_cache::{FileCache, Preprocess};
 use crate::llm::{
-    Attachment, AttachmentSource, FileReference, LlmProvider, LlmRequest, build_client,
-    resolve_api_key,
+    Attachment, AttachmentSource, FileReference, INLINE_MAX_BYTES, LlmClient, LlmProvider, LlmRequest,
+    LlmResponse, MalformedResponse, build_client, resolve_api_key,
 };
+use crate::merge_output::OnConflict;
 use crate::pdf_split;
-use crate::prompts;
+use crate::prompts::{self, PromptLang};
 use anyhow::{Context, Result, anyhow};
 use clap::{Args, ValueEnum};
-use serde_json::Value;
+use serde_json::{Value, json};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-const __DEFAULT__: &str = "__DEFAULT__";
+/// `--model` value meaning "the command's default model"
+pub(crate) const __DEFAULT__: &str = "__DEFAULT__";
+/// Model used when `--model` is not given
+pub(crate) const DEFAULT_MODEL: &str = "gemini-3.1-pro-preview";
+/// Extra attempts when the model returns malformed JSON
+const MALFORMED_RETRIES: usize = 1;
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone)]
 pub struct ExtractArgs {
     /// Task to run
     #[arg(value_enum)]
     pub task: ExtractTask,
 
-    /// Input PDF path
+    /// Input PDF path (a public http(s) URL with --remote-url)
     pub pdf: PathBuf,
 
     /// LLM provider (always Gemini)
-    #[arg(long, default_value = "gemini", hide = true)]
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
     pub provider: LlmProvider,
 
     /// Model name (default: task-specific model, see each task's default_model())
     /// Examples: gemini-3.1-pro-preview, gemini-2.5-flash, gemini-3.1-flash-lite-preview
-    #[arg(long, default_value = __DEFAULT__)]
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
     pub model: String,
 
     /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
@@ -46,12 +53,21 @@ pub struct ExtractArgs {
     #[arg(long)]
     pub out: Option<PathBuf>,
 
+    /// Add the result to the JSON document in --out under the task's name, merging it
+    /// with what is already there, instead of overwriting the file
+    #[arg(long, requires = "out", conflicts_with_all = ["interactive", "expand_variants"])]
+    pub append: bool,
+
+    /// With --append, which value wins where the new result differs from the file
+    #[arg(long, value_enum, default_value_t = OnConflict::Replace, requires = "append")]
+    pub on_conflict: OnConflict,
+
     /// Sampling temperature (currently not plumbed for OpenRouter in this implementation)
     #[arg(long)]
     pub temperature: Option<f32>,
 
     /// Show formatted (pretty-printed) JSON output
-    #[arg(long, short = 'f', visible_alias = "pretty")]
+    #[arg(long, short = 'f', visible_alias = "pretty", env = "DATASHEET_FORMATTED")]
     pub formatted: bool,
 
     /// Custom prompt text or path to prompt file (only for 'custom' task)
@@ -64,55 +80,391 @@ pub struct ExtractArgs {
     #[arg(long)]
     pub schema: Option<String>,
 
+    /// Build the prompt and schema step by step, then run (only for 'custom' task)
+    #[arg(long, conflicts_with_all = ["prompt", "schema", "user_task"])]
+    pub interactive: bool,
+
+    /// Use a prompt and schema saved by --interactive (only for 'custom' task)
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["prompt", "schema"])]
+    pub user_task: Option<String>,
+
+    /// Only send these pages to the model, e.g. "1-5,9,12-" (1-based, inclusive)
+    #[arg(long)]
+    pub pages: Option<String>,
+
+    /// For long documents: embed each page's text and send only the K pages most relevant
+    /// to the task (plus page 1); page embeddings are cached per PDF
+    #[arg(long, value_name = "K", conflicts_with = "pages")]
+    pub retrieve: Option<usize>,
+
+    /// How to send the PDF: auto sends it inline with --no-cache and through the File API
+    /// otherwise, switching to the File API when it is too large to send inline and to
+    /// chunks when it is over the page or file size limit
+    #[arg(long, value_enum, default_value_t = Upload::Auto)]
+    pub upload: Upload,
+
+    /// Language of the datasheet: instruct the model in it (zh-CN, ja, de; default en)
+    #[arg(long, value_enum, default_value_t = PromptLang::En, env = ENV_VAR_PROMPT_LANG)]
+    pub prompt_lang: PromptLang,
+
+    /// Strip watermarks, printer's marks, and crop marks from the PDF before upload
+    #[arg(long)]
+    pub clean_pdf: bool,
+
+    /// Mask this text (e.g. a company name or codename) in the PDF before it is sent;
+    /// repeatable and case-insensitive, or a regular expression written as /.../
+    #[arg(long, value_name = "TEXT")]
+    pub redact: Vec<String>,
+
+    /// Also mask email addresses in the PDF before it is sent
+    #[arg(long)]
+    pub redact_emails: bool,
+
+    /// Write what --redact and --redact-emails masked to this JSON file
+    #[arg(long, value_name = "FILE")]
+    pub redact_report: Option<PathBuf>,
+
+    /// PDF is a public URL: Gemini fetches it directly, nothing is downloaded or uploaded
+    #[arg(long, conflicts_with_all = [
+        "pages", "retrieve", "clean_pdf", "upload", "review", "redact", "redact_emails",
+    ])]
+    pub remote_url: bool,
+
     /// Disable file caching (re-upload PDF every request)
     /// By default, PDFs are uploaded once to Gemini's File API and cached for 48 hours
     #[arg(long)]
     pub no_cache: bool,
+
+    /// Delete the uploaded PDF from Gemini (and the local cache) when done
+    #[arg(long)]
+    pub cleanup: bool,
+
+    /// For family datasheets: list the variants with feature-matrix, then run the task once
+    /// per orderable part, writing <PART>.<task>.json files into the --out directory
+    #[arg(long, requires = "out", conflicts_with = "interactive")]
+    pub expand_variants: bool,
+
+    /// Open the result in $EDITOR (or review it field by field) before writing it; changed
+    /// fields are stored and applied to later extractions of the same PDF
+    #[arg(long, conflicts_with_all = ["interactive", "expand_variants"])]
+    pub review: bool,
+
+    /// Orderable part to restrict the extraction to (set per part by --expand-variants)
+    #[arg(skip)]
+    pub variant: Option<String>,
+
+    /// Named results of earlier pipeline steps to inject into the prompt
+    #[arg(skip)]
+    pub inputs: Vec<(String, Value)>,
+}
+
+pub(crate) const ENV_VAR_PROMPT_LANG: &str = "DATASHEET_PROMPT_LANG";
+
+impl ExtractArgs {
+    /// Run `task` on `pdf` from another command: every option has its
+    /// `datasheet extract` default, so callers only set what they take from
+    /// their own arguments.
+    pub fn for_task(task: ExtractTask, pdf: PathBuf) -> Self {
+        let prompt_lang = std::env::var(ENV_VAR_PROMPT_LANG)
+            .ok()
+            .and_then(|lang| PromptLang::from_str(&lang, true).ok())
+            .unwrap_or_default();
+        Self {
+            task,
+            pdf,
+            provider: LlmProvider::Gemini,
+            model: __DEFAULT__.to_string(),
+            api_key: None,
+            base_url: None,
+            out: None,
+            append: false,
+            on_conflict: OnConflict::Replace,
+            temperature: None,
+            formatted: false,
+            prompt: None,
+            schema: None,
+            interactive: false,
+            user_task: None,
+            pages: None,
+            retrieve: None,
+            upload: Upload::Auto,
+            prompt_lang,
+            clean_pdf: false,
+            redact: Vec::new(),
+            redact_emails: false,
+            redact_report: None,
+            remote_url: false,
+            no_cache: false,
+            cleanup: false,
+            expand_variants: false,
+            review: false,
+            variant: None,
+            inputs: Vec::new(),
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upload {
+    /// Pick from the PDF's size and page count
+    Auto,
+    /// Send the PDF in the request (fails if it is too large)
+    Inline,
+    /// Upload through the Gemini File API
+    File,
+    /// Split into parts along chapters, even if the PDF would fit whole
+    Chunked,
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy)]
 pub enum ExtractTask {
+    AnalogPerformance,
     ApplicationCircuit,
     BootConfig,
     Characteristics,
+    Connector,
+    Crystal,
     Custom,
+    Decoupling,
     DrcRules,
+    Driver,
     FeatureMatrix,
     Footprint,
     HighSpeed,
     LayoutConstraints,
+    Marking,
     Pinout,
+    PmicConfig,
     Power,
+    Programming,
     ReferenceDesign,
+    Rf,
+    SensorMath,
+    Soa,
+    TocFigures,
 }
 
 impl ExtractTask {
     pub fn prompt(self) -> prompts::PromptSpec {
         match self {
+            ExtractTask::AnalogPerformance => prompts::analog_performance(),
             ExtractTask::ApplicationCircuit => prompts::application_circuit(),
             ExtractTask::BootConfig => prompts::boot_config(),
             ExtractTask::Characteristics => prompts::characteristics(),
+            ExtractTask::Connector => prompts::connector(),
+            ExtractTask::Crystal => prompts::crystal(),
             ExtractTask::Custom => prompts::custom(),
+            ExtractTask::Decoupling => prompts::decoupling(),
             ExtractTask::DrcRules => prompts::drc_rules(),
+            ExtractTask::Driver => prompts::driver(),
             ExtractTask::FeatureMatrix => prompts::feature_matrix(),
             ExtractTask::Footprint => prompts::footprint(),
             ExtractTask::HighSpeed => prompts::high_speed(),
             ExtractTask::LayoutConstraints => prompts::layout_constraints(),
+            ExtractTask::Marking => prompts::marking(),
             ExtractTask::Pinout => prompts::pinout(),
+            ExtractTask::PmicConfig => prompts::pmic_config(),
             ExtractTask::Power => prompts::power(),
+            ExtractTask::Programming => prompts::programming(),
             ExtractTask::ReferenceDesign => prompts::reference_design(),
+            ExtractTask::Rf => prompts::rf(),
+            ExtractTask::SensorMath => prompts::sensor_math(),
+            ExtractTask::Soa => prompts::soa(),
+            ExtractTask::TocFigures => prompts::toc_figures(),
         }
     }
 
     pub fn default_model(self) -> &'static str {
-        "gemini-3.1-pro-preview"
+        DEFAULT_MODEL
     }
 }
 
+/// `model`, or `default` when `--model` was left unset.
+pub(crate) fn resolve_model(model: &str, default: &str) -> String {
+    if model == __DEFAULT__ { default.to_string() } else { model.to_string() }
+}
+
 pub fn run_extract(args: &ExtractArgs) -> Result<()> {
-    if !args.pdf.exists() {
-        return Err(anyhow!("PDF not found: {}", args.pdf.display()));
+    if args.interactive {
+        if !matches!(args.task, ExtractTask::Custom) {
+            return Err(anyhow!("--interactive can only be used with 'custom' task"));
+        }
+        return crate::wizard::run(args);
+    }
+    if args.expand_variants {
+        let result = run_expanded(args);
+        if args.cleanup {
+            cleanup_uploads(args.provider, args.api_key.clone(), args.base_url.clone());
+        }
+        return result;
+    }
+    let result = extract_json(args);
+    if args.cleanup {
+        cleanup_uploads(args.provider, args.api_key.clone(), args.base_url.clone());
+    }
+    let mut json = result?;
+    let spec = args.task.prompt();
+    if args.review {
+        json = crate::review::review(&args.pdf, &crate::review::key(spec.name, None), json)?;
+    }
+    let task_label = format!("{} ({})", spec.name, spec.description);
+    if let (true, Some(out)) = (args.append, args.out.as_deref()) {
+        let name = args.user_task.as_deref().unwrap_or(spec.name);
+        json = crate::merge_output::append(out, name, json, args.on_conflict)?;
     }
+    write_output(&json, args.out.as_deref(), args.formatted)
+        .with_context(|| format!("writing {task_label} output for {}", args.pdf.display()))?;
+    Ok(())
+}
+
+/// Run feature-matrix to list a family's parts, then the task once per part.
+fn run_expanded(args: &ExtractArgs) -> Result<()> {
+    if matches!(args.task, ExtractTask::FeatureMatrix) {
+        return Err(Error::validation(
+            "--expand-variants runs feature-matrix itself; choose the task to run per variant",
+        )
+        .into());
+    }
+    let dir = args.out.as_deref().ok_or_else(|| anyhow!("--expand-variants needs --out <DIR>"))?;
+    fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+
+    eprintln!("[VARIANTS] Listing variants in {}", args.pdf.display());
+    let matrix_args = ExtractArgs {
+        task: ExtractTask::FeatureMatrix,
+        prompt: None,
+        schema: None,
+        user_task: None,
+        pages: None,
+        expand_variants: false,
+        ..args.clone()
+    };
+    let matrix = extract_json(&matrix_args)?;
+    write_output(&matrix, Some(&dir.join("feature-matrix.json")), args.formatted)?;
+
+    let parts = variant_parts(&matrix);
+    if parts.is_empty() {
+        return Err(Error::not_found(format!(
+            "feature-matrix found no variants in {}",
+            args.pdf.display()
+        ))
+        .into());
+    }
+    eprintln!("[VARIANTS] {} part(s): {}", parts.len(), parts.join(", "));
+
+    let task = args.task.prompt().name;
+    let mut failed = Vec::new();
+    let mut names = crate::paths::UniqueNames::default();
+    for part in &parts {
+        let variant_args = ExtractArgs {
+            variant: Some(part.clone()),
+            expand_variants: false,
+            out: None,
+            ..args.clone()
+        };
+        match extract_json(&variant_args) {
+            Ok(json) => {
+                let path = dir.join(format!("{}.{}.json", names.name(part), task));
+                write_output(&json, Some(&path), args.formatted)
+                    .with_context(|| format!("writing {}", path.display()))?;
+                eprintln!("[VARIANTS] {} -> {}", part, path.display());
+            }
+            Err(e) => {
+                eprintln!("[VARIANTS] {}: {e:#}", part);
+                failed.push(part.as_str());
+            }
+        }
+    }
+    if !failed.is_empty() {
+        return Err(anyhow!(
+            "{} of {} variant extraction(s) failed: {}",
+            failed.len(),
+            parts.len(),
+            failed.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Orderable part numbers from a feature-matrix result, falling back to each
+/// variant's root part number when the ordering table was not captured.
+fn variant_parts(matrix: &Value) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for variant in matrix.get("variants").and_then(|v| v.as_array()).into_iter().flatten() {
+        let orderable: Vec<&str> = variant
+            .get("orderable_part_numbers")
+            .and_then(|o| o.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.as_str())
+            .collect();
+        let candidates = if orderable.is_empty() {
+            variant.get("root_part_number").and_then(|r| r.as_str()).into_iter().collect()
+        } else {
+            orderable
+        };
+        for part in candidates {
+            let part = part.trim();
+            if !part.is_empty() && !parts.iter().any(|p| p == part) {
+                parts.push(part.to_string());
+            }
+        }
+    }
+    parts
+}
+
+/// Delete the files this run uploaded to Gemini; failures are only warnings.
+pub fn cleanup_uploads(provider: LlmProvider, api_key: Option<String>, base_url: Option<String>) {
+    let result = resolve_api_key(provider, api_key)
+        .and_then(|key| crate::file_cache::cleanup_session(key, base_url));
+    if let Err(e) = result {
+        eprintln!("[CACHE] Warning: cleanup failed: {e:#}");
+    }
+}
+
+/// Run an extraction task and return its JSON without writing it anywhere.
+pub fn extract_json(args: &ExtractArgs) -> Result<Value> {
+    let task = args.task.prompt().name;
+    let started = std::time::Instant::now();
+    crate::progress::emit("task_started", json!({ "task": task, "pdf": args.pdf }));
+    let _usage = crate::usage::scope(task, Some(&args.pdf));
+    let result = extract_json_inner(args).map(|(json, versions)| {
+        let mut json =
+            crate::review::apply_stored(&args.pdf, &crate::review::key(task, args.variant.as_deref()), json);
+        crate::migrate::stamp(&mut json, task, Some(&versions));
+        json
+    });
+    match &result {
+        Ok(_) => crate::progress::emit(
+            "task_done",
+            json!({ "task": task, "pdf": args.pdf, "seconds": started.elapsed().as_secs_f64() }),
+        ),
+        Err(e) => crate::progress::emit(
+            "task_failed",
+            json!({ "task": task, "pdf": args.pdf, "error": format!("{e:#}") }),
+        ),
+    }
+    result
+}
+
+/// The extraction and the versions of the prompt and schema it used.
+fn extract_json_inner(args: &ExtractArgs) -> Result<(Value, crate::migrate::PromptVersions)> {
+    if args.remote_url {
+        remote_url(args)?;
+    } else if !args.pdf.exists() {
+        return Err(Error::not_found(format!("PDF not found: {}", args.pdf.display())).into());
+    }
+
+    // Everything below, retrieval included, only sees the redacted copy
+    let redacted;
+    let args = if args.redact.is_empty() && !args.redact_emails {
+        args
+    } else {
+        redacted = ExtractArgs {
+            pdf: redacted_pdf(args)?,
+            ..args.clone()
+        };
+        &redacted
+    };
 
     // Validate that --prompt and --schema are only used with Custom task
     if !matches!(args.task, ExtractTask::Custom) {
@@ -126,16 +478,31 @@ pub fn run_extract(args: &ExtractArgs) -> Result<()> {
                 "--schema can only be used with 'custom' task. Use 'datasheet extract custom <PDF> --schema \"...\"'"
             ));
         }
+        if args.user_task.is_some() {
+            return Err(anyhow!("--user-task can only be used with 'custom' task"));
+        }
     }
 
+    // A saved user task supplies the custom prompt and schema files
+    let (custom_prompt, custom_schema) = match &args.user_task {
+        Some(name) => {
+            let (prompt, schema) = crate::wizard::user_task_files(name)?;
+            (
+                Some(prompt.to_string_lossy().into_owned()),
+                Some(schema.to_string_lossy().into_owned()),
+            )
+        }
+        None => (args.prompt.clone(), args.schema.clone()),
+    };
+
     let mut prompt_spec = args.task.prompt();
-    let task_label = format!("{} ({})", prompt_spec.name, prompt_spec.description);
 
     // For custom task, allow overriding prompt and schema
     let prompt_text: String;
+    let mut from_source = false;
     if matches!(args.task, ExtractTask::Custom) {
         // Load custom prompt if provided (from file or inline)
-        if let Some(custom_prompt) = &args.prompt {
+        if let Some(custom_prompt) = &custom_prompt {
             prompt_text = load_text_or_file(custom_prompt)
                 .context("loading custom prompt")?;
         } else {
@@ -143,66 +510,231 @@ pub fn run_extract(args: &ExtractArgs) -> Result<()> {
         }
 
         // Load custom schema if provided (from file or inline JSON)
-        if let Some(custom_schema) = &args.schema {
+        if let Some(custom_schema) = &custom_schema {
             let schema_text = load_text_or_file(custom_schema)
                 .context("loading custom schema")?;
-            prompt_spec.schema = serde_json::from_str(&schema_text)
-                .context("parsing custom schema as JSON")?;
+            prompt_spec.schema = serde_json::from_str(&schema_text).map_err(|e| {
+                Error::validation(format!("parsing custom schema as JSON: {}", e))
+            })?;
         }
     } else {
-        prompt_text = prompt_spec.prompt.to_string();
+        match crate::prompt_source::task(prompt_spec.name)? {
+            Some(task) => {
+                prompt_text = task.prompt;
+                if let Some(schema) = task.schema {
+                    prompt_spec.schema = schema;
+                }
+                from_source = true;
+            }
+            None => prompt_text = prompt_spec.prompt.to_string(),
+        }
+    }
+    // A prompt from --prompt-source also replaces the built-in translations
+    let localize_as = if from_source { "" } else { prompt_spec.name };
+    let (prompt_text, translated) = prompts::localize(localize_as, &prompt_text, args.prompt_lang);
+    if args.prompt_lang != PromptLang::En && !translated && prompt_spec.name != "custom" {
+        eprintln!(
+            "[EXTRACT] No {} translation of the {} prompt; using the English one with {} reading guidance",
+            args.prompt_lang.code(),
+            prompt_spec.name,
+            args.prompt_lang.code()
+        );
     }
+    // Versioned before the per-run variant and input sections are added
+    let versions = crate::migrate::PromptVersions::new(&prompt_text, &prompt_spec.schema);
+    let prompt_text = match &args.variant {
+        Some(part) => format!(
+            "{prompt_text}\n\n---\n\n## VARIANT SELECTION\n\n\
+             This datasheet covers several parts. Extract data ONLY for the orderable part number \
+             `{part}`: use the values, pins, packages, and options that apply to it and leave out \
+             other variants. Where the output has a part number field, set it to `{part}`.\n"
+        ),
+        None => prompt_text,
+    };
+    let prompt_text = if args.inputs.is_empty() {
+        prompt_text
+    } else {
+        prompts::render_inputs(&prompt_text, &args.inputs).map_err(Error::validation)?
+    };
 
-    let api_key = resolve_api_key(args.provider, args.api_key.clone())?;
+    let pages = match (args.retrieve, &args.pages) {
+        (Some(k), _) => crate::retrieve::select_pages(
+            &args.pdf,
+            &crate::retrieve::query(prompt_spec.name, &prompt_text),
+            k,
+            args.api_key.clone(),
+            args.base_url.clone(),
+        )?,
+        (None, Some(pages)) => Some(pdf_split::parse_page_ranges(pages)?),
+        (None, None) => None,
+    };
+    let preprocess = Preprocess {
+        pages,
+        clean: args.clean_pdf,
+    };
+
+    // Use task-specific default if user didn't specify a model
+    let model = resolve_model(&args.model, args.task.default_model());
 
-    // Check if PDF needs splitting before doing anything else
-    let split_result = pdf_split::split_if_needed(&args.pdf)?;
-    if let Some(ref split) = split_result {
-        return run_split_extract(args, split, &prompt_text, &prompt_spec, &api_key);
+    let result_key = result_cache_key(args, &preprocess, &model, &prompt_text, &prompt_spec.schema)?;
+    if crate::offline::is_enabled() {
+        let cached = crate::offline::cached_result(&result_key).ok_or_else(|| {
+            Error::not_found(format!(
+                "--offline: no cached {} result for {} with these options; run it once online first",
+                prompt_spec.name,
+                args.pdf.display()
+            ))
+        })?;
+        eprintln!("[OFFLINE] Using cached {} result for {}", prompt_spec.name, args.pdf.display());
+        return Ok((cached, versions));
     }
 
-    let client = build_client(args.provider, api_key.clone(), args.base_url.clone())?;
+    let api_key = resolve_api_key(args.provider, args.api_key.clone())?;
 
-    // Get attachment source - use file cache unless disabled
-    let attachment = if args.no_cache {
-        // Read file directly and send inline
-        let data = fs::read(&args.pdf)
-            .with_context(|| format!("reading {}", args.pdf.display()))?;
-        AttachmentSource::Inline(crate::llm::Attachment {
-            mime_type: "application/pdf".to_string(),
-            data,
-        })
-    } else {
-        // Use file cache to upload/retrieve the file
-        let mut cache = FileCache::new(api_key, args.base_url.clone())
-            .context("initializing file cache")?;
-        let cached = cache.get_or_upload(&args.pdf)
-            .context("getting or uploading file to Gemini")?;
-        AttachmentSource::FileUri(FileReference {
-            mime_type: "application/pdf".to_string(),
-            file_uri: cached.uri,
-        })
+    // Held until the result is stored, so identical concurrent extractions
+    // (e.g. a part listed twice in a BOM batch) wait and reuse it
+    let _claim = match crate::offline::claim_result(&result_key) {
+        crate::offline::Claim::Reuse(json) => return Ok((json, versions)),
+        crate::offline::Claim::Run(lock) => lock,
     };
 
-    // Use task-specific default if user didn't specify a model
-    let model = if args.model == __DEFAULT__ {
-        args.task.default_model().to_string()
-    } else {
-        args.model.clone()
+    // Check if PDF needs splitting before doing anything else; a page
+    // selection is expected to fit in one request
+    let split_result = match (&preprocess.pages, args.upload) {
+        _ if args.remote_url => None,
+        (Some(_), Upload::Chunked) => {
+            return Err(Error::validation("--upload chunked cannot be combined with --pages or --retrieve").into());
+        }
+        (Some(_), _) | (None, Upload::Inline | Upload::File) => None,
+        (None, Upload::Auto) => pdf_split::split_if_needed(&args.pdf, 1)?,
+        (None, Upload::Chunked) => pdf_split::split_if_needed(&args.pdf, 2)?,
+    };
+    let json = match split_result {
+        Some(ref split) => run_split_extract(args, split, &prompt_text, &prompt_spec, &api_key)?,
+        None => match run_single_extract(args, &preprocess, &model, &prompt_text, &prompt_spec, &api_key)
+        {
+            // Smaller pieces of the document need less output each
+            Err(err) if truncated(&err) => match pdf_split::split_if_needed(&args.pdf, 2)? {
+                Some(split) => {
+                    eprintln!("[EXTRACT] Response hit the output token limit; extracting in chunks");
+                    run_split_extract(args, &split, &prompt_text, &prompt_spec, &api_key)?
+                }
+                None => return Err(with_partial(args, &model, err, &[], None)),
+            },
+            result => result?,
+        },
+    };
+
+    crate::offline::store_result(&result_key, &json);
+    Ok((json, versions))
+}
+
+/// A copy of the PDF with the `--redact` patterns masked. Copies and their
+/// reports are kept in the cache directory, keyed by the PDF and the patterns.
+fn redacted_pdf(args: &ExtractArgs) -> Result<PathBuf> {
+    let redactor =
+        crate::pdf_redact::Redactor::new(&args.redact, args.redact_emails).map_err(Error::validation)?;
+    let source = crate::file_cache::hash_file(&args.pdf)?;
+    let key = crate::file_cache::compute_hash(format!("{}|{}", source, redactor.fingerprint()).as_bytes());
+    let dir = crate::file_cache::cache_root().join("redacted");
+    let path = dir.join(format!("{}.pdf", &key[..32]));
+    let report_path = path.with_extension("json");
+
+    // The cached report leaves out the patterns; they are filled in from the arguments
+    let cached = fs::read_to_string(&report_path)
+        .ok()
+        .and_then(|text| serde_json::from_str::<crate::pdf_redact::Report>(&text).ok())
+        .filter(|report| path.is_file() && report.patterns.len() == redactor.names().count());
+    let report = match cached {
+        Some(mut report) => {
+            for (entry, name) in report.patterns.iter_mut().zip(redactor.names()) {
+                entry.pattern = name.to_string();
+            }
+            report
+        }
+        None => {
+            let data = fs::read(&args.pdf).with_context(|| format!("reading {}", args.pdf.display()))?;
+            let (redacted, mut report) = crate::pdf_redact::redact(&data, &redactor)
+                .with_context(|| format!("redacting {}", args.pdf.display()))?;
+            fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+            crate::paths::write_file(&path, redacted).with_context(|| format!("writing {}", path.display()))?;
+            let names: Vec<String> = report.patterns.iter_mut().map(|p| std::mem::take(&mut p.pattern)).collect();
+            crate::paths::write_file(&report_path, serde_json::to_string(&report)?)?;
+            for (entry, name) in report.patterns.iter_mut().zip(names) {
+                entry.pattern = name;
+            }
+            report
+        }
     };
+    report.log();
+
+    if let Some(out) = &args.redact_report {
+        let json = json!({ "pdf": args.pdf, "redacted_pdf": path, "patterns": report.patterns });
+        crate::paths::write_file(out, serde_json::to_string_pretty(&json)?)
+            .with_context(|| format!("writing {}", out.display()))?;
+    }
+    Ok(path)
+}
 
-    let response = client.generate_json(LlmRequest {
+/// Identify an extraction by everything that determines its output.
+fn result_cache_key(
+    args: &ExtractArgs,
+    preprocess: &Preprocess,
+    model: &str,
+    prompt_text: &str,
+    schema: &Value,
+) -> Result<String> {
+    let source = if args.remote_url {
+        format!("url:{}", remote_url(args)?)
+    } else {
+        crate::file_cache::hash_file(&args.pdf)?
+    };
+    Ok(format!(
+        "{}|{}|{}|{:?}|{}|{}",
+        source,
+        preprocess.fingerprint(),
         model,
-        prompt: prompt_text,
-        schema: prompt_spec.schema,
+        args.temperature,
+        crate::file_cache::compute_hash(prompt_text.as_bytes()),
+        crate::file_cache::compute_hash(schema.to_string().as_bytes()),
+    ))
+}
+
+/// Extract from the whole PDF (or page selection) in one request. Output cut
+/// off at the token limit is returned as is when the caller can retry in
+/// chunks (see [`truncated`]); other failures get a partial-output file.
+fn run_single_extract(
+    args: &ExtractArgs,
+    preprocess: &Preprocess,
+    model: &str,
+    prompt_text: &str,
+    prompt_spec: &crate::prompts::PromptSpec,
+    api_key: &str,
+) -> Result<Value> {
+    let client = build_client(args.provider, api_key.to_string(), args.base_url.clone())?;
+
+    let attachment = attachment(args, &args.pdf, preprocess, api_key)?;
+
+    let request = LlmRequest {
+        model: model.to_string(),
+        prompt: prompt_text.to_string(),
+        schema: prompt_spec.schema.clone(),
         attachment,
         temperature: args.temperature,
-    })?;
-
-    write_output(&response.json, args.out.as_deref(), args.formatted)
-        .with_context(|| format!("writing {task_label} output for {}", args.pdf.display()))?;
+    };
+    let chunkable = args.upload == Upload::Auto && preprocess.pages.is_none() && !args.remote_url;
+    match generate_with_retry(&*client, request) {
+        Ok(response) => Ok(response.json),
+        Err(err) if truncated(&err) && chunkable => Err(err),
+        Err(err) => Err(with_partial(args, model, err, &[], None)),
+    }
+}
 
-    Ok(())
+/// Whether the model's output was cut off at the output token limit.
+/// Asking again would cut it off again; the input has to get smaller.
+fn truncated(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<MalformedResponse>()
+        .is_some_and(|m| m.finish_reason.as_deref() == Some("MAX_TOKENS"))
 }
 
 fn run_split_extract(
@@ -211,13 +743,19 @@ fn run_split_extract(
     prompt_text: &str,
     prompt_spec: &crate::prompts::PromptSpec,
     api_key: &str,
-) -> Result<()> {
+) -> Result<Value> {
     let client = build_client(args.provider, api_key.to_string(), args.base_url.clone())?;
-    let model = if args.model == __DEFAULT__ {
-        args.task.default_model().to_string()
-    } else {
-        args.model.clone()
+    let preprocess = Preprocess {
+        pages: None,
+        clean: args.clean_pdf,
     };
+    let model = resolve_model(&args.model, args.task.default_model());
+
+    // Upload later parts in the background while earlier ones are extracted
+    if !sends_inline(args) {
+        let parts = split.parts.iter().map(|part| (part.path.clone(), preprocess.clone())).collect();
+        crate::file_cache::prefetch(api_key, args.base_url.clone(), parts);
+    }
 
     let mut results: Vec<Value> = Vec::new();
 
@@ -230,43 +768,215 @@ fn run_split_extract(
             part.end_page
         );
 
-        let attachment = if args.no_cache {
-            let data = fs::read(&part.path)
-                .with_context(|| format!("reading split part {}", part.path.display()))?;
-            AttachmentSource::Inline(Attachment {
-                mime_type: "application/pdf".to_string(),
-                data,
-            })
-        } else {
-            let mut cache = FileCache::new(api_key.to_string(), args.base_url.clone())
-                .context("initializing file cache")?;
-            let cached = cache
-                .get_or_upload(&part.path)
-                .context("getting or uploading split part to Gemini")?;
-            AttachmentSource::FileUri(FileReference {
-                mime_type: "application/pdf".to_string(),
-                file_uri: cached.uri,
-            })
-        };
+        let attachment = attachment(args, &part.path, &preprocess, api_key)?;
 
-        let response = client.generate_json(LlmRequest {
+        let request = LlmRequest {
             model: model.clone(),
             prompt: prompt_text.to_string(),
             schema: prompt_spec.schema.clone(),
             attachment,
             temperature: args.temperature,
-        })?;
-
-        results.push(response.json);
+        };
+        match generate_with_retry(&*client, request) {
+            Ok(response) => results.push(response.json),
+            Err(err) => return Err(with_partial(args, &model, err, &results, Some(split.parts.len()))),
+        }
     }
 
     // Merge results from all parts
-    let merged = merge_extraction_results(&results);
+    Ok(merge_extraction_results(&results))
+}
+
+/// Send the PDF at `path` inline when the upload strategy allows it and it fits
+/// in a request, otherwise through the File API (via the file cache).
+fn attachment(args: &ExtractArgs, path: &Path, preprocess: &Preprocess, api_key: &str) -> Result<AttachmentSource> {
+    if args.remote_url {
+        return Ok(AttachmentSource::FileUri(FileReference {
+            mime_type: "application/pdf".to_string(),
+            file_uri: remote_url(args)?.to_string(),
+        }));
+    }
+    if sends_inline(args) {
+        // An untouched PDF that is too large is not read at all, only measured
+        let mut len = fs::metadata(path).with_context(|| format!("reading {}", path.display()))?.len() as usize;
+        if len <= INLINE_MAX_BYTES || !preprocess.fingerprint().is_empty() {
+            let data = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+            let data = preprocess.apply(data)?;
+            if data.len() <= INLINE_MAX_BYTES {
+                return Ok(AttachmentSource::Inline(Attachment {
+                    mime_type: "application/pdf".to_string(),
+                    data,
+                }));
+            }
+            len = data.len();
+        }
+        let size = format!("{:.1} MB", len as f64 / (1024.0 * 1024.0));
+        if args.upload == Upload::Inline {
+            return Err(Error::validation(format!(
+                "{} is {}, over the {} MB inline limit; use --upload file or --upload auto",
+                path.display(),
+                size,
+                INLINE_MAX_BYTES / (1024 * 1024)
+            ))
+            .into());
+        }
+        eprintln!("[UPLOAD] {} is {}, too large to send inline; using the File API", path.display(), size);
+    }
+    let mut cache = FileCache::new(api_key.to_string(), args.base_url.clone())
+        .context("initializing file cache")?;
+    let cached = cache
+        .get_or_upload_with(path, preprocess)
+        .context("getting or uploading file to Gemini")?;
+    Ok(AttachmentSource::FileUri(FileReference {
+        mime_type: "application/pdf".to_string(),
+        file_uri: cached.uri,
+    }))
+}
 
-    write_output(&merged, args.out.as_deref(), args.formatted)
-        .with_context(|| format!("writing merged output for {}", args.pdf.display()))?;
+/// The PDF argument as a URL for --remote-url. Gemini can only fetch public
+/// http(s) URLs; anything else is rejected before a request is made.
+fn remote_url(args: &ExtractArgs) -> Result<&str> {
+    let url = args.pdf.to_str().unwrap_or_default();
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(url)
+    } else {
+        Err(Error::validation(format!("--remote-url needs an http(s) URL, got {}", args.pdf.display())).into())
+    }
+}
 
-    Ok(())
+/// Whether the upload strategy sends PDFs inline (unless one is too large).
+fn sends_inline(args: &ExtractArgs) -> bool {
+    match args.upload {
+        Upload::Inline => true,
+        Upload::Auto | Upload::Chunked => args.no_cache,
+        Upload::File => false,
+    }
+}
+
+/// Send a request, asking again if the model returns malformed JSON.
+fn generate_with_retry(client: &dyn LlmClient, request: LlmRequest) -> Result<LlmResponse> {
+    let mut attempt = 0;
+    loop {
+        match client.generate_json(request.clone()) {
+            Err(err) if attempt < MALFORMED_RETRIES
+                && err.downcast_ref::<MalformedResponse>().is_some()
+                && !truncated(&err) =>
+            {
+                attempt += 1;
+                eprintln!("[EXTRACT] {}; retrying ({}/{})", err, attempt, MALFORMED_RETRIES);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Save what a failed extraction produced to a `.partial.json` file: results
+/// of the split parts that finished, whatever parses from a malformed
+/// response, the raw response text, and diagnostics. Returns `err` with the
+/// file's location added.
+fn with_partial(
+    args: &ExtractArgs,
+    model: &str,
+    err: anyhow::Error,
+    completed: &[Value],
+    total_parts: Option<usize>,
+) -> anyhow::Error {
+    let malformed = err.downcast_ref::<MalformedResponse>();
+    if malformed.is_none() && completed.is_empty() {
+        return err;
+    }
+    let salvaged = malformed.and_then(|m| salvage_json(&m.text));
+    let mut pieces = completed.to_vec();
+    pieces.extend(salvaged.iter().cloned());
+    let task = args.task.prompt().name;
+    let report = json!({
+        "task": task,
+        "pdf": args.pdf,
+        "variant": args.variant,
+        "model": model,
+        "error": format!("{:#}", err),
+        "diagnostics": {
+            "finish_reason": malformed.and_then(|m| m.finish_reason.clone()),
+            "output_tokens": malformed.and_then(|m| m.output_tokens),
+            "truncated": malformed.map(|m| m.truncated),
+            "raw_length": malformed.map(|m| m.text.len()),
+            "salvaged_from_raw": salvaged.is_some(),
+            "parts_completed": total_parts.map(|_| completed.len()),
+            "parts_total": total_parts,
+        },
+        "partial": if pieces.is_empty() { Value::Null } else { merge_extraction_results(&pieces) },
+        "raw_text": malformed.map(|m| m.text.as_str()),
+    });
+
+    let stem = args.pdf.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match &args.variant {
+        Some(part) => format!("{}.{}.partial.json", crate::paths::sanitize_filename(part), task),
+        None => format!("{}.{}.partial.json", stem, task),
+    };
+    let path = match &args.out {
+        Some(dir) if dir.is_dir() => dir.join(name),
+        Some(out) => out.with_extension("partial.json"),
+        None => crate::paths::reports_dir().join(name),
+    };
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        let _ = fs::create_dir_all(dir);
+    }
+    let written = serde_json::to_string_pretty(&report)
+        .map_err(anyhow::Error::from)
+        .and_then(|text| crate::paths::write_file(&path, text).map_err(anyhow::Error::from));
+    match written {
+        Ok(()) => {
+            eprintln!("[EXTRACT] Partial output written to {}", path.display());
+            err.context(format!("partial output saved to {}", path.display()))
+        }
+        Err(e) => {
+            eprintln!("[EXTRACT] Warning: could not write {}: {}", path.display(), e);
+            err
+        }
+    }
+}
+
+/// Best-effort parse of malformed model output: the first complete JSON value,
+/// or for truncated output, the longest prefix that parses once its open
+/// objects and arrays are closed.
+fn salvage_json(text: &str) -> Option<Value> {
+    const MAX_ATTEMPTS: usize = 64;
+    let text = text.trim_start();
+    if let Some(Ok(value)) = serde_json::Deserializer::from_str(text).into_iter::<Value>().next() {
+        return Some(value);
+    }
+    let mut open = Vec::new();
+    let (mut in_string, mut escaped) = (false, false);
+    // Places the text can be cut, with the brackets still open there
+    let mut cuts: Vec<(usize, Vec<u8>)> = Vec::new();
+    for (i, b) in text.bytes().enumerate() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' | b'[' => open.push(b),
+            b'}' | b']' => {
+                open.pop();
+                if !open.is_empty() {
+                    cuts.push((i + 1, open.clone()));
+                }
+            }
+            b',' => cuts.push((i, open.clone())),
+            _ => {}
+        }
+    }
+    cuts.iter().rev().take(MAX_ATTEMPTS).find_map(|(end, open)| {
+        let mut candidate = text[..*end].to_string();
+        candidate.extend(open.iter().rev().map(|&b| if b == b'{' { '}' } else { ']' }));
+        serde_json::from_str(&candidate).ok()
+    })
 }
 
 /// Merge extraction results from multiple PDF parts.
@@ -338,17 +1048,48 @@ fn load_text_or_file(input: &str) -> Result<String> {
     }
 }
 
-fn write_output(value: &Value, out: Option<&Path>, formatted: bool) -> Result<()> {
-    let rendered = if formatted {
-        serde_json::to_string_pretty(value)?
-    } else {
-        serde_json::to_string(value)?
-    };
-
+/// Write `value` to `out`, or print it. `--select` only applies to the printed form.
+pub(crate) fn write_output(value: &Value, out: Option<&Path>, formatted: bool) -> Result<()> {
     if let Some(path) = out {
-        fs::write(path, rendered)?;
+        let rendered = if formatted {
+            serde_json::to_string_pretty(value)?
+        } else {
+            serde_json::to_string(value)?
+        };
+        crate::paths::write_file(path, rendered).with_context(|| format!("writing {}", path.display()))?;
+    } else if formatted {
+        println!("{}", crate::select::to_string_pretty(value)?);
     } else {
-        println!("{rendered}");
+        println!("{}", crate::select::to_string(value)?);
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variant_parts() {
+        let matrix = json!({
+            "variants": [
+                {"root_part_number": "STM32F407", "orderable_part_numbers": ["STM32F407VGT6", "STM32F407ZGT6"]},
+                {"root_part_number": "STM32F405", "orderable_part_numbers": []},
+                {"root_part_number": "STM32F407", "orderable_part_numbers": ["STM32F407VGT6"]}
+            ]
+        });
+        assert_eq!(variant_parts(&matrix), ["STM32F407VGT6", "STM32F407ZGT6", "STM32F405"]);
+        assert!(variant_parts(&json!({"variants": []})).is_empty());
+    }
+
+    #[test]
+    fn test_salvage_json() {
+        let truncated = r#"{"part_number": "TPS62130", "pins": [{"number": 1, "name": "SW"}, {"number": 2, "name": "P{G"#;
+        assert_eq!(
+            salvage_json(truncated),
+            Some(json!({"part_number": "TPS62130", "pins": [{"number": 1, "name": "SW"}, {"number": 2}]}))
+        );
+        assert_eq!(salvage_json("{\"a\": [1, 2]} trailing"), Some(json!({"a": [1, 2]})));
+        assert_eq!(salvage_json("not json"), None);
+    }
+}
@@ -6,6 +6,7 @@
 //! Unlike footprint-image (which uses a hardcoded footprint-detection prompt),
 //! this command accepts a user-supplied description of what to find.
 
+use crate::extract::{DEFAULT_MODEL, __DEFAULT__, resolve_model};
 use crate::llm::{LlmProvider, resolve_api_key};
 use crate::page_render;
 use anyhow::{Result, anyhow};
@@ -13,7 +14,6 @@ use clap::Args;
 use std::path::PathBuf;
 
 const PROMPT_TEMPLATE: &str = include_str!("../prompts/extract-pages.md");
-const __DEFAULT__: &str = "__DEFAULT__";
 
 #[derive(Args, Debug)]
 pub struct ExtractPagesArgs {
@@ -28,9 +28,9 @@ pub struct ExtractPagesArgs {
     #[arg(long, short = 'b')]
     pub batch: Option<PathBuf>,
 
-    /// Output directory for extracted PNGs
-    #[arg(long, short = 'o', default_value = ".")]
-    pub out_dir: PathBuf,
+    /// Output directory for extracted PNGs (default: <download dir>/reports)
+    #[arg(long, short = 'o')]
+    pub out_dir: Option<PathBuf>,
 
     /// Render DPI
     #[arg(long, default_value = "300")]
@@ -41,11 +41,11 @@ pub struct ExtractPagesArgs {
     pub padding: u32,
 
     /// LLM provider
-    #[arg(long, default_value = "gemini", hide = true)]
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
     pub provider: LlmProvider,
 
     /// Model name
-    #[arg(long, default_value = __DEFAULT__)]
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
     pub model: String,
 
     /// API key
@@ -93,28 +93,29 @@ pub fn run(args: &ExtractPagesArgs) -> Result<()> {
         }
     };
 
-    std::fs::create_dir_all(&args.out_dir)
-        .map_err(|e| anyhow!("creating output directory {}: {}", args.out_dir.display(), e))?;
+    let out_dir = args.out_dir.clone().unwrap_or_else(crate::paths::reports_dir);
+    std::fs::create_dir_all(&out_dir)
+        .map_err(|e| anyhow!("creating output directory {}: {}", out_dir.display(), e))?;
 
     let api_key = resolve_api_key(args.provider, args.api_key.clone())?;
-    let model = if args.model == __DEFAULT__ {
-        "gemini-3.1-pro-preview".to_string()
-    } else {
-        args.model.clone()
-    };
+    let model = resolve_model(&args.model, DEFAULT_MODEL);
 
     let prompt = PROMPT_TEMPLATE.replace("{DESCRIPTIONS}", &descriptions);
 
+    let _usage = crate::usage::scope("extract-pages", Some(&args.pdf));
+
     // ── Step 1: Detect locations (auto-splits large PDFs) ─────────────
     let locations = page_render::detect_pages(
         &args.pdf,
         &prompt,
-        args.no_cache,
-        &api_key,
-        &args.base_url,
-        args.provider,
-        &model,
-        "EXTRACT-PAGES",
+        &page_render::DetectOptions {
+            api_key: &api_key,
+            base_url: &args.base_url,
+            provider: args.provider,
+            model: &model,
+            no_cache: args.no_cache,
+            log_prefix: "EXTRACT-PAGES",
+        },
     )?;
 
     if locations.is_empty() {
@@ -142,12 +143,14 @@ pub fn run(args: &ExtractPagesArgs) -> Result<()> {
     let (saved, outputs) = page_render::render_and_crop(
         &args.pdf,
         &locations,
-        args.padding,
-        args.dpi,
-        &args.out_dir,
+        &out_dir,
         &pdf_stem,
-        !args.bounded,
-        "EXTRACT-PAGES",
+        &page_render::RenderOptions {
+            padding: args.padding,
+            dpi: args.dpi,
+            whole_page: !args.bounded,
+            log_prefix: "EXTRACT-PAGES",
+        },
     )?;
 
     eprintln!("[EXTRACT-PAGES] Done — {} PNG(s) extracted.", saved);
@@ -167,7 +170,7 @@ pub fn run(args: &ExtractPagesArgs) -> Result<()> {
     }
 
     let manifest = serde_json::json!({ "extractions": extractions });
-    println!("{}", serde_json::to_string_pretty(&manifest)?);
+    println!("{}", crate::select::to_string_pretty(&manifest)?);
 
     Ok(())
 }
@@ -4,23 +4,230 @@
 //! Gemini File API cache for avoiding repeated PDF uploads.
 //!
 //! This module implements caching for the Gemini File API, storing file hashes
-//! mapped to their Gemini file URIs. Files uploaded to Gemini expire after 48 hours,
-//! so the cache automatically cleans up expired entries.
+//! mapped to their Gemini file URIs. Uploaded files expire at the `expirationTime`
+//! Gemini reports (48 hours by default), so the cache automatically cleans up
+//! expired entries. Large PDFs may stay in the `PROCESSING` state for a while
+//! after upload; they are polled until `ACTIVE` before being used.
+//! Cached files whose reported size or SHA-256 differ from what was uploaded
+//! are treated as missing and uploaded again.
+//!
+//! Entries are keyed by the SHA-256 of the source PDF plus a fingerprint of any
+//! preprocessing applied before upload (see [`Preprocess`]), so a trimmed
+//! upload is never mistaken for the full document.
+//!
+//! Everything the tool caches (Gemini file references, PDF splits, indexes)
+//! lives under one directory, `--cache-dir` / `DATASHEET_CACHE_DIR` or the
+//! platform cache directory. With `--cache-max-size` / `DATASHEET_CACHE_MAX_SIZE`
+//! the least recently used PDF splits are evicted to keep it within budget.
+//!
+//! Several processes may share the cache (e.g. parallel batch runs), so saves
+//! take an advisory lock, merge with whatever is on disk, and replace the file
+//! atomically via a temp file and rename.
+//!
+//! Uploads use Gemini's resumable protocol: when the connection drops midway,
+//! the upload resumes from the offset Gemini reports instead of starting over.
+//! [`prefetch`] uploads a batch of files in the background, at most
+//! `--upload-concurrency` at a time, so extraction of one file can overlap
+//! with the upload of the next. Threads of one process never upload the same
+//! file twice; a thread that needs a file another is uploading waits for it.
+//! Unmodified PDFs are hashed and uploaded straight from disk in chunks, so a
+//! 300 MB reference manual does not have to fit in memory.
+//! Their hashes are remembered by path, size, and mtime in `file_hashes.json`,
+//! so repeated runs on the same PDF skip reading it just to look it up.
+//!
+//! Entries evicted locally are also deleted from Gemini (when they have not
+//! already expired there), and `datasheet cache prune --remote` or the
+//! `--cleanup` flag on `extract`/`part` free the account's file storage quota
+//! after batch runs.
 
 use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use fs4::fs_std::FileExt;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::progress::{ProgressReader, Throttle};
 
-/// How long Gemini keeps uploaded files (48 hours)
+/// How long Gemini keeps uploaded files (48 hours), used when the response has no expirationTime
 const GEMINI_FILE_TTL_SECS: u64 = 48 * 60 * 60;
 
+/// How often to poll a file that is still processing
+const PROCESSING_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Give up waiting for a file to become active after this long (10 minutes)
+const PROCESSING_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
 /// Safety margin before expiration to avoid race conditions (1 hour)
 const EXPIRY_MARGIN_SECS: u64 = 60 * 60;
 
+/// Times an interrupted upload is resumed before giving up
+const UPLOAD_RESUME_ATTEMPTS: u32 = 3;
+
+/// Background uploads run by [`prefetch`] when `--upload-concurrency` is not given
+const DEFAULT_UPLOAD_CONCURRENCY: usize = 2;
+
+/// Cache directory override from `--cache-dir` / `DATASHEET_CACHE_DIR`
+/// Store of file hashes keyed by path, under the cache directory
+const KNOWN_HASHES_FILE: &str = "file_hashes.json";
+
+/// Hashes are only remembered for files last modified at least this long ago;
+/// a write within the filesystem's timestamp granularity could otherwise change
+/// the contents without changing size or mtime.
+const HASH_SETTLE: Duration = Duration::from_secs(2);
+
+static CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Size budget in bytes from `--cache-max-size` / `DATASHEET_CACHE_MAX_SIZE`
+static MAX_SIZE: OnceLock<u64> = OnceLock::new();
+
+/// Hashes of files uploaded or reused by this process, for `--cleanup`
+static SESSION_FILES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Concurrent background uploads from `--upload-concurrency` / `DATASHEET_UPLOAD_CONCURRENCY`
+static UPLOAD_CONCURRENCY: OnceLock<usize> = OnceLock::new();
+
+/// Cache keys some thread of this process is currently looking up or uploading
+static IN_FLIGHT: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static IN_FLIGHT_DONE: Condvar = Condvar::new();
+
+#[derive(Subcommand, Debug)]
+pub enum CacheSubcommand {
+    /// Drop expired entries from the local cache (and optionally from Gemini)
+    Prune {
+        /// Also delete the evicted files from Gemini's File API
+        #[arg(long)]
+        remote: bool,
+
+        /// Evict every cached file, not just expired ones (PDF splits included)
+        #[arg(long)]
+        all: bool,
+
+        /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+        #[arg(long)]
+        api_key: Option<String>,
+
+        /// Optional base URL override for Gemini API
+        #[arg(long)]
+        base_url: Option<String>,
+    },
+
+    /// Write the unexpired file references to a bundle others can import
+    Export {
+        /// Bundle file to write
+        output: PathBuf,
+    },
+
+    /// Add file references from a bundle made with `cache export`
+    Import {
+        /// Bundle file to read
+        input: PathBuf,
+    },
+}
+
+/// Preprocessing applied to a PDF before it is uploaded.
+///
+/// Every option that changes the uploaded bytes must be part of the
+/// fingerprint, otherwise derived uploads would collide in the cache.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preprocess {
+    /// 1-based inclusive page ranges to keep (`None` keeps every page)
+    pub pages: Option<Vec<(u32, u32)>>,
+    /// Strip watermarks and printer's marks (`--clean-pdf`)
+    pub clean: bool,
+}
+
+impl Preprocess {
+    /// Stable description of the options, empty when the PDF is uploaded as-is
+    pub fn fingerprint(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(ref ranges) = self.pages {
+            let ranges: Vec<String> = ranges
+                .iter()
+                .map(|&(start, end)| match end {
+                    u32::MAX => format!("{start}-"),
+                    end if end == start => start.to_string(),
+                    end => format!("{start}-{end}"),
+                })
+                .collect();
+            parts.push(format!("pages={}", ranges.join(",")));
+        }
+        if self.clean {
+            parts.push("clean".to_string());
+        }
+        parts.join(";")
+    }
+
+    /// Cache key for a source PDF hash with these options applied
+    fn cache_key(&self, hash: &str) -> String {
+        let fingerprint = self.fingerprint();
+        if fingerprint.is_empty() {
+            hash.to_string()
+        } else {
+            format!("{hash}+{fingerprint}")
+        }
+    }
+
+    /// Produce the bytes to upload from the source PDF
+    pub fn apply(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        let data = match self.pages {
+            Some(ref ranges) => crate::pdf_split::trim_pages(&data, ranges)?,
+            None => data,
+        };
+        if self.clean {
+            return crate::pdf_clean::clean(&data);
+        }
+        Ok(data)
+    }
+}
+
+/// Bytes to upload: an untouched PDF is streamed from disk, a preprocessed copy
+/// is kept in memory and shared with each (resumed) request body.
+enum UploadSource<'a> {
+    File(&'a Path),
+    Memory(Arc<Vec<u8>>),
+}
+
+impl UploadSource<'_> {
+    fn len(&self) -> Result<u64> {
+        match self {
+            UploadSource::File(path) => Ok(fs::metadata(path)
+                .with_context(|| format!("reading {}", path.display()))?
+                .len()),
+            UploadSource::Memory(data) => Ok(data.len() as u64),
+        }
+    }
+
+    /// The bytes from `offset` to the end
+    fn read_from(&self, offset: u64) -> Result<Box<dyn Read + Send>> {
+        match self {
+            UploadSource::File(path) => {
+                let mut file = fs::File::open(path).with_context(|| format!("reading {}", path.display()))?;
+                file.seek(SeekFrom::Start(offset))?;
+                Ok(Box::new(file))
+            }
+            UploadSource::Memory(data) => {
+                let mut cursor = std::io::Cursor::new(SharedBytes(Arc::clone(data)));
+                cursor.set_position(offset);
+                Ok(Box::new(cursor))
+            }
+        }
+    }
+}
+
+struct SharedBytes(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 /// Information about a file uploaded to Gemini
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CachedFile {
@@ -32,32 +239,128 @@ pub struct CachedFile {
     pub expires_at: u64,
     /// Original file size in bytes (for validation)
     pub file_size: u64,
+    /// SHA256 (hex) of the uploaded bytes; missing in entries from older versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
 }
 
 impl CachedFile {
     /// Check if this cached file has expired or is about to expire
     pub fn is_expired(&self) -> bool {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
         // Add margin to avoid using files that are about to expire
-        now + EXPIRY_MARGIN_SECS >= self.expires_at
+        now_secs() + EXPIRY_MARGIN_SECS >= self.expires_at
+    }
+
+    /// Whether Gemini still holds the file (it may be expired for our purposes)
+    fn exists_remotely(&self) -> bool {
+        now_secs() < self.expires_at
     }
 }
 
 /// Cache mapping file content hashes to Gemini file info
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct CacheData {
-    /// Map of SHA256 hash (hex) -> cached file info
+    /// Map of SHA256 hash (hex), plus any preprocessing fingerprint -> cached file info
     pub files: HashMap<String, CachedFile>,
 }
 
+impl CacheData {
+    /// Merge entries from another copy of the cache; ours win on conflict.
+    fn merge_from(&mut self, other: CacheData) {
+        for (hash, file) in other.files {
+            self.files.entry(hash).or_insert(file);
+        }
+    }
+}
+
+/// A file hash remembered in `file_hashes.json`, valid while size and mtime match
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KnownHash {
+    size: u64,
+    /// Modification time, nanoseconds since the Unix epoch
+    mtime_ns: u64,
+    hash: String,
+}
+
+/// File metadata as returned by the Gemini File API
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileInfo {
+    name: String,
+    uri: String,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    expiration_time: Option<String>,
+    /// int64, which the API sends as a JSON string
+    #[serde(default)]
+    size_bytes: Option<serde_json::Value>,
+    /// Base64 SHA-256 of the stored bytes
+    #[serde(default)]
+    sha256_hash: Option<String>,
+}
+
+impl FileInfo {
+    fn size(&self) -> Option<u64> {
+        match self.size_bytes.as_ref()? {
+            serde_json::Value::String(s) => s.parse().ok(),
+            value => value.as_u64(),
+        }
+    }
+
+    /// SHA-256 as hex. The API documents a base64 digest but has also been seen
+    /// to base64-encode the hex string, so accept both.
+    fn sha256(&self) -> Option<String> {
+        use base64::Engine;
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(self.sha256_hash.as_deref()?)
+            .ok()?;
+        match decoded.len() {
+            32 => Some(hex::encode(decoded)),
+            64 => String::from_utf8(decoded).ok().map(|s| s.to_ascii_lowercase()),
+            _ => None,
+        }
+    }
+
+    /// How the stored file differs from what was uploaded, if it does. Fields
+    /// the API (or an older cache entry) does not provide are not compared.
+    fn mismatch(&self, file_size: u64, sha256: Option<&str>) -> Option<String> {
+        if let Some(size) = self.size().filter(|&size| size != file_size) {
+            return Some(format!("{} bytes on Gemini, {} uploaded", size, file_size));
+        }
+        match (self.sha256(), sha256) {
+            (Some(remote), Some(local)) if remote != local => Some("SHA-256 differs".to_string()),
+            _ => None,
+        }
+    }
+
+    /// Processing state; responses without one are treated as ready
+    fn state(&self) -> &str {
+        self.state.as_deref().unwrap_or("ACTIVE")
+    }
+
+    /// Expiry from `expirationTime`, falling back to the default TTL from now.
+    fn expires_at(&self) -> u64 {
+        self.expiration_time
+            .as_deref()
+            .and_then(parse_rfc3339)
+            .unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+                    + GEMINI_FILE_TTL_SECS
+            })
+    }
+}
+
 /// Manages the file cache for Gemini uploads
 pub struct FileCache {
     cache_dir: PathBuf,
     cache_file: PathBuf,
     data: CacheData,
+    /// Entries removed by this instance (hash -> file name), so saves don't merge them back
+    evicted: HashMap<String, String>,
     api_key: String,
     base_url: String,
     client: reqwest::blocking::Client,
@@ -91,6 +394,7 @@ impl FileCache {
             cache_dir,
             cache_file,
             data,
+            evicted: HashMap::new(),
             api_key,
             base_url,
             client,
@@ -104,20 +408,48 @@ impl FileCache {
 
     /// Get or upload a file to Gemini, returning the cached file info
     pub fn get_or_upload(&mut self, path: &Path) -> Result<CachedFile> {
-        let file_data = fs::read(path)
-            .with_context(|| format!("reading {}", path.display()))?;
-        let hash = compute_hash(&file_data);
+        self.get_or_upload_with(path, &Preprocess::default())
+    }
+
+    /// Get or upload a preprocessed version of a file; preprocessing only runs on a cache miss
+    pub fn get_or_upload_with(&mut self, path: &Path, preprocess: &Preprocess) -> Result<CachedFile> {
+        let source_hash = hash_file(path)?;
+        let hash = preprocess.cache_key(&source_hash);
+
+        // Another thread may be uploading this very file; wait and use its upload
+        let (_in_flight, waited) = InFlight::claim(&hash);
+        if waited {
+            self.reload();
+        }
 
         // Check if we have a valid cached entry
-        if let Some(cached) = self.data.files.get(&hash) {
+        if let Some(cached) = self.data.files.get(&hash).cloned() {
             if !cached.is_expired() {
-                // Verify the file still exists on Gemini
-                match self.check_file_exists(&cached.name) {
-                    Ok(true) => {
-                        eprintln!("[CACHE] Using cached file: {}", cached.uri);
-                        return Ok(cached.clone());
-                    }
-                    Ok(false) => {
+                // Verify the file still exists on Gemini and is ready to use
+                match self.get_file(&cached.name).and_then(|info| match info {
+                    Some(info) => self.wait_until_active(info).map(Some),
+                    None => Ok(None),
+                }) {
+                    Ok(Some(info)) => match info.mismatch(cached.file_size, cached.sha256.as_deref()) {
+                        None => {
+                            eprintln!("[CACHE] Using cached file: {}", cached.uri);
+                            let cached = CachedFile {
+                                expires_at: info.expires_at(),
+                                ..cached
+                            };
+                            remember_session_file(&hash);
+                            self.data.files.insert(hash, cached.clone());
+                            self.save()?;
+                            return Ok(cached);
+                        }
+                        Some(problem) => {
+                            eprintln!("[CACHE] Cached file {} is damaged ({}), re-uploading", cached.name, problem);
+                            if let Err(e) = self.delete_file(&cached.name) {
+                                eprintln!("[CACHE] {}", e);
+                            }
+                        }
+                    },
+                    Ok(None) => {
                         eprintln!("[CACHE] Cached file no longer exists on Gemini, re-uploading");
                     }
                     Err(e) => {
@@ -135,9 +467,18 @@ impl FileCache {
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_else(|| "datasheet.pdf".to_string());
 
-        let cached_file = self.upload_file(&file_data, &display_name)?;
+        let (source, sha256) = if preprocess.fingerprint().is_empty() {
+            (UploadSource::File(path), source_hash)
+        } else {
+            let data = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+            let data = preprocess.apply(data)?;
+            let sha256 = compute_hash(&data);
+            (UploadSource::Memory(Arc::new(data)), sha256)
+        };
+        let cached_file = self.upload_file(&source, &display_name, sha256)?;
 
         // Store in cache and save
+        remember_session_file(&hash);
         self.data.files.insert(hash, cached_file.clone());
         self.save()?;
 
@@ -145,8 +486,9 @@ impl FileCache {
     }
 
     /// Upload a file to Gemini using the resumable upload API
-    fn upload_file(&self, data: &[u8], display_name: &str) -> Result<CachedFile> {
-        let file_size = data.len() as u64;
+    fn upload_file(&self, source: &UploadSource, display_name: &str, sha256: String) -> Result<CachedFile> {
+        crate::offline::ensure_online("Uploading to Gemini").map_err(|e| anyhow!(e))?;
+        let file_size = source.len()?;
         eprintln!("[CACHE] Uploading {} bytes to Gemini...", file_size);
 
         // Step 1: Start resumable upload to get upload URL
@@ -194,15 +536,37 @@ impl FileCache {
             .context("parsing upload URL")?
             .to_string();
 
-        // Step 2: Upload the actual bytes
-        let upload_resp = self.client
-            .post(&upload_url)
-            .header("Content-Length", file_size.to_string())
-            .header("X-Goog-Upload-Offset", "0")
-            .header("X-Goog-Upload-Command", "upload, finalize")
-            .body(data.to_vec())
-            .send()
-            .context("uploading file data")?;
+        // Step 2: Upload the actual bytes, resuming where Gemini left off if interrupted
+        crate::progress::emit("upload_started", serde_json::json!({ "file": display_name, "bytes": file_size }));
+        let mut offset = 0;
+        let mut attempt = 0;
+        let upload_resp = loop {
+            let remaining = file_size - offset;
+            let throttle = Throttle::new("upload", ("file", display_name.to_string()), Some(remaining));
+            let body = ProgressReader::new(source.read_from(offset)?, throttle);
+            let result = self.client
+                .post(&upload_url)
+                .header("Content-Length", remaining.to_string())
+                .header("X-Goog-Upload-Offset", offset.to_string())
+                .header("X-Goog-Upload-Command", "upload, finalize")
+                .body(reqwest::blocking::Body::sized(body, remaining))
+                .send();
+            let reason = match result {
+                Ok(resp) if !resp.status().is_server_error() => break resp,
+                Ok(resp) if attempt == UPLOAD_RESUME_ATTEMPTS => break resp,
+                Err(e) if attempt == UPLOAD_RESUME_ATTEMPTS => {
+                    return Err(anyhow::Error::new(e).context("uploading file data"));
+                }
+                Ok(resp) => format!("HTTP {}", resp.status()),
+                Err(e) => e.to_string(),
+            };
+            attempt += 1;
+            offset = self.received_bytes(&upload_url).context("querying interrupted upload")?.min(file_size);
+            eprintln!(
+                "[CACHE] Upload of {} interrupted ({}); resuming at byte {} ({}/{})",
+                display_name, reason, offset, attempt, UPLOAD_RESUME_ATTEMPTS
+            );
+        };
 
         if !upload_resp.status().is_success() {
             let status = upload_resp.status();
@@ -210,45 +574,61 @@ impl FileCache {
             return Err(anyhow!("Failed to upload file ({}): {}", status, body));
         }
 
-        let upload_result: serde_json::Value = upload_resp.json()
+        #[derive(Deserialize)]
+        struct UploadResponse {
+            file: FileInfo,
+        }
+        let upload_result: UploadResponse = upload_resp.json()
             .context("parsing upload response")?;
 
-        // Extract file info from response
-        let file_obj = upload_result
-            .get("file")
-            .ok_or_else(|| anyhow!("Missing 'file' in upload response"))?;
-
-        let name = file_obj
-            .get("name")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing 'name' in file response"))?
-            .to_string();
-
-        let uri = file_obj
-            .get("uri")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing 'uri' in file response"))?
-            .to_string();
-
-        // Calculate expiration time (48 hours from now)
-        let expires_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs()
-            + GEMINI_FILE_TTL_SECS;
-
-        eprintln!("[CACHE] Uploaded successfully: {}", uri);
+        eprintln!("[CACHE] Uploaded successfully: {}", upload_result.file.uri);
+        crate::progress::emit("upload_done", serde_json::json!({ "file": display_name, "uri": upload_result.file.uri }));
+        let info = self.wait_until_active(upload_result.file)?;
+        if let Some(problem) = info.mismatch(file_size, Some(&sha256)) {
+            let _ = self.delete_file(&info.name);
+            return Err(anyhow!("Upload of {} arrived damaged: {}", display_name, problem));
+        }
 
         Ok(CachedFile {
-            name,
-            uri,
-            expires_at,
+            expires_at: info.expires_at(),
+            name: info.name,
+            uri: info.uri,
             file_size,
+            sha256: Some(sha256),
         })
     }
 
-    /// Check if a file still exists on Gemini
-    fn check_file_exists(&self, name: &str) -> Result<bool> {
+    /// How many bytes of a resumable upload Gemini has stored
+    fn received_bytes(&self, upload_url: &str) -> Result<u64> {
+        let resp = self.client
+            .post(upload_url)
+            .header("X-Goog-Upload-Command", "query")
+            .header("Content-Length", "0")
+            .send()
+            .context("sending upload query")?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("Upload query failed ({})", resp.status()));
+        }
+        resp.headers()
+            .get("x-goog-upload-size-received")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| anyhow!("Upload query response has no x-goog-upload-size-received header"))
+    }
+
+    /// Pick up entries other threads or processes have saved since we loaded
+    fn reload(&mut self) {
+        if let Some(on_disk) = fs::read_to_string(&self.cache_file)
+            .ok()
+            .and_then(|content| serde_json::from_str::<CacheData>(&content).ok())
+        {
+            self.data.merge_from(on_disk);
+        }
+    }
+
+    /// Fetch file metadata from Gemini; `None` if the file no longer exists
+    fn get_file(&self, name: &str) -> Result<Option<FileInfo>> {
+        crate::offline::ensure_online("Checking Gemini files").map_err(|e| anyhow!(e))?;
         let url = format!(
             "{}/{}?key={}",
             self.base_url, name, self.api_key
@@ -260,69 +640,575 @@ impl FileCache {
             .context("checking file existence")?;
 
         if resp.status().is_success() {
-            // Parse response to check state
-            let info: serde_json::Value = resp.json()
-                .context("parsing file info")?;
-
-            // Check if file is in ACTIVE state
-            let state = info
-                .get("state")
-                .and_then(|v| v.as_str())
-                .unwrap_or("UNKNOWN");
-
-            Ok(state == "ACTIVE")
+            Ok(Some(resp.json().context("parsing file info")?))
         } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
-            Ok(false)
+            Ok(None)
         } else {
             Err(anyhow!("Unexpected status checking file: {}", resp.status()))
         }
     }
 
+    /// Delete a file from Gemini; files that are already gone count as deleted
+    fn delete_file(&self, name: &str) -> Result<()> {
+        crate::offline::ensure_online("Deleting Gemini files").map_err(|e| anyhow!(e))?;
+        let url = format!(
+            "{}/{}?key={}",
+            self.base_url, name, self.api_key
+        );
+
+        let resp = self.client
+            .delete(&url)
+            .send()
+            .context("deleting file")?;
+
+        if resp.status().is_success() || resp.status() == reqwest::StatusCode::NOT_FOUND {
+            Ok(())
+        } else {
+            let status = resp.status();
+            let body = resp.text().unwrap_or_default();
+            Err(anyhow!("Failed to delete {} ({}): {}", name, status, body))
+        }
+    }
+
+    /// Poll a `PROCESSING` file until it becomes `ACTIVE`
+    fn wait_until_active(&self, mut info: FileInfo) -> Result<FileInfo> {
+        let started = std::time::Instant::now();
+        loop {
+            match info.state() {
+                "ACTIVE" => return Ok(info),
+                "PROCESSING" => {}
+                state => return Err(anyhow!("File {} is in state {}", info.name, state)),
+            }
+            if started.elapsed() >= PROCESSING_TIMEOUT {
+                return Err(anyhow!(
+                    "File {} still processing after {}s",
+                    info.name,
+                    PROCESSING_TIMEOUT.as_secs()
+                ));
+            }
+            eprintln!("[CACHE] Waiting for Gemini to finish processing {}...", info.name);
+            std::thread::sleep(PROCESSING_POLL_INTERVAL);
+            info = self
+                .get_file(&info.name)?
+                .ok_or_else(|| anyhow!("File {} disappeared while processing", info.name))?;
+        }
+    }
+
     /// Remove expired entries from the cache
     fn cleanup_expired(&mut self) {
-        let before_count = self.data.files.len();
-        self.data.files.retain(|_, cached| !cached.is_expired());
-        let removed = before_count - self.data.files.len();
-        if removed > 0 {
-            eprintln!("[CACHE] Cleaned up {} expired entries", removed);
+        let expired: Vec<String> = self
+            .data
+            .files
+            .iter()
+            .filter(|(_, cached)| cached.is_expired())
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        if !expired.is_empty() {
+            // Without a key (local-only `cache prune`) or offline, leave remote files alone
+            let remote = !self.api_key.is_empty() && !crate::offline::is_enabled();
+            let deleted = self.evict(&expired, remote);
+            eprintln!(
+                "[CACHE] Cleaned up {} expired entries ({} deleted from Gemini)",
+                expired.len(),
+                deleted
+            );
             // Save after cleanup
             let _ = self.save();
         }
     }
 
-    /// Save the cache to disk
-    fn save(&self) -> Result<()> {
+    /// Drop entries from the cache, optionally deleting them from Gemini.
+    ///
+    /// Returns how many files were deleted remotely. Files past their Gemini
+    /// expiration are skipped since Gemini has already removed them.
+    fn evict(&mut self, hashes: &[String], remote: bool) -> usize {
+        let mut deleted = 0;
+        for hash in hashes {
+            let Some(cached) = self.data.files.remove(hash) else {
+                continue;
+            };
+            if remote && cached.exists_remotely() {
+                match self.delete_file(&cached.name) {
+                    Ok(()) => deleted += 1,
+                    Err(e) => eprintln!("[CACHE] Warning: {}", e),
+                }
+            }
+            self.evicted.insert(hash.clone(), cached.name);
+        }
+        deleted
+    }
+
+    /// The unexpired entries, for `cache export`
+    pub fn export(&self) -> CacheData {
+        let files = self
+            .data
+            .files
+            .iter()
+            .filter(|(_, cached)| !cached.is_expired())
+            .map(|(hash, cached)| (hash.clone(), cached.clone()))
+            .collect();
+        CacheData { files }
+    }
+
+    /// Add unexpired entries from a bundle where there is no usable local
+    /// one, returning how many were added. Local entries win.
+    pub fn import(&mut self, bundle: CacheData) -> Result<usize> {
+        let mut added = 0;
+        for (hash, cached) in bundle.files {
+            let usable = self.data.files.get(&hash).is_some_and(|ours| !ours.is_expired());
+            if !usable && !cached.is_expired() {
+                self.evicted.remove(&hash);
+                self.data.files.insert(hash, cached);
+                added += 1;
+            }
+        }
+        self.save()?;
+        Ok(added)
+    }
+
+    /// Evict expired entries (or all with `all`), returning (evicted, deleted remotely)
+    pub fn prune(&mut self, remote: bool, all: bool) -> Result<(usize, usize)> {
+        let hashes: Vec<String> = self
+            .data
+            .files
+            .iter()
+            .filter(|(_, cached)| all || cached.is_expired())
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        let deleted = self.evict(&hashes, remote);
+        self.save()?;
+        Ok((hashes.len(), deleted))
+    }
+
+    /// Evict the files this process used and delete them from Gemini
+    pub fn cleanup_session(&mut self) -> Result<usize> {
+        let hashes = std::mem::take(&mut *SESSION_FILES.lock().unwrap_or_else(|e| e.into_inner()));
+        let deleted = self.evict(&hashes, true);
+        self.save()?;
+        Ok(deleted)
+    }
+
+    /// Save the cache to disk, merging with entries written by other processes
+    fn save(&mut self) -> Result<()> {
         // Ensure cache directory exists
         fs::create_dir_all(&self.cache_dir)
             .context("creating cache directory")?;
 
+        // Hold an exclusive lock for the read-merge-write cycle; released on drop
+        let lock = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.cache_dir.join("gemini_files.json.lock"))
+            .context("opening cache lock file")?;
+        FileExt::lock_exclusive(&lock).context("locking cache file")?;
+
+        if let Some(on_disk) = fs::read_to_string(&self.cache_file)
+            .ok()
+            .and_then(|content| serde_json::from_str::<CacheData>(&content).ok())
+        {
+            self.data.merge_from(on_disk);
+        }
+        self.data.files.retain(|hash, cached| {
+            !cached.is_expired() && self.evicted.get(hash) != Some(&cached.name)
+        });
+
         let content = serde_json::to_string_pretty(&self.data)
             .context("serializing cache")?;
 
-        fs::write(&self.cache_file, content)
+        // Write to a temp file and rename so readers never see a partial file
+        let tmp = self
+            .cache_dir
+            .join(format!("gemini_files.json.{}.tmp", std::process::id()));
+        fs::write(&tmp, content)
             .context("writing cache file")?;
+        fs::rename(&tmp, &self.cache_file)
+            .context("replacing cache file")?;
 
         Ok(())
     }
 }
 
+/// Claim on a cache key, released (waking any waiting thread) on drop.
+struct InFlight(String);
+
+impl InFlight {
+    /// Claim `key`, first waiting until no other thread holds it. Also returns
+    /// whether it had to wait, in which case the cache on disk has changed.
+    fn claim(key: &str) -> (Self, bool) {
+        let mut keys = IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner());
+        let mut waited = false;
+        while keys.iter().any(|k| k == key) {
+            waited = true;
+            keys = IN_FLIGHT_DONE.wait(keys).unwrap_or_else(|e| e.into_inner());
+        }
+        keys.push(key.to_string());
+        (InFlight(key.to_string()), waited)
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut keys = IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner());
+        keys.retain(|k| *k != self.0);
+        IN_FLIGHT_DONE.notify_all();
+    }
+}
+
+/// Upload `files` in the background, at most `--upload-concurrency` at a time
+/// and in order, so each is in the cache by the time the caller needs it.
+/// Failures are only logged: the caller's own lookup retries the upload.
+pub fn prefetch(api_key: &str, base_url: Option<String>, files: Vec<(PathBuf, Preprocess)>) {
+    let workers = upload_concurrency().min(files.len());
+    let queue = Arc::new(Mutex::new(VecDeque::from(files)));
+    for _ in 0..workers {
+        let queue = Arc::clone(&queue);
+        let (api_key, base_url) = (api_key.to_string(), base_url.clone());
+        std::thread::spawn(move || {
+            let mut cache = match FileCache::new(api_key, base_url) {
+                Ok(cache) => cache,
+                Err(e) => return eprintln!("[CACHE] Background uploads disabled: {e:#}"),
+            };
+            loop {
+                let next = queue.lock().unwrap_or_else(|e| e.into_inner()).pop_front();
+                let Some((path, preprocess)) = next else { break };
+                if let Err(e) = cache.get_or_upload_with(&path, &preprocess) {
+                    eprintln!("[CACHE] Background upload of {} failed: {e:#}", path.display());
+                }
+            }
+        });
+    }
+}
+
+/// Record that this process used the cached file for `hash`
+fn remember_session_file(hash: &str) {
+    let mut files = SESSION_FILES.lock().unwrap_or_else(|e| e.into_inner());
+    if !files.iter().any(|h| h == hash) {
+        files.push(hash.to_string());
+    }
+}
+
+/// Evict and delete the Gemini files used by this process (for `--cleanup`)
+pub fn cleanup_session(api_key: String, base_url: Option<String>) -> Result<()> {
+    if SESSION_FILES.lock().unwrap_or_else(|e| e.into_inner()).is_empty() {
+        return Ok(());
+    }
+    let mut cache = FileCache::new(api_key, base_url)?;
+    let deleted = cache.cleanup_session()?;
+    eprintln!("[CACHE] Deleted {} file(s) from Gemini", deleted);
+    Ok(())
+}
+
+pub fn execute(command: CacheSubcommand) -> Result<(), String> {
+    match command {
+        CacheSubcommand::Prune {
+            remote,
+            all,
+            api_key,
+            base_url,
+        } => {
+            // Only remote deletion needs a real key; local pruning works without one
+            let api_key = if remote {
+                crate::llm::resolve_api_key(crate::llm::LlmProvider::Gemini, api_key)
+                    .map_err(|e| e.to_string())?
+            } else {
+                api_key.unwrap_or_default()
+            };
+            let mut cache = FileCache::new(api_key, base_url).map_err(|e| e.to_string())?;
+            let (evicted, deleted) = cache.prune(remote, all).map_err(|e| e.to_string())?;
+            if all {
+                let splits = cache_root().join("splits");
+                if splits.exists() {
+                    fs::remove_dir_all(&splits).map_err(|e| format!("removing {}: {}", splits.display(), e))?;
+                }
+            } else {
+                enforce_budget();
+            }
+            if remote {
+                println!("Evicted {} cached file(s), deleted {} from Gemini", evicted, deleted);
+            } else {
+                println!("Evicted {} cached file(s)", evicted);
+            }
+            Ok(())
+        }
+        CacheSubcommand::Export { output } => {
+            let cache = FileCache::new(String::new(), None).map_err(|e| e.to_string())?;
+            let bundle = cache.export();
+            let content = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+            crate::paths::write_file(&output, content).map_err(|e| format!("writing {}: {}", output.display(), e))?;
+            println!("Exported {} cached file(s) to {}", bundle.files.len(), output.display());
+            Ok(())
+        }
+        CacheSubcommand::Import { input } => {
+            let content = fs::read_to_string(&input).map_err(|e| format!("reading {}: {}", input.display(), e))?;
+            let bundle: CacheData = serde_json::from_str(&content)
+                .map_err(|e| format!("{}: not a cache bundle: {}", input.display(), e))?;
+            let total = bundle.files.len();
+            let mut cache = FileCache::new(String::new(), None).map_err(|e| e.to_string())?;
+            let added = cache.import(bundle).map_err(|e| e.to_string())?;
+            println!("Imported {} of {} cached file(s) from {}", added, total, input.display());
+            Ok(())
+        }
+    }
+}
+
+/// Current Unix time in seconds
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 /// Compute SHA256 hash of data and return as hex string
-fn compute_hash(data: &[u8]) -> String {
+pub(crate) fn compute_hash(data: &[u8]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(data);
     let result = hasher.finalize();
     hex::encode(result)
 }
 
-/// Get the cache directory path
-fn get_cache_dir() -> Result<PathBuf> {
+/// SHA256 of a file. Hashes are remembered by path, size, and modification
+/// time, so an unchanged PDF is not read again on the next run.
+pub(crate) fn hash_file(path: &Path) -> Result<String> {
+    hash_file_with(&cache_root().join(KNOWN_HASHES_FILE), path)
+}
+
+fn hash_file_with(store: &Path, path: &Path) -> Result<String> {
+    let meta = fs::metadata(path).with_context(|| format!("reading {}", path.display()))?;
+    let modified = meta.modified().ok();
+    let mtime_ns = modified
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64);
+    let key = fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned();
+
+    let known = load_known_hashes(store);
+    if let Some(known) = known.get(&key).filter(|k| k.size == meta.len() && Some(k.mtime_ns) == mtime_ns) {
+        return Ok(known.hash.clone());
+    }
+
+    let hash = hash_contents(path)?;
+    let settled = modified.and_then(|t| t.elapsed().ok()).is_some_and(|age| age >= HASH_SETTLE);
+    if let (Some(mtime_ns), true) = (mtime_ns, settled) {
+        let known = KnownHash { size: meta.len(), mtime_ns, hash: hash.clone() };
+        // Best effort: if this fails the file is just hashed again next time
+        let _ = remember_hash(store, key, known);
+    }
+    Ok(hash)
+}
+
+/// SHA256 of a file, read in chunks so large PDFs are never held in memory
+fn hash_contents(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path).with_context(|| format!("reading {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).with_context(|| format!("reading {}", path.display()))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn load_known_hashes(store: &Path) -> HashMap<String, KnownHash> {
+    fs::read_to_string(store)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Add one hash to the store, dropping entries for files that no longer exist.
+/// Locked, merged, and replaced atomically like the upload cache.
+fn remember_hash(store: &Path, key: String, known: KnownHash) -> Result<()> {
+    let dir = store.parent().ok_or_else(|| anyhow!("invalid hash store path"))?;
+    fs::create_dir_all(dir)?;
+    let lock = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(store.with_extension("json.lock"))?;
+    FileExt::lock_exclusive(&lock)?;
+
+    let mut hashes = load_known_hashes(store);
+    hashes.insert(key, known);
+    hashes.retain(|path, _| Path::new(path).exists());
+
+    let tmp = store.with_extension(format!("json.{}.tmp", std::process::id()));
+    fs::write(&tmp, serde_json::to_string(&hashes)?)?;
+    fs::rename(&tmp, store)?;
+    Ok(())
+}
+
+/// Override the cache directory (called once from `main`)
+pub fn set_cache_dir(path: PathBuf) {
+    let _ = CACHE_DIR.set(path);
+}
+
+/// Set how many files [`prefetch`] uploads at once (called once from `main`)
+pub fn set_upload_concurrency(uploads: usize) {
+    let _ = UPLOAD_CONCURRENCY.set(uploads.max(1));
+}
+
+fn upload_concurrency() -> usize {
+    UPLOAD_CONCURRENCY.get().copied().unwrap_or(DEFAULT_UPLOAD_CONCURRENCY)
+}
+
+/// Set the cache size budget in bytes (called once from `main`)
+pub fn set_max_size(bytes: u64) {
+    let _ = MAX_SIZE.set(bytes);
+}
+
+/// Root directory for everything the tool caches
+pub fn cache_root() -> PathBuf {
+    if let Some(dir) = CACHE_DIR.get() {
+        return dir.clone();
+    }
+
     // Try to get platform-specific cache directory
     if let Some(cache_dir) = dirs::cache_dir() {
-        return Ok(cache_dir.join("datasheet-cli"));
+        return cache_dir.join("datasheet-cli");
     }
 
     // Fallback to .cache in current directory
-    Ok(PathBuf::from(".cache").join("datasheet-cli"))
+    PathBuf::from(".cache").join("datasheet-cli")
+}
+
+/// Get the cache directory path
+fn get_cache_dir() -> Result<PathBuf> {
+    Ok(cache_root())
+}
+
+/// Mark a cached file as recently used for LRU eviction
+pub(crate) fn touch(path: &Path) {
+    if let Ok(file) = fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// Parse a size like "500M", "2GB", "1.5GiB" or plain bytes (binary units)
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let trimmed = s.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}'", s))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return Err(format!("invalid size unit in '{}'", s)),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Evict least recently used PDF splits until the cache fits the size budget.
+///
+/// Other cached files count toward the total but are small and never evicted.
+pub fn enforce_budget() {
+    if let Some(&budget) = MAX_SIZE.get() {
+        evict_lru(budget);
+    }
+}
+
+/// Evict split sets, oldest first, until the cache directory is within `budget` bytes
+fn evict_lru(budget: u64) -> usize {
+    let root = cache_root();
+    let mut total = dir_size(&root);
+    if total <= budget {
+        return 0;
+    }
+
+    // Each split set is one unit; last use is the mtime of its metadata file
+    let mut units: Vec<(SystemTime, u64, PathBuf)> = fs::read_dir(root.join("splits"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .map(|path| {
+            let used = fs::metadata(path.join("split_meta.json"))
+                .or_else(|_| fs::metadata(&path))
+                .and_then(|m| m.modified())
+                .unwrap_or(UNIX_EPOCH);
+            (used, dir_size(&path), path)
+        })
+        .collect();
+    units.sort();
+
+    let mut evicted = 0;
+    for (_, size, path) in units {
+        if total <= budget {
+            break;
+        }
+        if fs::remove_dir_all(&path).is_ok() {
+            total = total.saturating_sub(size);
+            evicted += 1;
+        }
+    }
+    if evicted > 0 {
+        eprintln!(
+            "[CACHE] Evicted {} PDF split set(s) to stay within {} bytes (now {})",
+            evicted, budget, total
+        );
+    }
+    if total > budget {
+        eprintln!("[CACHE] Warning: cache is {} bytes, over the {} byte budget", total, budget);
+    }
+    evicted
+}
+
+/// Total size of all files under `path`
+fn dir_size(path: &Path) -> u64 {
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            _ => entry.metadata().map(|m| m.len()).unwrap_or(0),
+        })
+        .sum()
+}
+
+/// Parse an RFC 3339 UTC timestamp ("2024-05-01T12:34:56.789Z") into Unix seconds
+fn parse_rfc3339(s: &str) -> Option<u64> {
+    let (date, time) = s.trim().split_once('T')?;
+    let mut date_parts = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (date_parts.next()??, date_parts.next()??, date_parts.next()??);
+
+    // Split off the zone designator; only UTC ("Z" or "+00:00") is produced by Gemini
+    let (clock, offset_secs) = match time.find(['Z', 'z', '+', '-']) {
+        Some(i) => {
+            let zone = &time[i..];
+            let offset = if zone.eq_ignore_ascii_case("z") {
+                0
+            } else {
+                let sign = if zone.starts_with('-') { -1 } else { 1 };
+                let (h, m) = zone[1..].split_once(':')?;
+                sign * (h.parse::<i64>().ok()? * 3600 + m.parse::<i64>().ok()? * 60)
+            };
+            (&time[..i], offset)
+        }
+        None => (time, 0),
+    };
+    let mut clock_parts = clock.splitn(3, ':');
+    let hour: i64 = clock_parts.next()?.parse().ok()?;
+    let minute: i64 = clock_parts.next()?.parse().ok()?;
+    let second: f64 = clock_parts.next()?.parse().ok()?;
+
+    // Days since the Unix epoch for a proleptic Gregorian date
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let secs = days * 86_400 + hour * 3600 + minute * 60 + second as i64 - offset_secs;
+    u64::try_from(secs).ok()
 }
 
 /// Simple hex encoding (avoiding another dependency)
@@ -347,6 +1233,106 @@ mod tests {
         assert_eq!(hash.len(), 64); // SHA256 produces 32 bytes = 64 hex chars
     }
 
+    #[test]
+    fn test_hash_file_matches_compute_hash() {
+        // Larger than one read buffer, so the chunked hasher takes several reads
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let path = std::env::temp_dir().join(format!("datasheet-hash-test-{}", std::process::id()));
+        fs::write(&path, &data).unwrap();
+        assert_eq!(hash_contents(&path).unwrap(), compute_hash(&data));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_hash_file_remembers_by_mtime() {
+        let dir = std::env::temp_dir().join(format!("datasheet-known-hashes-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = dir.join(KNOWN_HASHES_FILE);
+        let path = dir.join("a.pdf");
+        let hash = compute_hash(b"test data");
+        let set_mtime = |secs_ago: u64| {
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(secs_ago)).unwrap();
+        };
+
+        // Just written: hashed but not remembered
+        fs::write(&path, b"test data").unwrap();
+        assert_eq!(hash_file_with(&store, &path).unwrap(), hash);
+        assert!(load_known_hashes(&store).is_empty());
+
+        // Remembered once settled, and reused while size and mtime match
+        set_mtime(3600);
+        let mtime = fs::metadata(&path).unwrap().modified().unwrap();
+        assert_eq!(hash_file_with(&store, &path).unwrap(), hash);
+        assert_eq!(load_known_hashes(&store).len(), 1);
+        fs::write(&path, b"TEST DATA").unwrap();
+        fs::File::options().write(true).open(&path).unwrap().set_modified(mtime).unwrap();
+        assert_eq!(hash_file_with(&store, &path).unwrap(), hash);
+
+        // A new mtime means a re-hash
+        set_mtime(60);
+        assert_eq!(hash_file_with(&store, &path).unwrap(), compute_hash(b"TEST DATA"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_in_flight_waits_for_other_thread() {
+        let (first, waited) = InFlight::claim("test-in-flight");
+        assert!(!waited);
+        let other = std::thread::spawn(|| InFlight::claim("test-in-flight").1);
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!other.is_finished());
+        drop(first);
+        assert!(other.join().unwrap());
+        assert!(!InFlight::claim("test-in-flight").1);
+    }
+
+    #[test]
+    fn test_preprocess_cache_key() {
+        assert_eq!(Preprocess::default().cache_key("abc"), "abc");
+        let trimmed = Preprocess {
+            pages: Some(vec![(1, 5), (9, 9), (12, u32::MAX)]),
+            clean: false,
+        };
+        assert_eq!(trimmed.cache_key("abc"), "abc+pages=1-5,9,12-");
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("500M"), Ok(500 << 20));
+        assert_eq!(parse_size("1.5 GiB"), Ok(3 << 29));
+        assert!(parse_size("2 parsecs").is_err());
+    }
+
+    #[test]
+    fn test_merge_from() {
+        let file = |name: &str| CachedFile {
+            name: name.to_string(),
+            uri: name.to_string(),
+            expires_at: u64::MAX,
+            file_size: 1,
+            sha256: None,
+        };
+        let mut ours = CacheData::default();
+        ours.files.insert("a".to_string(), file("ours-a"));
+        let mut theirs = CacheData::default();
+        theirs.files.insert("a".to_string(), file("theirs-a"));
+        theirs.files.insert("b".to_string(), file("theirs-b"));
+
+        ours.merge_from(theirs);
+        assert_eq!(ours.files["a"].name, "ours-a");
+        assert_eq!(ours.files["b"].name, "theirs-b");
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_rfc3339("2024-03-01T12:34:56.789012Z"), Some(1_709_296_496));
+        assert_eq!(parse_rfc3339("2024-03-01T14:34:56+02:00"), Some(1_709_296_496));
+        assert_eq!(parse_rfc3339("not a date"), None);
+    }
+
     #[test]
     fn test_expiry_check() {
         let now = SystemTime::now()
@@ -360,6 +1346,7 @@ mod tests {
             uri: "test".to_string(),
             expires_at: now + 2 * 3600, // 2 hours from now
             file_size: 100,
+            sha256: None,
         };
         assert!(!cached.is_expired());
 
@@ -369,6 +1356,7 @@ mod tests {
             uri: "test".to_string(),
             expires_at: now - 1, // Already passed
             file_size: 100,
+            sha256: None,
         };
         assert!(cached.is_expired());
 
@@ -378,7 +1366,69 @@ mod tests {
             uri: "test".to_string(),
             expires_at: now + 30 * 60, // 30 min from now (within 1 hour margin)
             file_size: 100,
+            sha256: None,
         };
         assert!(cached.is_expired());
     }
+
+    #[test]
+    fn test_import_keeps_usable_local_entries() {
+        let file = |name: &str, expires_at: u64| CachedFile {
+            name: name.to_string(),
+            uri: name.to_string(),
+            expires_at,
+            file_size: 1,
+            sha256: None,
+        };
+        let dir = std::env::temp_dir().join(format!("datasheet-import-test-{}", std::process::id()));
+        let mut cache = FileCache {
+            cache_file: dir.join("gemini_files.json"),
+            cache_dir: dir.clone(),
+            data: CacheData::default(),
+            evicted: HashMap::new(),
+            api_key: String::new(),
+            base_url: String::new(),
+            client: reqwest::blocking::Client::new(),
+        };
+        cache.data.files.insert("a".to_string(), file("files/ours", u64::MAX));
+        cache.data.files.insert("b".to_string(), file("files/stale", 1));
+
+        let mut bundle = CacheData::default();
+        bundle.files.insert("a".to_string(), file("files/theirs-a", u64::MAX));
+        bundle.files.insert("b".to_string(), file("files/theirs-b", u64::MAX));
+        bundle.files.insert("c".to_string(), file("files/expired", 1));
+        assert_eq!(cache.import(bundle).unwrap(), 1);
+        assert_eq!(cache.data.files["a"].name, "files/ours");
+        assert_eq!(cache.data.files["b"].name, "files/theirs-b");
+        assert!(!cache.data.files.contains_key("c"));
+        assert_eq!(cache.export().files.len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_remote_mismatch() {
+        let sha256 = compute_hash(b"%PDF-1.7");
+        let info = |size: serde_json::Value, hash: &str| FileInfo {
+            name: "files/abc".to_string(),
+            uri: "files/abc".to_string(),
+            state: None,
+            expiration_time: None,
+            size_bytes: Some(size),
+            sha256_hash: Some(hash.to_string()),
+        };
+        use base64::Engine;
+        let b64 = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+        let digest = b64(&Sha256::digest(b"%PDF-1.7"));
+
+        assert_eq!(info("8".into(), &digest).mismatch(8, Some(&sha256)), None);
+        assert_eq!(info(8.into(), &b64(sha256.as_bytes())).mismatch(8, Some(&sha256)), None);
+        assert_eq!(
+            info("5".into(), &digest).mismatch(8, Some(&sha256)).as_deref(),
+            Some("5 bytes on Gemini, 8 uploaded")
+        );
+        let other = b64(&Sha256::digest(b"%PDF-1.4"));
+        assert!(info("8".into(), &other).mismatch(8, Some(&sha256)).is_some());
+        // Entries cached before hashes were recorded only compare sizes
+        assert_eq!(info("8".into(), &other).mismatch(8, None), None);
+    }
 }
@@ -0,0 +1,643 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! `datasheet find`: parametric part search from a friendly spec.
+//!
+//! `--function LDO --package SOT-23-5 --vout 3.3` (or a free-text description
+//! the LLM turns into the same fields) becomes distributor filters. For
+//! DigiKey the specs are matched against the parametric filters of the
+//! category the function keyword mostly lands in and sent as
+//! ParameterId/ValueId filters; a spec no filter value matches is searched
+//! for as a keyword instead. Mouser has no parametric search, so its keyword
+//! results are checked here against each listing's product attributes.
+
+use anyhow::{Result, anyhow};
+use clap::Args;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::auth;
+use crate::bom::Source;
+use crate::db::parse_number;
+use crate::digikey;
+use crate::error::Error;
+use crate::extract::{__DEFAULT__, resolve_model};
+use crate::llm::{AttachmentSource, LlmProvider, LlmRequest, build_client, resolve_api_key};
+use crate::mouser;
+use crate::stock::{self, StockInfo, TableFormat, TableRow};
+
+const PROMPT: &str = include_str!("../prompts/find-spec.md");
+
+/// Columns of the default summary, per distributor.
+const SUMMARY_COLUMNS: &str = "mpn,mfr,sku,stock,price,desc";
+
+/// Friendly spec names, how they compare, and the distributor parameter names
+/// read for them (lowercase, DigiKey's then Mouser's, most specific first).
+const FRIENDLY: &[(&str, Compare, &[&str])] = &[
+    ("package", Compare::Text, &["supplier device package", "package / case", "package"]),
+    ("vout", Compare::Eq, &["voltage - output (min/fixed)", "voltage - output", "output voltage"]),
+    (
+        "vin",
+        Compare::Min,
+        &["voltage - input (max)", "voltage - supply", "input voltage, max", "input voltage - max", "supply voltage - max"],
+    ),
+    ("iout", Compare::Min, &["current - output", "output current"]),
+];
+
+#[derive(Args, Debug)]
+pub struct FindArgs {
+    /// Free-text requirement for the LLM to turn into filters,
+    /// e.g. "3.3 V LDO in SOT-23-5, 300 mA, runs from 12 V"
+    pub description: Option<String>,
+
+    /// Kind of part, searched as the keyword (e.g. "LDO", "buck converter")
+    #[arg(long, required_unless_present = "description")]
+    pub function: Option<String>,
+
+    /// Package as distributors list it (e.g. SOT-23-5, SOIC-8, 0603)
+    #[arg(long)]
+    pub package: Option<String>,
+
+    /// Output voltage, matched within 1% (e.g. 3.3)
+    #[arg(long)]
+    pub vout: Option<String>,
+
+    /// Input voltage the part must withstand (its maximum input voltage is at least this)
+    #[arg(long)]
+    pub vin: Option<String>,
+
+    /// Output current the part must supply (e.g. 500mA)
+    #[arg(long)]
+    pub iout: Option<String>,
+
+    /// Any other parameter by its distributor name: "Name=Value", "Name>=Value",
+    /// or "Name<=Value" (repeatable)
+    #[arg(long)]
+    pub spec: Vec<String>,
+
+    /// Only parts in stock
+    #[arg(long)]
+    pub in_stock: bool,
+
+    /// Distributors to search (default: digikey and mouser when configured)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub source: Vec<Source>,
+
+    /// Results per distributor
+    #[arg(long, short, default_value = "10")]
+    pub limit: usize,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    /// Print one table with these columns instead of the summary, e.g.
+    /// "distributor,mpn,stock,price@100" (see `mouser search --columns`)
+    #[arg(long, conflicts_with = "json")]
+    pub columns: Option<String>,
+
+    /// Print CSV (of --columns, default mpn,mfr,stock,price,lifecycle)
+    #[arg(long, conflicts_with = "json")]
+    pub csv: bool,
+
+    /// LLM provider
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
+    pub provider: LlmProvider,
+
+    /// Model name for translating the description
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
+    pub model: String,
+
+    /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Optional base URL override for Gemini API
+    #[arg(long)]
+    pub base_url: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Compare {
+    /// Same package or text, ignoring case and punctuation
+    Text,
+    /// Same number within 1%
+    Eq,
+    /// At least the number
+    Min,
+    /// At most the number
+    Max,
+}
+
+/// One requirement on a distributor parameter.
+#[derive(Serialize, Debug, Clone)]
+struct Spec {
+    /// Friendly name from [`FRIENDLY`] or a distributor parameter name
+    name: String,
+    value: String,
+    compare: Compare,
+    /// Parameter names read for it, lowercase, most specific first
+    #[serde(skip)]
+    names: Vec<String>,
+    /// Also read parameters whose name contains `name` (for `--spec` names)
+    #[serde(skip)]
+    fuzzy: bool,
+}
+
+impl Spec {
+    /// `bound` is `Min` or `Max` for "Name>=Value" and "Name<=Value".
+    fn new(name: &str, value: &str, bound: Option<Compare>) -> Self {
+        let (name, value) = (name.trim(), value.trim().to_string());
+        if let Some(&(friendly, compare, names)) = FRIENDLY.iter().find(|(n, _, _)| n.eq_ignore_ascii_case(name)) {
+            return Spec {
+                name: friendly.to_string(),
+                value,
+                compare: bound.unwrap_or(compare),
+                names: names.iter().map(|n| n.to_string()).collect(),
+                fuzzy: false,
+            };
+        }
+        let numeric = value.starts_with(|c: char| c.is_ascii_digit() || ".+-".contains(c)) && parse_number(&value).is_some();
+        Spec {
+            name: name.to_string(),
+            compare: bound.unwrap_or(if numeric { Compare::Eq } else { Compare::Text }),
+            value,
+            names: vec![name.to_lowercase()],
+            fuzzy: true,
+        }
+    }
+
+    fn label(&self) -> String {
+        let op = match self.compare {
+            Compare::Min => ">=",
+            Compare::Max => "<=",
+            Compare::Text | Compare::Eq => "=",
+        };
+        format!("{}{}{}", self.name, op, self.value)
+    }
+
+    /// Numeric specs need a number to compare with.
+    fn check(&self) -> Result<(), Error> {
+        if self.compare != Compare::Text && parse_number(&self.value).is_none() {
+            return Err(Error::validation(format!("{}: '{}' is not a number", self.name, self.value)));
+        }
+        Ok(())
+    }
+
+    /// Whether a distributor's value for the parameter meets the spec.
+    fn accepts(&self, value: &str) -> bool {
+        let numbers = || Some((parse_number(&self.value)?, range(value)?));
+        match (self.compare, numbers()) {
+            (Compare::Text, _) => text_matches(&self.value, value),
+            (Compare::Eq, Some((want, (low, _)))) => (low - want).abs() <= want.abs() * 0.01,
+            (Compare::Min, Some((want, (_, high)))) => high >= want,
+            (Compare::Max, Some((want, (low, _)))) => low <= want,
+            (_, None) => false,
+        }
+    }
+
+    /// Items whose name is one of the spec's parameter names, in the spec's
+    /// order of preference, then (for `--spec` names) ones containing it.
+    fn parameters<'a, T>(&self, items: &'a [T], name: impl Fn(&T) -> &str) -> Vec<&'a T> {
+        let mut found: Vec<&T> = Vec::new();
+        for wanted in &self.names {
+            found.extend(items.iter().filter(|item| name(item).trim().eq_ignore_ascii_case(wanted)));
+        }
+        if self.fuzzy {
+            for item in items {
+                if name(item).to_lowercase().contains(&self.names[0]) && !found.iter().any(|f| std::ptr::eq(*f, item)) {
+                    found.push(item);
+                }
+            }
+        }
+        found
+    }
+}
+
+/// "Name=Value", "Name>=Value", or "Name<=Value".
+fn parse_spec(spec: &str) -> Result<Spec, Error> {
+    let parsed = spec
+        .split_once(">=")
+        .map(|(n, v)| (n, v, Some(Compare::Min)))
+        .or_else(|| spec.split_once("<=").map(|(n, v)| (n, v, Some(Compare::Max))))
+        .or_else(|| spec.split_once('=').map(|(n, v)| (n, v, None)));
+    match parsed {
+        Some((name, value, bound)) if !name.trim().is_empty() && !value.trim().is_empty() => {
+            Ok(Spec::new(name, value, bound))
+        }
+        _ => Err(Error::validation(format!(
+            "Invalid --spec '{}': expected 'Name=Value', 'Name>=Value', or 'Name<=Value'",
+            spec
+        ))),
+    }
+}
+
+/// Lowercase letters and digits only: "SOT-23-5" → "sot235".
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Whether any of the comma-separated names in `value` is `want`, allowing a
+/// suffix such as "Thin" but not more digits: "SOT-23" does not match
+/// "SOT-23-5", and "0603" matches "0603 (1608 Metric)".
+fn text_matches(want: &str, value: &str) -> bool {
+    let want = normalize(want);
+    !want.is_empty()
+        && value.split(',').any(|piece| {
+            let piece = normalize(piece.split('(').next().unwrap_or(piece));
+            piece.strip_prefix(&want).is_some_and(|rest| !rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+}
+
+/// The low and high end of "2.5V ~ 5.5V" or "4.5 V to 18 V"; a single value is both.
+fn range(value: &str) -> Option<(f64, f64)> {
+    let (low, high) = value.split_once('~').or_else(|| value.split_once(" to ")).unwrap_or((value, value));
+    let (low, high) = (parse_number(low)?, parse_number(high)?);
+    Some((low.min(high), low.max(high)))
+}
+
+/// What to search for: the function keyword and the specs.
+#[derive(Serialize, Debug)]
+struct Query {
+    function: String,
+    specs: Vec<Spec>,
+}
+
+/// A spec sent to DigiKey as a parametric filter.
+#[derive(Serialize, Debug)]
+struct AppliedSpec {
+    spec: String,
+    parameter: String,
+    /// Filter values it accepts
+    values: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct Listing {
+    #[serde(flatten)]
+    info: StockInfo,
+    description: Option<String>,
+}
+
+/// One distributor's search and results.
+#[derive(Serialize, Debug)]
+struct SourceResult {
+    distributor: &'static str,
+    keyword: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    filters: Vec<AppliedSpec>,
+    /// Specs no DigiKey filter value matches (searched as keywords or ignored)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    unmatched: Vec<String>,
+    /// Matches at the distributor; for Mouser, the listings checked here
+    total: usize,
+    listings: Vec<Listing>,
+}
+
+#[derive(Serialize, Debug)]
+struct SourceError {
+    source: &'static str,
+    error: String,
+}
+
+pub fn run(args: &FindArgs) -> Result<()> {
+    let query = build_query(args)?;
+    let sources = if args.source.is_empty() { configured_sources() } else { args.source.clone() };
+    if sources.is_empty() {
+        return Err(Error::auth(
+            "find searches DigiKey and Mouser; set DIGIKEY_CLIENT_ID/DIGIKEY_CLIENT_SECRET or MOUSER_API_KEY",
+        )
+        .into());
+    }
+    if let Some(other) = sources.iter().find(|s| !matches!(s, Source::Digikey | Source::Mouser)) {
+        return Err(Error::validation(format!("find searches digikey and mouser, not {}", other.name())).into());
+    }
+    let table = TableFormat::from_args(args.columns.as_deref(), args.csv).map_err(|e| anyhow!(e))?;
+
+    let mut results = Vec::new();
+    let mut errors: Vec<(Source, Error)> = Vec::new();
+    for &source in &sources {
+        let result = match source {
+            Source::Digikey => search_digikey(&query, args.in_stock, args.limit),
+            _ => search_mouser(&query, args.in_stock, args.limit),
+        };
+        match result {
+            Ok(result) => results.push(result),
+            Err(e) => errors.push((source, e)),
+        }
+    }
+    if results.is_empty() {
+        let (_, first) = errors.remove(0);
+        for (source, e) in &errors {
+            eprintln!("[FIND] {}: {}", source.name(), e);
+        }
+        return Err(first.into());
+    }
+
+    crate::history::record(results.iter().flat_map(|r| r.listings.iter().map(|l| l.info.clone())));
+
+    if args.json {
+        let errors: Vec<SourceError> =
+            errors.iter().map(|(s, e)| SourceError { source: s.name(), error: e.to_string() }).collect();
+        let output = serde_json::json!({ "query": query, "results": results, "errors": errors });
+        let json = crate::select::to_string_pretty(&output).map_err(|e| anyhow!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if let Some(table) = table {
+        let rows: Vec<TableRow> = results.iter().flat_map(|r| r.listings.iter().map(table_row)).collect();
+        table.print(&rows);
+    } else {
+        let columns = stock::parse_columns(SUMMARY_COLUMNS).map_err(|e| anyhow!(e))?;
+        for result in &results {
+            print_result(result, &columns);
+        }
+    }
+    for (source, e) in &errors {
+        eprintln!("[FIND] {}: {}", source.name(), e);
+    }
+    Ok(())
+}
+
+/// DigiKey and Mouser when their credentials are set.
+fn configured_sources() -> Vec<Source> {
+    let mut sources = Vec::new();
+    if auth::credential("DIGIKEY_CLIENT_ID").is_some() && auth::credential("DIGIKEY_CLIENT_SECRET").is_some() {
+        sources.push(Source::Digikey);
+    }
+    if auth::credential("MOUSER_API_KEY").is_some() {
+        sources.push(Source::Mouser);
+    }
+    sources
+}
+
+/// The function and specs from the flags, on top of the LLM's reading of the
+/// description when there is one.
+fn build_query(args: &FindArgs) -> Result<Query> {
+    let mut specs = Vec::new();
+    let friendly = [("package", &args.package), ("vout", &args.vout), ("vin", &args.vin), ("iout", &args.iout)];
+    for (name, value) in friendly {
+        if let Some(value) = value {
+            specs.push(Spec::new(name, value, None));
+        }
+    }
+    for spec in &args.spec {
+        specs.push(parse_spec(spec)?);
+    }
+    for spec in &specs {
+        spec.check()?;
+    }
+
+    let mut function = args.function.clone();
+    if let Some(description) = &args.description {
+        let (llm_function, llm_specs) = translate(args, description)?;
+        function = function.or(Some(llm_function));
+        // Flags override what the LLM read for the same parameter
+        for spec in llm_specs {
+            if specs.iter().any(|s| s.names.iter().any(|n| spec.names.contains(n))) {
+                continue;
+            }
+            match spec.check() {
+                Ok(()) => specs.push(spec),
+                Err(e) => eprintln!("[FIND] Warning: ignoring {}", e),
+            }
+        }
+    }
+    let function = function.filter(|f| !f.trim().is_empty()).ok_or_else(|| {
+        Error::validation("No part function given; pass --function (e.g. --function LDO)")
+    })?;
+
+    let labels: Vec<String> = specs.iter().map(Spec::label).collect();
+    eprintln!("[FIND] {} {}", function, labels.join(" "));
+    Ok(Query { function: function.trim().to_string(), specs })
+}
+
+/// Have the LLM read the function and specs from a free-text requirement.
+fn translate(args: &FindArgs, description: &str) -> Result<(String, Vec<Spec>)> {
+    let api_key = resolve_api_key(args.provider, args.api_key.clone())?;
+    let model = resolve_model(&args.model, "gemini-3-flash-preview");
+    let client = build_client(args.provider, api_key, args.base_url.clone())?;
+    let _usage = crate::usage::scope("find", None);
+    eprintln!("[FIND] Translating the description with {}", model);
+    let response = client.generate_json(LlmRequest {
+        model,
+        prompt: format!("{}\n\n## Requirement\n{}", PROMPT, description),
+        schema: spec_schema(),
+        attachment: AttachmentSource::Multiple(Vec::new()),
+        temperature: Some(0.0),
+    })?;
+    Ok(read_translation(&response.json))
+}
+
+fn spec_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "function": { "type": "string" },
+            "package": { "type": "string" },
+            "specs": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "value": { "type": "string" },
+                        "compare": { "type": "string", "enum": ["eq", "min", "max"] }
+                    },
+                    "required": ["name", "value", "compare"]
+                }
+            }
+        },
+        "required": ["function", "package", "specs"]
+    })
+}
+
+fn read_translation(json: &Value) -> (String, Vec<Spec>) {
+    let text = |value: &Value, key: &str| value.get(key).and_then(|v| v.as_str()).unwrap_or_default().trim().to_string();
+    let mut specs = Vec::new();
+    let package = text(json, "package");
+    if !package.is_empty() {
+        specs.push(Spec::new("package", &package, None));
+    }
+    for item in json.get("specs").and_then(|s| s.as_array()).into_iter().flatten() {
+        let (name, value) = (text(item, "name"), text(item, "value"));
+        if name.is_empty() || value.is_empty() {
+            continue;
+        }
+        let bound = match text(item, "compare").as_str() {
+            "min" => Some(Compare::Min),
+            "max" => Some(Compare::Max),
+            _ => None,
+        };
+        specs.push(Spec::new(&name, &value, bound));
+    }
+    (text(json, "function"), specs)
+}
+
+fn search_digikey(query: &Query, in_stock: bool, limit: usize) -> Result<SourceResult, Error> {
+    let category = digikey::category_parameters(&query.function)?
+        .ok_or_else(|| Error::not_found(format!("No DigiKey category found for '{}'", query.function)))?;
+
+    let mut keyword = query.function.clone();
+    let mut filters = Vec::new();
+    let mut applied = Vec::new();
+    let mut unmatched = Vec::new();
+    for spec in &query.specs {
+        let matched = spec.parameters(&category.parameters, |p| &p.name).into_iter().find_map(|parameter| {
+            let values: Vec<&(String, String)> = parameter.values.iter().filter(|(_, name)| spec.accepts(name)).collect();
+            (!values.is_empty()).then_some((parameter, values))
+        });
+        match matched {
+            Some((parameter, values)) => {
+                applied.push(AppliedSpec {
+                    spec: spec.label(),
+                    parameter: parameter.name.clone(),
+                    values: values.iter().map(|(_, name)| name.clone()).collect(),
+                });
+                filters.push((parameter.id, values.into_iter().map(|(id, _)| id.clone()).collect()));
+            }
+            // A bound is no use as a keyword
+            None if matches!(spec.compare, Compare::Min | Compare::Max) => {
+                eprintln!("[FIND] Warning: no DigiKey filter in {} matches {}; ignoring it", category.name, spec.label());
+                unmatched.push(spec.label());
+            }
+            None => {
+                eprintln!(
+                    "[FIND] Warning: no DigiKey filter in {} matches {}; searching for it as a keyword",
+                    category.name,
+                    spec.label()
+                );
+                keyword = format!("{} {}", keyword, spec.value);
+                unmatched.push(spec.label());
+            }
+        }
+    }
+
+    let (rows, total) = digikey::parametric_search(&keyword, category.id, filters, in_stock, limit)?;
+    Ok(SourceResult {
+        distributor: Source::Digikey.name(),
+        keyword,
+        category: Some(category.name),
+        filters: applied,
+        unmatched,
+        total,
+        listings: rows.into_iter().map(|row| Listing { info: row.info, description: row.description }).collect(),
+    })
+}
+
+fn search_mouser(query: &Query, in_stock: bool, limit: usize) -> Result<SourceResult, Error> {
+    let mut keyword = query.function.clone();
+    if let Some(package) = query.specs.iter().find(|s| s.name == "package") {
+        keyword = format!("{} {}", keyword, package.value);
+    }
+    let listings = mouser::search_with_parameters(&keyword)?;
+    let total = listings.len();
+    let listings = listings
+        .into_iter()
+        .filter(|(row, attributes)| {
+            (!in_stock || row.info.stock.is_some_and(|s| s > 0)) && meets(&query.specs, &attributes.parameters)
+        })
+        .take(limit)
+        .map(|(row, _)| Listing { info: row.info, description: row.description })
+        .collect();
+    Ok(SourceResult {
+        distributor: Source::Mouser.name(),
+        keyword,
+        category: None,
+        filters: Vec::new(),
+        unmatched: Vec::new(),
+        total,
+        listings,
+    })
+}
+
+/// Whether a listing's attributes meet every spec. Attributes the listing
+/// does not have are not held against it, since Mouser's keyword results
+/// often carry only a few.
+fn meets(specs: &[Spec], attributes: &[(String, String)]) -> bool {
+    specs.iter().all(|spec| {
+        let present: Vec<&(String, String)> = attributes.iter().filter(|(_, v)| !matches!(v.trim(), "" | "-")).collect();
+        match spec.parameters(&present, |(name, _)| name).first() {
+            Some((_, value)) => spec.accepts(value),
+            None => true,
+        }
+    })
+}
+
+fn table_row(listing: &Listing) -> TableRow {
+    TableRow { info: listing.info.clone(), description: listing.description.clone() }
+}
+
+fn print_result(result: &SourceResult, columns: &[stock::Column]) {
+    match &result.category {
+        Some(category) => println!(
+            "DigiKey: {} of {} match(es) in {} for \"{}\"",
+            result.listings.len(),
+            result.total,
+            category,
+            result.keyword
+        ),
+        None => println!(
+            "Mouser: {} of {} listing(s) for \"{}\" match (attributes checked here)",
+            result.listings.len(),
+            result.total,
+            result.keyword
+        ),
+    }
+    for applied in &result.filters {
+        println!("  {}: {}", applied.parameter, applied.values.join(", "));
+    }
+    if !result.unmatched.is_empty() {
+        println!("  No filter for: {}", result.unmatched.join(", "));
+    }
+    if !result.listings.is_empty() {
+        let rows: Vec<TableRow> = result.listings.iter().map(table_row).collect();
+        print!("{}", stock::render_table(&rows, columns, false));
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_specs() {
+        let package = Spec::new("package", "SOT-23-5", None);
+        assert!(package.accepts("SOT-23-5"));
+        assert!(package.accepts("SOT-23-5 Thin, TSOT-23-5"));
+        assert!(package.accepts("SC-74A, SOT-753, SOT-23-5"));
+        assert!(!package.accepts("SOT-23-6"));
+        assert!(!Spec::new("package", "SOT-23", None).accepts("SOT-23-5"));
+        assert!(Spec::new("package", "0603", None).accepts("0603 (1608 Metric)"));
+
+        let vout = Spec::new("vout", "3.3", None);
+        assert!(vout.accepts("3.3V") && vout.accepts("3.30 V"));
+        assert!(!vout.accepts("3V") && !vout.accepts("Adjustable"));
+        let vin = Spec::new("vin", "16", None);
+        assert!(vin.accepts("18V") && vin.accepts("2.5V ~ 20V"));
+        assert!(!vin.accepts("6V"));
+        assert!(Spec::new("iout", "300mA", None).accepts("0.5 A"));
+
+        let custom = parse_spec("Current - Quiescent (Iq) <= 10uA").unwrap();
+        assert_eq!((custom.compare, custom.names[0].as_str()), (Compare::Max, "current - quiescent (iq)"));
+        assert!(custom.accepts("5 µA") && !custom.accepts("50µA"));
+        assert_eq!(parse_spec("Output Type=Fixed").unwrap().compare, Compare::Text);
+        assert_eq!(parse_spec("vin>=12V").unwrap().label(), "vin>=12V");
+        assert!(parse_spec("Fixed").is_err());
+        assert!(Spec::new("vout", "three", None).check().is_err());
+
+        // Mouser attributes: a rejected value drops the listing, a missing one does not
+        let specs = [package, vout];
+        let attributes = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+        };
+        assert!(meets(&specs, &attributes(&[("Package / Case", "SOT-23-5"), ("Output Voltage", "3.3 V")])));
+        assert!(meets(&specs, &attributes(&[("Package / Case", "SOT-23-5")])));
+        assert!(!meets(&specs, &attributes(&[("Package / Case", "SOT-23-5"), ("Output Voltage", "1.8 V")])));
+    }
+}
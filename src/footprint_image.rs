@@ -7,6 +7,7 @@
 //! 1. Send PDF to Gemini (auto-splitting large PDFs) → bounding boxes
 //! 2. Render only the needed pages via mupdf, crop, save as PNG
 
+use crate::extract::{DEFAULT_MODEL, __DEFAULT__, resolve_model};
 use crate::llm::{LlmProvider, resolve_api_key};
 use crate::page_render;
 use anyhow::{Result, anyhow};
@@ -14,16 +15,15 @@ use clap::Args;
 use std::path::PathBuf;
 
 const PROMPT: &str = include_str!("../prompts/extract-footprint-image.md");
-const __DEFAULT__: &str = "__DEFAULT__";
 
 #[derive(Args, Debug)]
 pub struct FootprintImageArgs {
     /// Input PDF path
     pub pdf: PathBuf,
 
-    /// Output directory for extracted footprint PNGs
-    #[arg(long, short = 'o', default_value = ".")]
-    pub out_dir: PathBuf,
+    /// Output directory for extracted footprint PNGs (default: <download dir>/reports)
+    #[arg(long, short = 'o')]
+    pub out_dir: Option<PathBuf>,
 
     /// Render DPI (higher = better quality, larger files)
     #[arg(long, default_value = "300")]
@@ -34,11 +34,11 @@ pub struct FootprintImageArgs {
     pub padding: u32,
 
     /// LLM provider
-    #[arg(long, default_value = "gemini", hide = true)]
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
     pub provider: LlmProvider,
 
     /// Model name
-    #[arg(long, default_value = __DEFAULT__)]
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
     pub model: String,
 
     /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
@@ -62,26 +62,27 @@ pub fn run(args: &FootprintImageArgs) -> Result<()> {
     if !args.pdf.exists() {
         return Err(anyhow!("PDF not found: {}", args.pdf.display()));
     }
-    std::fs::create_dir_all(&args.out_dir)
-        .map_err(|e| anyhow!("creating output directory {}: {}", args.out_dir.display(), e))?;
+    let out_dir = args.out_dir.clone().unwrap_or_else(crate::paths::reports_dir);
+    std::fs::create_dir_all(&out_dir)
+        .map_err(|e| anyhow!("creating output directory {}: {}", out_dir.display(), e))?;
 
     let api_key = resolve_api_key(args.provider, args.api_key.clone())?;
-    let model = if args.model == __DEFAULT__ {
-        "gemini-3.1-pro-preview".to_string()
-    } else {
-        args.model.clone()
-    };
+    let model = resolve_model(&args.model, DEFAULT_MODEL);
+
+    let _usage = crate::usage::scope("footprint-image", Some(&args.pdf));
 
     // ── Step 1: Detect footprint locations (auto-splits large PDFs) ────
     let all_footprints = page_render::detect_pages(
         &args.pdf,
         PROMPT,
-        args.no_cache,
-        &api_key,
-        &args.base_url,
-        args.provider,
-        &model,
-        "FOOTPRINT-IMAGE",
+        &page_render::DetectOptions {
+            api_key: &api_key,
+            base_url: &args.base_url,
+            provider: args.provider,
+            model: &model,
+            no_cache: args.no_cache,
+            log_prefix: "FOOTPRINT-IMAGE",
+        },
     )?;
 
     if all_footprints.is_empty() {
@@ -111,12 +112,14 @@ pub fn run(args: &FootprintImageArgs) -> Result<()> {
     let (saved, _) = page_render::render_and_crop(
         &args.pdf,
         &all_footprints,
-        args.padding,
-        args.dpi,
-        &args.out_dir,
+        &out_dir,
         &pdf_stem,
-        args.whole_page,
-        "FOOTPRINT-IMAGE",
+        &page_render::RenderOptions {
+            padding: args.padding,
+            dpi: args.dpi,
+            whole_page: args.whole_page,
+            log_prefix: "FOOTPRINT-IMAGE",
+        },
     )?;
 
     eprintln!("[FOOTPRINT-IMAGE] Done — {} PNG(s) extracted.", saved);
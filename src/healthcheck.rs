@@ -0,0 +1,277 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! `datasheet healthcheck`: a non-interactive readiness check for CI and containers.
+//!
+//! Verifies that credentials resolve (environment or keyring), that the cache,
+//! download, and data directories are writable, and that each configured
+//! provider accepts its credentials. Services without credentials are skipped
+//! unless named with `--require`. Exits non-zero when any check fails, and
+//! `--json` prints the results as one object.
+
+use anyhow::{Result, anyhow};
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::auth::{self, AuthService};
+use crate::llm::LlmProvider;
+
+#[derive(Args, Debug)]
+pub struct HealthcheckArgs {
+    /// Services that must be configured and reachable (default: check those with credentials)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub require: Vec<Service>,
+
+    /// Only check credentials and directories, not provider reachability
+    #[arg(long)]
+    pub no_network: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    Gemini,
+    Mouser,
+    Digikey,
+    Jlcpcb,
+    Inventree,
+    Partsbox,
+}
+
+impl Service {
+    const ALL: [Service; 6] = [
+        Service::Gemini,
+        Service::Mouser,
+        Service::Digikey,
+        Service::Jlcpcb,
+        Service::Inventree,
+        Service::Partsbox,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Service::Gemini => "gemini",
+            Service::Mouser => "mouser",
+            Service::Digikey => "digikey",
+            Service::Jlcpcb => "jlcpcb",
+            Service::Inventree => "inventree",
+            Service::Partsbox => "partsbox",
+        }
+    }
+
+    /// Keyring service holding the credentials; JLCPCB needs none.
+    fn auth(self) -> Option<AuthService> {
+        match self {
+            Service::Gemini => Some(AuthService::Gemini),
+            Service::Mouser => Some(AuthService::Mouser),
+            Service::Digikey => Some(AuthService::Digikey),
+            Service::Jlcpcb => None,
+            Service::Inventree => Some(AuthService::Inventree),
+            Service::Partsbox => Some(AuthService::Partsbox),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Ok,
+    Skip,
+    Fail,
+}
+
+#[derive(Serialize, Debug)]
+struct Check {
+    /// "credentials", "directory", or "network"
+    check: &'static str,
+    target: String,
+    status: Status,
+    detail: String,
+}
+
+#[derive(Serialize, Debug)]
+struct Report {
+    healthy: bool,
+    checks: Vec<Check>,
+}
+
+pub fn run(args: &HealthcheckArgs) -> Result<()> {
+    let mut checks = Vec::new();
+
+    let mut reachable = Vec::new();
+    for service in Service::ALL {
+        let required = args.require.contains(&service);
+        let Some(auth_service) = service.auth() else {
+            if required {
+                reachable.push(service);
+            }
+            continue;
+        };
+        let check = credentials_check(service, auth_service, required);
+        let has_api = matches!(service, Service::Gemini | Service::Mouser | Service::Digikey);
+        if check.status == Status::Ok && has_api {
+            reachable.push(service);
+        }
+        checks.push(check);
+    }
+
+    let data_dir = dirs::data_dir().map(|dir| dir.join("datasheet-cli"));
+    let directories = [
+        ("cache", Some(crate::file_cache::cache_root())),
+        ("download", Some(crate::paths::download_dir())),
+        ("data", data_dir),
+    ];
+    for (name, dir) in directories {
+        let (status, detail) = match dir {
+            Some(dir) => match writable(&dir) {
+                Ok(()) => (Status::Ok, format!("{} is writable", dir.display())),
+                Err(e) => (Status::Fail, e),
+            },
+            None => (Status::Fail, "could not determine the platform directory".to_string()),
+        };
+        checks.push(Check { check: "directory", target: name.to_string(), status, detail });
+    }
+
+    for service in reachable {
+        let (status, detail) = if args.no_network {
+            (Status::Skip, "--no-network".to_string())
+        } else if crate::offline::is_enabled() {
+            (Status::Skip, "--offline".to_string())
+        } else {
+            match reach(service) {
+                Ok(detail) => (Status::Ok, detail),
+                Err(e) => (Status::Fail, redact(&e, service)),
+            }
+        };
+        checks.push(Check { check: "network", target: service.name().to_string(), status, detail });
+    }
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    let report = Report { healthy: failed == 0, checks };
+
+    if args.json {
+        let json = crate::select::to_string_pretty(&report)
+            .map_err(|e| anyhow!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+    } else {
+        for check in &report.checks {
+            let status = match check.status {
+                Status::Ok => "ok",
+                Status::Skip => "skip",
+                Status::Fail => "FAIL",
+            };
+            println!("{:<5} {:<12} {:<10} {}", status, check.check, check.target, check.detail);
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow!("{} of {} health check(s) failed", failed, report.checks.len()));
+    }
+    Ok(())
+}
+
+fn credentials_check(service: Service, auth_service: AuthService, required: bool) -> Check {
+    let mut found = Vec::new();
+    let mut missing = Vec::new();
+    for &(var, _) in auth_service.credentials() {
+        match auth::credential_source(var) {
+            Some(source) => found.push(format!("{} from {}", var, source)),
+            None => missing.push(var),
+        }
+    }
+    // Gemini also reads GEMINI_API_KEY and DATASHEET_API_KEY
+    if service == Service::Gemini
+        && !missing.is_empty()
+        && crate::llm::resolve_api_key(LlmProvider::Gemini, None).is_ok()
+    {
+        missing.clear();
+        found.push("API key from environment".to_string());
+    }
+    if service == Service::Inventree && missing.is_empty() && std::env::var("INVENTREE_URL").is_err() {
+        missing.push("INVENTREE_URL");
+    }
+
+    let (status, detail) = if missing.is_empty() {
+        (Status::Ok, found.join(", "))
+    } else {
+        let status = if required { Status::Fail } else { Status::Skip };
+        (status, format!("{} not set", missing.join(", ")))
+    };
+    Check { check: "credentials", target: service.name().to_string(), status, detail }
+}
+
+/// Create `dir` if needed and write and remove a probe file in it.
+fn writable(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+    let probe: PathBuf = dir.join(format!(".healthcheck-{}", std::process::id()));
+    std::fs::write(&probe, b"ok").map_err(|e| format!("{} is not writable: {}", dir.display(), e))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Make one cheap authenticated request to `service`.
+fn reach(service: Service) -> Result<String, String> {
+    match service {
+        Service::Gemini => {
+            let key = crate::llm::resolve_api_key(LlmProvider::Gemini, None).map_err(|e| e.to_string())?;
+            let url = format!("{}/models?pageSize=1", LlmProvider::Gemini.default_base_url());
+            ureq::get(&url)
+                .set("x-goog-api-key", &key)
+                .timeout(Duration::from_secs(20))
+                .call()
+                .map_err(|e| crate::error::Error::from_ureq("Gemini API", e).to_string())?;
+            Ok("API key accepted".to_string())
+        }
+        Service::Mouser => crate::mouser::check_api_key()
+            .map(|()| "API key accepted".to_string())
+            .map_err(|e| e.to_string()),
+        Service::Digikey => crate::digikey::check_credentials()
+            .map(|()| "access token issued".to_string())
+            .map_err(|e| e.to_string()),
+        Service::Jlcpcb => {
+            crate::jlcpcb::search_parts("LM358", 1).map(|_| "search API reachable".to_string())
+        }
+        Service::Inventree | Service::Partsbox => Ok("not checked".to_string()),
+    }
+}
+
+/// Error messages can quote request URLs, and Mouser takes its key in the
+/// query string: mask the service's secrets before they reach CI logs.
+fn redact(message: &str, service: Service) -> String {
+    let mut message = message.to_string();
+    let mut vars: Vec<&str> = service
+        .auth()
+        .map(|a| a.credentials().iter().map(|&(var, _)| var).collect())
+        .unwrap_or_default();
+    if service == Service::Gemini {
+        vars.extend(LlmProvider::Gemini.api_key_env_vars());
+        vars.push("DATASHEET_API_KEY");
+    }
+    for var in vars {
+        if let Some(secret) = auth::credential(var).filter(|s| s.len() >= 4) {
+            message = message.replace(&secret, "***");
+        }
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writable() {
+        let dir = std::env::temp_dir().join(format!("datasheet-healthcheck-test-{}", std::process::id()));
+        assert!(writable(&dir.join("nested")).is_ok());
+        assert_eq!(std::fs::read_dir(dir.join("nested")).unwrap().count(), 0);
+        let file = dir.join("file");
+        std::fs::write(&file, b"").unwrap();
+        assert!(writable(&file).unwrap_err().contains("cannot create"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
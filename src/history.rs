@@ -0,0 +1,240 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Stock and price history: `--record-history` and `datasheet history`.
+//!
+//! `datasheet watch` always records its observations in the parts database's
+//! `stock_history` table. With `--record-history` (or
+//! `DATASHEET_RECORD_HISTORY`), the distributor `search` and `stock` commands,
+//! `find`, and `part-info` add a snapshot of every listing they print, priced at its
+//! smallest price break. `datasheet history <MPN>` shows the snapshots per
+//! distributor with the change in stock and price over the period.
+
+use anyhow::{Result, anyhow};
+use clap::Args;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::bom::Source;
+use crate::db::{self, PartsDb, StockSample};
+use crate::error::Error;
+use crate::stock::{self, StockInfo};
+
+static RECORD: AtomicBool = AtomicBool::new(false);
+
+/// Turn snapshot recording from search and stock commands on or off (called once from `main`).
+pub fn set_record(enabled: bool) {
+    RECORD.store(enabled, Ordering::Relaxed);
+}
+
+/// Add a snapshot of each listing to the stock history when `--record-history`
+/// is on. Listings replayed by `--offline` are not new observations and are
+/// skipped. Failures are only warnings.
+pub fn record(listings: impl IntoIterator<Item = StockInfo>) {
+    if !RECORD.load(Ordering::Relaxed) || crate::offline::is_enabled() {
+        return;
+    }
+    let db = match PartsDb::open(None) {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("[HISTORY] Warning: {}", e);
+            return;
+        }
+    };
+    let recorded_at = db::now();
+    for info in listings {
+        let sample = StockSample {
+            stock: info.stock,
+            unit_price: stock::unit_price(&info.price_breaks, None),
+            currency: Some(info.currency.clone()),
+            recorded_at,
+            quantity: info.price_breaks.iter().map(|pb| pb.quantity).min(),
+        };
+        if let Err(e) = db.record_stock(&info.mpn, info.distributor, &sample) {
+            eprintln!("[HISTORY] Warning: {}", e);
+            return;
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Manufacturer part number
+    pub mpn: String,
+
+    /// Only these distributors (comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub source: Vec<Source>,
+
+    /// Only snapshots from the last N days
+    #[arg(long)]
+    pub days: Option<u32>,
+
+    /// Most recent snapshots to list per distributor (the trend covers all of them)
+    #[arg(long, default_value = "20")]
+    pub limit: usize,
+
+    /// Database path (defaults to DATASHEET_DB or the platform data directory)
+    #[arg(long)]
+    pub db: Option<PathBuf>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct DistributorHistory {
+    distributor: String,
+    trend: Trend,
+    samples: Vec<StockSample>,
+}
+
+/// Change between the first and the last snapshot of the period.
+#[derive(Serialize, Debug, Default, PartialEq)]
+struct Trend {
+    snapshots: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock_change: Option<i64>,
+    /// Prices compared, taken at the latest snapshot's quantity and currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price_change_pct: Option<f64>,
+}
+
+pub fn run(args: &HistoryArgs) -> Result<()> {
+    let db = PartsDb::open(args.db.clone()).map_err(|e| anyhow!(e))?;
+    let since = args.days.map_or(0, |days| db::now() - i64::from(days) * 86_400);
+    let rows = db.stock_history(&args.mpn, since).map_err(|e| anyhow!(e))?;
+
+    let mut histories: Vec<DistributorHistory> = Vec::new();
+    for (distributor, sample) in rows {
+        if !args.source.is_empty() && !args.source.iter().any(|s| s.name() == distributor) {
+            continue;
+        }
+        match histories.last_mut().filter(|h| h.distributor == distributor) {
+            Some(history) => history.samples.push(sample),
+            None => histories.push(DistributorHistory { distributor, trend: Trend::default(), samples: vec![sample] }),
+        }
+    }
+    if histories.is_empty() {
+        return Err(Error::not_found(format!(
+            "No stock history for {}; `datasheet watch` records it, as do search and stock commands with --record-history",
+            args.mpn
+        ))
+        .into());
+    }
+    for history in &mut histories {
+        history.trend = trend(&history.samples);
+        let skip = history.samples.len().saturating_sub(args.limit);
+        history.samples.drain(..skip);
+    }
+
+    if args.json {
+        let json = crate::select::to_string_pretty(&serde_json::json!({ "mpn": args.mpn, "distributors": histories }))
+            .map_err(|e| anyhow!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    for history in &histories {
+        let trend = &history.trend;
+        println!("{} at {} ({} snapshot(s), times in UTC)", args.mpn, history.distributor, trend.snapshots);
+        if trend.snapshots > history.samples.len() {
+            println!("  ... {} earlier", trend.snapshots - history.samples.len());
+        }
+        for sample in &history.samples {
+            let price = match (sample.unit_price, &sample.currency) {
+                (Some(price), Some(currency)) => format!("{:.4} {}", price, currency),
+                (Some(price), None) => format!("{:.4}", price),
+                _ => "-".to_string(),
+            };
+            let quantity = sample.quantity.map(|q| format!(" @{}", q)).unwrap_or_default();
+            println!(
+                "  {}  {:>10}  {}{}",
+                crate::schedule::format_time(sample.recorded_at, 0),
+                sample.stock.map_or("-".to_string(), |s| s.to_string()),
+                price,
+                quantity
+            );
+        }
+        let mut changes = Vec::new();
+        if let Some(change) = trend.stock_change {
+            changes.push(format!("stock {:+}", change));
+        }
+        if let (Some(first), Some(last), Some(pct)) = (trend.first_price, trend.last_price, trend.price_change_pct) {
+            changes.push(format!("price {:.4} -> {:.4} ({:+.1}%)", first, last, pct));
+        }
+        if trend.snapshots > 1 && !changes.is_empty() {
+            println!("  Change: {}", changes.join(", "));
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn trend(samples: &[StockSample]) -> Trend {
+    let stocks: Vec<i64> = samples.iter().filter_map(|s| s.stock).collect();
+    let stock_change = match (stocks.first(), stocks.last()) {
+        (Some(first), Some(last)) if stocks.len() > 1 => Some(last - first),
+        _ => None,
+    };
+
+    // Watch entries and search snapshots may price different quantities
+    let latest = samples.iter().rev().find(|s| s.unit_price.is_some());
+    let comparable = |s: &&StockSample| {
+        latest.is_some_and(|l| s.quantity == l.quantity && s.currency == l.currency) && s.unit_price.is_some()
+    };
+    let first_price = samples.iter().find(comparable).and_then(|s| s.unit_price);
+    let last_price = latest.and_then(|s| s.unit_price);
+    let price_change_pct = match (first_price, last_price) {
+        (Some(first), Some(last)) if first > 0.0 => Some((last - first) / first * 100.0),
+        _ => None,
+    };
+    Trend { snapshots: samples.len(), stock_change, first_price, last_price, price_change_pct }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(recorded_at: i64, stock: i64, price: f64, quantity: i32) -> StockSample {
+        StockSample {
+            stock: Some(stock),
+            unit_price: Some(price),
+            currency: Some("USD".to_string()),
+            recorded_at,
+            quantity: Some(quantity),
+        }
+    }
+
+    #[test]
+    fn test_trend() {
+        let samples = [
+            sample(100, 5000, 9.99, 100),
+            sample(200, 4000, 2.00, 1),
+            sample(300, 3500, 2.20, 1),
+            sample(400, 3000, 2.50, 1),
+        ];
+        let trend = trend(&samples);
+        assert_eq!(trend.snapshots, 4);
+        assert_eq!(trend.stock_change, Some(-2000));
+        // The quantity-100 watch price is not compared with the quantity-1 ones
+        assert_eq!(trend.first_price, Some(2.00));
+        assert_eq!(trend.last_price, Some(2.50));
+        assert!((trend.price_change_pct.unwrap() - 25.0).abs() < 1e-9);
+
+        let db = PartsDb::open_in_memory().unwrap();
+        for s in &samples {
+            db.record_stock("lm5164ddar", "digikey", s).unwrap();
+        }
+        db.record_stock("LM5164DDAR", "mouser", &sample(150, 10, 2.1, 1)).unwrap();
+        let rows = db.stock_history("LM5164DDAR", 150).unwrap();
+        let seen: Vec<(&str, i64)> = rows.iter().map(|(d, s)| (d.as_str(), s.recorded_at)).collect();
+        assert_eq!(seen, [("digikey", 200), ("digikey", 300), ("digikey", 400), ("mouser", 150)]);
+    }
+}
@@ -0,0 +1,575 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Inventory system import/export (InvenTree, PartsBox).
+//!
+//! `export` pushes parts from the local parts database (distributor metadata and
+//! indexed extraction results) into an inventory system, creating or updating
+//! the part there. `import` pulls the inventory's parts back into the database
+//! so they can be queried alongside everything else.
+
+use clap::{Subcommand, ValueEnum};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::db::{Attribute, PartRecord, PartsDb};
+
+const ENV_VAR_INVENTREE_URL: &str = "INVENTREE_URL";
+const ENV_VAR_INVENTREE_TOKEN: &str = "INVENTREE_TOKEN";
+const ENV_VAR_PARTSBOX_KEY: &str = "PARTSBOX_API_KEY";
+const PARTSBOX_API_BASE: &str = "https://api.partsbox.com/api/1";
+/// InvenTree limits part descriptions to 250 characters.
+const INVENTREE_DESCRIPTION_MAX: usize = 250;
+
+/// Supported inventory systems.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventorySystem {
+    Inventree,
+    Partsbox,
+}
+
+/// Inventory subcommands.
+#[derive(Subcommand, Debug)]
+pub enum InventorySubcommand {
+    /// Push parts from the local database into an inventory system
+    Export {
+        /// Inventory system to push to
+        #[arg(value_enum)]
+        system: InventorySystem,
+
+        /// Manufacturer part numbers to export (`-` reads newline-separated MPNs from stdin)
+        mpns: Vec<String>,
+
+        /// Export every part matching a database query instead
+        #[arg(long, conflicts_with = "mpns")]
+        query: Option<String>,
+
+        /// Skip datasheet/extraction attributes (InvenTree parameters, PartsBox notes)
+        #[arg(long)]
+        no_parameters: bool,
+
+        /// InvenTree server URL (falls back to INVENTREE_URL)
+        #[arg(long)]
+        url: Option<String>,
+
+        /// API token (falls back to INVENTREE_TOKEN or PARTSBOX_API_KEY)
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Database path (defaults to DATASHEET_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+
+        /// Print the payloads instead of sending them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Pull parts from an inventory system into the local database
+    Import {
+        /// Inventory system to pull from
+        #[arg(value_enum)]
+        system: InventorySystem,
+
+        /// InvenTree server URL (falls back to INVENTREE_URL)
+        #[arg(long)]
+        url: Option<String>,
+
+        /// API token (falls back to INVENTREE_TOKEN or PARTSBOX_API_KEY)
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Database path (defaults to DATASHEET_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+}
+
+/// A part as reported by an inventory system.
+#[derive(Debug, Default)]
+struct InventoryPart {
+    mpn: String,
+    manufacturer: Option<String>,
+    description: Option<String>,
+    link: Option<String>,
+    stock: Option<f64>,
+}
+
+/// Execute an inventory subcommand.
+pub fn execute(command: InventorySubcommand) -> Result<(), String> {
+    crate::offline::ensure_online("Inventory sync")?;
+    match command {
+        InventorySubcommand::Export {
+            system,
+            mpns,
+            query,
+            no_parameters,
+            url,
+            token,
+            db,
+            dry_run,
+        } => {
+            let db = PartsDb::open(db)?;
+            let parts = select_parts(&db, &crate::bom::expand_stdin(mpns)?, query.as_deref())?;
+            if dry_run {
+                for part in &parts {
+                    let payload = match system {
+                        InventorySystem::Inventree => inventree_part_payload(part),
+                        InventorySystem::Partsbox => partsbox_part_payload(part, !no_parameters),
+                    };
+                    println!("{}", crate::select::to_string_pretty(&payload).unwrap_or_default());
+                }
+                return Ok(());
+            }
+            let client = Client::new(system, url, token)?;
+            let mut failed = 0;
+            for part in &parts {
+                match client.push(part, !no_parameters) {
+                    Ok(outcome) => println!("{}: {}", part.mpn, outcome),
+                    Err(e) => {
+                        failed += 1;
+                        eprintln!("[INVENTORY] {}: {}", part.mpn, e);
+                    }
+                }
+            }
+            if failed > 0 {
+                return Err(format!("{} of {} part(s) failed to export", failed, parts.len()));
+            }
+            Ok(())
+        }
+        InventorySubcommand::Import { system, url, token, db } => {
+            let db = PartsDb::open(db)?;
+            let client = Client::new(system, url, token)?;
+            let parts = client.pull()?;
+            let tag = format!("inventory:{}", client.name());
+            for part in &parts {
+                let id = db.upsert_part(
+                    &part.mpn,
+                    part.manufacturer.as_deref(),
+                    part.description.as_deref(),
+                    part.link.as_deref(),
+                )?;
+                let mut attrs = Vec::new();
+                if let Some(stock) = part.stock {
+                    attrs.push(Attribute::new(&tag, "in_stock", &stock.to_string()));
+                }
+                db.set_attributes(id, &tag, &attrs)?;
+            }
+            println!("Imported {} part(s) from {}", parts.len(), client.name());
+            Ok(())
+        }
+    }
+}
+
+/// Parts to export, with attributes, from explicit MPNs or a query.
+fn select_parts(db: &PartsDb, mpns: &[String], query: Option<&str>) -> Result<Vec<PartRecord>, String> {
+    let mpns: Vec<String> = match query {
+        Some(query) => db.query(query, usize::MAX)?.into_iter().map(|p| p.mpn).collect(),
+        None if mpns.is_empty() => return Err("Give part numbers or --query".to_string()),
+        None => mpns.to_vec(),
+    };
+    mpns.iter()
+        .map(|mpn| {
+            db.get(mpn)?.ok_or_else(|| {
+                format!("Part not in database: {} (run 'datasheet db fetch' first)", mpn)
+            })
+        })
+        .collect()
+}
+
+/// Attributes worth sending to an inventory system: extraction results and
+/// distributor parameters, but not volatile stock levels, library bookkeeping,
+/// or data that came from an inventory system in the first place.
+fn exportable_attributes(part: &PartRecord) -> impl Iterator<Item = &Attribute> {
+    part.attributes.iter().filter(|a| {
+        a.source != "library"
+            && !a.source.starts_with("inventory:")
+            && !matches!(a.name.as_str(), "stock" | "price" | "lead_time")
+    })
+}
+
+fn web_link(part: &PartRecord) -> Option<&str> {
+    part.datasheet.as_deref().filter(|d| d.starts_with("http"))
+}
+
+// --- Clients ---
+
+enum Client {
+    Inventree { url: String, token: String },
+    Partsbox { api_key: String },
+}
+
+impl Client {
+    fn new(system: InventorySystem, url: Option<String>, token: Option<String>) -> Result<Self, String> {
+        let from_env = |provided: Option<String>, var: &str, what: &str, flag: &str| {
+            provided
+                .filter(|v| !v.is_empty())
+                .or_else(|| crate::auth::credential(var))
+                .ok_or_else(|| format!("{} not provided. Set {} environment variable or use --{}", what, var, flag))
+        };
+        Ok(match system {
+            InventorySystem::Inventree => Client::Inventree {
+                url: from_env(url, ENV_VAR_INVENTREE_URL, "InvenTree URL", "url")?
+                    .trim_end_matches('/')
+                    .to_string(),
+                token: from_env(token, ENV_VAR_INVENTREE_TOKEN, "InvenTree API token", "token")?,
+            },
+            InventorySystem::Partsbox => Client::Partsbox {
+                api_key: from_env(token, ENV_VAR_PARTSBOX_KEY, "PartsBox API key", "token")?,
+            },
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Client::Inventree { .. } => "inventree",
+            Client::Partsbox { .. } => "partsbox",
+        }
+    }
+
+    /// Create or update a part; returns a short description of what happened.
+    fn push(&self, part: &PartRecord, parameters: bool) -> Result<String, String> {
+        match self {
+            Client::Inventree { url, token } => inventree_push(url, token, part, parameters),
+            Client::Partsbox { api_key } => partsbox_push(api_key, part, parameters),
+        }
+    }
+
+    fn pull(&self) -> Result<Vec<InventoryPart>, String> {
+        match self {
+            Client::Inventree { url, token } => inventree_pull(url, token),
+            Client::Partsbox { api_key } => partsbox_pull(api_key),
+        }
+    }
+}
+
+// --- InvenTree ---
+
+fn inventree_request(method: &str, url: &str, token: &str) -> ureq::Request {
+    ureq::request(method, url)
+        .set("Authorization", &format!("Token {}", token))
+        .set("Accept", "application/json")
+}
+
+fn inventree_call(method: &str, url: &str, token: &str, body: Option<Value>) -> Result<Value, String> {
+    let request = inventree_request(method, url, token);
+    let response = match body {
+        Some(body) => request.send_json(body),
+        None => request.call(),
+    };
+    response
+        .map_err(|e| match e {
+            ureq::Error::Status(code, r) => {
+                format!("InvenTree {} {} failed ({}): {}", method, url, code, r.into_string().unwrap_or_default())
+            }
+            e => format!("InvenTree request failed: {}", e),
+        })?
+        .into_json()
+        .map_err(|e| format!("Failed to parse InvenTree response: {}", e))
+}
+
+/// List endpoints return a bare array, or `{results: [...]}` when paginated.
+fn results(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items,
+        Value::Object(mut map) => match map.remove("results") {
+            Some(Value::Array(items)) => items,
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn pk(value: &Value) -> Result<i64, String> {
+    value
+        .get("pk")
+        .and_then(|p| p.as_i64())
+        .ok_or_else(|| "InvenTree response has no pk".to_string())
+}
+
+fn inventree_part_payload(part: &PartRecord) -> Value {
+    let description: String = part
+        .description
+        .as_deref()
+        .unwrap_or(&part.mpn)
+        .chars()
+        .take(INVENTREE_DESCRIPTION_MAX)
+        .collect();
+    let mut payload = json!({
+        "name": part.mpn,
+        "description": description,
+        "component": true,
+        "purchaseable": true,
+    });
+    if let Some(link) = web_link(part) {
+        payload["link"] = json!(link);
+    }
+    payload
+}
+
+fn inventree_push(url: &str, token: &str, part: &PartRecord, parameters: bool) -> Result<String, String> {
+    let mpn_query = urlencoding::encode(&part.mpn);
+    let existing = results(inventree_call(
+        "GET",
+        &format!("{}/api/company/part/manufacturer/?MPN={}", url, mpn_query),
+        token,
+        None,
+    )?);
+
+    let (part_pk, created) = match existing.first().and_then(|m| m.get("part")).and_then(|p| p.as_i64()) {
+        Some(part_pk) => {
+            inventree_call(
+                "PATCH",
+                &format!("{}/api/part/{}/", url, part_pk),
+                token,
+                Some(inventree_part_payload(part)),
+            )?;
+            (part_pk, false)
+        }
+        None => {
+            let created = inventree_call("POST", &format!("{}/api/part/", url), token, Some(inventree_part_payload(part)))?;
+            let part_pk = pk(&created)?;
+            let manufacturer = part.manufacturer.as_deref().unwrap_or("Unknown");
+            let company_pk = inventree_manufacturer(url, token, manufacturer)?;
+            let mut body = json!({ "part": part_pk, "manufacturer": company_pk, "MPN": part.mpn });
+            if let Some(link) = web_link(part) {
+                body["link"] = json!(link);
+            }
+            inventree_call("POST", &format!("{}/api/company/part/manufacturer/", url), token, Some(body))?;
+            (part_pk, true)
+        }
+    };
+
+    let mut count = 0;
+    if parameters {
+        let current: HashMap<i64, i64> = results(inventree_call(
+            "GET",
+            &format!("{}/api/part/parameter/?part={}", url, part_pk),
+            token,
+            None,
+        )?)
+        .iter()
+        .filter_map(|p| Some((p.get("template")?.as_i64()?, p.get("pk")?.as_i64()?)))
+        .collect();
+        let mut templates: HashMap<String, i64> = HashMap::new();
+
+        for attr in exportable_attributes(part) {
+            let template = match templates.get(&attr.name) {
+                Some(&t) => t,
+                None => {
+                    let t = inventree_template(url, token, &attr.name)?;
+                    templates.insert(attr.name.clone(), t);
+                    t
+                }
+            };
+            match current.get(&template) {
+                Some(param_pk) => inventree_call(
+                    "PATCH",
+                    &format!("{}/api/part/parameter/{}/", url, param_pk),
+                    token,
+                    Some(json!({ "data": attr.value })),
+                )?,
+                None => inventree_call(
+                    "POST",
+                    &format!("{}/api/part/parameter/", url),
+                    token,
+                    Some(json!({ "part": part_pk, "template": template, "data": attr.value })),
+                )?,
+            };
+            count += 1;
+        }
+    }
+
+    Ok(format!(
+        "{} part {} ({} parameter(s))",
+        if created { "created" } else { "updated" },
+        part_pk,
+        count
+    ))
+}
+
+/// Company pk for a manufacturer, creating the company if needed.
+fn inventree_manufacturer(url: &str, token: &str, name: &str) -> Result<i64, String> {
+    let found = results(inventree_call(
+        "GET",
+        &format!("{}/api/company/?is_manufacturer=true&name={}", url, urlencoding::encode(name)),
+        token,
+        None,
+    )?);
+    if let Some(company) = found.first() {
+        return pk(company);
+    }
+    pk(&inventree_call(
+        "POST",
+        &format!("{}/api/company/", url),
+        token,
+        Some(json!({ "name": name, "is_manufacturer": true })),
+    )?)
+}
+
+/// Parameter template pk for an attribute name, creating the template if needed.
+fn inventree_template(url: &str, token: &str, name: &str) -> Result<i64, String> {
+    let found = results(inventree_call(
+        "GET",
+        &format!("{}/api/part/parameter/template/?name={}", url, urlencoding::encode(name)),
+        token,
+        None,
+    )?);
+    if let Some(template) = found.iter().find(|t| t.get("name").and_then(|n| n.as_str()) == Some(name)) {
+        return pk(template);
+    }
+    pk(&inventree_call(
+        "POST",
+        &format!("{}/api/part/parameter/template/", url),
+        token,
+        Some(json!({ "name": name })),
+    )?)
+}
+
+fn inventree_pull(url: &str, token: &str) -> Result<Vec<InventoryPart>, String> {
+    let items = results(inventree_call(
+        "GET",
+        &format!("{}/api/company/part/manufacturer/?part_detail=true&manufacturer_detail=true", url),
+        token,
+        None,
+    )?);
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let str_at = |ptr: &str| item.pointer(ptr).and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(String::from);
+            Some(InventoryPart {
+                mpn: str_at("/MPN")?,
+                manufacturer: str_at("/manufacturer_detail/name"),
+                description: str_at("/part_detail/description"),
+                link: str_at("/link").or_else(|| str_at("/part_detail/link")),
+                stock: item.pointer("/part_detail/in_stock").and_then(|v| v.as_f64()),
+            })
+        })
+        .collect())
+}
+
+// --- PartsBox ---
+
+fn partsbox_call(api_key: &str, operation: &str, body: Value) -> Result<Value, String> {
+    let response: Value = ureq::post(&format!("{}/{}", PARTSBOX_API_BASE, operation))
+        .set("Authorization", &format!("APIKey {}", api_key))
+        .send_json(body)
+        .map_err(|e| format!("PartsBox {} failed: {}", operation, e))?
+        .into_json()
+        .map_err(|e| format!("Failed to parse PartsBox response: {}", e))?;
+    match response.get("partsbox.status/category").and_then(|s| s.as_str()) {
+        Some("status/ok") | None => Ok(response),
+        Some(_) => Err(format!(
+            "PartsBox {} failed: {}",
+            operation,
+            response
+                .get("partsbox.status/message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown error")
+        )),
+    }
+}
+
+/// Markdown notes listing the part's attributes, since PartsBox has no parameter API.
+fn partsbox_notes(part: &PartRecord) -> String {
+    let mut notes = String::from("| Parameter | Value | Source |\n|---|---|---|\n");
+    for attr in exportable_attributes(part) {
+        notes.push_str(&format!("| {} | {} | {} |\n", attr.name, attr.value.replace('|', "\\|"), attr.source));
+    }
+    if let Some(link) = web_link(part) {
+        notes.push_str(&format!("\nDatasheet: {}\n", link));
+    }
+    notes
+}
+
+fn partsbox_part_payload(part: &PartRecord, parameters: bool) -> Value {
+    let mut payload = json!({
+        "part/name": part.mpn,
+        "part/type": "local",
+        "part/mpn": part.mpn,
+    });
+    if let Some(ref manufacturer) = part.manufacturer {
+        payload["part/manufacturer"] = json!(manufacturer);
+    }
+    if let Some(ref description) = part.description {
+        payload["part/description"] = json!(description);
+    }
+    if parameters {
+        payload["part/notes"] = json!(partsbox_notes(part));
+    }
+    payload
+}
+
+fn partsbox_all(api_key: &str) -> Result<Vec<Value>, String> {
+    Ok(partsbox_call(api_key, "part/all", json!({}))?
+        .get("data")
+        .and_then(|d| d.as_array())
+        .cloned()
+        .unwrap_or_default())
+}
+
+fn partsbox_push(api_key: &str, part: &PartRecord, parameters: bool) -> Result<String, String> {
+    let existing = partsbox_all(api_key)?.into_iter().find(|p| {
+        p.get("part/mpn")
+            .and_then(|m| m.as_str())
+            .is_some_and(|m| m.eq_ignore_ascii_case(&part.mpn))
+    });
+    let mut payload = partsbox_part_payload(part, parameters);
+    match existing.as_ref().and_then(|p| p.get("part/id")) {
+        Some(id) => {
+            payload["part/id"] = id.clone();
+            partsbox_call(api_key, "part/update", payload)?;
+            Ok("updated".to_string())
+        }
+        None => {
+            partsbox_call(api_key, "part/create", payload)?;
+            Ok("created".to_string())
+        }
+    }
+}
+
+fn partsbox_pull(api_key: &str) -> Result<Vec<InventoryPart>, String> {
+    Ok(partsbox_all(api_key)?
+        .iter()
+        .filter_map(|p| {
+            let str_at = |key: &str| p.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(String::from);
+            Some(InventoryPart {
+                mpn: str_at("part/mpn").or_else(|| str_at("part/name"))?,
+                manufacturer: str_at("part/manufacturer"),
+                description: str_at("part/description"),
+                link: None,
+                stock: None,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_payloads() {
+        let part = PartRecord {
+            mpn: "TPS62130RGTR".to_string(),
+            manufacturer: Some("Texas Instruments".to_string()),
+            description: Some("x".repeat(300)),
+            datasheet: Some("https://www.ti.com/lit/ds/symlink/tps62130.pdf".to_string()),
+            attributes: vec![
+                Attribute::new("extract:characteristics", "vin", "17V"),
+                Attribute::new("distributor:digikey", "stock", "1200"),
+                Attribute::new("library", "sha256", "abc"),
+            ],
+        };
+
+        let inventree = inventree_part_payload(&part);
+        assert_eq!(inventree["description"].as_str().unwrap().len(), INVENTREE_DESCRIPTION_MAX);
+        assert_eq!(inventree["link"], part.datasheet.as_deref().unwrap());
+
+        let partsbox = partsbox_part_payload(&part, true);
+        let notes = partsbox["part/notes"].as_str().unwrap();
+        assert!(notes.contains("| vin | 17V |"));
+        assert!(!notes.contains("stock") && !notes.contains("sha256"));
+    }
+}
@@ -7,6 +7,9 @@
 use clap::Subcommand;
 use serde::{Deserialize, Serialize};
 
+use crate::currency;
+use crate::stock::{SearchHit, StockInfo, StockPriceBreak, TableRow};
+
 const SEARCH_URL: &str =
     "https://jlcpcb.com/api/overseas-pcb-order/v1/shoppingCart/smtGood/selectSmtComponentList/v2";
 const DETAIL_URL: &str =
@@ -66,31 +69,6 @@ pub enum JlcpcbSubcommand {
     },
 }
 
-// Normalized stock/pricing output type
-
-#[derive(Serialize)]
-struct StockInfo {
-    mpn: String,
-    manufacturer: Option<String>,
-    distributor: &'static str,
-    distributor_pn: Option<String>,
-    lifecycle_status: Option<String>,
-    stock: Option<i64>,
-    lead_time: Option<String>,
-    moq: Option<i32>,
-    order_multiple: Option<i32>,
-    currency: String,
-    price_breaks: Vec<StockPriceBreak>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    jlcpcb_category: Option<String>,
-}
-
-#[derive(Serialize)]
-struct StockPriceBreak {
-    quantity: i32,
-    unit_price: f64,
-}
-
 // --- Output types (what we serialize for --json) ---
 
 #[derive(Serialize, Debug)]
@@ -264,9 +242,10 @@ fn cmd_search(
 ) -> Result<(), String> {
     let limit = limit.min(100);
     let parts = jlcpcb_search(query, limit, manufacturer, package, basic_only, in_stock)?;
+    crate::history::record(parts.iter().map(|part| search_stock_info(part).into_target_currency()));
 
     if json_output {
-        let json = serde_json::to_string_pretty(&parts)
+        let json = crate::select::to_string_pretty(&parts)
             .map_err(|e| format!("Failed to serialize results: {}", e))?;
         println!("{}", json);
     } else {
@@ -288,27 +267,36 @@ fn cmd_search(
 
 fn cmd_part(part_number: &str, json_output: bool) -> Result<(), String> {
     // If it looks like an LCSC part number (C followed by digits), use the detail endpoint directly.
-    // Otherwise, search by MPN and use the first result.
+    // Otherwise, search by MPN and let the user choose among the hits.
     let lcsc_pn = if is_lcsc_part_number(part_number) {
         part_number.to_string()
     } else {
-        let results = jlcpcb_search(part_number, 5, None, None, false, false)?;
-        let first = results.into_iter().next().ok_or_else(|| {
-            format!("No JLCPCB/LCSC part found for: {}", part_number)
-        })?;
+        let results = jlcpcb_search(part_number, 10, None, None, false, false)?;
+        if results.is_empty() {
+            return Err(format!("No JLCPCB/LCSC part found for: {}", part_number));
+        }
+        let rows: Vec<TableRow> = results
+            .iter()
+            .map(|part| TableRow {
+                info: search_stock_info(part),
+                description: part.description.clone(),
+            })
+            .collect();
+        let index = crate::stock::pick(&format!("JLCPCB matches for {}", part_number), &rows)?;
+        let chosen = &results[index];
         eprintln!(
             "Resolved {} -> {} ({})",
             part_number,
-            first.lcsc_part_number,
-            first.manufacturer_part_number.as_deref().unwrap_or("?")
+            chosen.lcsc_part_number,
+            chosen.manufacturer_part_number.as_deref().unwrap_or("?")
         );
-        first.lcsc_part_number
+        chosen.lcsc_part_number.clone()
     };
 
     let part = jlcpcb_part_detail(&lcsc_pn)?;
 
     if json_output {
-        let json = serde_json::to_string_pretty(&part)
+        let json = crate::select::to_string_pretty(&part)
             .map_err(|e| format!("Failed to serialize part: {}", e))?;
         println!("{}", json);
     } else {
@@ -319,73 +307,11 @@ fn cmd_part(part_number: &str, json_output: bool) -> Result<(), String> {
 }
 
 fn cmd_stock(part_number: &str, json_output: bool) -> Result<(), String> {
-    let part = if is_lcsc_part_number(part_number) {
-        let detail = jlcpcb_part_detail(part_number)?;
-        let price_breaks: Vec<StockPriceBreak> = detail
-            .price_breaks
-            .iter()
-            .map(|pb| StockPriceBreak {
-                quantity: pb.quantity,
-                unit_price: pb.price_usd,
-            })
-            .collect();
-        StockInfo {
-            mpn: detail
-                .manufacturer_part_number
-                .clone()
-                .unwrap_or_else(|| detail.lcsc_part_number.clone()),
-            manufacturer: detail.manufacturer.clone(),
-            distributor: "jlcpcb",
-            distributor_pn: Some(detail.lcsc_part_number.clone()),
-            lifecycle_status: None,
-            stock: detail.stock,
-            lead_time: None,
-            moq: detail.minimum_order,
-            order_multiple: None,
-            currency: "USD".to_string(),
-            price_breaks,
-            jlcpcb_category: detail.category.clone(),
-        }
-    } else {
-        let results = jlcpcb_search(part_number, 5, None, None, false, false)?;
-        let first = results.into_iter().next().ok_or_else(|| {
-            format!("No JLCPCB/LCSC part found for: {}", part_number)
-        })?;
-        eprintln!(
-            "Resolved {} -> {} ({})",
-            part_number,
-            first.lcsc_part_number,
-            first.manufacturer_part_number.as_deref().unwrap_or("?")
-        );
-        let price_breaks: Vec<StockPriceBreak> = first
-            .price_breaks
-            .iter()
-            .map(|pb| StockPriceBreak {
-                quantity: pb.quantity,
-                unit_price: pb.price_usd,
-            })
-            .collect();
-        StockInfo {
-            mpn: first
-                .manufacturer_part_number
-                .clone()
-                .unwrap_or_else(|| first.lcsc_part_number.clone()),
-            manufacturer: first.manufacturer.clone(),
-            distributor: "jlcpcb",
-            distributor_pn: Some(first.lcsc_part_number.clone()),
-            lifecycle_status: None,
-            stock: first.stock,
-            lead_time: None,
-            moq: None,
-            order_multiple: None,
-            currency: "USD".to_string(),
-            price_breaks,
-            jlcpcb_category: first.category.clone(),
-        }
-    };
+    let part = lookup_stock(part_number)?;
+    crate::history::record([part.clone()]);
 
     if json_output {
-        let json = serde_json::to_string_pretty(&part)
+        let json = crate::select::to_string_pretty(&part)
             .map_err(|e| format!("Failed to serialize stock info: {}", e))?;
         println!("{}", json);
     } else {
@@ -426,9 +352,9 @@ fn cmd_stock(part_number: &str, json_output: bool) -> Result<(), String> {
             for pb in &part.price_breaks {
                 let qty_label = format!("{}+", pb.quantity);
                 println!(
-                    "    {:>width$} : ${:.4}",
+                    "    {:>width$} : {}",
                     qty_label,
-                    pb.unit_price,
+                    currency::format_price(pb.unit_price, &part.currency, 4),
                     width = max_qty_width
                 );
             }
@@ -438,6 +364,101 @@ fn cmd_stock(part_number: &str, json_output: bool) -> Result<(), String> {
     Ok(())
 }
 
+/// Look up normalized stock and pricing for an LCSC number or MPN (the search
+/// hit with exactly that MPN).
+pub(crate) fn lookup_stock(part_number: &str) -> Result<StockInfo, String> {
+    let part = if is_lcsc_part_number(part_number) {
+        let detail = jlcpcb_part_detail(part_number)?;
+        let price_breaks: Vec<StockPriceBreak> = detail
+            .price_breaks
+            .iter()
+            .map(|pb| StockPriceBreak {
+                quantity: pb.quantity,
+                unit_price: pb.price_usd,
+            })
+            .collect();
+        StockInfo {
+            mpn: detail
+                .manufacturer_part_number
+                .clone()
+                .unwrap_or_else(|| detail.lcsc_part_number.clone()),
+            manufacturer: detail.manufacturer.clone(),
+            distributor: "jlcpcb",
+            distributor_pn: Some(detail.lcsc_part_number.clone()),
+            lifecycle_status: None,
+            stock: detail.stock,
+            lead_time: None,
+            moq: detail.minimum_order,
+            order_multiple: None,
+            currency: "USD".to_string(),
+            price_breaks,
+            suggested_replacement: None,
+            jlcpcb_category: detail.category.clone(),
+            datasheet_url: detail.datasheet_url.clone().filter(|u| !u.is_empty()),
+        }
+    } else {
+        let results = jlcpcb_search(part_number, 5, None, None, false, false)?;
+        let part = exact_part(results, part_number)?;
+        eprintln!(
+            "Resolved {} -> {} ({})",
+            part_number,
+            part.lcsc_part_number,
+            part.manufacturer_part_number.as_deref().unwrap_or("?")
+        );
+        search_stock_info(&part)
+    };
+
+    Ok(part.into_target_currency())
+}
+
+/// The search hit whose MPN is `part_number`, ignoring case; the keyword
+/// search also returns variants, and not always after the part itself.
+fn exact_part(results: Vec<JlcpcbPart>, part_number: &str) -> Result<JlcpcbPart, String> {
+    let wanted = part_number.trim();
+    let similar: Vec<String> = results.iter().filter_map(|p| p.manufacturer_part_number.clone()).collect();
+    results
+        .into_iter()
+        .find(|p| p.manufacturer_part_number.as_deref().is_some_and(|mpn| mpn.trim().eq_ignore_ascii_case(wanted)))
+        .ok_or_else(|| {
+            if similar.is_empty() {
+                format!("No JLCPCB/LCSC part found for: {}", part_number)
+            } else {
+                format!("No JLCPCB/LCSC part found for: {} (LCSC lists {})", part_number, similar.join(", "))
+            }
+        })
+}
+
+/// Stock and pricing of a search hit, in USD.
+fn search_stock_info(part: &JlcpcbPart) -> StockInfo {
+    let price_breaks: Vec<StockPriceBreak> = part
+        .price_breaks
+        .iter()
+        .map(|pb| StockPriceBreak {
+            quantity: pb.quantity,
+            unit_price: pb.price_usd,
+        })
+        .collect();
+    StockInfo {
+        mpn: part
+            .manufacturer_part_number
+            .clone()
+            .unwrap_or_else(|| part.lcsc_part_number.clone()),
+        manufacturer: part.manufacturer.clone(),
+        distributor: "jlcpcb",
+        distributor_pn: Some(part.lcsc_part_number.clone()),
+        lifecycle_status: None,
+        stock: part.stock,
+        lead_time: None,
+        moq: None,
+        order_multiple: None,
+        currency: "USD".to_string(),
+        price_breaks,
+        suggested_replacement: None,
+        jlcpcb_category: part.category.clone(),
+        datasheet_url: part.datasheet_url.clone().filter(|u| !u.is_empty()),
+    }
+}
+
 fn format_number(n: i64) -> String {
     let s = n.to_string();
     let mut result = String::new();
@@ -454,6 +475,39 @@ fn is_lcsc_part_number(s: &str) -> bool {
     s.starts_with('C') && s.len() > 1 && s[1..].chars().all(|c| c.is_ascii_digit())
 }
 
+/// Resolve the datasheet URL for a part, preferring an exact MPN or LCSC number match.
+pub(crate) fn find_datasheet_url(part_number: &str) -> Result<Option<String>, String> {
+    let results = jlcpcb_search(part_number, 5, None, None, false, false)?;
+    let exact = results.into_iter().find(|p| {
+        p.lcsc_part_number.eq_ignore_ascii_case(part_number)
+            || p
+                .manufacturer_part_number
+                .as_deref()
+                .is_some_and(|m| m.eq_ignore_ascii_case(part_number))
+    });
+    Ok(exact.and_then(|p| p.datasheet_url))
+}
+
+/// Keyword search returning normalized hits.
+pub(crate) fn search_parts(keyword: &str, limit: usize) -> Result<Vec<SearchHit>, String> {
+    let parts = jlcpcb_search(keyword, limit, None, None, false, false)?;
+    Ok(parts
+        .into_iter()
+        .map(|part| SearchHit {
+            mpn: part
+                .manufacturer_part_number
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| part.lcsc_part_number.clone()),
+            manufacturer: part.manufacturer,
+            description: part.description,
+            distributor: "jlcpcb",
+            distributor_pn: Some(part.lcsc_part_number),
+            stock: part.stock,
+            datasheet_url: part.datasheet_url.filter(|u| !u.is_empty()),
+        })
+        .collect())
+}
+
 // --- API helpers ---
 
 fn jlcpcb_search(
@@ -486,12 +540,16 @@ fn jlcpcb_search(
         obj.insert("stockFlag".to_string(), serde_json::json!(true));
     }
 
-    let response: ApiResponse<SearchData> = ureq::post(SEARCH_URL)
-        .set("Content-Type", "application/json")
-        .set("Accept", "application/json")
-        .send_json(&body)
-        .map_err(|e| format!("JLCPCB search request failed: {}", e))?
-        .into_json()
+    let response = crate::offline::cached_response("jlcpcb", &format!("search:{}", body), || {
+        let call = crate::audit::start("jlcpcb", "POST", SEARCH_URL, Some(keyword));
+        let sent = ureq::post(SEARCH_URL)
+            .set("Content-Type", "application/json")
+            .set("Accept", "application/json")
+            .send_json(&body);
+        let response = call.finish(sent).map_err(|e| format!("JLCPCB search request failed: {}", e))?;
+        serde_json::from_slice(&response).map_err(|e| format!("Failed to parse JLCPCB search response: {}", e))
+    })?;
+    let response: ApiResponse<SearchData> = serde_json::from_value(response)
         .map_err(|e| format!("Failed to parse JLCPCB search response: {}", e))?;
 
     if response.code != 200 {
@@ -508,17 +566,20 @@ fn jlcpcb_search(
         .and_then(|p| p.list)
         .unwrap_or_default();
 
-    Ok(components.into_iter().map(|c| convert_search_component(c)).collect())
+    Ok(components.into_iter().map(convert_search_component).collect())
 }
 
 fn jlcpcb_part_detail(lcsc_part_number: &str) -> Result<JlcpcbPartDetail, String> {
     let url = format!("{}?componentCode={}", DETAIL_URL, lcsc_part_number);
 
-    let response: ApiResponse<DetailComponent> = ureq::get(&url)
-        .set("Accept", "application/json")
-        .call()
-        .map_err(|e| format!("JLCPCB part detail request failed: {}", e))?
-        .into_json()
+    let response = crate::offline::cached_response("jlcpcb", &format!("detail:{}", lcsc_part_number), || {
+        let call = crate::audit::start("jlcpcb", "GET", &url, Some(lcsc_part_number));
+        let body = call
+            .finish(ureq::get(&url).set("Accept", "application/json").call())
+            .map_err(|e| format!("JLCPCB part detail request failed: {}", e))?;
+        serde_json::from_slice(&body).map_err(|e| format!("Failed to parse JLCPCB part detail response: {}", e))
+    })?;
+    let response: ApiResponse<DetailComponent> = serde_json::from_value(response)
         .map_err(|e| format!("Failed to parse JLCPCB part detail response: {}", e))?;
 
     if response.code != 200 {
@@ -678,8 +739,9 @@ fn format_part_summary(part: &JlcpcbPart) -> String {
 
     if let Some(first) = part.price_breaks.first() {
         lines.push(format!(
-            "   Price: ${:.4} (qty {}+)",
-            first.price_usd, first.quantity
+            "   Price: {} (qty {}+)",
+            currency::format_price(first.price_usd, "USD", 4),
+            first.quantity
         ));
     }
 
@@ -736,7 +798,11 @@ fn print_part_details(part: &JlcpcbPartDetail) {
         println!("Pricing");
         println!("-------");
         for pb in &part.price_breaks {
-            println!("  {:>6}+ : ${:.4}", pb.quantity, pb.price_usd);
+            println!(
+                "  {:>6}+ : {}",
+                pb.quantity,
+                currency::format_price(pb.price_usd, "USD", 4)
+            );
         }
     }
 
@@ -768,3 +834,33 @@ fn print_part_details(part: &JlcpcbPartDetail) {
         println!("Datasheet: Not available");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(lcsc: &str, mpn: &str) -> JlcpcbPart {
+        JlcpcbPart {
+            lcsc_part_number: lcsc.to_string(),
+            manufacturer_part_number: Some(mpn.to_string()),
+            manufacturer: None,
+            description: None,
+            category: None,
+            package: None,
+            stock: Some(100),
+            price_breaks: Vec::new(),
+            datasheet_url: None,
+            product_url: None,
+            first_category: None,
+            second_category: None,
+        }
+    }
+
+    #[test]
+    fn test_exact_part() {
+        let results = || vec![hit("C7950", "LM358DR2G"), hit("C7950X", "LM358DR")];
+        assert_eq!(exact_part(results(), "lm358dr").unwrap().lcsc_part_number, "C7950X");
+        let err = exact_part(vec![hit("C7950", "LM358DR2G")], "LM358DR").unwrap_err();
+        assert!(err.contains("LM358DR2G"));
+    }
+}
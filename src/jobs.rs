@@ -0,0 +1,701 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Persistent extraction job queue backed by SQLite.
+//!
+//! `jobs submit` records extractions to run; `jobs run` works through the queue,
+//! retrying failures up to each job's attempt limit and recording token spend
+//! and results. Several `jobs run` workers can share one queue: jobs are
+//! claimed with a single atomic UPDATE, so each job runs once.
+//!
+//! The queue lives at `<data dir>/datasheet-cli/jobs.db` unless `--db` or
+//! `DATASHEET_JOBS_DB` is set.
+
+use clap::{Subcommand, ValueEnum};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::db::now;
+use crate::extract::{self, ExtractArgs, ExtractTask};
+use crate::llm;
+
+const ENV_VAR_DB: &str = "DATASHEET_JOBS_DB";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS jobs (
+    id INTEGER PRIMARY KEY,
+    task TEXT NOT NULL,
+    pdf TEXT NOT NULL,
+    options TEXT NOT NULL,
+    out TEXT,
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    tokens INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    result TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS jobs_status ON jobs(status, id);
+";
+
+#[derive(Subcommand, Debug)]
+pub enum JobsSubcommand {
+    /// Queue extraction jobs (one per PDF)
+    Submit {
+        /// Task to run
+        #[arg(value_enum)]
+        task: ExtractTask,
+
+        /// Input PDF paths (a .zip is unpacked and each PDF inside it queued)
+        #[arg(required = true)]
+        pdfs: Vec<PathBuf>,
+
+        /// Model name (default: the task's default model)
+        #[arg(long, env = "DATASHEET_MODEL")]
+        model: Option<String>,
+
+        /// Only send these pages to the model, e.g. "1-5,9"
+        #[arg(long)]
+        pages: Option<String>,
+
+        /// Custom prompt text or file (only for 'custom' task)
+        #[arg(long)]
+        prompt: Option<String>,
+
+        /// Custom JSON schema file or inline JSON (only for 'custom' task)
+        #[arg(long)]
+        schema: Option<String>,
+
+        /// Sampling temperature
+        #[arg(long)]
+        temperature: Option<f32>,
+
+        /// Send PDFs inline instead of through the file cache
+        #[arg(long)]
+        no_cache: bool,
+
+        /// Also write each result to <DIR>/<pdf stem>.<task>.json
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+
+        /// Retries after the first failed attempt
+        #[arg(long, default_value_t = 2)]
+        retries: u32,
+
+        /// Queue database path (defaults to DATASHEET_JOBS_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+
+    /// Work through queued jobs until the queue is empty
+    Run {
+        /// Stop after this many jobs
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+        #[arg(long)]
+        api_key: Option<String>,
+
+        /// Optional base URL override for Gemini API
+        #[arg(long)]
+        base_url: Option<String>,
+
+        /// Queue database path (defaults to DATASHEET_JOBS_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+
+        /// Webhook to POST a summary to when the run finishes or fails (repeatable; Slack-compatible)
+        #[arg(long, value_name = "URL", env = "DATASHEET_NOTIFY", value_delimiter = ',')]
+        notify: Vec<String>,
+    },
+
+    /// List jobs, newest first
+    List {
+        /// Only show jobs with this status
+        #[arg(long, value_enum)]
+        status: Option<JobStatus>,
+
+        /// Maximum number of jobs to show
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+
+        /// Queue database path (defaults to DATASHEET_JOBS_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show one job, including its result or last error
+    Show {
+        /// Job id
+        id: i64,
+
+        /// Queue database path (defaults to DATASHEET_JOBS_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Requeue finished, failed, or cancelled jobs
+    Retry {
+        /// Job ids
+        ids: Vec<i64>,
+
+        /// Requeue every failed job
+        #[arg(long)]
+        failed: bool,
+
+        /// Queue database path (defaults to DATASHEET_JOBS_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+
+    /// Cancel queued jobs
+    Cancel {
+        /// Job ids
+        #[arg(required = true)]
+        ids: Vec<i64>,
+
+        /// Queue database path (defaults to DATASHEET_JOBS_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self, String> {
+        JobStatus::from_str(s, true).map_err(|_| format!("Unknown job status '{}'", s))
+    }
+}
+
+/// Extraction options stored with each job.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct JobOptions {
+    model: Option<String>,
+    pages: Option<String>,
+    prompt: Option<String>,
+    schema: Option<String>,
+    temperature: Option<f32>,
+    #[serde(default)]
+    no_cache: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Job {
+    pub id: i64,
+    pub task: String,
+    pub pdf: String,
+    pub out: Option<String>,
+    pub status: JobStatus,
+    pub attempts: u32,
+    pub max_attempts: u32,
+    pub tokens: u64,
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    #[serde(skip)]
+    options: String,
+}
+
+/// Handle to the job queue.
+pub struct JobsDb {
+    conn: Connection,
+}
+
+impl JobsDb {
+    /// Open (and create if needed) the queue at `path`, `DATASHEET_JOBS_DB`, or the default location.
+    pub fn open(path: Option<PathBuf>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path,
+            None => match std::env::var(ENV_VAR_DB) {
+                Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+                _ => dirs::data_dir()
+                    .ok_or_else(|| "Could not determine data directory".to_string())?
+                    .join("datasheet-cli")
+                    .join("jobs.db"),
+            },
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let conn = Connection::open(&path)
+            .map_err(|e| format!("Failed to open job queue {}: {}", path.display(), e))?;
+        Self::init(conn)
+    }
+
+    #[cfg(test)]
+    fn open_in_memory() -> Result<Self, String> {
+        Self::init(Connection::open_in_memory().map_err(db_err)?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, String> {
+        // Concurrent workers wait for each other's writes instead of failing
+        conn.busy_timeout(Duration::from_secs(30)).map_err(db_err)?;
+        conn.execute_batch(SCHEMA).map_err(db_err)?;
+        Ok(Self { conn })
+    }
+
+    fn submit(&self, task: &str, pdf: &str, options: &JobOptions, out: Option<&str>, max_attempts: u32) -> Result<i64, String> {
+        let options = serde_json::to_string(options).map_err(|e| e.to_string())?;
+        let now = now();
+        self.conn
+            .execute(
+                "INSERT INTO jobs (task, pdf, options, out, status, max_attempts, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, 'queued', ?5, ?6, ?6)",
+                params![task, pdf, options, out, max_attempts, now],
+            )
+            .map_err(db_err)?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Atomically take the oldest queued job and mark it running.
+    fn claim(&self) -> Result<Option<Job>, String> {
+        let id: Option<i64> = self
+            .conn
+            .query_row(
+                "UPDATE jobs SET status = 'running', attempts = attempts + 1, updated_at = ?1
+                 WHERE id = (SELECT id FROM jobs WHERE status = 'queued' ORDER BY id LIMIT 1)
+                 RETURNING id",
+                params![now()],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_err)?;
+        match id {
+            Some(id) => self.get(id),
+            None => Ok(None),
+        }
+    }
+
+    fn finish(&self, id: i64, result: &Value, tokens: u64) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE jobs SET status = 'done', result = ?2, error = NULL,
+                 tokens = tokens + ?3, updated_at = ?4 WHERE id = ?1",
+                params![id, result.to_string(), tokens as i64, now()],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    /// Record a failed attempt; the job is requeued while attempts remain.
+    fn fail(&self, id: i64, error: &str, tokens: u64) -> Result<JobStatus, String> {
+        self.conn
+            .execute(
+                "UPDATE jobs SET
+                    status = CASE WHEN attempts < max_attempts THEN 'queued' ELSE 'failed' END,
+                    error = ?2, tokens = tokens + ?3, updated_at = ?4
+                 WHERE id = ?1",
+                params![id, error, tokens as i64, now()],
+            )
+            .map_err(db_err)?;
+        self.get(id)?
+            .map(|job| job.status)
+            .ok_or_else(|| format!("Job {} disappeared", id))
+    }
+
+    fn retry(&self, id: i64) -> Result<bool, String> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE jobs SET status = 'queued', attempts = 0, updated_at = ?2
+                 WHERE id = ?1 AND status IN ('done', 'failed', 'cancelled')",
+                params![id, now()],
+            )
+            .map_err(db_err)?;
+        Ok(changed > 0)
+    }
+
+    fn failed_ids(&self) -> Result<Vec<i64>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM jobs WHERE status = 'failed' ORDER BY id")
+            .map_err(db_err)?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(db_err)?
+            .collect::<Result<Vec<i64>, _>>()
+            .map_err(db_err)?;
+        Ok(ids)
+    }
+
+    fn cancel(&self, id: i64) -> Result<bool, String> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE jobs SET status = 'cancelled', updated_at = ?2 WHERE id = ?1 AND status = 'queued'",
+                params![id, now()],
+            )
+            .map_err(db_err)?;
+        Ok(changed > 0)
+    }
+
+    fn get(&self, id: i64) -> Result<Option<Job>, String> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS),
+                params![id],
+                row_to_job,
+            )
+            .optional()
+            .map_err(db_err)?
+            .transpose()
+    }
+
+    fn list(&self, status: Option<JobStatus>, limit: usize) -> Result<Vec<Job>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM jobs WHERE ?1 IS NULL OR status = ?1 ORDER BY id DESC LIMIT ?2",
+                JOB_COLUMNS
+            ))
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map(params![status.map(JobStatus::as_str), limit as i64], row_to_job)
+            .map_err(db_err)?;
+        let mut jobs = Vec::new();
+        for row in rows {
+            let mut job = row.map_err(db_err)??;
+            job.result = None;
+            jobs.push(job);
+        }
+        Ok(jobs)
+    }
+}
+
+const JOB_COLUMNS: &str =
+    "id, task, pdf, out, status, attempts, max_attempts, tokens, error, result, created_at, updated_at, options";
+
+fn row_to_job(row: &rusqlite::Row) -> rusqlite::Result<Result<Job, String>> {
+    let status: String = row.get(4)?;
+    let result: Option<String> = row.get(9)?;
+    let tokens: i64 = row.get(7)?;
+    Ok(JobStatus::parse(&status).map(|status| Job {
+        id: row.get(0).unwrap_or_default(),
+        task: row.get(1).unwrap_or_default(),
+        pdf: row.get(2).unwrap_or_default(),
+        out: row.get(3).unwrap_or_default(),
+        status,
+        attempts: row.get(5).unwrap_or_default(),
+        max_attempts: row.get(6).unwrap_or_default(),
+        tokens: tokens.max(0) as u64,
+        error: row.get(8).unwrap_or_default(),
+        result: result.and_then(|r| serde_json::from_str(&r).ok()),
+        created_at: row.get(10).unwrap_or_default(),
+        updated_at: row.get(11).unwrap_or_default(),
+        options: row.get(12).unwrap_or_default(),
+    }))
+}
+
+fn db_err(e: rusqlite::Error) -> String {
+    format!("Database error: {}", e)
+}
+
+pub fn execute(command: JobsSubcommand) -> Result<(), String> {
+    match command {
+        JobsSubcommand::Submit {
+            task,
+            pdfs,
+            model,
+            pages,
+            prompt,
+            schema,
+            temperature,
+            no_cache,
+            out_dir,
+            retries,
+            db,
+        } => {
+            let options = JobOptions {
+                model,
+                pages,
+                prompt,
+                schema,
+                temperature,
+                no_cache,
+            };
+            cmd_submit(&JobsDb::open(db)?, task, &pdfs, &options, out_dir.as_deref(), retries)
+        }
+        JobsSubcommand::Run {
+            limit,
+            api_key,
+            base_url,
+            db,
+            notify,
+        } => {
+            let started = Instant::now();
+            let outcome = JobsDb::open(db).and_then(|db| cmd_run(&db, limit, api_key, base_url));
+            crate::notify::send(&notify, "jobs run", started, &outcome);
+            outcome.map(|_| ())
+        }
+        JobsSubcommand::List {
+            status,
+            limit,
+            db,
+            json,
+        } => cmd_list(&JobsDb::open(db)?, status, limit, json),
+        JobsSubcommand::Show { id, db, json } => cmd_show(&JobsDb::open(db)?, id, json),
+        JobsSubcommand::Retry { ids, failed, db } => cmd_retry(&JobsDb::open(db)?, ids, failed),
+        JobsSubcommand::Cancel { ids, db } => {
+            let db = JobsDb::open(db)?;
+            for id in ids {
+                if db.cancel(id)? {
+                    println!("Cancelled job {}", id);
+                } else {
+                    eprintln!("[JOBS] Job {} is not queued; left unchanged", id);
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn task_name(task: ExtractTask) -> String {
+    task.to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_else(|| task.prompt().name.to_string())
+}
+
+fn cmd_submit(
+    db: &JobsDb,
+    task: ExtractTask,
+    pdfs: &[PathBuf],
+    options: &JobOptions,
+    out_dir: Option<&Path>,
+    retries: u32,
+) -> Result<(), String> {
+    let name = task_name(task);
+    for pdf in &crate::archive::expand_inputs(pdfs)? {
+        // Store absolute paths so workers can run from any directory
+        let pdf = std::fs::canonicalize(pdf).map_err(|e| format!("{}: {}", pdf.display(), e))?;
+        let out = out_dir.map(|dir| {
+            let stem = pdf.file_stem().unwrap_or_default().to_string_lossy();
+            std::path::absolute(dir.join(format!("{}.{}.json", stem, name)))
+                .unwrap_or_else(|_| dir.join(format!("{}.{}.json", stem, name)))
+        });
+        let id = db.submit(
+            &name,
+            &pdf.to_string_lossy(),
+            options,
+            out.as_ref().map(|p| p.to_string_lossy()).as_deref(),
+            retries + 1,
+        )?;
+        println!("Queued job {}: {} {}", id, name, pdf.display());
+    }
+    Ok(())
+}
+
+/// Returns the summary line.
+fn cmd_run(
+    db: &JobsDb,
+    limit: Option<usize>,
+    api_key: Option<String>,
+    base_url: Option<String>,
+) -> Result<String, String> {
+    let mut processed = 0;
+    let (mut done, mut failed) = (0, 0);
+    while limit.is_none_or(|limit| processed < limit) {
+        // Leave the remaining jobs queued rather than failing each one
+        if let Err(e) = crate::budget::check() {
+            return Err(format!(
+                "{}; stopped after {} job(s) ({} done, {} failed), the rest are still queued",
+                e, processed, done, failed
+            ));
+        }
+        let Some(job) = db.claim()? else { break };
+        processed += 1;
+        eprintln!(
+            "[JOBS] Running job {} ({} {}, attempt {}/{})",
+            job.id, job.task, job.pdf, job.attempts, job.max_attempts
+        );
+
+        let tokens_before = llm::tokens_used();
+        let outcome = run_job(&job, api_key.clone(), base_url.clone());
+        let tokens = llm::tokens_used() - tokens_before;
+        match outcome {
+            Ok(value) => {
+                db.finish(job.id, &value, tokens)?;
+                done += 1;
+                eprintln!("[JOBS] Job {} done ({} tokens)", job.id, tokens);
+            }
+            Err(e) => {
+                let status = db.fail(job.id, &e, tokens)?;
+                if status == JobStatus::Failed {
+                    failed += 1;
+                }
+                eprintln!("[JOBS] Job {} {}: {}", job.id, status.as_str(), e);
+            }
+        }
+    }
+
+    let summary = format!("Processed {} job(s): {} done, {} failed", processed, done, failed);
+    eprintln!("[JOBS] {}", summary);
+    Ok(summary)
+}
+
+fn run_job(job: &Job, api_key: Option<String>, base_url: Option<String>) -> Result<Value, String> {
+    let task = ExtractTask::from_str(&job.task, true).map_err(|_| format!("Unknown task '{}'", job.task))?;
+    let options: JobOptions = serde_json::from_str(&job.options).map_err(|e| format!("Bad job options: {}", e))?;
+    let defaults = ExtractArgs::for_task(task, PathBuf::from(&job.pdf));
+    let args = ExtractArgs {
+        model: options.model.unwrap_or(defaults.model.clone()),
+        api_key,
+        base_url,
+        temperature: options.temperature,
+        formatted: true,
+        prompt: options.prompt,
+        schema: options.schema,
+        pages: options.pages,
+        no_cache: options.no_cache,
+        ..defaults
+    };
+    let value = extract::extract_json(&args).map_err(|e| format!("{:#}", e))?;
+    if let Some(ref out) = job.out {
+        extract::write_output(&value, Some(Path::new(out)), true).map_err(|e| format!("{:#}", e))?;
+    }
+    Ok(value)
+}
+
+fn cmd_list(db: &JobsDb, status: Option<JobStatus>, limit: usize, json_output: bool) -> Result<(), String> {
+    let jobs = db.list(status, limit)?;
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&jobs)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if jobs.is_empty() {
+        println!("No jobs");
+        return Ok(());
+    }
+
+    println!("{} job(s):", jobs.len());
+    for job in &jobs {
+        let file = Path::new(&job.pdf)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| job.pdf.clone());
+        println!(
+            "  {:>5}  {:<9} {:<20} {}  ({}/{} attempts, {} tokens)",
+            job.id,
+            job.status.as_str(),
+            job.task,
+            file,
+            job.attempts,
+            job.max_attempts,
+            job.tokens
+        );
+    }
+    Ok(())
+}
+
+fn cmd_show(db: &JobsDb, id: i64, json_output: bool) -> Result<(), String> {
+    let job = db.get(id)?.ok_or_else(|| format!("No job {}", id))?;
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&job)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    println!("Job {}", job.id);
+    println!("  Task: {}", job.task);
+    println!("  PDF: {}", job.pdf);
+    if let Some(ref out) = job.out {
+        println!("  Output: {}", out);
+    }
+    println!("  Status: {}", job.status.as_str());
+    println!("  Attempts: {}/{}", job.attempts, job.max_attempts);
+    println!("  Tokens: {}", job.tokens);
+    if let Some(ref error) = job.error {
+        println!("  Last error: {}", error);
+    }
+    if let Some(ref result) = job.result {
+        println!();
+        println!("{}", crate::select::to_string_pretty(result).unwrap_or_default());
+    }
+    Ok(())
+}
+
+fn cmd_retry(db: &JobsDb, mut ids: Vec<i64>, failed: bool) -> Result<(), String> {
+    if failed {
+        ids.extend(db.failed_ids()?);
+    }
+    if ids.is_empty() {
+        return Err("No jobs to retry (give job ids or --failed)".to_string());
+    }
+    for id in ids {
+        if db.retry(id)? {
+            println!("Requeued job {}", id);
+        } else {
+            eprintln!("[JOBS] Job {} is queued, running, or missing; left unchanged", id);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lifecycle() {
+        let db = JobsDb::open_in_memory().unwrap();
+        let id = db.submit("pinout", "/tmp/a.pdf", &JobOptions::default(), None, 2).unwrap();
+
+        let job = db.claim().unwrap().unwrap();
+        assert_eq!((job.id, job.status, job.attempts), (id, JobStatus::Running, 1));
+        assert!(db.claim().unwrap().is_none());
+
+        // First failure requeues, second exhausts the attempts
+        assert_eq!(db.fail(id, "boom", 10).unwrap(), JobStatus::Queued);
+        db.claim().unwrap().unwrap();
+        assert_eq!(db.fail(id, "boom", 5).unwrap(), JobStatus::Failed);
+        assert_eq!(db.failed_ids().unwrap(), [id]);
+
+        assert!(db.retry(id).unwrap());
+        db.claim().unwrap().unwrap();
+        db.finish(id, &serde_json::json!({"ok": true}), 7).unwrap();
+        let job = db.get(id).unwrap().unwrap();
+        assert_eq!((job.status, job.tokens, job.error), (JobStatus::Done, 22, None));
+        assert!(!db.cancel(id).unwrap());
+    }
+}
@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Local check of model output against a task's JSON schema, for models that
+//! are given the schema in the prompt instead of through `responseJsonSchema`
+//! (`--schema-mode prompt`).
+//!
+//! Covers the keywords the built-in schemas use: `type`, `properties`,
+//! `required`, `additionalProperties`, `items`, `enum`, `minItems`, and
+//! `maxItems`. Other keywords are ignored rather than rejected.
+
+use serde_json::Value;
+
+/// Every place `value` breaks `schema`, as "<JSON pointer>: <problem>".
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(value, schema, "", &mut errors);
+    errors
+}
+
+fn check(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let at = if path.is_empty() { "/" } else { path };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| is_type(value, t)) {
+            errors.push(format!("{}: expected {}, got {}", at, types.join(" or "), type_name(value)));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array).filter(|a| !a.contains(value)) {
+        errors.push(format!("{}: {} is not one of {}", at, value, Value::Array(allowed.clone())));
+    }
+
+    match value {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for name in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+                if let Some(name) = name.as_str().filter(|n| !map.contains_key(*n)) {
+                    errors.push(format!("{}: missing required property \"{}\"", at, name));
+                }
+            }
+            for (name, field) in map {
+                let field_path = format!("{}/{}", path, name.replace('~', "~0").replace('/', "~1"));
+                match properties.and_then(|p| p.get(name)) {
+                    Some(field_schema) => check(field, field_schema, &field_path, errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.push(format!("{}: property \"{}\" is not allowed", at, name));
+                        }
+                        Some(extra @ Value::Object(_)) => check(field, extra, &field_path, errors),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64).filter(|&min| len < min) {
+                errors.push(format!("{}: {} item(s), at least {} required", at, len, min));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64).filter(|&max| len > max) {
+                errors.push(format!("{}: {} item(s), at most {} allowed", at, len, max));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item, item_schema, &format!("{}/{}", path, i), errors);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(_) => "number",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate() {
+        let schema = json!({
+            "type": "object",
+            "required": ["part", "pins"],
+            "additionalProperties": false,
+            "properties": {
+                "part": { "type": "string" },
+                "pins": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "number": { "type": ["string", "null"] },
+                            "kind": { "type": "string", "enum": ["power", "io"] }
+                        }
+                    }
+                }
+            }
+        });
+        let good = json!({ "part": "LM358", "pins": [{ "number": "1", "kind": "io" }, { "number": null }] });
+        assert!(validate(&good, &schema).is_empty());
+
+        let bad = json!({ "pins": [{ "number": 1, "kind": "analog" }], "note": "x" });
+        assert_eq!(
+            validate(&bad, &schema),
+            vec![
+                "/: missing required property \"part\"",
+                "/: property \"note\" is not allowed",
+                "/pins/0/kind: \"analog\" is not one of [\"power\",\"io\"]",
+                "/pins/0/number: expected string or null, got number",
+            ]
+        );
+    }
+}
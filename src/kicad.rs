@@ -0,0 +1,646 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! KiCad project integration.
+//!
+//! `kicad sync` scans a project's schematics for symbols with an MPN field and
+//! fills in what is missing:
+//! - datasheets are downloaded into `<project>/datasheets/`
+//! - SnapEDA symbols are merged into a project symbol library (`<lib>.kicad_sym`)
+//! - SnapEDA footprints are written to a project footprint library (`<lib>.pretty/`)
+//!
+//! The project `sym-lib-table` and `fp-lib-table` are created or extended so the
+//! new libraries show up in KiCad. Schematics themselves are never modified.
+
+use clap::Subcommand;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::bom::Source;
+use crate::paths::{UniqueNames, sanitize_filename};
+use crate::download::{self, DownloadOptions};
+use crate::snapeda;
+
+/// Schematic field names recognized as the manufacturer part number (case-insensitive).
+const MPN_FIELDS: &[&str] = &[
+    "mpn",
+    "manufacturer part number",
+    "manufacturer_part_number",
+    "mfr part number",
+    "mfr. part #",
+    "mfr_pn",
+    "mpn1",
+];
+const MANUFACTURER_FIELDS: &[&str] = &["manufacturer", "mfr", "manufacturer_name"];
+const DATASHEET_DIR: &str = "datasheets";
+
+/// KiCad subcommands.
+#[derive(Subcommand, Debug)]
+pub enum KicadSubcommand {
+    /// Fetch missing datasheets, symbols, and footprints for a project's parts
+    Sync {
+        /// KiCad project directory
+        project: PathBuf,
+
+        /// Nickname of the project libraries that fetched symbols/footprints go into
+        #[arg(long, default_value = "datasheet")]
+        lib_name: String,
+
+        /// Datasheet sources to try, in order (comma-separated)
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "mouser,digikey,jlcpcb,manufacturer")]
+        source: Vec<Source>,
+
+        /// Skip datasheet downloads
+        #[arg(long)]
+        no_datasheets: bool,
+
+        /// Skip SnapEDA symbol downloads
+        #[arg(long)]
+        no_symbols: bool,
+
+        /// Skip SnapEDA footprint downloads
+        #[arg(long)]
+        no_footprints: bool,
+
+        /// Only report what would be fetched
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Execute a KiCad subcommand.
+pub fn execute(command: KicadSubcommand) -> Result<(), String> {
+    match command {
+        KicadSubcommand::Sync {
+            project,
+            lib_name,
+            source,
+            no_datasheets,
+            no_symbols,
+            no_footprints,
+            dry_run,
+            json,
+        } => cmd_sync(
+            &project,
+            &SyncOptions {
+                lib_name,
+                sources: source,
+                datasheets: !no_datasheets,
+                symbols: !no_symbols,
+                footprints: !no_footprints,
+                dry_run,
+            },
+            json,
+        ),
+    }
+}
+
+struct SyncOptions {
+    lib_name: String,
+    sources: Vec<Source>,
+    datasheets: bool,
+    symbols: bool,
+    footprints: bool,
+    dry_run: bool,
+}
+
+/// A part referenced from the schematics, merged across all its instances.
+#[derive(Debug, Default)]
+struct SchematicPart {
+    mpn: String,
+    manufacturer: Option<String>,
+    references: Vec<String>,
+    has_footprint: bool,
+    has_datasheet: bool,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct PartReport {
+    mpn: String,
+    references: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    datasheet: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    footprint: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>,
+}
+
+fn cmd_sync(project: &Path, options: &SyncOptions, json_output: bool) -> Result<(), String> {
+    if !project.is_dir() {
+        return Err(format!("Not a directory: {}", project.display()));
+    }
+
+    let mut parts: BTreeMap<String, SchematicPart> = BTreeMap::new();
+    let schematics = find_schematics(project)?;
+    if schematics.is_empty() {
+        return Err(format!("No .kicad_sch files found in {}", project.display()));
+    }
+    for path in &schematics {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        for part in scan_schematic(&text)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+        {
+            let entry = parts.entry(part.mpn.to_uppercase()).or_insert_with(|| SchematicPart {
+                mpn: part.mpn.clone(),
+                ..Default::default()
+            });
+            entry.manufacturer = entry.manufacturer.take().or(part.manufacturer);
+            entry.references.extend(part.references);
+            entry.has_footprint |= part.has_footprint;
+            entry.has_datasheet |= part.has_datasheet;
+        }
+    }
+    eprintln!(
+        "[KICAD] {} part(s) with MPN fields in {} schematic(s)",
+        parts.len(),
+        schematics.len()
+    );
+
+    let sym_lib_path = project.join(format!("{}.kicad_sym", options.lib_name));
+    let fp_lib_dir = project.join(format!("{}.pretty", options.lib_name));
+    let datasheet_dir = project.join(DATASHEET_DIR);
+    let mut sym_lib = fs::read_to_string(&sym_lib_path).ok();
+    let mut reports = Vec::new();
+    let (mut new_symbols, mut new_footprints) = (0, 0);
+    let mut datasheet_names = UniqueNames::default();
+
+    for part in parts.values() {
+        let mut report = PartReport {
+            mpn: part.mpn.clone(),
+            references: part.references.clone(),
+            ..Default::default()
+        };
+
+        // ── Datasheet ──────────────────────────────────────────────────
+        let datasheet_path = datasheet_dir.join(format!("{}.pdf", datasheet_names.name(&part.mpn)));
+        if options.datasheets && !part.has_datasheet && !datasheet_path.exists() {
+            if options.dry_run {
+                report.datasheet = Some("missing".to_string());
+            } else {
+                match fetch_datasheet(part, &options.sources, &datasheet_path) {
+                    Ok(source) => {
+                        report.datasheet = Some(format!("{} (from {})", datasheet_path.display(), source));
+                    }
+                    Err(e) => report.errors.push(e),
+                }
+            }
+        }
+
+        // ── Symbol / footprint from SnapEDA ────────────────────────────
+        let want_symbol = options.symbols
+            && !sym_lib.as_deref().is_some_and(|lib| library_has_symbol(lib, &part.mpn));
+        let want_footprint = options.footprints && !part.has_footprint;
+        if want_symbol || want_footprint {
+            if options.dry_run {
+                if want_symbol {
+                    report.symbol = Some("missing".to_string());
+                }
+                if want_footprint {
+                    report.footprint = Some("missing".to_string());
+                }
+            } else {
+                eprintln!("[KICAD] Fetching KiCad files for {} from SnapEDA", part.mpn);
+                match snapeda::download_kicad(&part.mpn, want_symbol, want_footprint) {
+                    Ok(files) => {
+                        if let Some(text) = files.symbol {
+                            match merge_symbols(sym_lib.as_deref(), &text) {
+                                Ok((merged, names)) if !names.is_empty() => {
+                                    report.symbol = Some(format!("{}:{}", options.lib_name, names.join(", ")));
+                                    new_symbols += names.len();
+                                    sym_lib = Some(merged);
+                                }
+                                Ok(_) => {}
+                                Err(e) => report.errors.push(format!("symbol: {}", e)),
+                            }
+                        }
+                        if let Some(text) = files.footprint {
+                            match write_footprint(&fp_lib_dir, &text) {
+                                Ok(name) => {
+                                    report.footprint = Some(format!("{}:{}", options.lib_name, name));
+                                    new_footprints += 1;
+                                }
+                                Err(e) => report.errors.push(format!("footprint: {}", e)),
+                            }
+                        }
+                    }
+                    Err(e) => report.errors.push(format!("snapeda: {}", e)),
+                }
+            }
+        }
+
+        reports.push(report);
+    }
+
+    if !options.dry_run {
+        if new_symbols > 0 {
+            if let Some(ref lib) = sym_lib {
+                crate::paths::write_file(&sym_lib_path, lib)
+                    .map_err(|e| format!("Failed to write {}: {}", sym_lib_path.display(), e))?;
+            }
+            let uri = format!("${{KIPRJMOD}}/{}.kicad_sym", options.lib_name);
+            update_lib_table(&project.join("sym-lib-table"), "sym_lib_table", &options.lib_name, &uri)?;
+        }
+        if new_footprints > 0 {
+            let uri = format!("${{KIPRJMOD}}/{}.pretty", options.lib_name);
+            update_lib_table(&project.join("fp-lib-table"), "fp_lib_table", &options.lib_name, &uri)?;
+        }
+    }
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&reports)
+            .map_err(|e| format!("Failed to serialize report: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    for report in &reports {
+        let changes: Vec<String> = [
+            report.datasheet.as_ref().map(|d| format!("datasheet: {}", d)),
+            report.symbol.as_ref().map(|s| format!("symbol: {}", s)),
+            report.footprint.as_ref().map(|f| format!("footprint: {}", f)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if changes.is_empty() && report.errors.is_empty() {
+            continue;
+        }
+        println!("{} ({})", report.mpn, report.references.join(", "));
+        for change in changes {
+            println!("  {}", change);
+        }
+        for error in &report.errors {
+            println!("  ! {}", error);
+        }
+    }
+    if options.dry_run {
+        let pending = reports
+            .iter()
+            .filter(|r| r.datasheet.is_some() || r.symbol.is_some() || r.footprint.is_some())
+            .count();
+        println!("[dry run] {} part(s) need datasheets, symbols, or footprints", pending);
+    } else {
+        println!("{} symbol(s), {} footprint(s) added", new_symbols, new_footprints);
+    }
+    Ok(())
+}
+
+/// All `.kicad_sch` files under the project directory, skipping backups.
+fn find_schematics(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut found = Vec::new();
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            if !name.starts_with('.') && !name.ends_with("-backups") && name != DATASHEET_DIR {
+                found.extend(find_schematics(&path)?);
+            }
+        } else if name.ends_with(".kicad_sch") {
+            found.push(path);
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+fn fetch_datasheet(part: &SchematicPart, sources: &[Source], output: &Path) -> Result<&'static str, String> {
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut errors = Vec::new();
+    for &source in sources {
+        match source.find_datasheet_url(&part.mpn, part.manufacturer.as_deref()) {
+            Ok(Some(url)) => match download::download_pdf(&url, output, &DownloadOptions::default()) {
+                Ok(_) => return Ok(source.name()),
+                Err(e) => errors.push(format!("{}: {}", source.name(), e)),
+            },
+            Ok(None) => {}
+            Err(e) => errors.push(format!("{}: {}", source.name(), e)),
+        }
+    }
+    if errors.is_empty() {
+        Err("datasheet: not found".to_string())
+    } else {
+        Err(format!("datasheet: {}", errors.join("; ")))
+    }
+}
+
+// --- Schematic scanning ---
+
+/// Placed symbols with an MPN field, one entry per symbol instance.
+fn scan_schematic(text: &str) -> Result<Vec<SchematicPart>, String> {
+    let root = parse_sexp(text)?;
+    let mut parts = Vec::new();
+    collect_parts(&root, &mut parts);
+    Ok(parts)
+}
+
+fn collect_parts(node: &Sexp, parts: &mut Vec<SchematicPart>) {
+    let Sexp::List(children, _) = node else {
+        return;
+    };
+    // Placed symbols have a lib_id; library definitions under lib_symbols do not
+    if node.head() == Some("symbol") && node.child("lib_id").is_some() {
+        let mut props: Vec<(String, String)> = Vec::new();
+        for child in children.iter().filter(|c| c.head() == Some("property")) {
+            if let (Some(name), Some(value)) = (child.atom(1), child.atom(2)) {
+                props.push((name.to_lowercase(), value.to_string()));
+            }
+        }
+        let prop = |names: &[&str]| {
+            props
+                .iter()
+                .find(|(n, v)| names.contains(&n.as_str()) && !v.trim().is_empty())
+                .map(|(_, v)| v.trim().to_string())
+        };
+        let reference = prop(&["reference"]).unwrap_or_default();
+        if let Some(mpn) = prop(MPN_FIELDS).filter(|_| !reference.starts_with('#')) {
+            let has = |name: &str| prop(&[name]).is_some_and(|v| v != "~");
+            parts.push(SchematicPart {
+                mpn,
+                manufacturer: prop(MANUFACTURER_FIELDS),
+                references: vec![reference],
+                has_footprint: has("footprint"),
+                has_datasheet: has("datasheet"),
+            });
+        }
+        return;
+    }
+    for child in children {
+        collect_parts(child, parts);
+    }
+}
+
+// --- Library files ---
+
+/// Whether a `.kicad_sym` library already has a symbol for this MPN (by name or MPN/Value field).
+fn library_has_symbol(lib: &str, mpn: &str) -> bool {
+    let Ok(root) = parse_sexp(lib) else {
+        return false;
+    };
+    let Sexp::List(children, _) = &root else {
+        return false;
+    };
+    children.iter().filter(|c| c.head() == Some("symbol")).any(|symbol| {
+        symbol.atom(1).is_some_and(|n| n.eq_ignore_ascii_case(mpn))
+            || symbol.children().iter().any(|p| {
+                p.head() == Some("property")
+                    && p.atom(1).is_some_and(|n| {
+                        let n = n.to_lowercase();
+                        n == "value" || MPN_FIELDS.contains(&n.as_str())
+                    })
+                    && p.atom(2).is_some_and(|v| v.trim().eq_ignore_ascii_case(mpn))
+            })
+    })
+}
+
+/// Merge the top-level symbols of `downloaded` into `existing`, skipping names already present.
+///
+/// Returns the new library text and the names of the symbols added.
+fn merge_symbols(existing: Option<&str>, downloaded: &str) -> Result<(String, Vec<String>), String> {
+    let new_root = parse_sexp(downloaded)?;
+    if new_root.head() != Some("kicad_symbol_lib") {
+        return Err("downloaded file is not a KiCad symbol library".to_string());
+    }
+    let Some(existing) = existing else {
+        let names = new_root
+            .children()
+            .iter()
+            .filter(|c| c.head() == Some("symbol"))
+            .filter_map(|c| c.atom(1).map(String::from))
+            .collect();
+        return Ok((downloaded.to_string(), names));
+    };
+
+    let root = parse_sexp(existing)?;
+    let present: Vec<String> = root
+        .children()
+        .iter()
+        .filter(|c| c.head() == Some("symbol"))
+        .filter_map(|c| c.atom(1).map(String::from))
+        .collect();
+
+    let mut added = Vec::new();
+    let mut blocks = String::new();
+    for symbol in new_root.children().iter().filter(|c| c.head() == Some("symbol")) {
+        let Some(name) = symbol.atom(1) else {
+            continue;
+        };
+        if present.iter().any(|p| p == name) {
+            continue;
+        }
+        let (start, end) = symbol.span();
+        blocks.push_str("  ");
+        blocks.push_str(&downloaded[start..end]);
+        blocks.push('\n');
+        added.push(name.to_string());
+    }
+
+    // Insert before the closing paren of the library
+    let (_, end) = root.span();
+    let close = end - 1;
+    let mut merged = existing[..close].trim_end().to_string();
+    merged.push('\n');
+    merged.push_str(&blocks);
+    merged.push_str(&existing[close..]);
+    Ok((merged, added))
+}
+
+/// Write a footprint into the `.pretty` directory; returns the footprint name.
+fn write_footprint(dir: &Path, text: &str) -> Result<String, String> {
+    let root = parse_sexp(text)?;
+    if !matches!(root.head(), Some("footprint") | Some("module")) {
+        return Err("downloaded file is not a KiCad footprint".to_string());
+    }
+    let name = root
+        .atom(1)
+        .map(String::from)
+        .ok_or_else(|| "footprint has no name".to_string())?;
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.kicad_mod", sanitize_filename(&name)));
+    if !path.exists() {
+        crate::paths::write_file(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(name)
+}
+
+/// Add a `(lib ...)` row to a KiCad library table, creating the table if needed.
+fn update_lib_table(path: &Path, table: &str, name: &str, uri: &str) -> Result<(), String> {
+    let row = format!(
+        "  (lib (name \"{}\")(type \"KiCad\")(uri \"{}\")(options \"\")(descr \"Fetched by datasheet-cli\"))\n",
+        name, uri
+    );
+    let text = match fs::read_to_string(path) {
+        Ok(text) => {
+            let root = parse_sexp(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let exists = root
+                .children()
+                .iter()
+                .filter(|c| c.head() == Some("lib"))
+                .any(|lib| lib.child("name").and_then(|n| n.atom(1)) == Some(name));
+            if exists {
+                return Ok(());
+            }
+            let (_, end) = root.span();
+            format!("{}\n{}{}", text[..end - 1].trim_end(), row, &text[end - 1..])
+        }
+        Err(_) => format!("({}\n  (version 7)\n{})\n", table, row),
+    };
+    crate::paths::write_file(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    eprintln!("[KICAD] Added '{}' to {}", name, path.display());
+    Ok(())
+}
+
+// --- S-expressions ---
+
+/// A KiCad S-expression node with its byte span in the source text.
+#[derive(Debug)]
+enum Sexp {
+    Atom(String, (usize, usize)),
+    List(Vec<Sexp>, (usize, usize)),
+}
+
+impl Sexp {
+    fn span(&self) -> (usize, usize) {
+        match self {
+            Sexp::Atom(_, span) | Sexp::List(_, span) => *span,
+        }
+    }
+
+    fn children(&self) -> &[Sexp] {
+        match self {
+            Sexp::List(children, _) => children,
+            Sexp::Atom(..) => &[],
+        }
+    }
+
+    /// The atom at position `i` of a list.
+    fn atom(&self, i: usize) -> Option<&str> {
+        match self.children().get(i)? {
+            Sexp::Atom(s, _) => Some(s),
+            Sexp::List(..) => None,
+        }
+    }
+
+    /// The list's leading keyword, e.g. `symbol` for `(symbol ...)`.
+    fn head(&self) -> Option<&str> {
+        self.atom(0)
+    }
+
+    fn child(&self, head: &str) -> Option<&Sexp> {
+        self.children().iter().find(|c| c.head() == Some(head))
+    }
+}
+
+fn parse_sexp(text: &str) -> Result<Sexp, String> {
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+    skip_whitespace(bytes, &mut pos);
+    let node = parse_node(text, &mut pos)?;
+    Ok(node)
+}
+
+fn skip_whitespace(bytes: &[u8], pos: &mut usize) {
+    while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+        *pos += 1;
+    }
+}
+
+fn parse_node(text: &str, pos: &mut usize) -> Result<Sexp, String> {
+    let bytes = text.as_bytes();
+    let start = *pos;
+    match bytes.get(start) {
+        None => Err("unexpected end of input".to_string()),
+        Some(b'(') => {
+            *pos += 1;
+            let mut children = Vec::new();
+            loop {
+                skip_whitespace(bytes, pos);
+                match bytes.get(*pos) {
+                    None => return Err(format!("unclosed list starting at byte {}", start)),
+                    Some(b')') => {
+                        *pos += 1;
+                        return Ok(Sexp::List(children, (start, *pos)));
+                    }
+                    Some(_) => children.push(parse_node(text, pos)?),
+                }
+            }
+        }
+        Some(b')') => Err(format!("unexpected ')' at byte {}", start)),
+        Some(b'"') => {
+            *pos += 1;
+            let mut value = String::new();
+            let mut chars = text[*pos..].char_indices();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '"' => {
+                        *pos += i + 1;
+                        return Ok(Sexp::Atom(value, (start, *pos)));
+                    }
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            value.push(match escaped {
+                                'n' => '\n',
+                                't' => '\t',
+                                other => other,
+                            });
+                        }
+                    }
+                    c => value.push(c),
+                }
+            }
+            Err(format!("unterminated string at byte {}", start))
+        }
+        Some(_) => {
+            while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() && !matches!(bytes[*pos], b'(' | b')') {
+                *pos += 1;
+            }
+            Ok(Sexp::Atom(text[start..*pos].to_string(), (start, *pos)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_schematic() {
+        let sch = r##"(kicad_sch (version 20231120)
+  (lib_symbols (symbol "Device:R" (property "Reference" "R")))
+  (symbol (lib_id "Regulator:TPS62130") (at 10 10 0)
+    (property "Reference" "U1" (at 0 0 0))
+    (property "Footprint" "" (at 0 0 0))
+    (property "Datasheet" "~" (at 0 0 0))
+    (property "MPN" "TPS62130RGTR" (at 0 0 0)))
+  (symbol (lib_id "power:GND") (property "Reference" "#PWR01") (property "MPN" "X"))
+  (symbol (lib_id "Device:R") (property "Reference" "R1") (property "Footprint" "R_0402"))
+)"##;
+        let parts = scan_schematic(sch).unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].mpn, "TPS62130RGTR");
+        assert_eq!(parts[0].references, ["U1"]);
+        assert!(!parts[0].has_footprint && !parts[0].has_datasheet);
+    }
+
+    #[test]
+    fn test_merge_symbols() {
+        let existing = "(kicad_symbol_lib (version 20211014)\n  (symbol \"A\" (property \"Value\" \"A\"))\n)\n";
+        let downloaded = "(kicad_symbol_lib (version 20211014) (symbol \"A\") (symbol \"B\" (symbol \"B_0_1\")))";
+        let (merged, added) = merge_symbols(Some(existing), downloaded).unwrap();
+        assert_eq!(added, ["B"]);
+        assert!(library_has_symbol(&merged, "b"));
+        assert_eq!(parse_sexp(&merged).unwrap().children().len(), 4);
+    }
+}
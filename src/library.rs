@@ -0,0 +1,687 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Local datasheet library.
+//!
+//! A canonical on-disk store for datasheets shared by downloads and extractions:
+//!
+//! ```text
+//! <root>/<manufacturer>/<MPN>/<MPN>.pdf
+//! <root>/<manufacturer>/<MPN>/datasheet.json   (sidecar: source URL, hash, date, revision)
+//! ```
+//!
+//! The root defaults to the platform data directory and can be overridden with
+//! `--root` or `DATASHEET_LIBRARY`.
+//!
+//! The sidecar's `sha256` is the hash of the canonical copy. When a part is
+//! re-added with different content, the previous hash moves to `history` and a
+//! warning is printed: the manufacturer silently revised the datasheet, or the
+//! download is corrupt. `library verify` re-checks archived files against their
+//! recorded hashes and, with `--redownload`, against the source URL.
+
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::paths::sanitize_filename;
+use crate::download::{self, DownloadOptions};
+use crate::file_cache::compute_hash;
+
+const ENV_VAR_LIBRARY: &str = "DATASHEET_LIBRARY";
+const SIDECAR_NAME: &str = "datasheet.json";
+const UNKNOWN_MANUFACTURER: &str = "unknown";
+
+/// Library subcommands.
+#[derive(Subcommand, Debug)]
+pub enum LibrarySubcommand {
+    /// Add a datasheet (local PDF or URL) to the library
+    Add {
+        /// Path to a PDF, or an http(s) URL to download
+        source: String,
+
+        /// Manufacturer part number
+        #[arg(long)]
+        mpn: String,
+
+        /// Manufacturer name (used for the directory layout)
+        #[arg(long)]
+        manufacturer: Option<String>,
+
+        /// Datasheet revision (e.g. "Rev. F")
+        #[arg(long)]
+        revision: Option<String>,
+
+        /// Library root (defaults to DATASHEET_LIBRARY or the platform data directory)
+        #[arg(long)]
+        root: Option<PathBuf>,
+    },
+
+    /// List datasheets in the library
+    List {
+        /// Only show parts from this manufacturer
+        #[arg(long)]
+        manufacturer: Option<String>,
+
+        /// Library root (defaults to DATASHEET_LIBRARY or the platform data directory)
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Find datasheets by MPN (case-insensitive substring) and print their paths
+    Find {
+        /// MPN or part of one
+        query: String,
+
+        /// Library root (defaults to DATASHEET_LIBRARY or the platform data directory)
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Remove duplicate PDFs, pointing duplicate entries at one shared file
+    Dedupe {
+        /// Show what would be removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Library root (defaults to DATASHEET_LIBRARY or the platform data directory)
+        #[arg(long)]
+        root: Option<PathBuf>,
+    },
+
+    /// Check archived PDFs against their recorded SHA-256 hashes
+    Verify {
+        /// Only verify MPNs containing this text
+        query: Option<String>,
+
+        /// Also download each datasheet from its source URL and compare it to the archived copy
+        #[arg(long)]
+        redownload: bool,
+
+        /// Library root (defaults to DATASHEET_LIBRARY or the platform data directory)
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Sidecar metadata stored next to each datasheet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LibraryEntry {
+    pub mpn: String,
+    pub manufacturer: String,
+    /// PDF path relative to the library root
+    pub file: PathBuf,
+    pub sha256: String,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    /// Unix timestamp when the datasheet was added
+    pub added_at: u64,
+    /// Hashes of earlier revisions this entry replaced, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<HashRecord>,
+}
+
+/// A datasheet revision the library has seen.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HashRecord {
+    pub sha256: String,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    pub added_at: u64,
+}
+
+/// Result of checking one entry in `library verify`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+    /// The archived file matches its recorded hash (and the source, if re-downloaded)
+    Ok,
+    /// The archived file is gone
+    Missing,
+    /// The archived file no longer matches its recorded hash
+    Corrupt,
+    /// The source URL now serves different content
+    Changed,
+    /// The source URL now serves a revision recorded in `history`
+    Reverted,
+    /// The source URL could not be downloaded
+    Unreachable,
+}
+
+#[derive(Serialize, Debug)]
+struct VerifyReport {
+    mpn: String,
+    manufacturer: String,
+    status: VerifyStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+/// A datasheet library rooted at a directory.
+pub struct Library {
+    root: PathBuf,
+}
+
+impl Library {
+    /// Open the library at `root`, `DATASHEET_LIBRARY`, or the default data directory.
+    pub fn open(root: Option<PathBuf>) -> Result<Self, String> {
+        let root = match root {
+            Some(root) => root,
+            None => match std::env::var(ENV_VAR_LIBRARY) {
+                Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+                _ => dirs::data_dir()
+                    .ok_or_else(|| "Could not determine data directory".to_string())?
+                    .join("datasheet-cli")
+                    .join("library"),
+            },
+        };
+        fs::create_dir_all(&root)
+            .map_err(|e| format!("Failed to create library directory {}: {}", root.display(), e))?;
+        Ok(Self { root })
+    }
+
+    fn part_dir(&self, manufacturer: &str, mpn: &str) -> PathBuf {
+        self.root.join(manufacturer_slug(manufacturer)).join(sanitize_filename(mpn))
+    }
+
+    /// Absolute path of an entry's PDF.
+    pub fn pdf_path(&self, entry: &LibraryEntry) -> PathBuf {
+        self.root.join(&entry.file)
+    }
+
+    /// Add PDF bytes for a part, replacing any previous datasheet for the same part.
+    pub fn add_bytes(
+        &self,
+        mpn: &str,
+        manufacturer: Option<&str>,
+        data: &[u8],
+        source_url: Option<String>,
+        revision: Option<String>,
+    ) -> Result<LibraryEntry, String> {
+        if !data.starts_with(b"%PDF") {
+            return Err("not a PDF file".to_string());
+        }
+        let manufacturer = manufacturer
+            .filter(|m| !m.trim().is_empty())
+            .unwrap_or(UNKNOWN_MANUFACTURER)
+            .to_string();
+        let dir = self.part_dir(&manufacturer, mpn);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let sha256 = compute_hash(data);
+        let history = self.revision_history(&dir, mpn, &sha256);
+
+        let pdf_path = dir.join(format!("{}.pdf", sanitize_filename(mpn)));
+        if read_sidecar(&dir).is_some_and(|previous| previous.sha256 != sha256) {
+            self.detach_shared(&pdf_path, &dir)?;
+        }
+        crate::paths::write_file(&pdf_path, data)
+            .map_err(|e| format!("Failed to write {}: {}", pdf_path.display(), e))?;
+
+        let entry = LibraryEntry {
+            mpn: mpn.to_string(),
+            manufacturer,
+            file: pdf_path.strip_prefix(&self.root).unwrap_or(&pdf_path).to_path_buf(),
+            sha256,
+            size: data.len() as u64,
+            source_url,
+            revision,
+            added_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            history,
+        };
+        self.write_sidecar(&dir, &entry)?;
+        Ok(entry)
+    }
+
+    /// Give entries that `library dedupe` pointed at `pdf_path` their own copy
+    /// before it is overwritten, so their content does not change with it.
+    fn detach_shared(&self, pdf_path: &Path, dir: &Path) -> Result<(), String> {
+        let file = pdf_path.strip_prefix(&self.root).unwrap_or(pdf_path);
+        let sharing: Vec<LibraryEntry> = self
+            .entries()?
+            .into_iter()
+            .filter(|e| e.file == file && self.part_dir(&e.manufacturer, &e.mpn) != dir)
+            .collect();
+        let Some(first) = sharing.first() else {
+            return Ok(());
+        };
+
+        let first_dir = self.part_dir(&first.manufacturer, &first.mpn);
+        let copy = first_dir.join(format!("{}.pdf", sanitize_filename(&first.mpn)));
+        fs::copy(pdf_path, &copy).map_err(|e| format!("Failed to copy to {}: {}", copy.display(), e))?;
+        let copy_file = copy.strip_prefix(&self.root).unwrap_or(&copy).to_path_buf();
+        for entry in &sharing {
+            let updated = LibraryEntry {
+                file: copy_file.clone(),
+                ..entry.clone()
+            };
+            self.write_sidecar(&self.part_dir(&entry.manufacturer, &entry.mpn), &updated)?;
+        }
+        eprintln!(
+            "[LIBRARY] Moved the shared copy of {} deduplicated entr{} to {}",
+            sharing.len(),
+            if sharing.len() == 1 { "y" } else { "ies" },
+            copy_file.display()
+        );
+        Ok(())
+    }
+
+    /// History for a part about to be stored with `sha256`, warning if that
+    /// differs from the archived copy.
+    fn revision_history(&self, dir: &Path, mpn: &str, sha256: &str) -> Vec<HashRecord> {
+        let Some(previous) = read_sidecar(dir) else {
+            return Vec::new();
+        };
+        let mut history = previous.history;
+        if previous.sha256 != sha256 {
+            eprintln!(
+                "[LIBRARY] Warning: {} datasheet differs from the archived copy (sha256 {} -> {}); \
+                 the datasheet was revised or the download is corrupt",
+                mpn,
+                &previous.sha256[..12.min(previous.sha256.len())],
+                &sha256[..12.min(sha256.len())]
+            );
+            if history.iter().any(|h| h.sha256 == sha256) {
+                eprintln!("[LIBRARY] Note: the new copy matches an earlier revision of {}", mpn);
+            }
+            history.retain(|h| h.sha256 != previous.sha256);
+            history.push(HashRecord {
+                sha256: previous.sha256,
+                size: previous.size,
+                revision: previous.revision,
+                added_at: previous.added_at,
+            });
+        }
+        history
+    }
+
+    fn write_sidecar(&self, dir: &Path, entry: &LibraryEntry) -> Result<(), String> {
+        let json = crate::select::to_string_pretty(entry)
+            .map_err(|e| format!("Failed to serialize library entry: {}", e))?;
+        crate::paths::write_file(&dir.join(SIDECAR_NAME), json)
+            .map_err(|e| format!("Failed to write library entry: {}", e))
+    }
+
+    /// All entries, sorted by manufacturer then MPN.
+    pub fn entries(&self) -> Result<Vec<LibraryEntry>, String> {
+        let mut entries = Vec::new();
+        let manufacturers = fs::read_dir(&self.root)
+            .map_err(|e| format!("Failed to read library {}: {}", self.root.display(), e))?;
+        for mfr_dir in manufacturers.flatten().filter(|d| d.path().is_dir()) {
+            let Ok(parts) = fs::read_dir(mfr_dir.path()) else {
+                continue;
+            };
+            for part_dir in parts.flatten() {
+                let sidecar = part_dir.path().join(SIDECAR_NAME);
+                let Ok(content) = fs::read_to_string(&sidecar) else {
+                    continue;
+                };
+                match serde_json::from_str::<LibraryEntry>(&content) {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => eprintln!("[LIBRARY] Skipping {}: {}", sidecar.display(), e),
+                }
+            }
+        }
+        entries.sort_by(|a, b| {
+            (a.manufacturer.to_lowercase(), a.mpn.to_lowercase())
+                .cmp(&(b.manufacturer.to_lowercase(), b.mpn.to_lowercase()))
+        });
+        Ok(entries)
+    }
+
+    /// Entries whose MPN contains `query` (case-insensitive), exact matches first.
+    pub fn find(&self, query: &str) -> Result<Vec<LibraryEntry>, String> {
+        let query = query.to_lowercase();
+        let mut matches: Vec<LibraryEntry> = self
+            .entries()?
+            .into_iter()
+            .filter(|e| e.mpn.to_lowercase().contains(&query))
+            .collect();
+        matches.sort_by_key(|e| e.mpn.to_lowercase() != query);
+        Ok(matches)
+    }
+}
+
+fn read_sidecar(dir: &Path) -> Option<LibraryEntry> {
+    let content = fs::read_to_string(dir.join(SIDECAR_NAME)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Directory name for a manufacturer ("Texas Instruments" → "texas-instruments").
+fn manufacturer_slug(manufacturer: &str) -> String {
+    let slug: String = manufacturer
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        UNKNOWN_MANUFACTURER.to_string()
+    } else {
+        slug
+    }
+}
+
+/// Execute a library subcommand.
+pub fn execute(command: LibrarySubcommand) -> Result<(), String> {
+    match command {
+        LibrarySubcommand::Add {
+            source,
+            mpn,
+            manufacturer,
+            revision,
+            root,
+        } => cmd_add(&Library::open(root)?, &source, &mpn, manufacturer.as_deref(), revision),
+        LibrarySubcommand::List {
+            manufacturer,
+            root,
+            json,
+        } => cmd_list(&Library::open(root)?, manufacturer.as_deref(), json),
+        LibrarySubcommand::Find { query, root, json } => cmd_find(&Library::open(root)?, &query, json),
+        LibrarySubcommand::Dedupe { dry_run, root } => cmd_dedupe(&Library::open(root)?, dry_run),
+        LibrarySubcommand::Verify {
+            query,
+            redownload,
+            root,
+            json,
+        } => cmd_verify(&Library::open(root)?, query.as_deref(), redownload, json),
+    }
+}
+
+fn cmd_add(
+    library: &Library,
+    source: &str,
+    mpn: &str,
+    manufacturer: Option<&str>,
+    revision: Option<String>,
+) -> Result<(), String> {
+    let (data, source_url) = if source.starts_with("http://") || source.starts_with("https://") {
+        let scratch = std::env::temp_dir().join(format!("{}.pdf", sanitize_filename(mpn)));
+        let data = download::fetch_pdf(source, &scratch, &DownloadOptions::default(), &|_, _| {})?;
+        (data, Some(source.to_string()))
+    } else {
+        let data = fs::read(source).map_err(|e| format!("Failed to read {}: {}", source, e))?;
+        (data, None)
+    };
+
+    let entry = library.add_bytes(mpn, manufacturer, &data, source_url, revision)?;
+    println!("Added {} ({:.1} KB)", entry.mpn, entry.size as f64 / 1024.0);
+    println!("  {}", library.pdf_path(&entry).display());
+    Ok(())
+}
+
+fn cmd_list(library: &Library, manufacturer: Option<&str>, json_output: bool) -> Result<(), String> {
+    let mut entries = library.entries()?;
+    if let Some(mfr) = manufacturer {
+        let slug = manufacturer_slug(mfr);
+        entries.retain(|e| manufacturer_slug(&e.manufacturer) == slug);
+    }
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&entries)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("Library is empty: {}", library.root.display());
+        return Ok(());
+    }
+
+    println!("{} datasheet(s) in {}:", entries.len(), library.root.display());
+    println!();
+    for entry in &entries {
+        print!("  {:<24} {}", entry.mpn, entry.manufacturer);
+        if let Some(ref rev) = entry.revision {
+            print!(" ({})", rev);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn cmd_find(library: &Library, query: &str, json_output: bool) -> Result<(), String> {
+    let matches = library.find(query)?;
+    if matches.is_empty() {
+        return Err(format!("No datasheets matching '{}' in {}", query, library.root.display()));
+    }
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&matches)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    // One path per line so the output can be fed straight into `datasheet extract`
+    for entry in &matches {
+        println!("{}", library.pdf_path(entry).display());
+    }
+    Ok(())
+}
+
+fn cmd_dedupe(library: &Library, dry_run: bool) -> Result<(), String> {
+    let entries = library.entries()?;
+
+    // sha256 -> canonical file (the oldest entry with that content)
+    let mut by_hash: HashMap<&str, &LibraryEntry> = HashMap::new();
+    for entry in &entries {
+        let current = by_hash.entry(&entry.sha256).or_insert(entry);
+        if entry.added_at < current.added_at {
+            *current = entry;
+        }
+    }
+
+    let mut removed = 0;
+    let mut freed = 0;
+    for entry in &entries {
+        let canonical = by_hash[entry.sha256.as_str()];
+        if canonical.file == entry.file {
+            continue;
+        }
+        println!("  {} → {}", entry.mpn, canonical.file.display());
+        if dry_run {
+            removed += 1;
+            freed += entry.size;
+            continue;
+        }
+
+        let pdf_path = library.pdf_path(entry);
+        if pdf_path.exists() {
+            fs::remove_file(&pdf_path)
+                .map_err(|e| format!("Failed to remove {}: {}", pdf_path.display(), e))?;
+            freed += entry.size;
+        }
+        let updated = LibraryEntry {
+            file: canonical.file.clone(),
+            ..entry.clone()
+        };
+        let dir = library.part_dir(&entry.manufacturer, &entry.mpn);
+        library.write_sidecar(&dir, &updated)?;
+        removed += 1;
+    }
+
+    println!(
+        "{} {} duplicate(s), {:.1} KB",
+        if dry_run { "Would remove" } else { "Removed" },
+        removed,
+        freed as f64 / 1024.0
+    );
+    Ok(())
+}
+
+/// Check an entry's archived file, and optionally its source, against the recorded hash.
+fn verify_entry(library: &Library, entry: &LibraryEntry, redownload: bool) -> (VerifyStatus, Option<String>) {
+    let pdf_path = library.pdf_path(entry);
+    let Ok(data) = fs::read(&pdf_path) else {
+        return (VerifyStatus::Missing, Some(pdf_path.display().to_string()));
+    };
+    let actual = compute_hash(&data);
+    if actual != entry.sha256 || !data.starts_with(b"%PDF") {
+        return (VerifyStatus::Corrupt, Some(format!("sha256 is {}", actual)));
+    }
+
+    let Some(url) = entry.source_url.as_deref().filter(|_| redownload) else {
+        return (VerifyStatus::Ok, None);
+    };
+    let scratch = std::env::temp_dir().join(format!("{}.verify.pdf", sanitize_filename(&entry.mpn)));
+    let remote = match download::fetch_pdf(url, &scratch, &DownloadOptions::default(), &|_, _| {}) {
+        Ok(remote) => compute_hash(&remote),
+        Err(e) => return (VerifyStatus::Unreachable, Some(e)),
+    };
+    if remote == entry.sha256 {
+        (VerifyStatus::Ok, None)
+    } else if entry.history.iter().any(|h| h.sha256 == remote) {
+        (VerifyStatus::Reverted, Some(format!("source serves earlier revision {}", remote)))
+    } else {
+        (VerifyStatus::Changed, Some(format!("source sha256 is {}", remote)))
+    }
+}
+
+fn cmd_verify(library: &Library, query: Option<&str>, redownload: bool, json_output: bool) -> Result<(), String> {
+    let entries = match query {
+        Some(query) => library.find(query)?,
+        None => library.entries()?,
+    };
+
+    let reports: Vec<VerifyReport> = entries
+        .iter()
+        .map(|entry| {
+            let (status, detail) = verify_entry(library, entry, redownload);
+            VerifyReport {
+                mpn: entry.mpn.clone(),
+                manufacturer: entry.manufacturer.clone(),
+                status,
+                detail,
+            }
+        })
+        .collect();
+    let problems = reports.iter().filter(|r| r.status != VerifyStatus::Ok).count();
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&reports)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+    } else {
+        for report in &reports {
+            let status = serde_json::to_value(report.status).unwrap_or_default();
+            print!("  {:<24} {:<12}", report.mpn, status.as_str().unwrap_or_default());
+            if let Some(ref detail) = report.detail {
+                print!(" {}", detail);
+            }
+            println!();
+        }
+        println!("{} datasheet(s) checked, {} problem(s)", reports.len(), problems);
+    }
+
+    if problems > 0 {
+        return Err(crate::error::Error::validation(format!(
+            "{} datasheet(s) failed verification",
+            problems
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manufacturer_slug() {
+        assert_eq!(manufacturer_slug("Texas Instruments"), "texas-instruments");
+        assert_eq!(manufacturer_slug("STMicroelectronics, Inc."), "stmicroelectronics-inc");
+        assert_eq!(manufacturer_slug("  "), "unknown");
+    }
+
+    #[test]
+    fn test_readd_records_revision_history() {
+        let root = std::env::temp_dir().join(format!("datasheet-library-{}", std::process::id()));
+        let library = Library::open(Some(root.clone())).unwrap();
+
+        let first = library.add_bytes("LM358", Some("TI"), b"%PDF-1.4 rev A", None, Some("A".into())).unwrap();
+        let again = library.add_bytes("LM358", Some("TI"), b"%PDF-1.4 rev A", None, Some("A".into())).unwrap();
+        assert!(again.history.is_empty());
+
+        let second = library.add_bytes("LM358", Some("TI"), b"%PDF-1.4 rev B", None, Some("B".into())).unwrap();
+        assert_eq!(second.history.len(), 1);
+        assert_eq!(second.history[0].sha256, first.sha256);
+        assert_eq!(verify_entry(&library, &second, false).0, VerifyStatus::Ok);
+
+        fs::write(library.pdf_path(&second), b"%PDF-1.4 tampered").unwrap();
+        assert_eq!(verify_entry(&library, &second, false).0, VerifyStatus::Corrupt);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_readd_with_backup_keeps_previous_copy() {
+        let root = std::env::temp_dir().join(format!("datasheet-library-backup-{}", std::process::id()));
+        let library = Library::open(Some(root.clone())).unwrap();
+
+        crate::paths::set_backup(true);
+        library.add_bytes("TL431", Some("TI"), b"%PDF-1.4 rev A", None, None).unwrap();
+        let entry = library.add_bytes("TL431", Some("TI"), b"%PDF-1.4 rev B", None, None).unwrap();
+        crate::paths::set_backup(false);
+
+        let dir = library.part_dir("TI", "TL431");
+        assert_eq!(fs::read(dir.join("TL431.pdf.bak")).unwrap(), b"%PDF-1.4 rev A");
+        assert_eq!(fs::read(library.pdf_path(&entry)).unwrap(), b"%PDF-1.4 rev B");
+        let previous: LibraryEntry =
+            serde_json::from_slice(&fs::read(dir.join("datasheet.json.bak")).unwrap()).unwrap();
+        assert_eq!(previous.sha256, compute_hash(b"%PDF-1.4 rev A"));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_readd_after_dedupe_keeps_duplicates() {
+        let root = std::env::temp_dir().join(format!("datasheet-library-dedupe-{}", std::process::id()));
+        let library = Library::open(Some(root.clone())).unwrap();
+
+        library.add_bytes("LM358", Some("TI"), b"%PDF-1.4 rev A", None, None).unwrap();
+        library.add_bytes("LM358B", Some("TI"), b"%PDF-1.4 rev A", None, None).unwrap();
+        library.add_bytes("LM358C", Some("TI"), b"%PDF-1.4 rev A", None, None).unwrap();
+        cmd_dedupe(&library, false).unwrap();
+        let shared = library.find("LM358").unwrap()[0].file.clone();
+        assert!(library.entries().unwrap().iter().all(|e| e.file == shared));
+
+        library.add_bytes("LM358", Some("TI"), b"%PDF-1.4 rev B", None, None).unwrap();
+        let entries = library.find("LM358").unwrap();
+        assert_eq!(entries[0].sha256, compute_hash(b"%PDF-1.4 rev B"));
+        assert_eq!(entries[1].file, entries[2].file);
+        assert_ne!(entries[1].file, shared);
+        for entry in &entries {
+            assert_eq!(verify_entry(&library, entry, false).0, VerifyStatus::Ok, "{}", entry.mpn);
+        }
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -5,11 +5,52 @@ use anyhow::{anyhow, Context, Result};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use clap::ValueEnum;
+use crate::error::{Error, ErrorKind};
 use serde_json::Value;
 use std::env;
-use std::path::Path;
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
 use std::time::Duration;
 
+/// Total tokens (prompt + output) reported by Gemini in this process
+static TOKENS_USED: AtomicU64 = AtomicU64::new(0);
+
+/// Tokens used by all LLM requests so far in this process.
+pub fn tokens_used() -> u64 {
+    TOKENS_USED.load(Ordering::Relaxed)
+}
+
+/// How the output schema is given to the model (`--schema-mode`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum SchemaMode {
+    /// Native, switching to prompt for models that reject responseJsonSchema
+    #[default]
+    Auto,
+    /// Through the API's responseJsonSchema
+    Native,
+    /// In the prompt, with the output checked against the schema locally
+    Prompt,
+}
+
+static SCHEMA_MODE: OnceLock<SchemaMode> = OnceLock::new();
+
+/// Largest response body read from Gemini unless `--max-response-size` says otherwise
+const DEFAULT_MAX_RESPONSE_BYTES: u64 = 32 * 1024 * 1024;
+
+static MAX_RESPONSE_BYTES: OnceLock<u64> = OnceLock::new();
+
+/// Models that rejected responseJsonSchema in this process (`--schema-mode auto`)
+static PROMPT_SCHEMA_MODELS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn set_schema_mode(mode: SchemaMode) {
+    let _ = SCHEMA_MODE.set(mode);
+}
+
+pub fn set_max_response_size(bytes: u64) {
+    let _ = MAX_RESPONSE_BYTES.set(bytes);
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 pub enum LlmProvider {
     #[clap(name="gemini")]
@@ -26,22 +67,15 @@ impl LlmProvider {
     }
 }
 
+/// Largest attachment sent inline: Gemini caps a request at 20 MB and base64 adds a third
+pub const INLINE_MAX_BYTES: usize = 14 * 1024 * 1024;
+
 #[derive(Clone, Debug)]
 pub struct Attachment {
     pub mime_type: String,
     pub data: Vec<u8>,
 }
 
-impl Attachment {
-    pub fn from_path(path: &Path) -> Result<Self> {
-        let data = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
-        Ok(Self {
-            mime_type: "application/pdf".to_string(),
-            data,
-        })
-    }
-}
-
 /// A reference to a file uploaded to Gemini via the File API
 #[derive(Clone, Debug)]
 pub struct FileReference {
@@ -56,8 +90,40 @@ pub enum AttachmentSource {
     Inline(Attachment),
     /// Reference a pre-uploaded file via URI
     FileUri(FileReference),
+    /// Several attachments, sent in order before the prompt
+    Multiple(Vec<AttachmentSource>),
 }
 
+impl AttachmentSource {
+    /// Gemini request parts for this attachment.
+    fn parts(&self) -> Vec<Value> {
+        match self {
+            AttachmentSource::Inline(attachment) => {
+                let encoded_pdf = STANDARD.encode(&attachment.data);
+                eprintln!("[DEBUG] PDF size: {} bytes (inline)", attachment.data.len());
+                eprintln!("[DEBUG] Base64 length: {} chars", encoded_pdf.len());
+                vec![serde_json::json!({
+                    "inline_data": {
+                        "mime_type": attachment.mime_type,
+                        "data": encoded_pdf
+                    }
+                })]
+            }
+            AttachmentSource::FileUri(file_ref) => {
+                eprintln!("[DEBUG] Using cached file URI: {}", file_ref.file_uri);
+                vec![serde_json::json!({
+                    "file_data": {
+                        "mime_type": file_ref.mime_type,
+                        "file_uri": file_ref.file_uri
+                    }
+                })]
+            }
+            AttachmentSource::Multiple(sources) => sources.iter().flat_map(Self::parts).collect(),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct LlmRequest {
     pub model: String,
     pub prompt: String,
@@ -70,6 +136,165 @@ pub struct LlmResponse {
     pub json: Value,
 }
 
+/// Model output that did not parse as JSON, kept so callers can salvage what
+/// it contains. Sits in the error chain above the validation error.
+#[derive(Debug)]
+pub struct MalformedResponse {
+    pub text: String,
+    /// Gemini's finishReason, e.g. MAX_TOKENS for truncated output
+    pub finish_reason: Option<String>,
+    /// candidatesTokenCount of the response
+    pub output_tokens: Option<u64>,
+    /// The text ends in the middle of a JSON value
+    pub truncated: bool,
+}
+
+impl std::fmt::Display for MalformedResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = self.finish_reason.as_deref();
+        if reason == Some("MAX_TOKENS") {
+            write!(f, "model response was cut off at the output token limit")?;
+        } else if self.truncated {
+            write!(f, "model response ends in the middle of the JSON")?;
+        } else {
+            write!(f, "model response is not valid JSON")?;
+        }
+        match (self.output_tokens, reason) {
+            (Some(tokens), Some(reason)) => write!(f, " ({} output tokens, finish reason: {})", tokens, reason)?,
+            (Some(tokens), None) => write!(f, " ({} output tokens)", tokens)?,
+            (None, Some(reason)) => write!(f, " (finish reason: {})", reason)?,
+            (None, None) => {}
+        }
+        if self.truncated || reason == Some("MAX_TOKENS") {
+            write!(f, "; extract fewer pages at a time with --upload chunked or --pages")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MalformedResponse {}
+
+/// A failed Gemini request, classified so callers can react to it (retry,
+/// stop, or switch modes) and so it maps to the right exit code.
+#[derive(Debug)]
+pub enum LlmError {
+    /// HTTP 429 / RESOURCE_EXHAUSTED, with the wait the API asked for if any
+    RateLimited { retry_after: Option<Duration>, message: String },
+    /// The API key is missing, malformed, or not allowed to use the API
+    InvalidApiKey(String),
+    /// The prompt was refused or the response stopped for policy reasons
+    ContentBlocked(String),
+    /// The model does not accept `responseJsonSchema`
+    SchemaRejected(String),
+    /// The model (or endpoint) does not exist
+    ModelNotFound(String),
+    /// The request never got a response: connection, TLS, or timeout
+    Transport(String),
+    /// The response body was larger than `--max-response-size`
+    ResponseTooLarge { limit: u64 },
+    /// Any other error response
+    Api { status: u16, message: String },
+}
+
+impl LlmError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            LlmError::RateLimited { .. } => ErrorKind::RateLimited,
+            LlmError::InvalidApiKey(_) => ErrorKind::Auth,
+            LlmError::ContentBlocked(_) | LlmError::SchemaRejected(_) => ErrorKind::Validation,
+            LlmError::ModelNotFound(_) => ErrorKind::NotFound,
+            LlmError::Transport(_) => ErrorKind::Network,
+            LlmError::ResponseTooLarge { .. } => ErrorKind::Validation,
+            LlmError::Api { status, .. } => ErrorKind::from_status(*status),
+        }
+    }
+}
+
+impl std::fmt::Display for LlmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LlmError::RateLimited { retry_after: Some(wait), message } => {
+                write!(f, "Gemini rate limit reached (retry after {}s): {}", wait.as_secs(), message)
+            }
+            LlmError::RateLimited { retry_after: None, message } => {
+                write!(f, "Gemini rate limit reached: {}", message)
+            }
+            LlmError::InvalidApiKey(message) => write!(f, "Gemini rejected the API key: {}", message),
+            LlmError::ContentBlocked(message) => f.write_str(message),
+            LlmError::SchemaRejected(message) => {
+                write!(f, "model does not accept responseJsonSchema (try --schema-mode prompt): {}", message)
+            }
+            LlmError::ModelNotFound(message) => write!(f, "Gemini model not found: {}", message),
+            LlmError::Transport(message) => write!(f, "sending request to Gemini: {}", message),
+            LlmError::ResponseTooLarge { limit } => write!(
+                f,
+                "Gemini response is larger than {:.1} MB (--max-response-size); \
+                 extract fewer pages at a time with --upload chunked or --pages",
+                *limit as f64 / (1024.0 * 1024.0)
+            ),
+            LlmError::Api { status, message } => write!(f, "Gemini API error (status {}): {}", status, message),
+        }
+    }
+}
+
+impl std::error::Error for LlmError {}
+
+/// Classify an error response from status, `Retry-After`, and the JSON body's
+/// `error.status`, `error.message`, and `details` (ErrorInfo reason, RetryInfo).
+fn api_error(status: u16, retry_after: Option<&str>, body: &str) -> LlmError {
+    let json: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+    let error = json.get("error");
+    let message = error
+        .and_then(|e| e.get("message"))
+        .and_then(Value::as_str)
+        .unwrap_or(body)
+        .trim()
+        .to_string();
+    let rpc_status = error.and_then(|e| e.get("status")).and_then(Value::as_str).unwrap_or_default();
+    let details: Vec<&Value> = error
+        .and_then(|e| e.get("details"))
+        .and_then(Value::as_array)
+        .map(|d| d.iter().collect())
+        .unwrap_or_default();
+    let reason = details.iter().find_map(|d| d.get("reason").and_then(Value::as_str)).unwrap_or_default();
+
+    if status == 429 || rpc_status == "RESOURCE_EXHAUSTED" {
+        let retry_after = retry_after
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .or_else(|| {
+                // RetryInfo.retryDelay is a protobuf Duration string, e.g. "31s" or "1.5s"
+                details
+                    .iter()
+                    .find_map(|d| d.get("retryDelay").and_then(Value::as_str))
+                    .and_then(|delay| delay.strip_suffix('s')?.parse::<f64>().ok())
+                    .map(Duration::from_secs_f64)
+            });
+        return LlmError::RateLimited { retry_after, message };
+    }
+    if matches!(status, 401 | 403) || reason == "API_KEY_INVALID" {
+        return LlmError::InvalidApiKey(message);
+    }
+    if status == 404 {
+        return LlmError::ModelNotFound(message);
+    }
+    let lower = message.to_ascii_lowercase();
+    if status == 400
+        && ["responsejsonschema", "response_json_schema", "json schema"]
+            .iter()
+            .any(|needle| lower.contains(needle))
+    {
+        return LlmError::SchemaRejected(message);
+    }
+    LlmError::Api { status, message }
+}
+
+/// Rate-limited requests are retried this many times when the API says how long to wait
+const RATE_LIMIT_RETRIES: usize = 2;
+
+/// Longest `retry_after` waited out automatically; longer limits fail right away
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
 pub trait LlmClient {
     fn generate_json(&self, request: LlmRequest) -> Result<LlmResponse>;
 }
@@ -97,10 +322,15 @@ pub fn resolve_api_key(provider: LlmProvider, cli_key: Option<String>) -> Result
         }
     }
 
-    Err(anyhow!(
-        "missing API key (use --api-key or set one of: {})",
+    if let Some(key) = crate::auth::credential("GOOGLE_API_KEY") {
+        return Ok(key);
+    }
+
+    Err(Error::auth(format!(
+        "missing API key (use --api-key, set one of: {}, or run `datasheet auth set gemini`)",
         provider.api_key_env_vars().join(", ")
     ))
+    .into())
 }
 
 pub fn build_client(
@@ -138,47 +368,94 @@ impl GeminiLlm {
 
 impl LlmClient for GeminiLlm {
     fn generate_json(&self, request: LlmRequest) -> Result<LlmResponse> {
+        crate::offline::ensure_online("Gemini requests").map_err(|e| anyhow!(e))?;
+        crate::budget::check()?;
         eprintln!("[DEBUG] Model: {}", request.model);
 
-        // Build the file part based on attachment source
-        let file_part = match &request.attachment {
-            AttachmentSource::Inline(attachment) => {
-                let encoded_pdf = STANDARD.encode(&attachment.data);
-                eprintln!("[DEBUG] PDF size: {} bytes (inline)", attachment.data.len());
-                eprintln!("[DEBUG] Base64 length: {} chars", encoded_pdf.len());
-                serde_json::json!({
-                    "inline_data": {
-                        "mime_type": attachment.mime_type,
-                        "data": encoded_pdf
-                    }
-                })
+        let mode = SCHEMA_MODE.get().copied().unwrap_or_default();
+        let native = match mode {
+            SchemaMode::Native => true,
+            SchemaMode::Prompt => false,
+            SchemaMode::Auto => !PROMPT_SCHEMA_MODELS
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .contains(&request.model),
+        };
+        match self.generate_with_backoff(&request, native) {
+            Err(err) if native && mode == SchemaMode::Auto && rejects_schema(&err) => {
+                eprintln!(
+                    "[LLM] {} does not accept responseJsonSchema; sending the schema in the prompt",
+                    request.model
+                );
+                PROMPT_SCHEMA_MODELS
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(request.model.clone());
+                self.generate_with_backoff(&request, false)
             }
-            AttachmentSource::FileUri(file_ref) => {
-                eprintln!("[DEBUG] Using cached file URI: {}", file_ref.file_uri);
-                serde_json::json!({
-                    "file_data": {
-                        "mime_type": file_ref.mime_type,
-                        "file_uri": file_ref.file_uri
-                    }
-                })
+            result => result,
+        }
+    }
+}
+
+impl GeminiLlm {
+    /// [`Self::generate`], waiting out short rate limits the API reports.
+    fn generate_with_backoff(&self, request: &LlmRequest, native: bool) -> Result<LlmResponse> {
+        let mut attempt = 0;
+        loop {
+            let err = match self.generate(request, native) {
+                Err(err) => err,
+                result => return result,
+            };
+            let wait = match err.downcast_ref::<LlmError>() {
+                Some(LlmError::RateLimited { retry_after: Some(wait), .. }) => *wait,
+                _ => return Err(err),
+            };
+            if attempt == RATE_LIMIT_RETRIES || wait > MAX_RATE_LIMIT_WAIT {
+                return Err(err);
             }
+            attempt += 1;
+            eprintln!(
+                "[LLM] Rate limited; retrying in {:.0}s ({}/{})",
+                wait.as_secs_f64().ceil(),
+                attempt,
+                RATE_LIMIT_RETRIES
+            );
+            std::thread::sleep(wait);
+            crate::budget::check()?;
+        }
+    }
+
+    /// One generateContent call, with the schema in the generation config
+    /// (`native`) or appended to the prompt and checked locally.
+    fn generate(&self, request: &LlmRequest, native: bool) -> Result<LlmResponse> {
+        // File parts first, then the prompt
+        let mut parts = request.attachment.parts();
+        let prompt = if native {
+            request.prompt.clone()
+        } else {
+            format!(
+                "{}\n\n---\n\nRespond with a single JSON value that conforms to this JSON Schema \
+                 (no prose, no Markdown fences):\n\n{}\n",
+                request.prompt,
+                serde_json::to_string_pretty(&request.schema)?
+            )
         };
+        parts.push(serde_json::json!({ "text": prompt }));
 
         // Build the request body following Gemini API format
+        let mut generation_config = serde_json::json!({
+            "temperature": request.temperature.unwrap_or(1.0),
+            "responseMimeType": "application/json",
+        });
+        if native {
+            generation_config["responseJsonSchema"] = request.schema.clone();
+        }
         let body = serde_json::json!({
             "contents": [{
-                "parts": [
-                    file_part,
-                    {
-                        "text": request.prompt
-                    }
-                ]
+                "parts": parts
             }],
-            "generationConfig": {
-                "temperature": request.temperature.unwrap_or(1.0),
-                "responseMimeType": "application/json",
-                "responseJsonSchema": request.schema
-            }
+            "generationConfig": generation_config
         });
         
         // Construct the URL: {base_url}/models/{model}:generateContent?key={api_key}
@@ -191,29 +468,52 @@ impl LlmClient for GeminiLlm {
         
         eprintln!("[DEBUG] Calling: {}", url.replace(&self.api_key, "***"));
         
+        crate::progress::emit("request_sent", serde_json::json!({ "model": request.model }));
         let resp = self.client
             .post(&url)
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
-            .context("sending request to Gemini")?;
+            .map_err(|e| LlmError::Transport(e.to_string()))?;
         
         let status = resp.status();
-        let response_text = resp.text().context("reading response text")?;
+        let retry_after = header(&resp, "retry-after");
+        let response_text = read_body(resp)?;
         
         if !status.is_success() {
-            return Err(anyhow!(
-                "Gemini API error (status {}): {}",
-                status,
-                response_text
-            ));
+            return Err(api_error(status.as_u16(), retry_after.as_deref(), &response_text).into());
         }
         
         eprintln!("[DEBUG] Response: {}", &response_text[..response_text.len().min(500)]);
         
         let response_json: Value = serde_json::from_str(&response_text)
             .context("parsing Gemini response")?;
+
+        if let Some(total) = response_json
+            .pointer("/usageMetadata/totalTokenCount")
+            .and_then(Value::as_u64)
+        {
+            TOKENS_USED.fetch_add(total, Ordering::Relaxed);
+        }
+        let usage = |field: &str| response_json.pointer(&format!("/usageMetadata/{field}")).cloned();
+        let count = |field: &str| usage(field).and_then(|v| v.as_u64()).unwrap_or(0);
+        // Thinking tokens are billed as output
+        crate::usage::record(
+            &request.model,
+            count("promptTokenCount"),
+            count("candidatesTokenCount") + count("thoughtsTokenCount"),
+        );
+        crate::progress::emit("response_received", serde_json::json!({
+            "model": request.model,
+            "prompt_tokens": usage("promptTokenCount"),
+            "output_tokens": usage("candidatesTokenCount"),
+            "total_tokens": usage("totalTokenCount"),
+        }));
         
+        if let Some(reason) = blocked(&response_json) {
+            return Err(LlmError::ContentBlocked(reason).into());
+        }
+
         // Extract the text from candidates[0].content.parts[0].text
         let text = response_json
             .get("candidates")
@@ -225,9 +525,252 @@ impl LlmClient for GeminiLlm {
             .and_then(|t| t.as_str())
             .ok_or_else(|| anyhow!("unexpected Gemini response format: {}", response_json))?;
         
-        let json: Value = serde_json::from_str(text)
-            .context("parsing model JSON from Gemini text response")?;
-        
+        let malformed = |message: String, truncated: bool| {
+            anyhow::Error::new(Error::validation(message)).context(MalformedResponse {
+                text: text.to_string(),
+                finish_reason: response_json
+                    .pointer("/candidates/0/finishReason")
+                    .and_then(|r| r.as_str())
+                    .map(str::to_string),
+                output_tokens: response_json
+                    .pointer("/usageMetadata/candidatesTokenCount")
+                    .and_then(Value::as_u64),
+                truncated,
+            })
+        };
+        let json: Value = serde_json::from_str(text).map_err(|e| {
+            malformed(format!("parsing model JSON from Gemini text response: {}", e), e.is_eof())
+        })?;
+
+        // Native mode is enforced by the API; without it, check the output here
+        if !native {
+            let errors = crate::json_schema::validate(&json, &request.schema);
+            if !errors.is_empty() {
+                let message = format!("model JSON does not match the schema: {}", errors.join("; "));
+                return Err(malformed(message, false));
+            }
+        }
+
         Ok(LlmResponse { json })
     }
 }
+
+/// Whether a Gemini error says the model does not support responseJsonSchema.
+fn rejects_schema(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<LlmError>(), Some(LlmError::SchemaRejected(_)))
+}
+
+/// Read a response body, stopping at `--max-response-size` instead of
+/// buffering whatever the server sends.
+fn read_body(resp: reqwest::blocking::Response) -> Result<String, LlmError> {
+    let limit = MAX_RESPONSE_BYTES.get().copied().unwrap_or(DEFAULT_MAX_RESPONSE_BYTES);
+    let mut body = Vec::new();
+    resp.take(limit + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| LlmError::Transport(format!("reading response: {}", e)))?;
+    if body.len() as u64 > limit {
+        return Err(LlmError::ResponseTooLarge { limit });
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+fn header(resp: &reqwest::blocking::Response, name: &str) -> Option<String> {
+    resp.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+}
+
+/// Why Gemini refused the request or stopped the response, from the prompt's
+/// `promptFeedback.blockReason` or the candidate's `finishReason`. `None` when
+/// the response was not blocked.
+fn blocked(response: &Value) -> Option<String> {
+    if let Some(reason) = response.pointer("/promptFeedback/blockReason").and_then(Value::as_str) {
+        let message = response
+            .pointer("/promptFeedback/blockReasonMessage")
+            .and_then(Value::as_str)
+            .map(|m| format!(": {}", m))
+            .unwrap_or_default();
+        return Some(format!("Gemini blocked the request (block reason: {}){}", reason, message));
+    }
+    let candidate = response.pointer("/candidates/0")?;
+    let reason = candidate.get("finishReason").and_then(Value::as_str)?;
+    let explanation = match reason {
+        "SAFETY" | "IMAGE_SAFETY" => {
+            let categories: Vec<&str> = candidate
+                .get("safetyRatings")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter(|r| r.get("blocked").and_then(Value::as_bool) == Some(true))
+                .filter_map(|r| r.get("category").and_then(Value::as_str))
+                .collect();
+            if categories.is_empty() {
+                "the response was flagged by the safety filters".to_string()
+            } else {
+                format!("the response was flagged by the safety filters ({})", categories.join(", "))
+            }
+        }
+        "RECITATION" => "the response repeated copyrighted text too closely; \
+            narrow the input with --pages or try another model"
+            .to_string(),
+        "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => {
+            "the response contained blocked content".to_string()
+        }
+        _ => return None,
+    };
+    Some(format!("Gemini stopped the response: {} (finish reason: {})", explanation, reason))
+}
+
+/// Default Gemini embedding model.
+pub const DEFAULT_EMBEDDING_MODEL: &str = "gemini-embedding-001";
+
+/// Dimensions requested from the embedding model.
+pub const EMBEDDING_DIMENSIONS: usize = 768;
+
+/// Embed `texts` with the Gemini embedding API, returning one unit-length
+/// vector per text. `query` selects the retrieval-query task type instead of
+/// retrieval-document, which is what search strings should use.
+pub fn embed(
+    api_key: &str,
+    base_url: Option<String>,
+    model: &str,
+    texts: &[String],
+    query: bool,
+) -> Result<Vec<Vec<f32>>> {
+    crate::offline::ensure_online("Gemini embedding requests").map_err(|e| anyhow!(e))?;
+    crate::budget::check()?;
+    let base_url = base_url.unwrap_or_else(|| LlmProvider::Gemini.default_base_url().to_string());
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(300))
+        .build()
+        .context("building reqwest client")?;
+    let url = format!("{}/models/{}:batchEmbedContents?key={}", base_url, model, api_key);
+    let task_type = if query { "RETRIEVAL_QUERY" } else { "RETRIEVAL_DOCUMENT" };
+
+    // The batch endpoint accepts at most 100 requests per call
+    let mut vectors = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(100) {
+        let requests: Vec<Value> = batch
+            .iter()
+            .map(|text| {
+                serde_json::json!({
+                    "model": format!("models/{}", model),
+                    "content": { "parts": [{ "text": text }] },
+                    "taskType": task_type,
+                    "outputDimensionality": EMBEDDING_DIMENSIONS,
+                })
+            })
+            .collect();
+        let resp = client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({ "requests": requests }))
+            .send()
+            .map_err(|e| LlmError::Transport(e.to_string()))?;
+        let status = resp.status();
+        let retry_after = header(&resp, "retry-after");
+        let response_text = read_body(resp)?;
+        if !status.is_success() {
+            return Err(api_error(status.as_u16(), retry_after.as_deref(), &response_text).into());
+        }
+        let response_json: Value = serde_json::from_str(&response_text)
+            .context("parsing Gemini response")?;
+        let embeddings = response_json
+            .get("embeddings")
+            .and_then(Value::as_array)
+            .filter(|e| e.len() == batch.len())
+            .ok_or_else(|| anyhow!("unexpected Gemini embedding response: {}", response_text))?;
+        for embedding in embeddings {
+            let mut values: Vec<f32> = embedding
+                .get("values")
+                .and_then(Value::as_array)
+                .map(|v| v.iter().filter_map(Value::as_f64).map(|x| x as f32).collect())
+                .unwrap_or_default();
+            // Truncated outputs are not normalized by the API
+            let norm = values.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                values.iter_mut().for_each(|x| *x /= norm);
+            }
+            vectors.push(values);
+        }
+    }
+    Ok(vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_blocked() {
+        let response = json!({ "promptFeedback": { "blockReason": "PROHIBITED_CONTENT" } });
+        assert_eq!(
+            blocked(&response).as_deref(),
+            Some("Gemini blocked the request (block reason: PROHIBITED_CONTENT)")
+        );
+
+        let response = json!({ "candidates": [{
+            "finishReason": "SAFETY",
+            "safetyRatings": [
+                { "category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "HIGH", "blocked": true },
+                { "category": "HARM_CATEGORY_HARASSMENT", "probability": "NEGLIGIBLE" }
+            ]
+        }]});
+        assert!(blocked(&response).unwrap().contains("(HARM_CATEGORY_DANGEROUS_CONTENT)"));
+        let response = json!({ "candidates": [{ "finishReason": "RECITATION" }] });
+        assert!(blocked(&response).unwrap().contains("--pages"));
+
+        assert_eq!(blocked(&json!({ "candidates": [{ "finishReason": "STOP" }] })), None);
+        assert_eq!(blocked(&json!({ "candidates": [{ "finishReason": "MAX_TOKENS" }] })), None);
+    }
+
+    #[test]
+    fn test_malformed_display() {
+        let malformed = MalformedResponse {
+            text: r#"{"pins": [{"number": "1"#.to_string(),
+            finish_reason: Some("STOP".to_string()),
+            output_tokens: Some(8192),
+            truncated: true,
+        };
+        assert_eq!(
+            malformed.to_string(),
+            "model response ends in the middle of the JSON (8192 output tokens, finish reason: STOP); \
+             extract fewer pages at a time with --upload chunked or --pages"
+        );
+        let malformed = MalformedResponse { truncated: false, output_tokens: None, ..malformed };
+        assert_eq!(malformed.to_string(), "model response is not valid JSON (finish reason: STOP)");
+    }
+
+    #[test]
+    fn test_api_error() {
+        let body = json!({ "error": {
+            "code": 429,
+            "message": "Quota exceeded",
+            "status": "RESOURCE_EXHAUSTED",
+            "details": [{ "@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "31.5s" }]
+        }})
+        .to_string();
+        match api_error(429, None, &body) {
+            LlmError::RateLimited { retry_after, message } => {
+                assert_eq!(retry_after, Some(Duration::from_millis(31_500)));
+                assert_eq!(message, "Quota exceeded");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let limited = api_error(429, Some("7"), "busy");
+        assert!(matches!(limited, LlmError::RateLimited { retry_after: Some(d), .. } if d.as_secs() == 7));
+        assert_eq!(limited.kind().exit_code(), 5);
+
+        let body = json!({ "error": {
+            "code": 400,
+            "message": "API key not valid. Please pass a valid API key.",
+            "status": "INVALID_ARGUMENT",
+            "details": [{ "@type": "type.googleapis.com/google.rpc.ErrorInfo", "reason": "API_KEY_INVALID" }]
+        }})
+        .to_string();
+        assert!(matches!(api_error(400, None, &body), LlmError::InvalidApiKey(_)));
+        let body = r#"{"error":{"message":"Unknown name \"responseJsonSchema\" at 'generation_config'"}}"#;
+        assert!(matches!(api_error(400, None, body), LlmError::SchemaRejected(_)));
+        assert_eq!(api_error(404, None, "models/x is not found").kind(), ErrorKind::NotFound);
+        assert_eq!(api_error(503, None, "overloaded").kind(), ErrorKind::Network);
+    }
+}
@@ -7,32 +7,193 @@
 
 use anyhow::{Result, anyhow};
 use clap::{Parser, Subcommand};
+use std::process::ExitCode;
 
+mod alternates;
+mod annotate;
+mod archive;
+mod ask;
+mod audit;
+mod auth;
+mod bench;
+mod bom;
+mod budget;
+mod compare_revisions;
+mod completions;
+mod config;
+mod currency;
+mod db;
 mod digikey;
+mod dotenv;
+mod download;
+mod download_policy;
+mod embed;
+mod error;
 mod extract;
 mod extract_pages;
 mod file_cache;
+mod find;
 mod footprint_image;
+mod healthcheck;
+mod history;
+mod inventory;
+mod jobs;
 mod jlcpcb;
+mod json_schema;
+mod kicad;
+mod library;
 mod llm;
+mod merge_output;
+mod migrate;
 mod mouser;
+mod notify;
+mod offline;
 mod page_render;
+mod parameters;
+mod paths;
+mod part;
+mod part_info;
+mod pdf_clean;
+mod pdf_redact;
 mod pdf_split;
+mod picker;
+mod pipeline;
+mod progress;
+mod prompt_source;
+mod prompt_test;
 mod prompts;
+mod resolver;
+mod retrieve;
+mod review;
+mod scan;
+mod schedule;
+mod select;
 mod snapeda;
+mod stock;
+mod summarize;
 mod svd;
+mod tui;
+mod usage;
+mod verify_marking;
+mod watch;
+mod wizard;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Cli {
     #[command(subcommand)]
     command: Command,
+
+    /// Profile from ~/.config/datasheet-cli/config.toml to take defaults from
+    #[arg(long, global = true, env = "DATASHEET_PROFILE")]
+    profile: Option<String>,
+
+    /// Load variables from this .env file (a bare name is also looked for in parent
+    /// directories up to the git repository root); variables already set win
+    #[arg(long, global = true, value_name = "FILE", env = "DATASHEET_ENV_FILE")]
+    env_file: Option<std::path::PathBuf>,
+
+    /// Convert all prices to this currency (e.g. EUR, GBP, JPY)
+    #[arg(long, global = true, env = "DATASHEET_CURRENCY")]
+    currency: Option<String>,
+
+    /// Forbid network access; serve extractions, datasheets, and distributor data from local caches
+    #[arg(long, global = true, env = "DATASHEET_OFFLINE")]
+    offline: bool,
+
+    /// Never prompt; when a lookup matches several parts, take the first
+    #[arg(long, global = true, env = "DATASHEET_NO_INPUT")]
+    no_input: bool,
+
+    /// Directory for cached files (Gemini uploads, PDF splits, indexes)
+    #[arg(long, global = true, env = "DATASHEET_CACHE_DIR")]
+    cache_dir: Option<std::path::PathBuf>,
+
+    /// Directory for downloaded datasheets and generated reports (default: <Downloads>/datasheet-cli)
+    #[arg(long, global = true, env = "DATASHEET_DOWNLOAD_DIR")]
+    download_dir: Option<std::path::PathBuf>,
+
+    /// Keep the previous version of an overwritten output file as <name>.bak
+    #[arg(long, global = true, env = "DATASHEET_BACKUP")]
+    backup: bool,
+
+    /// Add a snapshot of every listing distributor search and stock commands print to the stock history
+    #[arg(long, global = true, env = "DATASHEET_RECORD_HISTORY")]
+    record_history: bool,
+
+    /// Progress output: human log lines, or NDJSON events on stderr for GUIs and CI wrappers
+    #[arg(long, global = true, value_enum, default_value = "text", env = "DATASHEET_PROGRESS")]
+    progress: progress::ProgressFormat,
+
+    /// Error output: human-readable, or one JSON object with the failure kind and exit code
+    #[arg(long, global = true, value_enum, default_value = "text", env = "DATASHEET_ERROR_FORMAT")]
+    error_format: error::ErrorFormat,
+
+    /// Print only part of the JSON output, e.g. '.packages[0].pins' or '/packages/0/pins'
+    #[arg(long, global = true, value_parser = select::parse)]
+    select: Option<select::Selector>,
+
+    /// Cache size budget, e.g. 500M or 2G; least recently used PDF splits are evicted beyond it
+    #[arg(long, global = true, env = "DATASHEET_CACHE_MAX_SIZE", value_parser = file_cache::parse_size)]
+    cache_max_size: Option<u64>,
+
+    /// How many PDFs to upload to Gemini at once ahead of extraction (default: 2)
+    #[arg(long, global = true, value_name = "N", env = "DATASHEET_UPLOAD_CONCURRENCY")]
+    upload_concurrency: Option<usize>,
+
+    /// How to give models the output schema: native (responseJsonSchema), prompt (in the
+    /// prompt, checked locally), or auto (native, falling back to prompt where unsupported)
+    #[arg(long, global = true, value_enum, default_value = "auto", env = "DATASHEET_SCHEMA_MODE")]
+    schema_mode: llm::SchemaMode,
+
+    /// Largest Gemini response to read, e.g. 64M (default: 32M)
+    #[arg(long, global = true, env = "DATASHEET_MAX_RESPONSE_SIZE", value_parser = file_cache::parse_size)]
+    max_response_size: Option<u64>,
+
+    /// Load prompts and schemas from a directory, a ZIP URL (pin with #sha256=...), or
+    /// git+<repo>[@<tag or commit>]; they replace built-in tasks of the same name
+    #[arg(long, global = true, value_name = "SOURCE", env = "DATASHEET_PROMPT_SOURCE")]
+    prompt_source: Option<String>,
+
+    /// Refuse LLM requests once this many were made today (UTC), counting every run
+    #[arg(long, global = true, value_name = "N", env = "DATASHEET_MAX_REQUESTS_PER_DAY")]
+    max_requests_per_day: Option<u64>,
+
+    /// Refuse further LLM requests once this run has used this many tokens
+    #[arg(long, global = true, value_name = "N", env = "DATASHEET_MAX_TOKENS_PER_RUN")]
+    max_tokens_per_run: Option<u64>,
+
+    /// Refuse further LLM requests once this run's estimated cost reaches this many USD
+    #[arg(long, global = true, value_name = "USD", env = "DATASHEET_MAX_SPEND_PER_RUN")]
+    max_spend_per_run: Option<f64>,
 }
 
 #[derive(Subcommand, Debug)]
 enum Command {
     /// Extract structured JSON data from datasheets using LLMs
     Extract(extract::ExtractArgs),
+    /// Ask a free-form question about a datasheet and get a cited answer
+    Ask(ask::AskArgs),
+    /// One-page datasheet summary for firmware, layout, or procurement
+    Summarize(summarize::SummarizeArgs),
+    /// Embed datasheet pages or extraction sections for `db semantic-search`
+    Embed(embed::EmbedArgs),
+    /// Report what changed between two revisions of a datasheet
+    CompareRevisions(compare_revisions::CompareRevisionsArgs),
+    /// Run a chain of extraction tasks, feeding each one's results into the next
+    Pipeline(pipeline::PipelineArgs),
+    /// Score extraction accuracy against a corpus of PDFs with golden results
+    Bench(bench::BenchArgs),
+    /// Compare prompt variants for a task on the same PDFs
+    PromptTest(prompt_test::PromptTestArgs),
+    /// Upgrade extraction output saved by older versions to the current schema
+    Migrate(migrate::MigrateArgs),
+    /// Find, download, cache, and extract a part's datasheet in one step
+    Part(part::PartArgs),
+    /// Look a part up at every distributor and merge stock, prices, lifecycle, and datasheet links
+    PartInfo(part_info::PartInfoArgs),
+    /// Search DigiKey and Mouser by function, package, and values, e.g. an LDO in SOT-23-5 at 3.3 V
+    Find(find::FindArgs),
     /// Mouser Electronics API for searching parts and downloading datasheets
     #[command(subcommand)]
     Mouser(mouser::MouserSubcommand),
@@ -45,20 +206,132 @@ enum Command {
     /// SnapEDA/SnapMagic CAD library — symbols, footprints, pin mappings (no API key required)
     #[command(subcommand)]
     Snapeda(snapeda::SnapedaSubcommand),
+    /// Bill-of-materials tools (bulk datasheet download, stock and lifecycle risk report)
+    #[command(subcommand)]
+    Bom(bom::BomSubcommand),
+    /// KiCad project integration (fetch missing datasheets, symbols, footprints)
+    #[command(subcommand)]
+    Kicad(kicad::KicadSubcommand),
+    /// Gemini file cache maintenance (prune local entries and remote files)
+    #[command(subcommand)]
+    Cache(file_cache::CacheSubcommand),
+    /// Store API keys and secrets in the OS keyring
+    #[command(subcommand)]
+    Auth(auth::AuthSubcommand),
+    /// Show the config file location and the active profile's settings
+    #[command(subcommand)]
+    Config(config::ConfigSubcommand),
+    /// Local datasheet library (add, list, find, dedupe)
+    #[command(subcommand)]
+    Library(library::LibrarySubcommand),
+    /// Local parts database (index extractions and distributor data, query parts)
+    #[command(subcommand)]
+    Db(db::DbSubcommand),
+    /// Push parts to (or pull parts from) InvenTree or PartsBox
+    #[command(subcommand)]
+    Inventory(inventory::InventorySubcommand),
+    /// Persistent extraction job queue (submit, run, list, show, retry, cancel)
+    #[command(subcommand)]
+    Jobs(jobs::JobsSubcommand),
+    /// Run datasheet commands on a cron schedule (add, list, remove, run)
+    #[command(subcommand)]
+    Schedule(schedule::ScheduleSubcommand),
+    /// Watch distributor stock and pricing, alerting on threshold crossings
+    Watch(watch::WatchArgs),
+    /// Show a part's recorded stock and price snapshots and how they changed
+    History(history::HistoryArgs),
+    /// Find and rank replacement / second-source candidates for a part
+    Alternates(alternates::AlternatesArgs),
+    /// Show a part's expected topside marking beside distributor product photos
+    VerifyMarking(verify_marking::VerifyMarkingArgs),
+    /// Read a DigiKey/Mouser bag label's 2D barcode: MPN, quantity, lot and date code
+    Scan(scan::ScanArgs),
+    /// Summarize recorded LLM token usage and estimated cost for a month
+    Usage(usage::UsageArgs),
+    /// Check credentials, directories, and provider reachability (for CI and containers)
+    Healthcheck(healthcheck::HealthcheckArgs),
+    /// Interactive terminal UI: search distributors, view parts, queue downloads and extractions
+    Tui(tui::TuiArgs),
     /// Download SVD (System View Description) register map files for microcontrollers
     #[command(subcommand)]
     Svd(svd::SvdSubcommand),
+    /// Print a shell completion script (bash, zsh, fish, elvish, powershell)
+    Completions(completions::CompletionsArgs),
+    /// Generate man pages for every command
+    Manpages(completions::ManpagesArgs),
     /// Extract footprint drawings from a PDF datasheet as cropped images
     FootprintImage(footprint_image::FootprintImageArgs),
     /// Extract specific pages/regions from a PDF using LLM-guided detection
     ExtractPages(extract_pages::ExtractPagesArgs),
+    /// Copy a PDF with highlights on the sources of an extraction result's values
+    Annotate(annotate::AnnotateArgs),
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn main() -> ExitCode {
+    let args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    if let Err(e) = config::apply(&args) {
+        return error::report(&e, error::ErrorFormat::Text);
+    }
+    let cli = Cli::parse_from(args);
+    let error_format = cli.error_format;
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => error::report(&e, error_format),
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    if let Some(ref code) = cli.currency {
+        currency::set_target(code);
+    }
+    offline::set(cli.offline);
+    picker::set_no_input(cli.no_input);
+    progress::set(cli.progress);
+    if let Some(selector) = cli.select {
+        select::set(selector);
+    }
+    if let Some(dir) = cli.cache_dir {
+        file_cache::set_cache_dir(dir);
+    }
+    if let Some(dir) = cli.download_dir {
+        paths::set_download_dir(dir);
+    }
+    paths::set_backup(cli.backup);
+    history::set_record(cli.record_history);
+    if let Some(bytes) = cli.cache_max_size {
+        file_cache::set_max_size(bytes);
+    }
+    if let Some(uploads) = cli.upload_concurrency {
+        file_cache::set_upload_concurrency(uploads);
+    }
+    llm::set_schema_mode(cli.schema_mode);
+    if let Some(bytes) = cli.max_response_size {
+        llm::set_max_response_size(bytes);
+    }
+    if let Some(source) = cli.prompt_source {
+        prompt_source::set(source);
+    }
+    budget::set(budget::Limits {
+        max_requests_per_day: cli.max_requests_per_day,
+        max_tokens_per_run: cli.max_tokens_per_run,
+        max_spend_per_run: cli.max_spend_per_run,
+    });
 
     match cli.command {
         Command::Extract(args) => extract::run_extract(&args),
+        Command::Completions(args) => completions::completions(&args),
+        Command::Manpages(args) => completions::manpages(&args),
+        Command::Ask(args) => ask::run(&args),
+        Command::Summarize(args) => summarize::run(&args),
+        Command::Embed(args) => embed::run(&args),
+        Command::CompareRevisions(args) => compare_revisions::run(&args),
+        Command::Pipeline(args) => pipeline::run(&args),
+        Command::Bench(args) => bench::run(&args),
+        Command::PromptTest(args) => prompt_test::run(&args),
+        Command::Migrate(args) => migrate::run(&args),
+        Command::Part(args) => part::run(&args),
+        Command::PartInfo(args) => part_info::run(&args),
+        Command::Find(args) => find::run(&args),
         Command::Mouser(subcommand) => {
             mouser::execute(subcommand).map_err(|e| anyhow!(e))
         }
@@ -71,10 +344,49 @@ fn main() -> Result<()> {
         Command::Snapeda(subcommand) => {
             snapeda::execute(subcommand).map_err(|e| anyhow!(e))
         }
+        Command::Bom(subcommand) => {
+            bom::execute(subcommand).map_err(|e| anyhow!(e))
+        }
+        Command::Kicad(subcommand) => {
+            kicad::execute(subcommand).map_err(|e| anyhow!(e))
+        }
+        Command::Cache(subcommand) => {
+            file_cache::execute(subcommand).map_err(|e| anyhow!(e))
+        }
+        Command::Auth(subcommand) => {
+            auth::execute(subcommand).map_err(|e| anyhow!(e))
+        }
+        Command::Config(subcommand) => {
+            config::execute(subcommand).map_err(|e| anyhow!(e))
+        }
+        Command::Library(subcommand) => {
+            library::execute(subcommand).map_err(|e| anyhow!(e))
+        }
+        Command::Db(subcommand) => {
+            db::execute(subcommand).map_err(|e| anyhow!(e))
+        }
+        Command::Inventory(subcommand) => {
+            inventory::execute(subcommand).map_err(|e| anyhow!(e))
+        }
+        Command::Jobs(subcommand) => {
+            jobs::execute(subcommand).map_err(|e| anyhow!(e))
+        }
+        Command::Schedule(subcommand) => {
+            schedule::execute(subcommand).map_err(|e| anyhow!(e))
+        }
+        Command::Watch(args) => watch::run(&args),
+        Command::History(args) => history::run(&args),
+        Command::Alternates(args) => alternates::run(&args),
+        Command::VerifyMarking(args) => verify_marking::run(&args),
+        Command::Scan(args) => scan::run(&args),
         Command::Svd(subcommand) => {
             svd::execute(subcommand).map_err(|e| anyhow!(e))
         }
+        Command::Tui(args) => tui::run(&args),
+        Command::Usage(args) => usage::run(&args),
+        Command::Healthcheck(args) => healthcheck::run(&args),
         Command::FootprintImage(args) => footprint_image::run(&args),
         Command::ExtractPages(args) => extract_pages::run(&args),
+        Command::Annotate(args) => annotate::run(&args),
     }
 }
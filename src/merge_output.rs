@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! `extract --append`: add a task's result to an existing per-part JSON
+//! document instead of overwriting it, so tasks can be run one at a time.
+//!
+//! The document has the layout `datasheet part` writes, one key per task. The
+//! new result goes under its task's key. If that key is already there, the two
+//! are merged: objects key by key, everything else (strings, numbers, arrays)
+//! as a whole, with `--on-conflict` deciding which side wins where they differ.
+//! A file holding a single task's output (stamped with `_schema` at the top) is
+//! first moved under that task's key.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Take the new value
+    #[default]
+    Replace,
+    /// Keep the value already in the file
+    Keep,
+    /// Fail without writing anything
+    Error,
+}
+
+/// The document at `path` (empty if the file does not exist yet) with `value`
+/// merged in under `task`.
+pub fn append(path: &Path, task: &str, value: Value, on_conflict: OnConflict) -> Result<Value> {
+    let mut document = match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Value::Object(Map::new()),
+        Err(e) => return Err(anyhow::Error::new(e).context(format!("reading {}", path.display()))),
+    };
+    let Value::Object(map) = &mut document else {
+        return Err(Error::validation(format!("{}: --append needs a JSON object", path.display())).into());
+    };
+    if let Some(single) = single_task(map) {
+        eprintln!("[EXTRACT] Moving the {} output in {} under \"{}\"", single, path.display(), single);
+        let previous = std::mem::take(map);
+        map.insert(single, Value::Object(previous));
+    }
+
+    let mut conflicts = Vec::new();
+    match map.get_mut(task) {
+        Some(existing) => merge(existing, value, on_conflict, &format!("/{}", task), &mut conflicts),
+        None => {
+            map.insert(task.to_string(), value);
+        }
+    }
+    if !conflicts.is_empty() {
+        if on_conflict == OnConflict::Error {
+            return Err(Error::validation(format!(
+                "{}: new {} result differs at {}; pass --on-conflict replace or keep",
+                path.display(),
+                task,
+                conflicts.join(", ")
+            ))
+            .into());
+        }
+        eprintln!(
+            "[EXTRACT] {} field(s) of {} differ from {}; {} ({})",
+            conflicts.len(),
+            task,
+            path.display(),
+            if on_conflict == OnConflict::Keep { "kept the existing values" } else { "took the new values" },
+            conflicts.join(", ")
+        );
+    }
+    Ok(document)
+}
+
+/// Task name of a document that is one task's `extract` output rather than a
+/// per-part document.
+fn single_task(map: &Map<String, Value>) -> Option<String> {
+    map.get("_schema")?.get("task")?.as_str().map(str::to_string)
+}
+
+/// Merge `new` into `existing`, recording the JSON pointer of every value the
+/// two disagree on.
+fn merge(existing: &mut Value, new: Value, on_conflict: OnConflict, path: &str, conflicts: &mut Vec<String>) {
+    match (existing, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, value) in new {
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                match old.get_mut(&key) {
+                    Some(slot) => merge(slot, value, on_conflict, &child, conflicts),
+                    None => {
+                        old.insert(key, value);
+                    }
+                }
+            }
+        }
+        (existing, new) => {
+            if *existing != new && !existing.is_null() && !new.is_null() {
+                conflicts.push(path.to_string());
+                if on_conflict == OnConflict::Replace {
+                    *existing = new;
+                }
+            } else if existing.is_null() {
+                *existing = new;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_append() {
+        let dir = std::env::temp_dir().join(format!("datasheet-append-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("LM358.json");
+
+        let pinout = json!({ "_schema": { "task": "pinout", "version": 1 }, "part_number": "LM358" });
+        let doc = append(&path, "pinout", pinout.clone(), OnConflict::Error).unwrap();
+        assert_eq!(doc, json!({ "pinout": pinout }));
+
+        // A single task's output is moved under its name
+        std::fs::write(&path, pinout.to_string()).unwrap();
+        let power = json!({ "rails": [{ "name": "VCC", "max": 32 }], "notes": null });
+        let doc = append(&path, "power", power.clone(), OnConflict::Error).unwrap();
+        assert_eq!(doc, json!({ "pinout": pinout, "power": power }));
+
+        std::fs::write(&path, doc.to_string()).unwrap();
+        let update = json!({ "rails": [{ "name": "VCC", "max": 36 }], "notes": "dual supply", "iq": 0.5 });
+        let err = append(&path, "power", update.clone(), OnConflict::Error).unwrap_err();
+        assert!(err.to_string().contains("differs at /power/rails"));
+
+        let kept = append(&path, "power", update.clone(), OnConflict::Keep).unwrap();
+        assert_eq!(kept["power"], json!({ "rails": [{ "name": "VCC", "max": 32 }], "notes": "dual supply", "iq": 0.5 }));
+        let replaced = append(&path, "power", update.clone(), OnConflict::Replace).unwrap();
+        assert_eq!(replaced["power"], update);
+        assert_eq!(replaced["pinout"], pinout);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Schema versions for extraction output and `datasheet migrate`.
+//!
+//! Every built-in task's output is stamped with `"_schema": {"task": ..., "version": N}`.
+//! When a task's schema changes shape, bump its version by adding a migration
+//! below that rewrites output of the previous version; `datasheet migrate` (and
+//! `db index`) run the migrations so results saved by older versions keep
+//! working. Output without a stamp predates versioning and counts as version 1.
+//!
+//! Fresh extractions also record `prompt_version` and `schema_version` in the
+//! stamp: short hashes of the prompt and schema they were made with, so stored
+//! records can be traced to the prompt generation (built-in release, language,
+//! or `--prompt-source` revision) that produced them.
+
+use anyhow::{Context, Result, anyhow};
+use clap::{Args, ValueEnum};
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+use crate::extract::ExtractTask;
+
+/// Key of the version stamp in extraction output.
+pub const STAMP: &str = "_schema";
+
+/// Rewrites output of `task` at version `from` into version `from + 1`.
+struct Migration {
+    task: &'static str,
+    from: u64,
+    description: &'static str,
+    apply: fn(&mut Value),
+}
+
+/// All schema changes, oldest first.
+const MIGRATIONS: &[Migration] = &[Migration {
+    task: "feature-matrix",
+    from: 1,
+    description: "add orderable_part_numbers to each variant",
+    apply: |value| {
+        for variant in value.get_mut("variants").and_then(|v| v.as_array_mut()).into_iter().flatten() {
+            if let Some(variant) = variant.as_object_mut() {
+                variant.entry("orderable_part_numbers").or_insert_with(|| json!([]));
+            }
+        }
+    },
+}];
+
+#[derive(Args, Debug)]
+pub struct MigrateArgs {
+    /// Extraction JSON files to upgrade
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+
+    /// Task that produced the files (needed for output saved before versioning)
+    #[arg(long, value_enum)]
+    pub task: Option<ExtractTask>,
+
+    /// Rewrite the files instead of printing the upgraded JSON (required for several files)
+    #[arg(long)]
+    pub in_place: bool,
+
+    /// Show formatted (pretty-printed) JSON output
+    #[arg(long, short = 'f', visible_alias = "pretty", env = "DATASHEET_FORMATTED")]
+    pub formatted: bool,
+}
+
+pub fn run(args: &MigrateArgs) -> Result<()> {
+    if args.files.len() > 1 && !args.in_place {
+        return Err(anyhow!("pass --in-place to upgrade several files"));
+    }
+    let task = args.task.map(|t| t.prompt().name);
+    for path in &args.files {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut value: Value =
+            serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+        let from = version(&value);
+        let applied = upgrade(&mut value, task).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+        for description in &applied {
+            eprintln!("[MIGRATE] {}: {}", path.display(), description);
+        }
+        eprintln!(
+            "[MIGRATE] {}: version {} -> {}{}",
+            path.display(),
+            from,
+            version(&value),
+            if applied.is_empty() { " (no changes)" } else { "" }
+        );
+        if args.in_place {
+            crate::extract::write_output(&value, Some(path), args.formatted)?;
+        } else {
+            crate::extract::write_output(&value, None, args.formatted)?;
+        }
+    }
+    Ok(())
+}
+
+/// Current schema version of a task's output.
+pub fn current_version(task: &str) -> u64 {
+    1 + MIGRATIONS.iter().filter(|m| m.task == task).count() as u64
+}
+
+/// Hashes identifying the prompt and schema an extraction was made with.
+pub struct PromptVersions {
+    pub prompt: String,
+    pub schema: String,
+}
+
+impl PromptVersions {
+    pub fn new(prompt: &str, schema: &Value) -> Self {
+        let short = |data: &[u8]| crate::file_cache::compute_hash(data)[..12].to_string();
+        Self {
+            prompt: short(prompt.as_bytes()),
+            schema: short(schema.to_string().as_bytes()),
+        }
+    }
+}
+
+/// Stamp a built-in task's output with its current schema version, and with
+/// the prompt and schema hashes when given. Other fields already in the stamp
+/// are kept.
+pub fn stamp(value: &mut Value, task: &str, versions: Option<&PromptVersions>) {
+    if task == "custom" {
+        return;
+    }
+    let Some(map) = value.as_object_mut() else {
+        return;
+    };
+    let stamp = map.entry(STAMP).or_insert_with(|| json!({}));
+    if !stamp.is_object() {
+        *stamp = json!({});
+    }
+    stamp["task"] = json!(task);
+    stamp["version"] = json!(current_version(task));
+    if let Some(versions) = versions {
+        stamp["prompt_version"] = json!(versions.prompt);
+        stamp["schema_version"] = json!(versions.schema);
+    }
+}
+
+fn version(value: &Value) -> u64 {
+    value.pointer(&format!("/{STAMP}/version")).and_then(|v| v.as_u64()).unwrap_or(1)
+}
+
+/// Bring output up to the current schema version of its task, taken from the
+/// stamp or else from `task`. Returns the changes applied.
+pub fn upgrade(value: &mut Value, task: Option<&str>) -> Result<Vec<&'static str>, String> {
+    let stamped = value.pointer(&format!("/{STAMP}/task")).and_then(|t| t.as_str()).map(str::to_string);
+    let task = match (stamped.as_deref(), task) {
+        (Some(stamped), Some(given)) if stamped != given => {
+            return Err(format!("output is stamped as '{}', not '{}'", stamped, given));
+        }
+        (Some(stamped), _) => stamped.to_string(),
+        (None, Some(given)) => given.to_string(),
+        (None, None) => return Err("output has no schema stamp; pass --task".to_string()),
+    };
+    if !ExtractTask::value_variants().iter().any(|t| t.prompt().name == task) {
+        return Err(format!("unknown task '{}'", task));
+    }
+    let from = version(value);
+    let current = current_version(&task);
+    if from > current {
+        return Err(format!(
+            "{} output is schema version {}, newer than this datasheet-cli supports ({}); upgrade datasheet-cli",
+            task, from, current
+        ));
+    }
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.task == task && m.from >= from) {
+        (migration.apply)(value);
+        applied.push(migration.description);
+    }
+    stamp(value, &task, None);
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade() {
+        let mut matrix = json!({"variants": [{"root_part_number": "STM32F405"}]});
+        assert!(upgrade(&mut matrix, None).is_err());
+        assert_eq!(upgrade(&mut matrix, Some("feature-matrix")).unwrap().len(), 1);
+        assert_eq!(matrix["variants"][0]["orderable_part_numbers"], json!([]));
+        assert_eq!(matrix[STAMP], json!({"task": "feature-matrix", "version": 2}));
+        // Stamped output needs no --task and is not migrated twice
+        assert!(upgrade(&mut matrix, None).unwrap().is_empty());
+
+        // Prompt hashes survive an upgrade
+        let versions = PromptVersions::new("Extract the pinout", &json!({"type": "object"}));
+        let mut pinout = json!({"_schema": {"task": "pinout", "version": 1}});
+        stamp(&mut pinout, "pinout", Some(&versions));
+        assert_eq!(pinout[STAMP]["prompt_version"].as_str().unwrap().len(), 12);
+        upgrade(&mut pinout, None).unwrap();
+        assert_eq!(pinout[STAMP]["schema_version"], json!(versions.schema));
+
+        let mut newer = json!({"_schema": {"task": "pinout", "version": 99}});
+        assert!(upgrade(&mut newer, None).unwrap_err().contains("newer"));
+    }
+}
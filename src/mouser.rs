@@ -5,12 +5,24 @@
 
 use clap::Subcommand;
 use serde::{Deserialize, Serialize};
-use std::fs::File;
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::currency;
+use crate::download::{self, DownloadOptions};
+use crate::error::{Error, ErrorKind};
+use crate::parameters::{self, PartParameters};
+use crate::stock::{SearchHit, StockInfo, StockPriceBreak, TableFormat, TableRow};
 
 const MOUSER_API_BASE: &str = "https://api.mouser.com/api/v1";
 const ENV_VAR_NAME: &str = "MOUSER_API_KEY";
 
+/// Most records Mouser returns for one keyword search request
+const MAX_RECORDS: usize = 50;
+
+/// Pause between the pages of `search --all`; Mouser allows 30 calls a minute
+const PAGE_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Mouser API subcommands.
 #[derive(Subcommand, Debug)]
 pub enum MouserSubcommand {
@@ -39,9 +51,26 @@ pub enum MouserSubcommand {
         #[arg(long, short)]
         exact: bool,
 
+        /// Fetch every page of results (50 per request), up to --max
+        #[arg(long, conflicts_with_all = ["limit", "page", "exact"])]
+        all: bool,
+
+        /// Most results to fetch with --all
+        #[arg(long, requires = "all", default_value = "500")]
+        max: usize,
+
         /// Output results as JSON
         #[arg(long)]
         json: bool,
+
+        /// Print a table with these columns instead of the summary, e.g.
+        /// "mpn,mfr,stock,price@100,lifecycle" (also: desc, sku, moq, lead, currency, distributor)
+        #[arg(long, conflicts_with = "json")]
+        columns: Option<String>,
+
+        /// Print CSV (of --columns, default mpn,mfr,stock,price,lifecycle)
+        #[arg(long, conflicts_with = "json")]
+        csv: bool,
     },
 
     /// Download datasheet for a part
@@ -57,9 +86,13 @@ pub enum MouserSubcommand {
         #[arg(long, short)]
         output: Option<PathBuf>,
 
-        /// Output directory (used if --output not specified)
+        /// Output directory (used if --output not specified; default: <download dir>/datasheets)
         #[arg(long, short)]
         dir: Option<PathBuf>,
+
+        /// Print the resolved datasheet URL instead of downloading it
+        #[arg(long, conflicts_with_all = ["output", "dir"])]
+        url_only: bool,
     },
 
     /// Get detailed information about a specific part
@@ -90,31 +123,6 @@ pub enum MouserSubcommand {
     },
 }
 
-// Normalized stock/pricing output type
-
-#[derive(Serialize)]
-struct StockInfo {
-    mpn: String,
-    manufacturer: Option<String>,
-    distributor: &'static str,
-    distributor_pn: Option<String>,
-    lifecycle_status: Option<String>,
-    stock: Option<i64>,
-    lead_time: Option<String>,
-    moq: Option<i32>,
-    order_multiple: Option<i32>,
-    currency: String,
-    price_breaks: Vec<StockPriceBreak>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    suggested_replacement: Option<String>,
-}
-
-#[derive(Serialize)]
-struct StockPriceBreak {
-    quantity: i32,
-    unit_price: f64,
-}
-
 // Mouser API request/response types
 
 #[derive(Serialize)]
@@ -238,7 +246,7 @@ struct PriceBreak {
 }
 
 /// Execute a Mouser subcommand.
-pub fn execute(command: MouserSubcommand) -> Result<(), String> {
+pub fn execute(command: MouserSubcommand) -> Result<(), Error> {
     match command {
         MouserSubcommand::Search {
             query,
@@ -247,14 +255,30 @@ pub fn execute(command: MouserSubcommand) -> Result<(), String> {
             page,
             offset,
             exact,
+            all,
+            max,
             json,
-        } => cmd_search(&query, api_key.as_deref(), limit, page, offset, exact, json),
+            columns,
+            csv,
+        } => {
+            let table = TableFormat::from_args(columns.as_deref(), csv)?;
+            let limit = if all { max } else { limit };
+            let options = SearchOptions {
+                limit,
+                page,
+                offset,
+                exact,
+                all,
+            };
+            cmd_search(&query, api_key.as_deref(), &options, json, table)
+        }
         MouserSubcommand::Download {
             part_number,
             api_key,
             output,
             dir,
-        } => cmd_download(&part_number, api_key.as_deref(), output, dir),
+            url_only,
+        } => cmd_download(&part_number, api_key.as_deref(), output, dir, url_only),
         MouserSubcommand::Part {
             part_number,
             api_key,
@@ -268,36 +292,58 @@ pub fn execute(command: MouserSubcommand) -> Result<(), String> {
     }
 }
 
-fn get_api_key(provided: Option<&str>) -> Result<String, String> {
+fn get_api_key(provided: Option<&str>) -> Result<String, Error> {
     if let Some(key) = provided {
         if !key.is_empty() {
             return Ok(key.to_string());
         }
     }
 
-    std::env::var(ENV_VAR_NAME).map_err(|_| {
-        format!(
-            "Mouser API key not provided. Set {} environment variable or use --api-key",
+    // Offline lookups replay cached responses, which are not keyed by API key
+    if crate::offline::is_enabled() {
+        return Ok(std::env::var(ENV_VAR_NAME).unwrap_or_default());
+    }
+
+    crate::auth::credential(ENV_VAR_NAME).ok_or_else(|| {
+        Error::auth(format!(
+            "Mouser API key not provided. Set {} environment variable, run `datasheet auth set mouser`, or use --api-key",
             ENV_VAR_NAME
-        )
+        ))
     })
 }
 
-fn cmd_search(
-    query: &str,
-    api_key: Option<&str>,
+/// Which results `mouser search` fetches.
+struct SearchOptions {
     limit: usize,
+    /// 1-based page number, takes precedence over `offset`
     page: Option<usize>,
     offset: Option<usize>,
+    /// Search by exact part number instead of keyword
     exact: bool,
+    /// Fetch every page, up to `limit` results
+    all: bool,
+}
+
+fn cmd_search(
+    query: &str,
+    api_key: Option<&str>,
+    options: &SearchOptions,
     json_output: bool,
-) -> Result<(), String> {
+    table: Option<TableFormat>,
+) -> Result<(), Error> {
     let api_key = get_api_key(api_key)?;
+    let SearchOptions {
+        limit,
+        page,
+        offset,
+        exact,
+        all,
+    } = *options;
 
     // Calculate starting record: page takes precedence over offset
     let starting_record = if let Some(p) = page {
         if p == 0 {
-            return Err("Page number must be 1 or greater".to_string());
+            return Err("Page number must be 1 or greater".to_string().into());
         }
         (p - 1) * limit
     } else {
@@ -306,12 +352,31 @@ fn cmd_search(
 
     let parts = if exact {
         search_by_part_number(&api_key, query)?
+    } else if all {
+        let interval = if crate::offline::is_enabled() { Duration::ZERO } else { PAGE_INTERVAL };
+        collect_pages(starting_record, limit, interval, |offset, records| {
+            search_keyword_page(&api_key, query, records, offset)
+        })?
     } else {
         search_by_keyword(&api_key, query, limit, starting_record)?
     };
+    crate::history::record(parts.iter().take(limit).map(|part| stock_info(part, query)));
+
+    if let Some(table) = table {
+        let rows: Vec<TableRow> = parts
+            .iter()
+            .take(limit)
+            .map(|part| TableRow {
+                info: stock_info(part, query),
+                description: part.description.clone(),
+            })
+            .collect();
+        table.print(&rows);
+        return Ok(());
+    }
 
     if json_output {
-        let json = serde_json::to_string_pretty(&parts)
+        let json = crate::select::to_string_pretty(&parts)
             .map_err(|e| format!("Failed to serialize results: {}", e))?;
         println!("{}", json);
     } else {
@@ -336,95 +401,75 @@ fn cmd_download(
     api_key: Option<&str>,
     output: Option<PathBuf>,
     dir: Option<PathBuf>,
-) -> Result<(), String> {
+    url_only: bool,
+) -> Result<(), Error> {
     let api_key = get_api_key(api_key)?;
 
     // Search for the part to get the datasheet URL
     let parts = search_by_part_number(&api_key, part_number)?;
-
-    if parts.is_empty() {
-        return Err(format!("Part not found: {}", part_number));
-    }
-
-    let part = &parts[0];
+    let part = choose_part(&parts, part_number)?;
     let datasheet_url = part
         .data_sheet_url
         .as_ref()
-        .ok_or_else(|| format!("No datasheet available for part: {}", part_number))?;
+        .ok_or_else(|| Error::not_found(format!("No datasheet available for part: {}", part_number)))?;
 
     if datasheet_url.is_empty() {
-        return Err(format!("No datasheet available for part: {}", part_number));
+        return Err(Error::not_found(format!("No datasheet available for part: {}", part_number)));
+    }
+
+    if url_only {
+        println!("{}", download::normalize_url(datasheet_url));
+        return Ok(());
     }
 
     // Determine output path
     let output_path = if let Some(path) = output {
         path
     } else {
-        let filename = format!(
-            "{}.pdf",
-            part.manufacturer_part_number
-                .as_ref()
-                .unwrap_or(&part_number.to_string())
-                .replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_")
-        );
-        if let Some(dir) = dir {
-            dir.join(filename)
-        } else {
-            PathBuf::from(filename)
-        }
+        let mpn = part.manufacturer_part_number.as_deref().unwrap_or(part_number);
+        let filename = format!("{}.pdf", crate::paths::sanitize_filename(mpn));
+        dir.unwrap_or_else(crate::paths::datasheets_dir).join(filename)
     };
 
     println!("Downloading datasheet for {}...", part_number);
     println!("  URL: {}", datasheet_url);
     println!("  Output: {}", output_path.display());
 
-    // Download the datasheet with proper headers (Mouser CDN requires User-Agent)
-    let response = ureq::get(datasheet_url)
-        .set("User-Agent", "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
-        .set("Accept", "application/pdf,*/*")
-        .set("Referer", "https://www.mouser.com/")
-        .call()
-        .map_err(|e| format!("Failed to download datasheet: {}", e))?;
-
-    // Verify we got a PDF, not an HTML error/redirect page
-    let content_type = response.content_type().to_string();
-
-    let mut file =
-        File::create(&output_path).map_err(|e| format!("Failed to create output file: {}", e))?;
-
-    let mut reader = response.into_reader();
-    let bytes_written = std::io::copy(&mut reader, &mut file)
-        .map_err(|e| format!("Failed to write datasheet: {}", e))?;
-
-    // Check if we got HTML instead of PDF (bot protection / redirect)
-    if content_type.contains("text/html") || bytes_written < 1024 {
-        // Remove the invalid file
-        let _ = std::fs::remove_file(&output_path);
-        return Err(format!(
-            "Download returned HTML instead of PDF (content-type: {}). \
-             Mouser may be blocking automated downloads for this URL.",
-            content_type
-        ));
-    }
+    // Mouser's CDN rejects requests without a browser User-Agent and Referer
+    let options = DownloadOptions::default().with_referer("https://www.mouser.com/");
+    let bytes_written = download::download_pdf(datasheet_url, &output_path, &options)?;
 
     println!("Datasheet downloaded successfully! ({:.1} KB)", bytes_written as f64 / 1024.0);
 
     Ok(())
 }
 
-fn cmd_part(part_number: &str, api_key: Option<&str>, json_output: bool) -> Result<(), String> {
-    let api_key = get_api_key(api_key)?;
-
-    let parts = search_by_part_number(&api_key, part_number)?;
-
+/// The part meant by `part_number`, asking the user when Mouser lists several.
+fn choose_part<'a>(parts: &'a [Part], part_number: &str) -> Result<&'a Part, Error> {
     if parts.is_empty() {
-        return Err(format!("Part not found: {}", part_number));
+        return Err(Error::not_found(format!("Part not found: {}", part_number)));
     }
+    let rows: Vec<TableRow> = parts
+        .iter()
+        .map(|part| TableRow {
+            info: stock_info(part, part_number),
+            description: part.description.clone(),
+        })
+        .collect();
+    let index = crate::stock::pick(&format!("Mouser matches for {}", part_number), &rows)?;
+    Ok(&parts[index])
+}
 
-    let part = &parts[0];
+fn cmd_part(part_number: &str, api_key: Option<&str>, json_output: bool) -> Result<(), Error> {
+    let api_key = get_api_key(api_key)?;
+
+    let parts = search_by_part_number(&api_key, part_number)?;
+    let part = choose_part(&parts, part_number)?;
 
     if json_output {
-        let json = serde_json::to_string_pretty(part)
+        let json = serde_json::to_value(part)
+            .map(|value| parameters::with_canonical(value, &part_parameters(part)))
+            .and_then(|value| crate::select::to_string_pretty(&value))
             .map_err(|e| format!("Failed to serialize part: {}", e))?;
         println!("{}", json);
     } else {
@@ -434,74 +479,12 @@ fn cmd_part(part_number: &str, api_key: Option<&str>, json_output: bool) -> Resu
     Ok(())
 }
 
-fn cmd_stock(part_number: &str, api_key: Option<&str>, json_output: bool) -> Result<(), String> {
-    let api_key = get_api_key(api_key)?;
-
-    let parts = search_by_part_number(&api_key, part_number)?;
-
-    if parts.is_empty() {
-        return Err(format!("Part not found: {}", part_number));
-    }
-
-    let part = &parts[0];
-
-    let mpn = part
-        .manufacturer_part_number
-        .clone()
-        .or_else(|| part.mouser_part_number.clone())
-        .unwrap_or_else(|| part_number.to_string());
-
-    let moq = part.min.as_deref().and_then(|s| s.parse::<i32>().ok());
-    let order_multiple = part.mult.as_deref().and_then(|s| s.parse::<i32>().ok());
-
-    let stock = part.availability_in_stock.as_deref().and_then(|s| {
-        // "4,137 In Stock" → extract leading number, strip commas
-        let num_part: String = s.chars().take_while(|c| c.is_ascii_digit() || *c == ',').collect();
-        num_part.replace(',', "").parse::<i64>().ok()
-    });
-
-    let price_breaks: Vec<StockPriceBreak> = part
-        .price_breaks
-        .as_deref()
-        .unwrap_or_default()
-        .iter()
-        .filter_map(|pb| {
-            let qty = pb.quantity?;
-            let price_str = pb.price.as_deref()?;
-            // Price may be "$10.13" or "1,234.56" — strip currency symbols and commas
-            let price: f64 = price_str.replace(['$', ',', '€', '£'], "").trim().parse().ok()?;
-            Some(StockPriceBreak {
-                quantity: qty,
-                unit_price: price,
-            })
-        })
-        .collect();
-
-    let currency = part
-        .price_breaks
-        .as_deref()
-        .unwrap_or_default()
-        .first()
-        .and_then(|pb| pb.currency.clone())
-        .unwrap_or_else(|| "USD".to_string());
-
-    let info = StockInfo {
-        mpn,
-        manufacturer: part.manufacturer.clone(),
-        distributor: "mouser",
-        distributor_pn: part.mouser_part_number.clone(),
-        lifecycle_status: part.lifecycle_status.clone(),
-        stock,
-        lead_time: part.lead_time.clone(),
-        moq,
-        order_multiple,
-        currency,
-        price_breaks,
-        suggested_replacement: part.suggested_replacement.clone(),
-    };
+fn cmd_stock(part_number: &str, api_key: Option<&str>, json_output: bool) -> Result<(), Error> {
+    let info = fetch_stock_info(&get_api_key(api_key)?, part_number)?;
+    crate::history::record([info.clone()]);
 
     if json_output {
-        let json = serde_json::to_string_pretty(&info)
+        let json = crate::select::to_string_pretty(&info)
             .map_err(|e| format!("Failed to serialize stock info: {}", e))?;
         println!("{}", json);
     } else {
@@ -547,9 +530,9 @@ fn cmd_stock(part_number: &str, api_key: Option<&str>, json_output: bool) -> Res
             for pb in &info.price_breaks {
                 let qty_label = format!("{}+", pb.quantity);
                 println!(
-                    "    {:>width$} : ${:.2}",
+                    "    {:>width$} : {}",
                     qty_label,
-                    pb.unit_price,
+                    currency::format_price(pb.unit_price, &info.currency, 2),
                     width = max_qty_width
                 );
             }
@@ -565,6 +548,193 @@ fn cmd_stock(part_number: &str, api_key: Option<&str>, json_output: bool) -> Res
     Ok(())
 }
 
+/// Look up normalized stock and pricing for a part using the `MOUSER_API_KEY` credential (environment or keyring).
+pub(crate) fn lookup_stock(part_number: &str) -> Result<StockInfo, Error> {
+    fetch_stock_info(&get_api_key(None)?, part_number)
+}
+
+/// Description and parametric attributes of a part (first search hit), using the
+/// `MOUSER_API_KEY` credential (environment or keyring).
+pub(crate) fn lookup_parameters(part_number: &str) -> Result<PartParameters, Error> {
+    let parts = search_by_part_number(&get_api_key(None)?, part_number)?;
+    let part = parts
+        .first()
+        .ok_or_else(|| Error::not_found(format!("Part not found: {}", part_number)))?;
+    Ok(PartParameters {
+        manufacturer: part.manufacturer.clone(),
+        description: part.description.clone(),
+        parameters: part_parameters(part),
+    })
+}
+
+/// `ProductAttributes` as name/value pairs; repeated names are joined with ", ".
+fn part_parameters(part: &Part) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    let attributes = part.product_attributes.as_ref().and_then(|v| v.as_array());
+    for attr in attributes.into_iter().flatten() {
+        let field = |key: &str| attr.get(key).and_then(|v| v.as_str()).map(str::trim);
+        let (Some(name), Some(value)) = (field("AttributeName"), field("AttributeValue")) else {
+            continue;
+        };
+        match pairs.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => {
+                existing.push_str(", ");
+                existing.push_str(value);
+            }
+            None => pairs.push((name.to_string(), value.to_string())),
+        }
+    }
+    pairs
+}
+
+fn fetch_stock_info(api_key: &str, part_number: &str) -> Result<StockInfo, Error> {
+    let parts = search_by_part_number(api_key, part_number)?;
+    Ok(stock_info(exact_part(&parts, part_number)?, part_number))
+}
+
+/// The listing whose manufacturer or Mouser part number is `part_number`,
+/// ignoring case. The part number search also returns variants (`LM358DR2G`
+/// for `LM358DR`), sometimes ranked first, which must not stand in for the
+/// part in stock and risk reports.
+fn exact_part<'a>(parts: &'a [Part], part_number: &str) -> Result<&'a Part, Error> {
+    let wanted = part_number.trim();
+    let same = |pn: &Option<String>| pn.as_deref().is_some_and(|pn| pn.trim().eq_ignore_ascii_case(wanted));
+    parts
+        .iter()
+        .find(|p| same(&p.manufacturer_part_number) || same(&p.mouser_part_number))
+        .ok_or_else(|| {
+            let similar: Vec<&str> = parts.iter().filter_map(|p| p.manufacturer_part_number.as_deref()).take(5).collect();
+            if similar.is_empty() {
+                Error::not_found(format!("Part not found: {}", part_number))
+            } else {
+                Error::not_found(format!("Part not found: {} (Mouser lists {})", part_number, similar.join(", ")))
+            }
+        })
+}
+
+/// Normalized stock and pricing of a search result; `part_number` is the
+/// fallback MPN.
+fn stock_info(part: &Part, part_number: &str) -> StockInfo {
+    let mpn = part
+        .manufacturer_part_number
+        .clone()
+        .or_else(|| part.mouser_part_number.clone())
+        .unwrap_or_else(|| part_number.to_string());
+
+    let moq = part.min.as_deref().and_then(|s| s.parse::<i32>().ok());
+    let order_multiple = part.mult.as_deref().and_then(|s| s.parse::<i32>().ok());
+
+    let stock = part.availability_in_stock.as_deref().and_then(|s| {
+        // "4,137 In Stock" → extract leading number, strip commas
+        let num_part: String = s.chars().take_while(|c| c.is_ascii_digit() || *c == ',').collect();
+        num_part.replace(',', "").parse::<i64>().ok()
+    });
+
+    let price_breaks: Vec<StockPriceBreak> = part
+        .price_breaks
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|pb| {
+            let qty = pb.quantity?;
+            let price_str = pb.price.as_deref()?;
+            // Price may be "$10.13", "1,234.56", or localized like "0,123 €"
+            let price = currency::parse_price(price_str)?;
+            Some(StockPriceBreak {
+                quantity: qty,
+                unit_price: price,
+            })
+        })
+        .collect();
+
+    let currency = part
+        .price_breaks
+        .as_deref()
+        .unwrap_or_default()
+        .first()
+        .and_then(|pb| pb.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+
+    StockInfo {
+        mpn,
+        manufacturer: part.manufacturer.clone(),
+        distributor: "mouser",
+        distributor_pn: part.mouser_part_number.clone(),
+        lifecycle_status: part.lifecycle_status.clone(),
+        stock,
+        lead_time: part.lead_time.clone(),
+        moq,
+        order_multiple,
+        currency,
+        price_breaks,
+        suggested_replacement: part.suggested_replacement.clone(),
+        jlcpcb_category: None,
+        datasheet_url: part.data_sheet_url.clone().filter(|u| !u.is_empty()),
+    }
+    .into_target_currency()
+}
+
+/// Keyword search returning normalized hits, using the `MOUSER_API_KEY` credential (environment or keyring).
+pub(crate) fn search_parts(keyword: &str, limit: usize) -> Result<Vec<SearchHit>, Error> {
+    let parts = search_by_keyword(&get_api_key(None)?, keyword, limit, 0)?;
+    Ok(parts
+        .into_iter()
+        .filter_map(|part| {
+            let stock = part.availability_in_stock.as_deref().and_then(|s| {
+                let num_part: String = s.chars().take_while(|c| c.is_ascii_digit() || *c == ',').collect();
+                num_part.replace(',', "").parse::<i64>().ok()
+            });
+            Some(SearchHit {
+                mpn: part.manufacturer_part_number.filter(|m| !m.is_empty())?,
+                manufacturer: part.manufacturer,
+                description: part.description,
+                distributor: "mouser",
+                distributor_pn: part.mouser_part_number,
+                stock,
+                datasheet_url: part.data_sheet_url.filter(|u| !u.is_empty()),
+            })
+        })
+        .collect())
+}
+
+/// One page of keyword search listings with their `ProductAttributes`, using
+/// the API key from the environment.
+pub(crate) fn search_with_parameters(keyword: &str) -> Result<Vec<(TableRow, PartParameters)>, Error> {
+    let parts = search_by_keyword(&get_api_key(None)?, keyword, MAX_RECORDS, 0)?;
+    Ok(parts
+        .iter()
+        .map(|part| {
+            let row = TableRow { info: stock_info(part, keyword), description: part.description.clone() };
+            let parameters = PartParameters {
+                manufacturer: part.manufacturer.clone(),
+                description: part.description.clone(),
+                parameters: part_parameters(part),
+            };
+            (row, parameters)
+        })
+        .collect())
+}
+
+/// Resolve the datasheet URL for a part using the `MOUSER_API_KEY` credential (environment or keyring).
+///
+/// Returns `Ok(None)` when the part exists but has no datasheet link.
+pub(crate) fn find_datasheet_url(part_number: &str) -> Result<Option<String>, Error> {
+    let api_key = get_api_key(None)?;
+    let parts = search_by_part_number(&api_key, part_number)?;
+    Ok(parts
+        .into_iter()
+        .find_map(|p| p.data_sheet_url.filter(|u| !u.is_empty())))
+}
+
+/// Resolve the product image URL for a part using the `MOUSER_API_KEY` credential (environment or keyring).
+pub(crate) fn find_photo_url(part_number: &str) -> Result<Option<String>, Error> {
+    let api_key = get_api_key(None)?;
+    let parts = search_by_part_number(&api_key, part_number)?;
+    Ok(parts
+        .into_iter()
+        .find_map(|p| p.image_path.filter(|u| !u.is_empty())))
+}
+
 fn format_number(n: i64) -> String {
     let s = n.to_string();
     let mut result = String::new();
@@ -577,7 +747,17 @@ fn format_number(n: i64) -> String {
     result.chars().rev().collect()
 }
 
-fn search_by_keyword(api_key: &str, keyword: &str, limit: usize, starting_record: usize) -> Result<Vec<Part>, String> {
+fn search_by_keyword(api_key: &str, keyword: &str, limit: usize, starting_record: usize) -> Result<Vec<Part>, Error> {
+    search_keyword_page(api_key, keyword, limit, starting_record).map(|(parts, _)| parts)
+}
+
+/// One page of keyword search results and the total number of matches.
+fn search_keyword_page(
+    api_key: &str,
+    keyword: &str,
+    limit: usize,
+    starting_record: usize,
+) -> Result<(Vec<Part>, usize), Error> {
     let url = format!("{}/search/keyword?apiKey={}", MOUSER_API_BASE, api_key);
 
     let request = KeywordSearchRequest {
@@ -590,12 +770,8 @@ fn search_by_keyword(api_key: &str, keyword: &str, limit: usize, starting_record
         },
     };
 
-    let response: SearchResponse = ureq::post(&url)
-        .set("Content-Type", "application/json")
-        .send_json(&request)
-        .map_err(|e| format!("API request failed: {}", e))?
-        .into_json()
-        .map_err(|e| format!("Failed to parse API response: {}", e))?;
+    let key = format!("keyword:{}:{}:{}", keyword, limit, starting_record);
+    let response: SearchResponse = post_cached(&url, &key, keyword, &request)?;
 
     if let Some(errors) = response.errors {
         if !errors.is_empty() {
@@ -604,18 +780,104 @@ fn search_by_keyword(api_key: &str, keyword: &str, limit: usize, starting_record
                 .filter_map(|e| e.message.clone())
                 .collect();
             if !error_msgs.is_empty() {
-                return Err(format!("API errors: {}", error_msgs.join(", ")));
+                return Err(api_error(&error_msgs));
             }
         }
     }
 
-    Ok(response
-        .search_results
-        .and_then(|r| r.parts)
-        .unwrap_or_default())
+    let results = response.search_results.unwrap_or(SearchResults { number_of_result: None, parts: None });
+    let total = results.number_of_result.and_then(|n| usize::try_from(n).ok()).unwrap_or(0);
+    Ok((results.parts.unwrap_or_default(), total))
+}
+
+/// Up to `max` results of a paged search from `start` on, waiting `interval`
+/// between requests. `fetch(offset, records)` returns one page and the total
+/// number of results. Being rate limited part way keeps the pages so far.
+fn collect_pages<T>(
+    start: usize,
+    max: usize,
+    interval: Duration,
+    mut fetch: impl FnMut(usize, usize) -> Result<(Vec<T>, usize), Error>,
+) -> Result<Vec<T>, Error> {
+    let mut items = Vec::new();
+    let mut offset = start;
+    while items.len() < max {
+        if offset > start {
+            std::thread::sleep(interval);
+        }
+        let (page, total) = match fetch(offset, (max - items.len()).min(MAX_RECORDS)) {
+            Ok(page) => page,
+            Err(e) if e.kind == ErrorKind::RateLimited && !items.is_empty() => {
+                eprintln!("[MOUSER] Rate limited after {} result(s), stopping there: {}", items.len(), e);
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        if page.is_empty() {
+            break;
+        }
+        offset += page.len();
+        items.extend(page);
+        if offset >= total {
+            break;
+        }
+        eprintln!("[MOUSER] Fetched {} of {} result(s)", items.len(), total.min(start + max) - start);
+    }
+    Ok(items)
+}
+
+/// POST a search request, recording the response for `--offline` replay.
+fn post_cached<T: serde::de::DeserializeOwned>(
+    url: &str,
+    key: &str,
+    part: &str,
+    request: &impl Serialize,
+) -> Result<T, Error> {
+    let value = crate::offline::cached_response("mouser", key, || {
+        let call = crate::audit::start("mouser", "POST", url, Some(part));
+        let body = call
+            .finish(ureq::post(url).set("Content-Type", "application/json").send_json(request))
+            .map_err(|e| Error::from_ureq("API request failed", *e))?;
+        serde_json::from_slice(&body).map_err(|e| Error::network(format!("Failed to parse API response: {}", e)))
+    })?;
+    serde_json::from_value(value).map_err(|e| Error::from(format!("Failed to parse API response: {}", e)))
+}
+
+/// Mouser reports bad keys and quota exhaustion as 200 responses with an `Errors` list.
+fn api_error(messages: &[String]) -> Error {
+    let text = messages.join(", ");
+    let lower = text.to_lowercase();
+    let kind = if lower.contains("api key") || lower.contains("unique identifier") {
+        ErrorKind::Auth
+    } else if lower.contains("exceeded") || lower.contains("too many") {
+        ErrorKind::RateLimited
+    } else {
+        ErrorKind::Other
+    };
+    Error::new(kind, format!("API errors: {}", text))
+}
+
+/// Confirm the configured API key is accepted with one uncached part number search.
+pub(crate) fn check_api_key() -> Result<(), Error> {
+    let api_key = get_api_key(None)?;
+    let url = format!("{}/search/partnumber?apiKey={}", MOUSER_API_BASE, api_key);
+    let request = PartNumberSearchRequest {
+        search_by_part_request: PartNumberSearchBody {
+            mouser_part_number: "LM358".to_string(),
+            part_search_options: None,
+        },
+    };
+    let call = crate::audit::start("mouser", "POST", &url, Some("LM358"));
+    let body = call
+        .finish(ureq::post(&url).timeout(std::time::Duration::from_secs(20)).send_json(&request))
+        .map_err(|e| Error::from_ureq("API request failed", *e))?;
+    let response: SearchResponse = serde_json::from_slice(&body)
+        .map_err(|e| Error::network(format!("Failed to parse API response: {}", e)))?;
+    let errors: Vec<String> = response.errors.unwrap_or_default().into_iter().filter_map(|e| e.message).collect();
+    if errors.is_empty() { Ok(()) } else { Err(api_error(&errors)) }
 }
 
-fn search_by_part_number(api_key: &str, part_number: &str) -> Result<Vec<Part>, String> {
+fn search_by_part_number(api_key: &str, part_number: &str) -> Result<Vec<Part>, Error> {
     let url = format!("{}/search/partnumber?apiKey={}", MOUSER_API_BASE, api_key);
 
     let request = PartNumberSearchRequest {
@@ -625,12 +887,8 @@ fn search_by_part_number(api_key: &str, part_number: &str) -> Result<Vec<Part>,
         },
     };
 
-    let response: SearchResponse = ureq::post(&url)
-        .set("Content-Type", "application/json")
-        .send_json(&request)
-        .map_err(|e| format!("API request failed: {}", e))?
-        .into_json()
-        .map_err(|e| format!("Failed to parse API response: {}", e))?;
+    let key = format!("partnumber:{}", part_number);
+    let response: SearchResponse = post_cached(&url, &key, part_number, &request)?;
 
     if let Some(errors) = response.errors {
         if !errors.is_empty() {
@@ -639,7 +897,7 @@ fn search_by_part_number(api_key: &str, part_number: &str) -> Result<Vec<Part>,
                 .filter_map(|e| e.message.clone())
                 .collect();
             if !error_msgs.is_empty() {
-                return Err(format!("API errors: {}", error_msgs.join(", ")));
+                return Err(api_error(&error_msgs));
             }
         }
     }
@@ -674,8 +932,8 @@ fn format_part_summary(part: &Part) -> String {
     if let Some(ref prices) = part.price_breaks {
         if let Some(first) = prices.first() {
             if let (Some(qty), Some(price)) = (&first.quantity, &first.price) {
-                let currency = first.currency.as_deref().unwrap_or("USD");
-                lines.push(format!("   Price: {} {} (qty {}+)", price, currency, qty));
+                let code = first.currency.as_deref().unwrap_or("USD");
+                lines.push(format!("   Price: {} (qty {}+)", display_price(price, code), qty));
             }
         }
     }
@@ -748,8 +1006,8 @@ fn print_part_details(part: &Part) {
             println!("-------");
             for pb in prices {
                 if let (Some(qty), Some(price)) = (&pb.quantity, &pb.price) {
-                    let currency = pb.currency.as_deref().unwrap_or("USD");
-                    println!("  {:>6}+ : {} {}", qty, price, currency);
+                    let code = pb.currency.as_deref().unwrap_or("USD");
+                    println!("  {:>6}+ : {}", qty, display_price(price, code));
                 }
             }
         }
@@ -771,3 +1029,59 @@ fn print_part_details(part: &Part) {
         println!("Datasheet: Not available");
     }
 }
+
+/// Show a Mouser price string in the `--currency` target, falling back to the raw text.
+fn display_price(price: &str, code: &str) -> String {
+    match currency::parse_price(price) {
+        Some(amount) => currency::format_price(amount, code, 4),
+        None => format!("{} {}", price, code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_pages() {
+        let results: Vec<usize> = (0..120).collect();
+        let requests = std::cell::RefCell::new(Vec::new());
+        let fetch = |offset: usize, records: usize| {
+            requests.borrow_mut().push((offset, records));
+            let end = (offset + records).min(results.len());
+            Ok((results[offset.min(end)..end].to_vec(), results.len()))
+        };
+        let all = collect_pages(0, 500, Duration::ZERO, fetch).unwrap();
+        assert_eq!(all, results);
+        assert_eq!(requests.take(), [(0, 50), (50, 50), (100, 50)]);
+
+        let capped = collect_pages(10, 60, Duration::ZERO, fetch).unwrap();
+        assert_eq!(capped, (10..70).collect::<Vec<_>>());
+        assert_eq!(requests.take(), [(10, 50), (60, 10)]);
+
+        let mut calls = 0;
+        let limited = collect_pages(0, 500, Duration::ZERO, |offset, records| {
+            calls += 1;
+            if calls > 1 {
+                return Err(Error::new(ErrorKind::RateLimited, "Too many requests"));
+            }
+            Ok(((offset..offset + records).collect::<Vec<_>>(), 1000))
+        })
+        .unwrap();
+        assert_eq!(limited.len(), 50);
+    }
+
+    #[test]
+    fn test_exact_part() {
+        let parts: Vec<Part> = serde_json::from_value(serde_json::json!([
+            { "ManufacturerPartNumber": "LM358DR2G", "MouserPartNumber": "863-LM358DR2G", "LifecycleStatus": "Obsolete" },
+            { "ManufacturerPartNumber": "LM358DR", "MouserPartNumber": "595-LM358DR" }
+        ]))
+        .unwrap();
+        assert_eq!(exact_part(&parts, "lm358dr").unwrap().mouser_part_number.as_deref(), Some("595-LM358DR"));
+        assert_eq!(exact_part(&parts, "863-LM358DR2G").unwrap().manufacturer_part_number.as_deref(), Some("LM358DR2G"));
+        let err = exact_part(&parts[..1], "LM358DR").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
+        assert!(err.to_string().contains("LM358DR2G"));
+    }
+}
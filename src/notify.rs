@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Completion notifications for long-running commands (`--notify <URL>`).
+//!
+//! When `jobs run`, `bom datasheets`, or `bom risk` finishes or fails, a JSON
+//! summary is POSTed to each target. Its `text` field makes it a valid Slack
+//! (or Mattermost) incoming-webhook message; other webhooks can read the
+//! structured fields next to it. Targets also come from `DATASHEET_NOTIFY` or
+//! `notify` in the config file.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+#[derive(Serialize, Debug)]
+struct Payload<'a> {
+    text: String,
+    command: &'a str,
+    /// "finished" or "failed"
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+    duration_secs: u64,
+}
+
+/// Post the outcome of `command` (its summary line, or its error) to every
+/// target. A failed notification is reported but does not change the outcome.
+pub fn send(targets: &[String], command: &str, started: Instant, outcome: &Result<String, String>) {
+    if targets.is_empty() {
+        return;
+    }
+    if let Err(e) = crate::offline::ensure_online("Sending notifications") {
+        eprintln!("[NOTIFY] {}", e);
+        return;
+    }
+    let payload = payload(command, started.elapsed(), outcome);
+    for target in targets {
+        let result = ureq::post(target)
+            .timeout(Duration::from_secs(15))
+            .send_json(&payload);
+        if let Err(e) = result {
+            eprintln!("[NOTIFY] Failed to notify {}: {}", target, e);
+        }
+    }
+}
+
+fn payload<'a>(command: &'a str, elapsed: Duration, outcome: &'a Result<String, String>) -> Payload<'a> {
+    let took = format_duration(elapsed);
+    let (status, text) = match outcome {
+        Ok(summary) => ("finished", format!("datasheet {} finished after {}: {}", command, took, summary)),
+        Err(error) => ("failed", format!("datasheet {} failed after {}: {}", command, took, error)),
+    };
+    Payload {
+        text,
+        command,
+        status,
+        summary: outcome.as_ref().ok().map(String::as_str),
+        error: outcome.as_ref().err().map(String::as_str),
+        duration_secs: elapsed.as_secs(),
+    }
+}
+
+/// "45s", "12m 5s", "3h 20m".
+fn format_duration(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, _) => format!("{}h {}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        let outcome = Ok("40 part(s): 38 downloaded, 2 not found".to_string());
+        let json = serde_json::to_value(payload("bom datasheets", Duration::from_secs(3725), &outcome)).unwrap();
+        assert_eq!(json["status"], "finished");
+        assert_eq!(json["text"], "datasheet bom datasheets finished after 1h 2m: 40 part(s): 38 downloaded, 2 not found");
+        assert_eq!(json["duration_secs"], 3725);
+        assert!(json.get("error").is_none());
+
+        let outcome = Err("No part numbers found in BOM: bom.csv".to_string());
+        let json = serde_json::to_value(payload("bom risk", Duration::from_secs(42), &outcome)).unwrap();
+        assert_eq!(json["status"], "failed");
+        assert_eq!(json["text"], "datasheet bom risk failed after 42s: No part numbers found in BOM: bom.csv");
+    }
+}
@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Offline mode and the caches that make it useful.
+//!
+//! With `--offline` (or `DATASHEET_OFFLINE=1`) nothing touches the network:
+//! - extraction results are served from the result cache, keyed by the PDF,
+//!   preprocessing, model, prompt, and schema
+//! - datasheets must already be in the local library
+//! - distributor lookups (Mouser, DigiKey, JLCPCB) are served from the
+//!   response cache
+//!
+//! Both caches are filled on every online run, so running a workflow once with
+//! network access is enough to replay it offline. Anything missing fails fast
+//! with a message naming what was needed.
+//!
+//! The result cache also deduplicates concurrent work: identical extractions
+//! running at the same time (threads or parallel `jobs run` workers) take a
+//! lock on their result key, and all but the first reuse its result.
+
+use fs4::fs_std::FileExt;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable offline mode (called once from `main`).
+pub fn set(enabled: bool) {
+    OFFLINE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Fail with a clear message if offline mode forbids `what`.
+pub fn ensure_online(what: &str) -> Result<(), String> {
+    if is_enabled() {
+        Err(format!("{} needs network access, but --offline is set", what))
+    } else {
+        Ok(())
+    }
+}
+
+/// Serve a distributor response from the cache when offline; otherwise fetch
+/// it and record it for later offline runs.
+///
+/// `key` identifies the request (endpoint plus parameters, never credentials).
+pub fn cached_response<E: From<crate::error::Error>>(
+    source: &str,
+    key: &str,
+    fetch: impl FnOnce() -> Result<Value, E>,
+) -> Result<Value, E> {
+    let path = cache_path("responses", source, key);
+    if is_enabled() {
+        return fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .ok_or_else(|| {
+                crate::error::Error::not_found(format!("--offline: no cached {} response for {}", source, key)).into()
+            });
+    }
+
+    let value = fetch()?;
+    write_cache(&path, &value);
+    Ok(value)
+}
+
+/// Previously stored extraction result for `key`, if any.
+pub fn cached_result(key: &str) -> Option<Value> {
+    let content = fs::read_to_string(cache_path("results", "extract", key)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Record an extraction result so it can be replayed offline.
+pub fn store_result(key: &str, value: &Value) {
+    write_cache(&cache_path("results", "extract", key), value);
+}
+
+/// Outcome of [`claim_result`].
+pub enum Claim {
+    /// No identical extraction was running; run it while holding the claim,
+    /// which is released on drop. `None` if the lock could not be taken.
+    Run(Option<fs::File>),
+    /// An identical extraction finished while this one waited; its result.
+    Reuse(Value),
+}
+
+/// Idempotency key for an extraction: a short digest of its result key.
+pub fn request_id(key: &str) -> String {
+    hex_digest(key)[..16].to_string()
+}
+
+/// Claim the extraction identified by `key`. If another thread or process is
+/// already running it, wait for that to finish and reuse the result it stored,
+/// so the same request is never paid for twice at once. Results stored by
+/// earlier runs are not reused; a waiter whose predecessor failed runs itself.
+pub fn claim_result(key: &str) -> Claim {
+    let result = cache_path("results", "extract", key);
+    let lock = cache_path("results", "inflight", key).with_extension("lock");
+    claim_at(&lock, &result, key)
+}
+
+fn claim_at(lock_path: &Path, result_path: &Path, key: &str) -> Claim {
+    if let Some(dir) = lock_path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let Ok(lock) = fs::OpenOptions::new().create(true).truncate(false).write(true).open(lock_path) else {
+        return Claim::Run(None);
+    };
+    if let Ok(true) = FileExt::try_lock_exclusive(&lock) {
+        return Claim::Run(Some(lock));
+    }
+
+    eprintln!("[EXTRACT] Identical request {} already in progress; waiting for it", request_id(key));
+    let started = SystemTime::now();
+    if FileExt::lock_exclusive(&lock).is_err() {
+        return Claim::Run(None);
+    }
+    let fresh = fs::metadata(result_path)
+        .and_then(|m| m.modified())
+        .is_ok_and(|modified| modified >= started);
+    let reused = fs::read_to_string(result_path)
+        .ok()
+        .filter(|_| fresh)
+        .and_then(|content| serde_json::from_str(&content).ok());
+    match reused {
+        Some(value) => {
+            eprintln!("[EXTRACT] Reusing the result of request {}", request_id(key));
+            Claim::Reuse(value)
+        }
+        None => Claim::Run(Some(lock)),
+    }
+}
+
+fn cache_path(kind: &str, source: &str, key: &str) -> PathBuf {
+    let digest = hex_digest(key);
+    crate::file_cache::cache_root()
+        .join(kind)
+        .join(source)
+        .join(format!("{}.json", &digest[..32]))
+}
+
+/// Best-effort write; a failed cache write should never fail the command.
+fn write_cache(path: &std::path::Path, value: &Value) {
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(json) = serde_json::to_string(value) {
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        if fs::write(&tmp, json).is_ok() {
+            let _ = fs::rename(&tmp, path);
+        }
+    }
+}
+
+fn hex_digest(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_path_is_stable_per_key() {
+        let a = cache_path("responses", "mouser", "partnumber:LM358");
+        assert_eq!(a, cache_path("responses", "mouser", "partnumber:LM358"));
+        assert_ne!(a, cache_path("responses", "mouser", "partnumber:LM324"));
+        assert!(a.parent().unwrap().ends_with("responses/mouser"));
+    }
+
+    #[test]
+    fn test_claim_waits_for_identical_request() {
+        let dir = std::env::temp_dir().join(format!("datasheet-claim-test-{}", std::process::id()));
+        let lock = dir.join("k.lock");
+        let result = dir.join("k.json");
+        fs::create_dir_all(&dir).unwrap();
+        // A result left by an earlier run is not reused
+        write_cache(&result, &serde_json::json!({"run": 0}));
+
+        let Claim::Run(Some(first)) = claim_at(&lock, &result, "k") else {
+            panic!("first claim should run");
+        };
+        let waiter = std::thread::spawn({
+            let (lock, result) = (lock.clone(), result.clone());
+            move || claim_at(&lock, &result, "k")
+        });
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        write_cache(&result, &serde_json::json!({"run": 1}));
+        drop(first);
+        match waiter.join().unwrap() {
+            Claim::Reuse(value) => assert_eq!(value["run"], 1),
+            Claim::Run(_) => panic!("waiter should reuse the finished result"),
+        }
+
+        let Claim::Run(Some(_)) = claim_at(&lock, &result, "k") else {
+            panic!("claim with nothing in flight should run");
+        };
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -99,20 +99,30 @@ pub fn location_schema() -> serde_json::Value {
 
 // ── LLM detection ─────────────────────────────────────────────────────
 
+/// LLM settings for [`detect_pages`].
+pub struct DetectOptions<'a> {
+    pub api_key: &'a str,
+    pub base_url: &'a Option<String>,
+    pub provider: LlmProvider,
+    pub model: &'a str,
+    pub no_cache: bool,
+    /// Log line prefix, e.g. "EXTRACT-PAGES"
+    pub log_prefix: &'a str,
+}
+
 /// Detect pages/regions in a PDF using a user-supplied prompt.
 ///
 /// Handles PDF splitting for large documents automatically.
-pub fn detect_pages(
-    pdf: &Path,
-    prompt: &str,
-    no_cache: bool,
-    api_key: &str,
-    base_url: &Option<String>,
-    provider: LlmProvider,
-    model: &str,
-    log_prefix: &str,
-) -> Result<Vec<PageLocation>> {
-    let split_result = pdf_split::split_if_needed(pdf)?;
+pub fn detect_pages(pdf: &Path, prompt: &str, options: &DetectOptions) -> Result<Vec<PageLocation>> {
+    let DetectOptions {
+        api_key,
+        base_url,
+        provider,
+        model,
+        no_cache,
+        log_prefix,
+    } = *options;
+    let split_result = pdf_split::split_if_needed(pdf, 1)?;
 
     let client = build_client(provider, api_key.to_string(), base_url.clone())?;
 
@@ -162,19 +172,33 @@ fn call_detect(
 
 // ── Rendering ─────────────────────────────────────────────────────────
 
+/// Rendering settings for [`render_and_crop`].
+pub struct RenderOptions<'a> {
+    /// Padding around each region, in 0-1000 normalized units
+    pub padding: u32,
+    pub dpi: u32,
+    /// Save whole pages instead of cropping to the detected regions
+    pub whole_page: bool,
+    /// Log line prefix, e.g. "EXTRACT-PAGES"
+    pub log_prefix: &'a str,
+}
+
 /// Render and crop detected page regions to PNG files.
 ///
 /// Returns the number of PNG files saved and a list of (label, filename) pairs.
 pub fn render_and_crop(
     pdf_path: &Path,
     locations: &[PageLocation],
-    padding: u32,
-    dpi: u32,
     out_dir: &Path,
     pdf_stem: &str,
-    whole_page: bool,
-    log_prefix: &str,
+    options: &RenderOptions,
 ) -> Result<(u32, Vec<(String, PathBuf)>)> {
+    let RenderOptions {
+        padding,
+        dpi,
+        whole_page,
+        log_prefix,
+    } = *options;
     let doc = mupdf::document::Document::open(
         pdf_path
             .to_str()
@@ -218,8 +242,8 @@ pub fn render_and_crop(
             .to_pixmap(&ctm, &mupdf::Colorspace::device_rgb(), 0.0, false)
             .with_context(|| format!("rendering page {}", page_num))?;
 
-        let w = pixmap.width() as u32;
-        let h = pixmap.height() as u32;
+        let w = pixmap.width();
+        let h = pixmap.height();
         let n = pixmap.n() as u32;
         let samples = pixmap.samples().to_vec();
 
@@ -272,7 +296,7 @@ pub fn render_and_crop(
 // ── Helpers ───────────────────────────────────────────────────────────
 
 pub fn sanitize_label(label: &str) -> String {
-    label
+    let label: String = label
         .chars()
         .map(|c| {
             if c.is_alphanumeric() || c == '-' || c == '_' {
@@ -281,7 +305,8 @@ pub fn sanitize_label(label: &str) -> String {
                 '_'
             }
         })
-        .collect()
+        .collect();
+    crate::paths::sanitize_filename(&label)
 }
 
 pub fn make_attachment(
@@ -292,19 +317,25 @@ pub fn make_attachment(
 ) -> Result<AttachmentSource> {
     if no_cache {
         let data = std::fs::read(pdf).with_context(|| format!("reading {}", pdf.display()))?;
-        Ok(AttachmentSource::Inline(crate::llm::Attachment {
-            mime_type: "application/pdf".to_string(),
-            data,
-        }))
-    } else {
-        let mut cache = FileCache::new(api_key.to_string(), base_url.clone())
-            .context("initializing file cache")?;
-        let cached = cache
-            .get_or_upload(pdf)
-            .context("uploading PDF to Gemini")?;
-        Ok(AttachmentSource::FileUri(FileReference {
-            mime_type: "application/pdf".to_string(),
-            file_uri: cached.uri,
-        }))
+        if data.len() <= crate::llm::INLINE_MAX_BYTES {
+            return Ok(AttachmentSource::Inline(crate::llm::Attachment {
+                mime_type: "application/pdf".to_string(),
+                data,
+            }));
+        }
+        eprintln!(
+            "[UPLOAD] {} is {:.1} MB, too large to send inline; using the File API",
+            pdf.display(),
+            data.len() as f64 / (1024.0 * 1024.0)
+        );
     }
+    let mut cache = FileCache::new(api_key.to_string(), base_url.clone())
+        .context("initializing file cache")?;
+    let cached = cache
+        .get_or_upload(pdf)
+        .context("uploading PDF to Gemini")?;
+    Ok(AttachmentSource::FileUri(FileReference {
+        mime_type: "application/pdf".to_string(),
+        file_uri: cached.uri,
+    }))
 }
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Distributor parametric data in one vocabulary.
+//!
+//! DigiKey `Parameters` and Mouser `ProductAttributes` name the same property
+//! differently ("Voltage - Rated" vs "Voltage Rating DC") and format values
+//! differently ("±10%" vs "10 %", one "-40°C ~ 125°C (TJ)" range vs separate
+//! minimum and maximum). `canonical` maps both onto the attribute names the
+//! parts database already uses, so queries and comparisons work whichever
+//! distributor the data came from.
+
+use serde_json::{Map, Value};
+
+/// Description and parametric attributes of a single part, as the distributor names them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PartParameters {
+    pub manufacturer: Option<String>,
+    pub description: Option<String>,
+    pub parameters: Vec<(String, String)>,
+}
+
+/// Canonical attribute names and the distributor parameter names read for
+/// them (lowercase), most specific first.
+const ALIASES: &[(&str, &[&str])] = &[
+    ("package", &["package / case", "package", "supplier device package"]),
+    ("mounting", &["mounting type", "mounting style"]),
+    ("tolerance", &["tolerance"]),
+    (
+        "voltage_rating",
+        &["voltage - rated", "voltage rating - dc", "voltage rating dc", "voltage - rated dc", "voltage rating"],
+    ),
+    ("temperature_min", &["minimum operating temperature"]),
+    ("temperature_max", &["maximum operating temperature"]),
+];
+
+/// Parameters holding a whole temperature range, used when there is no
+/// separate minimum or maximum.
+const TEMPERATURE_RANGES: &[&str] = &["operating temperature", "operating temperature range"];
+
+/// The canonical attributes found in `parameters`, in vocabulary order.
+pub(crate) fn canonical(parameters: &[(String, String)]) -> Vec<(&'static str, String)> {
+    let lookup = |name: &str| {
+        parameters
+            .iter()
+            .find(|(n, v)| n.trim().eq_ignore_ascii_case(name) && !matches!(v.trim(), "" | "-"))
+            .map(|(_, v)| v.as_str())
+    };
+    let range = TEMPERATURE_RANGES.iter().find_map(|name| lookup(name)).and_then(temperature_range);
+
+    let mut attrs = Vec::new();
+    for &(canonical, aliases) in ALIASES {
+        let value = aliases.iter().filter_map(|alias| lookup(alias)).find_map(|value| match canonical {
+            "package" => package(value),
+            "mounting" => Some(mounting(value)),
+            "tolerance" => Some(tolerance(value)),
+            "voltage_rating" => voltage(value),
+            _ => temperature(value),
+        });
+        let value = value.or_else(|| match canonical {
+            "temperature_min" => range.as_ref().map(|(min, _)| min.clone()),
+            "temperature_max" => range.as_ref().map(|(_, max)| max.clone()),
+            _ => None,
+        });
+        if let Some(value) = value {
+            attrs.push((canonical, value));
+        }
+    }
+    attrs
+}
+
+/// `part --json` output with the canonical attributes added under `CanonicalAttributes`.
+pub(crate) fn with_canonical(mut part: Value, parameters: &[(String, String)]) -> Value {
+    let attrs: Map<String, Value> = canonical(parameters)
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), Value::String(value)))
+        .collect();
+    if let Some(object) = part.as_object_mut() {
+        object.insert("CanonicalAttributes".to_string(), Value::Object(attrs));
+    }
+    part
+}
+
+/// "8-PowerSOIC (0.154\", 3.90mm Width)" → "8-PowerSOIC".
+fn package(value: &str) -> Option<String> {
+    let name = value.split(" (").next().unwrap_or(value).trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn mounting(value: &str) -> String {
+    let lower = value.to_lowercase();
+    if ["surface", "smd", "smt"].iter().any(|s| lower.contains(s)) {
+        "Surface Mount".to_string()
+    } else if ["through", "tht"].iter().any(|s| lower.contains(s)) {
+        "Through Hole".to_string()
+    } else {
+        value.trim().to_string()
+    }
+}
+
+/// "10 %" and "+/-10%" → "±10%"; asymmetric tolerances are kept as written.
+fn tolerance(value: &str) -> String {
+    let compact: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    let bare = compact
+        .strip_prefix('±')
+        .or_else(|| compact.strip_prefix("+/-"))
+        .or_else(|| compact.strip_prefix("+-"))
+        .unwrap_or(&compact);
+    if bare.starts_with(|c: char| c.is_ascii_digit()) && !bare.contains(',') {
+        format!("±{}", bare)
+    } else {
+        compact
+    }
+}
+
+/// "50 VDC" → "50V".
+fn voltage(value: &str) -> Option<String> {
+    let compact: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    let compact = compact.trim_end_matches("DC").trim_end_matches("AC");
+    (compact.starts_with(|c: char| c.is_ascii_digit()) && compact.ends_with('V')).then(|| compact.to_string())
+}
+
+/// "- 40 C", "+125°C", "150°C (TJ)" → "-40°C", "125°C", "150°C".
+fn temperature(value: &str) -> Option<String> {
+    let value = value.split('(').next().unwrap_or(value);
+    let compact: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    let number = compact.trim_end_matches('C').trim_end_matches(['°', 'º']);
+    let celsius: f64 = number.parse().ok()?;
+    Some(format!("{}°C", celsius))
+}
+
+/// "-40°C ~ 125°C (TJ)" or "- 40 C to + 125 C" → ("-40°C", "125°C").
+fn temperature_range(value: &str) -> Option<(String, String)> {
+    let (min, max) = value.split_once('~').or_else(|| value.split_once(" to "))?;
+    Some((temperature(min)?, temperature(max)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_canonical_matches_across_distributors() {
+        let digikey = params(&[
+            ("Capacitance", "10 µF"),
+            ("Tolerance", "±10%"),
+            ("Voltage - Rated", "50V"),
+            ("Operating Temperature", "-55°C ~ 125°C"),
+            ("Mounting Type", "Surface Mount, MLCC"),
+            ("Package / Case", "1206 (3216 Metric)"),
+        ]);
+        let mouser = params(&[
+            ("Packaging", "Reel"),
+            ("Package / Case", "1206"),
+            ("Tolerance", "10 %"),
+            ("Voltage Rating DC", "50 VDC"),
+            ("Minimum Operating Temperature", "- 55 C"),
+            ("Maximum Operating Temperature", "+ 125 C"),
+            ("Mounting Style", "SMD/SMT"),
+        ]);
+        let expected: Vec<(&str, String)> = [
+            ("package", "1206"),
+            ("mounting", "Surface Mount"),
+            ("tolerance", "±10%"),
+            ("voltage_rating", "50V"),
+            ("temperature_min", "-55°C"),
+            ("temperature_max", "125°C"),
+        ]
+        .iter()
+        .map(|(n, v)| (*n, v.to_string()))
+        .collect();
+        assert_eq!(canonical(&digikey), expected);
+        assert_eq!(canonical(&mouser), expected);
+        assert_eq!(tolerance("-20%, +80%"), "-20%,+80%");
+        assert_eq!(canonical(&params(&[("Operating Temperature", "-")])), []);
+    }
+}
@@ -0,0 +1,225 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! End-to-end pipeline: find a part's datasheet, cache it, and extract.
+//!
+//! 1. Reuse the datasheet from the local library if it is already there
+//! 2. Otherwise query the sources in order and download the first PDF found,
+//!    adding it to the library so later runs skip the download
+//! 3. Run every requested extraction task and merge the results into one JSON
+//!    object keyed by task name
+
+use crate::bom::Source;
+use crate::paths::sanitize_filename;
+use crate::download::{self, DownloadOptions};
+use crate::error::Error;
+use crate::extract::{self, ExtractArgs, ExtractTask, __DEFAULT__};
+use crate::library::Library;
+use crate::prompts::PromptLang;
+use crate::llm::LlmProvider;
+use anyhow::{Context, Result, anyhow};
+use clap::{Args, ValueEnum};
+use serde_json::{Map, Value, json};
+use std::path::{Path, PathBuf};
+
+/// Sources tried for `--source auto`, in order.
+const AUTO_SOURCES: &[Source] = &[Source::Mouser, Source::Digikey, Source::Jlcpcb, Source::Manufacturer];
+
+#[derive(Args, Debug)]
+pub struct PartArgs {
+    /// Manufacturer part number
+    pub mpn: String,
+
+    /// Extraction tasks to run (comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "pinout")]
+    pub tasks: Vec<ExtractTask>,
+
+    /// Datasheet sources to try, in order (comma-separated), or "auto" for all
+    #[arg(long, value_delimiter = ',', default_value = "auto")]
+    pub source: Vec<String>,
+
+    /// Manufacturer name (helps the manufacturer-site resolver and library layout)
+    #[arg(long)]
+    pub manufacturer: Option<String>,
+
+    /// Download again even if the library already has a datasheet for this part
+    #[arg(long)]
+    pub refresh: bool,
+
+    /// LLM provider (always Gemini)
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
+    pub provider: LlmProvider,
+
+    /// Model name (default: each task's default model)
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
+    pub model: String,
+
+    /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Optional base URL override for Gemini API
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Output file (defaults to stdout)
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+
+    /// Show formatted (pretty-printed) JSON output
+    #[arg(long, short = 'f', visible_alias = "pretty", env = "DATASHEET_FORMATTED")]
+    pub formatted: bool,
+
+    /// Disable file caching (re-upload PDF every request)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Language of the datasheet: instruct the model in it (zh-CN, ja, de; default en)
+    #[arg(long, value_enum, default_value_t = PromptLang::En, env = extract::ENV_VAR_PROMPT_LANG)]
+    pub prompt_lang: PromptLang,
+
+    /// Delete the uploaded datasheet from Gemini (and the local cache) when done
+    #[arg(long)]
+    pub cleanup: bool,
+}
+
+pub fn run(args: &PartArgs) -> Result<()> {
+    if args.tasks.iter().any(|t| matches!(t, ExtractTask::Custom)) {
+        return Err(anyhow!(
+            "The 'custom' task needs a prompt; use 'datasheet extract custom' instead"
+        ));
+    }
+    let sources = parse_sources(&args.source)?;
+    let library = Library::open(None).map_err(|e| anyhow!(e))?;
+
+    let (pdf, datasheet) = match cached_datasheet(&library, &args.mpn).filter(|_| !args.refresh) {
+        Some(pdf) => {
+            eprintln!("[PART] Using library datasheet {}", pdf.display());
+            let info = json!({ "file": pdf, "source": "library" });
+            (pdf, info)
+        }
+        None if crate::offline::is_enabled() => {
+            return Err(Error::not_found(format!(
+                "--offline: no datasheet for {} in the library; add it with 'datasheet library add' first",
+                args.mpn
+            ))
+            .into());
+        }
+        None => fetch_datasheet(&library, args, &sources)?,
+    };
+
+    let mut results = Map::new();
+    results.insert("mpn".to_string(), json!(args.mpn));
+    results.insert("datasheet".to_string(), datasheet);
+    let extracted = run_tasks(args, &pdf);
+    if args.cleanup {
+        extract::cleanup_uploads(args.provider, args.api_key.clone(), args.base_url.clone());
+    }
+    results.extend(extracted?);
+
+    let merged = Value::Object(results);
+    extract::write_output(&merged, args.out.as_deref(), args.formatted)
+}
+
+/// Run every requested extraction task, keyed by task name.
+fn run_tasks(args: &PartArgs, pdf: &Path) -> Result<Map<String, Value>> {
+    let mut results = Map::new();
+    for &task in &args.tasks {
+        let name = task.prompt().name;
+        eprintln!("[PART] Extracting {} from {}", name, pdf.display());
+        let extract_args = ExtractArgs {
+            provider: args.provider,
+            model: args.model.clone(),
+            api_key: args.api_key.clone(),
+            base_url: args.base_url.clone(),
+            formatted: args.formatted,
+            no_cache: args.no_cache,
+            prompt_lang: args.prompt_lang,
+            ..ExtractArgs::for_task(task, pdf.to_path_buf())
+        };
+        let value = extract::extract_json(&extract_args)
+            .with_context(|| format!("running {} extraction", name))?;
+        results.insert(name.to_string(), value);
+    }
+    Ok(results)
+}
+
+/// Expand "auto" and source names into the ordered list of sources to try.
+fn parse_sources(names: &[String]) -> Result<Vec<Source>> {
+    let mut sources = Vec::new();
+    for name in names {
+        let expanded = if name.eq_ignore_ascii_case("auto") {
+            AUTO_SOURCES.to_vec()
+        } else {
+            vec![Source::from_str(name, true).map_err(|_| anyhow!("Unknown source '{}'", name))?]
+        };
+        for source in expanded {
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// Library datasheet for an exact MPN match, if one exists on disk.
+fn cached_datasheet(library: &Library, mpn: &str) -> Option<PathBuf> {
+    let entry = library
+        .find(mpn)
+        .ok()?
+        .into_iter()
+        .find(|e| e.mpn.eq_ignore_ascii_case(mpn))?;
+    Some(library.pdf_path(&entry)).filter(|p| p.exists())
+}
+
+/// Download the datasheet from the first source that has one and add it to the library.
+fn fetch_datasheet(library: &Library, args: &PartArgs, sources: &[Source]) -> Result<(PathBuf, Value)> {
+    let scratch = std::env::temp_dir().join(format!("{}.pdf.download", sanitize_filename(&args.mpn)));
+    let options = DownloadOptions::default();
+    let mut errors = Vec::new();
+
+    for &source in sources {
+        let url = match source.find_datasheet_url(&args.mpn, args.manufacturer.as_deref()) {
+            Ok(Some(url)) => url,
+            Ok(None) => continue,
+            Err(e) => {
+                errors.push(format!("{}: {}", source.name(), e));
+                continue;
+            }
+        };
+        eprintln!("[PART] Downloading datasheet from {}: {}", source.name(), url);
+        match download::fetch_pdf(&url, &scratch, &options, &|_, _| {}) {
+            Ok(data) => {
+                let entry = library
+                    .add_bytes(&args.mpn, args.manufacturer.as_deref(), &data, Some(url.clone()), None)
+                    .map_err(|e| anyhow!("Failed to add {} to library: {}", args.mpn, e))?;
+                let pdf = library.pdf_path(&entry);
+                let info = json!({ "file": pdf, "source": source.name(), "url": url });
+                return Ok((pdf, info));
+            }
+            Err(e) => errors.push(format!("{}: {}", source.name(), e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Err(Error::not_found(format!("No datasheet found for {}", args.mpn)).into())
+    } else {
+        Err(Error::not_found(format!("No datasheet found for {}:\n  {}", args.mpn, errors.join("\n  "))).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sources() {
+        let names = |s: &[&str]| s.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_sources(&names(&["auto"])).unwrap(), AUTO_SOURCES);
+        assert_eq!(
+            parse_sources(&names(&["digikey", "auto"])).unwrap(),
+            [Source::Digikey, Source::Mouser, Source::Jlcpcb, Source::Manufacturer]
+        );
+        assert!(parse_sources(&names(&["octopart"])).is_err());
+    }
+}
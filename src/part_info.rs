@@ -0,0 +1,328 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! `datasheet part-info`: one part as every distributor lists it.
+//!
+//! Looks the MPN up at each distributor in parallel (by default Mouser and
+//! DigiKey when their credentials are set, and JLCPCB) and merges the answers:
+//! stock per distributor, the cheapest offer at each price break quantity,
+//! whether the lifecycle statuses agree, and every datasheet URL found.
+//! Distributors that fail are reported but do not fail the command unless
+//! none of them found the part.
+
+use anyhow::{Result, anyhow};
+use clap::Args;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::thread;
+
+use crate::auth;
+use crate::bom::Source;
+use crate::error::Error;
+use crate::stock::{self, StockInfo};
+
+#[derive(Args, Debug)]
+pub struct PartInfoArgs {
+    /// Manufacturer part number
+    pub mpn: String,
+
+    /// Sources to query (default: mouser and digikey when configured, and jlcpcb);
+    /// manufacturer adds the manufacturer's own datasheet URL
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub source: Vec<Source>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct PartInfo {
+    mpn: String,
+    manufacturer: Option<String>,
+    lifecycle: Lifecycle,
+    offers: Vec<StockInfo>,
+    best_prices: Vec<BestPrice>,
+    datasheets: Vec<Datasheet>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<SourceError>,
+}
+
+/// Lifecycle statuses reduced to a common scale.
+#[derive(Serialize, Debug)]
+struct Lifecycle {
+    /// `active`, `nrnd`, `eol`, or `obsolete`; the most severe one when the
+    /// distributors disagree, `None` when none reported a status
+    status: Option<&'static str>,
+    agreed: bool,
+    /// Each distributor's status as it reported it
+    reported: BTreeMap<&'static str, String>,
+}
+
+/// Cheapest offer at one price break quantity.
+#[derive(Serialize, Debug, PartialEq)]
+struct BestPrice {
+    quantity: i32,
+    unit_price: f64,
+    currency: String,
+    distributor: &'static str,
+}
+
+#[derive(Serialize, Debug)]
+struct Datasheet {
+    url: String,
+    sources: Vec<&'static str>,
+}
+
+#[derive(Serialize, Debug)]
+struct SourceError {
+    source: &'static str,
+    error: String,
+}
+
+pub fn run(args: &PartInfoArgs) -> Result<()> {
+    let sources = if args.source.is_empty() { configured_sources() } else { args.source.clone() };
+    let distributors: Vec<Source> = sources.iter().copied().filter(|&s| s != Source::Manufacturer).collect();
+
+    let results: Vec<(Source, Result<StockInfo, String>)> = thread::scope(|scope| {
+        let handles: Vec<_> = distributors
+            .iter()
+            .map(|&source| (source, scope.spawn(move || source.lookup_stock(&args.mpn))))
+            .collect();
+        handles
+            .into_iter()
+            .map(|(source, handle)| {
+                (source, handle.join().unwrap_or_else(|_| Err("lookup panicked".to_string())))
+            })
+            .collect()
+    });
+
+    let mut offers = Vec::new();
+    let mut errors = Vec::new();
+    for (source, result) in results {
+        match result {
+            Ok(info) => offers.push(info),
+            Err(error) => errors.push(SourceError { source: source.name(), error }),
+        }
+    }
+    if offers.is_empty() {
+        let detail: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.source, e.error)).collect();
+        return Err(Error::not_found(format!("{} not found at any distributor ({})", args.mpn, detail.join("; "))).into());
+    }
+
+    let manufacturer = offers.iter().find_map(|o| o.manufacturer.clone());
+    let mut datasheets: Vec<Datasheet> = Vec::new();
+    let mut add_datasheet = |url: &str, source: &'static str| {
+        let url = crate::download::normalize_url(url);
+        match datasheets.iter_mut().find(|d| d.url == url) {
+            Some(datasheet) => datasheet.sources.push(source),
+            None => datasheets.push(Datasheet { url, sources: vec![source] }),
+        }
+    };
+    for offer in &offers {
+        if let Some(url) = &offer.datasheet_url {
+            add_datasheet(url, offer.distributor);
+        }
+    }
+    if sources.contains(&Source::Manufacturer) {
+        match Source::Manufacturer.find_datasheet_url(&args.mpn, manufacturer.as_deref()) {
+            Ok(Some(url)) => add_datasheet(&url, Source::Manufacturer.name()),
+            Ok(None) => {}
+            Err(error) => errors.push(SourceError { source: Source::Manufacturer.name(), error }),
+        }
+    }
+
+    crate::history::record(offers.iter().cloned());
+
+    let info = PartInfo {
+        mpn: args.mpn.clone(),
+        manufacturer,
+        lifecycle: lifecycle(&offers),
+        best_prices: best_prices(&offers),
+        offers,
+        datasheets,
+        errors,
+    };
+
+    if args.json {
+        let json = crate::select::to_string_pretty(&info).map_err(|e| anyhow!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+    } else {
+        print_part_info(&info);
+    }
+    Ok(())
+}
+
+/// Mouser and DigiKey when their credentials are set; JLCPCB needs none.
+fn configured_sources() -> Vec<Source> {
+    let mut sources = Vec::new();
+    if auth::credential("MOUSER_API_KEY").is_some() {
+        sources.push(Source::Mouser);
+    }
+    if auth::credential("DIGIKEY_CLIENT_ID").is_some() && auth::credential("DIGIKEY_CLIENT_SECRET").is_some() {
+        sources.push(Source::Digikey);
+    }
+    sources.push(Source::Jlcpcb);
+    sources
+}
+
+/// A reported lifecycle status on the common scale of [`SEVERITY`].
+fn lifecycle_class(status: &str) -> Option<&'static str> {
+    let status = status.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|w| status.contains(w));
+    // "Discontinued at Digi-Key" is about the distributor's catalog, not the part
+    if status.contains("discontinued at") {
+        None
+    } else if has(&["obsolete", "discontinued"]) {
+        Some("obsolete")
+    } else if has(&["end of life", "last time buy", "eol", "ltb"]) {
+        Some("eol")
+    } else if has(&["not recommended", "not for new", "nrnd"]) {
+        Some("nrnd")
+    } else if has(&["active", "new product", "production"]) {
+        Some("active")
+    } else {
+        None
+    }
+}
+
+const SEVERITY: [&str; 4] = ["active", "nrnd", "eol", "obsolete"];
+
+fn lifecycle(offers: &[StockInfo]) -> Lifecycle {
+    let reported: BTreeMap<&'static str, String> = offers
+        .iter()
+        .filter_map(|o| Some((o.distributor, o.lifecycle_status.clone().filter(|s| !s.trim().is_empty())?)))
+        .collect();
+    let classes: Vec<&'static str> = reported.values().filter_map(|s| lifecycle_class(s)).collect();
+    let status = classes.iter().copied().max_by_key(|c| SEVERITY.iter().position(|s| s == c));
+    Lifecycle { status, agreed: classes.windows(2).all(|w| w[0] == w[1]), reported }
+}
+
+/// The cheapest offer at every quantity any distributor has a price break at.
+/// Offers are only compared within a currency (pass `--currency` to convert
+/// them all to one).
+fn best_prices(offers: &[StockInfo]) -> Vec<BestPrice> {
+    let mut tiers: Vec<(String, i32)> = offers
+        .iter()
+        .flat_map(|o| o.price_breaks.iter().map(|pb| (o.currency.clone(), pb.quantity)))
+        .collect();
+    tiers.sort();
+    tiers.dedup();
+    tiers
+        .into_iter()
+        .filter_map(|(currency, quantity)| {
+            offers
+                .iter()
+                .filter(|o| o.currency == currency)
+                .filter_map(|o| Some((o, stock::unit_price(&o.price_breaks, Some(quantity))?)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(offer, unit_price)| BestPrice { quantity, unit_price, currency, distributor: offer.distributor })
+        })
+        .collect()
+}
+
+fn print_part_info(info: &PartInfo) {
+    println!("{}", info.mpn);
+    if let Some(manufacturer) = &info.manufacturer {
+        println!("  Manufacturer: {}", manufacturer);
+    }
+    match (info.lifecycle.status, info.lifecycle.agreed) {
+        (Some(status), true) => println!("  Lifecycle: {}", status),
+        (Some(status), false) => {
+            let reported: Vec<String> = info.lifecycle.reported.iter().map(|(d, s)| format!("{}: {}", d, s)).collect();
+            println!("  Lifecycle: {} (distributors disagree; {})", status, reported.join(", "));
+        }
+        (None, _) => println!("  Lifecycle: unknown"),
+    }
+
+    println!("\nStock:");
+    for offer in &info.offers {
+        let listed = if offer.mpn.eq_ignore_ascii_case(&info.mpn) { String::new() } else { format!(" (as {})", offer.mpn) };
+        println!(
+            "  {:<8} {:>10}  {}{}",
+            offer.distributor,
+            offer.stock.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string()),
+            offer.distributor_pn.as_deref().unwrap_or("-"),
+            listed
+        );
+        let mut details = Vec::new();
+        if let Some(moq) = offer.moq {
+            details.push(format!("MOQ {}", moq));
+        }
+        if let Some(lead) = &offer.lead_time {
+            details.push(format!("lead {}", lead));
+        }
+        if let Some(status) = &offer.lifecycle_status {
+            details.push(status.clone());
+        }
+        if !details.is_empty() {
+            println!("  {:<8} {:>10}  {}", "", "", details.join(", "));
+        }
+    }
+
+    if !info.best_prices.is_empty() {
+        println!("\nBest price:");
+        for best in &info.best_prices {
+            println!("  {:>8}+  {:.4} {}  ({})", best.quantity, best.unit_price, best.currency, best.distributor);
+        }
+    }
+
+    if !info.datasheets.is_empty() {
+        println!("\nDatasheets:");
+        for datasheet in &info.datasheets {
+            println!("  {}  ({})", datasheet.url, datasheet.sources.join(", "));
+        }
+    }
+
+    for error in &info.errors {
+        eprintln!("[PART-INFO] {}: {}", error.source, error.error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stock::StockPriceBreak;
+
+    fn offer(distributor: &'static str, status: Option<&str>, breaks: &[(i32, f64)]) -> StockInfo {
+        StockInfo {
+            mpn: "LM5164DDAR".to_string(),
+            manufacturer: Some("Texas Instruments".to_string()),
+            distributor,
+            distributor_pn: None,
+            lifecycle_status: status.map(str::to_string),
+            stock: Some(100),
+            lead_time: None,
+            moq: None,
+            order_multiple: None,
+            currency: "USD".to_string(),
+            price_breaks: breaks.iter().map(|&(quantity, unit_price)| StockPriceBreak { quantity, unit_price }).collect(),
+            suggested_replacement: None,
+            jlcpcb_category: None,
+            datasheet_url: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_offers() {
+        let offers = [
+            offer("mouser", None, &[(1, 2.10), (10, 1.90), (100, 1.50)]),
+            offer("digikey", Some("Active"), &[(1, 1.98), (25, 1.70)]),
+            offer("jlcpcb", Some("Not Recommended for New Designs"), &[(5, 2.50)]),
+        ];
+        let best = best_prices(&offers);
+        let summary: Vec<(i32, f64, &str)> = best.iter().map(|b| (b.quantity, b.unit_price, b.distributor)).collect();
+        assert_eq!(
+            summary,
+            [(1, 1.98, "digikey"), (5, 1.98, "digikey"), (10, 1.90, "mouser"), (25, 1.70, "digikey"), (100, 1.50, "mouser")]
+        );
+
+        let merged = lifecycle(&offers);
+        assert_eq!(merged.status, Some("nrnd"));
+        assert!(!merged.agreed);
+        assert_eq!(merged.reported.len(), 2);
+        assert!(lifecycle(&offers[..2]).agreed);
+        assert_eq!(lifecycle_class("Discontinued at Digi-Key"), None);
+        assert_eq!(lifecycle_class("Last Time Buy"), Some("eol"));
+    }
+}
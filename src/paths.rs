@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Default output locations for files the user asked for.
+//!
+//! Downloads (datasheets, SVD files) and generated reports (footprint images,
+//! extracted pages) go under one download directory instead of the current
+//! working directory:
+//!
+//! | Platform | Default                                |
+//! |----------|----------------------------------------|
+//! | Linux    | `$XDG_DOWNLOAD_DIR/datasheet-cli`      |
+//! | macOS    | `~/Downloads/datasheet-cli`            |
+//! | Windows  | `{FOLDERID_Downloads}\datasheet-cli`   |
+//!
+//! `--download-dir` / `DATASHEET_DOWNLOAD_DIR` (or `download_dir` in the config
+//! file) overrides it. This is separate from the cache directory, whose
+//! contents may be evicted at any time.
+//!
+//! File names built from part numbers and labels go through
+//! [`sanitize_filename`], so a name that works on Linux also works on Windows.
+//! Output files are written with [`write_file`], which never leaves a
+//! half-written file behind and, with `--backup`, keeps the previous version.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use unicode_normalization::UnicodeNormalization;
+
+/// Longest name [`sanitize_filename`] returns, in bytes; leaves room for the
+/// extensions callers append within the usual 255-byte limit.
+const MAX_NAME_BYTES: usize = 200;
+
+/// Device names Windows reserves in every directory, with or without an extension.
+const RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Download directory override from `--download-dir` / `DATASHEET_DOWNLOAD_DIR`
+static DOWNLOAD_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keep the previous version of an overwritten output as `<name>.bak` (`--backup`)
+static BACKUP: AtomicBool = AtomicBool::new(false);
+
+/// Set the download directory (called once from `main`).
+pub fn set_download_dir(path: PathBuf) {
+    let _ = DOWNLOAD_DIR.set(path);
+}
+
+/// Root for downloads and reports.
+pub fn download_dir() -> PathBuf {
+    if let Some(dir) = DOWNLOAD_DIR.get() {
+        return dir.clone();
+    }
+    dirs::download_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join("Downloads")))
+        .map(|dir| dir.join("datasheet-cli"))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Where downloaded datasheets (and other reference files) are saved.
+pub fn datasheets_dir() -> PathBuf {
+    download_dir().join("datasheets")
+}
+
+/// Where generated reports and images are saved.
+pub fn reports_dir() -> PathBuf {
+    download_dir().join("reports")
+}
+
+/// A file name component for a part number, label, or other free text that is
+/// valid on every platform:
+///
+/// - Unicode is NFC-normalized, so text typed on macOS and on Linux names the same file
+/// - path separators, characters Windows forbids, and control characters become `_`
+/// - trailing dots and spaces, which Windows drops, are removed
+/// - reserved device names (`CON`, `NUL`, `COM1`, ...) get a `_` prefix
+/// - long names are cut to 200 bytes
+pub fn sanitize_filename(name: &str) -> String {
+    let mut out: String = name
+        .nfc()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    if out.len() > MAX_NAME_BYTES {
+        let cut = (0..=MAX_NAME_BYTES).rev().find(|&i| out.is_char_boundary(i)).unwrap_or(0);
+        out.truncate(cut);
+    }
+    let out = out.trim_end_matches(['.', ' ']);
+    if out.is_empty() {
+        return "_".to_string();
+    }
+    let stem = out.split('.').next().unwrap_or(out).trim_end();
+    if RESERVED.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+        return format!("_{}", out);
+    }
+    out.to_string()
+}
+
+/// Hands out file names within one directory: the sanitized name of each key,
+/// with `-2`, `-3`, ... added when different keys end up with the same name
+/// (`AB/C` and `AB:C`, or `LM358` and `lm358` on a case-insensitive file
+/// system). The same key always gets the same name.
+#[derive(Default)]
+pub struct UniqueNames {
+    /// Lowercased name -> key it was given to
+    taken: HashMap<String, String>,
+}
+
+impl UniqueNames {
+    pub fn name(&mut self, key: &str) -> String {
+        self.name_for(key, key)
+    }
+
+    /// Name `key` after `text`, e.g. an input file after its stem.
+    pub fn name_for(&mut self, key: &str, text: &str) -> String {
+        let base = sanitize_filename(text);
+        let mut name = base.clone();
+        for n in 2.. {
+            match self.taken.get(&name.to_lowercase()) {
+                Some(owner) if owner == key => return name,
+                Some(_) => name = format!("{}-{}", base, n),
+                None => break,
+            }
+        }
+        self.taken.insert(name.to_lowercase(), key.to_string());
+        name
+    }
+}
+
+/// `path` in a form that can be longer than Windows' 260-character limit: an
+/// absolute path with the `\\?\` prefix. Other platforms have no such limit
+/// and get `path` back unchanged.
+pub fn long_path(path: &Path) -> PathBuf {
+    if cfg!(windows) && path.as_os_str().len() >= 240 {
+        if let Ok(absolute) = std::path::absolute(path) {
+            let text = absolute.to_string_lossy().replace('/', "\\");
+            if text.starts_with(r"\\?\") {
+                return absolute;
+            }
+            return match text.strip_prefix(r"\\") {
+                Some(unc) => PathBuf::from(format!(r"\\?\UNC\{}", unc)),
+                None => PathBuf::from(format!(r"\\?\{}", text)),
+            };
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Keep `<name>.bak` copies when [`write_file`] replaces an existing file.
+pub fn set_backup(enabled: bool) {
+    BACKUP.store(enabled, Ordering::Relaxed);
+}
+
+/// Write `contents` to `path` atomically: into a temporary file next to it,
+/// then renamed over it, so a failed write leaves the old file intact.
+pub fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    write_file_with_backup(path, contents.as_ref(), BACKUP.load(Ordering::Relaxed))
+}
+
+fn write_file_with_backup(path: &Path, contents: &[u8], backup: bool) -> std::io::Result<()> {
+    let path = long_path(path);
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    let written = std::fs::File::create(&tmp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    if backup && path.is_file() {
+        let mut bak = path.clone().into_os_string();
+        bak.push(".bak");
+        if let Err(e) = std::fs::copy(&path, &bak) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e);
+        }
+    }
+    std::fs::rename(&tmp, &path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("LM358DR"), "LM358DR");
+        assert_eq!(sanitize_filename("AD8605ARTZ-R2/REEL7"), "AD8605ARTZ-R2_REEL7");
+        assert_eq!(sanitize_filename("con"), "_con");
+        assert_eq!(sanitize_filename("Nul.pdf"), "_Nul.pdf");
+        assert_eq!(sanitize_filename("CONTROLLER"), "CONTROLLER");
+        assert_eq!(sanitize_filename("TPS5430. "), "TPS5430");
+        assert_eq!(sanitize_filename(".."), "_");
+        assert_eq!(sanitize_filename("tab\there"), "tab_here");
+        // Decomposed and precomposed forms name the same file
+        assert_eq!(sanitize_filename("Cafe\u{301}"), sanitize_filename("Caf\u{e9}"));
+        let long = sanitize_filename(&"\u{e9}".repeat(150));
+        assert!(long.len() <= MAX_NAME_BYTES && long.chars().all(|c| c == '\u{e9}'));
+
+        let mut names = UniqueNames::default();
+        assert_eq!(names.name("AB/C"), "AB_C");
+        assert_eq!(names.name("AB:C"), "AB_C-2");
+        assert_eq!(names.name("ab_c"), "ab_c-3");
+        assert_eq!(names.name("AB/C"), "AB_C");
+    }
+
+    #[test]
+    fn test_write_file() {
+        let dir = std::env::temp_dir().join(format!("datasheet-write-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("parts.json");
+
+        write_file_with_backup(&path, b"v1", true).unwrap();
+        assert!(!dir.join("parts.json.bak").exists());
+        write_file_with_backup(&path, b"v2", false).unwrap();
+        assert!(!dir.join("parts.json.bak").exists());
+        write_file_with_backup(&path, b"v3", true).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"v3");
+        assert_eq!(std::fs::read(dir.join("parts.json.bak")).unwrap(), b"v2");
+        // No temporary files left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        // A failed write leaves the old file alone
+        assert!(write_file_with_backup(&dir.join("missing").join("x.json"), b"x", true).is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"v3");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
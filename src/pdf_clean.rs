@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! `--clean-pdf`: strip watermarks and printer's marks before upload.
+//!
+//! Distributor and "PRELIMINARY"/"CONFIDENTIAL" watermarks are repeated on
+//! every page, cost tokens, and end up in extracted text; crop marks around a
+//! print-ready page get read as dimension lines. Only content the PDF itself
+//! marks as such is removed, so the datasheet's own drawings are never touched:
+//!
+//! - `/Watermark` and `/PrinterMark` annotations
+//! - marked content tagged as a watermark artifact or drawn under an optional
+//!   content group named like "Watermark"
+//! - form XObjects that Acrobat's watermark tool tags as watermarks
+//!
+//! Pages with a `/TrimBox` inside their media box are cropped to it, which
+//! drops crop marks and colour bars printed outside the trimmed page.
+
+use anyhow::{Context, Result};
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::HashSet;
+
+use crate::annotate::{inherited, rect};
+
+#[derive(Debug, Default, PartialEq)]
+struct Cleaned {
+    annotations: usize,
+    watermarks: usize,
+    trimmed_pages: usize,
+}
+
+/// Remove watermarks and printer's marks, returning the cleaned PDF bytes.
+pub fn clean(pdf_data: &[u8]) -> Result<Vec<u8>> {
+    let mut doc = Document::load_mem(pdf_data).context("loading PDF for cleanup")?;
+    let cleaned = clean_document(&mut doc);
+    eprintln!(
+        "[CLEAN] Removed {} mark annotation(s) and {} watermark(s); trimmed {} page(s) to the trim box",
+        cleaned.annotations, cleaned.watermarks, cleaned.trimmed_pages
+    );
+    let mut out = Vec::new();
+    doc.save_to(&mut out).context("saving cleaned PDF")?;
+    Ok(out)
+}
+
+fn clean_document(doc: &mut Document) -> Cleaned {
+    let mut cleaned = Cleaned::default();
+    for page_id in doc.get_pages().into_values() {
+        cleaned.annotations += remove_mark_annotations(doc, page_id);
+        cleaned.watermarks += remove_watermarks(doc, page_id);
+        if trim_to_trim_box(doc, page_id) {
+            cleaned.trimmed_pages += 1;
+        }
+    }
+    cleaned
+}
+
+fn remove_mark_annotations(doc: &mut Document, page_id: ObjectId) -> usize {
+    let Some(annots) = doc
+        .get_dictionary(page_id)
+        .ok()
+        .and_then(|page| page.get(b"Annots").ok())
+        .and_then(|annots| doc.dereference(annots).ok())
+        .and_then(|(_, annots)| annots.as_array().ok())
+        .cloned()
+    else {
+        return 0;
+    };
+    let is_mark = |annot: &Object| {
+        doc.dereference(annot)
+            .ok()
+            .and_then(|(_, annot)| annot.as_dict().ok())
+            .and_then(|annot| annot.get(b"Subtype").ok())
+            .and_then(|subtype| subtype.as_name().ok())
+            .is_some_and(|subtype| subtype == b"Watermark" || subtype == b"PrinterMark")
+    };
+    let keep: Vec<Object> = annots.iter().filter(|annot| !is_mark(annot)).cloned().collect();
+    let removed = annots.len() - keep.len();
+    if removed > 0
+        && let Ok(Object::Dictionary(page)) = doc.get_object_mut(page_id)
+    {
+        page.set("Annots", keep);
+    }
+    removed
+}
+
+fn remove_watermarks(doc: &mut Document, page_id: ObjectId) -> usize {
+    let (properties, xobjects) = watermark_resources(doc, page_id);
+    // Pages whose content cannot be parsed are left as they are
+    let Ok(content) = doc.get_and_decode_page_content(page_id) else {
+        return 0;
+    };
+    let (operations, removed) = strip_watermarks(content.operations, &properties, &xobjects);
+    if removed == 0 {
+        return 0;
+    }
+    let Ok(data) = (Content { operations }).encode() else {
+        return 0;
+    };
+    if doc.change_page_content(page_id, data).is_err() {
+        return 0;
+    }
+    removed
+}
+
+/// Names of the page's marked-content property lists and form XObjects that
+/// are watermarks.
+fn watermark_resources(doc: &Document, page_id: ObjectId) -> (HashSet<Vec<u8>>, HashSet<Vec<u8>>) {
+    let (mut properties, mut xobjects) = (HashSet::new(), HashSet::new());
+    let Ok((inline, ids)) = doc.get_page_resources(page_id) else {
+        return (properties, xobjects);
+    };
+    let resources = inline.into_iter().chain(ids.iter().filter_map(|&id| doc.get_dictionary(id).ok()));
+    let entries = |resources: &Dictionary, key: &[u8]| -> Vec<(Vec<u8>, Object)> {
+        resources
+            .get(key)
+            .ok()
+            .and_then(|dict| doc.dereference(dict).ok())
+            .and_then(|(_, dict)| dict.as_dict().ok())
+            .map(|dict| {
+                dict.iter()
+                    .filter_map(|(name, value)| Some((name.clone(), doc.dereference(value).ok()?.1.clone())))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    for resources in resources {
+        for (name, value) in entries(resources, b"Properties") {
+            if value.as_dict().is_ok_and(is_watermark_properties) {
+                properties.insert(name);
+            }
+        }
+        for (name, value) in entries(resources, b"XObject") {
+            if value.as_stream().is_ok_and(|stream| is_watermark_xobject(doc, &stream.dict)) {
+                xobjects.insert(name);
+            }
+        }
+    }
+    (properties, xobjects)
+}
+
+/// A watermark artifact (`<< /Type /Pagination /Subtype /Watermark >>`) or an
+/// optional content group named like a watermark layer.
+fn is_watermark_properties(dict: &Dictionary) -> bool {
+    if dict.get(b"Subtype").and_then(Object::as_name).is_ok_and(|s| s == b"Watermark") {
+        return true;
+    }
+    dict.get(b"Type").and_then(Object::as_name).is_ok_and(|t| t == b"OCG")
+        && dict
+            .get(b"Name")
+            .and_then(Object::as_str)
+            .is_ok_and(|name| String::from_utf8_lossy(name).to_ascii_lowercase().contains("watermark"))
+}
+
+/// Acrobat tags watermark forms with `/PieceInfo << /ADBE_CompoundType << /Private /Watermark >> >>`.
+fn is_watermark_xobject(doc: &Document, dict: &Dictionary) -> bool {
+    let lookup = |dict: &Dictionary, key: &[u8]| -> Option<Object> {
+        doc.dereference(dict.get(key).ok()?).ok().map(|(_, v)| v.clone())
+    };
+    lookup(dict, b"PieceInfo")
+        .and_then(|info| lookup(info.as_dict().ok()?, b"ADBE_CompoundType"))
+        .and_then(|compound| lookup(compound.as_dict().ok()?, b"Private"))
+        .is_some_and(|private| private.as_name().is_ok_and(|name| name == b"Watermark"))
+}
+
+/// Drop watermark marked-content blocks (through their matching EMC) and
+/// invocations of watermark XObjects. Returns the kept operations and the
+/// number of watermarks removed.
+fn strip_watermarks(
+    operations: Vec<Operation>,
+    properties: &HashSet<Vec<u8>>,
+    xobjects: &HashSet<Vec<u8>>,
+) -> (Vec<Operation>, usize) {
+    let mut kept = Vec::with_capacity(operations.len());
+    let mut removed = 0;
+    let mut skip_depth = 0usize;
+    for op in operations {
+        if skip_depth > 0 {
+            match op.operator.as_str() {
+                "BMC" | "BDC" => skip_depth += 1,
+                "EMC" => skip_depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+        let watermark = match op.operator.as_str() {
+            "BDC" => op.operands.get(1).is_some_and(|props| match props {
+                Object::Name(name) => properties.contains(name),
+                Object::Dictionary(dict) => is_watermark_properties(dict),
+                _ => false,
+            }),
+            "Do" => op.operands.first().and_then(|name| name.as_name().ok()).is_some_and(|n| xobjects.contains(n)),
+            _ => false,
+        };
+        if !watermark {
+            kept.push(op);
+            continue;
+        }
+        removed += 1;
+        if op.operator == "BDC" {
+            skip_depth = 1;
+        }
+    }
+    (kept, removed)
+}
+
+/// Crop a page to its /TrimBox when that lies inside the media box.
+fn trim_to_trim_box(doc: &mut Document, page_id: ObjectId) -> bool {
+    let Some(media) = inherited(doc, page_id, b"MediaBox").and_then(|b| rect(doc, b)) else {
+        return false;
+    };
+    let Some(trim) = doc
+        .get_dictionary(page_id)
+        .ok()
+        .and_then(|page| page.get(b"TrimBox").ok().cloned())
+        .and_then(|b| rect(doc, b))
+    else {
+        return false;
+    };
+    let inside = trim[0] >= media[0] && trim[1] >= media[1] && trim[2] <= media[2] && trim[3] <= media[3];
+    let smaller = trim.iter().zip(&media).any(|(t, m)| (t - m).abs() > 0.5);
+    if !inside || !smaller || trim[2] - trim[0] < 1.0 || trim[3] - trim[1] < 1.0 {
+        return false;
+    }
+    let trim: Vec<Object> = trim.iter().map(|&v| v.into()).collect();
+    match doc.get_object_mut(page_id) {
+        Ok(Object::Dictionary(page)) => {
+            page.set("MediaBox", trim.clone());
+            page.set("CropBox", trim);
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{Stream, dictionary};
+
+    #[test]
+    fn test_clean_document() {
+        let mut doc = Document::with_version("1.7");
+        let pages_id = doc.new_object_id();
+        let content = b"/Artifact <</Type /Pagination /Subtype /Watermark>> BDC BT (DRAFT) Tj ET EMC \
+                        /P <</MCID 0>> BDC BT (VIN) Tj ET EMC"
+            .to_vec();
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+        let watermark = doc.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Watermark" });
+        let link = doc.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Link" });
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "Annots" => vec![watermark.into(), link.into()],
+            "TrimBox" => vec![36.into(), 36.into(), 648.into(), 828.into()],
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+                "MediaBox" => vec![0.into(), 0.into(), 684.into(), 864.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+
+        let cleaned = clean_document(&mut doc);
+        assert_eq!(cleaned, Cleaned { annotations: 1, watermarks: 1, trimmed_pages: 1 });
+
+        let text = String::from_utf8_lossy(&doc.get_page_content(page_id).unwrap()).to_string();
+        assert!(text.contains("(VIN)") && !text.contains("DRAFT"));
+        let page = doc.get_dictionary(page_id).unwrap();
+        assert_eq!(page.get(b"Annots").unwrap().as_array().unwrap(), &vec![Object::from(link)]);
+        assert_eq!(rect(&doc, page.get(b"MediaBox").unwrap().clone()), Some([36.0, 36.0, 648.0, 828.0]));
+        // Nothing left to remove the second time
+        assert_eq!(clean_document(&mut doc), Cleaned::default());
+    }
+}
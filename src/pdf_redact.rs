@@ -0,0 +1,440 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! `--redact`: mask confidential text in a PDF before it leaves the machine.
+//!
+//! Preliminary datasheets under NDA name customers, project codenames, and
+//! contacts. Matching text is replaced with `[REDACTED]`:
+//!
+//! - in text drawn by page content streams and form XObjects (headers and
+//!   footers are often forms), including matches split across the kerned
+//!   pieces of one text operator
+//! - in document strings: the info dictionary, bookmarks, annotations, and
+//!   link targets
+//! - in the XMP metadata stream
+//!
+//! Afterwards the page text is searched again. Text the redaction could not
+//! reach (drawn one glyph at a time, or in a font without a usable encoding)
+//! fails the extraction instead of being sent. Text inside images and text
+//! converted to outlines is not searched at all.
+
+use anyhow::{Context, Result, anyhow};
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Encoding, Object, ObjectId};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+const MASK: &str = "[REDACTED]";
+
+const EMAIL: &str = r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}";
+
+/// Compiled `--redact` patterns.
+pub struct Redactor {
+    patterns: Vec<(String, Regex)>,
+}
+
+/// What was redacted, per pattern.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Report {
+    pub patterns: Vec<PatternReport>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PatternReport {
+    pub pattern: String,
+    /// Matches masked in page text, including shared headers and footers
+    pub text: usize,
+    /// Pages the matches were on; text in form XObjects has no single page
+    pub pages: BTreeSet<u32>,
+    /// Matches masked in metadata, bookmarks, annotations, and links
+    pub metadata: usize,
+}
+
+impl Redactor {
+    /// `terms` are matched case-insensitively, with any run of whitespace
+    /// matching any other; `/.../` is a regular expression used as written.
+    pub fn new(terms: &[String], emails: bool) -> Result<Self, String> {
+        let mut patterns = Vec::new();
+        for term in terms {
+            let source = match term.strip_prefix('/').and_then(|t| t.strip_suffix('/')) {
+                Some(regex) if !regex.is_empty() => regex.to_string(),
+                _ if term.trim().is_empty() => return Err("--redact needs non-empty text".to_string()),
+                _ => {
+                    let words: Vec<String> = term.split_whitespace().map(regex::escape).collect();
+                    format!(r"(?i){}", words.join(r"\s+"))
+                }
+            };
+            let regex = Regex::new(&source).map_err(|e| format!("invalid --redact pattern '{}': {}", term, e))?;
+            patterns.push((term.clone(), regex));
+        }
+        if emails {
+            patterns.push(("email addresses".to_string(), Regex::new(EMAIL).expect("valid email regex")));
+        }
+        Ok(Self { patterns })
+    }
+
+    /// The patterns as given, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.patterns.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Stable description of the patterns, for cache keys. Hashed so that the
+    /// confidential terms themselves are not written into cache metadata.
+    pub fn fingerprint(&self) -> String {
+        let sources: Vec<&str> = self.patterns.iter().map(|(_, regex)| regex.as_str()).collect();
+        crate::file_cache::compute_hash(sources.join("\n").as_bytes())
+    }
+
+    /// Byte ranges of `text` to mask, merged, counting matches per pattern.
+    fn find(&self, text: &str, counts: &mut [usize]) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (i, (_, regex)) in self.patterns.iter().enumerate() {
+            for found in regex.find_iter(text).filter(|m| !m.is_empty()) {
+                counts[i] += 1;
+                ranges.push(found.range());
+            }
+        }
+        ranges.sort_by_key(|r| r.start);
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    fn replace(&self, text: &str, counts: &mut [usize]) -> Option<String> {
+        let ranges = self.find(text, counts);
+        if ranges.is_empty() {
+            return None;
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut at = 0;
+        for range in ranges {
+            out.push_str(&text[at..range.start]);
+            out.push_str(MASK);
+            at = range.end;
+        }
+        out.push_str(&text[at..]);
+        Some(out)
+    }
+}
+
+/// Redact `pdf_data`, returning the new PDF and what was masked. Fails if
+/// matching text is still on a page afterwards.
+pub fn redact(pdf_data: &[u8], redactor: &Redactor) -> Result<(Vec<u8>, Report)> {
+    let mut doc = Document::load_mem(pdf_data).context("loading PDF for redaction")?;
+    if doc.is_encrypted() {
+        return Err(anyhow!("cannot redact an encrypted PDF"));
+    }
+    let n = redactor.patterns.len();
+    let mut text = vec![0; n];
+    let mut metadata = vec![0; n];
+    let mut pages: Vec<BTreeSet<u32>> = vec![BTreeSet::new(); n];
+
+    for (number, page_id) in doc.get_pages() {
+        let mut counts = vec![0; n];
+        let Some(data) = redact_page(&doc, page_id, redactor, &mut counts) else {
+            continue;
+        };
+        doc.change_page_content(page_id, data).context("writing redacted page")?;
+        for (i, &count) in counts.iter().enumerate().filter(|(_, c)| **c > 0) {
+            text[i] += count;
+            pages[i].insert(number);
+        }
+    }
+
+    let forms: Vec<ObjectId> = doc
+        .objects
+        .iter()
+        .filter(|(_, object)| {
+            object.as_stream().is_ok_and(|s| s.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Form"))
+        })
+        .map(|(&id, _)| id)
+        .collect();
+    for id in forms {
+        let Some(data) = redact_form(&doc, id, redactor, &mut text) else {
+            continue;
+        };
+        if let Ok(Object::Stream(stream)) = doc.get_object_mut(id) {
+            stream.set_plain_content(data);
+        }
+    }
+
+    for object in doc.objects.values_mut() {
+        match object {
+            Object::Stream(stream) if stream.dict.get(b"Type").and_then(Object::as_name).ok() == Some(b"Metadata") => {
+                let xml = stream.decompressed_content().unwrap_or_else(|_| stream.content.clone());
+                if let Some(xml) = std::str::from_utf8(&xml).ok().and_then(|x| redactor.replace(x, &mut metadata)) {
+                    stream.set_plain_content(xml.into_bytes());
+                }
+            }
+            Object::Stream(stream) => redact_strings_in_dict(&mut stream.dict, redactor, &mut metadata),
+            other => redact_strings(other, redactor, &mut metadata),
+        }
+    }
+    if let Ok(info) = doc.trailer.get_mut(b"Info") {
+        redact_strings(info, redactor, &mut metadata);
+    }
+
+    let mut remaining = vec![0; n];
+    for (&number, &page_id) in &doc.get_pages() {
+        let page_text = crate::compare_revisions::page_text(&doc, page_id).unwrap_or_default();
+        let before = remaining.iter().sum::<usize>();
+        redactor.find(&page_text, &mut remaining);
+        if remaining.iter().sum::<usize>() > before {
+            let left: Vec<&str> = redactor
+                .patterns
+                .iter()
+                .zip(&remaining)
+                .filter(|(_, c)| **c > 0)
+                .map(|((term, _), _)| term.as_str())
+                .collect();
+            return Err(crate::error::Error::validation(format!(
+                "page {}: could not redact {} (text drawn in pieces or in an unsupported font); \
+                 nothing was sent",
+                number,
+                left.join(", ")
+            ))
+            .into());
+        }
+    }
+
+    let report = Report {
+        patterns: redactor
+            .patterns
+            .iter()
+            .enumerate()
+            .map(|(i, (term, _))| PatternReport {
+                pattern: term.clone(),
+                text: text[i],
+                pages: std::mem::take(&mut pages[i]),
+                metadata: metadata[i],
+            })
+            .collect(),
+    };
+    let mut out = Vec::new();
+    doc.save_to(&mut out).context("saving redacted PDF")?;
+    Ok((out, report))
+}
+
+impl Report {
+    /// One log line per pattern.
+    pub fn log(&self) {
+        for p in &self.patterns {
+            let pages: Vec<String> = p.pages.iter().map(u32::to_string).collect();
+            let pages = if pages.is_empty() { String::new() } else { format!(" (pages {})", pages.join(", ")) };
+            eprintln!(
+                "[REDACT] {}: {} in page text{}, {} in metadata and links",
+                p.pattern, p.text, pages, p.metadata
+            );
+        }
+    }
+}
+
+fn redact_page(doc: &Document, page_id: ObjectId, redactor: &Redactor, counts: &mut [usize]) -> Option<Vec<u8>> {
+    let encodings: BTreeMap<Vec<u8>, Encoding> = doc
+        .get_page_fonts(page_id)
+        .ok()?
+        .into_iter()
+        .filter_map(|(name, font)| font.get_font_encoding(doc).ok().map(|e| (name, e)))
+        .collect();
+    let content = doc.get_page_content(page_id).ok()?;
+    redact_content(&content, &encodings, redactor, counts)
+}
+
+fn redact_form(doc: &Document, id: ObjectId, redactor: &Redactor, counts: &mut [usize]) -> Option<Vec<u8>> {
+    let stream = doc.get_object(id).ok()?.as_stream().ok()?;
+    let fonts: Option<&Dictionary> = stream
+        .dict
+        .get_deref(b"Resources", doc)
+        .and_then(Object::as_dict)
+        .and_then(|resources| resources.get_deref(b"Font", doc))
+        .and_then(Object::as_dict)
+        .ok();
+    let encodings: BTreeMap<Vec<u8>, Encoding> = fonts
+        .into_iter()
+        .flat_map(|fonts| fonts.iter())
+        .filter_map(|(name, font)| {
+            let font = doc.dereference(font).ok()?.1.as_dict().ok()?;
+            Some((name.clone(), font.get_font_encoding(doc).ok()?))
+        })
+        .collect();
+    let content = stream.decompressed_content().unwrap_or_else(|_| stream.content.clone());
+    redact_content(&content, &encodings, redactor, counts)
+}
+
+/// Mask matches in the text operators of a content stream; `None` if nothing matched.
+fn redact_content(
+    content: &[u8],
+    encodings: &BTreeMap<Vec<u8>, Encoding>,
+    redactor: &Redactor,
+    counts: &mut [usize],
+) -> Option<Vec<u8>> {
+    let mut content = Content::decode(content).ok()?;
+    let mut encoding = None;
+    let mut changed = false;
+    for op in &mut content.operations {
+        match op.operator.as_str() {
+            "Tf" => {
+                encoding = op
+                    .operands
+                    .first()
+                    .and_then(|font| font.as_name().ok())
+                    .and_then(|name| encodings.get(name));
+            }
+            "Tj" | "TJ" | "'" | "\"" => {
+                if let Some(encoding) = encoding {
+                    changed |= redact_operation(op, encoding, redactor, counts);
+                }
+            }
+            _ => {}
+        }
+    }
+    if changed { content.encode().ok() } else { None }
+}
+
+/// Mask matches in one text-showing operator. The strings of a `TJ` array are
+/// searched as one text, so a match split by kerning is still found; the mask
+/// goes into the piece where the match starts and the other pieces lose their
+/// matched characters.
+fn redact_operation(op: &mut Operation, encoding: &Encoding, redactor: &Redactor, counts: &mut [usize]) -> bool {
+    let Some(operand) = op.operands.last_mut() else {
+        return false;
+    };
+    let mut pieces: Vec<&mut Vec<u8>> = match operand {
+        Object::String(bytes, _) => vec![bytes],
+        Object::Array(items) => items
+            .iter_mut()
+            .filter_map(|item| match item {
+                Object::String(bytes, _) => Some(bytes),
+                _ => None,
+            })
+            .collect(),
+        _ => return false,
+    };
+    let Ok(texts) = pieces.iter().map(|bytes| Document::decode_text(encoding, bytes)).collect::<Result<Vec<_>, _>>()
+    else {
+        return false;
+    };
+    let ranges = redactor.find(&texts.concat(), counts);
+    if ranges.is_empty() {
+        return false;
+    }
+
+    let mut start = 0;
+    for (bytes, text) in pieces.iter_mut().zip(&texts) {
+        let end = start + text.len();
+        let mut out = String::new();
+        for (offset, ch) in text.char_indices() {
+            let at = start + offset;
+            match ranges.iter().find(|r| r.contains(&at)) {
+                Some(range) if range.start == at => out.push_str(MASK),
+                Some(_) => {}
+                None => out.push(ch),
+            }
+        }
+        if out != *text {
+            **bytes = Document::encode_text(encoding, &out);
+        }
+        start = end;
+    }
+    true
+}
+
+fn redact_strings_in_dict(dict: &mut Dictionary, redactor: &Redactor, counts: &mut [usize]) {
+    for (_, value) in dict.iter_mut() {
+        redact_strings(value, redactor, counts);
+    }
+}
+
+/// Mask matches in every text string inside `object`.
+fn redact_strings(object: &mut Object, redactor: &Redactor, counts: &mut [usize]) {
+    match object {
+        Object::String(..) => {
+            let redacted = lopdf::decode_text_string(object).ok().and_then(|text| redactor.replace(&text, counts));
+            if let Some(redacted) = redacted {
+                *object = lopdf::text_string(&redacted);
+            }
+        }
+        Object::Array(items) => items.iter_mut().for_each(|item| redact_strings(item, redactor, counts)),
+        Object::Dictionary(dict) => redact_strings_in_dict(dict, redactor, counts),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{Stream, dictionary};
+
+    fn pdf(operations: Vec<Operation>) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let font = doc.add_object(dictionary! {
+            "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica", "Encoding" => "WinAnsiEncoding",
+        });
+        let content = Content { operations }.encode().unwrap();
+        let contents = doc.add_object(Stream::new(dictionary! {}, content));
+        let pages_id = doc.new_object_id();
+        let page = doc.add_object(dictionary! {
+            "Type" => "Page", "Parent" => pages_id, "Contents" => contents,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page.into()], "Count" => 1 }),
+        );
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog);
+        let info = doc.add_object(dictionary! { "Author" => Object::string_literal("Jane <jane@acme.com>") });
+        doc.trailer.set("Info", info);
+        let mut out = Vec::new();
+        doc.save_to(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_redact() {
+        let ops = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 10.into()]),
+            Operation::new("Tj", vec![Object::string_literal("Made for ACME  corp by Project Falcon")]),
+            Operation::new("Td", vec![0.into(), (-12).into()]),
+            // Kerned pieces of one operator
+            Operation::new(
+                "TJ",
+                vec![Object::Array(vec![
+                    Object::string_literal("Fal"),
+                    (-20).into(),
+                    Object::string_literal("con rev B"),
+                ])],
+            ),
+            Operation::new("ET", vec![]),
+        ];
+        let terms = ["Acme Corp".to_string(), "/Falc[o0]n/".to_string()];
+        let redactor = Redactor::new(&terms, true).unwrap();
+        let (out, report) = redact(&pdf(ops), &redactor).unwrap();
+
+        let doc = Document::load_mem(&out).unwrap();
+        let page = *doc.get_pages().get(&1).unwrap();
+        let text = crate::compare_revisions::page_text(&doc, page).unwrap();
+        assert_eq!(text, "Made for [REDACTED] by Project [REDACTED]\n[REDACTED] rev B\n");
+        let counts: Vec<(usize, usize)> = report.patterns.iter().map(|p| (p.text, p.metadata)).collect();
+        assert_eq!(counts, vec![(1, 0), (2, 0), (0, 1)]);
+        assert_eq!(report.patterns[1].pages, BTreeSet::from([1]));
+
+        // A match drawn one glyph at a time cannot be masked and stops the upload
+        let ops = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 10.into()]),
+            Operation::new("Tj", vec![Object::string_literal("Fal")]),
+            Operation::new("Tj", vec![Object::string_literal("con")]),
+            Operation::new("ET", vec![]),
+        ];
+        assert!(redact(&pdf(ops), &redactor).unwrap_err().to_string().contains("could not redact"));
+    }
+}
@@ -1,20 +1,26 @@
 // SPDX-License-Identifier: GPL-3.0-only
 // SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
 
-//! PDF splitting for large datasheets that exceed the Gemini page limit.
+//! PDF splitting for large datasheets that exceed the Gemini page or file size limit.
 //!
-//! When a PDF exceeds MAX_PAGES, it is split along TOC chapter boundaries
-//! into multiple files. Split files are cached by content hash so subsequent
-//! runs reuse the cached splits.
+//! When a PDF exceeds MAX_PAGES or MAX_FILE_BYTES, it is split along TOC
+//! chapter boundaries into multiple files. Split files are cached by content
+//! hash so subsequent runs reuse the cached splits.
 
 use anyhow::{Context, Result};
 use lopdf::Document;
-use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 const MAX_PAGES: u32 = 1000;
 
+/// Largest PDF the Gemini File API accepts
+const MAX_FILE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Size to aim for when splitting by size; parts repeat shared fonts and
+/// images, so leave headroom below MAX_FILE_BYTES
+const TARGET_PART_BYTES: u64 = 40 * 1024 * 1024;
+
 /// Information about how a PDF was split
 #[derive(Debug)]
 pub struct SplitResult {
@@ -28,39 +34,66 @@ pub struct SplitPart {
     pub end_page: u32,
 }
 
-/// Check if a PDF needs splitting and return the split parts if so.
-/// Returns None if the PDF is within the page limit.
-pub fn split_if_needed(pdf_path: &Path) -> Result<Option<SplitResult>> {
+/// Check if a PDF needs splitting and return the split parts if so. At least
+/// `min_parts` parts are made when the PDF has enough pages (pass 1 to split
+/// only when it is over the page or size limit).
+/// Returns None if the PDF is sent whole.
+pub fn split_if_needed(pdf_path: &Path, min_parts: u32) -> Result<Option<SplitResult>> {
     // Load the document once for page count + TOC
     let doc = Document::load(pdf_path)
         .with_context(|| format!("loading PDF: {}", pdf_path.display()))?;
 
     let pages = doc.get_pages();
     let page_count = pages.len() as u32;
-
-    if page_count <= MAX_PAGES {
+    let file_size = std::fs::metadata(pdf_path)
+        .with_context(|| format!("reading {}", pdf_path.display()))?
+        .len();
+
+    let by_pages = page_count.div_ceil(MAX_PAGES);
+    let by_size = if file_size > MAX_FILE_BYTES {
+        file_size.div_ceil(TARGET_PART_BYTES) as u32
+    } else {
+        1
+    };
+    let num_parts = by_pages.max(by_size).max(min_parts).min(page_count);
+    if num_parts <= 1 {
         return Ok(None);
     }
 
-    eprintln!(
-        "[SPLIT] PDF has {} pages (limit: {}), splitting...",
-        page_count, MAX_PAGES
-    );
+    if by_pages > 1 {
+        eprintln!(
+            "[SPLIT] PDF has {} pages (limit: {}), splitting...",
+            page_count, MAX_PAGES
+        );
+    } else if by_size > 1 {
+        eprintln!(
+            "[SPLIT] PDF is {:.1} MB (limit: {} MB), splitting...",
+            file_size as f64 / (1024.0 * 1024.0),
+            MAX_FILE_BYTES / (1024 * 1024)
+        );
+    } else {
+        eprintln!("[SPLIT] Splitting {} pages into {} parts...", page_count, num_parts);
+    }
+    // Parts split for size must drop the other pages' objects to get smaller
+    let prune = num_parts > by_pages;
 
     // Check cache first (hash the file, not the parsed doc)
-    let file_data = std::fs::read(pdf_path)
-        .with_context(|| format!("reading {}", pdf_path.display()))?;
-    let hash = compute_hash(&file_data);
-    let cache_dir = get_split_cache_dir()?.join(&hash[..16]);
+    let hash = crate::file_cache::hash_file(pdf_path)?;
+    let cache_dir = if prune {
+        get_split_cache_dir()?.join(format!("{}-{}", &hash[..16], num_parts))
+    } else {
+        get_split_cache_dir()?.join(&hash[..16])
+    };
 
     if let Some(cached) = check_cached_splits(&cache_dir, page_count)? {
         eprintln!("[SPLIT] Using cached split ({} parts)", cached.parts.len());
+        crate::file_cache::touch(&cache_dir.join("split_meta.json"));
         return Ok(Some(cached));
     }
 
     // Parse TOC for intelligent splitting
     let toc = read_toc_from_doc(&doc);
-    let ranges = compute_split_ranges(page_count, &toc);
+    let ranges = compute_split_ranges(page_count, num_parts, &toc);
 
     eprintln!(
         "[SPLIT] Splitting into {} parts: {}",
@@ -76,11 +109,13 @@ pub fn split_if_needed(pdf_path: &Path) -> Result<Option<SplitResult>> {
     std::fs::create_dir_all(&cache_dir).context("creating split cache directory")?;
 
     // Split the PDF using fast page-keep approach
+    let file_data = std::fs::read(pdf_path)
+        .with_context(|| format!("reading {}", pdf_path.display()))?;
     let mut parts = Vec::new();
     for (i, (start, end)) in ranges.iter().enumerate() {
         let part_path = cache_dir.join(format!("part-{}.pdf", i + 1));
 
-        split_pdf_fast(&file_data, &pages, *start, *end, &part_path)?;
+        split_pdf_fast(&file_data, &pages, *start, *end, &part_path, prune)?;
 
         eprintln!(
             "[SPLIT] Saved pages {}-{} ({} pages) -> {}",
@@ -89,6 +124,15 @@ pub fn split_if_needed(pdf_path: &Path) -> Result<Option<SplitResult>> {
             end - start + 1,
             part_path.file_name().unwrap_or_default().to_string_lossy()
         );
+        let part_size = std::fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+        if part_size > MAX_FILE_BYTES {
+            eprintln!(
+                "[SPLIT] Warning: part {} is still {:.1} MB, over the {} MB upload limit",
+                i + 1,
+                part_size as f64 / (1024.0 * 1024.0),
+                MAX_FILE_BYTES / (1024 * 1024)
+            );
+        }
 
         parts.push(SplitPart {
             path: part_path,
@@ -98,6 +142,7 @@ pub fn split_if_needed(pdf_path: &Path) -> Result<Option<SplitResult>> {
     }
 
     save_split_metadata(&cache_dir, page_count, &ranges)?;
+    crate::file_cache::enforce_budget();
 
     Ok(Some(SplitResult { parts }))
 }
@@ -121,9 +166,7 @@ fn read_toc_from_doc(doc: &Document) -> Vec<(usize, u32)> {
 }
 
 /// Compute optimal split ranges that respect TOC chapter boundaries.
-fn compute_split_ranges(total_pages: u32, toc: &[(usize, u32)]) -> Vec<(u32, u32)> {
-    let num_parts = ((total_pages as f64) / (MAX_PAGES as f64)).ceil() as u32;
-
+fn compute_split_ranges(total_pages: u32, num_parts: u32, toc: &[(usize, u32)]) -> Vec<(u32, u32)> {
     if num_parts <= 1 {
         return vec![(1, total_pages)];
     }
@@ -148,7 +191,7 @@ fn compute_split_ranges(total_pages: u32, toc: &[(usize, u32)]) -> Vec<(u32, u32
                 .copied()
                 .min_by_key(|&candidate| {
                     let distance = (candidate as i64 - ideal_page as i64).unsigned_abs();
-                    if candidate > ideal_page + MAX_PAGES / 4 {
+                    if candidate > ideal_page + total_pages / num_parts / 4 {
                         distance + 10000
                     } else {
                         distance
@@ -205,6 +248,7 @@ fn split_pdf_fast(
     start: u32,
     end: u32,
     output: &Path,
+    prune: bool,
 ) -> Result<()> {
     let mut doc = Document::load_mem(pdf_data).context("loading PDF for splitting")?;
 
@@ -224,7 +268,11 @@ fn split_pdf_fast(
     // Skip prune_objects and renumber_objects — they are the slow part.
     // The resulting PDF will have orphaned objects (larger file) but is
     // still valid and readable. Since these are temporary cached files
-    // only used for Gemini upload, size doesn't matter much.
+    // only used for Gemini upload, size doesn't matter much, except when
+    // the split is meant to get under the upload size limit.
+    if prune {
+        doc.prune_objects();
+    }
 
     doc.save(output)
         .with_context(|| format!("saving split PDF: {}", output.display()))?;
@@ -232,6 +280,65 @@ fn split_pdf_fast(
     Ok(())
 }
 
+/// Parse a page selection like "1-5,9,12-" into sorted, merged 1-based ranges.
+/// An open-ended range ("12-") runs to the last page.
+pub fn parse_page_ranges(spec: &str) -> Result<Vec<(u32, u32)>> {
+    let mut ranges = Vec::new();
+    for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let parse = |s: &str| -> Result<u32> {
+            s.trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|&n| n > 0)
+                .with_context(|| format!("invalid page number '{}' in '{}'", s, spec))
+        };
+        let (start, end) = match item.split_once('-') {
+            Some((a, b)) if b.trim().is_empty() => (parse(a)?, u32::MAX),
+            Some((a, b)) => (parse(a)?, parse(b)?),
+            None => {
+                let n = parse(item)?;
+                (n, n)
+            }
+        };
+        if end < start {
+            anyhow::bail!("invalid page range '{}'", item);
+        }
+        ranges.push((start, end));
+    }
+    if ranges.is_empty() {
+        anyhow::bail!("empty page selection");
+    }
+
+    ranges.sort_unstable();
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    Ok(merged)
+}
+
+/// Keep only the pages in `ranges`, returning the trimmed PDF bytes.
+pub fn trim_pages(pdf_data: &[u8], ranges: &[(u32, u32)]) -> Result<Vec<u8>> {
+    let mut doc = Document::load_mem(pdf_data).context("loading PDF for trimming")?;
+    let total_pages = doc.get_pages().len() as u32;
+
+    let keep = |p: u32| ranges.iter().any(|&(start, end)| p >= start && p <= end);
+    if !(1..=total_pages).any(keep) {
+        anyhow::bail!("page selection matches none of the {} pages", total_pages);
+    }
+    let pages_to_delete: Vec<u32> = (1..=total_pages).filter(|&p| !keep(p)).rev().collect();
+    for &page_num in &pages_to_delete {
+        doc.delete_pages(&[page_num]);
+    }
+
+    let mut out = Vec::new();
+    doc.save_to(&mut out).context("saving trimmed PDF")?;
+    Ok(out)
+}
+
 fn check_cached_splits(cache_dir: &Path, expected_total: u32) -> Result<Option<SplitResult>> {
     let meta_path = cache_dir.join("split_meta.json");
     if !meta_path.exists() {
@@ -297,16 +404,28 @@ fn save_split_metadata(cache_dir: &Path, total_pages: u32, ranges: &[(u32, u32)]
     Ok(())
 }
 
-fn compute_hash(data: &[u8]) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    let result = hasher.finalize();
-    result.iter().map(|b| format!("{:02x}", b)).collect()
+fn get_split_cache_dir() -> Result<PathBuf> {
+    Ok(crate::file_cache::cache_root().join("splits"))
 }
 
-fn get_split_cache_dir() -> Result<PathBuf> {
-    if let Some(cache_dir) = dirs::cache_dir() {
-        return Ok(cache_dir.join("datasheet-cli").join("splits"));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_page_ranges() {
+        assert_eq!(parse_page_ranges("9, 1-5,4-6").unwrap(), [(1, 6), (9, 9)]);
+        assert_eq!(parse_page_ranges("12-").unwrap(), [(12, u32::MAX)]);
+        assert!(parse_page_ranges("5-2").is_err());
+        assert!(parse_page_ranges("0").is_err());
+        assert!(parse_page_ranges("").is_err());
+    }
+
+    #[test]
+    fn test_compute_split_ranges() {
+        // Splits at the chapter nearest the midpoint
+        assert_eq!(compute_split_ranges(100, 2, &[(1, 45), (1, 60), (2, 50)]), [(1, 44), (45, 100)]);
+        assert_eq!(compute_split_ranges(2500, 3, &[]), [(1, 832), (833, 1665), (1666, 2500)]);
+        assert_eq!(compute_split_ranges(80, 1, &[(1, 40)]), [(1, 80)]);
     }
-    Ok(PathBuf::from(".cache").join("datasheet-cli").join("splits"))
 }
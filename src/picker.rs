@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Choosing one part when a lookup matches several.
+//!
+//! Commands that act on a single part (`mouser download`, `jlcpcb part`, ...)
+//! show a fuzzy-filtered list on the terminal: type to narrow it down, arrows
+//! to move, Enter to choose. With `--no-input`, or when stdin or stderr is not
+//! a terminal (scripts, the TUI's child processes), the first match is taken
+//! and named on stderr. The list is drawn on stderr so piped stdout stays clean.
+
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+use ratatui::{Terminal, TerminalOptions, Viewport};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Rows of matches shown at once.
+const MAX_ROWS: usize = 10;
+
+static NO_INPUT: AtomicBool = AtomicBool::new(false);
+
+/// Never prompt (`--no-input`); lookups take the first match.
+pub fn set_no_input(no_input: bool) {
+    NO_INPUT.store(no_input, Ordering::Relaxed);
+}
+
+/// Let the user choose one of `items` (one display line each) and return its
+/// index. `what` names the lookup, e.g. "Mouser matches for LM358".
+pub fn pick(what: &str, items: &[String]) -> Result<usize, String> {
+    match items.len() {
+        0 => return Err(format!("No {}", what)),
+        1 => return Ok(0),
+        _ => {}
+    }
+    let interactive = !NO_INPUT.load(Ordering::Relaxed)
+        && std::io::stdin().is_terminal()
+        && std::io::stderr().is_terminal();
+    if !interactive {
+        eprintln!("[PICK] {} {}; using the first: {}", items.len(), what, items[0]);
+        return Ok(0);
+    }
+
+    enable_raw_mode().map_err(|e| format!("Failed to start picker: {}", e))?;
+    let result = run(what, items);
+    let _ = disable_raw_mode();
+    let index = result?.ok_or_else(|| "Selection cancelled".to_string())?;
+    eprintln!("Selected: {}", items[index]);
+    Ok(index)
+}
+
+fn run(what: &str, items: &[String]) -> Result<Option<usize>, String> {
+    let height = items.len().min(MAX_ROWS) as u16 + 2;
+    let mut terminal = Terminal::with_options(
+        CrosstermBackend::new(std::io::stderr()),
+        TerminalOptions { viewport: Viewport::Inline(height) },
+    )
+    .map_err(|e| format!("Failed to start picker: {}", e))?;
+    let io_err = |e: std::io::Error| format!("Picker failed: {}", e);
+
+    let mut query = String::new();
+    let mut state = ListState::default().with_selected(Some(0));
+    let chosen = loop {
+        let matches = filter(&query, items);
+        if state.selected().is_none_or(|i| i >= matches.len()) {
+            state.select((!matches.is_empty()).then_some(0));
+        }
+        terminal
+            .draw(|frame| {
+                let [prompt, list, footer] =
+                    Layout::vertical([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)])
+                        .areas(frame.area());
+                let prompt_line = Line::from(vec![what.bold(), "> ".into(), query.as_str().into()]);
+                frame.render_widget(Paragraph::new(prompt_line), prompt);
+                let rows: Vec<ListItem> = matches.iter().map(|&i| ListItem::new(items[i].as_str())).collect();
+                frame.render_stateful_widget(
+                    List::new(rows).highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+                    list,
+                    &mut state,
+                );
+                frame.render_widget(
+                    Paragraph::new(format!(
+                        "  {}/{}  type to filter, \u{2191}\u{2193} move, Enter choose, Esc cancel",
+                        matches.len(),
+                        items.len()
+                    ))
+                    .dim(),
+                    footer,
+                );
+            })
+            .map_err(io_err)?;
+
+        let Event::Key(key) = event::read().map_err(io_err)? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => break None,
+            KeyCode::Char('c') if ctrl => break None,
+            KeyCode::Enter => {
+                if let Some(i) = state.selected().and_then(|i| matches.get(i)) {
+                    break Some(*i);
+                }
+            }
+            KeyCode::Up => state.select_previous(),
+            KeyCode::Char('p') if ctrl => state.select_previous(),
+            KeyCode::Down => state.select_next(),
+            KeyCode::Char('n') if ctrl => state.select_next(),
+            KeyCode::Backspace => {
+                query.pop();
+                state.select(Some(0));
+            }
+            KeyCode::Char(c) if !ctrl => {
+                query.push(c);
+                state.select(Some(0));
+            }
+            _ => {}
+        }
+    };
+    terminal.clear().map_err(io_err)?;
+    Ok(chosen)
+}
+
+/// Indices of the items matching `query`, best first; original order for ties.
+fn filter(query: &str, items: &[String]) -> Vec<usize> {
+    let mut scored: Vec<(i64, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| fuzzy_score(query, item).map(|score| (score, i)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Skim-style subsequence match: every query character must appear in order
+/// (ignoring case and spaces). Consecutive characters and matches at the start
+/// of a word score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + text[pos..].iter().position(|&c| c == q)?;
+        score += 1;
+        if last.is_some_and(|l| l + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_filter() {
+        let items: Vec<String> = [
+            "LM358DR  Texas Instruments  SOIC-8",
+            "LM358P  Texas Instruments  PDIP-8",
+            "LM2904DR  TI",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(filter("", &items), [0, 1, 2]);
+        assert_eq!(filter("358p", &items), [1]);
+        assert_eq!(filter("dr", &items), [0, 2]);
+        assert_eq!(filter("pdip", &items), [1]);
+        assert_eq!(fuzzy_score("xyz", "LM358"), None);
+        assert!(fuzzy_score("lm358", "LM358DR").unwrap() > fuzzy_score("lm358", "L M 3 5 8").unwrap());
+    }
+}
@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Chained extraction tasks.
+//!
+//! A TOML file lists the steps to run on one datasheet. A step's `uses` names
+//! earlier steps whose results are injected into its prompt, so e.g. the
+//! high-speed task works from the exact pin names the pinout task found.
+//! Custom prompts can place a result (or part of it, by JSON pointer) with a
+//! `{{step}}` or `{{step/json/pointer}}` placeholder; otherwise results are
+//! appended to the prompt.
+//!
+//! ```toml
+//! [[step]]
+//! task = "pinout"
+//!
+//! [[step]]
+//! task = "high-speed"
+//! uses = ["pinout"]
+//!
+//! [[step]]
+//! name = "usb-pins"
+//! task = "custom"
+//! uses = ["pinout"]
+//! prompt = "usb-pins.md"            # relative to this file; may contain {{pinout/packages}}
+//! schema = "usb-pins.schema.json"
+//! pages = "1-4"
+//! ```
+
+use anyhow::{Context, Result, anyhow};
+use clap::{Args, ValueEnum};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::extract::{self, ExtractArgs, ExtractTask, __DEFAULT__};
+use crate::llm::LlmProvider;
+use crate::prompts::PromptLang;
+
+#[derive(Args, Debug)]
+pub struct PipelineArgs {
+    /// TOML file listing the steps
+    pub config: PathBuf,
+
+    /// Datasheet PDF
+    pub pdf: PathBuf,
+
+    /// LLM provider (always Gemini)
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
+    pub provider: LlmProvider,
+
+    /// Model name (default: each task's default model; a step's `model` overrides it)
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
+    pub model: String,
+
+    /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Optional base URL override for Gemini API
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Output file (defaults to stdout)
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+
+    /// Show formatted (pretty-printed) JSON output
+    #[arg(long, short = 'f', visible_alias = "pretty", env = "DATASHEET_FORMATTED")]
+    pub formatted: bool,
+
+    /// Disable file caching (re-upload PDF every request)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Language of the datasheet: instruct the model in it (zh-CN, ja, de; default en)
+    #[arg(long, value_enum, default_value_t = PromptLang::En, env = extract::ENV_VAR_PROMPT_LANG)]
+    pub prompt_lang: PromptLang,
+}
+
+#[derive(Deserialize, Debug)]
+struct PipelineConfig {
+    #[serde(default, rename = "step")]
+    steps: Vec<StepConfig>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StepConfig {
+    task: String,
+    /// Key for the step's result (defaults to the task name)
+    name: Option<String>,
+    #[serde(default)]
+    uses: Vec<String>,
+    prompt: Option<String>,
+    schema: Option<String>,
+    pages: Option<String>,
+    model: Option<String>,
+}
+
+pub fn run(args: &PipelineArgs) -> Result<()> {
+    if !args.pdf.exists() {
+        return Err(Error::not_found(format!("PDF not found: {}", args.pdf.display())).into());
+    }
+    let text = std::fs::read_to_string(&args.config)
+        .with_context(|| format!("reading {}", args.config.display()))?;
+    let config: PipelineConfig =
+        toml::from_str(&text).with_context(|| format!("parsing {}", args.config.display()))?;
+    if config.steps.is_empty() {
+        return Err(anyhow!("{} has no [[step]] entries", args.config.display()));
+    }
+    let base = args.config.parent().unwrap_or(Path::new("."));
+
+    let mut steps = Vec::new();
+    for step in &config.steps {
+        let task = ExtractTask::from_str(&step.task, true)
+            .map_err(|_| Error::validation(format!("unknown task '{}'", step.task)))?;
+        let name = step.name.clone().unwrap_or_else(|| task.prompt().name.to_string());
+        if steps.iter().any(|(n, _, _)| *n == name) {
+            return Err(Error::validation(format!(
+                "more than one step is named '{}'; give them distinct `name`s",
+                name
+            ))
+            .into());
+        }
+        steps.push((name, task, step));
+    }
+    let deps: Vec<(String, Vec<String>)> =
+        steps.iter().map(|(name, _, step)| (name.clone(), step.uses.clone())).collect();
+    let order = order_steps(&deps).map_err(Error::validation)?;
+
+    let mut results = Map::new();
+    for i in order {
+        let (name, task, step) = &steps[i];
+        let inputs = step
+            .uses
+            .iter()
+            .map(|u| (u.clone(), results[u.as_str()].clone()))
+            .collect();
+        if step.uses.is_empty() {
+            eprintln!("[PIPELINE] Running {}", name);
+        } else {
+            eprintln!("[PIPELINE] Running {} with {}", name, step.uses.join(", "));
+        }
+        let extract_args = ExtractArgs {
+            provider: args.provider,
+            model: step.model.clone().unwrap_or_else(|| args.model.clone()),
+            api_key: args.api_key.clone(),
+            base_url: args.base_url.clone(),
+            formatted: args.formatted,
+            prompt: step.prompt.as_deref().map(|p| resolve_path(base, p)),
+            schema: step.schema.as_deref().map(|s| resolve_path(base, s)),
+            pages: step.pages.clone(),
+            no_cache: args.no_cache,
+            prompt_lang: args.prompt_lang,
+            inputs,
+            ..ExtractArgs::for_task(*task, args.pdf.clone())
+        };
+        let value = extract::extract_json(&extract_args)
+            .with_context(|| format!("running pipeline step '{}'", name))?;
+        results.insert(name.clone(), value);
+    }
+
+    extract::write_output(&Value::Object(results), args.out.as_deref(), args.formatted)
+}
+
+/// Paths in the config are relative to the config file; anything that is not
+/// a file there (e.g. an inline prompt) is passed through unchanged.
+pub(crate) fn resolve_path(base: &Path, value: &str) -> String {
+    let path = base.join(value);
+    if path.is_file() {
+        path.to_string_lossy().into_owned()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Order steps so each runs after the steps it uses, keeping file order where
+/// dependencies allow.
+fn order_steps(steps: &[(String, Vec<String>)]) -> Result<Vec<usize>, String> {
+    for (name, uses) in steps {
+        for dep in uses {
+            if !steps.iter().any(|(n, _)| n == dep) {
+                return Err(format!("step '{}' uses unknown step '{}'", name, dep));
+            }
+        }
+    }
+    let mut done = vec![false; steps.len()];
+    let mut order = Vec::with_capacity(steps.len());
+    while order.len() < steps.len() {
+        let ready = (0..steps.len()).find(|&i| {
+            !done[i]
+                && steps[i]
+                    .1
+                    .iter()
+                    .all(|dep| steps.iter().position(|(n, _)| n == dep).is_some_and(|j| done[j]))
+        });
+        match ready {
+            Some(i) => {
+                done[i] = true;
+                order.push(i);
+            }
+            None => {
+                let stuck: Vec<&str> = (0..steps.len())
+                    .filter(|&i| !done[i])
+                    .map(|i| steps[i].0.as_str())
+                    .collect();
+                return Err(format!("steps depend on each other in a cycle: {}", stuck.join(", ")));
+            }
+        }
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(name: &str, uses: &[&str]) -> (String, Vec<String>) {
+        (name.to_string(), uses.iter().map(|u| u.to_string()).collect())
+    }
+
+    #[test]
+    fn test_order_steps() {
+        let steps = vec![step("high-speed", &["pinout"]), step("pinout", &[]), step("power", &[])];
+        assert_eq!(order_steps(&steps).unwrap(), vec![1, 0, 2]);
+        assert!(order_steps(&[step("a", &["b"]), step("b", &["a"])]).unwrap_err().contains("cycle"));
+        assert!(order_steps(&[step("a", &["missing"])]).unwrap_err().contains("unknown step"));
+    }
+}
@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Machine-readable progress events.
+//!
+//! With `--progress json` every milestone is written to stderr as one JSON
+//! object per line, e.g.
+//!
+//! ```text
+//! {"bytes_sent":1048576,"event":"upload_progress","file":"STM32F407.pdf","percent":25,"total":4194304,"ts":1767225600123}
+//! ```
+//!
+//! Events: `task_started`, `upload_started`, `upload_progress`, `upload_done`,
+//! `download_started`, `download_progress`, `download_done`, `download_failed`,
+//! `request_sent`, `response_received` (with token counts), `task_done`,
+//! `task_failed`.
+//!
+//! Event lines always start with `{`; anything else on stderr is a human log
+//! line and can be ignored. Progress bars are hidden in this mode.
+
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressFormat {
+    /// Log lines and progress bars for people
+    Text,
+    /// NDJSON events on stderr for programs
+    Json,
+}
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Select the progress format (called once from `main`).
+pub fn set(format: ProgressFormat) {
+    JSON.store(format == ProgressFormat::Json, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Write one event line if JSON progress is enabled. `fields` must be an object.
+pub fn emit(event: &str, fields: Value) {
+    if !is_json() {
+        return;
+    }
+    let mut line = Map::new();
+    line.insert("event".into(), json!(event));
+    line.insert("ts".into(), json!(now_millis()));
+    if let Value::Object(fields) = fields {
+        line.extend(fields);
+    }
+    // One write per line so events from worker threads never interleave
+    let text = format!("{}\n", Value::Object(line));
+    let _ = std::io::stderr().lock().write_all(text.as_bytes());
+}
+
+/// Emits a `<kind>_progress` event each time another 5% of `total` is reached.
+pub struct Throttle {
+    kind: &'static str,
+    subject: (&'static str, String),
+    total: Option<u64>,
+    last_percent: Option<u64>,
+}
+
+impl Throttle {
+    /// `subject` names what is moving, e.g. `("file", "STM32F407.pdf")`.
+    pub fn new(kind: &'static str, subject: (&'static str, String), total: Option<u64>) -> Self {
+        Throttle { kind, subject, total, last_percent: None }
+    }
+
+    pub fn update(&mut self, done: u64, total: Option<u64>) {
+        if !is_json() {
+            return;
+        }
+        let total = total.or(self.total);
+        let percent = total.filter(|&t| t > 0).map(|t| (done * 100 / t).min(100));
+        // Unknown totals report every MiB instead
+        let step = percent.map(|p| p / 5).unwrap_or(done >> 20);
+        if self.last_percent == Some(step) {
+            return;
+        }
+        self.last_percent = Some(step);
+        let mut fields = json!({ "total": total, "percent": percent });
+        fields[self.subject.0] = json!(self.subject.1);
+        fields[if self.kind == "upload" { "bytes_sent" } else { "bytes" }] = json!(done);
+        emit(&format!("{}_progress", self.kind), fields);
+    }
+}
+
+/// Reader wrapper that reports upload progress as the HTTP client consumes it.
+pub struct ProgressReader<R> {
+    inner: R,
+    done: u64,
+    throttle: Throttle,
+}
+
+impl<R: Read> ProgressReader<R> {
+    pub fn new(inner: R, throttle: Throttle) -> Self {
+        ProgressReader { inner, done: 0, throttle }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.done += n as u64;
+        self.throttle.update(self.done, None);
+        Ok(n)
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,295 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Prompts and schemas from a shared source (`--prompt-source`).
+//!
+//! A source holds one directory per task, laid out like saved user tasks:
+//! `<task>/prompt.md` and an optional `<task>/schema.json`. A directory named
+//! after a built-in task (`pinout`, `power`, ...) replaces that task's prompt,
+//! and its schema when one is given. Any other name can be run with
+//! `extract custom --user-task NAME`.
+//!
+//! A source is one of:
+//! - a local directory, used in place
+//! - `git+<repository URL>[@<tag or commit>]`, cloned with `git`
+//! - an `http(s)://` URL of a ZIP archive of the layout, optionally pinned
+//!   with `#sha256=<hex>`; a single top-level directory (as in GitHub's
+//!   archive downloads) is allowed
+//!
+//! Remote sources are kept under the cache directory. Pinned ones are fetched
+//! once; unpinned ones are refreshed once a day, falling back to the cached
+//! copy when that fails. `--offline` uses the cached copy as is.
+
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use crate::error::Error;
+
+/// How long an unpinned remote source is used before it is fetched again.
+const REFRESH: Duration = Duration::from_secs(24 * 60 * 60);
+
+static SPEC: OnceLock<String> = OnceLock::new();
+static ROOT: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+
+#[derive(Debug, PartialEq)]
+enum Source {
+    Dir(PathBuf),
+    Git { url: String, rev: Option<String> },
+    Zip { url: String, sha256: Option<String> },
+}
+
+/// A task's prompt and, if the source has one, its schema.
+pub struct TaskPrompt {
+    pub prompt: String,
+    pub schema: Option<Value>,
+}
+
+/// Set the prompt source (called once from `main`).
+pub fn set(spec: String) {
+    let _ = SPEC.set(spec);
+}
+
+/// The source's replacement for `task`, if a source is set and has one.
+pub fn task(task: &str) -> Result<Option<TaskPrompt>> {
+    let Some(dir) = task_dir(task)? else {
+        return Ok(None);
+    };
+    let prompt_path = dir.join("prompt.md");
+    if !prompt_path.is_file() {
+        return Ok(None);
+    }
+    let prompt = fs::read_to_string(&prompt_path).map_err(|e| anyhow!("{}: {}", prompt_path.display(), e))?;
+    let schema_path = dir.join("schema.json");
+    let schema = match fs::read_to_string(&schema_path) {
+        Ok(text) => Some(serde_json::from_str(&text).map_err(|e| {
+            Error::validation(format!("{}: invalid JSON schema: {}", schema_path.display(), e))
+        })?),
+        Err(_) => None,
+    };
+    eprintln!("[PROMPTS] Using the {} prompt from {}", task, SPEC.get().map_or("", String::as_str));
+    Ok(Some(TaskPrompt { prompt, schema }))
+}
+
+/// Prompt and schema files for a user task from the source, if it has both.
+pub fn task_files(name: &str) -> Result<Option<(PathBuf, PathBuf)>> {
+    Ok(task_dir(name)?
+        .map(|dir| (dir.join("prompt.md"), dir.join("schema.json")))
+        .filter(|(prompt, schema)| prompt.is_file() && schema.is_file()))
+}
+
+fn task_dir(task: &str) -> Result<Option<PathBuf>> {
+    let Some(spec) = SPEC.get() else {
+        return Ok(None);
+    };
+    let root = ROOT.get_or_init(|| resolve(spec)).clone().map_err(|e| anyhow!(e))?;
+    Ok(Some(root.join(task)))
+}
+
+fn parse(spec: &str) -> Result<Source, String> {
+    if let Some(repo) = spec.strip_prefix("git+") {
+        // An '@' after the last '/' separates the revision; earlier ones are user names
+        let slash = repo.rfind('/').unwrap_or(0);
+        let (url, rev) = match repo[slash..].rfind('@') {
+            Some(at) => (&repo[..slash + at], Some(repo[slash + at + 1..].to_string())),
+            None => (repo, None),
+        };
+        if url.is_empty() || rev.as_deref() == Some("") {
+            return Err(format!("invalid git prompt source '{}'", spec));
+        }
+        return Ok(Source::Git { url: url.to_string(), rev });
+    }
+    if spec.starts_with("http://") || spec.starts_with("https://") {
+        let (url, sha256) = match spec.split_once("#sha256=") {
+            Some((url, hex)) if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                (url, Some(hex.to_ascii_lowercase()))
+            }
+            Some(_) => return Err(format!("invalid pin in '{}': expected #sha256=<64 hex digits>", spec)),
+            None => (spec, None),
+        };
+        return Ok(Source::Zip { url: url.to_string(), sha256 });
+    }
+    Ok(Source::Dir(PathBuf::from(spec)))
+}
+
+/// Make the source available locally and return the directory holding its tasks.
+fn resolve(spec: &str) -> Result<PathBuf, String> {
+    let source = parse(spec)?;
+    let cache = crate::file_cache::cache_root()
+        .join("prompt-sources")
+        .join(&format!("{:x}", Sha256::digest(spec.as_bytes()))[..16]);
+    match source {
+        Source::Dir(dir) if dir.is_dir() => Ok(dir),
+        Source::Dir(dir) => Err(format!("prompt source {} is not a directory", dir.display())),
+        Source::Git { url, rev } => {
+            let pinned = rev.is_some();
+            refresh(&cache, pinned, spec, || fetch_git(&url, rev.as_deref(), &cache))?;
+            Ok(cache)
+        }
+        Source::Zip { url, sha256 } => {
+            let pinned = sha256.is_some();
+            refresh(&cache, pinned, spec, || fetch_zip(&url, sha256.as_deref(), &cache))?;
+            Ok(archive_root(&cache))
+        }
+    }
+}
+
+/// Fetch a remote source into `cache` unless the copy there can be used.
+fn refresh(cache: &Path, pinned: bool, spec: &str, fetch: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+    let stamp = cache.with_extension("fetched");
+    let age = fs::metadata(&stamp)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|fetched| SystemTime::now().duration_since(fetched).ok());
+    let cached = cache.is_dir() && age.is_some();
+    if cached && (pinned || crate::offline::is_enabled() || age.is_some_and(|age| age < REFRESH)) {
+        return Ok(());
+    }
+    crate::offline::ensure_online(&format!("Fetching prompt source {}", spec))?;
+
+    eprintln!("[PROMPTS] Fetching {}", spec);
+    match fetch() {
+        Ok(()) => {
+            let _ = fs::write(&stamp, b"");
+            Ok(())
+        }
+        Err(e) if cached => {
+            eprintln!("[PROMPTS] Warning: could not refresh {}: {}; using the cached copy", spec, e);
+            Ok(())
+        }
+        Err(e) => Err(format!("fetching prompt source {}: {}", spec, e)),
+    }
+}
+
+fn fetch_git(url: &str, rev: Option<&str>, dir: &Path) -> Result<(), String> {
+    if !dir.join(".git").is_dir() {
+        let _ = fs::remove_dir_all(dir);
+        if let Some(parent) = dir.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("creating {}: {}", parent.display(), e))?;
+        }
+        git(None, &["clone", "--quiet", url, &dir.to_string_lossy()])?;
+    }
+    match rev {
+        Some(rev) => {
+            let commit = format!("{}^{{commit}}", rev);
+            if git(Some(dir), &["rev-parse", "--verify", "--quiet", &commit]).is_err() {
+                git(Some(dir), &["fetch", "--quiet", "--tags", "origin"])?;
+            }
+            git(Some(dir), &["checkout", "--quiet", "--detach", rev])
+        }
+        None => git(Some(dir), &["pull", "--quiet", "--ff-only"]),
+    }
+}
+
+fn git(dir: Option<&Path>, args: &[&str]) -> Result<(), String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    let output = command
+        .args(args)
+        .output()
+        .map_err(|e| format!("running git (needed for git+ prompt sources): {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("git {}: {}", args[0], String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+fn fetch_zip(url: &str, sha256: Option<&str>, dir: &Path) -> Result<(), String> {
+    let response = ureq::get(url)
+        .timeout(Duration::from_secs(60))
+        .call()
+        .map_err(|e| Error::from_ureq("prompt source", e).to_string())?;
+    let mut bytes = Vec::new();
+    response.into_reader().read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+
+    let digest = format!("{:x}", Sha256::digest(&bytes));
+    if let Some(expected) = sha256.filter(|&expected| expected != digest) {
+        return Err(format!("archive SHA-256 is {}, but the source is pinned to {}", digest, expected));
+    }
+
+    // Unpack next to the cache and swap it in, so a failure leaves the old copy intact
+    let tmp = dir.with_extension(format!("{}.tmp", std::process::id()));
+    let _ = fs::remove_dir_all(&tmp);
+    unpack(&bytes, &tmp).inspect_err(|_| {
+        let _ = fs::remove_dir_all(&tmp);
+    })?;
+    let _ = fs::remove_dir_all(dir);
+    fs::rename(&tmp, dir).map_err(|e| format!("replacing {}: {}", dir.display(), e))
+}
+
+fn unpack(bytes: &[u8], dir: &Path) -> Result<(), String> {
+    let mut archive =
+        zip::ZipArchive::new(std::io::Cursor::new(bytes)).map_err(|e| format!("not a ZIP archive: {}", e))?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(|e| format!("reading archive entry: {}", e))?;
+        // enclosed_name rejects absolute paths and `..` components
+        let Some(name) = file.enclosed_name() else { continue };
+        if file.is_dir() {
+            continue;
+        }
+        let path = dir.join(&name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("creating {}: {}", parent.display(), e))?;
+        }
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).map_err(|e| format!("reading {}: {}", name.display(), e))?;
+        fs::write(&path, data).map_err(|e| format!("writing {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// The directory holding the tasks: `dir` itself, or its only subdirectory when
+/// the archive wraps everything in one top-level folder.
+fn archive_root(dir: &Path) -> PathBuf {
+    let entries: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    match entries.as_slice() {
+        [only] if only.is_dir() && !only.join("prompt.md").is_file() => only.clone(),
+        _ => dir.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("git+git@github.com:acme/prompts.git@v1.2").unwrap(),
+            Source::Git { url: "git@github.com:acme/prompts.git".into(), rev: Some("v1.2".into()) }
+        );
+        assert_eq!(
+            parse("git+https://github.com/acme/prompts").unwrap(),
+            Source::Git { url: "https://github.com/acme/prompts".into(), rev: None }
+        );
+        let pin = "AB".repeat(32);
+        assert_eq!(
+            parse(&format!("https://example.com/prompts.zip#sha256={}", pin)).unwrap(),
+            Source::Zip { url: "https://example.com/prompts.zip".into(), sha256: Some(pin.to_ascii_lowercase()) }
+        );
+        assert!(parse("https://example.com/prompts.zip#sha256=abc").is_err());
+        assert_eq!(parse("./prompts").unwrap(), Source::Dir(PathBuf::from("./prompts")));
+    }
+
+    #[test]
+    fn test_archive_root() {
+        let dir = std::env::temp_dir().join(format!("datasheet-prompt-source-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("prompts-main/pinout")).unwrap();
+        assert_eq!(archive_root(&dir), dir.join("prompts-main"));
+        // A lone task directory is a task, not a wrapper
+        fs::write(dir.join("prompts-main/pinout/prompt.md"), "x").unwrap();
+        assert_eq!(archive_root(&dir.join("prompts-main")), dir.join("prompts-main"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,404 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Side-by-side comparison of prompt variants for one task.
+//!
+//! Every variant runs over the same PDFs with the task's schema, and the report
+//! shows for each variant how often its output matched the schema, how much of
+//! the schema it filled in, and which fields differ from the first variant.
+//! Unlike `bench`, no golden results are needed; this is for iterating on a
+//! prompt before the changes are worth labeling.
+
+use anyhow::{Context, Result, anyhow};
+use clap::Args;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use crate::bench::fields;
+use crate::extract::{self, ExtractArgs, ExtractTask, __DEFAULT__};
+use crate::llm::LlmProvider;
+
+/// Value of `--variant` that stands for the task's built-in prompt
+const BUILTIN: &str = "builtin";
+/// Differing fields listed per PDF in the text report
+const SHOWN_DIFF_FIELDS: usize = 10;
+
+#[derive(Args, Debug)]
+pub struct PromptTestArgs {
+    /// Task whose schema the variants share
+    #[arg(long, value_enum)]
+    pub task: ExtractTask,
+
+    /// Prompt file to test (repeat for each variant; "builtin" is the task's own prompt)
+    #[arg(long = "variant", required = true)]
+    pub variants: Vec<String>,
+
+    /// Datasheet PDFs to run every variant on
+    #[arg(required = true)]
+    pub pdfs: Vec<PathBuf>,
+
+    /// JSON schema file or inline JSON (required for the 'custom' task)
+    #[arg(long)]
+    pub schema: Option<String>,
+
+    /// Only send these pages to the model, e.g. "1-5,9,12-" (1-based, inclusive)
+    #[arg(long)]
+    pub pages: Option<String>,
+
+    /// LLM provider (always Gemini)
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
+    pub provider: LlmProvider,
+
+    /// Model name (default: the task's default model)
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
+    pub model: String,
+
+    /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Optional base URL override for Gemini API
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Disable file caching (re-upload PDF every request)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Output the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct VariantReport {
+    variant: String,
+    runs: usize,
+    failed: usize,
+    schema_valid: usize,
+    schema_validity: f64,
+    /// Share of the schema's fields filled in at least once, averaged over runs
+    field_coverage: f64,
+    /// Non-null fields extracted, summed over runs
+    fields: usize,
+    pdfs: Vec<RunReport>,
+}
+
+#[derive(Serialize, Debug)]
+struct RunReport {
+    pdf: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    schema_errors: Vec<String>,
+    coverage: f64,
+    fields: usize,
+    /// Fields only in this variant's output, compared with the first variant
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    added: Vec<String>,
+    /// Fields only in the first variant's output
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    missing: Vec<String>,
+}
+
+pub fn run(args: &PromptTestArgs) -> Result<()> {
+    let schema: Value = match &args.schema {
+        Some(schema) => {
+            let text = match std::fs::read_to_string(schema) {
+                Ok(text) => text,
+                Err(_) => schema.clone(),
+            };
+            serde_json::from_str(&text).context("parsing --schema as JSON")?
+        }
+        None if matches!(args.task, ExtractTask::Custom) => {
+            return Err(anyhow!("the 'custom' task needs --schema"));
+        }
+        None => args.task.prompt().schema,
+    };
+    let schema_text = serde_json::to_string(&schema)?;
+    let schema_fields = schema_paths(&schema);
+    let model = extract::resolve_model(&args.model, args.task.default_model());
+
+    let mut reports = Vec::new();
+    let mut baseline: Vec<Option<Value>> = Vec::new();
+    for (index, variant) in args.variants.iter().enumerate() {
+        let prompt = if variant == BUILTIN {
+            args.task.prompt().prompt.to_string()
+        } else {
+            std::fs::read_to_string(variant).with_context(|| format!("reading variant {}", variant))?
+        };
+        let mut report = VariantReport { variant: variant.clone(), ..VariantReport::default() };
+        for pdf in &args.pdfs {
+            eprintln!("[PROMPT-TEST] {} on {}", variant, pdf.display());
+            let extract_args = ExtractArgs {
+                provider: args.provider,
+                model: model.clone(),
+                api_key: args.api_key.clone(),
+                base_url: args.base_url.clone(),
+                prompt: Some(prompt.clone()),
+                schema: Some(schema_text.clone()),
+                pages: args.pages.clone(),
+                no_cache: args.no_cache,
+                ..ExtractArgs::for_task(ExtractTask::Custom, pdf.clone())
+            };
+            let output = extract::extract_json(&extract_args);
+            report.runs += 1;
+            let run = match output {
+                Ok(value) => {
+                    let mut schema_errors = Vec::new();
+                    check_schema(&schema, &value, "", &mut schema_errors);
+                    let filled = filled_paths(&value);
+                    let (added, missing) = match baseline.get(report.pdfs.len()) {
+                        Some(Some(first)) if index > 0 => diff_fields(first, &value),
+                        _ => (Vec::new(), Vec::new()),
+                    };
+                    if index == 0 {
+                        baseline.push(Some(value.clone()));
+                    }
+                    report.schema_valid += usize::from(schema_errors.is_empty());
+                    RunReport {
+                        pdf: pdf.clone(),
+                        error: None,
+                        schema_errors,
+                        coverage: coverage(&schema_fields, &filled),
+                        fields: fields(&value).values().sum(),
+                        added,
+                        missing,
+                    }
+                }
+                Err(e) => {
+                    eprintln!("[PROMPT-TEST] Failed: {:#}", e);
+                    if index == 0 {
+                        baseline.push(None);
+                    }
+                    report.failed += 1;
+                    RunReport {
+                        pdf: pdf.clone(),
+                        error: Some(format!("{:#}", e)),
+                        schema_errors: Vec::new(),
+                        coverage: 0.0,
+                        fields: 0,
+                        added: Vec::new(),
+                        missing: Vec::new(),
+                    }
+                }
+            };
+            report.pdfs.push(run);
+        }
+        report.schema_validity = report.schema_valid as f64 / report.runs as f64;
+        report.field_coverage = report.pdfs.iter().map(|r| r.coverage).sum::<f64>() / report.runs as f64;
+        report.fields = report.pdfs.iter().map(|r| r.fields).sum();
+        reports.push(report);
+    }
+
+    if args.json {
+        let report = serde_json::json!({
+            "task": args.task.prompt().name,
+            "model": model,
+            "schema_fields": schema_fields.len(),
+            "variants": reports,
+        });
+        println!("{}", crate::select::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    print_report(&reports, &args.variants[0]);
+    Ok(())
+}
+
+fn print_report(reports: &[VariantReport], baseline: &str) {
+    println!(
+        "{:<32} {:>5} {:>7} {:>13} {:>9} {:>7}",
+        "Variant", "Runs", "Failed", "Schema valid", "Coverage", "Fields"
+    );
+    for report in reports {
+        println!(
+            "{:<32} {:>5} {:>7} {:>12.0}% {:>8.0}% {:>7}",
+            report.variant,
+            report.runs,
+            report.failed,
+            report.schema_validity * 100.0,
+            report.field_coverage * 100.0,
+            report.fields
+        );
+    }
+    for report in reports {
+        for run in &report.pdfs {
+            for error in &run.schema_errors {
+                println!("\n{} on {}: schema: {}", report.variant, run.pdf.display(), error);
+            }
+        }
+    }
+    for report in reports.iter().skip(1) {
+        for run in &report.pdfs {
+            if run.added.is_empty() && run.missing.is_empty() {
+                continue;
+            }
+            println!(
+                "\n{} vs {} on {} ({} added, {} missing):",
+                report.variant,
+                baseline,
+                run.pdf.display(),
+                run.added.len(),
+                run.missing.len()
+            );
+            for field in run.missing.iter().take(SHOWN_DIFF_FIELDS) {
+                println!("  - {}", field);
+            }
+            for field in run.added.iter().take(SHOWN_DIFF_FIELDS) {
+                println!("  + {}", field);
+            }
+            let hidden = run.added.len().saturating_sub(SHOWN_DIFF_FIELDS)
+                + run.missing.len().saturating_sub(SHOWN_DIFF_FIELDS);
+            if hidden > 0 {
+                println!("  ... {} more (use --json for all)", hidden);
+            }
+        }
+    }
+}
+
+/// Fields only in `after` and fields only in `before`, as sorted `path = value` lines.
+fn diff_fields(before: &Value, after: &Value) -> (Vec<String>, Vec<String>) {
+    let (before, after) = (fields(before), fields(after));
+    let only = |a: &HashMap<String, usize>, b: &HashMap<String, usize>| {
+        let mut out: Vec<String> = a
+            .iter()
+            .filter(|(field, count)| b.get(*field).copied().unwrap_or(0) < **count)
+            .map(|(field, _)| field.clone())
+            .collect();
+        out.sort();
+        out
+    };
+    (only(&after, &before), only(&before, &after))
+}
+
+/// Leaf paths a schema defines (array positions as `*`).
+fn schema_paths(schema: &Value) -> BTreeSet<String> {
+    fn walk(schema: &Value, path: &mut String, out: &mut BTreeSet<String>) {
+        let len = path.len();
+        if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
+            for (key, child) in properties {
+                path.push('/');
+                path.push_str(key);
+                walk(child, path, out);
+                path.truncate(len);
+            }
+        } else if let Some(items) = schema.get("items") {
+            path.push_str("/*");
+            walk(items, path, out);
+            path.truncate(len);
+        } else {
+            out.insert(path.clone());
+        }
+    }
+    let mut out = BTreeSet::new();
+    walk(schema, &mut String::new(), &mut out);
+    out
+}
+
+/// Leaf paths with a non-null value in an output.
+fn filled_paths(value: &Value) -> BTreeSet<String> {
+    fields(value)
+        .keys()
+        .filter_map(|field| field.split_once(" = ").map(|(path, _)| path.to_string()))
+        .collect()
+}
+
+fn coverage(schema_fields: &BTreeSet<String>, filled: &BTreeSet<String>) -> f64 {
+    if schema_fields.is_empty() {
+        return 0.0;
+    }
+    schema_fields.intersection(filled).count() as f64 / schema_fields.len() as f64
+}
+
+/// Check the parts of JSON Schema the built-in schemas use: type, enum,
+/// required, properties, and items.
+fn check_schema(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let at = if path.is_empty() { "/" } else { path };
+    if let Some(kind) = schema.get("type") {
+        let allowed: Vec<&str> = match kind {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(types) => types.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        let matches = |t: &str| match t {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "number" => value.is_number(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => true,
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| matches(t)) {
+            errors.push(format!("{at}: expected {}, got {}", allowed.join(" or "), value));
+            return;
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(|e| e.as_array())
+        && !value.is_null()
+        && !options.contains(value)
+    {
+        errors.push(format!("{at}: {} is not one of {}", value, Value::Array(options.clone())));
+    }
+    if let Value::Object(map) = value {
+        for required in schema.get("required").and_then(|r| r.as_array()).into_iter().flatten() {
+            if let Some(key) = required.as_str()
+                && !map.contains_key(key)
+            {
+                errors.push(format!("{}/{}: required field is missing", path, key));
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
+            for (key, child) in map {
+                if let Some(child_schema) = properties.get(key) {
+                    check_schema(child_schema, child, &format!("{path}/{key}"), errors);
+                }
+            }
+        }
+    }
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            check_schema(item_schema, item, &format!("{path}/{i}"), errors);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_schema_checks_and_coverage() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "part_number": {"type": "string"},
+                "pins": {"type": "array", "items": {
+                    "type": "object",
+                    "properties": {
+                        "number": {"type": "integer"},
+                        "type": {"type": "string", "enum": ["power", "io"]}
+                    },
+                    "required": ["number"]
+                }}
+            },
+            "required": ["part_number", "pins"]
+        });
+        let value = json!({"pins": [{"number": 1, "type": "power"}, {"type": "analog"}]});
+        let mut errors = Vec::new();
+        check_schema(&schema, &value, "", &mut errors);
+        assert_eq!(
+            errors,
+            vec![
+                "/part_number: required field is missing",
+                "/pins/1/number: required field is missing",
+                "/pins/1/type: \"analog\" is not one of [\"power\",\"io\"]",
+            ]
+        );
+        assert!((coverage(&schema_paths(&schema), &filled_paths(&value)) - 2.0 / 3.0).abs() < 1e-9);
+    }
+}
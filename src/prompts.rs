@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-only
 // SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
 
+use clap::ValueEnum;
 use serde_json::{Value, json};
 
 pub struct PromptSpec {
@@ -24,18 +25,242 @@ impl PromptSpec {
     }
 }
 
+/// Insert the results of earlier tasks into a prompt.
+///
+/// A `{{name}}` or `{{name/json/pointer}}` placeholder naming an input is
+/// replaced with that input's JSON (or the part the pointer selects). Inputs no
+/// placeholder mentions are appended in a section of their own, which is how
+/// they reach the built-in prompts. Other `{{...}}` text is left alone.
+pub fn render_inputs(prompt: &str, inputs: &[(String, Value)]) -> Result<String, String> {
+    let mut out = String::with_capacity(prompt.len());
+    let mut used = vec![false; inputs.len()];
+    let mut rest = prompt;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let inner = rest[start + 2..start + len].trim();
+        let (name, pointer) = match inner.find('/') {
+            Some(i) => (&inner[..i], &inner[i..]),
+            None => (inner, ""),
+        };
+        out.push_str(&rest[..start]);
+        match inputs.iter().position(|(n, _)| n == name) {
+            Some(i) => {
+                let value = inputs[i]
+                    .1
+                    .pointer(pointer)
+                    .ok_or_else(|| format!("{{{{{inner}}}}}: {name} output has no '{pointer}'"))?;
+                out.push_str(&serde_json::to_string_pretty(value).map_err(|e| e.to_string())?);
+                used[i] = true;
+            }
+            None => out.push_str(&rest[start..start + len + 2]),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+
+    let unused: Vec<&(String, Value)> =
+        inputs.iter().zip(&used).filter(|(_, used)| !**used).map(|(input, _)| input).collect();
+    if !unused.is_empty() {
+        out.push_str(
+            "\n\n---\n\n## RESULTS FROM EARLIER TASKS\n\n\
+             These were extracted from this same document by earlier tasks. Treat them as ground \
+             truth: reuse their names, pin numbers, and values exactly instead of re-deriving them.\n",
+        );
+        for (name, value) in unused {
+            let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+            out.push_str(&format!("\n### {name}\n\n```json\n{json}\n```\n"));
+        }
+    }
+    Ok(out)
+}
+
+const PROMPT_ANALOG_PERFORMANCE: &str = include_str!("../prompts/extract-analog-performance.md");
 const PROMPT_APPLICATION_CIRCUIT: &str = include_str!("../prompts/extract-application-circuit.md");
 const PROMPT_BOOT_CONFIG: &str = include_str!("../prompts/extract-boot-config.md");
 const PROMPT_CHARACTERISTICS: &str = include_str!("../prompts/extract-characteristics.md");
+const PROMPT_CONNECTOR: &str = include_str!("../prompts/extract-connector.md");
+const PROMPT_CRYSTAL: &str = include_str!("../prompts/extract-crystal.md");
 const PROMPT_CUSTOM: &str = include_str!("../prompts/extract-custom.md");
+const PROMPT_DECOUPLING: &str = include_str!("../prompts/extract-decoupling.md");
 const PROMPT_DRC_RULES: &str = include_str!("../prompts/extract-drc-rules.md");
+const PROMPT_DRIVER: &str = include_str!("../prompts/extract-driver.md");
 const PROMPT_FEATURE_MATRIX: &str = include_str!("../prompts/extract-feature-matrix.md");
 const PROMPT_FOOTPRINT: &str = include_str!("../prompts/extract-footprint.md");
 const PROMPT_HIGH_SPEED: &str = include_str!("../prompts/extract-high-speed.md");
 const PROMPT_LAYOUT_CONSTRAINTS: &str = include_str!("../prompts/extract-layout-constraints.md");
+const PROMPT_MARKING: &str = include_str!("../prompts/extract-marking.md");
 const PROMPT_PINOUT: &str = include_str!("../prompts/extract-pinout.md");
+const PROMPT_PMIC_CONFIG: &str = include_str!("../prompts/extract-pmic-config.md");
 const PROMPT_POWER: &str = include_str!("../prompts/extract-power.md");
+const PROMPT_PROGRAMMING: &str = include_str!("../prompts/extract-programming.md");
 const PROMPT_REFERENCE_DESIGN: &str = include_str!("../prompts/extract-reference-design.md");
+const PROMPT_RF: &str = include_str!("../prompts/extract-rf.md");
+const PROMPT_SENSOR_MATH: &str = include_str!("../prompts/extract-sensor-math.md");
+const PROMPT_SOA: &str = include_str!("../prompts/extract-soa.md");
+const PROMPT_SUMMARY_FIRMWARE: &str = concat!(
+    include_str!("../prompts/summarize.md"),
+    include_str!("../prompts/summarize-firmware.md")
+);
+const PROMPT_SUMMARY_LAYOUT: &str = concat!(
+    include_str!("../prompts/summarize.md"),
+    include_str!("../prompts/summarize-layout.md")
+);
+const PROMPT_SUMMARY_PROCUREMENT: &str = concat!(
+    include_str!("../prompts/summarize.md"),
+    include_str!("../prompts/summarize-procurement.md")
+);
+const PROMPT_TOC_FIGURES: &str = include_str!("../prompts/extract-toc-figures.md");
+
+/// Language to instruct the model in (`--prompt-lang`), for datasheets that
+/// are not in English.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PromptLang {
+    /// English
+    #[default]
+    En,
+    /// Simplified Chinese
+    #[value(name = "zh-CN", alias = "zh-cn", alias = "zh")]
+    ZhCn,
+    /// Japanese
+    Ja,
+    /// German
+    De,
+}
+
+impl PromptLang {
+    pub fn code(self) -> &'static str {
+        match self {
+            PromptLang::En => "en",
+            PromptLang::ZhCn => "zh-CN",
+            PromptLang::Ja => "ja",
+            PromptLang::De => "de",
+        }
+    }
+
+    /// Guidance for reading a document in this language, appended to every prompt
+    fn section(self) -> Option<&'static str> {
+        match self {
+            PromptLang::En => None,
+            PromptLang::ZhCn => Some(include_str!("../prompts/zh-CN/language.md")),
+            PromptLang::Ja => Some(include_str!("../prompts/ja/language.md")),
+            PromptLang::De => Some(include_str!("../prompts/de/language.md")),
+        }
+    }
+}
+
+/// Translated task prompts. The JSON examples stay in English so the output
+/// keys do not change with the language.
+const LOCALIZED: &[(PromptLang, &str, &str)] = &[
+    (PromptLang::De, "characteristics", include_str!("../prompts/de/extract-characteristics.md")),
+    (PromptLang::De, "feature-matrix", include_str!("../prompts/de/extract-feature-matrix.md")),
+    (PromptLang::De, "pinout", include_str!("../prompts/de/extract-pinout.md")),
+    (PromptLang::De, "power", include_str!("../prompts/de/extract-power.md")),
+    (PromptLang::Ja, "characteristics", include_str!("../prompts/ja/extract-characteristics.md")),
+    (PromptLang::Ja, "feature-matrix", include_str!("../prompts/ja/extract-feature-matrix.md")),
+    (PromptLang::Ja, "pinout", include_str!("../prompts/ja/extract-pinout.md")),
+    (PromptLang::Ja, "power", include_str!("../prompts/ja/extract-power.md")),
+    (PromptLang::ZhCn, "characteristics", include_str!("../prompts/zh-CN/extract-characteristics.md")),
+    (PromptLang::ZhCn, "feature-matrix", include_str!("../prompts/zh-CN/extract-feature-matrix.md")),
+    (PromptLang::ZhCn, "pinout", include_str!("../prompts/zh-CN/extract-pinout.md")),
+    (PromptLang::ZhCn, "power", include_str!("../prompts/zh-CN/extract-power.md")),
+];
+
+/// The prompt for `task` in `lang`: its translation if there is one, otherwise
+/// `prompt` as given, followed by the language's reading guidance. Returns
+/// whether a translation was used.
+pub fn localize(task: &str, prompt: &str, lang: PromptLang) -> (String, bool) {
+    let Some(section) = lang.section() else {
+        return (prompt.to_string(), false);
+    };
+    let translation = LOCALIZED.iter().find(|(l, t, _)| *l == lang && *t == task).map(|(_, _, p)| *p);
+    let body = translation.unwrap_or(prompt);
+    (format!("{}\n\n{}", body.trim_end(), section), translation.is_some())
+}
+
+pub fn analog_performance() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "analog-performance",
+        "ADC/DAC/AFE performance across conditions",
+        PROMPT_ANALOG_PERFORMANCE,
+    );
+    let number = json!({"type": ["number", "null"]});
+    let text = json!({"type": ["string", "null"]});
+    let spec_row = json!({
+        "type": "object",
+        "properties": {
+            "parameter": {"type": "string", "enum": [
+                "enob", "snr", "sinad", "thd", "sfdr", "inl", "dnl", "offset_error",
+                "gain_error", "noise", "settling_time", "conversion_time", "missing_codes",
+                "crosstalk", "psrr", "cmrr", "input_bandwidth", "other"
+            ]},
+            "name": {"type": "string"},
+            "symbol": text,
+            "min": number,
+            "typ": number,
+            "max": number,
+            "unit": {"type": "string"},
+            "conditions": text,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["parameter", "name", "unit"]
+    });
+    let reference = json!({
+        "type": ["object", "null"],
+        "properties": {
+            "internal": text,
+            "external_voltage_min": number,
+            "external_voltage_max": number,
+            "pins": {"type": "array", "items": {"type": "string"}},
+            "input_current": text,
+            "decoupling": text,
+            "notes": {"type": "array", "items": {"type": "string"}},
+            "source_page": {"type": "integer"}
+        }
+    });
+    // Built in parts to stay within json!'s macro recursion limit
+    let converter = json!({
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "type": {"type": "string", "enum": ["adc", "dac", "afe", "sigma_delta_adc", "other"]},
+            "architecture": text,
+            "resolution_bits": number,
+            "channels": number,
+            "input_type": text,
+            "sample_rates": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "min": number,
+                        "typ": number,
+                        "max": number,
+                        "conditions": text,
+                        "source_page": {"type": "integer"}
+                    }
+                }
+            },
+            "specs": {"type": "array", "items": spec_row},
+            "reference": reference,
+            "analog_range": text,
+            "input_impedance": text,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["name", "type", "specs"]
+    });
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "source_pages": {"type": "array", "items": {"type": "integer"}},
+            "converters": {"type": "array", "items": converter}
+        },
+        "required": ["part_number", "converters"]
+    });
+    spec
+}
 
 pub fn application_circuit() -> PromptSpec {
     let mut spec = PromptSpec::new(
@@ -177,6 +402,339 @@ pub fn characteristics() -> PromptSpec {
     spec
 }
 
+pub fn connector() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "connector",
+        "Connector pinout, ratings, mating, and PCB layout",
+        PROMPT_CONNECTOR,
+    );
+    let number = json!({"type": ["number", "null"]});
+    let text = json!({"type": ["string", "null"]});
+    let strings = json!({"type": "array", "items": {"type": "string"}});
+    let pin_numbering = json!({
+        "type": "object",
+        "properties": {
+            "pin1_location": text,
+            "numbering": text,
+            "view": text,
+            "pins": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "number": {"type": "string"},
+                        "name": text,
+                        "function": text
+                    },
+                    "required": ["number"]
+                }
+            },
+            "mounting_pins": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "electrical": text
+                    },
+                    "required": ["name"]
+                }
+            }
+        }
+    });
+    let ratings = json!({
+        "type": "object",
+        "properties": {
+            "current_per_contact_a": number,
+            "current_derating": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "condition": {"type": "string"},
+                        "current_a": {"type": "number"}
+                    },
+                    "required": ["condition", "current_a"]
+                }
+            },
+            "voltage_rating_v": number,
+            "voltage_type": text,
+            "contact_resistance_mohm": number,
+            "insulation_resistance": text,
+            "dielectric_withstanding": text,
+            "mating_cycles": number,
+            "mating_force": text,
+            "unmating_force": text,
+            "temperature_min_c": number,
+            "temperature_max_c": number,
+            "wire_gauge": text,
+            "contact_plating": text
+        }
+    });
+    let mating = json!({
+        "type": "object",
+        "properties": {
+            "keying": text,
+            "latching": text,
+            "mates_with": strings,
+            "mated_height_mm": number
+        }
+    });
+    let pcb_layout = json!({
+        "type": "object",
+        "properties": {
+            "pad_type": text,
+            "signal_hole_diameter_mm": number,
+            "signal_pad_diameter_mm": number,
+            "signal_pad_size_mm": {
+                "type": ["object", "null"],
+                "properties": {"width": {"type": "number"}, "length": {"type": "number"}}
+            },
+            "mounting_holes": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "description": {"type": "string"},
+                        "diameter_mm": number,
+                        "plated": {"type": ["boolean", "null"]}
+                    },
+                    "required": ["description"]
+                }
+            },
+            "mounting_pads": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "description": {"type": "string"},
+                        "width": number,
+                        "length": number
+                    },
+                    "required": ["description"]
+                }
+            },
+            "dimension_formulas": strings,
+            "board_thickness": text,
+            "keep_out": strings,
+            "notes": strings,
+            "source_page": {"type": "integer"}
+        }
+    });
+    let variant = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "positions": number,
+            "notes": text
+        },
+        "required": ["part_number"]
+    });
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "source_pages": {"type": "array", "items": {"type": "integer"}},
+            "series": text,
+            "connector_type": text,
+            "gender": text,
+            "mounting": text,
+            "positions": {"type": "array", "items": {"type": "integer"}},
+            "rows": number,
+            "pitch_mm": number,
+            "row_pitch_mm": number,
+            "variants": {"type": "array", "items": variant},
+            "pin_numbering": pin_numbering,
+            "ratings": ratings,
+            "mating": mating,
+            "pcb_layout": pcb_layout
+        },
+        "required": ["part_number", "pitch_mm", "pin_numbering", "ratings", "pcb_layout"]
+    });
+    spec
+}
+
+pub fn crystal() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "crystal",
+        "Crystal/oscillator requirements and load capacitors",
+        PROMPT_CRYSTAL,
+    );
+    let number = json!({"type": ["number", "null"]});
+    let text = json!({"type": ["string", "null"]});
+    // Built in two parts to stay within json!'s macro recursion limit
+    let oscillator = json!({
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "pins": {"type": "array", "items": {"type": "string"}},
+            "type": {"type": "string", "enum": [
+                "crystal", "ceramic_resonator", "external_clock", "crystal_or_external"
+            ]},
+            "frequency_min_hz": number,
+            "frequency_max_hz": number,
+            "frequency_typ_hz": number,
+            "frequency_tolerance_ppm": number,
+            "frequency_stability_ppm": number,
+            "esr_max_ohm": number,
+            "esr_limits": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "condition": {"type": "string"},
+                        "esr_max_ohm": {"type": "number"}
+                    },
+                    "required": ["condition", "esr_max_ohm"]
+                }
+            },
+            "load_capacitance_pf": number,
+            "load_capacitance_min_pf": number,
+            "load_capacitance_max_pf": number,
+            "shunt_capacitance_max_pf": number,
+            "drive_level_max_uw": number,
+            "gm_crit_max": number,
+            "startup_time_max_ms": number,
+            "internal_load_capacitance_pf": number,
+            "pin_capacitance_pf": number,
+            "stray_capacitance_pf": number,
+            "load_capacitor_calculation": {
+                "type": ["object", "null"],
+                "properties": {
+                    "formula": {"type": "string"},
+                    "variables": {"type": "object", "additionalProperties": {"type": "string"}},
+                    "recommended_values": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "value_pf": {"type": "number"},
+                                "condition": text
+                            },
+                            "required": ["value_pf"]
+                        }
+                    },
+                    "notes": {"type": "array", "items": {"type": "string"}},
+                    "source_page": {"type": "integer"}
+                },
+                "required": ["formula"]
+            },
+            "external_clock": {
+                "type": ["object", "null"],
+                "properties": {
+                    "input_high_voltage": text,
+                    "input_low_voltage": text,
+                    "duty_cycle": text,
+                    "rise_fall_time": text
+                }
+            },
+            "source_text": {"type": "string"},
+            "source_page": {"type": "integer"}
+        },
+        "required": ["name", "pins", "type"]
+    });
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "source_pages": {"type": "array", "items": {"type": "integer"}},
+            "oscillators": {"type": "array", "items": oscillator},
+            "layout_guidelines": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "text": {"type": "string"},
+                        "source_page": {"type": "integer"}
+                    },
+                    "required": ["text"]
+                }
+            }
+        },
+        "required": ["part_number", "oscillators"]
+    });
+    spec
+}
+
+pub fn decoupling() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "decoupling",
+        "Decoupling and bypass capacitor recommendations",
+        PROMPT_DECOUPLING,
+    );
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "source_pages": {"type": "array", "items": {"type": "integer"}},
+            "rails": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "rail_name": {"type": "string"},
+                        "pins": {"type": "array", "items": {"type": "string"}},
+                        "voltage": {"type": ["string", "null"]},
+                        "capacitors": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "role": {"type": "string", "enum": [
+                                        "bypass", "bulk", "input", "output", "filter", "other"
+                                    ]},
+                                    "value": {"type": "string"},
+                                    "quantity": {"type": ["integer", "string"]},
+                                    "dielectric": {"type": ["string", "null"]},
+                                    "voltage_rating": {"type": ["string", "null"]},
+                                    "package": {"type": ["string", "null"]},
+                                    "esr": {"type": ["string", "null"]},
+                                    "effective_capacitance": {"type": ["string", "null"]},
+                                    "placement": {"type": ["string", "null"]},
+                                    "max_distance": {"type": ["string", "null"]},
+                                    "source_text": {"type": "string"},
+                                    "source_page": {"type": "integer"}
+                                },
+                                "required": ["role", "value", "quantity"]
+                            }
+                        },
+                        "source_page": {"type": "integer"}
+                    },
+                    "required": ["rail_name", "capacitors"]
+                }
+            },
+            "bulk_requirements": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "rail_name": {"type": "string"},
+                        "min_capacitance": {"type": ["string", "null"]},
+                        "max_capacitance": {"type": ["string", "null"]},
+                        "condition": {"type": ["string", "null"]},
+                        "source_text": {"type": "string"},
+                        "source_page": {"type": "integer"}
+                    },
+                    "required": ["rail_name"]
+                }
+            },
+            "placement_guidelines": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "text": {"type": "string"},
+                        "source_page": {"type": "integer"}
+                    },
+                    "required": ["text"]
+                }
+            }
+        },
+        "required": ["part_number", "rails"]
+    });
+    spec
+}
+
 pub fn drc_rules() -> PromptSpec {
     let mut spec = PromptSpec::new("drc-rules", "PCB design rule constraints", PROMPT_DRC_RULES);
     spec.schema = json!({
@@ -193,6 +751,141 @@ pub fn drc_rules() -> PromptSpec {
     spec
 }
 
+pub fn driver() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "driver",
+        "Motor/gate driver: gate drive, dead time, bootstrap, current sense, protections",
+        PROMPT_DRIVER,
+    );
+    let text = json!({"type": ["string", "null"]});
+    let strings = json!({"type": "array", "items": {"type": "string"}});
+    let options = json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "setting": {"type": "string"},
+                "result": {"type": "string"}
+            },
+            "required": ["setting", "result"]
+        }
+    });
+    let gate_drive = json!({
+        "type": ["object", "null"],
+        "properties": {
+            "source_current": text,
+            "sink_current": text,
+            "gate_drive_voltage": text,
+            "max_gate_charge": text,
+            "charge_pump": text,
+            "slew_rate_control": text
+        }
+    });
+    let dead_time = json!({
+        "type": ["object", "null"],
+        "properties": {
+            "fixed": text,
+            "settings": strings,
+            "configured_by": text,
+            "formula": text,
+            "default": text
+        }
+    });
+    let bootstrap = json!({
+        "type": ["object", "null"],
+        "properties": {
+            "required": {"type": ["boolean", "null"]},
+            "recommended_capacitor": text,
+            "sizing_formula": text,
+            "diode": text,
+            "min_low_side_on_time": text,
+            "max_duty_cycle": text
+        }
+    });
+    let current_sense = json!({
+        "type": ["object", "null"],
+        "properties": {
+            "method": text,
+            "amplifier_count": {"type": ["integer", "null"]},
+            "gain_options": strings,
+            "gain_configured_by": text,
+            "default_gain": text,
+            "output_range": text,
+            "accuracy": text,
+            "current_regulation": text
+        }
+    });
+    let protection = json!({
+        "type": "object",
+        "properties": {
+            "type": {"type": "string", "enum": [
+                "uvlo", "ovp", "ocp", "vds_monitor", "short_circuit", "thermal_warning",
+                "thermal_shutdown", "gate_fault", "charge_pump_uvlo", "other"
+            ]},
+            "name": {"type": "string"},
+            "threshold": text,
+            "deglitch": text,
+            "response": text,
+            "configured_by": text,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["type", "name"]
+    });
+    let config_pin = json!({
+        "type": "object",
+        "properties": {
+            "pin": {"type": "string"},
+            "function": {"type": "string"},
+            "options": options
+        },
+        "required": ["pin", "function"]
+    });
+    let config_register = json!({
+        "type": "object",
+        "properties": {
+            "address": {"type": "string"},
+            "name": {"type": "string"},
+            "fields": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "bits": {"type": "string"},
+                        "default": text,
+                        "description": text
+                    },
+                    "required": ["name", "bits"]
+                }
+            }
+        },
+        "required": ["address", "name"]
+    });
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "source_pages": {"type": "array", "items": {"type": "integer"}},
+            "driver_type": text,
+            "integrated_fets": {"type": ["boolean", "null"]},
+            "channels": {"type": ["integer", "null"]},
+            "supply_voltage": text,
+            "output_current": text,
+            "rds_on": text,
+            "control_interface": strings,
+            "gate_drive": gate_drive,
+            "dead_time": dead_time,
+            "bootstrap": bootstrap,
+            "current_sense": current_sense,
+            "protections": {"type": "array", "items": protection},
+            "config_pins": {"type": "array", "items": config_pin},
+            "config_registers": {"type": "array", "items": config_register}
+        },
+        "required": ["part_number", "protections"]
+    });
+    spec
+}
+
 pub fn feature_matrix() -> PromptSpec {
     let mut spec = PromptSpec::new(
         "feature-matrix",
@@ -205,7 +898,14 @@ pub fn feature_matrix() -> PromptSpec {
             "part_number_decoding": {"type": "object", "additionalProperties": true},
             "variants": {
                 "type": "array",
-                "items": {"type": "object"}
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "root_part_number": {"type": "string"},
+                        "orderable_part_numbers": {"type": "array", "items": {"type": "string"}}
+                    },
+                    "additionalProperties": true
+                }
             },
             "interface_support_summary": {"type": "object", "additionalProperties": true}
         },
@@ -279,16 +979,45 @@ pub fn layout_constraints() -> PromptSpec {
     spec
 }
 
-pub fn pinout() -> PromptSpec {
-    let mut spec = PromptSpec::new("pinout", "Pinout and configuration", PROMPT_PINOUT);
-    // Keep schema shallow to avoid Gemini API nesting depth limits
+pub fn marking() -> PromptSpec {
+    let mut spec = PromptSpec::new("marking", "Topside device marking", PROMPT_MARKING);
     spec.schema = json!({
         "type": "object",
         "properties": {
-            "part_details": {
-                "type": "object",
-                "additionalProperties": true
-            },
+            "part_number": {"type": "string"},
+            "source_pages": {"type": "array", "items": {"type": "integer"}},
+            "markings": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "orderable_part_number": {"type": "string"},
+                        "package": {"type": "string"},
+                        "marking_lines": {"type": "array", "items": {"type": "string"}},
+                        "legend": {"type": "object", "additionalProperties": true},
+                        "pin1_indicator": {"type": ["string", "null"]},
+                        "source_page": {"type": "integer"}
+                    },
+                    "required": ["orderable_part_number", "marking_lines"]
+                }
+            },
+            "notes": {"type": "array", "items": {"type": "string"}}
+        },
+        "required": ["part_number", "markings"]
+    });
+    spec
+}
+
+pub fn pinout() -> PromptSpec {
+    let mut spec = PromptSpec::new("pinout", "Pinout and configuration", PROMPT_PINOUT);
+    // Keep schema shallow to avoid Gemini API nesting depth limits
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_details": {
+                "type": "object",
+                "additionalProperties": true
+            },
             "packages": {
                 "type": "array",
                 "items": {
@@ -309,6 +1038,138 @@ pub fn pinout() -> PromptSpec {
     spec
 }
 
+pub fn pmic_config() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "pmic-config",
+        "PMIC/charger configuration: charge profile, rails, straps, registers",
+        PROMPT_PMIC_CONFIG,
+    );
+    let text = json!({"type": ["string", "null"]});
+    let page = json!({"type": "integer"});
+    let charge_profile = json!({
+        "type": ["object", "null"],
+        "properties": {
+            "chemistry": {"type": "array", "items": {"type": "string"}},
+            "cells": text,
+            "stages": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "stage": {"type": "string"},
+                        "entry_condition": text,
+                        "current": text,
+                        "voltage": text,
+                        "configurable": text,
+                        "default": text
+                    },
+                    "required": ["stage"]
+                }
+            },
+            "charge_voltage": text,
+            "charge_current": text,
+            "termination_current": text,
+            "safety_timers": text,
+            "thermal_regulation": text,
+            "source_page": page
+        }
+    });
+    let rail = json!({
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "type": {"type": "string"},
+            "output_voltage_range": text,
+            "default_voltage": text,
+            "max_current": text,
+            "enabled_by_default": {"type": ["boolean", "null"]},
+            "configured_by": text,
+            "sequence_slot": text,
+            "source_page": page
+        },
+        "required": ["name", "type"]
+    });
+    let strap_pin = json!({
+        "type": "object",
+        "properties": {
+            "pin": {"type": "string"},
+            "function": {"type": "string"},
+            "options": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "setting": {"type": "string"},
+                        "result": {"type": "string"}
+                    },
+                    "required": ["setting", "result"]
+                }
+            },
+            "sampled_when": text,
+            "source_page": page
+        },
+        "required": ["pin", "function", "options"]
+    });
+    let interface = json!({
+        "type": ["object", "null"],
+        "properties": {
+            "type": {"type": "string"},
+            "addresses": {"type": "array", "items": {"type": "string"}},
+            "max_clock": text,
+            "notes": text,
+            "source_page": page
+        }
+    });
+    let register = json!({
+        "type": "object",
+        "properties": {
+            "address": {"type": "string"},
+            "name": {"type": "string"},
+            "reset_value": text,
+            "fields": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "bits": {"type": "string"},
+                        "access": text,
+                        "default": text,
+                        "description": text
+                    },
+                    "required": ["name", "bits"]
+                }
+            },
+            "source_page": page
+        },
+        "required": ["address", "name"]
+    });
+    let otp_variant = json!({
+        "type": "object",
+        "properties": {
+            "variant": {"type": "string"},
+            "settings": {"type": "object", "additionalProperties": {"type": "string"}},
+            "source_page": page
+        },
+        "required": ["variant", "settings"]
+    });
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "source_pages": {"type": "array", "items": {"type": "integer"}},
+            "charge_profile": charge_profile,
+            "rails": {"type": "array", "items": rail},
+            "strap_pins": {"type": "array", "items": strap_pin},
+            "interface": interface,
+            "registers": {"type": "array", "items": register},
+            "otp_defaults": {"type": "array", "items": otp_variant}
+        },
+        "required": ["part_number", "rails"]
+    });
+    spec
+}
+
 pub fn power() -> PromptSpec {
     let mut spec = PromptSpec::new("power", "Power requirements", PROMPT_POWER);
     spec.schema = json!({
@@ -329,6 +1190,119 @@ pub fn power() -> PromptSpec {
     spec
 }
 
+pub fn programming() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "programming",
+        "Bootloader, flash programming, and debug security",
+        PROMPT_PROGRAMMING,
+    );
+    let text = json!({"type": ["string", "null"]});
+    let strings = json!({"type": "array", "items": {"type": "string"}});
+    let interface = json!({
+        "type": "object",
+        "properties": {
+            "type": {"type": "string", "enum": [
+                "swd", "jtag", "uart_bootloader", "usb_dfu", "i2c_bootloader", "spi_bootloader",
+                "can_bootloader", "icsp", "updi", "pdi", "spi_isp", "other"
+            ]},
+            "name": {"type": "string"},
+            "pins": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "pin": {"type": "string"},
+                        "signal": {"type": "string"}
+                    },
+                    "required": ["pin", "signal"]
+                }
+            },
+            "protocol": text,
+            "baud_or_clock": text,
+            "in_boot_rom": {"type": ["boolean", "null"]},
+            "requirements": strings,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["type", "name", "pins"]
+    });
+    let entry = json!({
+        "type": "object",
+        "properties": {
+            "method": {"type": "string"},
+            "conditions": {"type": "string"},
+            "timing": text,
+            "exit": text,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["method", "conditions"]
+    });
+    let flash = json!({
+        "type": ["object", "null"],
+        "properties": {
+            "memories": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "size": text,
+                        "page_or_sector_size": text,
+                        "endurance_cycles": {"type": ["number", "null"]},
+                        "retention": text
+                    },
+                    "required": ["name"]
+                }
+            },
+            "supply_voltage": text,
+            "programming_voltage": text,
+            "timings": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "operation": {"type": "string"},
+                        "typ": text,
+                        "max": text,
+                        "conditions": text
+                    },
+                    "required": ["operation"]
+                }
+            },
+            "programming_width": text,
+            "source_page": {"type": "integer"}
+        }
+    });
+    let security = json!({
+        "type": "object",
+        "properties": {
+            "feature": {"type": "string"},
+            "type": {"type": "string", "enum": [
+                "readout_protection", "debug_disable", "write_protection", "secure_boot",
+                "encryption", "password_unlock", "other"
+            ]},
+            "levels": strings,
+            "reversible": {"type": ["boolean", "null"]},
+            "unlock": text,
+            "configured_by": text,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["feature", "type"]
+    });
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "source_pages": {"type": "array", "items": {"type": "integer"}},
+            "interfaces": {"type": "array", "items": interface},
+            "bootloader_entry": {"type": "array", "items": entry},
+            "flash_programming": flash,
+            "security": {"type": "array", "items": security}
+        },
+        "required": ["part_number", "interfaces", "bootloader_entry", "security"]
+    });
+    spec
+}
+
 pub fn reference_design() -> PromptSpec {
     let mut spec = PromptSpec::new(
         "reference-design",
@@ -353,6 +1327,467 @@ pub fn reference_design() -> PromptSpec {
     spec
 }
 
+pub fn rf() -> PromptSpec {
+    let mut spec = PromptSpec::new("rf", "RF bands, TX power, sensitivity, antenna", PROMPT_RF);
+    let number = json!({"type": ["number", "null"]});
+    let text = json!({"type": ["string", "null"]});
+    let strings = json!({"type": "array", "items": {"type": "string"}});
+    let band = json!({
+        "type": "object",
+        "properties": {
+            "protocol": {"type": "string"},
+            "frequency_min_mhz": number,
+            "frequency_max_mhz": number,
+            "channels": text,
+            "channel_spacing": text,
+            "modulation": strings,
+            "data_rates": strings,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["protocol"]
+    });
+    let tx_row = json!({
+        "type": "object",
+        "properties": {
+            "protocol": {"type": "string"},
+            "mode": text,
+            "min": number,
+            "typ": number,
+            "max": number,
+            "programmable_range": text,
+            "current_ma": number,
+            "conditions": text,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["protocol"]
+    });
+    let rx_row = json!({
+        "type": "object",
+        "properties": {
+            "protocol": {"type": "string"},
+            "mode": text,
+            "min": number,
+            "typ": number,
+            "max": number,
+            "error_criterion": text,
+            "current_ma": number,
+            "conditions": text,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["protocol"]
+    });
+    let rx_other = json!({
+        "type": "object",
+        "properties": {
+            "parameter": {"type": "string"},
+            "min": number,
+            "typ": number,
+            "max": number,
+            "unit": {"type": "string"},
+            "conditions": text,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["parameter"]
+    });
+    let antenna = json!({
+        "type": ["object", "null"],
+        "properties": {
+            "pins": strings,
+            "type": text,
+            "impedance_ohm": number,
+            "optimal_load_impedance": text,
+            "matching_network": text,
+            "matching_components": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "designator": {"type": "string"},
+                        "value": {"type": "string"},
+                        "notes": text
+                    },
+                    "required": ["designator", "value"]
+                }
+            },
+            "layout_guidelines": strings,
+            "source_page": {"type": "integer"}
+        }
+    });
+    let regulatory = json!({
+        "type": "object",
+        "properties": {
+            "region_or_body": {"type": "string"},
+            "id": text,
+            "notes": text,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["region_or_body"]
+    });
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "source_pages": {"type": "array", "items": {"type": "integer"}},
+            "bands": {"type": "array", "items": band},
+            "tx_power": {"type": "array", "items": tx_row},
+            "rx_sensitivity": {"type": "array", "items": rx_row},
+            "rx_other": {"type": "array", "items": rx_other},
+            "antenna": antenna,
+            "regulatory": {"type": "array", "items": regulatory}
+        },
+        "required": ["part_number", "bands"]
+    });
+    spec
+}
+
+pub fn sensor_math() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "sensor-math",
+        "Sensor conversion, calibration, and compensation formulas",
+        PROMPT_SENSOR_MATH,
+    );
+    let text = json!({"type": ["string", "null"]});
+    let page = json!({"type": "integer"});
+    let registers = json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "address": {"type": "string"},
+                "bits": {"type": "string"},
+                "position": {"type": "string", "enum": ["msb", "lsb", "xlsb"]}
+            },
+            "required": ["address", "bits"]
+        }
+    });
+    let quantity = json!({
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "unit": {"type": "string"},
+            "range": text,
+            "resolution": text
+        },
+        "required": ["name", "unit"]
+    });
+    let raw_value = json!({
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "datasheet_name": text,
+            "registers": registers,
+            "width_bits": {"type": "integer"},
+            "signed": {"type": "boolean"},
+            "assembly": {"type": "string"},
+            "source_page": page
+        },
+        "required": ["name", "registers", "width_bits", "signed", "assembly"]
+    });
+    let coefficient = json!({
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "registers": registers,
+            "type": {"type": "string"},
+            "assembly": {"type": "string"},
+            "source_page": page
+        },
+        "required": ["name", "registers", "type", "assembly"]
+    });
+    let constant = json!({
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "value": {"type": "number"},
+            "unit": text,
+            "condition": text,
+            "source_page": page
+        },
+        "required": ["name", "value"]
+    });
+    let conversion = json!({
+        "type": "object",
+        "properties": {
+            "quantity": {"type": "string"},
+            "output_unit": {"type": "string"},
+            "output_scale": {"type": "number"},
+            "arithmetic": {"type": "string", "enum": ["integer_32", "integer_64", "float", "double"]},
+            "depends_on": {"type": "array", "items": {"type": "string"}},
+            "steps": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "target": {"type": "string"},
+                        "type": {"type": "string"},
+                        "expression": {"type": "string"},
+                        "comment": text
+                    },
+                    "required": ["target", "type", "expression"]
+                }
+            },
+            "result": {"type": "string"},
+            "invalid_when": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "condition": {"type": "string"},
+                        "return": {"type": "string"}
+                    },
+                    "required": ["condition", "return"]
+                }
+            },
+            "source_text": text,
+            "source_page": page
+        },
+        "required": ["quantity", "output_unit", "arithmetic", "steps", "result"]
+    });
+    let setting = json!({
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "register": text,
+            "bits": text,
+            "options": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "value": {"type": "string"},
+                        "meaning": {"type": "string"},
+                        "affects": {"type": "array", "items": {"type": "string"}}
+                    },
+                    "required": ["value", "meaning"]
+                }
+            }
+        },
+        "required": ["name", "options"]
+    });
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "source_pages": {"type": "array", "items": {"type": "integer"}},
+            "quantities": {"type": "array", "items": quantity},
+            "raw_values": {"type": "array", "items": raw_value},
+            "calibration": {"type": "array", "items": coefficient},
+            "constants": {"type": "array", "items": constant},
+            "conversions": {"type": "array", "items": conversion},
+            "settings": {"type": "array", "items": setting}
+        },
+        "required": ["part_number", "quantities", "raw_values", "conversions"]
+    });
+    spec
+}
+
+pub fn soa() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "soa",
+        "Power dissipation, derating, and safe operating area",
+        PROMPT_SOA,
+    );
+    let number = json!({"type": ["number", "null"]});
+    let text = json!({"type": ["string", "null"]});
+    let points = json!({
+        "type": "array",
+        "items": {"type": "array", "items": {"type": "number"}, "minItems": 2, "maxItems": 2}
+    });
+    let axis = json!({
+        "type": "object",
+        "properties": {
+            "parameter": {"type": "string"},
+            "unit": {"type": "string"},
+            "scale": {"type": "string", "enum": ["log", "linear"]}
+        },
+        "required": ["parameter", "unit"]
+    });
+    let rating = json!({
+        "type": "object",
+        "properties": {
+            "value_w": {"type": "number"},
+            "reference": {"type": "string"},
+            "reference_temp_c": number,
+            "package": text,
+            "derating_w_per_c": number,
+            "conditions": text,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["value_w", "reference"]
+    });
+    let derating = json!({
+        "type": "object",
+        "properties": {
+            "parameter": {"type": "string"},
+            "unit": {"type": "string"},
+            "temperature_reference": {"type": "string"},
+            "conditions": text,
+            "source": {"type": "string", "enum": ["table", "graph"]},
+            "sampled": {"type": "boolean"},
+            "points": points,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["parameter", "unit", "points"]
+    });
+    let curve = json!({
+        "type": "object",
+        "properties": {
+            "label": {"type": "string"},
+            "pulse_width_s": number,
+            "sampled": {"type": "boolean"},
+            "points": points
+        },
+        "required": ["label", "points"]
+    });
+    let soa = json!({
+        "type": "object",
+        "properties": {
+            "type": {"type": "string"},
+            "conditions": text,
+            "x_axis": axis,
+            "y_axis": axis,
+            "curves": {"type": "array", "items": curve},
+            "limits": {"type": "array", "items": {"type": "string"}},
+            "source_page": {"type": "integer"}
+        },
+        "required": ["type", "x_axis", "y_axis", "curves"]
+    });
+    let transient = json!({
+        "type": "object",
+        "properties": {
+            "reference": {"type": "string"},
+            "duty_cycle": text,
+            "sampled": {"type": "boolean"},
+            "points": points,
+            "source_page": {"type": "integer"}
+        },
+        "required": ["reference", "points"]
+    });
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "source_pages": {"type": "array", "items": {"type": "integer"}},
+            "tj_max_c": number,
+            "power_ratings": {"type": "array", "items": rating},
+            "derating": {"type": "array", "items": derating},
+            "soa": {"type": "array", "items": soa},
+            "transient_thermal": {"type": "array", "items": transient}
+        },
+        "required": ["part_number", "power_ratings", "soa"]
+    });
+    spec
+}
+
+pub fn summary_firmware() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "summary-firmware",
+        "Summary for firmware engineers",
+        PROMPT_SUMMARY_FIRMWARE,
+    );
+    spec.schema = summary_schema();
+    spec
+}
+
+pub fn summary_layout() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "summary-layout",
+        "Summary for PCB layout",
+        PROMPT_SUMMARY_LAYOUT,
+    );
+    spec.schema = summary_schema();
+    spec
+}
+
+pub fn summary_procurement() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "summary-procurement",
+        "Summary for procurement",
+        PROMPT_SUMMARY_PROCUREMENT,
+    );
+    spec.schema = summary_schema();
+    spec
+}
+
+/// Shared by the audience-specific summaries; only the section headings differ.
+fn summary_schema() -> Value {
+    let page = json!({"type": ["integer", "null"]});
+    let point = json!({
+        "type": "object",
+        "properties": {
+            "text": {"type": "string"},
+            "page": page
+        },
+        "required": ["text", "page"]
+    });
+    let fact = json!({
+        "type": "object",
+        "properties": {
+            "label": {"type": "string"},
+            "value": {"type": "string"},
+            "page": page
+        },
+        "required": ["label", "value", "page"]
+    });
+    json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "title": {"type": "string"},
+            "overview": {"type": "string"},
+            "key_facts": {"type": "array", "items": fact},
+            "sections": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "heading": {"type": "string"},
+                        "points": {"type": "array", "items": point}
+                    },
+                    "required": ["heading", "points"]
+                }
+            },
+            "cautions": {"type": "array", "items": point}
+        },
+        "required": ["part_number", "overview", "key_facts", "sections", "cautions"]
+    })
+}
+
+pub fn toc_figures() -> PromptSpec {
+    let mut spec = PromptSpec::new(
+        "toc-figures",
+        "Inventory of tables and figures",
+        PROMPT_TOC_FIGURES,
+    );
+    let text = json!({"type": ["string", "null"]});
+    let item = json!({
+        "type": "object",
+        "properties": {
+            "kind": {"type": "string", "enum": ["table", "figure"]},
+            "number": text,
+            "caption": {"type": "string"},
+            "section": text,
+            "page": {"type": "integer"},
+            "page_end": {"type": "integer"},
+            "printed_page": text,
+            "description": text
+        },
+        "required": ["kind", "caption", "page"]
+    });
+    spec.schema = json!({
+        "type": "object",
+        "properties": {
+            "part_number": {"type": "string"},
+            "page_count": {"type": "integer"},
+            "table_count": {"type": "integer"},
+            "figure_count": {"type": "integer"},
+            "items": {"type": "array", "items": item}
+        },
+        "required": ["part_number", "items"]
+    });
+    spec
+}
+
 pub fn custom() -> PromptSpec {
     let spec = PromptSpec::new(
         "custom",
@@ -362,3 +1797,48 @@ pub fn custom() -> PromptSpec {
     // Schema will be overridden by user if provided, otherwise uses default from PromptSpec::new
     spec
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_inputs() {
+        let inputs = vec![
+            ("pinout".to_string(), json!({"pins": [{"name": "USB_DP"}]})),
+            ("power".to_string(), json!({"rails": []})),
+        ];
+        let prompt = "Pins: {{pinout/pins/0/name}}. Keep {{ other }} as is.";
+        let rendered = render_inputs(prompt, &inputs).unwrap();
+        assert!(rendered.starts_with("Pins: \"USB_DP\". Keep {{ other }} as is."));
+        assert!(rendered.contains("### power\n\n```json\n{\n  \"rails\": []\n}\n```"));
+        assert!(!rendered.contains("### pinout"));
+        assert!(render_inputs("{{pinout/missing}}", &inputs).is_err());
+    }
+
+    #[test]
+    fn test_localized_prompts() {
+        let english = |task: &str| match task {
+            "characteristics" => characteristics().prompt,
+            "feature-matrix" => feature_matrix().prompt,
+            "pinout" => pinout().prompt,
+            "power" => power().prompt,
+            other => panic!("no English prompt for {other}"),
+        };
+        let json_block = |prompt: &str| {
+            let start = prompt.find("```json").expect("prompt has a JSON example");
+            let len = prompt[start + 7..].find("```").unwrap();
+            prompt[start..start + 7 + len].to_string()
+        };
+        // Translations must ask for exactly the same output as the English prompt
+        for (lang, task, prompt) in LOCALIZED {
+            assert_eq!(json_block(prompt), json_block(english(task)), "{lang:?} {task}");
+        }
+
+        let (text, translated) = localize("pinout", PROMPT_PINOUT, PromptLang::Ja);
+        assert!(translated && text.starts_with("**重要な要件:**") && text.contains("## 文書の言語"));
+        let (text, translated) = localize("soa", PROMPT_SOA, PromptLang::De);
+        assert!(!translated && text.starts_with(PROMPT_SOA.trim_end()) && text.contains("SPRACHE DES DOKUMENTS"));
+        assert_eq!(localize("soa", PROMPT_SOA, PromptLang::En), (PROMPT_SOA.to_string(), false));
+    }
+}
@@ -0,0 +1,247 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Resolve datasheet URLs directly on manufacturer websites.
+//!
+//! Distributor datasheet links are often stale or sit behind bot protection.
+//! Most large manufacturers publish datasheets at predictable URLs keyed by the
+//! generic part number, so we construct candidate URLs from the MPN and keep the
+//! first one that actually serves a PDF.
+
+use crate::download::DownloadOptions;
+
+/// Shortest generic part number we will try when trimming ordering suffixes.
+const MIN_BASE_LEN: usize = 4;
+
+/// A manufacturer with a predictable datasheet URL scheme.
+struct ManufacturerPattern {
+    name: &'static str,
+    /// Lowercase substrings matched against a BOM/distributor manufacturer name
+    aliases: &'static [&'static str],
+    /// Uppercase MPN prefixes used when no manufacturer name is known
+    prefixes: &'static [&'static str],
+    /// Build a URL from a candidate base part number
+    url: fn(&str) -> String,
+    /// Split points for trimming ordering suffixes ('-' separated segments vs. characters)
+    segmented: bool,
+}
+
+const PATTERNS: &[ManufacturerPattern] = &[
+    ManufacturerPattern {
+        name: "Texas Instruments",
+        aliases: &["texas instruments", "ti"],
+        prefixes: &["TPS", "TLV", "SN74", "SN65", "LMR", "OPA", "INA", "DRV", "BQ", "ADS", "DAC", "TMP", "CC26", "CC13", "MSP430"],
+        url: |base| format!("https://www.ti.com/lit/ds/symlink/{}.pdf", base.to_lowercase()),
+        segmented: false,
+    },
+    ManufacturerPattern {
+        name: "STMicroelectronics",
+        aliases: &["stmicro", "st micro", "st"],
+        prefixes: &["STM32", "STM8", "L78", "LD11", "LSM", "LIS", "VL53", "STUSB"],
+        url: |base| format!("https://www.st.com/resource/en/datasheet/{}.pdf", base.to_lowercase()),
+        segmented: false,
+    },
+    ManufacturerPattern {
+        name: "NXP",
+        aliases: &["nxp", "freescale"],
+        prefixes: &["LPC", "PCA", "PCF", "MKL", "MK6", "IMXRT", "TJA"],
+        url: |base| format!("https://www.nxp.com/docs/en/data-sheet/{}.pdf", base.to_uppercase()),
+        segmented: false,
+    },
+    ManufacturerPattern {
+        name: "Analog Devices",
+        aliases: &["analog devices", "linear technology", "maxim"],
+        prefixes: &["ADM", "ADP", "ADUM", "AD", "LTC", "LT", "MAX"],
+        url: |base| {
+            format!(
+                "https://www.analog.com/media/en/technical-documentation/data-sheets/{}.pdf",
+                base.to_lowercase()
+            )
+        },
+        segmented: false,
+    },
+    ManufacturerPattern {
+        name: "Microchip",
+        aliases: &["microchip", "atmel"],
+        prefixes: &["PIC", "ATMEGA", "ATTINY", "ATSAM", "MCP", "LAN", "KSZ"],
+        url: |base| format!("https://ww1.microchip.com/downloads/en/DeviceDoc/{}.pdf", base.to_uppercase()),
+        segmented: false,
+    },
+    ManufacturerPattern {
+        name: "Espressif",
+        aliases: &["espressif"],
+        prefixes: &["ESP32", "ESP8266"],
+        url: |base| {
+            format!(
+                "https://www.espressif.com/sites/default/files/documentation/{}_datasheet_en.pdf",
+                base.to_lowercase()
+            )
+        },
+        segmented: true,
+    },
+    ManufacturerPattern {
+        name: "Nexperia",
+        aliases: &["nexperia"],
+        prefixes: &["74LVC", "74AHC", "74HC", "BSS", "PMV", "PESD"],
+        url: |base| format!("https://assets.nexperia.com/documents/data-sheet/{}.pdf", base.to_uppercase()),
+        segmented: false,
+    },
+    ManufacturerPattern {
+        name: "onsemi",
+        aliases: &["onsemi", "on semi", "fairchild"],
+        prefixes: &["NCP", "NCV", "MC78", "FDN", "FDS", "NTR"],
+        url: |base| format!("https://www.onsemi.com/pdf/datasheet/{}-d.pdf", base.to_lowercase()),
+        segmented: false,
+    },
+    ManufacturerPattern {
+        name: "Diodes Incorporated",
+        aliases: &["diodes"],
+        prefixes: &["AP2", "AP7", "DMG", "DMN", "DMP"],
+        url: |base| format!("https://www.diodes.com/assets/Datasheets/{}.pdf", base.to_uppercase()),
+        segmented: false,
+    },
+];
+
+/// Find a manufacturer datasheet URL for a part, validating that it serves a PDF.
+///
+/// `manufacturer` is the manufacturer name from the BOM or a distributor, if known;
+/// otherwise the MPN prefix is used to guess. Returns `Ok(None)` when no supported
+/// manufacturer matches; errors when a manufacturer matched but no candidate URL served a PDF.
+pub(crate) fn find_datasheet_url(mpn: &str, manufacturer: Option<&str>) -> Result<Option<String>, String> {
+    let Some(pattern) = match_pattern(mpn, manufacturer) else {
+        return Ok(None);
+    };
+    crate::offline::ensure_online("Probing manufacturer sites")?;
+    let urls = candidate_urls(mpn, pattern);
+    match urls.iter().find(|url| serves_pdf(url)) {
+        Some(url) => Ok(Some(url.clone())),
+        None => Err(format!("no datasheet found on the {} site ({} URL(s) tried)", pattern.name, urls.len())),
+    }
+}
+
+/// Candidate datasheet URLs for a part, most specific first.
+fn candidate_urls(mpn: &str, pattern: &ManufacturerPattern) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for base in base_part_numbers(mpn, pattern.segmented) {
+        let url = (pattern.url)(&base);
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+fn match_pattern(mpn: &str, manufacturer: Option<&str>) -> Option<&'static ManufacturerPattern> {
+    if let Some(name) = manufacturer {
+        let name = name.to_lowercase();
+        let words: Vec<&str> = name.split(|c: char| !c.is_alphanumeric()).collect();
+        // Short aliases ("ti", "st") must match a whole word, longer ones a substring
+        let found = PATTERNS.iter().find(|p| {
+            p.aliases.iter().any(|alias| {
+                if alias.len() <= 2 {
+                    words.contains(alias)
+                } else {
+                    name.contains(alias)
+                }
+            })
+        });
+        if found.is_some() {
+            return found;
+        }
+    }
+
+    let upper = mpn.to_uppercase();
+    PATTERNS
+        .iter()
+        .find(|p| p.prefixes.iter().any(|prefix| upper.starts_with(prefix)))
+}
+
+/// Generic part numbers to try, from the full MPN down to the shortest plausible base.
+///
+/// Ordering codes append package/temperature/reel suffixes to the generic part
+/// number (TPS62130RGTR → tps62130, STM32F407VGT6 → stm32f407vg), so we trim
+/// trailing characters (or '-' segments for module names) until we hit a digit
+/// boundary that is still long enough to be unique.
+fn base_part_numbers(mpn: &str, segmented: bool) -> Vec<String> {
+    let mpn = mpn.trim();
+    // Strip common ordering suffixes separated by '/', '#', or '+'
+    let mpn = mpn.split(['/', '#', '+']).next().unwrap_or(mpn);
+    let mut bases = vec![mpn.to_string()];
+
+    if segmented {
+        let parts: Vec<&str> = mpn.split('-').collect();
+        for n in (1..parts.len()).rev() {
+            let base = parts[..n].join("-");
+            if base.len() >= MIN_BASE_LEN {
+                bases.push(base);
+            }
+        }
+        return bases;
+    }
+
+    let chars: Vec<char> = mpn.chars().collect();
+    let Some(last_digit) = chars.iter().rposition(|c| c.is_ascii_digit()) else {
+        return bases;
+    };
+    // Trim the trailing letters after the last digit one at a time, keeping at most one
+    for end in (last_digit + 1..chars.len()).rev() {
+        let base: String = chars[..end].iter().collect();
+        if base.len() >= MIN_BASE_LEN && end <= last_digit + 2 {
+            bases.push(base);
+        }
+    }
+    // Then drop whole letter+digit groups from the end (e.g. STM32F407VGT6 → STM32F407VG)
+    let mut end = chars.len();
+    while end > 0 && chars[end - 1].is_ascii_digit() {
+        end -= 1;
+    }
+    for _ in 0..2 {
+        if end == 0 {
+            break;
+        }
+        end -= 1;
+        let base: String = chars[..end].iter().collect();
+        if base.len() >= MIN_BASE_LEN && !bases.contains(&base) {
+            bases.push(base);
+        }
+    }
+    bases
+}
+
+/// Whether a URL responds with a PDF (following redirects).
+fn serves_pdf(url: &str) -> bool {
+    match ureq::head(url)
+        .set("User-Agent", &DownloadOptions::default().user_agent)
+        .call() {
+        Ok(response) => response.content_type().contains("pdf"),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(mpn: &str, manufacturer: Option<&str>) -> Vec<String> {
+        match_pattern(mpn, manufacturer)
+            .map(|p| candidate_urls(mpn, p))
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_candidate_urls() {
+        let ti = urls("TPS62130RGTR", Some("Texas Instruments"));
+        assert!(ti.contains(&"https://www.ti.com/lit/ds/symlink/tps62130.pdf".to_string()));
+
+        let st = urls("STM32F407VGT6", None);
+        assert!(st.contains(&"https://www.st.com/resource/en/datasheet/stm32f407vg.pdf".to_string()));
+
+        let esp = urls("ESP32-S3-WROOM-1-N8R8", Some("Espressif Systems"));
+        assert!(esp.contains(
+            &"https://www.espressif.com/sites/default/files/documentation/esp32-s3-wroom-1_datasheet_en.pdf"
+                .to_string()
+        ));
+
+        assert!(urls("RC0603FR-0710KL", Some("Yageo")).is_empty());
+    }
+}
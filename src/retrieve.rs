@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Retrieval pre-pass for long documents (`extract --retrieve K`).
+//!
+//! Each page's text layer is embedded once and cached by PDF hash, the task's
+//! topic is embedded as a query, and only the K best-matching pages (plus the
+//! first page, which names the part) are sent to the model. On a
+//! thousand-page reference manual that is most of the cost of an extraction.
+
+use crate::embed::page_texts;
+use crate::llm::{self, LlmProvider, resolve_api_key};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What each built-in task looks for, phrased the way datasheets phrase it.
+const TOPICS: &[(&str, &str)] = &[
+    ("analog-performance", "ADC DAC performance: INL DNL SNR SINAD ENOB THD offset gain error vs temperature"),
+    ("application-circuit", "typical application circuit schematic with component values"),
+    ("boot-config", "boot mode selection pins, strapping options, boot configuration"),
+    ("characteristics", "absolute maximum ratings, recommended operating conditions, electrical characteristics table, thermal information"),
+    ("connector", "connector pin assignment, current and voltage rating, mating, PCB layout"),
+    ("crystal", "crystal oscillator requirements, load capacitance, ESR, external clock"),
+    ("decoupling", "decoupling and bypass capacitor recommendations, power supply filtering"),
+    ("drc-rules", "PCB layout guidelines: trace width, clearance, via, ground plane"),
+    ("driver", "gate driver: dead time, bootstrap, current sense, protection, undervoltage lockout"),
+    ("feature-matrix", "device comparison table, ordering information, part number decoding, device options"),
+    ("footprint", "package mechanical drawing, dimensions, recommended land pattern"),
+    ("high-speed", "high-speed interface routing: impedance, length matching, differential pairs"),
+    ("layout-constraints", "PCB layout guidelines and layout example"),
+    ("marking", "package marking, top-side marking, device marking code"),
+    ("pinout", "pin configuration and functions, pin description table, alternate functions"),
+    ("pmic-config", "charger and PMIC configuration: charge profile, output rails, registers, straps"),
+    ("power", "power supply pins, power sequencing, supply current consumption, power modes"),
+    ("programming", "bootloader, flash programming, debug interface, read-out protection"),
+    ("reference-design", "reference design schematic, bill of materials"),
+    ("rf", "RF characteristics: frequency bands, TX output power, RX sensitivity, antenna matching"),
+    ("sensor-math", "conversion formula, calibration coefficients, compensation, register to physical value"),
+    ("soa", "safe operating area, power dissipation, derating curve, thermal resistance"),
+    ("toc-figures", "table of contents, list of tables, list of figures"),
+];
+
+/// Longest custom prompt sent as the retrieval query.
+const MAX_QUERY_CHARS: usize = 2000;
+
+/// Cached page vectors for one PDF.
+#[derive(Serialize, Deserialize, Default)]
+struct PageVectors {
+    model: String,
+    pages: BTreeMap<u32, Vec<f32>>,
+    /// Query vectors by query text, so a repeated task needs no request at all
+    queries: BTreeMap<String, Vec<f32>>,
+}
+
+/// The retrieval query for a task: its topic, or the prompt itself for custom tasks.
+pub fn query(task: &str, prompt: &str) -> String {
+    match TOPICS.iter().find(|(name, _)| *name == task) {
+        Some((_, topic)) => topic.to_string(),
+        None => prompt.chars().take(MAX_QUERY_CHARS).collect(),
+    }
+}
+
+/// Page ranges of the `k` pages most relevant to `query`, plus page 1, or
+/// `None` when the whole document should be sent: it has no more pages than
+/// that anyway, or too few pages have a text layer to rank.
+pub fn select_pages(
+    pdf: &Path,
+    query: &str,
+    k: usize,
+    api_key: Option<String>,
+    base_url: Option<String>,
+) -> Result<Option<Vec<(u32, u32)>>> {
+    let (total, texts) = page_texts(pdf)?;
+    if k + 1 >= total {
+        return Ok(None);
+    }
+    if texts.len() * 2 < total {
+        eprintln!(
+            "[RETRIEVE] Only {} of {} pages have a text layer; sending the whole document",
+            texts.len(),
+            total
+        );
+        return Ok(None);
+    }
+
+    let model = llm::DEFAULT_EMBEDDING_MODEL;
+    let path = cache_path(&crate::file_cache::hash_file(pdf)?);
+    let mut cache: PageVectors = fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .filter(|c: &PageVectors| c.model == model)
+        .unwrap_or_else(|| PageVectors { model: model.to_string(), ..Default::default() });
+
+    let missing: Vec<&(u32, String)> = texts.iter().filter(|(page, _)| !cache.pages.contains_key(page)).collect();
+    let need_query = !cache.queries.contains_key(query);
+    if !missing.is_empty() || need_query {
+        let api_key = resolve_api_key(LlmProvider::Gemini, api_key)?;
+        if !missing.is_empty() {
+            eprintln!("[RETRIEVE] Embedding {} page(s) of {}", missing.len(), pdf.display());
+            let page_texts: Vec<String> = missing.iter().map(|(_, text)| text.clone()).collect();
+            let vectors = llm::embed(&api_key, base_url.clone(), model, &page_texts, false)?;
+            for ((page, _), vector) in missing.into_iter().zip(vectors) {
+                cache.pages.insert(*page, vector);
+            }
+        }
+        if need_query {
+            let vector = llm::embed(&api_key, base_url, model, &[query.to_string()], true)?
+                .pop()
+                .unwrap_or_default();
+            cache.queries.insert(query.to_string(), vector);
+        }
+        if let Err(e) = save(&path, &cache) {
+            eprintln!("[RETRIEVE] Warning: could not cache page embeddings: {e:#}");
+        }
+    }
+
+    let query_vector = &cache.queries[query];
+    let scores: Vec<(u32, f32)> = cache
+        .pages
+        .iter()
+        .map(|(&page, vector)| (page, dot(query_vector, vector)))
+        .collect();
+    let ranges = top_pages(&scores, k);
+    let kept: u32 = ranges.iter().map(|(start, end)| end - start + 1).sum();
+    eprintln!(
+        "[RETRIEVE] Sending {} of {} pages: {}",
+        kept,
+        total,
+        ranges
+            .iter()
+            .map(|&(start, end)| if start == end { start.to_string() } else { format!("{start}-{end}") })
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    Ok(Some(ranges))
+}
+
+/// Page 1 and the `k` best-scoring pages, as sorted, merged 1-based ranges.
+fn top_pages(scores: &[(u32, f32)], k: usize) -> Vec<(u32, u32)> {
+    let mut ranked = scores.to_vec();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut pages: Vec<u32> = ranked.into_iter().take(k).map(|(page, _)| page).collect();
+    pages.push(1);
+    pages.sort_unstable();
+    pages.dedup();
+
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for page in pages {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == page => *end = page,
+            _ => ranges.push((page, page)),
+        }
+    }
+    ranges
+}
+
+/// Vectors from `llm::embed` are unit length, so this is cosine similarity.
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn cache_path(hash: &str) -> PathBuf {
+    crate::file_cache::cache_root()
+        .join("embeddings")
+        .join(format!("{}.json", &hash[..16]))
+}
+
+fn save(path: &Path, cache: &PageVectors) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec(cache)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_pages() {
+        let scores = [(2, 0.1), (5, 0.9), (6, 0.8), (9, 0.7), (12, 0.2)];
+        assert_eq!(top_pages(&scores, 3), [(1, 1), (5, 6), (9, 9)]);
+        assert_eq!(top_pages(&[(1, 0.5), (2, 0.4)], 1), [(1, 1)]);
+        assert_eq!(query("pinout", "ignored").split(',').next(), Some("pin configuration and functions"));
+        assert_eq!(query("custom", "Find the I2C address"), "Find the I2C address");
+    }
+}
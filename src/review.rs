@@ -0,0 +1,329 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Human review of extraction results (`extract --review`).
+//!
+//! The result is opened in `$VISUAL` / `$EDITOR` (or, without one, the fields
+//! are offered one by one on the terminal), and every field that changed is
+//! recorded as a correction for that PDF and task. Later extractions of the
+//! same PDF apply the stored corrections wherever the model returns the value
+//! that was corrected, so a fixed pin name stays fixed across re-runs; a field
+//! whose extracted value has since changed is left alone and reported.
+//!
+//! Corrections live in `<data dir>/datasheet-cli/corrections/<pdf sha256>/<task>.json`.
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// One field changed during review.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Correction {
+    /// JSON pointer to the field
+    pub pointer: String,
+    /// Value the model returned (null if the field was missing)
+    pub original: Value,
+    /// Value after review
+    pub corrected: Value,
+}
+
+/// Name corrections are stored under: the task, plus the part for `--expand-variants` runs.
+pub fn key(task: &str, variant: Option<&str>) -> String {
+    match variant {
+        Some(part) => format!("{}.{}", task, crate::paths::sanitize_filename(part)),
+        None => task.to_string(),
+    }
+}
+
+/// Apply the stored corrections for this PDF and task to a fresh extraction.
+pub fn apply_stored(pdf: &Path, key: &str, mut value: Value) -> Value {
+    let Some(root) = corrections_root().filter(|root| root.is_dir()) else {
+        return value;
+    };
+    let Ok(hash) = crate::file_cache::hash_file(pdf) else {
+        return value;
+    };
+    let path = root.join(hash).join(format!("{key}.json"));
+    let corrections = load(&path);
+    if corrections.is_empty() {
+        return value;
+    }
+    let (applied, stale) = apply(&mut value, &corrections);
+    if applied > 0 {
+        eprintln!("[REVIEW] Applied {} stored correction(s) from {}", applied, path.display());
+    }
+    for pointer in stale {
+        eprintln!("[REVIEW] Skipped correction at {}: the extracted value has changed since review", pointer);
+    }
+    value
+}
+
+/// Let the user correct `value`, record what changed, and return the corrected result.
+pub fn review(pdf: &Path, key: &str, value: Value) -> Result<Value> {
+    let edited = match editor() {
+        Some(editor) => edit_in_editor(&editor, &value)?,
+        None => edit_fields(&value)?,
+    };
+    let changes = diff(&value, &edited);
+    if changes.is_empty() {
+        eprintln!("[REVIEW] No changes");
+        return Ok(edited);
+    }
+
+    let hash = crate::file_cache::hash_file(pdf)?;
+    let root = corrections_root().ok_or_else(|| anyhow!("Could not determine data directory"))?;
+    let path = root.join(hash).join(format!("{key}.json"));
+    let mut corrections = load(&path);
+    let changed = changes.len();
+    merge(&mut corrections, changes);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    crate::paths::write_file(&path, serde_json::to_string_pretty(&corrections)?)
+        .with_context(|| format!("writing {}", path.display()))?;
+    eprintln!(
+        "[REVIEW] Recorded {} changed field(s); {} correction(s) stored in {}",
+        changed,
+        corrections.len(),
+        path.display()
+    );
+    Ok(edited)
+}
+
+fn corrections_root() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("datasheet-cli").join("corrections"))
+}
+
+fn load(path: &Path) -> Vec<Correction> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn editor() -> Option<String> {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+}
+
+/// Open the result in the user's editor until it saves valid JSON.
+fn edit_in_editor(editor: &str, value: &Value) -> Result<Value> {
+    let path = std::env::temp_dir().join(format!("datasheet-review-{}.json", std::process::id()));
+    fs::write(&path, serde_json::to_string_pretty(value)?)
+        .with_context(|| format!("writing {}", path.display()))?;
+    let result = loop {
+        let mut parts = editor.split_whitespace();
+        let program = parts.next().unwrap_or(editor);
+        let status = Command::new(program)
+            .args(parts)
+            .arg(&path)
+            .status()
+            .with_context(|| format!("running editor '{}'", editor));
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => break Err(anyhow!("editor '{}' exited with {}", editor, status)),
+            Err(e) => break Err(e),
+        }
+        let text = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        match serde_json::from_str(&text) {
+            Ok(edited) => break Ok(edited),
+            Err(e) => {
+                eprintln!("[REVIEW] The edited file is not valid JSON: {}", e);
+                if !confirm("Edit again?")? {
+                    break Err(anyhow!("review abandoned: edited result is not valid JSON"));
+                }
+            }
+        }
+    };
+    let _ = fs::remove_file(&path);
+    result
+}
+
+/// Offer each field in turn; Enter keeps it, `:q` keeps the rest.
+fn edit_fields(value: &Value) -> Result<Value> {
+    eprintln!("[REVIEW] No $EDITOR set; reviewing field by field.");
+    eprintln!("Enter a new value (JSON, or plain text for a string), Enter to keep it, or :q to keep the rest.");
+    let mut fields = Vec::new();
+    leaves(value, &mut String::new(), &mut fields);
+    let mut edited = value.clone();
+    let stdin = io::stdin();
+    for (pointer, current) in fields {
+        eprint!("{} = {}\n> ", pointer, current);
+        io::stderr().flush().ok();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let input = line.trim();
+        if input == ":q" {
+            break;
+        }
+        if input.is_empty() {
+            continue;
+        }
+        let new = serde_json::from_str(input).unwrap_or_else(|_| Value::String(input.to_string()));
+        if let Some(slot) = edited.pointer_mut(&pointer) {
+            *slot = new;
+        }
+    }
+    Ok(edited)
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    eprint!("{} [Y/n] ", question);
+    io::stderr().flush().ok();
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line)? == 0 {
+        return Ok(false);
+    }
+    Ok(!line.trim().eq_ignore_ascii_case("n"))
+}
+
+/// Scalar fields (and empty containers) with their JSON pointers.
+fn leaves(value: &Value, pointer: &mut String, out: &mut Vec<(String, Value)>) {
+    let len = pointer.len();
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                pointer.push('/');
+                pointer.push_str(&escape(key));
+                leaves(child, pointer, out);
+                pointer.truncate(len);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (i, child) in items.iter().enumerate() {
+                pointer.push_str(&format!("/{i}"));
+                leaves(child, pointer, out);
+                pointer.truncate(len);
+            }
+        }
+        _ => out.push((pointer.clone(), value.clone())),
+    }
+}
+
+/// Fields that differ between two results. Arrays whose length changed are
+/// reported whole; a missing field counts as null.
+fn diff(before: &Value, after: &Value) -> Vec<Correction> {
+    fn walk(before: &Value, after: &Value, pointer: &mut String, out: &mut Vec<Correction>) {
+        let len = pointer.len();
+        match (before, after) {
+            (Value::Object(a), Value::Object(b)) => {
+                let keys = a.keys().chain(b.keys().filter(|k| !a.contains_key(*k)));
+                for key in keys {
+                    pointer.push('/');
+                    pointer.push_str(&escape(key));
+                    let null = Value::Null;
+                    walk(a.get(key).unwrap_or(&null), b.get(key).unwrap_or(&null), pointer, out);
+                    pointer.truncate(len);
+                }
+            }
+            (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+                for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                    pointer.push_str(&format!("/{i}"));
+                    walk(x, y, pointer, out);
+                    pointer.truncate(len);
+                }
+            }
+            _ if before != after => out.push(Correction {
+                pointer: pointer.clone(),
+                original: before.clone(),
+                corrected: after.clone(),
+            }),
+            _ => {}
+        }
+    }
+    let mut out = Vec::new();
+    walk(before, after, &mut String::new(), &mut out);
+    out
+}
+
+/// Fold new changes into the stored corrections, keeping the model's original
+/// value for fields corrected before and dropping corrections that were undone.
+fn merge(stored: &mut Vec<Correction>, changes: Vec<Correction>) {
+    for change in changes {
+        match stored.iter().position(|c| c.pointer == change.pointer) {
+            Some(i) => stored[i].corrected = change.corrected,
+            None => stored.push(change),
+        }
+    }
+    stored.retain(|c| c.original != c.corrected);
+}
+
+/// Apply corrections whose original value matches the extraction. Returns the
+/// number applied and the pointers skipped because the value has changed.
+fn apply(value: &mut Value, corrections: &[Correction]) -> (usize, Vec<String>) {
+    let mut applied = 0;
+    let mut stale = Vec::new();
+    for correction in corrections {
+        let current = value.pointer(&correction.pointer).cloned().unwrap_or(Value::Null);
+        if current == correction.corrected {
+            continue;
+        }
+        if current != correction.original || !set(value, &correction.pointer, correction.corrected.clone()) {
+            stale.push(correction.pointer.clone());
+            continue;
+        }
+        applied += 1;
+    }
+    (applied, stale)
+}
+
+/// Set the value at `pointer`, adding the field to its parent object if missing.
+fn set(value: &mut Value, pointer: &str, new: Value) -> bool {
+    if let Some(slot) = value.pointer_mut(pointer) {
+        *slot = new;
+        return true;
+    }
+    let Some((parent, key)) = pointer.rsplit_once('/') else {
+        return false;
+    };
+    match value.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.insert(key.replace("~1", "/").replace("~0", "~"), new);
+            true
+        }
+        _ => false,
+    }
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_corrections_round_trip() {
+        let extracted = json!({"pins": [{"number": 1, "name": "PA0"}, {"number": 2, "name": "VDD"}]});
+        let mut reviewed = extracted.clone();
+        reviewed["pins"][0]["name"] = json!("PA0/WKUP");
+        reviewed["pins"][1]["type"] = json!("power");
+
+        let mut stored = Vec::new();
+        merge(&mut stored, diff(&extracted, &reviewed));
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].pointer, "/pins/0/name");
+
+        // Re-extraction returns the same mistakes plus a changed pin 2
+        let mut fresh = extracted.clone();
+        fresh["pins"][1]["type"] = json!("ground");
+        let (applied, stale) = apply(&mut fresh, &stored);
+        assert_eq!(applied, 1);
+        assert_eq!(stale, vec!["/pins/1/type".to_string()]);
+        assert_eq!(fresh["pins"][0]["name"], "PA0/WKUP");
+
+        // Undoing a correction in a later review drops it
+        merge(&mut stored, diff(&reviewed, &extracted));
+        assert!(stored.is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! `datasheet scan`: read the 2D barcode on a distributor's bag label.
+//!
+//! DigiKey, Mouser, and other ECIA members print an ISO/IEC 15434 message on
+//! their labels: `[)>` RS `06` GS, then fields separated by GS, each starting
+//! with an ANSI MH10.8.2 data identifier (`1P` manufacturer part number, `Q`
+//! quantity, `1T` lot code, `9D` date code, ...), and RS EOT at the end.
+//!
+//! USB scanners type the label like a keyboard, and not every one sends the
+//! control characters unchanged, so the common stand-ins (`{GS}`, `<GS>`,
+//! `^]`, and the Unicode control pictures like `␝`) are accepted too. With no
+//! argument, every line of stdin is one scan, for scanning a stack of bags.
+
+use anyhow::{Result, anyhow};
+use clap::Args;
+use serde::Serialize;
+use std::io::BufRead;
+
+use crate::bom::Source;
+use crate::error::Error;
+use crate::stock::StockInfo;
+
+const RS: char = '\u{1e}';
+const GS: char = '\u{1d}';
+const EOT: char = '\u{04}';
+
+/// How scanners and people write the separators when they are not sent as is.
+const STAND_INS: &[(&str, char)] = &[
+    ("{RS}", RS),
+    ("<RS>", RS),
+    ("^^", RS),
+    ("\u{241e}", RS),
+    ("{GS}", GS),
+    ("<GS>", GS),
+    ("^]", GS),
+    ("\u{241d}", GS),
+    ("{EOT}", EOT),
+    ("<EOT>", EOT),
+    ("^D", EOT),
+    ("\u{2404}", EOT),
+];
+
+#[derive(Args, Debug)]
+pub struct ScanArgs {
+    /// Barcode text as the scanner typed it (default: one scan per line of stdin)
+    pub barcode: Option<String>,
+
+    /// Look the part up to show current stock and lifecycle
+    #[arg(long)]
+    pub lookup: bool,
+
+    /// Distributor to look the part up at (default: the one that printed the label)
+    #[arg(long, value_enum, requires = "lookup")]
+    pub source: Option<Source>,
+
+    /// Output as JSON (one object per line when reading stdin)
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// The fields of one label.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct Label {
+    /// Distributor that printed the label, when the fields give it away
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distributor: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mpn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manufacturer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distributor_pn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lot_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_of_origin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purchase_order: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sales_order: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invoice: Option<String>,
+    /// Every field as (data identifier, value), in label order
+    pub fields: Vec<(String, String)>,
+}
+
+#[derive(Serialize, Debug)]
+struct Scan {
+    #[serde(flatten)]
+    label: Label,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lookup: Option<StockInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lookup_error: Option<String>,
+}
+
+pub fn run(args: &ScanArgs) -> Result<()> {
+    if let Some(barcode) = &args.barcode {
+        return scan(args, barcode);
+    }
+    let mut failed = 0;
+    for line in std::io::stdin().lock().lines() {
+        let line = line.map_err(|e| anyhow!("reading stdin: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Err(e) = scan(args, &line) {
+            eprintln!("[SCAN] {:#}", e);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(Error::validation(format!("{} scan(s) could not be read", failed)).into());
+    }
+    Ok(())
+}
+
+fn scan(args: &ScanArgs, barcode: &str) -> Result<()> {
+    let label = parse(barcode)?;
+    let (lookup, lookup_error) = if args.lookup { look_up(&label, args.source) } else { (None, None) };
+    let scan = Scan { label, lookup, lookup_error };
+
+    if args.json {
+        // Compact when reading stdin, so each scan stays one line
+        let json = if args.barcode.is_some() {
+            crate::select::to_string_pretty(&scan)
+        } else {
+            serde_json::to_string(&scan)
+        }
+        .map_err(|e| anyhow!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+    } else {
+        print_scan(&scan);
+    }
+    Ok(())
+}
+
+/// Parse the text of an ECIA label barcode.
+pub fn parse(barcode: &str) -> Result<Label, Error> {
+    let mut text = barcode.trim().to_string();
+    for (stand_in, c) in STAND_INS {
+        text = text.replace(stand_in, &c.to_string());
+    }
+    let body = text
+        .strip_prefix("[)>")
+        .map(|rest| rest.trim_start_matches(RS))
+        .ok_or_else(|| Error::validation(format!("not an ECIA label barcode (no \"[)>\" header): {}", barcode)))?;
+    let body = body.strip_prefix("06").unwrap_or(body);
+    let body = body.trim_end_matches([RS, EOT]);
+
+    let mut label = Label::default();
+    for field in body.split(GS).map(|f| f.trim_end_matches([RS, EOT])).filter(|f| !f.is_empty()) {
+        let Some((id, value)) = split_identifier(field) else {
+            return Err(Error::validation(format!("unrecognized label field: {}", field)));
+        };
+        let value = value.trim().to_string();
+        let slot = match id {
+            "1P" => Some(&mut label.mpn),
+            "P" | "30P" => Some(&mut label.distributor_pn),
+            "1V" => Some(&mut label.manufacturer),
+            "1T" => Some(&mut label.lot_code),
+            "9D" | "10D" | "D" => Some(&mut label.date_code),
+            "4L" => Some(&mut label.country_of_origin),
+            "K" => Some(&mut label.purchase_order),
+            "1K" => Some(&mut label.sales_order),
+            "10K" => Some(&mut label.invoice),
+            "Q" => {
+                label.quantity = value.parse().ok();
+                None
+            }
+            _ => None,
+        };
+        // The first of a repeated identifier wins; DigiKey's 30P is its own part number, P may be the customer's
+        if let Some(slot) = slot.filter(|slot| !value.is_empty() && (slot.is_none() || id == "30P")) {
+            *slot = Some(value.clone());
+        }
+        label.fields.push((id.to_string(), value));
+    }
+    if label.fields.is_empty() {
+        return Err(Error::validation(format!("label barcode has no fields: {}", barcode)));
+    }
+    label.distributor = distributor(&label);
+    Ok(label)
+}
+
+/// A data identifier is up to three digits and a letter.
+fn split_identifier(field: &str) -> Option<(&str, &str)> {
+    let digits = field.bytes().take_while(u8::is_ascii_digit).count();
+    let letter = field.as_bytes().get(digits).is_some_and(u8::is_ascii_uppercase);
+    (digits <= 3 && letter).then(|| field.split_at(digits + 1))
+}
+
+fn distributor(label: &Label) -> Option<&'static str> {
+    let has = |id: &str| label.fields.iter().any(|(i, _)| i == id);
+    let digikey_pn = label.distributor_pn.as_deref().is_some_and(|pn| pn.ends_with("-ND"));
+    if digikey_pn || has("30P") || (has("11Z") && has("12Z")) {
+        Some("digikey")
+    } else if has("14K") {
+        Some("mouser")
+    } else {
+        None
+    }
+}
+
+fn look_up(label: &Label, source: Option<Source>) -> (Option<StockInfo>, Option<String>) {
+    let source = source.or(match label.distributor {
+        Some("digikey") => Some(Source::Digikey),
+        Some("mouser") => Some(Source::Mouser),
+        _ => None,
+    });
+    let Some(source) = source else {
+        return (None, Some("unknown distributor; pass --source".to_string()));
+    };
+    // The distributor's own part number finds exactly the packaging that was shipped,
+    // but P may hold the customer's part number instead
+    let mut parts = Vec::new();
+    if source.name() == label.distributor.unwrap_or_default() {
+        parts.extend(label.distributor_pn.as_deref());
+    }
+    parts.extend(label.mpn.as_deref());
+    let mut error = "label has no part number".to_string();
+    for part in parts {
+        match source.lookup_stock(part) {
+            Ok(info) => return (Some(info), None),
+            Err(e) => error = e,
+        }
+    }
+    (None, Some(error))
+}
+
+fn print_scan(scan: &Scan) {
+    let label = &scan.label;
+    let rows = [
+        ("MPN", label.mpn.clone()),
+        ("Manufacturer", label.manufacturer.clone()),
+        ("Distributor", label.distributor.map(str::to_string)),
+        ("Distributor PN", label.distributor_pn.clone()),
+        ("Quantity", label.quantity.map(|q| q.to_string())),
+        ("Lot code", label.lot_code.clone()),
+        ("Date code", label.date_code.clone()),
+        ("Origin", label.country_of_origin.clone()),
+        ("PO", label.purchase_order.clone()),
+        ("Sales order", label.sales_order.clone()),
+        ("Invoice", label.invoice.clone()),
+    ];
+    for (name, value) in rows {
+        if let Some(value) = value {
+            println!("{:<15} {}", format!("{}:", name), value);
+        }
+    }
+    if let Some(info) = &scan.lookup {
+        let stock = info.stock.map_or("?".to_string(), |s| s.to_string());
+        let status = info.lifecycle_status.as_deref().map(|s| format!(", {}", s)).unwrap_or_default();
+        println!("{:<15} {} at {}{}", "Stock:", stock, info.distributor, status);
+    }
+    if let Some(error) = &scan.lookup_error {
+        eprintln!("[SCAN] Lookup failed: {}", error);
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let digikey = "[)>\u{1e}06\u{1d}P296-LM5164DDARCT-ND\u{1d}1PLM5164DDAR\u{1d}K\u{1d}1K74985321\u{1d}\
+                       10K88776655\u{1d}9D2231\u{1d}1TABC123\u{1d}4LMX\u{1d}Q10\u{1d}11ZPICK\u{1d}12Z4185823\u{1d}\
+                       13Z123456\u{1d}20Z0000000000\u{1e}\u{04}";
+        let label = parse(digikey).unwrap();
+        assert_eq!(label.distributor, Some("digikey"));
+        assert_eq!(label.mpn.as_deref(), Some("LM5164DDAR"));
+        assert_eq!(label.distributor_pn.as_deref(), Some("296-LM5164DDARCT-ND"));
+        assert_eq!(label.quantity, Some(10));
+        assert_eq!(label.lot_code.as_deref(), Some("ABC123"));
+        assert_eq!(label.date_code.as_deref(), Some("2231"));
+        assert_eq!(label.purchase_order, None);
+        assert_eq!(label.sales_order.as_deref(), Some("74985321"));
+        assert_eq!(label.fields.len(), 13);
+
+        // As a scanner without control characters types it
+        let mouser = "[)>{RS}06{GS}KPO-1234{GS}14K001{GS}1PSTM32F407VGT6{GS}Q5{GS}11K123456789{GS}4LCN\
+                      {GS}1VSTMicroelectronics{GS}1T3A1B2C{GS}10D2318{RS}{EOT}";
+        let label = parse(mouser).unwrap();
+        assert_eq!(label.distributor, Some("mouser"));
+        assert_eq!(label.mpn.as_deref(), Some("STM32F407VGT6"));
+        assert_eq!(label.manufacturer.as_deref(), Some("STMicroelectronics"));
+        assert_eq!(label.purchase_order.as_deref(), Some("PO-1234"));
+        assert_eq!(label.date_code.as_deref(), Some("2318"));
+        assert_eq!(label.country_of_origin.as_deref(), Some("CN"));
+
+        assert!(parse("296-LM5164DDARCT-ND").is_err());
+        assert!(parse("[)>\u{1e}06\u{1d}lowercase\u{1e}\u{04}").is_err());
+    }
+}
@@ -0,0 +1,590 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Built-in scheduler for recurring commands.
+//!
+//! `schedule add "0 6 * * 1" "bom risk bom.csv"` stores a cron expression and
+//! a datasheet command line; `schedule run` stays in the foreground and runs
+//! each command (as a child `datasheet` process) whenever its expression
+//! matches, so recurring checks need no system cron or Task Scheduler entry.
+//!
+//! Expressions have the usual five fields (minute, hour, day of month, month,
+//! day of week) with `*`, lists, ranges, steps, and month/day names, plus
+//! `@hourly`, `@daily`, `@weekly`, and `@monthly`. They are evaluated in the
+//! local time zone unless `--utc-offset` (or `DATASHEET_UTC_OFFSET`) is given.
+//!
+//! Schedules live at `<data dir>/datasheet-cli/schedule.db` unless `--db` or
+//! `DATASHEET_SCHEDULE_DB` is set.
+
+use clap::{Parser, Subcommand};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::db::now;
+
+const ENV_VAR_DB: &str = "DATASHEET_SCHEDULE_DB";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS schedules (
+    id INTEGER PRIMARY KEY,
+    cron TEXT NOT NULL,
+    command TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_run INTEGER,
+    last_status TEXT,
+    last_error TEXT
+);
+";
+
+#[derive(Subcommand, Debug)]
+pub enum ScheduleSubcommand {
+    /// Add a recurring command, e.g. `schedule add "0 6 * * 1" "bom risk bom.csv"`
+    Add {
+        /// Cron expression: minute hour day-of-month month day-of-week, or @daily etc.
+        cron: String,
+
+        /// datasheet command line to run, without the leading `datasheet`
+        command: String,
+
+        /// Schedule database path (defaults to DATASHEET_SCHEDULE_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+
+    /// List schedules with their next and last runs
+    List {
+        /// Offset of the time zone the expressions are written in, e.g. +02:00 (default: local time)
+        #[arg(long, env = "DATASHEET_UTC_OFFSET", allow_hyphen_values = true)]
+        utc_offset: Option<String>,
+
+        /// Schedule database path (defaults to DATASHEET_SCHEDULE_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Remove schedules
+    Remove {
+        /// Schedule ids
+        #[arg(required = true)]
+        ids: Vec<i64>,
+
+        /// Schedule database path (defaults to DATASHEET_SCHEDULE_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+
+    /// Run scheduled commands as they come due (runs until interrupted)
+    Run {
+        /// Offset of the time zone the expressions are written in, e.g. +02:00 (default: local time)
+        #[arg(long, env = "DATASHEET_UTC_OFFSET", allow_hyphen_values = true)]
+        utc_offset: Option<String>,
+
+        /// Schedule database path (defaults to DATASHEET_SCHEDULE_DB or the platform data directory)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct Schedule {
+    pub id: i64,
+    pub cron: String,
+    pub command: String,
+    pub created_at: i64,
+    pub last_run: Option<i64>,
+    pub last_status: Option<String>,
+    pub last_error: Option<String>,
+    /// Unix time of the next run (filled in by `schedule list`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run: Option<i64>,
+}
+
+/// Handle to the schedule database.
+pub struct ScheduleDb {
+    conn: Connection,
+}
+
+impl ScheduleDb {
+    /// Open (and create if needed) the database at `path`, `DATASHEET_SCHEDULE_DB`, or the default location.
+    pub fn open(path: Option<PathBuf>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path,
+            None => match std::env::var(ENV_VAR_DB) {
+                Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+                _ => dirs::data_dir()
+                    .ok_or_else(|| "Could not determine data directory".to_string())?
+                    .join("datasheet-cli")
+                    .join("schedule.db"),
+            },
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let conn = Connection::open(&path)
+            .map_err(|e| format!("Failed to open schedule database {}: {}", path.display(), e))?;
+        conn.busy_timeout(Duration::from_secs(30)).map_err(db_err)?;
+        conn.execute_batch(SCHEMA).map_err(db_err)?;
+        Ok(Self { conn })
+    }
+
+    fn add(&self, cron: &str, command: &str) -> Result<i64, String> {
+        self.conn
+            .execute(
+                "INSERT INTO schedules (cron, command, created_at) VALUES (?1, ?2, ?3)",
+                params![cron, command, now()],
+            )
+            .map_err(db_err)?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    fn remove(&self, id: i64) -> Result<bool, String> {
+        let changed = self
+            .conn
+            .execute("DELETE FROM schedules WHERE id = ?1", params![id])
+            .map_err(db_err)?;
+        Ok(changed > 0)
+    }
+
+    /// Mark the run due at `at` as started, unless another `schedule run` sharing
+    /// the database already has.
+    fn claim(&self, id: i64, at: i64) -> Result<bool, String> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE schedules SET last_run = ?2, last_status = 'running', last_error = NULL
+                 WHERE id = ?1 AND (last_run IS NULL OR last_run < ?2)",
+                params![id, at],
+            )
+            .map_err(db_err)?;
+        Ok(changed > 0)
+    }
+
+    fn record_run(&self, id: i64, at: i64, outcome: &Result<(), String>) -> Result<(), String> {
+        let (status, error) = match outcome {
+            Ok(()) => ("ok", None),
+            Err(e) => ("failed", Some(e.as_str())),
+        };
+        self.conn
+            .execute(
+                "UPDATE schedules SET last_run = ?2, last_status = ?3, last_error = ?4 WHERE id = ?1",
+                params![id, at, status, error],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<Schedule>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, cron, command, created_at, last_run, last_status, last_error
+                 FROM schedules ORDER BY id",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(Schedule {
+                    id: row.get(0)?,
+                    cron: row.get(1)?,
+                    command: row.get(2)?,
+                    created_at: row.get(3)?,
+                    last_run: row.get(4)?,
+                    last_status: row.get(5)?,
+                    last_error: row.get(6)?,
+                    next_run: None,
+                })
+            })
+            .map_err(db_err)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+    }
+}
+
+fn db_err(e: rusqlite::Error) -> String {
+    format!("Database error: {}", e)
+}
+
+pub fn execute(command: ScheduleSubcommand) -> Result<(), String> {
+    match command {
+        ScheduleSubcommand::Add { cron, command, db } => {
+            Cron::parse(&cron)?;
+            command_args(&command)?;
+            let id = ScheduleDb::open(db)?.add(cron.trim(), command.trim())?;
+            println!("Added schedule {}: {}  datasheet {}", id, cron.trim(), command.trim());
+            Ok(())
+        }
+        ScheduleSubcommand::List { utc_offset, db, json } => {
+            let offset = utc_offset.as_deref().map(parse_utc_offset).transpose()?;
+            cmd_list(&ScheduleDb::open(db)?, offset.unwrap_or_else(local_utc_offset), json)
+        }
+        ScheduleSubcommand::Remove { ids, db } => {
+            let db = ScheduleDb::open(db)?;
+            for id in ids {
+                if db.remove(id)? {
+                    println!("Removed schedule {}", id);
+                } else {
+                    eprintln!("[SCHEDULE] No schedule {}", id);
+                }
+            }
+            Ok(())
+        }
+        ScheduleSubcommand::Run { utc_offset, db } => {
+            cmd_run(&ScheduleDb::open(db)?, utc_offset.as_deref().map(parse_utc_offset).transpose()?)
+        }
+    }
+}
+
+/// Split `command` like a shell would and check that it is a datasheet command.
+fn command_args(command: &str) -> Result<Vec<String>, String> {
+    let mut args = shlex::split(command).ok_or_else(|| format!("Unbalanced quotes in '{}'", command))?;
+    if args.first().is_some_and(|arg| arg == "datasheet") {
+        args.remove(0);
+    }
+    if args.first().is_none_or(|arg| arg == "schedule") {
+        return Err(format!("'{}' is not a command that can be scheduled", command));
+    }
+    crate::Cli::try_parse_from(std::iter::once("datasheet".to_string()).chain(args.iter().cloned()))
+        .map_err(|e| {
+            let message = e.to_string();
+            let first = message.lines().next().unwrap_or_default();
+            format!("Invalid command '{}': {}", command, first.trim_start_matches("error: "))
+        })?;
+    Ok(args)
+}
+
+fn cmd_list(db: &ScheduleDb, offset: i64, json_output: bool) -> Result<(), String> {
+    let mut schedules = db.list()?;
+    for schedule in &mut schedules {
+        schedule.next_run = Cron::parse(&schedule.cron).ok().and_then(|cron| cron.next_after(now(), offset));
+    }
+
+    if json_output {
+        let json = crate::select::to_string_pretty(&schedules)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if schedules.is_empty() {
+        println!("No schedules");
+        return Ok(());
+    }
+
+    println!("{} schedule(s):", schedules.len());
+    for schedule in &schedules {
+        let next = schedule.next_run.map(|ts| format_time(ts, offset)).unwrap_or_else(|| "never".to_string());
+        println!("  {:>4}  {:<16} datasheet {}", schedule.id, schedule.cron, schedule.command);
+        match (schedule.last_run, schedule.last_status.as_deref()) {
+            (Some(last), Some(status)) => {
+                println!("        next: {}  last: {} ({})", next, format_time(last, offset), status)
+            }
+            _ => println!("        next: {}", next),
+        }
+    }
+    Ok(())
+}
+
+/// `utc_offset` overrides the local offset, which is re-read every pass so a
+/// daylight saving change takes effect without a restart.
+fn cmd_run(db: &ScheduleDb, utc_offset: Option<i64>) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Could not locate the datasheet binary: {}", e))?;
+    eprintln!("[SCHEDULE] Running {} schedule(s); press Ctrl-C to stop", db.list()?.len());
+
+    let mut checked = now().div_euclid(60);
+    loop {
+        let current = now().div_euclid(60);
+        // Every minute since the last check, so a slow run does not skip one;
+        // a schedule runs at most once per pass however many minutes matched.
+        let minutes = (checked + 1)..=current;
+        checked = current.max(checked);
+        let offset = utc_offset.unwrap_or_else(local_utc_offset);
+        for schedule in db.list()? {
+            let cron = match Cron::parse(&schedule.cron) {
+                Ok(cron) => cron,
+                Err(e) => {
+                    eprintln!("[SCHEDULE] Skipping schedule {}: {}", schedule.id, e);
+                    continue;
+                }
+            };
+            let Some(due) = minutes.clone().rev().find(|&minute| cron.matches(minute * 60, offset)) else {
+                continue;
+            };
+            if !db.claim(schedule.id, due * 60)? {
+                continue;
+            }
+            eprintln!("[SCHEDULE] Running schedule {}: datasheet {}", schedule.id, schedule.command);
+            let outcome = command_args(&schedule.command).and_then(|args| run_child(&exe, &args));
+            match outcome {
+                Ok(()) => eprintln!("[SCHEDULE] Schedule {} finished", schedule.id),
+                Err(ref e) => eprintln!("[SCHEDULE] Schedule {} failed: {}", schedule.id, e),
+            }
+            db.record_run(schedule.id, due * 60, &outcome)?;
+        }
+
+        let wait = 60 - now().rem_euclid(60);
+        std::thread::sleep(Duration::from_secs(wait as u64));
+    }
+}
+
+fn run_child(exe: &std::path::Path, args: &[String]) -> Result<(), String> {
+    let status = Command::new(exe)
+        .args(args)
+        .stdin(Stdio::null())
+        .status()
+        .map_err(|e| format!("failed to start: {}", e))?;
+    if status.success() { Ok(()) } else { Err(format!("exited with {}", status)) }
+}
+
+/// "+02:00", "-0530", "+2", "UTC" → offset in seconds.
+fn parse_utc_offset(s: &str) -> Result<i64, String> {
+    let invalid = || format!("Invalid UTC offset '{}': expected e.g. +02:00 or -05:30", s);
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("utc") || s.eq_ignore_ascii_case("z") {
+        return Ok(0);
+    }
+    let (sign, rest) = match s.split_at_checked(1) {
+        Some(("+", rest)) => (1, rest),
+        Some(("-", rest)) => (-1, rest),
+        _ => return Err(invalid()),
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let hours: i64 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 14 || minutes > 59 {
+        return Err(invalid());
+    }
+    Ok(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Offset of the local time zone from UTC right now, in seconds.
+#[cfg(unix)]
+fn local_utc_offset() -> i64 {
+    let ts = now() as libc::time_t;
+    // SAFETY: an all-zero `tm` is valid, and localtime_r only writes into it.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&ts, &mut tm) }.is_null() {
+        return 0;
+    }
+    tm.tm_gmtoff as i64
+}
+
+/// Offset of the local time zone from UTC right now, in seconds.
+#[cfg(windows)]
+fn local_utc_offset() -> i64 {
+    use windows_sys::Win32::System::Time::{GetTimeZoneInformation, TIME_ZONE_ID_INVALID, TIME_ZONE_INFORMATION};
+    const TIME_ZONE_ID_DAYLIGHT: u32 = 2;
+
+    let mut info = TIME_ZONE_INFORMATION::default();
+    // SAFETY: GetTimeZoneInformation only writes into the struct it is given.
+    let bias = match unsafe { GetTimeZoneInformation(&mut info) } {
+        TIME_ZONE_ID_INVALID => return 0,
+        TIME_ZONE_ID_DAYLIGHT => info.Bias + info.DaylightBias,
+        _ => info.Bias + info.StandardBias,
+    };
+    // Bias is UTC minus local time, in minutes
+    -(bias as i64) * 60
+}
+
+#[cfg(not(any(unix, windows)))]
+fn local_utc_offset() -> i64 {
+    0
+}
+
+/// "2026-10-19 06:00" at `offset` seconds from UTC.
+pub(crate) fn format_time(ts: i64, offset: i64) -> String {
+    let t = CivilTime::at(ts, offset);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", t.year, t.month, t.day, t.hour, t.minute)
+}
+
+/// Broken-down local time.
+struct CivilTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    /// 0 = Sunday
+    weekday: u32,
+    hour: u32,
+    minute: u32,
+}
+
+impl CivilTime {
+    fn at(ts: i64, offset: i64) -> Self {
+        let local = ts + offset;
+        let days = local.div_euclid(86_400);
+        let secs = local.rem_euclid(86_400);
+        // Civil-from-days (Howard Hinnant's algorithm)
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        CivilTime {
+            year: yoe + era * 400 + i64::from(month <= 2),
+            month,
+            day,
+            // 1970-01-01 was a Thursday
+            weekday: (days + 4).rem_euclid(7) as u32,
+            hour: (secs / 3600) as u32,
+            minute: (secs % 3600 / 60) as u32,
+        }
+    }
+}
+
+/// A parsed five-field cron expression; each field is a bit set of allowed values.
+#[derive(Debug, PartialEq)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Day of month and day of week were both restricted: either may match
+    either_day: bool,
+}
+
+const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl Cron {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let expanded = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Invalid cron expression '{}': expected 5 fields (minute hour day month weekday)",
+                expr
+            ));
+        };
+        let field = |value, min, max, names: &[&str]| {
+            parse_field(value, min, max, names)
+                .map_err(|e| format!("Invalid cron expression '{}': {}", expr, e))
+        };
+        // Day of week 7 is Sunday too
+        let weekdays = field(weekday, 0, 7, &WEEKDAYS)?;
+        Ok(Cron {
+            minutes: field(minute, 0, 59, &[])?,
+            hours: field(hour, 0, 23, &[])?,
+            days: field(day, 1, 31, &[])?,
+            months: field(month, 1, 12, &MONTHS)?,
+            weekdays: (weekdays | weekdays >> 7) & 0x7f,
+            either_day: day != "*" && weekday != "*",
+        })
+    }
+
+    fn matches(&self, ts: i64, offset: i64) -> bool {
+        let t = CivilTime::at(ts, offset);
+        let bit = |set: u64, value: u32| set & (1 << value) != 0;
+        let day = bit(self.days, t.day);
+        let weekday = bit(self.weekdays, t.weekday);
+        bit(self.minutes, t.minute)
+            && bit(self.hours, t.hour)
+            && bit(self.months, t.month)
+            && if self.either_day { day || weekday } else { day && weekday }
+    }
+
+    /// Start of the first matching minute after `ts`, looking up to a year ahead.
+    fn next_after(&self, ts: i64, offset: i64) -> Option<i64> {
+        let first = ts.div_euclid(60) + 1;
+        (first..first + 366 * 1440).map(|minute| minute * 60).find(|&t| self.matches(t, offset))
+    }
+}
+
+/// One cron field ("*", "*/15", "1-5", "mon,wed,fri", "0-30/10") as a bit set.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        let lower = s.to_lowercase();
+        let n = match names.iter().position(|name| *name == lower) {
+            // Month names start at 1, weekday names at 0
+            Some(i) => i as u32 + min,
+            None => s.parse().map_err(|_| format!("'{}' is not a number", s))?,
+        };
+        if (min..=max).contains(&n) { Ok(n) } else { Err(format!("{} is outside {}-{}", n, min, max)) }
+    };
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("'{}' is not a step", step))?;
+                if step == 0 {
+                    return Err("step must be at least 1".to_string());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (value(a)?, value(b)?),
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            return Err(format!("range {}-{} is backwards", start, end));
+        }
+        for n in (start..=end).step_by(step as usize) {
+            set |= 1 << n;
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cron_schedule() {
+        // Monday 2026-10-19 06:00 UTC
+        let monday_six = 1_792_389_600;
+        let cron = Cron::parse("0 6 * * 1").unwrap();
+        assert!(cron.matches(monday_six, 0));
+        assert!(!cron.matches(monday_six + 60, 0));
+        assert!(!cron.matches(monday_six + 86_400, 0));
+        assert_eq!(cron.next_after(monday_six - 3 * 86_400, 0), Some(monday_six));
+        assert_eq!(cron.next_after(monday_six, 0), Some(monday_six + 7 * 86_400));
+        // 06:00 at UTC+2 is 04:00 UTC
+        assert_eq!(cron.next_after(monday_six - 86_400, 7200), Some(monday_six - 7200));
+        assert_eq!(format_time(monday_six, 0), "2026-10-19 06:00");
+
+        assert_eq!(Cron::parse("0 0 * * 0").unwrap(), Cron::parse("0 0 * * sun,7").unwrap());
+        assert_eq!(Cron::parse("@weekly").unwrap(), Cron::parse("0 0 * * SUN").unwrap());
+        assert_eq!(parse_field("*/20", 0, 59, &[]).unwrap(), 1 | 1 << 20 | 1 << 40);
+        assert_eq!(parse_field("1-5/2,jun", 1, 12, &MONTHS).unwrap(), 1 << 1 | 1 << 3 | 1 << 5 | 1 << 6);
+        assert!(Cron::parse("0 6 * *").is_err());
+        assert!(Cron::parse("60 * * * *").is_err());
+
+        // Day of month and weekday both set: either matches (the 1st, or any Monday)
+        let either = Cron::parse("0 6 1 * mon").unwrap();
+        assert!(either.matches(monday_six, 0));
+        assert!(either.matches(monday_six - 18 * 86_400, 0));
+
+        assert_eq!(parse_utc_offset("+02:00").unwrap(), 7200);
+        assert_eq!(parse_utc_offset("-0530").unwrap(), -19_800);
+        assert!(parse_utc_offset("02:00").is_err());
+        let local = local_utc_offset();
+        assert!(local.abs() <= 14 * 3600 && local % 900 == 0, "implausible local offset {}", local);
+    }
+}
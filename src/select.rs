@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! `--select`: pluck part of the JSON output without piping through jq.
+//!
+//! Two syntaxes are accepted:
+//! - jq-style paths: `.packages[0].pins`, `.parts[].mpn`, `."Mfr Part #"`,
+//!   `.pins[-1]`; `[]` iterates, and the results are collected into an array
+//! - JSON pointers: `/packages/0/pins`
+//!
+//! Missing keys and out-of-range indexes select `null`, as in jq.
+//!
+//! Every command that prints JSON (extractions and `--json` outputs) renders
+//! through [`to_string`] / [`to_string_pretty`], which apply the selection.
+//! Files written with `--out` use plain `serde_json`, so they are never cut down.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+static SELECTOR: OnceLock<Selector> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Key(String),
+    Index(i64),
+    Iterate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selector {
+    Path(Vec<Step>),
+    Pointer(String),
+}
+
+/// Set the selection applied to JSON output (called once from `main`).
+pub fn set(selector: Selector) {
+    let _ = SELECTOR.set(selector);
+}
+
+/// Parse a `--select` expression (clap value parser).
+pub fn parse(expr: &str) -> Result<Selector, String> {
+    let expr = expr.trim();
+    if expr.starts_with('/') {
+        return Ok(Selector::Pointer(expr.to_string()));
+    }
+    if !expr.starts_with('.') {
+        return Err(format!("'{}': expected a path starting with '.' or a JSON pointer starting with '/'", expr));
+    }
+
+    let chars: Vec<char> = expr.chars().collect();
+    let mut steps = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '.' if chars.get(i + 1) == Some(&'"') => {
+                let end = chars[i + 2..]
+                    .iter()
+                    .position(|&c| c == '"')
+                    .ok_or_else(|| format!("'{}': unterminated quoted key", expr))?;
+                steps.push(Step::Key(chars[i + 2..i + 2 + end].iter().collect()));
+                i += end + 3;
+            }
+            '.' => {
+                let len = chars[i + 1..]
+                    .iter()
+                    .take_while(|&&c| c.is_alphanumeric() || c == '_' || c == '-')
+                    .count();
+                if len > 0 {
+                    steps.push(Step::Key(chars[i + 1..i + 1 + len].iter().collect()));
+                }
+                i += len + 1;
+            }
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .ok_or_else(|| format!("'{}': missing ']'", expr))?;
+                let inner: String = chars[i + 1..i + end].iter().collect();
+                let inner = inner.trim();
+                steps.push(if inner.is_empty() {
+                    Step::Iterate
+                } else if let Some(key) = inner.strip_prefix('"').and_then(|k| k.strip_suffix('"')) {
+                    Step::Key(key.to_string())
+                } else {
+                    Step::Index(inner.parse().map_err(|_| format!("'{}': invalid index '{}'", expr, inner))?)
+                });
+                i += end + 1;
+            }
+            c => return Err(format!("'{}': unexpected '{}'", expr, c)),
+        }
+    }
+    Ok(Selector::Path(steps))
+}
+
+impl Selector {
+    pub fn apply(&self, value: &Value) -> Value {
+        match self {
+            Selector::Pointer(pointer) => value.pointer(pointer).cloned().unwrap_or(Value::Null),
+            Selector::Path(steps) => {
+                let mut current = vec![value.clone()];
+                let mut iterated = false;
+                for step in steps {
+                    current = current
+                        .into_iter()
+                        .flat_map(|v| match step {
+                            Step::Key(key) => vec![v.get(key).cloned().unwrap_or(Value::Null)],
+                            Step::Index(index) => vec![index_value(&v, *index)],
+                            Step::Iterate => {
+                                iterated = true;
+                                match v {
+                                    Value::Array(items) => items,
+                                    Value::Object(map) => map.into_iter().map(|(_, v)| v).collect(),
+                                    _ => Vec::new(),
+                                }
+                            }
+                        })
+                        .collect();
+                }
+                if iterated {
+                    Value::Array(current)
+                } else {
+                    current.pop().unwrap_or(Value::Null)
+                }
+            }
+        }
+    }
+}
+
+fn index_value(value: &Value, index: i64) -> Value {
+    let Some(items) = value.as_array() else {
+        return Value::Null;
+    };
+    let index = if index < 0 { items.len() as i64 + index } else { index };
+    usize::try_from(index)
+        .ok()
+        .and_then(|i| items.get(i))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+fn selected<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Value> {
+    let value = serde_json::to_value(value)?;
+    Ok(match SELECTOR.get() {
+        Some(selector) => selector.apply(&value),
+        None => value,
+    })
+}
+
+/// Compact JSON of `value` after applying `--select`.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    serde_json::to_string(&selected(value)?)
+}
+
+/// Pretty-printed JSON of `value` after applying `--select`.
+pub fn to_string_pretty<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&selected(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_select_paths() {
+        let doc = json!({
+            "packages": [{ "pins": [{ "pin_name": "VCC" }, { "pin_name": "GND" }] }],
+            "Mfr Part #": "LM358"
+        });
+        let select = |expr: &str| parse(expr).unwrap().apply(&doc);
+
+        assert_eq!(select("."), doc);
+        assert_eq!(select(".packages[0].pins[-1].pin_name"), json!("GND"));
+        assert_eq!(select(".packages[0].pins[].pin_name"), json!(["VCC", "GND"]));
+        assert_eq!(select(".\"Mfr Part #\""), json!("LM358"));
+        assert_eq!(select("/packages/0/pins/0/pin_name"), json!("VCC"));
+        assert_eq!(select(".missing.deeper"), Value::Null);
+        assert!(parse("packages").is_err());
+    }
+}
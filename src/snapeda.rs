@@ -37,7 +37,7 @@ mod cache {
     }
 
     fn cache_root() -> Option<PathBuf> {
-        dirs::cache_dir().map(|d| d.join("datasheet-cli").join("snapeda"))
+        Some(crate::file_cache::cache_root().join("snapeda"))
     }
 
     fn sanitize_key(key: &str) -> String {
@@ -177,8 +177,6 @@ struct FootprintInfo {
 
 #[derive(Deserialize, Debug)]
 struct SearchResponse {
-    #[allow(dead_code)]
-    query_type: Option<String>,
     results: Vec<SearchResult>,
 }
 
@@ -224,7 +222,6 @@ struct EagleSmd {
     y: f64,
     dx: f64,
     dy: f64,
-    layer: u32,
     cream: bool,
 }
 
@@ -247,21 +244,13 @@ struct EagleWire {
     y1: f64,
     x2: f64,
     y2: f64,
-    #[allow(dead_code)]
     width: f64,
     layer: u32,
 }
 
 struct EaglePin {
     name: String,
-    #[allow(dead_code)]
-    x: f64,
-    #[allow(dead_code)]
-    y: f64,
     direction: String,
-    rotation: Option<String>,
-    #[allow(dead_code)]
-    length: String,
 }
 
 struct EagleConnect {
@@ -282,7 +271,6 @@ struct EagleSymbol {
 
 struct EagleDeviceSet {
     name: String,
-    prefix: Option<String>,
     package_name: Option<String>,
     connects: Vec<EagleConnect>,
 }
@@ -413,6 +401,7 @@ struct ComponentDimensions {
 // --- Command execution ---
 
 pub fn execute(command: SnapedaSubcommand) -> Result<(), String> {
+    crate::offline::ensure_online("SnapEDA")?;
     match command {
         SnapedaSubcommand::Search { query, limit, json } => cmd_search(&query, limit, json),
         SnapedaSubcommand::Part {
@@ -443,7 +432,7 @@ fn cmd_search(query: &str, limit: usize, json_output: bool) -> Result<(), String
     let results: Vec<&SearchResult> = results.iter().take(limit).collect();
 
     if json_output {
-        let json = serde_json::to_string_pretty(&results)
+        let json = crate::select::to_string_pretty(&results)
             .map_err(|e| format!("Failed to serialize results: {}", e))?;
         println!("{}", json);
     } else {
@@ -716,7 +705,7 @@ fn cmd_symbol(
     let output = fetch_part_output(&resolved)?;
 
     let text = if json_output {
-        serialize_json(&output.pinout, formatted)?
+        serialize_json(&output.pinout, formatted, out.is_some())?
     } else {
         format_pinout_human(&output)
     };
@@ -734,7 +723,7 @@ fn cmd_footprint(
     let output = fetch_part_output(&resolved)?;
 
     let text = if json_output {
-        serialize_json(&output.footprint, formatted)?
+        serialize_json(&output.footprint, formatted, out.is_some())?
     } else {
         format_footprint_human(&output)
     };
@@ -1041,7 +1030,7 @@ fn cmd_download(part: &str, format: &str, out: Option<PathBuf>) -> Result<(), St
     // Infer output filename from the S3 URL
     let url_filename = download_url
         .split('/')
-        .last()
+        .next_back()
         .and_then(|s| s.split('?').next())
         .unwrap_or("download")
         .to_string();
@@ -1118,7 +1107,7 @@ fn cmd_download(part: &str, format: &str, out: Option<PathBuf>) -> Result<(), St
                     }
                     out_path.join(&file_basename)
                 };
-                std::fs::write(&target, &bytes)
+                crate::paths::write_file(&target, &bytes)
                     .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
                 eprintln!("Wrote: {}", target.display());
             } else {
@@ -1151,7 +1140,7 @@ fn cmd_download(part: &str, format: &str, out: Option<PathBuf>) -> Result<(), St
                         .map_err(|e| format!("Failed to create output directory: {}", e))?;
                 }
             }
-            std::fs::write(&target, &raw_bytes)
+            crate::paths::write_file(&target, &raw_bytes)
                 .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
             eprintln!("Wrote: {} ({} bytes)", target.display(), raw_bytes.len());
         } else {
@@ -1162,6 +1151,36 @@ fn cmd_download(part: &str, format: &str, out: Option<PathBuf>) -> Result<(), St
     Ok(())
 }
 
+/// KiCad library files for a part from an authenticated SnapEDA download.
+pub(crate) struct KicadFiles {
+    /// `.kicad_sym` symbol library text
+    pub symbol: Option<String>,
+    /// `.kicad_mod` footprint text
+    pub footprint: Option<String>,
+}
+
+/// Download the KiCad symbol and/or footprint for a part (requires `snapeda login`).
+pub(crate) fn download_kicad(part: &str, symbol: bool, footprint: bool) -> Result<KicadFiles, String> {
+    crate::offline::ensure_online("SnapEDA")?;
+    let (session_id, csrf) = get_session_cookies()
+        .ok_or_else(|| "Not logged in. Run 'datasheet snapeda login' first.".to_string())?;
+    let resolved = resolve_and_fetch(part)?;
+
+    let symbol = if symbol {
+        Some(authenticated_download_text(&resolved, &session_id, &csrf, "kicad", ".kicad_sym")?)
+    } else {
+        None
+    };
+    let footprint = if footprint {
+        Some(authenticated_download_text(
+            &resolved, &session_id, &csrf, "kicad_modv6", ".kicad_mod",
+        )?)
+    } else {
+        None
+    };
+    Ok(KicadFiles { symbol, footprint })
+}
+
 /// Authenticated download of a specific format, returning the raw bytes of the first
 /// file in the zip/archive that matches `target_ext`.
 fn authenticated_download_text(
@@ -1267,7 +1286,6 @@ fn parse_kicad_mod(content: &str, part_name: &str) -> Result<EagleLibrary, Strin
                             y,
                             dx: w,
                             dy: h,
-                            layer: 1,
                             cream: has_paste,
                         }));
                     }
@@ -1322,8 +1340,8 @@ fn parse_kicad_mod(content: &str, part_name: &str) -> Result<EagleLibrary, Strin
                 let width = extract_sexp_single_value(block, "width")
                     .or_else(|| extract_sexp_single_value(block, "stroke"))
                     .unwrap_or(0.05);
-                wires.push(EagleWire { x1, y1, x2: x2, y2: y1, width, layer: 39 });
-                wires.push(EagleWire { x1: x2, y1, x2: x2, y2, width, layer: 39 });
+                wires.push(EagleWire { x1, y1, x2, y2: y1, width, layer: 39 });
+                wires.push(EagleWire { x1: x2, y1, x2, y2, width, layer: 39 });
                 wires.push(EagleWire { x1: x2, y1: y2, x2: x1, y2, width, layer: 39 });
                 wires.push(EagleWire { x1, y1: y2, x2: x1, y2: y1, width, layer: 39 });
             }
@@ -1366,6 +1384,7 @@ fn extract_sexp_block(s: &str) -> Option<&str> {
     None
 }
 
+#[allow(clippy::collapsible_match)]
 fn tokenize_sexp_flat(s: &str) -> Vec<String> {
     let inner = s.trim();
     let inner = if inner.starts_with('(') && inner.ends_with(')') {
@@ -1433,7 +1452,7 @@ fn extract_sexp_single_value(block: &str, keyword: &str) -> Option<f64> {
     let pattern = format!("({} ", keyword);
     let start = block.find(&pattern)?;
     let after = &block[start + pattern.len()..];
-    let end = after.find(|c: char| c == ')' || c == ' ')?;
+    let end = after.find([')', ' '])?;
     after[..end].trim().parse::<f64>().ok()
 }
 
@@ -1517,7 +1536,6 @@ fn parse_eagle_xml(xml: &str) -> Result<EagleLibrary, String> {
                         let y = parse_f64(elem.attribute("y").unwrap_or("0"));
                         let dx = parse_f64(elem.attribute("dx").unwrap_or("0"));
                         let dy = parse_f64(elem.attribute("dy").unwrap_or("0"));
-                        let layer = parse_u32(elem.attribute("layer").unwrap_or("1"));
                         let cream = elem.attribute("cream").map(|v| v != "no").unwrap_or(true);
                         pads.push(EaglePad::Smd(EagleSmd {
                             name: pad_name,
@@ -1525,7 +1543,6 @@ fn parse_eagle_xml(xml: &str) -> Result<EagleLibrary, String> {
                             y,
                             dx,
                             dy,
-                            layer,
                             cream,
                         }));
                     } else if elem.has_tag_name("pad") {
@@ -1533,7 +1550,7 @@ fn parse_eagle_xml(xml: &str) -> Result<EagleLibrary, String> {
                         let x = parse_f64(elem.attribute("x").unwrap_or("0"));
                         let y = parse_f64(elem.attribute("y").unwrap_or("0"));
                         let drill = parse_f64(elem.attribute("drill").unwrap_or("0"));
-                        let diameter = elem.attribute("diameter").map(|v| parse_f64(v));
+                        let diameter = elem.attribute("diameter").map(parse_f64);
                         let shape = elem.attribute("shape").map(|v| v.to_string());
                         pads.push(EaglePad::ThroughHole(EagleThroughHolePad {
                             name: pad_name,
@@ -1563,19 +1580,8 @@ fn parse_eagle_xml(xml: &str) -> Result<EagleLibrary, String> {
 
                 for elem in sym_node.children().filter(|n| n.has_tag_name("pin")) {
                     let pin_name = elem.attribute("name").unwrap_or("").to_string();
-                    let x = parse_f64(elem.attribute("x").unwrap_or("0"));
-                    let y = parse_f64(elem.attribute("y").unwrap_or("0"));
                     let direction = elem.attribute("direction").unwrap_or("pas").to_string();
-                    let rotation = elem.attribute("rot").map(|v| v.to_string());
-                    let length = elem.attribute("length").unwrap_or("short").to_string();
-                    pins.push(EaglePin {
-                        name: pin_name,
-                        x,
-                        y,
-                        direction,
-                        rotation,
-                        length,
-                    });
+                    pins.push(EaglePin { name: pin_name, direction });
                 }
 
                 symbols.push(EagleSymbol { name, pins });
@@ -1583,7 +1589,6 @@ fn parse_eagle_xml(xml: &str) -> Result<EagleLibrary, String> {
         } else if child.has_tag_name("devicesets") {
             for ds_node in child.children().filter(|n| n.has_tag_name("deviceset")) {
                 let name = ds_node.attribute("name").unwrap_or("").to_string();
-                let prefix = ds_node.attribute("prefix").map(|v| v.to_string());
 
                 let mut package_name: Option<String> = None;
                 let mut connects: Vec<EagleConnect> = Vec::new();
@@ -1614,7 +1619,6 @@ fn parse_eagle_xml(xml: &str) -> Result<EagleLibrary, String> {
 
                 devicesets.push(EagleDeviceSet {
                     name,
-                    prefix,
                     package_name,
                     connects,
                 });
@@ -1711,10 +1715,10 @@ fn build_output(
 
     // Use the first deviceset
     let deviceset = library.devicesets.first();
-    let symbol_name = deviceset.and_then(|ds| {
+    let symbol_name = deviceset.map(|ds| {
         // The symbol name is typically the same as the deviceset name
         // but could differ; we look it up from the gates
-        Some(ds.name.clone())
+        ds.name.clone()
     });
 
     // Find symbol: prefer exact name match, else first
@@ -1834,7 +1838,7 @@ fn build_output(
         }
 
         // Sort pads numerically
-        fp_pads.sort_by(|a, b| a.number.cmp(&b.number));
+        fp_pads.sort_by_key(|a| a.number);
 
         // Courtyard: find wires on layer 39
         let courtyard_wires: Vec<&EagleWire> =
@@ -1932,19 +1936,20 @@ fn build_output(
 
 // --- Output helpers ---
 
-fn serialize_json<T: Serialize>(value: &T, formatted: bool) -> Result<String, String> {
-    if formatted {
-        serde_json::to_string_pretty(value)
-            .map_err(|e| format!("Failed to serialize output: {}", e))
-    } else {
-        serde_json::to_string(value)
-            .map_err(|e| format!("Failed to serialize output: {}", e))
-    }
+/// JSON text for `value`; `--select` only applies when it is printed rather than written to a file.
+fn serialize_json<T: Serialize>(value: &T, formatted: bool, to_file: bool) -> Result<String, String> {
+    let text = match (formatted, to_file) {
+        (true, true) => serde_json::to_string_pretty(value),
+        (false, true) => serde_json::to_string(value),
+        (true, false) => crate::select::to_string_pretty(value),
+        (false, false) => crate::select::to_string(value),
+    };
+    text.map_err(|e| format!("Failed to serialize output: {}", e))
 }
 
 fn write_output(text: &str, out: Option<PathBuf>) -> Result<(), String> {
     if let Some(path) = out {
-        std::fs::write(&path, text)
+        crate::paths::write_file(&path, text)
             .map_err(|e| format!("Failed to write output file: {}", e))?;
         eprintln!("Wrote output to: {}", path.display());
     } else {
@@ -1960,7 +1965,7 @@ fn emit_output(
     output: &SnapedaOutput,
 ) -> Result<(), String> {
     let text = if json_output {
-        serialize_json(output, formatted)?
+        serialize_json(output, formatted, out.is_some())?
     } else {
         format_full_human(output)
     };
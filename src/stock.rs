@@ -0,0 +1,347 @@
+//! Normalized stock/pricing data shared by the distributor integrations.
+//!
+//! Each distributor's `stock` subcommand emits this shape with `--json`, and
+//! cross-distributor features (BOM risk reports) consume it directly.
+//! Search commands render it as a table with `--columns` / `--csv`.
+
+use serde::Serialize;
+
+use crate::currency;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StockInfo {
+    pub mpn: String,
+    pub manufacturer: Option<String>,
+    pub distributor: &'static str,
+    pub distributor_pn: Option<String>,
+    pub lifecycle_status: Option<String>,
+    pub stock: Option<i64>,
+    pub lead_time: Option<String>,
+    pub moq: Option<i32>,
+    pub order_multiple: Option<i32>,
+    pub currency: String,
+    pub price_breaks: Vec<StockPriceBreak>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_replacement: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jlcpcb_category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub datasheet_url: Option<String>,
+}
+
+impl StockInfo {
+    /// Convert all prices to the `--currency` target, if one is set.
+    pub fn into_target_currency(mut self) -> Self {
+        let mut converted_to = None;
+        for pb in &mut self.price_breaks {
+            let (price, code) = currency::convert(pb.unit_price, &self.currency);
+            pb.unit_price = price;
+            converted_to = Some(code);
+        }
+        if let Some(code) = converted_to {
+            self.currency = code;
+        }
+        self
+    }
+}
+
+/// One keyword-search result, normalized across distributors.
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchHit {
+    pub mpn: String,
+    pub manufacturer: Option<String>,
+    pub description: Option<String>,
+    pub distributor: &'static str,
+    pub distributor_pn: Option<String>,
+    pub stock: Option<i64>,
+    pub datasheet_url: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StockPriceBreak {
+    pub quantity: i32,
+    pub unit_price: f64,
+}
+
+/// A search result as rendered by `--columns` / `--csv`.
+#[derive(Debug, Clone)]
+pub struct TableRow {
+    pub info: StockInfo,
+    pub description: Option<String>,
+}
+
+/// Columns used by `--csv` when `--columns` is not given.
+pub const DEFAULT_COLUMNS: &str = "mpn,mfr,stock,price,lifecycle";
+
+/// One `--columns` entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Mpn,
+    Manufacturer,
+    Description,
+    Distributor,
+    DistributorPn,
+    Stock,
+    Moq,
+    Lifecycle,
+    LeadTime,
+    Currency,
+    /// Unit price at this order quantity (`price` is the first price break)
+    Price(Option<i32>),
+}
+
+impl Column {
+    fn header(self) -> String {
+        match self {
+            Column::Mpn => "MPN".to_string(),
+            Column::Manufacturer => "Manufacturer".to_string(),
+            Column::Description => "Description".to_string(),
+            Column::Distributor => "Distributor".to_string(),
+            Column::DistributorPn => "SKU".to_string(),
+            Column::Stock => "Stock".to_string(),
+            Column::Moq => "MOQ".to_string(),
+            Column::Lifecycle => "Lifecycle".to_string(),
+            Column::LeadTime => "Lead time".to_string(),
+            Column::Currency => "Currency".to_string(),
+            Column::Price(None) => "Price".to_string(),
+            Column::Price(Some(qty)) => format!("Price@{}", qty),
+        }
+    }
+
+    fn numeric(self) -> bool {
+        matches!(self, Column::Stock | Column::Moq | Column::Price(_))
+    }
+
+    /// Cell text; prices are formatted with their currency unless `raw` (CSV).
+    fn cell(self, row: &TableRow, raw: bool) -> String {
+        let info = &row.info;
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        match self {
+            Column::Mpn => info.mpn.clone(),
+            Column::Manufacturer => text(&info.manufacturer),
+            Column::Description => text(&row.description),
+            Column::Distributor => info.distributor.to_string(),
+            Column::DistributorPn => text(&info.distributor_pn),
+            Column::Stock => info.stock.map(|s| s.to_string()).unwrap_or_default(),
+            Column::Moq => info.moq.map(|m| m.to_string()).unwrap_or_default(),
+            Column::Lifecycle => text(&info.lifecycle_status),
+            Column::LeadTime => text(&info.lead_time),
+            Column::Currency => info.currency.clone(),
+            Column::Price(qty) => match unit_price(&info.price_breaks, qty) {
+                Some(price) if raw => price.to_string(),
+                Some(price) => currency::format_price(price, &info.currency, 4),
+                None => String::new(),
+            },
+        }
+    }
+}
+
+/// Unit price for an order of `qty`: the largest price break not above it.
+/// `None` means the first break.
+pub(crate) fn unit_price(breaks: &[StockPriceBreak], qty: Option<i32>) -> Option<f64> {
+    match qty {
+        None => breaks.iter().min_by_key(|pb| pb.quantity).map(|pb| pb.unit_price),
+        Some(qty) => breaks
+            .iter()
+            .filter(|pb| pb.quantity <= qty)
+            .max_by_key(|pb| pb.quantity)
+            .map(|pb| pb.unit_price),
+    }
+}
+
+/// `--columns` / `--csv` output settings of a search command.
+#[derive(Debug, Clone)]
+pub struct TableFormat {
+    pub columns: Vec<Column>,
+    pub csv: bool,
+}
+
+impl TableFormat {
+    /// `None` when neither flag was given and the command prints its usual summary.
+    pub fn from_args(columns: Option<&str>, csv: bool) -> Result<Option<Self>, String> {
+        if columns.is_none() && !csv {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            columns: parse_columns(columns.unwrap_or(DEFAULT_COLUMNS))?,
+            csv,
+        }))
+    }
+
+    pub fn print(&self, rows: &[TableRow]) {
+        print!("{}", render_table(rows, &self.columns, self.csv));
+    }
+}
+
+/// Columns shown for each candidate when a lookup matches several parts.
+const PICK_COLUMNS: &[Column] = &[
+    Column::Mpn,
+    Column::Manufacturer,
+    Column::DistributorPn,
+    Column::Stock,
+    Column::Description,
+];
+
+/// Ask which of several matching parts was meant (see `picker`); returns its index.
+pub fn pick(what: &str, rows: &[TableRow]) -> Result<usize, String> {
+    let table = render_table(rows, PICK_COLUMNS, false);
+    let lines: Vec<String> = table.lines().skip(1).map(str::to_string).collect();
+    crate::picker::pick(what, &lines)
+}
+
+/// Parse a `--columns` list such as `mpn,mfr,stock,price@100,lifecycle`.
+pub fn parse_columns(spec: &str) -> Result<Vec<Column>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let lower = name.to_ascii_lowercase();
+            if let Some(qty) = lower.strip_prefix("price@") {
+                return qty
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|&q| q > 0)
+                    .map(|q| Column::Price(Some(q)))
+                    .ok_or_else(|| format!("Invalid quantity in column '{}': expected e.g. price@100", name));
+            }
+            Ok(match lower.as_str() {
+                "mpn" => Column::Mpn,
+                "mfr" | "manufacturer" => Column::Manufacturer,
+                "desc" | "description" => Column::Description,
+                "distributor" => Column::Distributor,
+                "sku" | "dpn" => Column::DistributorPn,
+                "stock" => Column::Stock,
+                "moq" => Column::Moq,
+                "lifecycle" | "status" => Column::Lifecycle,
+                "lead" | "lead_time" => Column::LeadTime,
+                "currency" => Column::Currency,
+                "price" => Column::Price(None),
+                _ => {
+                    return Err(format!(
+                        "Unknown column '{}'. Available: mpn, mfr, desc, distributor, sku, stock, moq, \
+                         lifecycle, lead, currency, price, price@<qty>",
+                        name
+                    ));
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .and_then(|columns| {
+            if columns.is_empty() {
+                Err("--columns needs at least one column".to_string())
+            } else {
+                Ok(columns)
+            }
+        })
+}
+
+/// Render rows as an aligned table, or as CSV with a header line.
+pub fn render_table(rows: &[TableRow], columns: &[Column], csv: bool) -> String {
+    let headers: Vec<String> = columns.iter().map(|c| c.header()).collect();
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| columns.iter().map(|c| c.cell(row, csv)).collect())
+        .collect();
+
+    let mut out = String::new();
+    if csv {
+        for line in std::iter::once(&headers).chain(&cells) {
+            let fields: Vec<String> = line.iter().map(|field| csv_field(field)).collect();
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        return out;
+    }
+
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            std::iter::once(&headers)
+                .chain(&cells)
+                .map(|line| line[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    for line in std::iter::once(&headers).chain(&cells) {
+        let fields: Vec<String> = line
+            .iter()
+            .zip(columns)
+            .zip(&widths)
+            .map(|((field, column), &width)| {
+                let field = if field.is_empty() { "-" } else { field };
+                if column.numeric() {
+                    format!("{:>width$}", field)
+                } else {
+                    format!("{:<width$}", field)
+                }
+            })
+            .collect();
+        out.push_str(fields.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(mpn: &str, stock: Option<i64>, breaks: &[(i32, f64)]) -> TableRow {
+        TableRow {
+            info: StockInfo {
+                mpn: mpn.to_string(),
+                manufacturer: Some("Texas Instruments".to_string()),
+                distributor: "digikey",
+                distributor_pn: None,
+                lifecycle_status: Some("Active".to_string()),
+                stock,
+                lead_time: None,
+                moq: Some(1),
+                order_multiple: None,
+                currency: "USD".to_string(),
+                price_breaks: breaks
+                    .iter()
+                    .map(|&(quantity, unit_price)| StockPriceBreak { quantity, unit_price })
+                    .collect(),
+                suggested_replacement: None,
+                jlcpcb_category: None,
+                datasheet_url: None,
+            },
+            description: Some("Buck, 1A, \"SO-8\"".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_columns_table_and_csv() {
+        let columns = parse_columns("mpn, stock,price@100,desc").unwrap();
+        assert_eq!(columns[2], Column::Price(Some(100)));
+        assert!(parse_columns("mpn,colour").is_err());
+        assert!(parse_columns("price@0").is_err());
+
+        let rows = [
+            row("LM5164DDAR", Some(12543), &[(1, 2.43), (10, 1.98), (100, 1.5)]),
+            row("LM5164DDAT", None, &[]),
+        ];
+        let csv = render_table(&rows, &columns, true);
+        assert_eq!(
+            csv,
+            "MPN,Stock,Price@100,Description\n\
+             LM5164DDAR,12543,1.5,\"Buck, 1A, \"\"SO-8\"\"\"\n\
+             LM5164DDAT,,,\"Buck, 1A, \"\"SO-8\"\"\"\n"
+        );
+
+        let table = render_table(&rows, &parse_columns("mpn,stock,price@50").unwrap(), false);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "MPN         Stock  Price@50");
+        assert_eq!(lines[1], "LM5164DDAR  12543   $1.9800");
+        assert_eq!(lines[2], "LM5164DDAT      -         -");
+    }
+}
@@ -0,0 +1,216 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! One-page datasheet summaries tailored to who is reading them.
+//!
+//! Each audience has its own prompt (sharing one schema), so a firmware
+//! summary covers interfaces and timing while a procurement summary covers
+//! orderable parts and compliance.
+
+use crate::error::Error;
+use crate::extract::{DEFAULT_MODEL, __DEFAULT__, resolve_model};
+use crate::llm::{LlmProvider, LlmRequest, build_client, resolve_api_key};
+use crate::{page_render, prompts};
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum Audience {
+    /// Interfaces, registers, timing, power modes
+    Firmware,
+    /// Package, decoupling, routing, thermal
+    Layout,
+    /// Orderable parts, grades, compliance, lifecycle
+    Procurement,
+}
+
+impl Audience {
+    fn prompt(self) -> prompts::PromptSpec {
+        match self {
+            Audience::Firmware => prompts::summary_firmware(),
+            Audience::Layout => prompts::summary_layout(),
+            Audience::Procurement => prompts::summary_procurement(),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct SummarizeArgs {
+    /// Datasheet PDF
+    pub pdf: PathBuf,
+
+    /// Who the summary is for
+    #[arg(long, value_enum, default_value = "firmware")]
+    pub audience: Audience,
+
+    /// LLM provider
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
+    pub provider: LlmProvider,
+
+    /// Model name
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
+    pub model: String,
+
+    /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Optional base URL override for Gemini API
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Disable file caching (re-upload PDF every request)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Write the summary to a file instead of stdout
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+pub fn run(args: &SummarizeArgs) -> Result<()> {
+    if !args.pdf.exists() {
+        return Err(Error::not_found(format!("PDF not found: {}", args.pdf.display())).into());
+    }
+    let spec = args.audience.prompt();
+    let model = resolve_model(&args.model, DEFAULT_MODEL);
+
+    let data = std::fs::read(&args.pdf).with_context(|| format!("reading {}", args.pdf.display()))?;
+    let key = format!(
+        "{}|{}|{}|{}",
+        spec.name,
+        crate::file_cache::compute_hash(&data),
+        model,
+        crate::file_cache::compute_hash(spec.prompt.as_bytes())
+    );
+
+    let summary = if crate::offline::is_enabled() {
+        let cached = crate::offline::cached_result(&key).ok_or_else(|| {
+            Error::not_found(format!(
+                "--offline: no cached {} for {}; run it once online first",
+                spec.name,
+                args.pdf.display()
+            ))
+        })?;
+        eprintln!("[OFFLINE] Using cached {} for {}", spec.name, args.pdf.display());
+        cached
+    } else {
+        let api_key = resolve_api_key(args.provider, args.api_key.clone())?;
+        let attachment =
+            page_render::make_attachment(&args.pdf, args.no_cache, &api_key, &args.base_url)?;
+        let client = build_client(args.provider, api_key, args.base_url.clone())?;
+        let _usage = crate::usage::scope(spec.name, Some(&args.pdf));
+        let response = client.generate_json(LlmRequest {
+            model,
+            prompt: spec.prompt.to_string(),
+            schema: spec.schema,
+            attachment,
+            temperature: Some(0.0),
+        })?;
+        crate::offline::store_result(&key, &response.json);
+        response.json
+    };
+
+    let output = if args.json && args.out.is_some() {
+        format!("{}\n", serde_json::to_string_pretty(&summary)?)
+    } else if args.json {
+        format!("{}\n", crate::select::to_string_pretty(&summary)?)
+    } else {
+        format_summary(&summary)
+    };
+    match args.out {
+        Some(ref path) => {
+            crate::paths::write_file(path, output).with_context(|| format!("writing {}", path.display()))?;
+            eprintln!("Summary written to {}", path.display());
+        }
+        None => print!("{}", output),
+    }
+    Ok(())
+}
+
+/// Render a summary as Markdown.
+fn format_summary(summary: &Value) -> String {
+    let text = |v: &Value, key: &str| {
+        v.get(key).and_then(|t| t.as_str()).unwrap_or_default().to_string()
+    };
+    let cited = |v: &Value| match v.get("page").and_then(|p| p.as_i64()) {
+        Some(page) => format!("{} (p. {})", text(v, "text"), page),
+        None => text(v, "text"),
+    };
+    let items = |v: &Value, key: &str| {
+        v.get(key).and_then(|i| i.as_array()).cloned().unwrap_or_default()
+    };
+
+    let mut out = String::new();
+    let _ = write!(out, "# {}", text(summary, "part_number"));
+    let title = text(summary, "title");
+    if !title.is_empty() {
+        let _ = write!(out, " - {}", title);
+    }
+    let _ = writeln!(out, "\n\n{}", text(summary, "overview"));
+
+    let facts = items(summary, "key_facts");
+    if !facts.is_empty() {
+        out.push_str("\n| Key fact | Value | Page |\n|---|---|---|\n");
+        for fact in &facts {
+            let page = fact
+                .get("page")
+                .and_then(|p| p.as_i64())
+                .map(|p| p.to_string())
+                .unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                text(fact, "label"),
+                text(fact, "value"),
+                page
+            );
+        }
+    }
+    for section in items(summary, "sections") {
+        let _ = writeln!(out, "\n## {}\n", text(&section, "heading"));
+        for point in items(&section, "points") {
+            let _ = writeln!(out, "- {}", cited(&point));
+        }
+    }
+    let cautions = items(summary, "cautions");
+    if !cautions.is_empty() {
+        out.push_str("\n## Cautions\n\n");
+        for caution in &cautions {
+            let _ = writeln!(out, "- {}", cited(caution));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_summary() {
+        let summary = json!({
+            "part_number": "BME280",
+            "title": "Combined humidity and pressure sensor",
+            "overview": "I2C/SPI environmental sensor.",
+            "key_facts": [{"label": "I2C address", "value": "0x76 / 0x77", "page": 33}],
+            "sections": [{"heading": "Interfaces", "points": [{"text": "SPI up to 10 MHz.", "page": 34}]}],
+            "cautions": [{"text": "No errata are listed.", "page": null}]
+        });
+        let text = format_summary(&summary);
+        assert!(text.starts_with(
+            "# BME280 - Combined humidity and pressure sensor\n\nI2C/SPI environmental sensor.\n"
+        ));
+        assert!(text.contains("| I2C address | 0x76 / 0x77 | 33 |"));
+        assert!(text.contains("## Interfaces\n\n- SPI up to 10 MHz. (p. 34)\n"));
+        assert!(text.contains("## Cautions\n\n- No errata are listed.\n"));
+    }
+}
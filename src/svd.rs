@@ -31,7 +31,7 @@ pub enum SvdSubcommand {
         /// Filter by vendor (useful when chip name is ambiguous)
         #[arg(long)]
         vendor: Option<String>,
-        /// Output file path (defaults to <chip>.svd in current directory)
+        /// Output file path (defaults to <download dir>/datasheets/<chip>.svd)
         #[arg(long, short)]
         out: Option<PathBuf>,
     },
@@ -66,7 +66,7 @@ struct GitTreeEntry {
 // --- Cache ---
 
 fn cache_dir() -> Option<PathBuf> {
-    dirs::cache_dir().map(|d| d.join("datasheet-cli").join("svd"))
+    Some(crate::file_cache::cache_root().join("svd"))
 }
 
 fn get_cached_index() -> Option<Vec<SvdEntry>> {
@@ -98,6 +98,7 @@ fn fetch_svd_index() -> Result<Vec<SvdEntry>, String> {
         return Ok(cached);
     }
 
+    crate::offline::ensure_online("Fetching the SVD index")?;
     eprintln!("Fetching SVD index from GitHub (this may take a moment)...");
 
     let response = ureq::get(GITHUB_TREES_URL)
@@ -164,7 +165,7 @@ fn cmd_search(query: &str, vendor: Option<&str>, json: bool) -> Result<(), Strin
     if json {
         println!(
             "{}",
-            serde_json::to_string_pretty(&results).unwrap_or_default()
+            crate::select::to_string_pretty(&results).unwrap_or_default()
         );
         return Ok(());
     }
@@ -230,8 +231,9 @@ fn cmd_download(chip: &str, vendor: Option<&str>, out: Option<PathBuf>) -> Resul
 
     let entry = matches[0];
     let url = format!("{}/{}", RAW_BASE_URL, entry.path);
-    let dest = out.unwrap_or_else(|| PathBuf::from(format!("{}.svd", entry.chip)));
+    let dest = out.unwrap_or_else(|| crate::paths::datasheets_dir().join(format!("{}.svd", entry.chip)));
 
+    crate::offline::ensure_online("Downloading SVD files")?;
     eprintln!("Downloading {} from cmsis-svd-data...", entry.filename);
 
     let response = ureq::get(&url)
@@ -245,7 +247,11 @@ fn cmd_download(chip: &str, vendor: Option<&str>, out: Option<PathBuf>) -> Resul
         .read_to_end(&mut content)
         .map_err(|e| format!("Failed to read response: {e}"))?;
 
-    std::fs::write(&dest, &content).map_err(|e| format!("Failed to write file: {e}"))?;
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    crate::paths::write_file(&dest, &content).map_err(|e| format!("Failed to write file: {e}"))?;
 
     println!("Downloaded {} ({} bytes) to {}", entry.filename, content.len(), dest.display());
     Ok(())
@@ -264,7 +270,7 @@ fn cmd_vendors(json: bool) -> Result<(), String> {
             .iter()
             .map(|(v, c)| serde_json::json!({"vendor": v, "count": c}))
             .collect();
-        println!("{}", serde_json::to_string_pretty(&vendors).unwrap_or_default());
+        println!("{}", crate::select::to_string_pretty(&vendors).unwrap_or_default());
         return Ok(());
     }
 
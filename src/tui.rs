@@ -0,0 +1,444 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Interactive terminal UI for exploring parts.
+//!
+//! Search a distributor, inspect stock and pricing for a result, then queue
+//! datasheet downloads or extractions. Downloads and extractions run as child
+//! `datasheet` processes (`library add`, `part`) so their log output streams
+//! into the jobs pane instead of corrupting the screen.
+
+use clap::{Args, ValueEnum};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::bom::Source;
+use crate::extract::ExtractTask;
+use crate::stock::{SearchHit, StockInfo};
+
+const SOURCES: &[Source] = &[Source::Mouser, Source::Digikey, Source::Jlcpcb];
+
+#[derive(Args, Debug)]
+pub struct TuiArgs {
+    /// Distributor to search first
+    #[arg(long, value_enum, default_value = "mouser")]
+    pub source: Source,
+
+    /// Initial search query
+    pub query: Option<String>,
+
+    /// Maximum search results
+    #[arg(long, default_value_t = 50)]
+    pub limit: usize,
+}
+
+/// Results from background work, delivered to the UI thread.
+enum Message {
+    Search(Result<Vec<SearchHit>, String>),
+    Details(String, Result<Box<StockInfo>, String>),
+    JobLine(usize, String),
+    JobDone(usize, Result<(), String>),
+}
+
+#[derive(PartialEq, Eq)]
+enum JobState {
+    Running,
+    Done,
+    Failed,
+}
+
+struct Job {
+    label: String,
+    state: JobState,
+    last_line: String,
+}
+
+struct App {
+    source: usize,
+    query: String,
+    editing: bool,
+    limit: usize,
+    hits: Vec<SearchHit>,
+    list: ListState,
+    details: Option<Result<StockInfo, String>>,
+    task: ExtractTask,
+    jobs: Vec<Job>,
+    status: String,
+    tx: Sender<Message>,
+}
+
+pub fn run(args: &TuiArgs) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut app = App {
+        source: SOURCES.iter().position(|s| *s == args.source).unwrap_or(0),
+        query: args.query.clone().unwrap_or_default(),
+        editing: args.query.is_none(),
+        limit: args.limit,
+        hits: Vec::new(),
+        list: ListState::default(),
+        details: None,
+        task: ExtractTask::Pinout,
+        jobs: Vec::new(),
+        status: String::new(),
+        tx,
+    };
+    if !app.query.is_empty() {
+        app.search();
+    }
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, &rx);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App, rx: &Receiver<Message>) -> anyhow::Result<()> {
+    loop {
+        while let Ok(message) = rx.try_recv() {
+            app.handle(message);
+        }
+        terminal.draw(|frame| draw(frame, app))?;
+
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        if app.editing {
+            match key.code {
+                KeyCode::Enter => {
+                    app.editing = false;
+                    app.search();
+                }
+                KeyCode::Esc => app.editing = false,
+                KeyCode::Backspace => {
+                    app.query.pop();
+                }
+                KeyCode::Char(c) => app.query.push(c),
+                _ => {}
+            }
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('/') | KeyCode::Char('i') => app.editing = true,
+            KeyCode::Tab => {
+                app.source = (app.source + 1) % SOURCES.len();
+                app.search();
+            }
+            KeyCode::Down | KeyCode::Char('j') => app.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => app.move_selection(-1),
+            KeyCode::Enter => app.load_details(),
+            KeyCode::Char('t') => app.cycle_task(),
+            KeyCode::Char('d') => app.queue_download(),
+            KeyCode::Char('e') => app.queue_extraction(),
+            _ => {}
+        }
+    }
+}
+
+impl App {
+    fn source(&self) -> Source {
+        SOURCES[self.source]
+    }
+
+    fn selected(&self) -> Option<&SearchHit> {
+        self.list.selected().and_then(|i| self.hits.get(i))
+    }
+
+    fn search(&mut self) {
+        let query = self.query.trim().to_string();
+        if query.is_empty() {
+            return;
+        }
+        self.status = format!("Searching {} for '{}'...", self.source().name(), query);
+        let (source, limit, tx) = (self.source(), self.limit, self.tx.clone());
+        thread::spawn(move || {
+            let _ = tx.send(Message::Search(source.search(&query, limit)));
+        });
+    }
+
+    fn load_details(&mut self) {
+        let Some(hit) = self.selected() else { return };
+        let (mpn, distributor) = (hit.mpn.clone(), hit.distributor);
+        let Some(source) = SOURCES.iter().copied().find(|s| s.name() == distributor) else {
+            return;
+        };
+        self.status = format!("Loading {} from {}...", mpn, distributor);
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let result = source.lookup_stock(&mpn).map(Box::new);
+            let _ = tx.send(Message::Details(mpn, result));
+        });
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.hits.is_empty() {
+            return;
+        }
+        let current = self.list.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, self.hits.len() as isize - 1) as usize;
+        self.list.select(Some(next));
+        self.details = None;
+    }
+
+    fn cycle_task(&mut self) {
+        let tasks: Vec<ExtractTask> = ExtractTask::value_variants()
+            .iter()
+            .copied()
+            .filter(|t| !matches!(t, ExtractTask::Custom))
+            .collect();
+        let current = tasks.iter().position(|t| t.prompt().name == self.task.prompt().name);
+        self.task = tasks[current.map_or(0, |i| (i + 1) % tasks.len())];
+    }
+
+    fn queue_download(&mut self) {
+        let Some(hit) = self.selected().cloned() else { return };
+        let Some(ref url) = hit.datasheet_url else {
+            self.status = format!("{} has no datasheet link on {}", hit.mpn, hit.distributor);
+            return;
+        };
+        let mut args = vec!["library".to_string(), "add".to_string(), url.clone(), "--mpn".to_string(), hit.mpn.clone()];
+        if let Some(ref mfr) = hit.manufacturer {
+            args.extend(["--manufacturer".to_string(), mfr.clone()]);
+        }
+        self.spawn_job(format!("download {}", hit.mpn), args);
+    }
+
+    fn queue_extraction(&mut self) {
+        let Some(hit) = self.selected().cloned() else { return };
+        let task = task_arg(self.task);
+        let out = format!("{}.{}.json", crate::paths::sanitize_filename(&hit.mpn), task);
+        let mut args = vec![
+            "part".to_string(),
+            hit.mpn.clone(),
+            "--tasks".to_string(),
+            task.clone(),
+            "--source".to_string(),
+            format!("{},auto", hit.distributor),
+            "--out".to_string(),
+            out.clone(),
+            "--formatted".to_string(),
+        ];
+        if let Some(ref mfr) = hit.manufacturer {
+            args.extend(["--manufacturer".to_string(), mfr.clone()]);
+        }
+        self.spawn_job(format!("{} {} -> {}", task, hit.mpn, out), args);
+    }
+
+    /// Run `datasheet <args>` in the background, streaming its stderr into the jobs pane.
+    fn spawn_job(&mut self, label: String, mut args: Vec<String>) {
+        if crate::offline::is_enabled() {
+            args.insert(0, "--offline".to_string());
+        }
+        let id = self.jobs.len();
+        self.jobs.push(Job {
+            label,
+            state: JobState::Running,
+            last_line: "starting...".to_string(),
+        });
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let result = run_child(id, &args, &tx);
+            let _ = tx.send(Message::JobDone(id, result));
+        });
+    }
+
+    fn handle(&mut self, message: Message) {
+        match message {
+            Message::Search(Ok(hits)) => {
+                self.status = format!("{} result(s) from {}", hits.len(), self.source().name());
+                self.list.select(if hits.is_empty() { None } else { Some(0) });
+                self.hits = hits;
+                self.details = None;
+            }
+            Message::Search(Err(e)) => self.status = format!("Search failed: {}", e),
+            Message::Details(mpn, result) => {
+                if self.selected().is_some_and(|hit| hit.mpn == mpn) {
+                    self.status.clear();
+                    self.details = Some(result.map(|info| *info));
+                }
+            }
+            Message::JobLine(id, line) => self.jobs[id].last_line = line,
+            Message::JobDone(id, result) => {
+                let job = &mut self.jobs[id];
+                match result {
+                    Ok(()) => {
+                        job.state = JobState::Done;
+                        job.last_line = "done".to_string();
+                    }
+                    Err(e) => {
+                        job.state = JobState::Failed;
+                        job.last_line = e;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn task_arg(task: ExtractTask) -> String {
+    task.to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn run_child(id: usize, args: &[String], tx: &Sender<Message>) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let mut child = Command::new(exe)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to start: {}", e))?;
+
+    let mut last = String::new();
+    if let Some(stderr) = child.stderr.take() {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            let line = line.trim().to_string();
+            if line.is_empty() {
+                continue;
+            }
+            last = line.clone();
+            let _ = tx.send(Message::JobLine(id, line));
+        }
+    }
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(if last.is_empty() { format!("exited with {}", status) } else { last })
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [search_area, main_area, jobs_area, help_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(8),
+        Constraint::Length(8),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [results_area, details_area] =
+        Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(main_area);
+
+    let search_style = if app.editing {
+        Style::new().yellow()
+    } else {
+        Style::new()
+    };
+    let search = Paragraph::new(app.query.as_str())
+        .style(search_style)
+        .block(Block::bordered().title(format!(" Search: {} (Tab to switch) ", app.source().name())));
+    frame.render_widget(search, search_area);
+    if app.editing {
+        frame.set_cursor_position((search_area.x + 1 + app.query.chars().count() as u16, search_area.y + 1));
+    }
+
+    let items: Vec<ListItem> = app
+        .hits
+        .iter()
+        .map(|hit| {
+            let stock = hit.stock.map_or("-".to_string(), |s| s.to_string());
+            ListItem::new(format!(
+                "{:<22} {:>9}  {}",
+                hit.mpn,
+                stock,
+                hit.manufacturer.as_deref().unwrap_or("")
+            ))
+        })
+        .collect();
+    let results = List::new(items)
+        .block(Block::bordered().title(format!(" Results ({}) ", app.hits.len())))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(results, results_area, &mut app.list);
+
+    let details = Paragraph::new(detail_lines(app))
+        .wrap(Wrap { trim: false })
+        .block(Block::bordered().title(" Details (Enter to load stock) "));
+    frame.render_widget(details, details_area);
+
+    let jobs: Vec<ListItem> = app
+        .jobs
+        .iter()
+        .rev()
+        .map(|job| {
+            let (marker, style) = match job.state {
+                JobState::Running => ("…", Style::new().yellow()),
+                JobState::Done => ("✓", Style::new().green()),
+                JobState::Failed => ("✗", Style::new().red()),
+            };
+            ListItem::new(format!("{} {}: {}", marker, job.label, job.last_line)).style(style)
+        })
+        .collect();
+    frame.render_widget(List::new(jobs).block(Block::bordered().title(" Jobs ")), jobs_area);
+
+    let help = format!(
+        " / search  ↑↓ select  Enter details  d download  e extract [{}]  t task  q quit   {}",
+        app.task.prompt().name,
+        app.status
+    );
+    frame.render_widget(Line::from(help).dim(), help_area);
+}
+
+fn detail_lines(app: &App) -> Vec<Line<'static>> {
+    let Some(hit) = app.selected() else {
+        return vec![Line::from("No part selected")];
+    };
+    let mut lines = vec![Line::from(hit.mpn.clone()).bold()];
+    if let Some(ref mfr) = hit.manufacturer {
+        lines.push(Line::from(format!("Manufacturer: {}", mfr)));
+    }
+    if let Some(ref pn) = hit.distributor_pn {
+        lines.push(Line::from(format!("{} #: {}", hit.distributor, pn)));
+    }
+    if let Some(ref desc) = hit.description {
+        lines.push(Line::from(desc.clone()));
+    }
+    lines.push(Line::from(format!(
+        "Datasheet: {}",
+        hit.datasheet_url.as_deref().unwrap_or("(none)")
+    )));
+
+    match app.details {
+        Some(Ok(ref info)) => {
+            lines.push(Line::from(""));
+            if let Some(ref status) = info.lifecycle_status {
+                lines.push(Line::from(format!("Lifecycle: {}", status)));
+            }
+            if let Some(stock) = info.stock {
+                lines.push(Line::from(format!("Stock: {}", stock)));
+            }
+            if let Some(ref lead) = info.lead_time {
+                lines.push(Line::from(format!("Lead time: {}", lead)));
+            }
+            for pb in &info.price_breaks {
+                lines.push(Line::from(format!(
+                    "  {:>7}+  {}",
+                    pb.quantity,
+                    crate::currency::format_price(pb.unit_price, &info.currency, 4)
+                )));
+            }
+        }
+        Some(Err(ref e)) => lines.push(Line::from(format!("Lookup failed: {}", e)).red()),
+        None => {}
+    }
+    lines
+}
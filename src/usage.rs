@@ -0,0 +1,329 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! LLM usage ledger and `datasheet usage`.
+//!
+//! Every Gemini response is appended to `<data dir>/datasheet-cli/usage.jsonl`
+//! (or `$DATASHEET_USAGE_LEDGER`) with the model, token counts, estimated
+//! cost, task, and PDF hash. `datasheet usage --month 2026-01` totals a month
+//! by model and by task.
+//!
+//! Costs are estimated from list prices at the time of the call and stored in
+//! the ledger, so later price changes do not rewrite history.
+
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const ENV_VAR_LEDGER: &str = "DATASHEET_USAGE_LEDGER";
+
+/// USD per million (prompt, output) tokens; first matching prefix wins.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gemini-3.1-pro", 2.00, 12.00),
+    ("gemini-3-pro", 2.00, 12.00),
+    ("gemini-3-flash", 0.50, 3.00),
+    ("gemini-2.5-pro", 1.25, 10.00),
+    ("gemini-2.5-flash-lite", 0.10, 0.40),
+    ("gemini-2.5-flash", 0.30, 2.50),
+    ("gemini-2.0-flash-lite", 0.075, 0.30),
+    ("gemini-2.0-flash", 0.10, 0.40),
+];
+
+#[derive(Args, Debug)]
+pub struct UsageArgs {
+    /// Month to summarize, as YYYY-MM (default: the current month, UTC)
+    #[arg(long, value_name = "YYYY-MM", value_parser = parse_month)]
+    pub month: Option<String>,
+
+    /// Output JSON instead of tables
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// One LLM call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageEntry {
+    /// Unix seconds
+    pub ts: u64,
+    pub model: String,
+    pub task: String,
+    pub pdf_sha256: Option<String>,
+    pub prompt_tokens: u64,
+    pub output_tokens: u64,
+    /// Estimated USD; `None` for models without a known price
+    pub cost: Option<f64>,
+}
+
+#[derive(Clone)]
+struct Context {
+    task: String,
+    pdf_sha256: Option<String>,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
+    static SESSION: RefCell<Totals> = const {
+        RefCell::new(Totals { calls: 0, prompt_tokens: 0, output_tokens: 0, cost: 0.0, unpriced_calls: 0 })
+    };
+}
+
+/// Attributes LLM calls on this thread to a task until dropped.
+pub struct Scope {
+    previous: Option<Context>,
+}
+
+/// Attribute LLM calls made on this thread to `task` (and `pdf`) while the guard lives.
+pub fn scope(task: &str, pdf: Option<&Path>) -> Scope {
+    let context = Context {
+        task: task.to_string(),
+        pdf_sha256: pdf
+            .and_then(|p| std::fs::read(p).ok())
+            .map(|data| crate::file_cache::compute_hash(&data)),
+    };
+    Scope {
+        previous: CONTEXT.with(|c| c.replace(Some(context))),
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        CONTEXT.with(|c| *c.borrow_mut() = self.previous.take());
+    }
+}
+
+/// Estimated USD for a call, if the model's price is known.
+pub fn estimate_cost(model: &str, prompt_tokens: u64, output_tokens: u64) -> Option<f64> {
+    let model = model.trim_start_matches("models/");
+    PRICES
+        .iter()
+        .find(|(prefix, _, _)| model.starts_with(prefix))
+        .map(|(_, input, output)| {
+            (prompt_tokens as f64 * input + output_tokens as f64 * output) / 1_000_000.0
+        })
+}
+
+/// Append a ledger entry for one LLM response; failures are only warnings.
+pub fn record(model: &str, prompt_tokens: u64, output_tokens: u64) {
+    let context = CONTEXT.with(|c| c.borrow().clone());
+    let entry = UsageEntry {
+        ts: now_secs(),
+        model: model.to_string(),
+        task: context.as_ref().map_or_else(|| "unknown".to_string(), |c| c.task.clone()),
+        pdf_sha256: context.and_then(|c| c.pdf_sha256),
+        prompt_tokens,
+        output_tokens,
+        cost: estimate_cost(model, prompt_tokens, output_tokens),
+    };
+    SESSION.with(|s| s.borrow_mut().add(&entry));
+    if let Some(cost) = entry.cost {
+        crate::budget::record_cost(cost);
+    }
+    if let Err(e) = append(&entry) {
+        eprintln!("[USAGE] Warning: could not record usage: {}", e);
+    }
+}
+
+/// Totals for the LLM calls made on this thread so far (for per-run costs).
+pub fn session_totals() -> Totals {
+    SESSION.with(|s| s.borrow().clone())
+}
+
+/// Number of LLM calls recorded in the ledger since midnight UTC, by any process.
+pub fn calls_today() -> Result<u64, String> {
+    let midnight = now_secs() - now_secs() % 86_400;
+    let entries = read_entries(&ledger_path()?)?;
+    Ok(entries.iter().filter(|e| e.ts >= midnight).count() as u64)
+}
+
+fn ledger_path() -> Result<PathBuf, String> {
+    match std::env::var(ENV_VAR_LEDGER) {
+        Ok(path) if !path.trim().is_empty() => Ok(PathBuf::from(path)),
+        _ => Ok(dirs::data_dir()
+            .ok_or_else(|| "Could not determine data directory".to_string())?
+            .join("datasheet-cli")
+            .join("usage.jsonl")),
+    }
+}
+
+fn append(entry: &UsageEntry) -> Result<(), String> {
+    let path = ledger_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    line.push('\n');
+    // Appends of one short line are atomic, so parallel runs can share the ledger
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| f.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn read_entries(path: &Path) -> Result<Vec<UsageEntry>, String> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    Ok(std::io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct Totals {
+    pub calls: u64,
+    pub prompt_tokens: u64,
+    pub output_tokens: u64,
+    pub cost: f64,
+    /// Calls whose model has no known price (not included in `cost`)
+    pub unpriced_calls: u64,
+}
+
+impl Totals {
+    fn add(&mut self, entry: &UsageEntry) {
+        self.calls += 1;
+        self.prompt_tokens += entry.prompt_tokens;
+        self.output_tokens += entry.output_tokens;
+        match entry.cost {
+            Some(cost) => self.cost += cost,
+            None => self.unpriced_calls += 1,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub month: String,
+    pub total: Totals,
+    pub by_model: BTreeMap<String, Totals>,
+    pub by_task: BTreeMap<String, Totals>,
+}
+
+fn summarize(entries: &[UsageEntry], month: &str) -> Summary {
+    let mut summary = Summary { month: month.to_string(), ..Default::default() };
+    for entry in entries.iter().filter(|e| month_of(e.ts) == month) {
+        summary.total.add(entry);
+        summary.by_model.entry(entry.model.clone()).or_default().add(entry);
+        summary.by_task.entry(entry.task.clone()).or_default().add(entry);
+    }
+    summary
+}
+
+pub fn run(args: &UsageArgs) -> anyhow::Result<()> {
+    let month = args.month.clone().unwrap_or_else(|| month_of(now_secs()));
+    let path = ledger_path().map_err(|e| anyhow::anyhow!(e))?;
+    let summary = summarize(&read_entries(&path).map_err(|e| anyhow::anyhow!(e))?, &month);
+
+    if args.json {
+        println!("{}", crate::select::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+
+    if summary.total.calls == 0 {
+        println!("No LLM usage recorded for {} ({}).", month, path.display());
+        return Ok(());
+    }
+    println!("Usage for {}\n", month);
+    print_table("Model", &summary.by_model);
+    println!();
+    print_table("Task", &summary.by_task);
+    println!(
+        "\nTotal: {} call(s), {} prompt + {} output tokens, ~${:.2}",
+        summary.total.calls, summary.total.prompt_tokens, summary.total.output_tokens, summary.total.cost
+    );
+    if summary.total.unpriced_calls > 0 {
+        println!(
+            "({} call(s) used models without a known price and are not in the cost)",
+            summary.total.unpriced_calls
+        );
+    }
+    Ok(())
+}
+
+fn print_table(label: &str, rows: &BTreeMap<String, Totals>) {
+    println!(
+        "{:<28} {:>7} {:>14} {:>14} {:>10}",
+        label, "Calls", "Prompt tok", "Output tok", "Cost (USD)"
+    );
+    for (name, totals) in rows {
+        println!(
+            "{:<28} {:>7} {:>14} {:>14} {:>10.2}",
+            name, totals.calls, totals.prompt_tokens, totals.output_tokens, totals.cost
+        );
+    }
+}
+
+fn parse_month(s: &str) -> Result<String, String> {
+    let valid = s.len() == 7
+        && s.as_bytes()[4] == b'-'
+        && s[..4].parse::<u32>().is_ok()
+        && matches!(s[5..].parse::<u32>(), Ok(1..=12));
+    if valid {
+        Ok(s.to_string())
+    } else {
+        Err(format!("'{}': expected YYYY-MM", s))
+    }
+}
+
+/// `YYYY-MM` (UTC) of a Unix timestamp.
+fn month_of(ts: u64) -> String {
+    // Civil-from-days (Howard Hinnant's algorithm)
+    let z = (ts / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}", year, month)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_month() {
+        let entry = |ts, model: &str, task: &str| UsageEntry {
+            ts,
+            model: model.to_string(),
+            task: task.to_string(),
+            pdf_sha256: None,
+            prompt_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cost: estimate_cost(model, 1_000_000, 100_000),
+        };
+        let entries = [
+            entry(1_767_225_600, "gemini-2.5-flash", "pinout"), // 2026-01-01
+            entry(1_769_903_999, "gemini-2.5-flash-lite", "pinout"), // 2026-01-31
+            entry(1_769_904_000, "gemini-2.5-flash", "power"), // 2026-02-01
+            entry(1_767_225_600, "local-model", "footprint"),
+        ];
+
+        let summary = summarize(&entries, "2026-01");
+        assert_eq!(summary.total.calls, 3);
+        assert_eq!(summary.total.unpriced_calls, 1);
+        assert!((summary.by_model["gemini-2.5-flash"].cost - 0.55).abs() < 1e-9);
+        assert!((summary.by_task["pinout"].cost - 0.69).abs() < 1e-9);
+        assert!(!summary.by_task.contains_key("power"));
+        assert!(parse_month("2026-13").is_err());
+    }
+}
@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Marking cross-check for incoming inspection.
+//!
+//! Extracts the expected topside marking for an MPN from its datasheet (the
+//! `marking` extraction task) and prints it beside the distributor product photo
+//! URLs, so a physical part or listing photo can be checked against it.
+
+use crate::extract::{DEFAULT_MODEL, __DEFAULT__, resolve_model};
+use crate::library::Library;
+use crate::llm::{LlmProvider, LlmRequest, build_client, resolve_api_key};
+use crate::{digikey, mouser, page_render, prompts};
+use anyhow::{Result, anyhow};
+use clap::Args;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+
+type PhotoLookup = fn(&str) -> Result<Option<String>, crate::error::Error>;
+
+#[derive(Args, Debug)]
+pub struct VerifyMarkingArgs {
+    /// Manufacturer part number to check
+    pub mpn: String,
+
+    /// Datasheet PDF (defaults to the part's entry in the datasheet library)
+    #[arg(long)]
+    pub datasheet: Option<PathBuf>,
+
+    /// LLM provider
+    #[arg(long, default_value = "gemini", hide = true, env = "DATASHEET_PROVIDER")]
+    pub provider: LlmProvider,
+
+    /// Model name
+    #[arg(long, default_value = __DEFAULT__, env = "DATASHEET_MODEL")]
+    pub model: String,
+
+    /// API key (falls back to GOOGLE_API_KEY or GEMINI_API_KEY)
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Optional base URL override for Gemini API
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Disable file caching (re-upload PDF every request)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct Photo {
+    distributor: &'static str,
+    url: String,
+}
+
+#[derive(Serialize, Debug)]
+struct MarkingReport<'a> {
+    mpn: &'a str,
+    datasheet: PathBuf,
+    /// Marking entries for the requested MPN (all entries if none matched)
+    markings: Vec<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<String>,
+    photos: Vec<Photo>,
+}
+
+pub fn run(args: &VerifyMarkingArgs) -> Result<()> {
+    let datasheet = match args.datasheet {
+        Some(ref pdf) => pdf.clone(),
+        None => library_datasheet(&args.mpn)?,
+    };
+    if !datasheet.exists() {
+        return Err(anyhow!("PDF not found: {}", datasheet.display()));
+    }
+
+    let extraction = extract_marking(args, &datasheet)?;
+    if let Some(error) = extraction.get("error").and_then(|e| e.as_str()) {
+        return Err(anyhow!("{}: {}", datasheet.display(), error));
+    }
+    let all: Vec<Value> = extraction
+        .get("markings")
+        .and_then(|m| m.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let markings = markings_for(&all, &args.mpn);
+    let notes = extraction
+        .get("notes")
+        .and_then(|n| n.as_array())
+        .map(|n| n.iter().filter_map(|s| s.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+
+    let mut photos = Vec::new();
+    let lookups: [(&'static str, PhotoLookup); 2] = [
+        ("digikey", digikey::find_photo_url),
+        ("mouser", mouser::find_photo_url),
+    ];
+    for (distributor, lookup) in lookups {
+        match lookup(&args.mpn) {
+            Ok(Some(url)) => photos.push(Photo { distributor, url }),
+            Ok(None) => eprintln!("[MARKING] {}: no product photo", distributor),
+            Err(e) => eprintln!("[MARKING] {}: {}", distributor, e),
+        }
+    }
+
+    let report = MarkingReport {
+        mpn: &args.mpn,
+        datasheet,
+        markings: if markings.is_empty() { all } else { markings },
+        notes,
+        photos,
+    };
+
+    if args.json {
+        println!("{}", crate::select::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("Expected marking for {} ({}):", report.mpn, report.datasheet.display());
+    if report.markings.is_empty() {
+        println!("  (no marking information found)");
+    }
+    for marking in &report.markings {
+        let field = |key: &str| marking.get(key).and_then(|v| v.as_str());
+        println!();
+        print!("  {}", field("orderable_part_number").unwrap_or("?"));
+        if let Some(package) = field("package") {
+            print!(" [{}]", package);
+        }
+        if let Some(page) = marking.get("source_page").and_then(|p| p.as_i64()) {
+            print!("  (page {})", page);
+        }
+        println!();
+        for line in marking
+            .get("marking_lines")
+            .and_then(|l| l.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|l| l.as_str())
+        {
+            println!("    | {}", line);
+        }
+        if let Some(legend) = marking.get("legend").and_then(|l| l.as_object()) {
+            for (code, meaning) in legend {
+                println!("    {} = {}", code, meaning.as_str().unwrap_or_default());
+            }
+        }
+        if let Some(pin1) = field("pin1_indicator") {
+            println!("    Pin 1: {}", pin1);
+        }
+    }
+    for note in &report.notes {
+        println!("  Note: {}", note);
+    }
+
+    println!();
+    if report.photos.is_empty() {
+        println!("No distributor product photos found.");
+    }
+    for photo in &report.photos {
+        println!("Photo ({}): {}", photo.distributor, photo.url);
+    }
+
+    Ok(())
+}
+
+/// Datasheet path for an exact MPN match in the local library.
+fn library_datasheet(mpn: &str) -> Result<PathBuf> {
+    let library = Library::open(None).map_err(|e| anyhow!(e))?;
+    let entry = library
+        .find(mpn)
+        .map_err(|e| anyhow!(e))?
+        .into_iter()
+        .find(|e| e.mpn.eq_ignore_ascii_case(mpn))
+        .ok_or_else(|| {
+            anyhow!(
+                "No datasheet for {} in the library; pass --datasheet or run 'datasheet library add'",
+                mpn
+            )
+        })?;
+    Ok(library.pdf_path(&entry))
+}
+
+fn extract_marking(args: &VerifyMarkingArgs, pdf: &std::path::Path) -> Result<Value> {
+    let api_key = resolve_api_key(args.provider, args.api_key.clone())?;
+    let model = resolve_model(&args.model, DEFAULT_MODEL);
+    let spec = prompts::marking();
+
+    let attachment = page_render::make_attachment(pdf, args.no_cache, &api_key, &args.base_url)?;
+    let client = build_client(args.provider, api_key, args.base_url.clone())?;
+    let _usage = crate::usage::scope("verify-marking", Some(pdf));
+    eprintln!("[MARKING] Extracting marking from {} with {}", pdf.display(), model);
+    let response = client.generate_json(LlmRequest {
+        model,
+        prompt: spec.prompt.to_string(),
+        schema: spec.schema,
+        attachment,
+        temperature: Some(0.0),
+    })?;
+    Ok(response.json)
+}
+
+/// Marking entries whose orderable part number matches `mpn`.
+///
+/// Datasheets often list the base part while distributors sell a packaging
+/// variant (or vice versa), so a prefix match in either direction counts.
+fn markings_for(markings: &[Value], mpn: &str) -> Vec<Value> {
+    let mpn = mpn.to_uppercase();
+    let orderable = |m: &Value| {
+        m.get("orderable_part_number")
+            .and_then(|p| p.as_str())
+            .map(str::to_uppercase)
+            .unwrap_or_default()
+    };
+    let exact: Vec<Value> = markings.iter().filter(|m| orderable(m) == mpn).cloned().collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+    markings
+        .iter()
+        .filter(|m| {
+            let pn = orderable(m);
+            !pn.is_empty() && (mpn.starts_with(&pn) || pn.starts_with(&mpn))
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_markings_for() {
+        let markings = vec![
+            json!({"orderable_part_number": "TPS62130RGTR", "marking_lines": ["62130"]}),
+            json!({"orderable_part_number": "TPS62130RGTT", "marking_lines": ["62130"]}),
+            json!({"orderable_part_number": "TPS62131RGTR", "marking_lines": ["62131"]}),
+        ];
+        assert_eq!(markings_for(&markings, "tps62130rgtr").len(), 1);
+        assert_eq!(markings_for(&markings, "TPS62130").len(), 2);
+        assert!(markings_for(&markings, "LM358").is_empty());
+    }
+}
@@ -0,0 +1,363 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Stock and price watcher.
+//!
+//! Polls distributors for the parts listed in a TOML config, records every
+//! observation in the parts database, and notifies when stock or price crosses
+//! a configured threshold.
+//!
+//! ```toml
+//! interval_minutes = 60
+//!
+//! [notify]
+//! webhook = "https://hooks.example.com/..."   # JSON POST
+//! command = "mail -s 'datasheet watch' me@example.com"   # message on stdin
+//!
+//! [[watch]]
+//! mpn = "STM32F407VGT6"
+//! sources = ["mouser", "digikey"]
+//! min_stock = 500
+//! max_price = 12.5
+//! quantity = 100
+//! ```
+
+use anyhow::{Context, Result, anyhow};
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use crate::bom::Source;
+use crate::db::{self, PartsDb, StockSample};
+use crate::stock::StockInfo;
+
+const DEFAULT_INTERVAL_MINUTES: u64 = 60;
+
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    /// TOML file listing the parts to watch
+    #[arg(long, short)]
+    pub config: PathBuf,
+
+    /// Poll once and exit instead of running on the configured interval
+    #[arg(long)]
+    pub once: bool,
+
+    /// Database path for history (defaults to DATASHEET_DB or the platform data directory)
+    #[arg(long)]
+    pub db: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug)]
+struct WatchConfig {
+    #[serde(default = "default_interval")]
+    interval_minutes: u64,
+    #[serde(default)]
+    notify: NotifyConfig,
+    #[serde(default, rename = "watch")]
+    watches: Vec<WatchEntry>,
+}
+
+fn default_interval() -> u64 {
+    DEFAULT_INTERVAL_MINUTES
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct NotifyConfig {
+    /// URL that receives a JSON POST per alert
+    webhook: Option<String>,
+    /// Shell command that receives the alert text on stdin (e.g. a mail command)
+    command: Option<String>,
+    /// Set to false to silence stdout alerts (history is still recorded)
+    #[serde(default = "default_true")]
+    stdout: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug)]
+struct WatchEntry {
+    mpn: String,
+    #[serde(default = "default_sources")]
+    sources: Vec<String>,
+    /// Alert when stock at a distributor drops below (or recovers to) this level
+    min_stock: Option<i64>,
+    /// Alert when the unit price rises above (or falls back to) this value
+    max_price: Option<f64>,
+    /// Order quantity used to pick the price break
+    #[serde(default = "default_quantity")]
+    quantity: i32,
+}
+
+fn default_sources() -> Vec<String> {
+    vec!["mouser".to_string(), "digikey".to_string(), "jlcpcb".to_string()]
+}
+
+fn default_quantity() -> i32 {
+    1
+}
+
+#[derive(Serialize, Debug)]
+struct Alert {
+    mpn: String,
+    distributor: &'static str,
+    kind: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stock: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit_price: Option<f64>,
+}
+
+pub fn run(args: &WatchArgs) -> Result<()> {
+    let text = std::fs::read_to_string(&args.config)
+        .with_context(|| format!("reading {}", args.config.display()))?;
+    let config: WatchConfig = toml::from_str(&text)
+        .with_context(|| format!("parsing {}", args.config.display()))?;
+    if config.watches.is_empty() {
+        return Err(anyhow!("No [[watch]] entries in {}", args.config.display()));
+    }
+
+    let db = PartsDb::open(args.db.clone()).map_err(|e| anyhow!(e))?;
+
+    loop {
+        for entry in &config.watches {
+            for alert in poll_entry(&db, entry) {
+                notify(&config.notify, &alert);
+            }
+        }
+        if args.once {
+            return Ok(());
+        }
+        eprintln!("[WATCH] Sleeping {} minute(s)", config.interval_minutes);
+        thread::sleep(Duration::from_secs(config.interval_minutes.max(1) * 60));
+    }
+}
+
+/// Poll every source for one entry, record history, and return any threshold crossings.
+fn poll_entry(db: &PartsDb, entry: &WatchEntry) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    for name in &entry.sources {
+        let Ok(source) = Source::from_str(name, true) else {
+            eprintln!("[WATCH] {}: unknown source '{}'", entry.mpn, name);
+            continue;
+        };
+        match source.lookup_stock(&entry.mpn) {
+            Ok(info) => alerts.extend(observe(db, entry, &info)),
+            Err(e) => eprintln!("[WATCH] {}: {}: {}", entry.mpn, source.name(), e),
+        }
+    }
+    alerts
+}
+
+/// Record one lookup and compare it with the previous poll at the entry's quantity.
+fn observe(db: &PartsDb, entry: &WatchEntry, info: &StockInfo) -> Vec<Alert> {
+    let sample = StockSample {
+        stock: info.stock,
+        unit_price: price_at(info, entry.quantity),
+        currency: Some(info.currency.clone()),
+        recorded_at: db::now(),
+        quantity: Some(entry.quantity),
+    };
+    let previous = db
+        .last_stock(&entry.mpn, info.distributor, sample.quantity, sample.currency.as_deref())
+        .unwrap_or_else(|e| {
+            eprintln!("[WATCH] {}", e);
+            None
+        });
+    if let Err(e) = db.record_stock(&entry.mpn, info.distributor, &sample) {
+        eprintln!("[WATCH] {}", e);
+    }
+    eprintln!(
+        "[WATCH] {} @ {}: stock {}, price {}",
+        entry.mpn,
+        info.distributor,
+        sample.stock.map_or("?".to_string(), |s| s.to_string()),
+        sample
+            .unit_price
+            .map_or("?".to_string(), |p| format!("{:.4} {}", p, info.currency))
+    );
+
+    check_thresholds(entry, info.distributor, previous.as_ref(), &sample)
+}
+
+/// Unit price at an order quantity: the largest price break not above it.
+fn price_at(info: &StockInfo, quantity: i32) -> Option<f64> {
+    info.price_breaks
+        .iter()
+        .filter(|pb| pb.quantity <= quantity.max(1))
+        .max_by_key(|pb| pb.quantity)
+        .or_else(|| info.price_breaks.iter().min_by_key(|pb| pb.quantity))
+        .map(|pb| pb.unit_price)
+}
+
+/// Compare against the previous observation; with no history, alert if already past the threshold.
+fn check_thresholds(
+    entry: &WatchEntry,
+    distributor: &'static str,
+    previous: Option<&StockSample>,
+    current: &StockSample,
+) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    let alert = |kind, message: String| Alert {
+        mpn: entry.mpn.clone(),
+        distributor,
+        kind,
+        message,
+        stock: current.stock,
+        unit_price: current.unit_price,
+    };
+
+    if let (Some(min), Some(stock)) = (entry.min_stock, current.stock) {
+        let was_low = previous.and_then(|p| p.stock).map(|s| s < min);
+        let is_low = stock < min;
+        if was_low != Some(is_low) && (is_low || was_low.is_some()) {
+            let message = if is_low {
+                format!("{} stock at {} dropped to {} (below {})", entry.mpn, distributor, stock, min)
+            } else {
+                format!("{} stock at {} recovered to {} (at least {})", entry.mpn, distributor, stock, min)
+            };
+            alerts.push(alert(if is_low { "stock_low" } else { "stock_recovered" }, message));
+        }
+    }
+
+    if let (Some(max), Some(price)) = (entry.max_price, current.unit_price) {
+        let was_high = previous.and_then(|p| p.unit_price).map(|p| p > max);
+        let is_high = price > max;
+        if was_high != Some(is_high) && (is_high || was_high.is_some()) {
+            let message = if is_high {
+                format!(
+                    "{} price at {} rose to {:.4} @ {} (above {})",
+                    entry.mpn, distributor, price, entry.quantity, max
+                )
+            } else {
+                format!(
+                    "{} price at {} fell to {:.4} @ {} (at most {})",
+                    entry.mpn, distributor, price, entry.quantity, max
+                )
+            };
+            alerts.push(alert(if is_high { "price_high" } else { "price_ok" }, message));
+        }
+    }
+
+    alerts
+}
+
+fn notify(config: &NotifyConfig, alert: &Alert) {
+    if config.stdout {
+        println!("[ALERT] {}", alert.message);
+    }
+
+    if let Some(ref url) = config.webhook {
+        let body = serde_json::json!({
+            "text": alert.message,
+            "alert": alert,
+        });
+        if let Err(e) = crate::offline::ensure_online("Webhook alerts") {
+            eprintln!("[WATCH] {}", e);
+        } else if let Err(e) = ureq::post(url).send_json(body) {
+            eprintln!("[WATCH] Webhook failed: {}", e);
+        }
+    }
+
+    if let Some(ref command) = config.command {
+        let result = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .spawn()
+            .and_then(|mut child| {
+                if let Some(mut stdin) = child.stdin.take() {
+                    writeln!(stdin, "{}", alert.message)?;
+                }
+                child.wait()
+            });
+        match result {
+            Ok(status) if !status.success() => eprintln!("[WATCH] Notify command exited with {}", status),
+            Err(e) => eprintln!("[WATCH] Notify command failed: {}", e),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(stock: i64, price: f64) -> StockSample {
+        StockSample {
+            stock: Some(stock),
+            unit_price: Some(price),
+            currency: None,
+            recorded_at: 0,
+            quantity: None,
+        }
+    }
+
+    #[test]
+    fn test_threshold_crossings() {
+        let entry: WatchEntry = toml::from_str("mpn = \"X\"\nmin_stock = 100\nmax_price = 2.0").unwrap();
+
+        // First observation already past both thresholds
+        let alerts = check_thresholds(&entry, "mouser", None, &sample(50, 3.0));
+        assert_eq!(alerts.iter().map(|a| a.kind).collect::<Vec<_>>(), ["stock_low", "price_high"]);
+
+        // No change in state: no alerts
+        assert!(check_thresholds(&entry, "mouser", Some(&sample(50, 3.0)), &sample(40, 2.5)).is_empty());
+
+        // Recovery
+        let alerts = check_thresholds(&entry, "mouser", Some(&sample(50, 3.0)), &sample(500, 1.5));
+        assert_eq!(alerts.iter().map(|a| a.kind).collect::<Vec<_>>(), ["stock_recovered", "price_ok"]);
+
+        // Healthy first observation: nothing to report
+        assert!(check_thresholds(&entry, "mouser", None, &sample(500, 1.5)).is_empty());
+    }
+
+    #[test]
+    fn test_search_snapshot_between_polls() {
+        use crate::stock::StockPriceBreak;
+
+        let db = PartsDb::open_in_memory().unwrap();
+        let entry: WatchEntry = toml::from_str("mpn = \"LM358DR\"\nmax_price = 2.0\nquantity = 100").unwrap();
+        let info = |price_at_100: f64| StockInfo {
+            mpn: "LM358DR".to_string(),
+            manufacturer: None,
+            distributor: "mouser",
+            distributor_pn: None,
+            lifecycle_status: None,
+            stock: Some(5000),
+            lead_time: None,
+            moq: None,
+            order_multiple: None,
+            currency: "USD".to_string(),
+            price_breaks: vec![
+                StockPriceBreak { quantity: 1, unit_price: 3.0 },
+                StockPriceBreak { quantity: 100, unit_price: price_at_100 },
+            ],
+            suggested_replacement: None,
+            jlcpcb_category: None,
+            datasheet_url: None,
+        };
+
+        assert!(observe(&db, &entry, &info(1.5)).is_empty());
+        // `mouser search --record-history` snapshots the quantity-1 price in between
+        let snapshot = StockSample {
+            stock: Some(5000),
+            unit_price: Some(3.0),
+            currency: Some("USD".to_string()),
+            recorded_at: db::now(),
+            quantity: Some(1),
+        };
+        db.record_stock("LM358DR", "mouser", &snapshot).unwrap();
+        // Still under the threshold at quantity 100: no price_ok from the 3.0 snapshot
+        assert!(observe(&db, &entry, &info(1.6)).is_empty());
+        let alerts = observe(&db, &entry, &info(2.5));
+        assert_eq!(alerts.iter().map(|a| a.kind).collect::<Vec<_>>(), ["price_high"]);
+    }
+}
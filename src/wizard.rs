@@ -0,0 +1,290 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! Interactive builder for `extract custom --interactive`.
+//!
+//! Asks what to extract and which fields (name, type, description) the result
+//! should have, generates a JSON schema and prompt from the answers, previews
+//! them, runs the extraction, and can save the pair as a user task that
+//! `extract custom --user-task <NAME>` loads later.
+//!
+//! User tasks live in `<config dir>/datasheet-cli/tasks/<NAME>/` as
+//! `prompt.md` and `schema.json`.
+
+use anyhow::{Context, Result, anyhow};
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use crate::extract::{self, ExtractArgs};
+use crate::prompts;
+
+/// Value type of a field in the generated schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    StringList,
+    NumberList,
+    /// Array of objects with their own columns (e.g. a table of pins)
+    Table,
+}
+
+impl FieldKind {
+    const CHOICES: &'static [(&'static str, FieldKind)] = &[
+        ("string", FieldKind::String),
+        ("number", FieldKind::Number),
+        ("integer", FieldKind::Integer),
+        ("boolean", FieldKind::Boolean),
+        ("string[]", FieldKind::StringList),
+        ("number[]", FieldKind::NumberList),
+        ("table", FieldKind::Table),
+    ];
+
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_ascii_lowercase();
+        if s.is_empty() {
+            return Some(FieldKind::String);
+        }
+        Self::CHOICES
+            .iter()
+            .find(|(name, _)| *name == s || name.trim_end_matches("[]").to_string() + "s" == s)
+            .map(|&(_, kind)| kind)
+    }
+
+    fn name(self) -> &'static str {
+        Self::CHOICES
+            .iter()
+            .find(|&&(_, kind)| kind == self)
+            .map(|&(name, _)| name)
+            .unwrap_or("string")
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    name: String,
+    kind: FieldKind,
+    description: String,
+    /// Columns of a `table` field
+    columns: Vec<Field>,
+}
+
+pub fn run(args: &ExtractArgs) -> Result<()> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+
+    eprintln!("Custom extraction wizard for {}", args.pdf.display());
+    eprintln!("Answer each question; press Enter on an empty field name to finish.\n");
+
+    let goal = ask(&mut input, "What should be extracted? (one sentence)")?;
+    if goal.is_empty() {
+        return Err(anyhow!("A description of what to extract is required"));
+    }
+    let fields = ask_fields(&mut input, "Field", 0)?;
+    if fields.is_empty() {
+        return Err(anyhow!("At least one field is required"));
+    }
+
+    let schema = build_schema(&fields);
+    let prompt = build_prompt(&goal, &fields);
+    eprintln!("\nGenerated schema:\n{}\n", serde_json::to_string_pretty(&schema)?);
+
+    if confirm(&mut input, "Run the extraction now?", true)? {
+        let run_args = ExtractArgs {
+            prompt: Some(prompt.clone()),
+            schema: Some(schema.to_string()),
+            interactive: false,
+            ..args.clone()
+        };
+        extract::run_extract(&run_args)?;
+    }
+
+    let name = ask(&mut input, "Save as a reusable user task? Name (empty to skip)")?;
+    if !name.is_empty() {
+        let dir = save_user_task(&name, &prompt, &schema)?;
+        eprintln!("Saved to {}", dir.display());
+        eprintln!(
+            "Run it again with: datasheet extract custom <PDF> --user-task {}",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Directory holding the saved user task `name`.
+pub fn user_task_dir(name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow!(
+            "Invalid task name '{}' (use letters, digits, '-' and '_')",
+            name
+        ));
+    }
+    let config = dirs::config_dir().ok_or_else(|| anyhow!("Could not determine config directory"))?;
+    Ok(config.join("datasheet-cli").join("tasks").join(name))
+}
+
+/// Prompt and schema file paths for a saved user task.
+pub fn user_task_files(name: &str) -> Result<(PathBuf, PathBuf)> {
+    let dir = user_task_dir(name)?;
+    let (prompt, schema) = (dir.join("prompt.md"), dir.join("schema.json"));
+    if !prompt.is_file() || !schema.is_file() {
+        if let Some(files) = crate::prompt_source::task_files(name)? {
+            return Ok(files);
+        }
+        return Err(anyhow!("No user task '{}' in {}", name, dir.display()));
+    }
+    Ok((prompt, schema))
+}
+
+fn save_user_task(name: &str, prompt: &str, schema: &Value) -> Result<PathBuf> {
+    let dir = user_task_dir(name)?;
+    fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    crate::paths::write_file(&dir.join("prompt.md"), prompt).context("writing prompt.md")?;
+    crate::paths::write_file(&dir.join("schema.json"), serde_json::to_string_pretty(schema)?)
+        .context("writing schema.json")?;
+    Ok(dir)
+}
+
+fn ask_fields(input: &mut impl BufRead, label: &str, depth: usize) -> Result<Vec<Field>> {
+    let indent = "  ".repeat(depth);
+    let types: Vec<&str> = FieldKind::CHOICES
+        .iter()
+        .map(|&(name, _)| name)
+        .filter(|&name| depth == 0 || name != "table")
+        .collect();
+
+    let mut fields = Vec::new();
+    loop {
+        let name = ask(input, &format!("{}{} name", indent, label))?;
+        if name.is_empty() {
+            return Ok(fields);
+        }
+        let kind = loop {
+            let answer = ask(input, &format!("{}  Type [{}] (default string)", indent, types.join(", ")))?;
+            match FieldKind::parse(&answer) {
+                Some(FieldKind::Table) if depth > 0 => eprintln!("{}  Tables cannot be nested", indent),
+                Some(kind) => break kind,
+                None => eprintln!("{}  Unknown type '{}'", indent, answer),
+            }
+        };
+        let description = ask(input, &format!("{}  Description (what it means, units)", indent))?;
+        let columns = if kind == FieldKind::Table {
+            eprintln!("{}  Columns of '{}':", indent, name);
+            ask_fields(input, "Column", depth + 1)?
+        } else {
+            Vec::new()
+        };
+        fields.push(Field {
+            name: name.replace(' ', "_"),
+            kind,
+            description,
+            columns,
+        });
+    }
+}
+
+fn ask(input: &mut impl BufRead, question: &str) -> Result<String> {
+    eprint!("{}: ", question);
+    io::stderr().flush().ok();
+    let mut line = String::new();
+    if input.read_line(&mut line).context("reading answer")? == 0 {
+        return Err(anyhow!("Input ended before the wizard finished"));
+    }
+    Ok(line.trim().to_string())
+}
+
+fn confirm(input: &mut impl BufRead, question: &str, default: bool) -> Result<bool> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    let answer = ask(input, &format!("{} [{}]", question, hint))?;
+    Ok(match answer.to_ascii_lowercase().as_str() {
+        "" => default,
+        a => a.starts_with('y'),
+    })
+}
+
+fn field_schema(field: &Field) -> Value {
+    let mut schema = match field.kind {
+        FieldKind::String => json!({ "type": ["string", "null"] }),
+        FieldKind::Number => json!({ "type": ["number", "null"] }),
+        FieldKind::Integer => json!({ "type": ["integer", "null"] }),
+        FieldKind::Boolean => json!({ "type": ["boolean", "null"] }),
+        FieldKind::StringList => json!({ "type": "array", "items": { "type": "string" } }),
+        FieldKind::NumberList => json!({ "type": "array", "items": { "type": "number" } }),
+        FieldKind::Table => json!({ "type": "array", "items": object_schema(&field.columns) }),
+    };
+    if !field.description.is_empty() {
+        schema["description"] = json!(field.description);
+    }
+    schema
+}
+
+fn object_schema(fields: &[Field]) -> Value {
+    let properties: Map<String, Value> = fields
+        .iter()
+        .map(|f| (f.name.clone(), field_schema(f)))
+        .collect();
+    let required: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+/// Schema for the answers: the user's fields plus the source pages used.
+fn build_schema(fields: &[Field]) -> Value {
+    let mut schema = object_schema(fields);
+    schema["properties"]["source_pages"] = json!({ "type": "array", "items": { "type": "integer" } });
+    schema
+}
+
+/// Custom-task prompt followed by the user's requirements and field list.
+fn build_prompt(goal: &str, fields: &[Field]) -> String {
+    let mut prompt = prompts::custom().prompt.to_string();
+    prompt.push_str("\n\n**User Requirements:**\n\n");
+    prompt.push_str(goal);
+    prompt.push_str("\n\nReturn exactly these fields (use `null` when the datasheet does not state a value):\n\n");
+    for field in fields {
+        describe_field(&mut prompt, field, 0);
+    }
+    prompt.push_str("\nAlso list the page numbers you used in `source_pages`.\n");
+    prompt
+}
+
+fn describe_field(out: &mut String, field: &Field, depth: usize) {
+    out.push_str(&format!("{}- `{}` ({})", "  ".repeat(depth), field.name, field.kind.name()));
+    if !field.description.is_empty() {
+        out.push_str(&format!(": {}", field.description));
+    }
+    out.push('\n');
+    for column in &field.columns {
+        describe_field(out, column, depth + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wizard_builds_schema_from_answers() {
+        let answers = "pin\ninteger\nPin number\nfunctions\ntable\n\nname\n\nFunction name\n\n\n";
+        let fields = ask_fields(&mut answers.as_bytes(), "Field", 0).unwrap();
+        let schema = build_schema(&fields);
+
+        assert_eq!(schema["properties"]["pin"]["type"], json!(["integer", "null"]));
+        assert_eq!(schema["properties"]["pin"]["description"], "Pin number");
+        let columns = &schema["properties"]["functions"]["items"]["properties"];
+        assert_eq!(columns["name"]["type"], json!(["string", "null"]));
+        assert_eq!(schema["required"], json!(["pin", "functions"]));
+        assert!(build_prompt("Pins", &fields).contains("- `functions` (table)\n  - `name` (string): Function name"));
+    }
+}
@@ -0,0 +1,289 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// SPDX-FileCopyrightText: 2026 Alexander Kiselev <alex@akiselev.com>
+
+//! DigiKey commands against a mock API.
+//!
+//! Responses are replayed from `tests/fixtures/digikey`. To refresh them from
+//! the sandbox, run the same command with real credentials, `--sandbox`, and
+//! `DIGIKEY_RECORD_DIR=tests/fixtures/digikey`; each response is saved under
+//! the name these tests load.
+
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use wiremock::matchers::{body_partial_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn fixture(name: &str) -> Value {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/digikey").join(name);
+    serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap()
+}
+
+/// A mock server that hands out a token to the test credentials.
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/oauth2/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "test-token",
+            "token_type": "Bearer",
+            "expires_in": 600
+        })))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Run `datasheet` against the mock server with an empty home and cache.
+fn datasheet(server: &MockServer, args: &[&str]) -> Output {
+    let home = scratch_dir();
+    let output = Command::new(env!("CARGO_BIN_EXE_datasheet"))
+        .args(args)
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("HOME", &home)
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .env("DIGIKEY_API_BASE", server.uri())
+        .env("DIGIKEY_CLIENT_ID", "test-id")
+        .env("DIGIKEY_CLIENT_SECRET", "test-secret")
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_dir_all(&home);
+    output
+}
+
+fn scratch_dir() -> PathBuf {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "datasheet-digikey-test-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn stdout_json(output: &Output) -> Value {
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_json_reports_counts_and_paging() {
+    let server = server().await;
+    for offset in [0, 4] {
+        Mock::given(method("POST"))
+            .and(path("/products/v4/search/keyword"))
+            .and(header("Authorization", "Bearer test-token"))
+            .and(body_partial_json(json!({ "Keywords": "LM5164", "Limit": 2, "Offset": offset })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(fixture(&format!("keyword-LM5164-2-{offset}.json"))),
+            )
+            .mount(&server)
+            .await;
+    }
+
+    let first = stdout_json(&datasheet(&server, &["digikey", "search", "LM5164", "--limit", "2", "--json"]));
+    assert_eq!(first["products_count"], 5);
+    assert_eq!(first["exact_manufacturer_products_count"], 2);
+    assert_eq!(first["page"], 1);
+    assert_eq!(first["returned"], 2);
+    assert_eq!(first["next_offset"], 2);
+    assert_eq!(first["filters"], json!({ "in_stock": false }));
+    assert_eq!(first["products"][0]["ManufacturerPartNumber"], "LM5164DDAR");
+
+    let last = stdout_json(&datasheet(
+        &server,
+        &["digikey", "search", "LM5164", "--limit", "2", "--page", "3", "--json"],
+    ));
+    assert_eq!(last["offset"], 4);
+    assert_eq!(last["returned"], 1);
+    assert_eq!(last["next_offset"], Value::Null);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_csv_uses_selected_columns() {
+    let server = server().await;
+    Mock::given(method("POST"))
+        .and(path("/products/v4/search/keyword"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("keyword-LM5164-2-0.json")))
+        .mount(&server)
+        .await;
+
+    let output = datasheet(
+        &server,
+        &["digikey", "search", "LM5164", "--limit", "2", "--columns", "mpn,stock,price@10,sku", "--csv"],
+    );
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "MPN,Stock,Price@10,SKU\n\
+         LM5164DDAR,12543,1.98,296-LM5164DDARCT-ND\n\
+         LM5164DDAT,0,,296-LM5164DDAT-ND\n"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_exact_keeps_only_the_part_number() {
+    let server = server().await;
+    Mock::given(method("POST"))
+        .and(path("/products/v4/search/keyword"))
+        .and(body_partial_json(json!({
+            "Keywords": "lm5164ddar",
+            "FilterOptionsRequest": { "SearchOptions": ["ManufacturerPartSearch"] }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("keyword-LM5164-2-0.json")))
+        .mount(&server)
+        .await;
+
+    let output = datasheet(&server, &["digikey", "search", "lm5164ddar", "--exact", "--json"]);
+    let result = stdout_json(&output);
+    let mpns: Vec<&Value> = result["products"].as_array().unwrap().iter().map(|p| &p["ManufacturerPartNumber"]).collect();
+    assert_eq!(mpns, ["LM5164DDAR"]);
+    assert_eq!(result["filters"], json!({ "in_stock": false, "exact": true }));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stock_parses_product_details() {
+    let server = server().await;
+    Mock::given(method("GET"))
+        .and(path("/products/v4/search/LM5164DDAR/productdetails"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("productdetails-LM5164DDAR.json")))
+        .mount(&server)
+        .await;
+
+    let stock = stdout_json(&datasheet(&server, &["digikey", "stock", "LM5164DDAR", "--json"]));
+    assert_eq!(stock["mpn"], "LM5164DDAR");
+    assert_eq!(stock["manufacturer"], "Texas Instruments");
+    assert_eq!(stock["stock"], 12543);
+    assert_eq!(stock["price_breaks"][1]["quantity"], 10);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn download_url_only_prints_the_url() {
+    let server = server().await;
+    Mock::given(method("GET"))
+        .and(path("/products/v4/search/LM5164DDAR/productdetails"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("productdetails-LM5164DDAR.json")))
+        .mount(&server)
+        .await;
+
+    let output = datasheet(&server, &["digikey", "download", "LM5164DDAR", "--url-only"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "https://www.ti.com/lit/ds/symlink/lm5164.pdf\n");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn part_accepts_product_page_urls() {
+    let server = server().await;
+    for part in ["LM5164DDAR", "296-LM5164DDARCT-ND"] {
+        Mock::given(method("GET"))
+            .and(path(format!("/products/v4/search/{part}/productdetails")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(fixture("productdetails-LM5164DDAR.json")))
+            .mount(&server)
+            .await;
+    }
+
+    for url in [
+        "https://www.digikey.com/en/products/detail/texas-instruments/LM5164DDAR/9924236",
+        "https://www.digikey.de/de/products/detail/texas-instruments/LM5164DDAR/9924236?s=N4Ig",
+        "www.digikey.com/product-detail/en/texas-instruments/LM5164DDAR/296-LM5164DDARCT-ND/9924236",
+    ] {
+        let part = stdout_json(&datasheet(&server, &["digikey", "part", url, "--json"]));
+        assert_eq!(part["ManufacturerPartNumber"], "LM5164DDAR", "{url}");
+        assert_eq!(
+            part["CanonicalAttributes"],
+            json!({
+                "package": "8-PowerSOIC",
+                "mounting": "Surface Mount",
+                "temperature_min": "-40°C",
+                "temperature_max": "150°C"
+            })
+        );
+    }
+
+    let output = datasheet(&server, &["digikey", "part", "https://www.mouser.com/ProductDetail/595-LM358P"]);
+    assert_eq!(output.status.code(), Some(6));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn part_reads_v4_product_details() {
+    let server = server().await;
+    Mock::given(method("GET"))
+        .and(path("/products/v4/search/TPS62130RGTR/productdetails"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("productdetails-TPS62130RGTR.json")))
+        .mount(&server)
+        .await;
+
+    let part = stdout_json(&datasheet(&server, &["digikey", "part", "TPS62130RGTR", "--json"]));
+    assert_eq!(part["ManufacturerPartNumber"], "TPS62130RGTR");
+    assert_eq!(part["ProductDescription"], "IC REG BUCK ADJUSTABLE 3A 16VQFN");
+    assert_eq!(part["DigiKeyPartNumber"], "296-38462-1-ND");
+    assert_eq!(part["StandardPricing"][1]["UnitPrice"], 2.24);
+    assert_eq!(part["PartStatus"], "Active");
+    assert_eq!(part["Parameters"].as_array().unwrap().len(), 15);
+    assert_eq!(part["ProductVariations"][1]["PackageType"]["Name"], "Tape & Reel (TR)");
+    assert_eq!(part["Classifications"]["ExportControlClassNumber"], "EAR99");
+    assert_eq!(part["OtherNames"], json!(["296-38462-6-ND", "2156-TPS62130RGTR"]));
+    // Fields not modeled are passed through
+    assert_eq!(part["UnitPrice"], 3.12);
+    assert_eq!(part["CanonicalAttributes"]["package"], "16-VFQFN Exposed Pad");
+
+    let output = datasheet(&server, &["digikey", "part", "TPS62130RGTR"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.contains("Package / Case: 16-VFQFN Exposed Pad"), "{text}");
+    assert!(text.contains("Category: Power Management (PMIC) > Voltage Regulators - DC DC Switching Regulators"));
+    assert!(text.contains("296-38462-2-ND (Tape & Reel (TR))"));
+    assert!(text.contains("Manufacturer Lead Time: 6 weeks"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn part_falls_back_to_keyword_search() {
+    let server = server().await;
+    Mock::given(method("GET"))
+        .and(path("/products/v4/search/LM5164/productdetails"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/products/v4/search/keyword"))
+        .and(body_partial_json(json!({ "Keywords": "LM5164" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("keyword-LM5164-2-0.json")))
+        .mount(&server)
+        .await;
+
+    // Without a terminal there is no picker: the first hit is taken and named on stderr
+    let output = datasheet(&server, &["digikey", "part", "LM5164", "--json"]);
+    let part = stdout_json(&output);
+    assert_eq!(part["ManufacturerPartNumber"], "LM5164DDAR");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2 DigiKey matches for LM5164"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_errors_map_to_exit_codes() {
+    let server = server().await;
+    Mock::given(method("GET"))
+        .and(path("/products/v4/search/NOPE/productdetails"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/products/v4/search/LIMITED/productdetails"))
+        .respond_with(ResponseTemplate::new(429).set_body_string("Too Many Requests"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/products/v4/search/DENIED/productdetails"))
+        .respond_with(ResponseTemplate::new(401).set_body_string("Bearer token expired"))
+        .mount(&server)
+        .await;
+
+    let code = |part: &str| datasheet(&server, &["digikey", "stock", part]).status.code();
+    assert_eq!(code("NOPE"), Some(4));
+    assert_eq!(code("LIMITED"), Some(5));
+    assert_eq!(code("DENIED"), Some(3));
+}